@@ -0,0 +1,158 @@
+//! Throughput of every primitive on every backend the CPU supports, across message sizes,
+//! to check that a backend change is actually faster.
+use benchmark_simple::*;
+use raycrypt::aeads::aegis256::{self, Backend as AesBackend};
+use raycrypt::aeads::chachapoly1305::ChaChaPoly1305;
+use raycrypt::aeads::{aegis128l, aes256gcm};
+use raycrypt::ciphers::chacha::{Backend as ChaChaBackend, ChaCha};
+use raycrypt::hashes::blake3::{Backend as Blake3Backend, Blake3};
+use raycrypt::hashes::sha2::{self, Backend as ShaBackend, Sha256};
+use raycrypt::macs::poly1305;
+
+const SIZES: [usize; 4] = [64, 1024, 16 * 1024, 1024 * 1024];
+
+const CHACHA_BACKENDS: [ChaChaBackend; 5] = [
+    ChaChaBackend::Avx512,
+    ChaChaBackend::Avx2,
+    ChaChaBackend::Sse2,
+    ChaChaBackend::Neon,
+    ChaChaBackend::Fallback,
+];
+
+const AES_BACKENDS: [AesBackend; 3] = [
+    AesBackend::AesNi,
+    AesBackend::ArmCrypto,
+    AesBackend::Portable,
+];
+
+const SHA_BACKENDS: [ShaBackend; 2] = [ShaBackend::ShaNi, ShaBackend::Portable];
+
+const BLAKE3_BACKENDS: [Blake3Backend; 2] = [Blake3Backend::Sse41, Blake3Backend::Portable];
+
+/// Runs about 16 MiB through every benchmark, whatever the message size.
+fn options(size: usize) -> Options {
+    Options {
+        iterations: (16 * 1024 * 1024 / size as u64).min(10_000),
+        warmup_iterations: (1024 * 1024 / size as u64).min(1_000),
+        min_samples: 5,
+        max_samples: 10,
+        max_rsd: 1.0,
+        ..Default::default()
+    }
+}
+
+fn report(name: &str, backend: &str, size: usize, result: BenchResult) {
+    println!(
+        "{}, {}, {} bytes: {}",
+        name,
+        backend,
+        size,
+        result.throughput(size as u128)
+    );
+}
+
+fn main() {
+    let bench = Bench::new();
+    let key = [0x42u8; 32];
+    let nonce = [0x24u8; 32];
+
+    for size in SIZES {
+        let options = &options(size);
+        let msg = vec![0u8; size];
+
+        for backend in CHACHA_BACKENDS {
+            let Some(cipher) = ChaCha::with_backend(&key, None, backend) else {
+                continue;
+            };
+            let res = bench.run(options, || cipher.encrypt(&msg, &nonce[..12]));
+            report("chacha20 keystream", backend.name(), size, res);
+
+            let aead = ChaChaPoly1305::with_backend(&key, None, backend).unwrap();
+            let sealed = aead.encrypt(&msg, &nonce[..12], b"");
+            let res = bench.run(options, || aead.encrypt(&msg, &nonce[..12], b""));
+            report("chacha20poly1305 seal", backend.name(), size, res);
+            let res = bench.run(options, || aead.decrypt(&sealed, &nonce[..12], b""));
+            report("chacha20poly1305 open", backend.name(), size, res);
+        }
+
+        // Poly1305 has a single scalar implementation
+        let res = bench.run(options, || poly1305::poly1305(&key, &msg));
+        report("poly1305", "scalar", size, res);
+
+        for backend in AES_BACKENDS {
+            if !backend.is_supported() {
+                continue;
+            }
+
+            let sealed =
+                aegis256::encrypt_with_backend::<16>(backend, &key, &msg, &nonce, b"").unwrap();
+            let res = bench.run(options, || {
+                aegis256::encrypt_with_backend::<16>(backend, &key, &msg, &nonce, b"")
+            });
+            report("aegis256 seal", backend.name(), size, res);
+            let res = bench.run(options, || {
+                aegis256::decrypt_with_backend::<16>(backend, &key, &sealed, &nonce, b"")
+            });
+            report("aegis256 open", backend.name(), size, res);
+
+            let sealed =
+                aegis128l::encrypt_with_backend::<16>(backend, &key[..16], &msg, &nonce[..16], b"")
+                    .unwrap();
+            let res = bench.run(options, || {
+                aegis128l::encrypt_with_backend::<16>(backend, &key[..16], &msg, &nonce[..16], b"")
+            });
+            report("aegis128l seal", backend.name(), size, res);
+            let res = bench.run(options, || {
+                aegis128l::decrypt_with_backend::<16>(
+                    backend,
+                    &key[..16],
+                    &sealed,
+                    &nonce[..16],
+                    b"",
+                )
+            });
+            report("aegis128l open", backend.name(), size, res);
+
+            let sealed =
+                aes256gcm::encrypt_with_backend(backend, &key, &msg, &nonce[..12], b"").unwrap();
+            let res = bench.run(options, || {
+                aes256gcm::encrypt_with_backend(backend, &key, &msg, &nonce[..12], b"")
+            });
+            report("aes256gcm seal", backend.name(), size, res);
+            let res = bench.run(options, || {
+                aes256gcm::decrypt_with_backend(backend, &key, &sealed, &nonce[..12], b"")
+            });
+            report("aes256gcm open", backend.name(), size, res);
+        }
+
+        for backend in SHA_BACKENDS {
+            if !backend.is_supported() {
+                continue;
+            }
+
+            let res = bench.run(options, || {
+                let mut hasher = Sha256::with_backend(backend).unwrap();
+                hasher.update(&msg);
+                hasher.finalize()
+            });
+            report("sha256", backend.name(), size, res);
+        }
+
+        for backend in BLAKE3_BACKENDS {
+            if !backend.is_supported() {
+                continue;
+            }
+
+            let res = bench.run(options, || {
+                let mut hasher = Blake3::with_backend(backend).unwrap();
+                hasher.update(&msg);
+                hasher.finalize()
+            });
+            report("blake3", backend.name(), size, res);
+        }
+
+        // like Poly1305, SHA-512 has a single portable implementation
+        let res = bench.run(options, || sha2::sha512(&msg));
+        report("sha512", "portable", size, res);
+    }
+}
@@ -1,32 +1,63 @@
 use benchmark_simple::*;
 use chacha20poly1305::{
-    aead::{Aead, AeadCore, KeyInit, OsRng},
-    ChaCha20Poly1305, Nonce
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305,
 };
 use raycrypt::aeads::aegis256::encrypt;
-use raycrypt::aeads::chachapoly1305::encrypt as chapoly;
-use serde_json::ser::CharEscape;
+use raycrypt::aeads::chachapoly1305::{decrypt as chapoly_decrypt, encrypt as chapoly};
+use raycrypt::ciphers::chacha::{self, Backend, ChaCha};
+use raycrypt::macs::poly1305::Poly1305;
 
 fn test_aegis(key: &[u8], nonce: &[u8], msg: &[u8]) {
     encrypt::<16>(key, msg, nonce, &[0u8]);
 }
 
 fn test_chapoly(key: &[u8], nonce: &[u8], msg: &[u8]) {
-    chapoly(key.to_vec(), msg, nonce, &[0u8], None);
+    chapoly(key, msg, nonce, &[0u8], None);
+}
+
+// encrypts everything first and then authenticates it, the way encryption used to work
+fn test_chapoly_two_pass(key: &[u8], nonce: &[u8], msg: &[u8]) {
+    let nonce = &nonce[..12];
+    let mut ciphertext = chacha::encrypt(key, msg, nonce, None);
+
+    let mut poly1305 = Poly1305::new(&chacha::keystream(key, nonce, 0, None)[..32]);
+    poly1305.update(&[0u8]);
+    poly1305.update(&ciphertext);
+    poly1305.update(&[1u64.to_le_bytes(), (msg.len() as u64).to_le_bytes()].concat());
+    ciphertext.extend_from_slice(&poly1305.tag());
+}
+
+fn test_chapoly_decrypt(key: &[u8], nonce: &[u8], ciphertext: &[u8]) {
+    chapoly_decrypt(key, ciphertext, &nonce[..12], &[0u8], None).unwrap();
+}
+
+// verifies the whole ciphertext first and then decrypts it, the way decryption used to work
+fn test_chapoly_decrypt_two_pass(key: &[u8], nonce: &[u8], ciphertext: &[u8]) {
+    let nonce = &nonce[..12];
+    let (body, tag) = ciphertext.split_at(ciphertext.len() - 16);
+
+    let mut poly1305 = Poly1305::new(&chacha::keystream(key, nonce, 0, None)[..32]);
+    poly1305.update(&[0u8]);
+    poly1305.update(body);
+    poly1305.update(&[1u64.to_le_bytes(), (body.len() as u64).to_le_bytes()].concat());
+    assert!(poly1305.verify(tag));
+
+    chacha::encrypt(key, body, nonce, None);
 }
 
 fn test_rustcrypto(key: &[u8], nonce: &[u8], msg: &[u8]) {
-    let key = chacha20poly1305::Key::from_slice(&[0u8; 32]);
-    let nonce = chacha20poly1305::Nonce::from_slice(&[0u8; 12]);
+    let key = chacha20poly1305::Key::from_slice(key);
+    let nonce = chacha20poly1305::Nonce::from_slice(&nonce[..12]);
     let state = ChaCha20Poly1305::new(key);
     state.encrypt(nonce, msg).unwrap();
 }
 
 fn main() {
     let bench = Bench::new();
-    let mut m = vec![0u8; 16384];
-    let mut k = vec![0u8; 32];
-    let mut nonce = k.clone();
+    let m = vec![0u8; 16384];
+    let k = vec![0u8; 32];
+    let nonce = k.clone();
 
     let options = &Options {
         iterations: 100,
@@ -37,12 +68,75 @@ fn main() {
         ..Default::default()
     };
 
-    let res = bench.run(&options, || test_aegis(&k, &nonce, &m));
+    let res = bench.run(options, || test_aegis(&k, &nonce, &m));
+    println!("{}", res.throughput(m.len() as u128));
+
+    let res = bench.run(options, || test_chapoly(&k, &nonce, &m));
     println!("{}", res.throughput(m.len() as u128));
 
-    let res = bench.run(&options, || test_chapoly(&k, &nonce, &m));
+    let res = bench.run(options, || test_rustcrypto(&k, &nonce, &m));
     println!("{}", res.throughput(m.len() as u128));
 
-    let res = bench.run(&options, || test_rustcrypto(&k, &nonce, &m));
-    println!("{}", res.throughput(m.len() as u128)); 
+    // single-pass against two-pass ChaCha-Poly1305 on a message larger than the caches
+    let large = vec![0u8; 16 * 1024 * 1024];
+    let large_options = &Options {
+        iterations: 5,
+        warmup_iterations: 1,
+        ..options.clone()
+    };
+
+    let res = bench.run(large_options, || test_chapoly(&k, &nonce, &large));
+    println!(
+        "chacha20poly1305, single pass: {}",
+        res.throughput(large.len() as u128)
+    );
+
+    let res = bench.run(large_options, || test_chapoly_two_pass(&k, &nonce, &large));
+    println!(
+        "chacha20poly1305, two pass: {}",
+        res.throughput(large.len() as u128)
+    );
+
+    let sealed = chapoly(&k, &large, &nonce[..12], &[0u8], None);
+
+    let res = bench.run(large_options, || test_chapoly_decrypt(&k, &nonce, &sealed));
+    println!(
+        "chacha20poly1305 decrypt, single pass: {}",
+        res.throughput(large.len() as u128)
+    );
+
+    let res = bench.run(large_options, || {
+        test_chapoly_decrypt_two_pass(&k, &nonce, &sealed)
+    });
+    println!(
+        "chacha20poly1305 decrypt, two pass: {}",
+        res.throughput(large.len() as u128)
+    );
+
+    // a single keystream block on the portable backend
+    let cipher = ChaCha::with_backend(&k, None, Backend::Fallback).unwrap();
+    let res = bench.run(options, || cipher.keystream(&nonce[..12], 1));
+    println!("portable keystream block: {}", res);
+
+    // the sixteen-block AVX-512 path against the eight-block AVX2 one
+    for backend in [Backend::Avx512, Backend::Avx2] {
+        let Some(cipher) = ChaCha::with_backend(&k, None, backend) else {
+            continue;
+        };
+        let res = bench.run(options, || cipher.encrypt(&m, &nonce[..12]));
+        println!(
+            "chacha20, {}: {}",
+            backend.name(),
+            res.throughput(m.len() as u128)
+        );
+    }
+
+    // short messages, where the fixed per-message cost dominates
+    for m in [vec![], vec![0u8; 64]] {
+        let res = bench.run(options, || test_aegis(&k, &nonce, &m));
+        println!("aegis256, {} bytes: {}", m.len(), res);
+
+        let res = bench.run(options, || test_chapoly(&k, &nonce, &m));
+        println!("chacha20poly1305, {} bytes: {}", m.len(), res);
+    }
 }
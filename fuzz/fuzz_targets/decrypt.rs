@@ -0,0 +1,41 @@
+//! Feeds arbitrary bytes to every decryption and parsing entry point. None may panic, and
+//! none may accept input that was not produced under the key.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use raycrypt::aeads::registry::from_name;
+use raycrypt::aeads::{keywrap, versioned, xchachasiv};
+
+const ALGORITHMS: [(&str, usize); 7] = [
+    ("chacha20poly1305", 32),
+    ("chacha8poly1305", 32),
+    ("xchacha20poly1305", 32),
+    ("aegis256", 32),
+    ("aegis128l", 16),
+    ("aes256gcm", 32),
+    ("chacha12poly1305", 32),
+];
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    algorithm: u8,
+    key: [u8; 32],
+    nonce: [u8; 32],
+    ad: &'a [u8],
+    ciphertext: &'a [u8],
+}
+
+fuzz_target!(|input: Input| {
+    let (name, key_length) = ALGORITHMS[input.algorithm as usize % ALGORITHMS.len()];
+    let cipher = from_name(name, &input.key[..key_length]).unwrap();
+    let nonce = &input.nonce[..cipher.nonce_length()];
+    assert!(
+        cipher.decrypt(input.ciphertext, nonce, input.ad).is_err(),
+        "{name}"
+    );
+
+    assert!(xchachasiv::decrypt(&input.key, input.ciphertext, input.ad, b"").is_err());
+    assert!(versioned::decrypt_versioned(&input.key, input.ciphertext, input.ad).is_err());
+    assert!(keywrap::unwrap_key(&input.key, input.ciphertext).is_err());
+});
@@ -0,0 +1,110 @@
+//! Compares ChaCha20, ChaCha12 and ChaCha8 on every backend the CPU supports, and
+//! ChaCha20-Poly1305 and XChaCha20-Poly1305, against the RustCrypto implementations.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use chacha20::cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use libfuzzer_sys::fuzz_target;
+use raycrypt::aeads::chachapoly1305::ChaChaPoly1305;
+use raycrypt::aeads::xchachapoly1305::XChaChaPoly1305;
+use raycrypt::ciphers::chacha::{last_block_counter, Backend, ChaCha};
+
+const BACKENDS: [Backend; 5] = [
+    Backend::Avx512,
+    Backend::Avx2,
+    Backend::Sse2,
+    Backend::Neon,
+    Backend::Fallback,
+];
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    key: [u8; 32],
+    nonce: [u8; 24],
+    counter: u32,
+    rounds: u8,
+    ad: Vec<u8>,
+    message: Vec<u8>,
+}
+
+/// Returns the keystream of the reference ChaCha with `rounds` XORed into `message`.
+fn reference(
+    rounds: usize,
+    key: &[u8; 32],
+    nonce: &[u8; 12],
+    counter: u32,
+    message: &[u8],
+) -> Vec<u8> {
+    fn apply<C: KeyIvInit + StreamCipher + StreamCipherSeek>(
+        key: &[u8; 32],
+        nonce: &[u8; 12],
+        counter: u32,
+        buffer: &mut [u8],
+    ) {
+        let mut cipher = C::new_from_slices(key, nonce).unwrap();
+        cipher.seek(counter as u64 * 64);
+        cipher.apply_keystream(buffer);
+    }
+
+    let mut buffer = message.to_vec();
+    match rounds {
+        8 => apply::<chacha20::ChaCha8>(key, nonce, counter, &mut buffer),
+        12 => apply::<chacha20::ChaCha12>(key, nonce, counter, &mut buffer),
+        _ => apply::<chacha20::ChaCha20>(key, nonce, counter, &mut buffer),
+    }
+
+    buffer
+}
+
+fuzz_target!(|input: Input| {
+    let rounds = [8, 12, 20][input.rounds as usize % 3];
+    let nonce: &[u8; 12] = input.nonce[..12].try_into().unwrap();
+
+    // both implementations refuse to run past the end of the 32-bit block counter, and
+    // the reference also keeps the very last block back
+    let last = last_block_counter(input.counter, input.message.len() as u64);
+    if last.is_ok_and(|last| last < u32::MAX) {
+        let expected = reference(rounds, &input.key, nonce, input.counter, &input.message);
+
+        for backend in BACKENDS {
+            let Some(cipher) = ChaCha::with_backend(&input.key, Some(rounds), backend) else {
+                continue;
+            };
+            assert_eq!(
+                cipher.encrypt_with_counter(&input.message, nonce, input.counter),
+                expected,
+                "{} with {rounds} rounds",
+                backend.name()
+            );
+        }
+    }
+
+    let payload = || Payload {
+        msg: &input.message,
+        aad: &input.ad,
+    };
+
+    let expected = chacha20poly1305::ChaCha20Poly1305::new(&input.key.into())
+        .encrypt(nonce.into(), payload())
+        .unwrap();
+    let ours = ChaChaPoly1305::new(&input.key, None);
+    assert_eq!(ours.encrypt(&input.message, nonce, &input.ad), expected);
+    assert_eq!(
+        ours.decrypt(&expected, nonce, &input.ad).unwrap(),
+        input.message
+    );
+
+    let expected = chacha20poly1305::XChaCha20Poly1305::new(&input.key.into())
+        .encrypt(&input.nonce.into(), payload())
+        .unwrap();
+    let ours = XChaChaPoly1305::new(&input.key, None);
+    assert_eq!(
+        ours.encrypt(&input.message, &input.nonce, &input.ad),
+        expected
+    );
+    assert_eq!(
+        ours.decrypt(&expected, &input.nonce, &input.ad).unwrap(),
+        input.message
+    );
+});
@@ -0,0 +1,75 @@
+//! Encrypts and decrypts arbitrary messages under every AEAD, and checks that a flipped
+//! bit anywhere in the ciphertext is rejected.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use raycrypt::aeads::registry::from_name;
+use raycrypt::aeads::xchachasiv::XChaChaSiv;
+
+/// The AEADs of the registry, and XChaCha20-SIV, with their key lengths.
+const ALGORITHMS: [(&str, usize); 9] = [
+    ("chacha20poly1305", 32),
+    ("chacha12poly1305", 32),
+    ("chacha8poly1305", 32),
+    ("xchacha20poly1305", 32),
+    ("xchacha8poly1305", 32),
+    ("aegis256", 32),
+    ("aegis128l", 16),
+    ("aes256gcm", 32),
+    ("xchacha20siv", 32),
+];
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    algorithm: u8,
+    key: [u8; 32],
+    nonce: [u8; 32],
+    siv_nonce: Vec<u8>,
+    ad: Vec<u8>,
+    plaintext: Vec<u8>,
+    flip: u16,
+}
+
+fuzz_target!(|input: Input| {
+    let (name, key_length) = ALGORITHMS[input.algorithm as usize % ALGORITHMS.len()];
+    let key = &input.key[..key_length];
+
+    if name == "xchacha20siv" {
+        let cipher = XChaChaSiv::new(key);
+        check(
+            name,
+            &input,
+            |plaintext| cipher.encrypt(plaintext, &input.siv_nonce, &input.ad),
+            |ciphertext| cipher.decrypt(ciphertext, &input.siv_nonce, &input.ad).ok(),
+        );
+    } else {
+        let cipher = from_name(name, key).unwrap();
+        let nonce = &input.nonce[..cipher.nonce_length()];
+        check(
+            name,
+            &input,
+            |plaintext| cipher.encrypt(plaintext, nonce, &input.ad),
+            |ciphertext| cipher.decrypt(ciphertext, nonce, &input.ad).ok(),
+        );
+    }
+});
+
+fn check(
+    name: &str,
+    input: &Input,
+    encrypt: impl Fn(&[u8]) -> Vec<u8>,
+    decrypt: impl Fn(&[u8]) -> Option<Vec<u8>>,
+) {
+    let ciphertext = encrypt(&input.plaintext);
+    assert_eq!(
+        decrypt(&ciphertext).as_ref(),
+        Some(&input.plaintext),
+        "{name}"
+    );
+
+    let mut tampered = ciphertext;
+    let bit = input.flip as usize % (tampered.len() * 8);
+    tampered[bit / 8] ^= 1 << (bit % 8);
+    assert_eq!(decrypt(&tampered), None, "{name}");
+}
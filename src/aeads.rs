@@ -1,3 +1,249 @@
+#[cfg(all(feature = "chacha", feature = "poly1305"))]
 pub mod chachapoly1305;
+#[cfg(feature = "xchacha")]
 pub mod xchachapoly1305;
+#[cfg(feature = "chacha")]
+pub mod xchachasiv;
+#[cfg(feature = "aegis256")]
 pub mod aegis256;
+#[cfg(feature = "aegis128l")]
+pub mod aegis128l;
+#[cfg(feature = "aes256gcm")]
+pub mod aes256gcm;
+#[cfg(feature = "xchacha")]
+pub mod versioned;
+#[cfg(all(feature = "xchacha", feature = "std"))]
+pub mod file;
+#[cfg(all(feature = "xchacha", feature = "argon2", feature = "getrandom"))]
+pub mod password;
+#[cfg(all(feature = "xchacha", feature = "blake2b"))]
+pub mod cryptobox;
+#[cfg(feature = "xchacha")]
+pub mod keywrap;
+#[cfg(all(feature = "xchacha", feature = "getrandom"))]
+pub mod session;
+#[cfg(feature = "xchacha")]
+pub mod secretstream;
+#[cfg(feature = "xchacha")]
+pub mod ratchet;
+#[cfg(all(feature = "chacha", feature = "poly1305"))]
+pub mod hpke;
+pub mod record;
+#[cfg(all(feature = "chacha", feature = "poly1305"))]
+pub mod stream;
+#[cfg(all(feature = "chacha", feature = "poly1305"))]
+pub mod convergent;
+#[cfg(feature = "chacha")]
+pub mod nonce;
+#[cfg(feature = "blake2")]
+pub mod chachablake2;
+#[cfg(any(
+    all(feature = "chacha", feature = "poly1305"),
+    feature = "aegis256",
+    feature = "aes256gcm",
+    feature = "blake2"
+))]
+pub mod registry;
+
+use crate::errors::{DecryptError, TagMismatch};
+use crate::padding::{self, Padding};
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+/// The interface shared by the AEADs of this module, so code written against it can
+/// switch algorithms by changing a type parameter.
+///
+/// Each method behaves like the cipher's own method of the same name: a nonce of the
+/// wrong length panics, and a message that does not authenticate fails with
+/// [`TagMismatch`]. Ciphers with options, such as the ChaCha round count, are created
+/// with their defaults.
+///
+/// ```
+/// use raycrypt::aeads::Aead;
+/// use raycrypt::aeads::chachapoly1305::ChaChaPoly1305;
+///
+/// fn roundtrip<A: Aead>(message: &[u8]) -> Vec<u8> {
+///     let cipher = A::from_key(&vec![0x42; A::KEY_LENGTH]);
+///     let nonce = vec![0; A::NONCE_LENGTH];
+///
+///     let ciphertext = cipher.encrypt(message, &nonce, b"ad");
+///     assert_eq!(ciphertext.len(), message.len() + A::TAG_LENGTH);
+///
+///     cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap()
+/// }
+///
+/// assert_eq!(roundtrip::<ChaChaPoly1305>(b"message"), b"message");
+/// ```
+pub trait Aead: Sized {
+    const KEY_LENGTH: usize;
+    const NONCE_LENGTH: usize;
+    const TAG_LENGTH: usize;
+
+    /// The tag of [`Aead::encrypt_detached`], `TAG_LENGTH` bytes.
+    type Tag: AsRef<[u8]>;
+
+    /// Creates the cipher from `key`. Panics if it is not `KEY_LENGTH` bytes.
+    fn from_key(key: &[u8]) -> Self;
+
+    /// Encrypts `plaintext` and appends the tag.
+    fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8>;
+
+    /// Decrypts a message produced by [`Aead::encrypt`].
+    fn decrypt(&self, ciphertext: &[u8], nonce: &[u8], ad: &[u8]) -> Result<Vec<u8>, TagMismatch>;
+
+    /// Like [`Aead::encrypt`], but returns the ciphertext and the tag separately.
+    fn encrypt_detached(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> (Vec<u8>, Self::Tag);
+
+    /// Decrypts a ciphertext and tag produced by [`Aead::encrypt_detached`].
+    fn decrypt_detached(
+        &self,
+        ciphertext: &[u8],
+        tag: &Self::Tag,
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch>;
+
+    /// Pads `plaintext` with [`padding::pad`] before encrypting it, so that the
+    /// ciphertext only reveals the padded length.
+    ///
+    /// ```
+    /// use raycrypt::aeads::Aead;
+    /// use raycrypt::aeads::chachapoly1305::ChaChaPoly1305;
+    /// use raycrypt::padding::Padding;
+    ///
+    /// let cipher = ChaChaPoly1305::from_key(&[0x42; 32]);
+    /// let nonce = [0; 12];
+    ///
+    /// let ciphertext = cipher.encrypt_padded(b"message", &nonce, b"ad", Padding::Block(32));
+    /// assert_eq!(ciphertext.len(), 32 + 16);
+    /// assert_eq!(cipher.decrypt_padded(&ciphertext, &nonce, b"ad").unwrap(), b"message");
+    /// ```
+    fn encrypt_padded(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        padding: Padding,
+    ) -> Vec<u8> {
+        let mut padded = padding::pad(plaintext, padding);
+        let ciphertext = self.encrypt(&padded, nonce, ad);
+        padded.zeroize();
+
+        ciphertext
+    }
+
+    /// Decrypts a message produced by [`Aead::encrypt_padded`] and strips its padding.
+    ///
+    /// The padding is only looked at once the message has authenticated, so a forged
+    /// message always fails with [`DecryptError::TagMismatch`], and
+    /// [`DecryptError::InvalidPadding`] means the sender did not pad it.
+    fn decrypt_padded(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, DecryptError> {
+        let mut plaintext = self.decrypt(ciphertext, nonce, ad)?;
+
+        match padding::unpad(&plaintext) {
+            Ok(unpadded) => {
+                let length = unpadded.len();
+                plaintext.truncate(length);
+
+                Ok(plaintext)
+            }
+            Err(error) => {
+                plaintext.zeroize();
+                Err(error.into())
+            }
+        }
+    }
+}
+
+/// Implements [`Aead`] for a cipher whose own methods already have its signatures, with
+/// `$new` creating the cipher from `key`.
+// unused when every AEAD feature is off
+#[allow(unused_macros)]
+macro_rules! impl_aead {
+    ($aead:ty, $key_length:expr, $nonce_length:expr, $tag_length:expr, |$key:ident| $new:expr) => {
+        impl Aead for $aead {
+            const KEY_LENGTH: usize = $key_length;
+            const NONCE_LENGTH: usize = $nonce_length;
+            const TAG_LENGTH: usize = $tag_length;
+
+            type Tag = [u8; $tag_length];
+
+            fn from_key($key: &[u8]) -> Self {
+                assert_eq!($key.len(), $key_length, "the key has the wrong length");
+                $new
+            }
+
+            fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+                <$aead>::encrypt(self, plaintext, nonce, ad)
+            }
+
+            fn decrypt(
+                &self,
+                ciphertext: &[u8],
+                nonce: &[u8],
+                ad: &[u8],
+            ) -> Result<Vec<u8>, TagMismatch> {
+                <$aead>::decrypt(self, ciphertext, nonce, ad)
+            }
+
+            fn encrypt_detached(
+                &self,
+                plaintext: &[u8],
+                nonce: &[u8],
+                ad: &[u8],
+            ) -> (Vec<u8>, Self::Tag) {
+                <$aead>::encrypt_detached(self, plaintext, nonce, ad)
+            }
+
+            fn decrypt_detached(
+                &self,
+                ciphertext: &[u8],
+                tag: &Self::Tag,
+                nonce: &[u8],
+                ad: &[u8],
+            ) -> Result<Vec<u8>, TagMismatch> {
+                <$aead>::decrypt_detached(self, ciphertext, tag, nonce, ad)
+            }
+        }
+    };
+}
+
+#[cfg(all(feature = "chacha", feature = "poly1305"))]
+impl_aead!(chachapoly1305::ChaChaPoly1305, 32, 12, 16, |key| {
+    chachapoly1305::ChaChaPoly1305::new(key, None)
+});
+
+#[cfg(feature = "xchacha")]
+impl_aead!(xchachapoly1305::XChaChaPoly1305, 32, 24, 16, |key| {
+    xchachapoly1305::XChaChaPoly1305::new(key, None)
+});
+
+#[cfg(feature = "aegis256")]
+impl_aead!(aegis256::Aegis256, 32, 32, 16, |key| {
+    aegis256::Aegis256::new(key)
+});
+
+#[cfg(feature = "aegis256")]
+impl_aead!(aegis256::Aegis256<32>, 32, 32, 32, |key| {
+    aegis256::Aegis256::<32>::with_tag_length(key)
+});
+
+#[cfg(feature = "aegis128l")]
+impl_aead!(aegis128l::Aegis128L, 16, 16, 16, |key| {
+    aegis128l::Aegis128L::new(key)
+});
+
+#[cfg(feature = "aes256gcm")]
+impl_aead!(aes256gcm::Aes256Gcm, 32, 12, 16, |key| {
+    aes256gcm::Aes256Gcm::new(key)
+});
+
+#[cfg(feature = "blake2")]
+impl_aead!(chachablake2::ChaChaBlake2, 32, 12, 32, |key| {
+    chachablake2::ChaChaBlake2::new(key, None)
+});
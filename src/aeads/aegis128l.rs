@@ -0,0 +1,448 @@
+//! AEGIS-128L, with a 16-byte key and nonce.
+//!
+//! AEGIS-128L has eight state blocks instead of the six of AEGIS-256 and absorbs 32 bytes
+//! per update, two blocks into different parts of the state. It runs on the same AES
+//! backends as AEGIS-256, see [`aegis256::backend`](crate::aeads::aegis256::backend).
+use crate::aeads::aegis256::backends::{dispatch, wipe, AesBlock};
+use crate::aeads::aegis256::{backend, Backend};
+use crate::errors::{InvalidMac, TagMismatch};
+use crate::utils::constant_time::ct_eq;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::{Index, IndexMut};
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const KEY_LENGTH: usize = 16;
+pub const NONCE_LENGTH: usize = 16;
+
+/// Returns a random key from the operating system's random number generator.
+///
+/// Panics if the random number generator fails.
+#[cfg(feature = "getrandom")]
+pub fn generate_key() -> [u8; KEY_LENGTH] {
+    let mut key = [0u8; KEY_LENGTH];
+    getrandom(&mut key).expect("failed to read random bytes");
+
+    key
+}
+
+/// The number of bytes absorbed per state update.
+pub(crate) const RATE: usize = 32;
+
+const C0: [u8; 16] = [
+    0x00, 0x01, 0x01, 0x02, 0x03, 0x05, 0x08, 0x0d, 0x15, 0x22, 0x37, 0x59, 0x90, 0xe9, 0x79, 0x62,
+];
+
+const C1: [u8; 16] = [
+    0xdb, 0x3d, 0x18, 0x55, 0x6d, 0xc2, 0x2f, 0xf1, 0x20, 0x11, 0x31, 0x42, 0x73, 0xb5, 0x28, 0xdd,
+];
+
+pub(crate) struct State<Block: AesBlock>([Block; 8]);
+
+impl<Block: AesBlock> Drop for State<Block> {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+impl<Block: AesBlock> State<Block> {
+    #[inline(always)]
+    pub(crate) fn new(key: &[u8], nonce: &[u8]) -> State<Block> {
+        let c0 = Block::load(&C0);
+        let c1 = Block::load(&C1);
+
+        let k = Block::load(&key[..16]);
+        let n = Block::load(&nonce[..16]);
+        let k_n = k.xor(n);
+
+        let mut state = State([k_n, c1, c0, c1, k_n, k.xor(c0), k.xor(c1), k.xor(c0)]);
+
+        for _ in 0..10 {
+            state.update(n, k);
+        }
+
+        state
+    }
+
+    /// Updates the state with `m0` XORed into S0 and `m1` into S4.
+    #[inline(always)]
+    fn update(&mut self, m0: Block, m1: Block) {
+        let temp = self[7];
+
+        for i in (1..8).rev() {
+            self[i] = self[i - 1].enc(self[i]);
+        }
+
+        self[0] = temp.enc(self[0]);
+        self[0] = self[0].xor(m0);
+        self[4] = self[4].xor(m1);
+    }
+
+    /// Returns the eight state blocks.
+    #[inline(always)]
+    pub(crate) fn blocks(&self) -> [[u8; 16]; 8] {
+        self.0.map(|block| block.store())
+    }
+
+    /// Rebuilds a state from the blocks returned by [`State::blocks`].
+    #[inline(always)]
+    pub(crate) fn from_blocks(blocks: &[[u8; 16]; 8]) -> State<Block> {
+        State(blocks.map(|block| Block::load(&block)))
+    }
+
+    /// Absorbs one 32-byte block of AAD.
+    #[inline(always)]
+    pub(crate) fn absorb(&mut self, src: &[u8]) {
+        self.update(Block::load(&src[..16]), Block::load(&src[16..32]));
+    }
+
+    #[inline(always)]
+    fn keystream(&self) -> (Block, Block) {
+        let z0 = self[6].xor(self[1]).xor(self[2].and(self[3]));
+        let z1 = self[2].xor(self[5]).xor(self[6].and(self[7]));
+
+        (z0, z1)
+    }
+
+    #[inline(always)]
+    fn enc(&mut self, src: &[u8], dst: &mut [u8]) {
+        let (z0, z1) = self.keystream();
+        let t0 = Block::load(&src[..16]);
+        let t1 = Block::load(&src[16..32]);
+
+        t0.xor(z0).store_into(&mut dst[..16]);
+        t1.xor(z1).store_into(&mut dst[16..32]);
+
+        self.update(t0, t1);
+    }
+
+    #[inline(always)]
+    fn dec(&mut self, src: &[u8], dst: &mut [u8]) {
+        let (z0, z1) = self.keystream();
+        let out0 = Block::load(&src[..16]).xor(z0);
+        let out1 = Block::load(&src[16..32]).xor(z1);
+
+        out0.store_into(&mut dst[..16]);
+        out1.store_into(&mut dst[16..32]);
+
+        self.update(out0, out1);
+    }
+
+    /// Decrypts a last block shorter than 32 bytes. The padding of the plaintext is
+    /// cleared before it is absorbed, as it would have been zero when encrypting.
+    #[inline(always)]
+    fn declast(&mut self, src: &[u8]) -> [u8; RATE] {
+        let mut padded = [0u8; RATE];
+        padded[..src.len()].copy_from_slice(src);
+
+        let (z0, z1) = self.keystream();
+        let mut dst = [0u8; RATE];
+        Block::load(&padded[..16])
+            .xor(z0)
+            .store_into(&mut dst[..16]);
+        Block::load(&padded[16..])
+            .xor(z1)
+            .store_into(&mut dst[16..]);
+        dst[src.len()..].fill(0);
+
+        self.absorb(&dst);
+
+        dst
+    }
+
+    #[inline(always)]
+    pub(crate) fn finalize<const MAC_LENGTH: usize>(
+        &mut self,
+        adlen: usize,
+        mlen: usize,
+    ) -> [u8; MAC_LENGTH] {
+        let temp = Block::load(
+            &[
+                ((adlen as u64) << 3).to_le_bytes(),
+                ((mlen as u64) << 3).to_le_bytes(),
+            ]
+            .concat(),
+        )
+        .xor(self[2]);
+
+        for _ in 0..7 {
+            self.update(temp, temp);
+        }
+
+        let mut mac = [0u8; MAC_LENGTH];
+        if MAC_LENGTH == 16 {
+            self[0]
+                .xor(self[1])
+                .xor(self[2])
+                .xor(self[3])
+                .xor(self[4])
+                .xor(self[5])
+                .xor(self[6])
+                .store_into(&mut mac);
+        } else {
+            self[0]
+                .xor(self[1])
+                .xor(self[2])
+                .xor(self[3])
+                .store_into(&mut mac[..16]);
+            self[4]
+                .xor(self[5])
+                .xor(self[6])
+                .xor(self[7])
+                .store_into(&mut mac[16..32]);
+        }
+
+        mac
+    }
+}
+
+impl<Block: AesBlock> Index<usize> for State<Block> {
+    type Output = Block;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl<Block: AesBlock> IndexMut<usize> for State<Block> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+/// Absorbs `ad` in 32-byte blocks, zero padding the last one.
+#[inline(always)]
+fn absorb_ad<Block: AesBlock>(state: &mut State<Block>, ad: &[u8]) {
+    for block in ad.chunks(RATE) {
+        if block.len() < RATE {
+            let mut pad = [0u8; RATE];
+            pad[..block.len()].copy_from_slice(block);
+
+            state.absorb(&pad);
+        } else {
+            state.absorb(block);
+        }
+    }
+}
+
+/// Encrypts `msg` and appends a `MAC_LENGTH` byte tag, which is 16 or 32 bytes.
+///
+/// A nonce must never be used twice with the same key.
+pub fn encrypt<const MAC_LENGTH: usize>(
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Vec<u8> {
+    encrypt_with_backend::<MAC_LENGTH>(backend(), key, msg, nonce, ad).unwrap()
+}
+
+/// Like [`encrypt`], but runs on `backend` instead of the detected one, or returns `None`
+/// if the CPU does not support it. All backends produce identical output.
+pub fn encrypt_with_backend<const MAC_LENGTH: usize>(
+    backend: Backend,
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Option<Vec<u8>> {
+    if !backend.is_supported() {
+        return None;
+    }
+
+    let (mut ciphertext, tag) =
+        dispatch!(backend, Block => encrypt_on::<Block, MAC_LENGTH>(key, msg, nonce, ad));
+    ciphertext.extend_from_slice(&tag);
+
+    Some(ciphertext)
+}
+
+/// Like [`encrypt`], but returns the ciphertext and the tag separately.
+pub fn encrypt_detached<const MAC_LENGTH: usize>(
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> (Vec<u8>, [u8; MAC_LENGTH]) {
+    dispatch!(backend(), Block => encrypt_on::<Block, MAC_LENGTH>(key, msg, nonce, ad))
+}
+
+#[inline(always)]
+fn encrypt_on<Block: AesBlock, const MAC_LENGTH: usize>(
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> (Vec<u8>, [u8; MAC_LENGTH]) {
+    let mut state = State::<Block>::new(key, nonce);
+    absorb_ad(&mut state, ad);
+
+    // room for the padded last block, which is truncated, and the tag
+    let padded_len = msg.len().div_ceil(RATE) * RATE;
+    let mut ciphertext = Vec::with_capacity(padded_len + MAC_LENGTH);
+    ciphertext.resize(padded_len, 0);
+
+    for (block, dst) in msg.chunks(RATE).zip(ciphertext.chunks_mut(RATE)) {
+        if block.len() < RATE {
+            let mut pad = [0u8; RATE];
+            pad[..block.len()].copy_from_slice(block);
+
+            state.enc(&pad, dst);
+        } else {
+            state.enc(block, dst);
+        }
+    }
+
+    ciphertext.truncate(msg.len());
+
+    let tag = state.finalize::<MAC_LENGTH>(ad.len(), msg.len());
+
+    (ciphertext, tag)
+}
+
+/// Decrypts a message produced by [`encrypt`] with the same `MAC_LENGTH`, or fails if
+/// its tag does not match or it is too short to contain one.
+pub fn decrypt<const MAC_LENGTH: usize>(
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, InvalidMac> {
+    decrypt_with_backend::<MAC_LENGTH>(backend(), key, msg, nonce, ad).unwrap()
+}
+
+/// Like [`decrypt`], but runs on `backend` instead of the detected one, or returns `None`
+/// if the CPU does not support it.
+pub fn decrypt_with_backend<const MAC_LENGTH: usize>(
+    backend: Backend,
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Option<Result<Vec<u8>, InvalidMac>> {
+    if !backend.is_supported() {
+        return None;
+    }
+
+    if msg.len() < MAC_LENGTH {
+        return Some(Err(InvalidMac));
+    }
+
+    let (ciphertext, tag) = msg.split_at(msg.len() - MAC_LENGTH);
+    let plaintext = dispatch!(backend, Block => decrypt_on::<Block, MAC_LENGTH>(key, ciphertext, tag, nonce, ad));
+
+    Some(plaintext)
+}
+
+/// Decrypts a ciphertext and tag produced by [`encrypt_detached`], or fails if the tag
+/// does not match.
+pub fn decrypt_detached<const MAC_LENGTH: usize>(
+    key: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8; MAC_LENGTH],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, InvalidMac> {
+    dispatch!(backend(), Block => decrypt_on::<Block, MAC_LENGTH>(key, ciphertext, tag, nonce, ad))
+}
+
+#[inline(always)]
+fn decrypt_on<Block: AesBlock, const MAC_LENGTH: usize>(
+    key: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, InvalidMac> {
+    let mut state = State::<Block>::new(key, nonce);
+    absorb_ad(&mut state, ad);
+
+    let mut plaintext = vec![0u8; ciphertext.len()];
+
+    for (block, dst) in ciphertext.chunks(RATE).zip(plaintext.chunks_mut(RATE)) {
+        if block.len() < RATE {
+            dst.copy_from_slice(&state.declast(block)[..block.len()]);
+        } else {
+            state.dec(block, dst);
+        }
+    }
+
+    let expected = state.finalize::<MAC_LENGTH>(ad.len(), ciphertext.len());
+
+    if !ct_eq(tag, &expected) {
+        plaintext.zeroize();
+        return Err(InvalidMac);
+    }
+
+    Ok(plaintext)
+}
+
+/// AEGIS-128L with a 128-bit tag, with the same interface as
+/// [`Aegis256`](crate::aeads::aegis256::Aegis256).
+///
+/// Nonces are 16 bytes, too short to be chosen at random for many messages under one
+/// key. For 256-bit tags use the free functions with a `MAC_LENGTH` of 32.
+#[derive(ZeroizeOnDrop)]
+pub struct Aegis128L {
+    key: [u8; 16],
+}
+
+impl Aegis128L {
+    /// Panics if `key` is not 16 bytes.
+    pub fn new(key: &[u8]) -> Aegis128L {
+        assert_eq!(key.len(), 16, "AEGIS-128L keys are 16 bytes");
+
+        let mut cipher_key = [0u8; 16];
+        cipher_key.copy_from_slice(key);
+
+        Aegis128L { key: cipher_key }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        assert_eq!(nonce.len(), 16, "AEGIS-128L nonces are 16 bytes");
+        encrypt::<16>(&self.key, plaintext, nonce, ad)
+    }
+
+    pub fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        assert_eq!(nonce.len(), 16, "AEGIS-128L nonces are 16 bytes");
+        if ciphertext.len() < 16 {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        decrypt::<16>(&self.key, ciphertext, nonce, ad)
+            .map_err(|_| TagMismatch { length_valid: true })
+    }
+
+    /// Like [`Aegis128L::encrypt`], but returns the ciphertext and the tag separately.
+    pub fn encrypt_detached(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> (Vec<u8>, [u8; 16]) {
+        assert_eq!(nonce.len(), 16, "AEGIS-128L nonces are 16 bytes");
+        encrypt_detached::<16>(&self.key, plaintext, nonce, ad)
+    }
+
+    /// Decrypts a ciphertext and tag produced by [`Aegis128L::encrypt_detached`].
+    pub fn decrypt_detached(
+        &self,
+        ciphertext: &[u8],
+        tag: &[u8; 16],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        assert_eq!(nonce.len(), 16, "AEGIS-128L nonces are 16 bytes");
+        decrypt_detached::<16>(&self.key, ciphertext, tag, nonce, ad)
+            .map_err(|_| TagMismatch { length_valid: true })
+    }
+}
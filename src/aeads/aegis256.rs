@@ -1,2 +1,29 @@
 pub(crate) mod backends;
-pub use backends::{decrypt, encrypt};
+mod cipher;
+mod incremental;
+#[cfg(all(debug_assertions, feature = "std"))]
+mod reuse;
+pub use backends::{
+    active_backend, backend, decrypt, decrypt_detached, decrypt_with_backend, detect_backend,
+    encrypt, encrypt_detached, encrypt_with_backend, init_state, tag_batch, Backend,
+};
+pub use cipher::Aegis256;
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
+pub use incremental::{Decryptor, Encryptor};
+#[cfg(all(debug_assertions, feature = "std"))]
+pub use reuse::reuse_warnings;
+
+pub const KEY_LENGTH: usize = 32;
+pub const NONCE_LENGTH: usize = 32;
+
+/// Returns a random key from the operating system's random number generator.
+///
+/// Panics if the random number generator fails.
+#[cfg(feature = "getrandom")]
+pub fn generate_key() -> [u8; KEY_LENGTH] {
+    let mut key = [0u8; KEY_LENGTH];
+    getrandom(&mut key).expect("failed to read random bytes");
+
+    key
+}
@@ -2,16 +2,167 @@
 pub mod aesni;
 #[cfg(target_arch = "aarch64")]
 pub mod armcrypto;
+pub mod portable;
 
-#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-use crate::aeads::aegis256::backends::aesni::Block;
+use crate::errors::{InvalidLength, InvalidMac};
 #[cfg(target_arch = "aarch64")]
-use crate::aeads::aegis256::backends::armcrypto::Block;
-
-use crate::errors::InvalidMac;
-use crate::utils::const_time_eq;
+use crate::utils::aarch64_feature_detected;
+use crate::utils::constant_time::ct_eq;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use crate::utils::x86_feature_detected;
+use alloc::vec::Vec;
+use zeroize::Zeroize;
 
 use core::ops::{Index, IndexMut};
+use core::ptr;
+use core::sync::atomic;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+/// A 128-bit block with the operations AEGIS and AES-GCM need, implemented by every
+/// backend.
+pub trait AesBlock: Copy {
+    /// Loads the first 16 bytes of `items`. Panics if `items` is shorter than a block.
+    fn load(items: &[u8]) -> Self;
+    /// Loads `items`, at most 16 bytes, zero padded to a full block.
+    #[inline(always)]
+    fn load_padded(items: &[u8]) -> Self {
+        let mut padded = [0u8; 16];
+        padded[..items.len()].copy_from_slice(items);
+
+        Self::load(&padded)
+    }
+    fn store(&self) -> [u8; 16];
+    /// Writes the block into the first 16 bytes of `out`.
+    fn store_into(&self, out: &mut [u8]);
+    fn xor(&self, other: Self) -> Self;
+    /// One AES encryption round of `self` with `other` as the round key.
+    fn enc(&self, other: Self) -> Self;
+    /// The last round of AES encryption, without MixColumns, like `AESENCLAST`.
+    #[cfg_attr(not(feature = "aes256gcm"), allow(dead_code))]
+    fn enc_last(&self, other: Self) -> Self;
+    fn and(&self, other: Self) -> Self;
+    /// Multiplies two elements of the GHASH field. Each is the block with its bytes
+    /// reversed, read as a little-endian integer, and so is the product.
+    #[cfg_attr(not(feature = "aes256gcm"), allow(dead_code))]
+    fn gf_mul(&self, other: Self) -> Self;
+}
+
+/// Reduces the carry-less product `hi:lo` of two GHASH field elements, as taken by
+/// [`AesBlock::gf_mul`], modulo `x^128 + x^7 + x^2 + x + 1`.
+///
+/// GHASH reflects its bits, with the coefficient of `x^0` in the top bit, so the product
+/// is one bit short of the 256 bits it spans and is shifted left first. The low half
+/// then holds the coefficients from `x^128` on, and multiplying by `x` is a right shift.
+#[cfg_attr(not(feature = "aes256gcm"), allow(dead_code))]
+#[inline(always)]
+pub(crate) fn gf_reduce(hi: u128, lo: u128) -> u128 {
+    let (hi, lo) = ((hi << 1) | (lo >> 127), lo << 1);
+
+    // the bits the shifts below drop are again above x^127, so fold them back in first
+    let folded = lo ^ (lo << 127) ^ (lo << 126) ^ (lo << 121);
+    hi ^ folded ^ (folded >> 1) ^ (folded >> 2) ^ (folded >> 7)
+}
+
+/// The AES implementations AEGIS-256 can run on, from fastest to slowest.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Backend {
+    AesNi,
+    ArmCrypto,
+    /// A constant-time software AES round, much slower than the others.
+    Portable,
+}
+
+impl Backend {
+    /// Returns whether the running CPU supports this backend.
+    pub fn is_supported(self) -> bool {
+        match self {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::AesNi => x86_feature_detected!("aes", "sse2", "pclmulqdq"),
+            #[cfg(target_arch = "aarch64")]
+            Backend::ArmCrypto => aarch64_feature_detected!("aes"),
+            Backend::Portable => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Returns the name of the backend: `"aesni"`, `"armcrypto"` or `"portable"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::AesNi => "aesni",
+            Backend::ArmCrypto => "armcrypto",
+            Backend::Portable => "portable",
+        }
+    }
+}
+
+/// Detects the fastest backend supported by the running CPU.
+pub fn detect_backend() -> Backend {
+    [Backend::AesNi, Backend::ArmCrypto]
+        .into_iter()
+        .find(|backend| backend.is_supported())
+        .unwrap_or(Backend::Portable)
+}
+
+/// Returns the backend used by [`encrypt`], [`decrypt`] and the other AEGIS-256
+/// functions.
+///
+/// Detection runs once, on first use, and the result is cached for the lifetime of the
+/// process, so the same binary runs on CPUs with and without AES instructions.
+#[cfg(feature = "std")]
+pub fn backend() -> Backend {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+    *BACKEND.get_or_init(detect_backend)
+}
+
+/// Returns the backend used by [`encrypt`], [`decrypt`] and the other AEGIS-256
+/// functions.
+///
+/// Without the `std` feature the CPU cannot be queried, so this is the fastest backend
+/// the crate was compiled for, e.g. with `-C target-feature=+aes`.
+#[cfg(not(feature = "std"))]
+pub fn backend() -> Backend {
+    detect_backend()
+}
+
+/// Runs `f` on `backend`, which the caller has checked to be supported.
+///
+/// The hardware backends are entered through a function compiled with their target
+/// features, so their intrinsics are inlined into `f` instead of being called one by one.
+/// AEGIS-128L and AES-256-GCM run on the same backends through this macro.
+macro_rules! dispatch {
+    ($backend:expr, $block:ident => $call:expr) => {
+        match $backend {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            $crate::aeads::aegis256::Backend::AesNi => {
+                #[target_feature(enable = "aes,sse2,pclmulqdq")]
+                unsafe fn run<R>(f: impl FnOnce() -> R) -> R {
+                    f()
+                }
+
+                type $block = $crate::aeads::aegis256::backends::aesni::Block;
+                unsafe { run(|| $call) }
+            }
+            #[cfg(target_arch = "aarch64")]
+            $crate::aeads::aegis256::Backend::ArmCrypto => {
+                #[target_feature(enable = "aes,neon")]
+                unsafe fn run<R>(f: impl FnOnce() -> R) -> R {
+                    f()
+                }
+
+                type $block = $crate::aeads::aegis256::backends::armcrypto::Block;
+                unsafe { run(|| $call) }
+            }
+            _ => {
+                type $block = $crate::aeads::aegis256::backends::portable::Block;
+                $call
+            }
+        }
+    };
+}
+
+pub(crate) use dispatch;
 
 const C0: [u8; 16] = [
     0x00, 0x01, 0x01, 0x02, 0x03, 0x05, 0x08, 0x0d, 0x15, 0x22, 0x37, 0x59, 0x90, 0xe9, 0x79, 0x62,
@@ -21,10 +172,29 @@ const C1: [u8; 16] = [
     0xdb, 0x3d, 0x18, 0x55, 0x6d, 0xc2, 0x2f, 0xf1, 0x20, 0x11, 0x31, 0x42, 0x73, 0xb5, 0x28, 0xdd,
 ];
 
-pub struct State([Block; 6]);
+/// Overwrites `blocks` with zeros in a way the compiler cannot remove, as `zeroize`
+/// does. The backends' block types have no `Zeroize` implementation.
+#[inline(always)]
+pub(crate) fn wipe<Block: AesBlock>(blocks: &mut [Block]) {
+    let zero = Block::load(&[0u8; 16]);
+    for block in blocks.iter_mut() {
+        unsafe { ptr::write_volatile(block, zero) };
+    }
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+pub struct State<Block: AesBlock>([Block; 6]);
+
+// the state is derived from the key, and the key can be recovered from it
+impl<Block: AesBlock> Drop for State<Block> {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
 
-impl State {
-    pub fn new(key: &[u8], nonce: &[u8]) -> State {
+impl<Block: AesBlock> State<Block> {
+    #[inline(always)]
+    pub fn new(key: &[u8], nonce: &[u8]) -> State<Block> {
         let c0 = Block::load(&C0);
         let c1 = Block::load(&C1);
 
@@ -49,17 +219,19 @@ impl State {
         output
     }
 
+    #[inline(always)]
     fn update(&mut self, d: Block) {
         let temp = self[5];
 
         for i in (1..6).rev() {
-            *&mut self[i] = self[i - 1].enc(self[i]);
+            self[i] = self[i - 1].enc(self[i]);
         }
 
-        *&mut self[0] = temp.enc(self[0]);
-        *&mut self[0] = self[0].xor(d);
+        self[0] = temp.enc(self[0]);
+        self[0] = self[0].xor(d);
     }
 
+    #[inline(always)]
     pub fn finalize<const MAC_LENGTH: usize>(
         &mut self,
         adlen: usize,
@@ -80,42 +252,77 @@ impl State {
 
         let mut mac = [0u8; MAC_LENGTH];
         if MAC_LENGTH == 16 {
-            mac.copy_from_slice(
-                &self[5]
-                    .xor(self[4])
-                    .xor(self[3])
-                    .xor(self[2])
-                    .xor(self[1])
-                    .xor(self[0])
-                    .store(),
-            );
+            self[5]
+                .xor(self[4])
+                .xor(self[3])
+                .xor(self[2])
+                .xor(self[1])
+                .xor(self[0])
+                .store_into(&mut mac);
         } else {
-            mac[0..16].copy_from_slice(&self[2].xor(self[1]).xor(self[0]).store());
+            self[2].xor(self[1]).xor(self[0]).store_into(&mut mac[0..16]);
 
-            mac[16..32].copy_from_slice(&self[5].xor(self[4]).xor(self[3]).store());
+            self[5].xor(self[4]).xor(self[3]).store_into(&mut mac[16..32]);
         };
 
         mac
     }
 
+    /// Returns the six state blocks.
+    pub fn blocks(&self) -> [[u8; 16]; 6] {
+        self.0.map(|block| block.store())
+    }
+
+    /// Rebuilds a state from the blocks returned by [`State::blocks`].
+    #[inline(always)]
+    pub fn from_blocks(blocks: &[[u8; 16]; 6]) -> State<Block> {
+        State(blocks.map(|block| Block::load(&block)))
+    }
+
+    #[inline(always)]
     pub fn absorb(&mut self, src: &[u8]) {
         self.update(Block::load(src));
     }
 
-    pub fn enc(&mut self, src: &[u8]) -> [u8; 16] {
+    /// Absorbs `ad` in 16-byte blocks. The last partial block is loaded zero padded, as
+    /// loading it directly would read past the end of `ad`.
+    #[inline(always)]
+    pub fn absorb_ad(&mut self, ad: &[u8]) {
+        let mut blocks = ad.chunks_exact(16);
+        for block in &mut blocks {
+            self.absorb(block);
+        }
+
+        let rest = blocks.remainder();
+        if !rest.is_empty() {
+            self.update(Block::load_padded(rest));
+        }
+    }
+
+    /// Returns the keystream the next message block is XORed with. It depends only on the
+    /// state, so it is known before the block is.
+    #[inline(always)]
+    pub fn keystream(&self) -> [u8; 16] {
+        self[5]
+            .xor(self[4])
+            .xor(self[1])
+            .xor(self[2].and(self[3]))
+            .store()
+    }
+
+    #[inline(always)]
+    pub fn enc(&mut self, src: &[u8], dst: &mut [u8]) {
         let msg = Block::load(src);
-        let dst = msg
-            .xor(self[5])
+        msg.xor(self[5])
             .xor(self[4])
             .xor(self[1])
             .xor(self[2].and(self[3]))
-            .store();
+            .store_into(dst);
 
         self.update(msg);
-
-        dst
     }
 
+    #[inline(always)]
     pub fn dec(&mut self, src: &[u8]) -> [u8; 16] {
         let msg = Block::load(src)
             .xor(self[5])
@@ -128,6 +335,7 @@ impl State {
         msg.store()
     }
 
+    #[inline(always)]
     pub fn declast(&mut self, src: &[u8]) -> [u8; 16] {
         let mut dst = [0u8; 16];
 
@@ -148,7 +356,7 @@ impl State {
     }
 }
 
-impl Index<usize> for State {
+impl<Block: AesBlock> Index<usize> for State<Block> {
     type Output = Block;
 
     fn index(&self, index: usize) -> &Self::Output {
@@ -156,90 +364,231 @@ impl Index<usize> for State {
     }
 }
 
-impl IndexMut<usize> for State {
+impl<Block: AesBlock> IndexMut<usize> for State<Block> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         &mut self.0[index]
     }
 }
 
+/// Returns the name of the AES implementation AEGIS-256 runs on, see [`Backend::name`].
+pub fn active_backend() -> &'static str {
+    backend().name()
+}
+
+/// Returns the six AEGIS-256 state blocks S0 to S5 after initialisation with `key` and
+/// `nonce`, before any AAD is absorbed.
+///
+/// This is meant for checking the initialisation phase against other implementations.
+/// Fails if `key` or `nonce` is not 32 bytes.
+pub fn init_state(key: &[u8], nonce: &[u8]) -> Result<[[u8; 16]; 6], InvalidLength> {
+    if key.len() != 32 || nonce.len() != 32 {
+        return Err(InvalidLength);
+    }
+
+    Ok(dispatch!(backend(), Block => State::<Block>::new(key, nonce).blocks()))
+}
+
+/// Encrypts `msg` and appends a `MAC_LENGTH` byte tag.
+///
+/// A nonce must never be used twice with the same key. Debug builds count every time
+/// that happens, see [`reuse_warnings`](super::reuse_warnings).
 pub fn encrypt<const MAC_LENGTH: usize>(
     key: &[u8],
     msg: &[u8],
     nonce: &[u8],
     ad: &[u8],
 ) -> Vec<u8> {
-    let mut state = State::new(key, nonce);
+    encrypt_with_backend::<MAC_LENGTH>(backend(), key, msg, nonce, ad).unwrap()
+}
+
+/// Like [`encrypt`], but runs on `backend` instead of the detected one, or returns `None`
+/// if the CPU does not support it. All backends produce identical output.
+pub fn encrypt_with_backend<const MAC_LENGTH: usize>(
+    backend: Backend,
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Option<Vec<u8>> {
+    if !backend.is_supported() {
+        return None;
+    }
 
-    let mut ciphertext = Vec::new();
+    #[cfg(all(debug_assertions, feature = "std"))]
+    crate::aeads::aegis256::reuse::record(key, nonce);
+
+    let (mut ciphertext, tag) =
+        dispatch!(backend, Block => encrypt_on::<Block, MAC_LENGTH>(key, msg, nonce, ad));
+    ciphertext.extend_from_slice(&tag);
+
+    Some(ciphertext)
+}
+
+/// Like [`encrypt`], but returns the ciphertext and the tag separately.
+pub fn encrypt_detached<const MAC_LENGTH: usize>(
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> (Vec<u8>, [u8; MAC_LENGTH]) {
+    #[cfg(all(debug_assertions, feature = "std"))]
+    crate::aeads::aegis256::reuse::record(key, nonce);
+
+    dispatch!(backend(), Block => encrypt_on::<Block, MAC_LENGTH>(key, msg, nonce, ad))
+}
+
+#[inline(always)]
+fn encrypt_on<Block: AesBlock, const MAC_LENGTH: usize>(
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> (Vec<u8>, [u8; MAC_LENGTH]) {
+    let mut state = State::<Block>::new(key, nonce);
+
+    // room for the padded last block, which is truncated, and the tag
+    let padded_len = msg.len().div_ceil(16) * 16;
+    let mut ciphertext = Vec::with_capacity(padded_len + MAC_LENGTH);
+    ciphertext.resize(padded_len, 0);
     let adlen = ad.len();
     let msglen = msg.len();
 
-    for block in ad.chunks(16) {
-        if block.len() != 16 {
-            let mut pad: [u8; 16] = [0u8; 16];
-            pad[..block.len()].copy_from_slice(block);
-
-            state.absorb(&pad);
-        } else {
-            state.absorb(block);
-        }
-    }
+    state.absorb_ad(ad);
 
-    for block in msg.chunks(16) {
+    for (block, dst) in msg.chunks(16).zip(ciphertext.chunks_mut(16)) {
         if block.len() != 16 {
             let mut pad: [u8; 16] = [0u8; 16];
             pad[..block.len()].copy_from_slice(block);
 
-            ciphertext.extend_from_slice(&state.enc(&pad));
+            state.enc(&pad, dst);
         } else {
-            ciphertext.extend_from_slice(&state.enc(block));
+            state.enc(block, dst);
         }
     }
 
-    ciphertext.resize(msglen, 0);
+    ciphertext.truncate(msglen);
 
     let tag = state.finalize::<MAC_LENGTH>(adlen, msglen);
 
-    ciphertext.extend_from_slice(&tag);
+    (ciphertext, tag)
+}
+
+/// Computes an AEGIS-256 tag over `ad` with an empty message for each key in `keys`.
+///
+/// Each tag equals the output of [`encrypt`] with an empty message. The AEGIS state is
+/// initialised from the key, so it cannot be forked after absorbing `ad` and reused for
+/// another key: the absorption is repeated per key, and only the padding of the final
+/// partial block of `ad` is shared.
+pub fn tag_batch<const MAC_LENGTH: usize>(
+    keys: &[&[u8]],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Vec<[u8; MAC_LENGTH]> {
+    let full = ad.len() - ad.len() % 16;
+    let mut last = [0u8; 16];
+    last[..ad.len() - full].copy_from_slice(&ad[full..]);
+
+    dispatch!(backend(), Block => tag_batch_on::<Block, MAC_LENGTH>(keys, nonce, ad, full, &last))
+}
 
-    ciphertext
+#[inline(always)]
+fn tag_batch_on<Block: AesBlock, const MAC_LENGTH: usize>(
+    keys: &[&[u8]],
+    nonce: &[u8],
+    ad: &[u8],
+    full: usize,
+    last: &[u8; 16],
+) -> Vec<[u8; MAC_LENGTH]> {
+    keys.iter()
+        .map(|key| {
+            let mut state = State::<Block>::new(key, nonce);
+
+            for block in ad[..full].chunks(16) {
+                state.absorb(block);
+            }
+
+            if full != ad.len() {
+                state.absorb(last);
+            }
+
+            state.finalize::<MAC_LENGTH>(ad.len(), 0)
+        })
+        .collect()
 }
 
+/// Decrypts a message produced by [`encrypt`] with the same `MAC_LENGTH`, or fails if
+/// its tag does not match or it is too short to contain one.
 pub fn decrypt<const MAC_LENGTH: usize>(
     key: &[u8],
     msg: &[u8],
     nonce: &[u8],
     ad: &[u8],
 ) -> Result<Vec<u8>, InvalidMac> {
-    let mut state = State::new(key, nonce);
+    decrypt_with_backend::<MAC_LENGTH>(backend(), key, msg, nonce, ad).unwrap()
+}
 
-    for block in ad.chunks(16) {
-        if block.len() < 16 {
-            let mut pad: [u8; 16] = [0u8; 16];
-            pad[..block.len()].copy_from_slice(block);
+/// Like [`decrypt`], but runs on `backend` instead of the detected one, or returns `None`
+/// if the CPU does not support it.
+pub fn decrypt_with_backend<const MAC_LENGTH: usize>(
+    backend: Backend,
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Option<Result<Vec<u8>, InvalidMac>> {
+    if !backend.is_supported() {
+        return None;
+    }
 
-            state.absorb(&pad);
-        } else {
-            state.absorb(block);
-        }
+    if msg.len() < MAC_LENGTH {
+        return Some(Err(InvalidMac));
     }
 
-    let mut plaintext = Vec::new();
+    let (ciphertext, tag) = msg.split_at(msg.len() - MAC_LENGTH);
+    let plaintext = dispatch!(backend, Block => decrypt_on::<Block, MAC_LENGTH>(key, ciphertext, tag, nonce, ad));
 
-    for block in msg[..msg.len() - MAC_LENGTH].chunks(16) {
-        if block.len() < 16 {
-            let mut pad: [u8; 16] = [0u8; 16];
-            pad[..block.len()].copy_from_slice(block);
+    Some(plaintext)
+}
+
+/// Decrypts a ciphertext and tag produced by [`encrypt_detached`], or fails if the tag
+/// does not match.
+pub fn decrypt_detached<const MAC_LENGTH: usize>(
+    key: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8; MAC_LENGTH],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, InvalidMac> {
+    dispatch!(backend(), Block => decrypt_on::<Block, MAC_LENGTH>(key, ciphertext, tag, nonce, ad))
+}
 
-            plaintext.extend_from_slice(&state.declast(&pad)[..block.len()]);
+#[inline(always)]
+fn decrypt_on<Block: AesBlock, const MAC_LENGTH: usize>(
+    key: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, InvalidMac> {
+    let mut state = State::<Block>::new(key, nonce);
+
+    state.absorb_ad(ad);
+
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+
+    for block in ciphertext.chunks(16) {
+        if block.len() < 16 {
+            // declast pads the block itself, and needs its length to clear the padding
+            plaintext.extend_from_slice(&state.declast(block)[..block.len()]);
         } else {
-            plaintext.extend_from_slice(&state.dec(&block));
+            plaintext.extend_from_slice(&state.dec(block));
         }
     }
 
-    let tag = state.finalize::<MAC_LENGTH>(ad.len(), msg.len());
+    let expected = state.finalize::<MAC_LENGTH>(ad.len(), ciphertext.len());
 
-    if const_time_eq(&msg[msg.len() - MAC_LENGTH..], &tag) {
+    if !ct_eq(tag, &expected) {
+        plaintext.zeroize();
         return Err(InvalidMac);
     }
 
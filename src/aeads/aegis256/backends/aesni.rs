@@ -1,33 +1,115 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
 use core::arch::x86_64::*;
 
+use crate::aeads::aegis256::backends::{gf_reduce, AesBlock};
+
 #[derive(Clone, Copy)]
 pub struct Block(__m128i);
 
-impl Block {
+// The intrinsics need AES-NI and PCLMULQDQ, so these must only be called from within
+// `#[target_feature(enable = "aes,pclmulqdq")]` code on a CPU that has them, see
+// `Backend::AesNi`.
+impl AesBlock for Block {
     #[inline(always)]
-    pub fn load(items: &[u8]) -> Block {
+    fn load(items: &[u8]) -> Block {
+        assert!(items.len() >= 16);
         Block(unsafe { _mm_loadu_si128(items.as_ptr() as *const __m128i) })
     }
 
     #[inline(always)]
-    pub fn store(&self) -> [u8; 16] {
+    fn store(&self) -> [u8; 16] {
         let mut output = [0u8; 16];
         unsafe { _mm_storeu_si128(output.as_mut_ptr() as *mut _, self.0) };
         output
     }
 
     #[inline(always)]
-    pub fn xor(&self, other: Block) -> Block {
+    fn store_into(&self, out: &mut [u8]) {
+        assert!(out.len() >= 16);
+        unsafe { _mm_storeu_si128(out.as_mut_ptr() as *mut _, self.0) };
+    }
+
+    #[inline(always)]
+    fn xor(&self, other: Block) -> Block {
         Block(unsafe { _mm_xor_si128(self.0, other.0) })
     }
 
     #[inline(always)]
-    pub fn enc(&self, other: Block) -> Block {
+    fn enc(&self, other: Block) -> Block {
         Block(unsafe { _mm_aesenc_si128(self.0, other.0) })
     }
 
     #[inline(always)]
-    pub fn and(&self, other: Block) -> Block {
+    fn enc_last(&self, other: Block) -> Block {
+        Block(unsafe { _mm_aesenclast_si128(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    fn and(&self, other: Block) -> Block {
         Block(unsafe { _mm_and_si128(self.0, other.0) })
     }
+
+    #[inline(always)]
+    fn gf_mul(&self, other: Block) -> Block {
+        let (a, b) = (self.0, other.0);
+
+        // the four 64x64-bit products, the two middle ones summed
+        let (lo, hi, middle) = unsafe {
+            let lo = _mm_clmulepi64_si128::<0x00>(a, b);
+            let hi = _mm_clmulepi64_si128::<0x11>(a, b);
+            let middle = _mm_xor_si128(
+                _mm_clmulepi64_si128::<0x01>(a, b),
+                _mm_clmulepi64_si128::<0x10>(a, b),
+            );
+
+            let [lo, hi, middle] = [lo, hi, middle].map(|x| core::mem::transmute::<_, u128>(x));
+            (lo, hi, middle)
+        };
+
+        let product = gf_reduce(hi ^ (middle >> 64), lo ^ (middle << 64));
+        Block(unsafe { core::mem::transmute::<u128, __m128i>(product) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Block;
+    use crate::aeads::aegis256::backends::AesBlock;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_store_into() {
+        let items: Vec<u8> = (0..16).collect();
+        let block = Block::load(&items);
+
+        let mut out = [0xffu8; 20];
+        block.store_into(&mut out);
+
+        assert_eq!(out[..16], block.store());
+        assert_eq!(out[16..], [0xff; 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_store_into_short() {
+        Block::load(&[0u8; 16]).store_into(&mut [0u8; 15]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_load_short() {
+        Block::load(&[0u8; 15]);
+    }
+
+    #[test]
+    fn test_load_padded() {
+        let items: Vec<u8> = (1..=15).collect();
+        let mut padded = [0u8; 16];
+        padded[..15].copy_from_slice(&items);
+
+        assert_eq!(Block::load_padded(&items).store(), padded);
+        assert_eq!(Block::load_padded(&[]).store(), [0u8; 16]);
+    }
 }
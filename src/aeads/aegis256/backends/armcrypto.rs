@@ -1,33 +1,85 @@
 use core::arch::aarch64::*;
 
+use crate::aeads::aegis256::backends::{gf_reduce, AesBlock};
+
 #[derive(Clone, Copy)]
 pub struct Block(uint8x16_t);
 
-impl Block {
+/// One AES round like x86's `AESENC`. `AESE` XORs the round key in before SubBytes and
+/// ShiftRows, while `AESENC` does it after MixColumns, so this runs `AESE` with a zero key
+/// and XORs `round_key` in last.
+#[inline]
+#[target_feature(enable = "aes")]
+unsafe fn aes_round(block: uint8x16_t, round_key: uint8x16_t) -> uint8x16_t {
+    let round = vaesmcq_u8(vaeseq_u8(block, vdupq_n_u8(0)));
+    veorq_u8(round, round_key)
+}
+
+/// The last AES round like x86's `AESENCLAST`, which is [`aes_round`] without `AESMC`.
+#[inline]
+#[target_feature(enable = "aes")]
+unsafe fn aes_last_round(block: uint8x16_t, round_key: uint8x16_t) -> uint8x16_t {
+    veorq_u8(vaeseq_u8(block, vdupq_n_u8(0)), round_key)
+}
+
+/// Multiplies two GHASH field elements with `PMULL`, see [`AesBlock::gf_mul`].
+#[inline]
+#[target_feature(enable = "aes")]
+unsafe fn gf_mul(a: uint8x16_t, b: uint8x16_t) -> uint8x16_t {
+    let (a, b) = (vreinterpretq_u64_u8(a), vreinterpretq_u64_u8(b));
+    let (a0, a1) = (vgetq_lane_u64::<0>(a), vgetq_lane_u64::<1>(a));
+    let (b0, b1) = (vgetq_lane_u64::<0>(b), vgetq_lane_u64::<1>(b));
+
+    let lo = vmull_p64(a0, b0);
+    let hi = vmull_p64(a1, b1);
+    let middle = vmull_p64(a0, b1) ^ vmull_p64(a1, b0);
+
+    let product = gf_reduce(hi ^ (middle >> 64), lo ^ (middle << 64));
+    vld1q_u8(product.to_le_bytes().as_ptr())
+}
+
+impl AesBlock for Block {
     #[inline(always)]
-    pub fn load(items: &[u8]) -> Block {
-        Block(unsafe { vld1q_u8(items.as_ptr() as *const __m128i) })
+    fn load(items: &[u8]) -> Block {
+        assert!(items.len() >= 16);
+        Block(unsafe { vld1q_u8(items.as_ptr()) })
     }
 
     #[inline(always)]
-    pub fn store(&self) -> [u8; 16] {
+    fn store(&self) -> [u8; 16] {
         let mut output = [0u8; 16];
         unsafe { vst1q_u8(output.as_mut_ptr() as *mut _, self.0) };
         output
     }
 
     #[inline(always)]
-    pub fn xor(&self, other: Block) -> Block {
+    fn store_into(&self, out: &mut [u8]) {
+        assert!(out.len() >= 16);
+        unsafe { vst1q_u8(out.as_mut_ptr() as *mut _, self.0) };
+    }
+
+    #[inline(always)]
+    fn xor(&self, other: Block) -> Block {
         Block(unsafe { veorq_u8(self.0, other.0) })
     }
 
     #[inline(always)]
-    pub fn enc(&self, other: Block) -> Block {
-        Block(unsafe { _mm_aesenc_si128(self.0, other.0) })
+    fn enc(&self, other: Block) -> Block {
+        Block(unsafe { aes_round(self.0, other.0) })
     }
 
     #[inline(always)]
-    pub fn and(&self, other: Block) -> Block {
+    fn enc_last(&self, other: Block) -> Block {
+        Block(unsafe { aes_last_round(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    fn and(&self, other: Block) -> Block {
         Block(unsafe { vandq_u8(self.0, other.0) })
     }
+
+    #[inline(always)]
+    fn gf_mul(&self, other: Block) -> Block {
+        Block(unsafe { gf_mul(self.0, other.0) })
+    }
 }
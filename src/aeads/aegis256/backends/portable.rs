@@ -0,0 +1,323 @@
+//! A software AES round and GHASH multiplication for CPUs without AES instructions.
+//!
+//! The S-box is computed rather than looked up. The 16 bytes of a block are transposed
+//! into 8 bit planes, one per bit position, and all of them go through the S-box circuit
+//! of Boyar and Peralta at once, using only XOR, AND and NOT. No branch or memory access
+//! depends on secret data, so the round runs in constant time.
+//!
+//! It is portable to any target, but slow: AEGIS-256 runs at around 50 MB/s on it on a
+//! recent x86 core, about 40 times slower than with AES-NI. It is only picked when the
+//! CPU has no AES instructions.
+use crate::aeads::aegis256::backends::{gf_reduce, AesBlock};
+
+#[derive(Clone, Copy)]
+pub struct Block([u8; 16]);
+
+/// Multiplies each byte of `a` by `x` modulo the AES polynomial.
+#[inline(always)]
+fn xtime(a: u32) -> u32 {
+    ((a & 0x7f7f_7f7f) << 1) ^ (((a >> 7) & 0x0101_0101) * 0x1b)
+}
+
+/// Transposes a matrix of 8x8 bits, stored one row per byte, with three rounds of swaps
+/// between mirrored bits, as in Hacker's Delight §7-3.
+#[inline(always)]
+fn transpose(mut x: u64) -> u64 {
+    let t = (x ^ (x >> 7)) & 0x00aa_00aa_00aa_00aa;
+    x ^= t ^ (t << 7);
+    let t = (x ^ (x >> 14)) & 0x0000_cccc_0000_cccc;
+    x ^= t ^ (t << 14);
+    let t = (x ^ (x >> 28)) & 0x0000_0000_f0f0_f0f0;
+    x ^ t ^ (t << 28)
+}
+
+/// Transposes a block into bit planes: bit `i` of plane `b` is bit `b` of byte `i`.
+#[inline(always)]
+fn bitslice(bytes: &[u8; 16]) -> [u16; 8] {
+    let low = transpose(u64::from_le_bytes(bytes[..8].try_into().unwrap())).to_le_bytes();
+    let high = transpose(u64::from_le_bytes(bytes[8..].try_into().unwrap())).to_le_bytes();
+
+    core::array::from_fn(|b| u16::from_le_bytes([low[b], high[b]]))
+}
+
+/// The inverse of [`bitslice`].
+#[inline(always)]
+fn unbitslice(planes: &[u16; 8]) -> [u8; 16] {
+    let low = u64::from_le_bytes(planes.map(|plane| plane as u8));
+    let high = u64::from_le_bytes(planes.map(|plane| (plane >> 8) as u8));
+
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&transpose(low).to_le_bytes());
+    bytes[8..].copy_from_slice(&transpose(high).to_le_bytes());
+
+    bytes
+}
+
+/// Applies the S-box to every byte of a bitsliced block.
+///
+/// This is the 113 gate circuit from Boyar and Peralta, "A depth-16 circuit for the AES
+/// S-box". The circuit numbers bits from the most significant one, so `u0` is plane 7
+/// and `s0` goes to plane 7.
+#[inline(always)]
+fn sub_bytes(planes: &mut [u16; 8]) {
+    let [u7, u6, u5, u4, u3, u2, u1, u0] = *planes;
+
+    // the top linear transformation
+    let y14 = u3 ^ u5;
+    let y13 = u0 ^ u6;
+    let y9 = u0 ^ u3;
+    let y8 = u0 ^ u5;
+    let t0 = u1 ^ u2;
+    let y1 = t0 ^ u7;
+    let y4 = y1 ^ u3;
+    let y12 = y13 ^ y14;
+    let y2 = y1 ^ u0;
+    let y5 = y1 ^ u6;
+    let y3 = y5 ^ y8;
+    let t1 = u4 ^ y12;
+    let y15 = t1 ^ u5;
+    let y20 = t1 ^ u1;
+    let y6 = y15 ^ u7;
+    let y10 = y15 ^ t0;
+    let y11 = y20 ^ y9;
+    let y7 = u7 ^ y11;
+    let y17 = y10 ^ y11;
+    let y19 = y10 ^ y8;
+    let y16 = t0 ^ y11;
+    let y21 = y13 ^ y16;
+    let y18 = u0 ^ y16;
+
+    // the nonlinear middle, an inversion in GF(2^4)^2
+    let t2 = y12 & y15;
+    let t3 = y3 & y6;
+    let t4 = t3 ^ t2;
+    let t5 = y4 & u7;
+    let t6 = t5 ^ t2;
+    let t7 = y13 & y16;
+    let t8 = y5 & y1;
+    let t9 = t8 ^ t7;
+    let t10 = y2 & y7;
+    let t11 = t10 ^ t7;
+    let t12 = y9 & y11;
+    let t13 = y14 & y17;
+    let t14 = t13 ^ t12;
+    let t15 = y8 & y10;
+    let t16 = t15 ^ t12;
+    let t17 = t4 ^ y20;
+    let t18 = t6 ^ t16;
+    let t19 = t9 ^ t14;
+    let t20 = t11 ^ t16;
+    let t21 = t17 ^ t14;
+    let t22 = t18 ^ y19;
+    let t23 = t19 ^ y21;
+    let t24 = t20 ^ y18;
+    let t25 = t21 ^ t22;
+    let t26 = t21 & t23;
+    let t27 = t24 ^ t26;
+    let t28 = t25 & t27;
+    let t29 = t28 ^ t22;
+    let t30 = t23 ^ t24;
+    let t31 = t22 ^ t26;
+    let t32 = t31 & t30;
+    let t33 = t32 ^ t24;
+    let t34 = t23 ^ t33;
+    let t35 = t27 ^ t33;
+    let t36 = t24 & t35;
+    let t37 = t36 ^ t34;
+    let t38 = t27 ^ t36;
+    let t39 = t29 & t38;
+    let t40 = t25 ^ t39;
+    let t41 = t40 ^ t37;
+    let t42 = t29 ^ t33;
+    let t43 = t29 ^ t40;
+    let t44 = t33 ^ t37;
+    let t45 = t42 ^ t41;
+    let z0 = t44 & y15;
+    let z1 = t37 & y6;
+    let z2 = t33 & u7;
+    let z3 = t43 & y16;
+    let z4 = t40 & y1;
+    let z5 = t29 & y7;
+    let z6 = t42 & y11;
+    let z7 = t45 & y17;
+    let z8 = t41 & y10;
+    let z9 = t44 & y12;
+    let z10 = t37 & y3;
+    let z11 = t33 & y4;
+    let z12 = t43 & y13;
+    let z13 = t40 & y5;
+    let z14 = t29 & y2;
+    let z15 = t42 & y9;
+    let z16 = t45 & y14;
+    let z17 = t41 & y8;
+
+    // the bottom linear transformation, with the constant 0x63 as the NOTs
+    let tc1 = z15 ^ z16;
+    let tc2 = z10 ^ tc1;
+    let tc3 = z9 ^ tc2;
+    let tc4 = z0 ^ z2;
+    let tc5 = z1 ^ z0;
+    let tc6 = z3 ^ z4;
+    let tc7 = z12 ^ tc4;
+    let tc8 = z7 ^ tc6;
+    let tc9 = z8 ^ tc7;
+    let tc10 = tc8 ^ tc9;
+    let tc11 = tc6 ^ tc5;
+    let tc12 = z3 ^ z5;
+    let tc13 = z13 ^ tc1;
+    let tc14 = tc4 ^ tc12;
+    let s3 = tc3 ^ tc11;
+    let tc16 = z6 ^ tc8;
+    let tc17 = z14 ^ tc10;
+    let tc18 = tc13 ^ tc14;
+    let s7 = !(z12 ^ tc18);
+    let tc20 = z15 ^ tc16;
+    let tc21 = tc2 ^ z11;
+    let s0 = tc3 ^ tc16;
+    let s6 = !(tc10 ^ tc18);
+    let s4 = tc14 ^ s3;
+    let s1 = !(s3 ^ tc16);
+    let tc26 = tc17 ^ tc20;
+    let s2 = !(tc26 ^ z17);
+    let s5 = tc21 ^ tc17;
+
+    *planes = [s7, s6, s5, s4, s3, s2, s1, s0];
+}
+
+/// SubBytes and ShiftRows, the part of a round shared by [`AesBlock::enc`] and
+/// [`AesBlock::enc_last`].
+#[inline(always)]
+fn sub_shift(block: &[u8; 16]) -> [u8; 16] {
+    let mut planes = bitslice(block);
+    sub_bytes(&mut planes);
+    let substituted = unbitslice(&planes);
+
+    // the state is stored column by column, byte `r + 4 * c` is row r of column c
+    let mut state = [0u8; 16];
+    for (i, byte) in state.iter_mut().enumerate() {
+        let (row, column) = (i % 4, i / 4);
+        *byte = substituted[row + 4 * ((column + row) % 4)];
+    }
+
+    state
+}
+
+/// Carry-less multiplication of two 128-bit integers, returned as the high and low
+/// halves of the product.
+///
+/// Every bit of `b` selects a shifted copy of `a` through a mask rather than a branch, so
+/// this runs in constant time.
+#[cfg_attr(not(feature = "aes256gcm"), allow(dead_code))]
+#[inline(always)]
+fn clmul(a: u128, b: u128) -> (u128, u128) {
+    let (mut hi, mut lo) = (0u128, 0u128);
+    for i in 0..128 {
+        let mask = 0u128.wrapping_sub((b >> i) & 1);
+        lo ^= (a << i) & mask;
+        // the bits shifted out of `lo`, none for i = 0
+        hi ^= (a >> 1 >> (127 - i)) & mask;
+    }
+
+    (hi, lo)
+}
+
+impl AesBlock for Block {
+    #[inline(always)]
+    fn load(items: &[u8]) -> Block {
+        let mut block = [0u8; 16];
+        block.copy_from_slice(&items[..16]);
+
+        Block(block)
+    }
+
+    #[inline(always)]
+    fn store(&self) -> [u8; 16] {
+        self.0
+    }
+
+    #[inline(always)]
+    fn store_into(&self, out: &mut [u8]) {
+        out[..16].copy_from_slice(&self.0);
+    }
+
+    #[inline(always)]
+    fn xor(&self, other: Block) -> Block {
+        Block(core::array::from_fn(|i| self.0[i] ^ other.0[i]))
+    }
+
+    /// One AES encryption round with `other` as the round key, like `AESENC`.
+    fn enc(&self, other: Block) -> Block {
+        let state = sub_shift(&self.0);
+
+        // MixColumns on each column as a little-endian word, so rotating the word by a
+        // byte lines every row up with the next one
+        let mut output = [0u8; 16];
+        for (column, out) in state.chunks_exact(4).zip(output.chunks_exact_mut(4)) {
+            let a = u32::from_le_bytes(column.try_into().unwrap());
+            let b = a.rotate_right(8);
+
+            let mixed = xtime(a ^ b) ^ b ^ a.rotate_right(16) ^ a.rotate_right(24);
+            out.copy_from_slice(&mixed.to_le_bytes());
+        }
+
+        Block(output).xor(other)
+    }
+
+    fn enc_last(&self, other: Block) -> Block {
+        Block(sub_shift(&self.0)).xor(other)
+    }
+
+    #[inline(always)]
+    fn and(&self, other: Block) -> Block {
+        Block(core::array::from_fn(|i| self.0[i] & other.0[i]))
+    }
+
+    fn gf_mul(&self, other: Block) -> Block {
+        let (hi, lo) = clmul(u128::from_le_bytes(self.0), u128::from_le_bytes(other.0));
+
+        Block(gf_reduce(hi, lo).to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bitslice, sub_bytes, unbitslice};
+
+    /// The S-box from its definition: the inverse in GF(2^8), then the affine map.
+    fn reference_sub_byte(x: u8) -> u8 {
+        let mul = |mut a: u8, mut b: u8| {
+            let mut product = 0;
+            while b != 0 {
+                if b & 1 == 1 {
+                    product ^= a;
+                }
+                a = (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 };
+                b >>= 1;
+            }
+            product
+        };
+
+        // x^254 is the inverse of x, and maps 0 to 0
+        let inverse = (0..254).fold(1, |power, _| mul(power, x));
+
+        inverse
+            ^ inverse.rotate_left(1)
+            ^ inverse.rotate_left(2)
+            ^ inverse.rotate_left(3)
+            ^ inverse.rotate_left(4)
+            ^ 0x63
+    }
+
+    #[test]
+    fn test_sub_bytes() {
+        for start in (0..=255u8).step_by(16) {
+            let bytes: [u8; 16] = core::array::from_fn(|i| start + i as u8);
+
+            let mut planes = bitslice(&bytes);
+            assert_eq!(unbitslice(&planes), bytes);
+
+            sub_bytes(&mut planes);
+            assert_eq!(unbitslice(&planes), bytes.map(reference_sub_byte));
+        }
+    }
+}
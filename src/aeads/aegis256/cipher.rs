@@ -0,0 +1,119 @@
+use crate::aeads::aegis256::backends::{decrypt, decrypt_detached, encrypt, encrypt_detached};
+use crate::aeads::aegis256::incremental::{Decryptor, Encryptor, Incremental};
+use crate::errors::TagMismatch;
+use alloc::vec::Vec;
+use zeroize::ZeroizeOnDrop;
+
+/// AEGIS-256 with a `MAC_LENGTH` byte tag, 16 by default, with the same interface as
+/// [`XChaChaPoly1305`](crate::aeads::xchachapoly1305::XChaChaPoly1305).
+///
+/// Nonces are 32 bytes, long enough to be chosen at random. The draft specifies 128- and
+/// 256-bit tags: [`Aegis256::new`] gives the former, and
+/// [`Aegis256::<32>::with_tag_length`](Aegis256::with_tag_length) the latter.
+///
+/// ```
+/// use raycrypt::aeads::aegis256::Aegis256;
+///
+/// let key = [0x42u8; 32];
+/// let nonce = [0x24u8; 32];
+///
+/// let cipher = Aegis256::<32>::with_tag_length(&key);
+/// let ciphertext = cipher.encrypt(b"message", &nonce, b"ad");
+/// assert_eq!(ciphertext.len(), 7 + 32);
+/// assert_eq!(cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap(), b"message");
+/// ```
+#[derive(ZeroizeOnDrop)]
+pub struct Aegis256<const MAC_LENGTH: usize = 16> {
+    key: [u8; 32],
+}
+
+impl Aegis256 {
+    /// Creates the cipher with a 128-bit tag. Panics if `key` is not 32 bytes.
+    pub fn new(key: &[u8]) -> Aegis256 {
+        Aegis256::with_tag_length(key)
+    }
+}
+
+impl<const MAC_LENGTH: usize> Aegis256<MAC_LENGTH> {
+    /// Creates the cipher with a `MAC_LENGTH` byte tag. Panics if `key` is not 32 bytes
+    /// or `MAC_LENGTH` is not 16 or 32.
+    pub fn with_tag_length(key: &[u8]) -> Aegis256<MAC_LENGTH> {
+        assert_eq!(key.len(), 32, "AEGIS-256 keys are 32 bytes");
+        assert!(
+            MAC_LENGTH == 16 || MAC_LENGTH == 32,
+            "AEGIS-256 tags are 16 or 32 bytes"
+        );
+
+        let mut cipher_key = [0u8; 32];
+        cipher_key.copy_from_slice(key);
+
+        Aegis256 { key: cipher_key }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        assert_eq!(nonce.len(), 32, "AEGIS-256 nonces are 32 bytes");
+        encrypt::<MAC_LENGTH>(&self.key, plaintext, nonce, ad)
+    }
+
+    pub fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        assert_eq!(nonce.len(), 32, "AEGIS-256 nonces are 32 bytes");
+        if ciphertext.len() < MAC_LENGTH {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        decrypt::<MAC_LENGTH>(&self.key, ciphertext, nonce, ad)
+            .map_err(|_| TagMismatch { length_valid: true })
+    }
+
+    /// Like [`Aegis256::encrypt`], but returns the ciphertext and the tag separately.
+    pub fn encrypt_detached(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> (Vec<u8>, [u8; MAC_LENGTH]) {
+        assert_eq!(nonce.len(), 32, "AEGIS-256 nonces are 32 bytes");
+        encrypt_detached::<MAC_LENGTH>(&self.key, plaintext, nonce, ad)
+    }
+
+    /// Decrypts a ciphertext and tag produced by [`Aegis256::encrypt_detached`].
+    pub fn decrypt_detached(
+        &self,
+        ciphertext: &[u8],
+        tag: &[u8; MAC_LENGTH],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        assert_eq!(nonce.len(), 32, "AEGIS-256 nonces are 32 bytes");
+        decrypt_detached::<MAC_LENGTH>(&self.key, ciphertext, tag, nonce, ad)
+            .map_err(|_| TagMismatch { length_valid: true })
+    }
+
+    /// Starts an incremental encryption of a message too large to hold in memory at once.
+    ///
+    /// Ciphertext is returned piece by piece from [`Encryptor::update`] and the tag by
+    /// [`Encryptor::finalize`]. The output is identical to [`Aegis256::encrypt`] of the
+    /// whole message, however the plaintext is split.
+    pub fn encryptor(&self, nonce: &[u8], ad: &[u8]) -> Encryptor<MAC_LENGTH> {
+        assert_eq!(nonce.len(), 32, "AEGIS-256 nonces are 32 bytes");
+
+        #[cfg(all(debug_assertions, feature = "std"))]
+        crate::aeads::aegis256::reuse::record(&self.key, nonce);
+
+        Encryptor::new(Incremental::new(&self.key, nonce, ad))
+    }
+
+    /// Starts an incremental decryption of a message produced by [`Aegis256::encrypt`] or
+    /// an [`Encryptor`]. See [`Decryptor`] for the caveats.
+    pub fn decryptor(&self, nonce: &[u8], ad: &[u8]) -> Decryptor<MAC_LENGTH> {
+        assert_eq!(nonce.len(), 32, "AEGIS-256 nonces are 32 bytes");
+        Decryptor::new(Incremental::new(&self.key, nonce, ad))
+    }
+}
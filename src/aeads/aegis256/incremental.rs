@@ -0,0 +1,247 @@
+//! Incremental AEGIS-256 encryption and decryption, for messages that arrive in pieces.
+//!
+//! The AEGIS state only absorbs whole 16-byte blocks, but the keystream of a block
+//! depends only on the state before it. So a chunk that ends inside a block is XORed
+//! with that block's keystream right away, and its plaintext is kept until the block is
+//! complete and can be absorbed. The last partial block is absorbed zero padded, as in
+//! one-shot encryption.
+use crate::aeads::aegis256::backends::{dispatch, AesBlock, State};
+use crate::aeads::aegis256::{backend, Backend};
+use crate::errors::TagMismatch;
+use crate::utils::constant_time::ct_eq;
+use alloc::vec::Vec;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// The state shared by [`Encryptor`] and [`Decryptor`].
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub(super) struct Incremental {
+    state: [[u8; 16]; 6],
+    /// The AAD or plaintext of the current block.
+    buffer: [u8; 16],
+    /// The keystream of the current message block, once it has been started.
+    keystream: [u8; 16],
+    buffered: usize,
+    ad_len: usize,
+    msg_len: usize,
+    /// Whether more AAD may follow, i.e. the AAD has not been padded yet.
+    ad_open: bool,
+    #[zeroize(skip)]
+    backend: Backend,
+}
+
+impl Incremental {
+    pub(super) fn new(key: &[u8], nonce: &[u8], ad: &[u8]) -> Incremental {
+        let backend = backend();
+        let mut incremental = Incremental {
+            state: dispatch!(backend, Block => State::<Block>::new(key, nonce).blocks()),
+            buffer: [0u8; 16],
+            keystream: [0u8; 16],
+            buffered: 0,
+            ad_len: 0,
+            msg_len: 0,
+            ad_open: true,
+            backend,
+        };
+        incremental.update_ad(ad);
+
+        incremental
+    }
+
+    fn update_ad(&mut self, mut ad: &[u8]) {
+        assert!(self.ad_open, "AAD must be supplied before any message data");
+        self.ad_len += ad.len();
+
+        if self.buffered > 0 {
+            let taken = ad.len().min(16 - self.buffered);
+            self.buffer[self.buffered..self.buffered + taken].copy_from_slice(&ad[..taken]);
+            self.buffered += taken;
+            ad = &ad[taken..];
+
+            if self.buffered < 16 {
+                return;
+            }
+
+            let buffer = self.buffer;
+            dispatch!(self.backend, Block => absorb::<Block>(&mut self.state, &buffer));
+            self.buffered = 0;
+        }
+
+        let whole = ad.len() - ad.len() % 16;
+        dispatch!(self.backend, Block => absorb::<Block>(&mut self.state, &ad[..whole]));
+
+        let rest = &ad[whole..];
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    /// Absorbs the last partial block of AAD, zero padded.
+    fn close_ad(&mut self) {
+        if self.ad_open {
+            self.absorb_padded();
+            self.ad_open = false;
+        }
+    }
+
+    fn absorb_padded(&mut self) {
+        if self.buffered > 0 {
+            let mut padded = [0u8; 16];
+            padded[..self.buffered].copy_from_slice(&self.buffer[..self.buffered]);
+            dispatch!(self.backend, Block => absorb::<Block>(&mut self.state, &padded));
+            self.buffered = 0;
+        }
+    }
+
+    fn update(&mut self, data: &[u8], decrypt: bool) -> Vec<u8> {
+        self.close_ad();
+        self.msg_len += data.len();
+
+        let mut output = Vec::with_capacity(data.len());
+        dispatch!(self.backend, Block => self.process::<Block>(data, decrypt, &mut output));
+
+        output
+    }
+
+    #[inline(always)]
+    fn process<Block: AesBlock>(&mut self, mut data: &[u8], decrypt: bool, output: &mut Vec<u8>) {
+        let mut state = State::<Block>::from_blocks(&self.state);
+
+        // the rest of a block started by the previous chunk
+        if self.buffered > 0 {
+            let taken = data.len().min(16 - self.buffered);
+            self.xor_partial(&data[..taken], decrypt, output);
+            data = &data[taken..];
+
+            if self.buffered < 16 {
+                self.state = state.blocks();
+                return;
+            }
+
+            state.absorb(&self.buffer);
+            self.buffered = 0;
+        }
+
+        let mut blocks = data.chunks_exact(16);
+        for block in &mut blocks {
+            if decrypt {
+                output.extend_from_slice(&state.dec(block));
+            } else {
+                let start = output.len();
+                output.resize(start + 16, 0);
+                state.enc(block, &mut output[start..]);
+            }
+        }
+
+        let rest = blocks.remainder();
+        if !rest.is_empty() {
+            self.keystream = state.keystream();
+            self.xor_partial(rest, decrypt, output);
+        }
+
+        self.state = state.blocks();
+    }
+
+    /// XORs `data` with the keystream of the current block and keeps its plaintext.
+    fn xor_partial(&mut self, data: &[u8], decrypt: bool, output: &mut Vec<u8>) {
+        for (byte, key) in data.iter().zip(&self.keystream[self.buffered..]) {
+            let xored = byte ^ key;
+            output.push(xored);
+            self.buffer[self.buffered] = if decrypt { xored } else { *byte };
+            self.buffered += 1;
+        }
+    }
+
+    fn finish<const MAC_LENGTH: usize>(&mut self) -> [u8; MAC_LENGTH] {
+        self.close_ad();
+        self.absorb_padded();
+
+        let (state, ad_len, msg_len) = (&self.state, self.ad_len, self.msg_len);
+        dispatch!(self.backend, Block => {
+            State::<Block>::from_blocks(state).finalize::<MAC_LENGTH>(ad_len, msg_len)
+        })
+    }
+}
+
+#[inline(always)]
+fn absorb<Block: AesBlock>(state: &mut [[u8; 16]; 6], blocks: &[u8]) {
+    let mut loaded = State::<Block>::from_blocks(state);
+    for block in blocks.chunks_exact(16) {
+        loaded.absorb(block);
+    }
+
+    *state = loaded.blocks();
+}
+
+/// Incremental AEGIS-256 encryption, see [`Aegis256::encryptor`](super::Aegis256::encryptor).
+///
+/// Chunks may have any length, and the output is identical to one-shot encryption of
+/// their concatenation.
+pub struct Encryptor<const MAC_LENGTH: usize> {
+    inner: Incremental,
+}
+
+impl<const MAC_LENGTH: usize> Encryptor<MAC_LENGTH> {
+    pub(super) fn new(inner: Incremental) -> Encryptor<MAC_LENGTH> {
+        Encryptor { inner }
+    }
+
+    /// Authenticates another part of the AAD, after the `ad` the encryption was started
+    /// with. The parts are authenticated as their concatenation.
+    ///
+    /// Panics if called after [`Encryptor::update`].
+    pub fn update_aad(&mut self, ad: &[u8]) {
+        self.inner.update_ad(ad);
+    }
+
+    /// Encrypts the next chunk of plaintext and returns its ciphertext.
+    pub fn update(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.inner.update(chunk, false)
+    }
+
+    /// Returns the tag over the AAD and all ciphertext returned so far.
+    pub fn finalize(mut self) -> [u8; MAC_LENGTH] {
+        self.inner.finish()
+    }
+}
+
+/// Incremental AEGIS-256 decryption, see [`Aegis256::decryptor`](super::Aegis256::decryptor).
+///
+/// [`Decryptor::update`] returns plaintext before the tag has been checked, as the tag
+/// only arrives at the end. That plaintext is unauthenticated: it must not be acted on,
+/// and has to be discarded if [`Decryptor::finalize`] fails.
+pub struct Decryptor<const MAC_LENGTH: usize> {
+    inner: Incremental,
+}
+
+impl<const MAC_LENGTH: usize> Decryptor<MAC_LENGTH> {
+    pub(super) fn new(inner: Incremental) -> Decryptor<MAC_LENGTH> {
+        Decryptor { inner }
+    }
+
+    /// Authenticates another part of the AAD, see [`Encryptor::update_aad`].
+    ///
+    /// Panics if called after [`Decryptor::update`].
+    pub fn update_aad(&mut self, ad: &[u8]) {
+        self.inner.update_ad(ad);
+    }
+
+    /// Decrypts the next chunk of ciphertext, without its tag, and returns the
+    /// unauthenticated plaintext.
+    pub fn update(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.inner.update(chunk, true)
+    }
+
+    /// Checks `tag` against the AAD and all ciphertext passed to [`Decryptor::update`].
+    pub fn finalize(mut self, tag: &[u8]) -> Result<(), TagMismatch> {
+        if tag.len() != MAC_LENGTH {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        if !ct_eq(&self.inner.finish::<MAC_LENGTH>(), tag) {
+            return Err(TagMismatch { length_valid: true });
+        }
+
+        Ok(())
+    }
+}
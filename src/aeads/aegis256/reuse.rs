@@ -0,0 +1,49 @@
+//! Debug-only detection of AEGIS-256 nonce reuse.
+//!
+//! Encrypting two messages with the same key and nonce is catastrophic for
+//! confidentiality: the first 16-byte block of both ciphertexts is XORed with the same
+//! keystream, and as the state absorbs the plaintext, an attacker who knows or chooses
+//! parts of the messages can recover the state and decrypt the rest of every message
+//! under that key and nonce. A recovered state also allows forging messages for that
+//! key and nonce. No key recovery is known from this, so messages under other nonces stay
+//! secure, which is the extent of the misuse resistance. AEGIS-256 is not a nonce-misuse
+//! resistant AEAD.
+//!
+//! In debug builds [`encrypt`](super::encrypt) records a SHA-256 digest of every key and
+//! nonce pair and counts every pair seen again, see [`reuse_warnings`]. The keys
+//! themselves are never stored. Only encryption is tracked, as decrypting the same message several
+//! times is harmless. The record is process-wide and is never cleared, so memory grows
+//! with every message; release builds do none of this.
+use crate::hashes::sha2::Sha256;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+fn seen() -> &'static Mutex<HashSet<[u8; 32]>> {
+    static SEEN: OnceLock<Mutex<HashSet<[u8; 32]>>> = OnceLock::new();
+    SEEN.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+pub(crate) fn record(key: &[u8], nonce: &[u8]) {
+    let mut hasher = Sha256::new();
+    hasher.update(key);
+    hasher.update(nonce);
+    let digest = hasher.finalize();
+
+    let fresh = seen()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(digest);
+
+    if !fresh {
+        WARNINGS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Returns how many times [`encrypt`](super::encrypt) has been called with a key and
+/// nonce pair it had already seen in this process. Only available in debug builds.
+pub fn reuse_warnings() -> usize {
+    WARNINGS.load(Ordering::Relaxed)
+}
@@ -0,0 +1,402 @@
+//! AES-256-GCM (NIST SP 800-38D), with a 32-byte key, a 12-byte nonce and a 16-byte tag.
+//!
+//! GCM encrypts in counter mode and authenticates the AAD and ciphertext with GHASH, a
+//! polynomial hash over GF(2^128). It is here for interoperability with protocols and
+//! peers that require it; AEGIS and ChaCha20-Poly1305 are the better choices otherwise.
+//! It runs on the same backends as AEGIS, see
+//! [`aegis256::backend`](crate::aeads::aegis256::backend), and the AES-NI backend
+//! multiplies with `PCLMULQDQ` and the ARM one with `PMULL`.
+use crate::aeads::aegis256::backends::{dispatch, wipe, AesBlock};
+use crate::aeads::aegis256::{backend, Backend};
+use crate::errors::{InvalidMac, TagMismatch};
+use crate::utils::constant_time::ct_eq;
+use alloc::vec::Vec;
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const KEY_LENGTH: usize = 32;
+pub const NONCE_LENGTH: usize = 12;
+pub const TAG_LENGTH: usize = 16;
+
+/// Returns a random key from the operating system's random number generator.
+///
+/// Panics if the random number generator fails.
+#[cfg(feature = "getrandom")]
+pub fn generate_key() -> [u8; KEY_LENGTH] {
+    let mut key = [0u8; KEY_LENGTH];
+    getrandom(&mut key).expect("failed to read random bytes");
+
+    key
+}
+
+/// The longest plaintext a single message can have, in bytes, about 64 GiB.
+///
+/// The message is encrypted from counter 2 on and the counter is 32 bits, so it has
+/// `2^32 - 2` blocks of 16 bytes. Encrypting a longer one panics, and decrypting one
+/// fails as if the tag did not match.
+pub const MAX_PLAINTEXT_LENGTH: u64 = (u32::MAX as u64 - 1) * 16;
+
+/// The number of rounds of AES-256, and one less than the number of round keys.
+const ROUNDS: usize = 14;
+
+/// The round constants of the key schedule, one per eight words.
+const RCON: [u8; 7] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40];
+
+/// Applies the S-box to each byte of `word`.
+///
+/// With the word in every column of the block, ShiftRows only moves bytes between equal
+/// columns, so the last round with a zero round key is SubBytes alone.
+#[inline(always)]
+fn sub_word<Block: AesBlock>(word: [u8; 4]) -> [u8; 4] {
+    let block = Block::load(&[word; 4].concat()).enc_last(Block::load(&[0u8; 16]));
+
+    block.store()[..4].try_into().unwrap()
+}
+
+/// The AES-256 block cipher with its expanded key.
+struct Cipher<Block: AesBlock>([Block; ROUNDS + 1]);
+
+impl<Block: AesBlock> Drop for Cipher<Block> {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+impl<Block: AesBlock> Cipher<Block> {
+    #[inline(always)]
+    fn new(key: &[u8]) -> Cipher<Block> {
+        let mut words = [[0u8; 4]; 4 * (ROUNDS + 1)];
+        for (word, bytes) in words.iter_mut().zip(key[..KEY_LENGTH].chunks_exact(4)) {
+            word.copy_from_slice(bytes);
+        }
+
+        for i in 8..words.len() {
+            let mut temp = words[i - 1];
+            if i % 8 == 0 {
+                temp.rotate_left(1);
+                temp = sub_word::<Block>(temp);
+                temp[0] ^= RCON[i / 8 - 1];
+            } else if i % 8 == 4 {
+                temp = sub_word::<Block>(temp);
+            }
+
+            words[i] = core::array::from_fn(|j| words[i - 8][j] ^ temp[j]);
+        }
+
+        let round_keys = core::array::from_fn(|i| Block::load(&words[4 * i..4 * i + 4].concat()));
+        words.zeroize();
+
+        Cipher(round_keys)
+    }
+
+    #[inline(always)]
+    fn encrypt(&self, block: Block) -> Block {
+        let mut block = block.xor(self.0[0]);
+        for round_key in &self.0[1..ROUNDS] {
+            block = block.enc(*round_key);
+        }
+
+        block.enc_last(self.0[ROUNDS])
+    }
+}
+
+/// Loads at most 16 bytes, zero padded, as a GHASH field element, see
+/// [`AesBlock::gf_mul`].
+#[inline(always)]
+fn load_element<Block: AesBlock>(bytes: &[u8]) -> Block {
+    let mut padded = [0u8; 16];
+    padded[..bytes.len()].copy_from_slice(bytes);
+    padded.reverse();
+
+    Block::load(&padded)
+}
+
+struct Ghash<Block: AesBlock> {
+    h: Block,
+    y: Block,
+}
+
+impl<Block: AesBlock> Drop for Ghash<Block> {
+    fn drop(&mut self) {
+        wipe(core::slice::from_mut(&mut self.h));
+        wipe(core::slice::from_mut(&mut self.y));
+    }
+}
+
+impl<Block: AesBlock> Ghash<Block> {
+    #[inline(always)]
+    fn new(h: Block) -> Ghash<Block> {
+        let mut bytes = h.store();
+        bytes.reverse();
+        let h = Block::load(&bytes);
+        bytes.zeroize();
+
+        Ghash {
+            h,
+            y: Block::load(&[0u8; 16]),
+        }
+    }
+
+    /// Absorbs `data` in 16-byte blocks, zero padding the last one.
+    #[inline(always)]
+    fn update_padded(&mut self, data: &[u8]) {
+        for block in data.chunks(16) {
+            self.y = self.y.xor(load_element(block)).gf_mul(self.h);
+        }
+    }
+
+    /// Absorbs the length block and returns the hash.
+    #[inline(always)]
+    fn finalize(mut self, ad_len: usize, ciphertext_len: usize) -> Block {
+        let lengths = [
+            (ad_len as u64 * 8).to_be_bytes(),
+            (ciphertext_len as u64 * 8).to_be_bytes(),
+        ]
+        .concat();
+        self.update_padded(&lengths);
+
+        let mut hash = self.y.store();
+        hash.reverse();
+
+        Block::load(&hash)
+    }
+}
+
+/// Returns the counter block for `nonce` and `counter`.
+#[inline(always)]
+fn counter_block<Block: AesBlock>(nonce: &[u8], counter: u32) -> Block {
+    let mut block = [0u8; 16];
+    block[..NONCE_LENGTH].copy_from_slice(nonce);
+    block[NONCE_LENGTH..].copy_from_slice(&counter.to_be_bytes());
+
+    Block::load(&block)
+}
+
+/// XORs the keystream for `nonce` into `data`, from counter 2 on.
+#[inline(always)]
+fn apply_keystream<Block: AesBlock>(cipher: &Cipher<Block>, nonce: &[u8], data: &mut [u8]) {
+    for (i, chunk) in data.chunks_mut(16).enumerate() {
+        let keystream = cipher.encrypt(counter_block(nonce, i as u32 + 2)).store();
+        for (byte, key) in chunk.iter_mut().zip(keystream) {
+            *byte ^= key;
+        }
+    }
+}
+
+/// Returns the tag over `ad` and `ciphertext`: their GHASH masked with the encrypted
+/// counter block 1.
+#[inline(always)]
+fn tag<Block: AesBlock>(
+    cipher: &Cipher<Block>,
+    nonce: &[u8],
+    ciphertext: &[u8],
+    ad: &[u8],
+) -> [u8; TAG_LENGTH] {
+    let mut ghash = Ghash::new(cipher.encrypt(Block::load(&[0u8; 16])));
+    ghash.update_padded(ad);
+    ghash.update_padded(ciphertext);
+
+    let mask = cipher.encrypt(counter_block(nonce, 1));
+    ghash.finalize(ad.len(), ciphertext.len()).xor(mask).store()
+}
+
+/// Encrypts `msg` and appends a 16-byte tag.
+///
+/// A nonce must never be used twice with the same key: unlike with AEGIS, a repeated
+/// nonce also reveals the GHASH key and lets anyone forge messages.
+///
+/// Panics if `key` is shorter than 32 bytes, if `nonce` is not 12 bytes or if `msg` is
+/// longer than [`MAX_PLAINTEXT_LENGTH`].
+pub fn encrypt(key: &[u8], msg: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+    encrypt_with_backend(backend(), key, msg, nonce, ad).unwrap()
+}
+
+/// Like [`encrypt`], but runs on `backend` instead of the detected one, or returns `None`
+/// if the CPU does not support it. All backends produce identical output.
+pub fn encrypt_with_backend(
+    backend: Backend,
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Option<Vec<u8>> {
+    if !backend.is_supported() {
+        return None;
+    }
+
+    let (mut ciphertext, tag) =
+        dispatch!(backend, Block => encrypt_on::<Block>(key, msg, nonce, ad));
+    ciphertext.extend_from_slice(&tag);
+
+    Some(ciphertext)
+}
+
+/// Like [`encrypt`], but returns the ciphertext and the tag separately.
+pub fn encrypt_detached(
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> (Vec<u8>, [u8; TAG_LENGTH]) {
+    dispatch!(backend(), Block => encrypt_on::<Block>(key, msg, nonce, ad))
+}
+
+#[inline(always)]
+fn encrypt_on<Block: AesBlock>(
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> (Vec<u8>, [u8; TAG_LENGTH]) {
+    assert_eq!(nonce.len(), NONCE_LENGTH, "AES-256-GCM nonces are 12 bytes");
+    assert!(
+        msg.len() as u64 <= MAX_PLAINTEXT_LENGTH,
+        "AES-256-GCM messages are at most {MAX_PLAINTEXT_LENGTH} bytes"
+    );
+
+    let cipher = Cipher::<Block>::new(key);
+
+    // room for the tag, which the caller may append
+    let mut ciphertext = Vec::with_capacity(msg.len() + TAG_LENGTH);
+    ciphertext.extend_from_slice(msg);
+    apply_keystream(&cipher, nonce, &mut ciphertext);
+
+    let tag = tag(&cipher, nonce, &ciphertext, ad);
+
+    (ciphertext, tag)
+}
+
+/// Decrypts a message produced by [`encrypt`], or fails if its tag does not match or it
+/// is too short to contain one.
+///
+/// Panics if `key` is shorter than 32 bytes or if `nonce` is not 12 bytes.
+pub fn decrypt(key: &[u8], msg: &[u8], nonce: &[u8], ad: &[u8]) -> Result<Vec<u8>, InvalidMac> {
+    decrypt_with_backend(backend(), key, msg, nonce, ad).unwrap()
+}
+
+/// Like [`decrypt`], but runs on `backend` instead of the detected one, or returns `None`
+/// if the CPU does not support it.
+pub fn decrypt_with_backend(
+    backend: Backend,
+    key: &[u8],
+    msg: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Option<Result<Vec<u8>, InvalidMac>> {
+    if !backend.is_supported() {
+        return None;
+    }
+
+    if msg.len() < TAG_LENGTH {
+        return Some(Err(InvalidMac));
+    }
+
+    let (ciphertext, tag) = msg.split_at(msg.len() - TAG_LENGTH);
+    let plaintext =
+        dispatch!(backend, Block => decrypt_on::<Block>(key, ciphertext, tag, nonce, ad));
+
+    Some(plaintext)
+}
+
+/// Decrypts a ciphertext and tag produced by [`encrypt_detached`], or fails if the tag
+/// does not match.
+pub fn decrypt_detached(
+    key: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8; TAG_LENGTH],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, InvalidMac> {
+    dispatch!(backend(), Block => decrypt_on::<Block>(key, ciphertext, tag, nonce, ad))
+}
+
+#[inline(always)]
+fn decrypt_on<Block: AesBlock>(
+    key: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, InvalidMac> {
+    assert_eq!(nonce.len(), NONCE_LENGTH, "AES-256-GCM nonces are 12 bytes");
+    if ciphertext.len() as u64 > MAX_PLAINTEXT_LENGTH {
+        return Err(InvalidMac);
+    }
+
+    let cipher = Cipher::<Block>::new(key);
+
+    // the tag covers the ciphertext, so it is checked before anything is decrypted
+    let expected = self::tag(&cipher, nonce, ciphertext, ad);
+    if !ct_eq(tag, &expected) {
+        return Err(InvalidMac);
+    }
+
+    let mut plaintext = ciphertext.to_vec();
+    apply_keystream(&cipher, nonce, &mut plaintext);
+
+    Ok(plaintext)
+}
+
+/// AES-256-GCM with the same interface as
+/// [`ChaChaPoly1305`](crate::aeads::chachapoly1305::ChaChaPoly1305).
+///
+/// Nonces are 12 bytes, too short to be chosen at random for more than about 2^32
+/// messages under one key.
+#[derive(ZeroizeOnDrop)]
+pub struct Aes256Gcm {
+    key: [u8; KEY_LENGTH],
+}
+
+impl Aes256Gcm {
+    /// Panics if `key` is not 32 bytes.
+    pub fn new(key: &[u8]) -> Aes256Gcm {
+        assert_eq!(key.len(), KEY_LENGTH, "AES-256-GCM keys are 32 bytes");
+
+        let mut cipher_key = [0u8; KEY_LENGTH];
+        cipher_key.copy_from_slice(key);
+
+        Aes256Gcm { key: cipher_key }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        encrypt(&self.key, plaintext, nonce, ad)
+    }
+
+    pub fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        if ciphertext.len() < TAG_LENGTH {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        decrypt(&self.key, ciphertext, nonce, ad).map_err(|_| TagMismatch { length_valid: true })
+    }
+
+    /// Like [`Aes256Gcm::encrypt`], but returns the ciphertext and the tag separately.
+    pub fn encrypt_detached(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> (Vec<u8>, [u8; TAG_LENGTH]) {
+        encrypt_detached(&self.key, plaintext, nonce, ad)
+    }
+
+    /// Decrypts a ciphertext and tag produced by [`Aes256Gcm::encrypt_detached`].
+    pub fn decrypt_detached(
+        &self,
+        ciphertext: &[u8],
+        tag: &[u8; TAG_LENGTH],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        decrypt_detached(&self.key, ciphertext, tag, nonce, ad)
+            .map_err(|_| TagMismatch { length_valid: true })
+    }
+}
@@ -0,0 +1,150 @@
+//! ChaCha with keyed BLAKE2b as the authenticator, as an alternative to ChaCha-Poly1305.
+//!
+//! This is an Encrypt-then-MAC construction and not part of RFC 8439. The message is
+//! encrypted with ChaCha from block 1 as in RFC 8439, and the first 32 bytes of keystream
+//! block 0 key BLAKE2b-256, which authenticates
+//! `ad || ciphertext || len(ad) || len(ciphertext)` with both lengths as little-endian
+//! 64-bit integers. The 32-byte tag is appended to the ciphertext.
+//!
+//! Unlike Poly1305, BLAKE2b is a PRF and collision resistant, so a tag commits to the
+//! key, AAD and ciphertext, and the MAC does not break if its key were ever reused. The
+//! cipher still needs a unique nonce per message: nonce reuse leaks the XOR of the
+//! plaintexts just as with ChaCha-Poly1305.
+use crate::ciphers::chacha::ChaCha;
+use crate::config::Config;
+pub use crate::errors::TagMismatch;
+use blake2::digest::consts::U32;
+use blake2::digest::Mac;
+use blake2::Blake2bMac;
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
+use zeroize::Zeroize;
+
+pub const KEY_LENGTH: usize = 32;
+pub const NONCE_LENGTH: usize = 12;
+pub const TAG_LENGTH: usize = 32;
+
+/// Returns a random key from the operating system's random number generator.
+///
+/// Panics if the random number generator fails.
+#[cfg(feature = "getrandom")]
+pub fn generate_key() -> [u8; KEY_LENGTH] {
+    let mut key = [0u8; KEY_LENGTH];
+    getrandom(&mut key).expect("failed to read random bytes");
+
+    key
+}
+
+type Blake2bMac256 = Blake2bMac<U32>;
+
+pub struct ChaChaBlake2 {
+    cipher: ChaCha,
+}
+
+impl ChaChaBlake2 {
+    /// Panics if `key` is not 32 bytes.
+    pub fn new(key: &[u8], rounds: Option<usize>) -> ChaChaBlake2 {
+        ChaChaBlake2 {
+            cipher: ChaCha::new(key, rounds),
+        }
+    }
+
+    fn authenticate(&self, nonce: &[u8], ad: &[u8], ciphertext: &[u8]) -> Blake2bMac256 {
+        let mut mac_key = self.cipher.keystream(nonce, 0);
+        let mut mac = Blake2bMac256::new_from_slice(&mac_key[..32]).unwrap();
+        mac_key.zeroize();
+
+        mac.update(ad);
+        mac.update(ciphertext);
+        mac.update(&(ad.len() as u64).to_le_bytes());
+        mac.update(&(ciphertext.len() as u64).to_le_bytes());
+
+        mac
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        let (mut ciphertext, tag) = self.encrypt_detached(plaintext, nonce, ad);
+        ciphertext.extend_from_slice(&tag);
+
+        ciphertext
+    }
+
+    pub fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        if ciphertext.len() < TAG_LENGTH {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - TAG_LENGTH);
+        self.decrypt_detached(ciphertext, tag.try_into().unwrap(), nonce, ad)
+    }
+
+    /// Like [`ChaChaBlake2::encrypt`], but returns the ciphertext and the tag separately.
+    pub fn encrypt_detached(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> (Vec<u8>, [u8; TAG_LENGTH]) {
+        // leave room for the tag, so joining them again does not reallocate
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + TAG_LENGTH);
+        self.cipher
+            .encrypt_into(plaintext, nonce, 1, &mut ciphertext);
+
+        let tag = self
+            .authenticate(nonce, ad, &ciphertext)
+            .finalize()
+            .into_bytes();
+
+        (ciphertext, tag.into())
+    }
+
+    /// Decrypts a ciphertext and tag produced by [`ChaChaBlake2::encrypt_detached`].
+    pub fn decrypt_detached(
+        &self,
+        ciphertext: &[u8],
+        tag: &[u8; TAG_LENGTH],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        match self.authenticate(nonce, ad, ciphertext).verify_slice(tag) {
+            Ok(()) => Ok(self.cipher.encrypt(ciphertext, nonce)),
+            Err(_) => Err(TagMismatch { length_valid: true }),
+        }
+    }
+
+    pub fn config(&self) -> Config {
+        Config {
+            algorithm: "ChaCha-BLAKE2b",
+            rounds: self.cipher.rounds(),
+            nonce_len: 12,
+            tag_len: TAG_LENGTH,
+        }
+    }
+}
+
+pub fn encrypt(
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Vec<u8> {
+    ChaChaBlake2::new(key, rounds).encrypt(plaintext, nonce, ad)
+}
+
+pub fn decrypt(
+    key: &[u8],
+    ciphertext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, TagMismatch> {
+    ChaChaBlake2::new(key, rounds).decrypt(ciphertext, nonce, ad)
+}
@@ -1,6 +1,1237 @@
 pub use crate::ciphers::chacha;
-pub use crate::errors::InvalidMac;
+use crate::ciphers::chacha::{ChaCha, KeystreamPosition};
+use crate::config::Config;
+use crate::errors::InvalidLength;
+pub use crate::errors::{DecryptError, InvalidMac, SequenceError, TagMismatch};
 pub use crate::macs::poly1305::Poly1305;
+use crate::utils::constant_time::ct_eq;
+use alloc::vec::Vec;
+use core::ops::Deref;
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+#[cfg(feature = "std")]
+use std::path::Path;
+use zeroize::Zeroize;
+
+pub const KEY_LENGTH: usize = 32;
+pub const NONCE_LENGTH: usize = 12;
+pub const TAG_LENGTH: usize = 16;
+
+/// Returns a random key from the operating system's random number generator.
+///
+/// Panics if the random number generator fails.
+#[cfg(feature = "getrandom")]
+pub fn generate_key() -> [u8; KEY_LENGTH] {
+    let mut key = [0u8; KEY_LENGTH];
+    getrandom(&mut key).expect("failed to read random bytes");
+
+    key
+}
+
+/// The longest plaintext a single message can have, in bytes, about 256 GiB.
+///
+/// The message is encrypted from block 1 on and the block counter is 32 bits (RFC 8439
+/// §2.8), so it has `2^32 - 1` blocks of 64 bytes. A longer message would wrap the counter
+/// around and reuse keystream. Encrypting one panics, see [`ChaChaPoly1305::try_encrypt`]
+/// for a version that fails instead, and decrypting one fails as if the tag did not match.
+pub const MAX_PLAINTEXT_LENGTH: u64 = u32::MAX as u64 * 64;
+
+/// Size of the chunks in which [`ChaChaPoly1305::encrypt_with_aad_file`] reads the AAD.
+/// Must be a multiple of 16, as Poly1305 pads every update to 16 bytes.
+#[cfg(feature = "std")]
+const AAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Size of the chunks in which encryption hands the ciphertext to Poly1305, so each chunk
+/// is authenticated while it is still in cache. Must be a multiple of 64, the ChaCha
+/// block size.
+const MAC_CHUNK_SIZE: usize = 4096;
+
+/// The HChaCha nonce [`ChaChaPoly1305::rekey`] derives the next key with.
+pub const REKEY_CONTEXT: [u8; 16] = *b"ChaChaPoly rekey";
+
+/// The layout of the length block Poly1305 authenticates after the AAD and ciphertext.
+///
+/// RFC 8439 §2.8 defines it as the AAD length followed by the ciphertext length, each a
+/// 64-bit little-endian integer, and every conforming implementation uses that. The other
+/// layouts are not part of any standard: they exist only to decrypt and produce messages
+/// for implementations that got the length block wrong, and messages under one layout do
+/// not authenticate under another.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LengthEncoding {
+    /// `le64(ad_len) || le64(ciphertext_len)`, as in RFC 8439.
+    #[default]
+    LittleEndian,
+    /// `be64(ad_len) || be64(ciphertext_len)`.
+    BigEndian,
+    /// `le64(ciphertext_len) || le64(ad_len)`, the lengths in the wrong order.
+    SwappedOrder,
+}
+
+impl LengthEncoding {
+    fn block(self, ad_len: u64, ciphertext_len: u64) -> [u8; 16] {
+        let (first, second) = match self {
+            LengthEncoding::LittleEndian => (ad_len.to_le_bytes(), ciphertext_len.to_le_bytes()),
+            LengthEncoding::BigEndian => (ad_len.to_be_bytes(), ciphertext_len.to_be_bytes()),
+            LengthEncoding::SwappedOrder => (ciphertext_len.to_le_bytes(), ad_len.to_le_bytes()),
+        };
+
+        let mut block = [0u8; 16];
+        block[..8].copy_from_slice(&first);
+        block[8..].copy_from_slice(&second);
+
+        block
+    }
+}
+
+pub struct ChaChaPoly1305 {
+    cipher: ChaCha,
+    length_encoding: LengthEncoding,
+}
+
+impl ChaChaPoly1305 {
+    /// Panics if `key` is not 32 bytes.
+    pub fn new(key: &[u8], rounds: Option<usize>) -> ChaChaPoly1305 {
+        ChaChaPoly1305 {
+            cipher: ChaCha::new(key, rounds),
+            length_encoding: LengthEncoding::LittleEndian,
+        }
+    }
+
+    /// Creates the cipher with ChaCha on a specific backend, see [`ChaCha::with_backend`].
+    /// Returns `None` if the CPU does not support it.
+    pub fn with_backend(
+        key: &[u8],
+        rounds: Option<usize>,
+        backend: chacha::Backend,
+    ) -> Option<ChaChaPoly1305> {
+        Some(ChaChaPoly1305 {
+            cipher: ChaCha::with_backend(key, rounds, backend)?,
+            length_encoding: LengthEncoding::LittleEndian,
+        })
+    }
+
+    /// Returns this cipher with the length block laid out as `length_encoding` in every
+    /// operation, see [`LengthEncoding`]. Only for interop with nonconforming
+    /// implementations: the default is RFC 8439.
+    pub fn with_length_encoding(mut self, length_encoding: LengthEncoding) -> ChaChaPoly1305 {
+        self.length_encoding = length_encoding;
+        self
+    }
+
+    /// Sets up Poly1305 with the one-time key and authenticates `ad`, `ciphertext`,
+    /// `trailer_ad` and the length block, each padded to 16 bytes.
+    fn authenticate(
+        &self,
+        nonce: &[u8],
+        ad: &[u8],
+        ciphertext: &[u8],
+        trailer_ad: &[u8],
+    ) -> Poly1305 {
+        let mut poly1305_key = self.cipher.keystream(nonce, 0);
+        let poly1305 = mac(
+            &poly1305_key[..32],
+            self.length_encoding,
+            ad,
+            ciphertext,
+            trailer_ad,
+        );
+        poly1305_key.zeroize();
+
+        poly1305
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        let (mut ciphertext, tag) = self.encrypt_detached(plaintext, nonce, ad);
+        ciphertext.extend_from_slice(&tag);
+
+        ciphertext
+    }
+
+    pub fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        let (ciphertext, tag) = split_tag(ciphertext).map_err(|_| TagMismatch {
+            length_valid: false,
+        })?;
+
+        self.decrypt_detached(ciphertext, tag, nonce, ad)
+    }
+
+    /// Like [`ChaChaPoly1305::encrypt`], but fails instead of panicking if `nonce` is not
+    /// 12 bytes or `plaintext` is longer than [`MAX_PLAINTEXT_LENGTH`].
+    pub fn try_encrypt(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, InvalidLength> {
+        if nonce.len() != NONCE_LENGTH || !fits_counter(plaintext.len()) {
+            return Err(InvalidLength);
+        }
+
+        Ok(self.encrypt(plaintext, nonce, ad))
+    }
+
+    /// Like [`ChaChaPoly1305::decrypt`], but fails with [`DecryptError::InvalidNonce`] if
+    /// `nonce` is not 12 bytes instead of panicking.
+    pub fn try_decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, DecryptError> {
+        if nonce.len() != NONCE_LENGTH {
+            return Err(DecryptError::InvalidNonce);
+        }
+
+        Ok(self.decrypt(ciphertext, nonce, ad)?)
+    }
+
+    /// Like [`ChaChaPoly1305::encrypt`], but runs `rounds` ChaCha rounds for this call
+    /// instead of the cipher's own, e.g. to compare round counts without creating a cipher
+    /// for each. `None` keeps the cipher's rounds. The output is identical to that of a
+    /// cipher created with `rounds`.
+    ///
+    /// Panics if `rounds` is odd or below [`MIN_ROUNDS`](crate::ciphers::chacha::MIN_ROUNDS).
+    pub fn encrypt_with_rounds(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        rounds: Option<usize>,
+    ) -> Vec<u8> {
+        match rounds {
+            Some(rounds) => self.with_rounds(rounds).encrypt(plaintext, nonce, ad),
+            None => self.encrypt(plaintext, nonce, ad),
+        }
+    }
+
+    /// Decrypts a message produced by [`ChaChaPoly1305::encrypt_with_rounds`] with the
+    /// same `rounds`. Panics if `rounds` is odd or below 8.
+    pub fn decrypt_with_rounds(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        rounds: Option<usize>,
+    ) -> Result<Vec<u8>, TagMismatch> {
+        match rounds {
+            Some(rounds) => self.with_rounds(rounds).decrypt(ciphertext, nonce, ad),
+            None => self.decrypt(ciphertext, nonce, ad),
+        }
+    }
+
+    /// Returns this cipher with its key and backend, running `rounds` rounds.
+    fn with_rounds(&self, rounds: usize) -> ChaChaPoly1305 {
+        ChaChaPoly1305 {
+            cipher: self.cipher.with_rounds(rounds),
+            length_encoding: self.length_encoding,
+        }
+    }
+
+    /// Like [`ChaChaPoly1305::decrypt`], but rejects messages whose plaintext would be
+    /// longer than `max_len` bytes before allocating anything, so untrusted input cannot
+    /// make it allocate more than the limit.
+    pub fn decrypt_bounded(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        max_len: usize,
+    ) -> Result<Vec<u8>, DecryptError> {
+        if ciphertext.len().saturating_sub(16) > max_len {
+            return Err(DecryptError::TooLong { max_len });
+        }
+
+        Ok(self.decrypt(ciphertext, nonce, ad)?)
+    }
+
+    /// Like [`ChaChaPoly1305::encrypt`], but returns the ciphertext and the tag
+    /// separately, e.g. to store the tag in a header field.
+    pub fn encrypt_detached(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> (Vec<u8>, [u8; 16]) {
+        // leave room for the tag, so joining them again does not reallocate
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + 16);
+        let tag = self.seal_into(plaintext, nonce, ad, &[], &mut ciphertext);
+
+        (ciphertext, tag.try_into().unwrap())
+    }
+
+    /// Decrypts a ciphertext and tag produced by [`ChaChaPoly1305::encrypt_detached`].
+    pub fn decrypt_detached(
+        &self,
+        ciphertext: &[u8],
+        tag: &[u8; 16],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        let mut plaintext = Vec::with_capacity(ciphertext.len());
+        self.open_into(ciphertext, tag, nonce, ad, &[], &mut plaintext)?;
+
+        Ok(plaintext)
+    }
+
+    /// Like [`ChaChaPoly1305::encrypt`], but also authenticates `trailer_ad` after the
+    /// ciphertext.
+    ///
+    /// This is not part of RFC 8439: the trailer is fed to Poly1305 (padded to 16 bytes)
+    /// between the ciphertext and the length block, and the AAD length in the length block
+    /// covers both `ad` and `trailer_ad`. Both sides have to agree on the trailer. With an
+    /// empty trailer the output is identical to [`ChaChaPoly1305::encrypt`].
+    pub fn encrypt_with_trailer(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        trailer_ad: &[u8],
+    ) -> Vec<u8> {
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + 16);
+        let tag = self.seal_into(plaintext, nonce, ad, trailer_ad, &mut ciphertext);
+        ciphertext.extend_from_slice(&tag);
+
+        ciphertext
+    }
+
+    /// Encrypts `plaintext` from block 1 on, appending the ciphertext to `out`, and returns
+    /// the tag over `ad`, the ciphertext and `trailer_ad`.
+    fn seal_into(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        trailer_ad: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Vec<u8> {
+        let start = out.len();
+        out.extend_from_slice(plaintext);
+
+        self.apply_and_mac(&mut out[start..], nonce, ad, trailer_ad)
+    }
+
+    /// Verifies `tag` and only then decrypts `ciphertext` into `out`. On a mismatch
+    /// nothing is written, so no unauthenticated plaintext is ever handed out, and the
+    /// comparison runs in constant time.
+    fn open_into(
+        &self,
+        ciphertext: &[u8],
+        tag: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        trailer_ad: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<(), TagMismatch> {
+        if !fits_counter(ciphertext.len()) {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        let mut poly1305 = self.authenticate(nonce, ad, ciphertext, trailer_ad);
+        if !poly1305.verify(tag) {
+            return Err(TagMismatch { length_valid: true });
+        }
+
+        self.cipher.encrypt_into(ciphertext, nonce, 1, out);
+
+        Ok(())
+    }
+
+    /// Encrypts `data` in place from block 1 on and returns the tag over `ad`, the
+    /// ciphertext and `trailer_ad`.
+    ///
+    /// This is a single pass: the input is processed in chunks of [`MAC_CHUNK_SIZE`] and
+    /// every chunk of ciphertext is fed to Poly1305 right after the XOR, while it is still
+    /// in cache. The chunks are multiples of 16 bytes, so no padding is added between them
+    /// and the tag equals the one over the whole ciphertext. Decryption cannot do the same,
+    /// as it checks the tag before decrypting anything.
+    fn apply_and_mac(
+        &self,
+        data: &mut [u8],
+        nonce: &[u8],
+        ad: &[u8],
+        trailer_ad: &[u8],
+    ) -> Vec<u8> {
+        chacha::last_block_counter(1, data.len() as u64)
+            .expect("plaintext is too long for the ChaCha block counter");
+
+        let mut poly1305_key = self.cipher.keystream(nonce, 0);
+        let mut poly1305 = Poly1305::new(&poly1305_key[..32]);
+        poly1305_key.zeroize();
+        poly1305.update(ad);
+
+        for (i, chunk) in data.chunks_mut(MAC_CHUNK_SIZE).enumerate() {
+            let counter = 1 + (i * MAC_CHUNK_SIZE / 64) as u32;
+
+            self.cipher.encrypt_in_place(chunk, nonce, counter);
+            poly1305.update(chunk);
+        }
+
+        poly1305.update(trailer_ad);
+        let ad_len = (ad.len() + trailer_ad.len()) as u64;
+        poly1305.update(&self.length_encoding.block(ad_len, data.len() as u64));
+
+        poly1305.tag()
+    }
+
+    /// Decrypts a message produced by [`ChaChaPoly1305::encrypt_with_trailer`].
+    pub fn decrypt_with_trailer(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        trailer_ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        if ciphertext.len() < 16 {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - 16);
+
+        let mut plaintext = Vec::with_capacity(ciphertext.len());
+        self.open_into(ciphertext, tag, nonce, ad, trailer_ad, &mut plaintext)?;
+
+        Ok(plaintext)
+    }
+
+    /// Like [`ChaChaPoly1305::encrypt`], but writes the ciphertext and tag to `out`,
+    /// replacing its contents. Reusing `out` across calls avoids reallocating.
+    pub fn encrypt_into(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8], out: &mut Vec<u8>) {
+        out.clear();
+        out.reserve(plaintext.len() + 16);
+
+        let tag = self.seal_into(plaintext, nonce, ad, &[], out);
+        out.extend_from_slice(&tag);
+    }
+
+    /// Like [`ChaChaPoly1305::decrypt`], but writes the plaintext to `out`, replacing its
+    /// contents. `out` is left empty if the tag does not match.
+    pub fn decrypt_into(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<(), TagMismatch> {
+        out.clear();
+
+        if ciphertext.len() < 16 {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - 16);
+
+        out.reserve(ciphertext.len());
+        self.open_into(ciphertext, tag, nonce, ad, &[], out)
+    }
+
+    /// Like [`ChaChaPoly1305::encrypt`], but takes the plaintext as a list of buffers, such
+    /// as `std::io::IoSlice`s or `&[u8]`s, that are encrypted as one message.
+    ///
+    /// The buffers are not copied together first. The keystream and Poly1305 run across
+    /// them byte for byte, so the buffers may have any length and the output, with a
+    /// single tag, is identical to [`ChaChaPoly1305::encrypt`] of their concatenation.
+    ///
+    /// Panics if the message is too long for the 32-bit block counter.
+    pub fn encrypt_vectored<B: Deref<Target = [u8]>>(
+        &self,
+        plaintext: &[B],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Vec<u8> {
+        let len = plaintext.iter().map(|buffer| buffer.len()).sum::<usize>();
+        let mut mac = IncrementalMac::new(self, nonce, ad);
+        mac.reserve(len);
+
+        let mut output = Vec::with_capacity(len + 16);
+        let mut position = KeystreamPosition::new(nonce, 1);
+        for buffer in plaintext {
+            let start = output.len();
+            position.apply(&self.cipher, buffer, &mut output);
+            mac.update(&output[start..]);
+        }
+        output.extend_from_slice(&mac.finish());
+
+        output
+    }
+
+    /// Decrypts a message given as a list of buffers, such as one produced by
+    /// [`ChaChaPoly1305::encrypt_vectored`] or [`ChaChaPoly1305::encrypt`] and received
+    /// in pieces. The buffers hold the ciphertext followed by the tag, which may be split
+    /// across the last of them.
+    ///
+    /// The tag is checked before anything is decrypted, and the plaintext is returned in
+    /// one buffer.
+    pub fn decrypt_vectored<B: Deref<Target = [u8]>>(
+        &self,
+        ciphertext: &[B],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        let len = ciphertext.iter().map(|buffer| buffer.len()).sum::<usize>();
+        if len < 16 || !fits_counter(len - 16) {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+        let len = len - 16;
+
+        let mut mac = IncrementalMac::new(self, nonce, ad);
+        let mut bodies = Vec::with_capacity(ciphertext.len());
+        let mut tag = [0u8; 16];
+        let mut offset = 0;
+        for buffer in ciphertext {
+            let (body, trailing) = buffer.split_at(len.saturating_sub(offset).min(buffer.len()));
+            mac.update(body);
+            bodies.push(body);
+
+            if !trailing.is_empty() {
+                let start = offset + body.len() - len;
+                tag[start..start + trailing.len()].copy_from_slice(trailing);
+            }
+            offset += buffer.len();
+        }
+
+        if !ct_eq(&mac.finish(), &tag) {
+            return Err(TagMismatch { length_valid: true });
+        }
+
+        let mut plaintext = Vec::with_capacity(len);
+        let mut position = KeystreamPosition::new(nonce, 1);
+        for body in bodies {
+            position.apply(&self.cipher, body, &mut plaintext);
+        }
+
+        Ok(plaintext)
+    }
+
+    /// Encrypts a message made of segments, of which only the flagged ones are encrypted.
+    ///
+    /// The output is the segments in order, each either encrypted (`true`) or copied as
+    /// is (`false`), followed by a 16-byte tag over `ad` and the whole output, computed as
+    /// in RFC 8439. The keystream runs over the encrypted segments only, starting at block
+    /// 1: it continues byte for byte from one encrypted segment to the next and does not
+    /// advance over cleartext segments. With every segment encrypted the output is
+    /// identical to [`ChaChaPoly1305::encrypt`] of their concatenation.
+    pub fn encrypt_segments(&self, segments: &[(&[u8], bool)], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        let len = segments
+            .iter()
+            .map(|(segment, _)| segment.len())
+            .sum::<usize>();
+        let mut output = Vec::with_capacity(len + 16);
+
+        let mut position = 0;
+        for (segment, encrypt) in segments {
+            if *encrypt {
+                output.extend(self.apply_keystream_at(segment, nonce, position));
+                position += segment.len();
+            } else {
+                output.extend_from_slice(segment);
+            }
+        }
+
+        let tag = self.authenticate(nonce, ad, &output, &[]).tag();
+        output.extend_from_slice(&tag);
+
+        output
+    }
+
+    /// Decrypts a message produced by [`ChaChaPoly1305::encrypt_segments`], given the
+    /// length and flag of every segment. Returns the segments' plaintext concatenated.
+    pub fn decrypt_segments(
+        &self,
+        ciphertext: &[u8],
+        segments: &[(usize, bool)],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        let len = segments.iter().map(|(len, _)| len).sum::<usize>();
+        if ciphertext.len() != len + 16 || !fits_counter(len) {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        let (ciphertext, tag) = ciphertext.split_at(len);
+
+        if !self.authenticate(nonce, ad, ciphertext, &[]).verify(tag) {
+            return Err(TagMismatch { length_valid: true });
+        }
+
+        let mut plaintext = Vec::with_capacity(len);
+        let mut start = 0;
+        let mut position = 0;
+        for (len, encrypted) in segments {
+            let segment = &ciphertext[start..start + len];
+            start += len;
+
+            if *encrypted {
+                plaintext.extend(self.apply_keystream_at(segment, nonce, position));
+                position += len;
+            } else {
+                plaintext.extend_from_slice(segment);
+            }
+        }
+
+        Ok(plaintext)
+    }
+
+    /// XORs `data` with the message keystream from byte `position` on.
+    fn apply_keystream_at(&self, data: &[u8], nonce: &[u8], position: usize) -> Vec<u8> {
+        let mut output = data.to_vec();
+        // the message keystream starts at block 1
+        self.cipher
+            .apply_keystream_range(&mut output, nonce, 64 + position as u64);
+
+        output
+    }
+
+    /// Like [`ChaChaPoly1305::encrypt`], but the AAD is built from several fields.
+    ///
+    /// Each field is preceded by its length as a 4-byte little-endian integer, and the
+    /// framed fields are concatenated and authenticated as the AAD. Unlike plain
+    /// concatenation this is unambiguous: `["ab", "c"]` and `["a", "bc"]` give different
+    /// tags. Panics if a field is longer than `u32::MAX` bytes.
+    pub fn encrypt_framed_aad(&self, plaintext: &[u8], nonce: &[u8], fields: &[&[u8]]) -> Vec<u8> {
+        self.encrypt(plaintext, nonce, &frame_aad(fields))
+    }
+
+    /// Decrypts a message produced by [`ChaChaPoly1305::encrypt_framed_aad`].
+    pub fn decrypt_framed_aad(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        fields: &[&[u8]],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        self.decrypt(ciphertext, nonce, &frame_aad(fields))
+    }
+
+    /// Like [`ChaChaPoly1305::encrypt`], but binds the message to an application context
+    /// string for domain separation.
+    ///
+    /// The authenticated data is the framing of [`ChaChaPoly1305::encrypt_framed_aad`]
+    /// over the fields `[context, ad]`, so `len(context) || context || len(ad) || ad` with
+    /// 4-byte little-endian lengths. A ciphertext from one context does not decrypt in
+    /// another, whatever the AAD. Panics if `context` or `ad` is longer than `u32::MAX`
+    /// bytes.
+    pub fn encrypt_with_context(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        context: &str,
+    ) -> Vec<u8> {
+        self.encrypt(plaintext, nonce, &context_aad(ad, context))
+    }
+
+    /// Decrypts a message produced by [`ChaChaPoly1305::encrypt_with_context`].
+    pub fn decrypt_with_context(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        context: &str,
+    ) -> Result<Vec<u8>, TagMismatch> {
+        self.decrypt(ciphertext, nonce, &context_aad(ad, context))
+    }
+
+    /// Encrypts `buffer` in place and appends the 16-byte tag, so `buffer` ends up holding
+    /// the same bytes [`ChaChaPoly1305::encrypt`] would return. Only the tag may need the
+    /// buffer to grow.
+    pub fn encrypt_in_place(&self, buffer: &mut Vec<u8>, nonce: &[u8], ad: &[u8]) {
+        let tag = self.apply_and_mac(buffer, nonce, ad, &[]);
+        buffer.extend_from_slice(&tag);
+    }
+
+    /// Decrypts `buffer`, holding the ciphertext followed by the tag, in place.
+    ///
+    /// The tag is verified before anything is written. On success `buffer` holds the
+    /// plaintext, with the tag truncated off; on failure it is left untouched.
+    pub fn decrypt_in_place(
+        &self,
+        buffer: &mut Vec<u8>,
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<(), TagMismatch> {
+        if buffer.len() < 16 || !fits_counter(buffer.len() - 16) {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        let (ciphertext, tag) = buffer.split_at(buffer.len() - 16);
+
+        if !self.authenticate(nonce, ad, ciphertext, &[]).verify(tag) {
+            return Err(TagMismatch { length_valid: true });
+        }
+
+        buffer.truncate(ciphertext.len());
+        self.cipher.encrypt_in_place(buffer, nonce, 1);
+
+        Ok(())
+    }
+
+    /// Like [`ChaChaPoly1305::encrypt`], but reads the AAD from the file at `aad_path`.
+    ///
+    /// The file is streamed through Poly1305 in chunks rather than loaded into memory,
+    /// so this suits large AAD such as manifests. The output is identical to calling
+    /// [`ChaChaPoly1305::encrypt`] with the file contents as `ad`.
+    #[cfg(feature = "std")]
+    pub fn encrypt_with_aad_file<P: AsRef<Path>>(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad_path: P,
+    ) -> io::Result<Vec<u8>> {
+        let mut file = File::open(aad_path)?;
+
+        let mut poly1305_key = self.cipher.keystream(nonce, 0);
+        let mut poly1305 = Poly1305::new(&poly1305_key[..32]);
+        poly1305_key.zeroize();
+
+        let mut buffer = alloc::vec![0u8; AAD_CHUNK_SIZE];
+        let mut ad_len = 0u64;
+        loop {
+            // only the last chunk may be shorter than the buffer
+            let mut filled = 0;
+            while filled < buffer.len() {
+                match file.read(&mut buffer[filled..]) {
+                    Ok(0) => break,
+                    Ok(n) => filled += n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            poly1305.update(&buffer[..filled]);
+            ad_len += filled as u64;
+
+            if filled < buffer.len() {
+                break;
+            }
+        }
+
+        // the output is allocated once, with room for the tag after the ciphertext
+        let mut output = Vec::with_capacity(plaintext.len() + 16);
+        self.cipher.encrypt_into(plaintext, nonce, 1, &mut output);
+        poly1305.update(&output);
+        poly1305.update(&self.length_encoding.block(ad_len, output.len() as u64));
+        output.extend_from_slice(&poly1305.tag());
+
+        Ok(output)
+    }
+
+    /// Like [`ChaChaPoly1305::encrypt`], but takes the AAD as chunks, e.g. protocol
+    /// headers held in separate buffers.
+    ///
+    /// The chunks may have any length: their concatenation is authenticated exactly as if
+    /// it had been passed as `ad`, so the output decrypts with either
+    /// [`ChaChaPoly1305::decrypt`] or [`ChaChaPoly1305::decrypt_with_aad_chunks`].
+    pub fn encrypt_with_aad_chunks<I>(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad_chunks: I,
+    ) -> Vec<u8>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let mut mac = IncrementalMac::with_ad_chunks(self, nonce, ad_chunks);
+        mac.reserve(plaintext.len());
+
+        let mut output = Vec::with_capacity(plaintext.len() + 16);
+        self.cipher.encrypt_into(plaintext, nonce, 1, &mut output);
+        mac.update(&output);
+        output.extend_from_slice(&mac.finish());
+
+        output
+    }
+
+    /// Like [`ChaChaPoly1305::decrypt`], but takes the AAD as chunks that are fed to
+    /// Poly1305 as they are produced, e.g. from an iterator or [`core::iter::from_fn`].
+    ///
+    /// The whole AAD is never held in memory. The chunks may have any length: their
+    /// concatenation is authenticated exactly as if it had been passed as `ad`. The tag is
+    /// checked before anything is decrypted.
+    pub fn decrypt_with_aad_chunks<I>(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad_chunks: I,
+    ) -> Result<Vec<u8>, TagMismatch>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let (ciphertext, tag) = split_tag(ciphertext).map_err(|_| TagMismatch {
+            length_valid: false,
+        })?;
+        if !fits_counter(ciphertext.len()) {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        let mut mac = IncrementalMac::with_ad_chunks(self, nonce, ad_chunks);
+        mac.reserve(ciphertext.len());
+        mac.update(ciphertext);
+
+        if !ct_eq(&mac.finish(), tag) {
+            return Err(TagMismatch { length_valid: true });
+        }
+
+        let mut plaintext = ciphertext.to_vec();
+        self.cipher.encrypt_in_place(&mut plaintext, nonce, 1);
+
+        Ok(plaintext)
+    }
+
+    /// Like [`ChaChaPoly1305::encrypt`], but with an explicitly supplied Poly1305 key
+    /// instead of one derived from keystream block 0.
+    ///
+    /// This deviates from RFC 8439 and is meant for experimental Encrypt-then-MAC
+    /// constructions with independent keys. The output is not compatible with
+    /// [`ChaChaPoly1305::encrypt`]. Encryption still starts at block 1, and block 0 is
+    /// unused. `mac_key` must be used for a single message only, as Poly1305 is a
+    /// one-time MAC.
+    pub fn encrypt_with_mac_key(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        mac_key: &[u8; 32],
+    ) -> Vec<u8> {
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + 16);
+        self.cipher
+            .encrypt_into(plaintext, nonce, 1, &mut ciphertext);
+
+        let tag = mac(mac_key, self.length_encoding, ad, &ciphertext, &[]).tag();
+        ciphertext.extend_from_slice(&tag);
+
+        ciphertext
+    }
+
+    /// Decrypts a message produced by [`ChaChaPoly1305::encrypt_with_mac_key`].
+    pub fn decrypt_with_mac_key(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        mac_key: &[u8; 32],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        if ciphertext.len() < 16 || !fits_counter(ciphertext.len() - 16) {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - 16);
+
+        if mac(mac_key, self.length_encoding, ad, ciphertext, &[]).verify(tag) {
+            Ok(self.cipher.encrypt(ciphertext, nonce))
+        } else {
+            Err(TagMismatch { length_valid: true })
+        }
+    }
+
+    /// Encrypts each message under its own nonce, derived from `base_nonce` with
+    /// [`sequence_nonce`] using the message's position in `messages`.
+    pub fn encrypt_sequence(
+        &self,
+        messages: Vec<Vec<u8>>,
+        base_nonce: &[u8; 12],
+        ad: &[u8],
+    ) -> Vec<Vec<u8>> {
+        messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| {
+                let nonce = sequence_nonce(base_nonce, index as u64);
+                self.encrypt(message, &nonce, ad)
+            })
+            .collect()
+    }
+
+    /// Decrypts messages produced by [`ChaChaPoly1305::encrypt_sequence`], in order.
+    ///
+    /// Stops at the first message that does not authenticate and returns its index. The
+    /// plaintext of the messages before it is wiped, so the sequence is only returned as
+    /// a whole.
+    pub fn decrypt_sequence(
+        &self,
+        ciphertexts: &[Vec<u8>],
+        base_nonce: &[u8; 12],
+        ad: &[u8],
+    ) -> Result<Vec<Vec<u8>>, SequenceError> {
+        let mut plaintexts = Vec::with_capacity(ciphertexts.len());
+
+        for (index, ciphertext) in ciphertexts.iter().enumerate() {
+            let nonce = sequence_nonce(base_nonce, index as u64);
+
+            match self.decrypt(ciphertext, &nonce, ad) {
+                Ok(plaintext) => plaintexts.push(plaintext),
+                Err(error) => {
+                    plaintexts.iter_mut().for_each(Zeroize::zeroize);
+                    return Err(SequenceError { index, error });
+                }
+            }
+        }
+
+        Ok(plaintexts)
+    }
+
+    /// Advances the key for forward secrecy and returns the cipher under the new key.
+    ///
+    /// The new key is `HChaCha(key, REKEY_CONTEXT)`, with the same number of rounds. Both
+    /// sides of a session rekey at agreed points, such as every so many messages, to get
+    /// the same chain of keys. `self` is consumed and its key state is wiped, so a
+    /// compromise after rekeying does not reveal earlier keys, while anyone holding an
+    /// earlier key can still derive every later one. Messages encrypted before rekeying
+    /// can no longer be decrypted by this cipher.
+    pub fn rekey(self) -> ChaChaPoly1305 {
+        let mut key = self.cipher.hchacha(&REKEY_CONTEXT);
+        let next = ChaChaPoly1305::new(&key, Some(self.cipher.rounds()))
+            .with_length_encoding(self.length_encoding);
+        key.zeroize();
+
+        next
+    }
+
+    /// Starts an incremental encryption of a message too large to hold in memory at once.
+    ///
+    /// Ciphertext is returned piece by piece from [`Encryptor::update`] and the tag by
+    /// [`Encryptor::finalize`]. The output is identical to [`ChaChaPoly1305::encrypt`] of
+    /// the whole message, however the plaintext is split.
+    pub fn encryptor(&self, nonce: &[u8], ad: &[u8]) -> Encryptor<'_> {
+        Encryptor {
+            cipher: StreamCipher::Borrowed(&self.cipher),
+            position: KeystreamPosition::new(nonce, 1),
+            mac: IncrementalMac::new(self, nonce, ad),
+        }
+    }
+
+    /// Starts an incremental decryption of a message produced by
+    /// [`ChaChaPoly1305::encrypt`] or an [`Encryptor`]. See [`Decryptor`] for the caveats.
+    pub fn decryptor(&self, nonce: &[u8], ad: &[u8]) -> Decryptor<'_> {
+        Decryptor {
+            cipher: StreamCipher::Borrowed(&self.cipher),
+            position: KeystreamPosition::new(nonce, 1),
+            mac: IncrementalMac::new(self, nonce, ad),
+        }
+    }
+
+    /// Like [`ChaChaPoly1305::encryptor`], but takes the cipher along, so the stream can
+    /// be kept or returned on its own.
+    pub fn into_encryptor(self, nonce: &[u8], ad: &[u8]) -> Encryptor<'static> {
+        let mac = IncrementalMac::new(&self, nonce, ad);
+
+        Encryptor {
+            cipher: StreamCipher::Owned(self.cipher),
+            position: KeystreamPosition::new(nonce, 1),
+            mac,
+        }
+    }
+
+    /// Like [`ChaChaPoly1305::decryptor`], but takes the cipher along, so the stream can
+    /// be kept or returned on its own.
+    pub fn into_decryptor(self, nonce: &[u8], ad: &[u8]) -> Decryptor<'static> {
+        let mac = IncrementalMac::new(&self, nonce, ad);
+
+        Decryptor {
+            cipher: StreamCipher::Owned(self.cipher),
+            position: KeystreamPosition::new(nonce, 1),
+            mac,
+        }
+    }
+
+    pub fn config(&self) -> Config {
+        Config {
+            algorithm: "ChaCha-Poly1305",
+            rounds: self.cipher.rounds(),
+            nonce_len: NONCE_LENGTH,
+            tag_len: TAG_LENGTH,
+        }
+    }
+}
+
+/// Feeds the ciphertext of an incremental encryption or decryption to Poly1305.
+///
+/// Poly1305 pads every update to 16 bytes, so ciphertext is only passed on in whole
+/// 16-byte blocks, and up to 15 bytes wait in `pending` for the next chunk. The final
+/// partial block is padded once, in [`IncrementalMac::finish`], as for a one-shot message.
+struct IncrementalMac {
+    poly1305: Poly1305,
+    pending: [u8; 16],
+    pending_len: usize,
+    ad_len: u64,
+    ciphertext_len: u64,
+    length_encoding: LengthEncoding,
+    /// Whether more AAD may follow, i.e. the AAD has not been padded yet.
+    ad_open: bool,
+}
+
+impl IncrementalMac {
+    fn new(aead: &ChaChaPoly1305, nonce: &[u8], ad: &[u8]) -> IncrementalMac {
+        let mut poly1305_key = aead.cipher.keystream(nonce, 0);
+        let poly1305 = Poly1305::new(&poly1305_key[..32]);
+        poly1305_key.zeroize();
+
+        let mut mac = IncrementalMac {
+            poly1305,
+            pending: [0u8; 16],
+            pending_len: 0,
+            ad_len: 0,
+            ciphertext_len: 0,
+            length_encoding: aead.length_encoding,
+            ad_open: true,
+        };
+        mac.update_ad(ad);
+
+        mac
+    }
+
+    /// Like [`IncrementalMac::new`], but authenticates the AAD chunk by chunk.
+    fn with_ad_chunks<I>(aead: &ChaChaPoly1305, nonce: &[u8], ad_chunks: I) -> IncrementalMac
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let mut mac = IncrementalMac::new(aead, nonce, &[]);
+        for chunk in ad_chunks {
+            mac.update_ad(chunk.as_ref());
+        }
+
+        mac
+    }
+
+    /// Authenticates more AAD. It goes through the same 16-byte buffering as the
+    /// ciphertext, and is padded once, when the first ciphertext arrives or the tag is
+    /// computed.
+    fn update_ad(&mut self, ad: &[u8]) {
+        assert!(self.ad_open, "AAD must be supplied before any message data");
+
+        self.ad_len += ad.len() as u64;
+        self.absorb(ad);
+    }
+
+    fn close_ad(&mut self) {
+        if self.ad_open {
+            self.poly1305.update(&self.pending[..self.pending_len]);
+            self.pending_len = 0;
+            self.ad_open = false;
+        }
+    }
+
+    /// Checks that `len` more bytes fit under the block counter before they are processed.
+    fn reserve(&self, len: usize) {
+        chacha::last_block_counter(1, self.ciphertext_len + len as u64)
+            .expect("message is too long for the ChaCha block counter");
+    }
+
+    fn update(&mut self, ciphertext: &[u8]) {
+        self.close_ad();
+        self.ciphertext_len += ciphertext.len() as u64;
+        self.absorb(ciphertext);
+    }
+
+    /// Passes `data` on to Poly1305 in whole 16-byte blocks, keeping the rest pending.
+    fn absorb(&mut self, data: &[u8]) {
+        let fill = (16 - self.pending_len).min(data.len());
+        self.pending[self.pending_len..self.pending_len + fill].copy_from_slice(&data[..fill]);
+        self.pending_len += fill;
+        let data = &data[fill..];
+
+        if self.pending_len < 16 {
+            return;
+        }
+        self.poly1305.update(&self.pending);
+
+        let whole = data.len() - data.len() % 16;
+        self.poly1305.update(&data[..whole]);
+
+        self.pending_len = data.len() - whole;
+        self.pending[..self.pending_len].copy_from_slice(&data[whole..]);
+    }
+
+    fn finish(mut self) -> [u8; 16] {
+        self.close_ad();
+        self.poly1305.update(&self.pending[..self.pending_len]);
+        let lengths = self.length_encoding.block(self.ad_len, self.ciphertext_len);
+        self.poly1305.update(&lengths);
+
+        let mut tag = [0u8; 16];
+        tag.copy_from_slice(&self.poly1305.tag());
+
+        tag
+    }
+}
+
+/// The cipher an [`Encryptor`] or [`Decryptor`] runs on, either borrowed from a
+/// [`ChaChaPoly1305`] or owned by the stream.
+enum StreamCipher<'a> {
+    Borrowed(&'a ChaCha),
+    Owned(ChaCha),
+}
+
+impl Deref for StreamCipher<'_> {
+    type Target = ChaCha;
+
+    fn deref(&self) -> &ChaCha {
+        match self {
+            StreamCipher::Borrowed(cipher) => cipher,
+            StreamCipher::Owned(cipher) => cipher,
+        }
+    }
+}
+
+/// Incremental ChaCha-Poly1305 encryption, see [`ChaChaPoly1305::encryptor`].
+///
+/// Chunks may have any length. The keystream carries over between calls byte for byte, so
+/// a chunk that ends inside a 64-byte block leaves the rest of that block for the next
+/// one, and the block counter advances exactly as in one-shot encryption.
+pub struct Encryptor<'a> {
+    cipher: StreamCipher<'a>,
+    position: KeystreamPosition,
+    mac: IncrementalMac,
+}
+
+impl Encryptor<'_> {
+    /// Authenticates another part of the AAD, after the `ad` the encryption was started
+    /// with. The parts are authenticated as their concatenation, so AAD spread over several
+    /// buffers need not be copied together first.
+    ///
+    /// Panics if called after [`Encryptor::update`].
+    pub fn update_aad(&mut self, ad: &[u8]) {
+        self.mac.update_ad(ad);
+    }
+
+    /// Encrypts the next chunk of plaintext and returns its ciphertext.
+    ///
+    /// Panics if the message grows past the end of the 32-bit block counter.
+    pub fn update(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.mac.reserve(chunk.len());
+
+        let mut ciphertext = Vec::with_capacity(chunk.len());
+        self.position.apply(&self.cipher, chunk, &mut ciphertext);
+        self.mac.update(&ciphertext);
+
+        ciphertext
+    }
+
+    /// Returns the tag over the AAD and all ciphertext returned so far.
+    pub fn finalize(self) -> [u8; 16] {
+        self.mac.finish()
+    }
+}
+
+/// Incremental ChaCha-Poly1305 decryption, see [`ChaChaPoly1305::decryptor`].
+///
+/// [`Decryptor::update`] returns plaintext before the tag has been checked, as the tag
+/// only arrives at the end. That plaintext is unauthenticated: it must not be acted on,
+/// and has to be discarded if [`Decryptor::finalize`] fails. Use
+/// [`ChaChaPoly1305::decrypt`] or the STREAM construction in
+/// [`stream`](crate::aeads::stream) where that is not acceptable.
+pub struct Decryptor<'a> {
+    cipher: StreamCipher<'a>,
+    position: KeystreamPosition,
+    mac: IncrementalMac,
+}
+
+impl Decryptor<'_> {
+    /// Authenticates another part of the AAD, see [`Encryptor::update_aad`].
+    ///
+    /// Panics if called after [`Decryptor::update`].
+    pub fn update_aad(&mut self, ad: &[u8]) {
+        self.mac.update_ad(ad);
+    }
+
+    /// Decrypts the next chunk of ciphertext, without its tag, and returns the
+    /// unauthenticated plaintext.
+    ///
+    /// Panics if the message grows past the end of the 32-bit block counter.
+    pub fn update(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.mac.reserve(chunk.len());
+        self.mac.update(chunk);
+
+        let mut plaintext = Vec::with_capacity(chunk.len());
+        self.position.apply(&self.cipher, chunk, &mut plaintext);
+
+        plaintext
+    }
+
+    /// Checks `tag` against the AAD and all ciphertext passed to [`Decryptor::update`].
+    pub fn finalize(self, tag: &[u8]) -> Result<(), TagMismatch> {
+        if tag.len() != 16 {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        if ct_eq(&self.mac.finish(), tag) {
+            Ok(())
+        } else {
+            Err(TagMismatch { length_valid: true })
+        }
+    }
+}
+
+/// Returns whether a message of `len` bytes fits under the block counter, see
+/// [`MAX_PLAINTEXT_LENGTH`].
+fn fits_counter(len: usize) -> bool {
+    len as u64 <= MAX_PLAINTEXT_LENGTH
+}
+
+/// Authenticates `ad`, `ciphertext`, `trailer_ad` and the length block laid out as
+/// `length_encoding`, each padded to 16 bytes, with Poly1305 under `poly1305_key`.
+fn mac(
+    poly1305_key: &[u8],
+    length_encoding: LengthEncoding,
+    ad: &[u8],
+    ciphertext: &[u8],
+    trailer_ad: &[u8],
+) -> Poly1305 {
+    let mut poly1305 = Poly1305::new(poly1305_key);
+
+    poly1305.update(ad);
+    poly1305.update(ciphertext);
+    poly1305.update(trailer_ad);
+
+    let ad_len = (ad.len() + trailer_ad.len()) as u64;
+    poly1305.update(&length_encoding.block(ad_len, ciphertext.len() as u64));
+
+    poly1305
+}
+
+fn frame_aad(fields: &[&[u8]]) -> Vec<u8> {
+    let mut ad = Vec::with_capacity(fields.iter().map(|field| field.len() + 4).sum());
+
+    for field in fields {
+        let len = u32::try_from(field.len()).expect("AAD field is too long");
+        ad.extend_from_slice(&len.to_le_bytes());
+        ad.extend_from_slice(field);
+    }
+
+    ad
+}
+
+fn context_aad(ad: &[u8], context: &str) -> Vec<u8> {
+    frame_aad(&[context.as_bytes(), ad])
+}
 
 pub fn encrypt(
     key: &[u8],
@@ -9,43 +1240,255 @@ pub fn encrypt(
     ad: &[u8],
     rounds: Option<usize>,
 ) -> Vec<u8> {
-    let ciphertext = chacha::encrypt(key, plaintext, nonce, rounds);
+    ChaChaPoly1305::new(key, rounds).encrypt(plaintext, nonce, ad)
+}
 
-    let poly1305_key = chacha::keystream(key, nonce, 0, rounds);
-    let mut poly1305 = Poly1305::new(&poly1305_key);
+pub fn decrypt(
+    key: &[u8],
+    ciphertext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, TagMismatch> {
+    ChaChaPoly1305::new(key, rounds).decrypt(ciphertext, nonce, ad)
+}
 
-    poly1305.update(ad);
-    poly1305.update(&ciphertext);
+pub fn encrypt_with_trailer(
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    trailer_ad: &[u8],
+    rounds: Option<usize>,
+) -> Vec<u8> {
+    ChaChaPoly1305::new(key, rounds).encrypt_with_trailer(plaintext, nonce, ad, trailer_ad)
+}
+
+pub fn try_encrypt(
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, InvalidLength> {
+    ChaChaPoly1305::new(key, rounds).try_encrypt(plaintext, nonce, ad)
+}
+
+pub fn try_decrypt(
+    key: &[u8],
+    ciphertext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, DecryptError> {
+    ChaChaPoly1305::new(key, rounds).try_decrypt(ciphertext, nonce, ad)
+}
+
+pub fn decrypt_with_trailer(
+    key: &[u8],
+    ciphertext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    trailer_ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, TagMismatch> {
+    ChaChaPoly1305::new(key, rounds).decrypt_with_trailer(ciphertext, nonce, ad, trailer_ad)
+}
 
-    let lengths = [(ad.len() as u64).to_le_bytes(), (ciphertext.len() as u64).to_le_bytes()].concat();
-    poly1305.update(&lengths);
+pub fn encrypt_framed_aad(
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    fields: &[&[u8]],
+    rounds: Option<usize>,
+) -> Vec<u8> {
+    ChaChaPoly1305::new(key, rounds).encrypt_framed_aad(plaintext, nonce, fields)
+}
 
-    let tag = poly1305.tag();
+pub fn decrypt_framed_aad(
+    key: &[u8],
+    ciphertext: &[u8],
+    nonce: &[u8],
+    fields: &[&[u8]],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, TagMismatch> {
+    ChaChaPoly1305::new(key, rounds).decrypt_framed_aad(ciphertext, nonce, fields)
+}
 
-    [ciphertext, tag].concat().to_vec()
+pub fn encrypt_with_context(
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    context: &str,
+    rounds: Option<usize>,
+) -> Vec<u8> {
+    ChaChaPoly1305::new(key, rounds).encrypt_with_context(plaintext, nonce, ad, context)
 }
 
-pub fn decrypt(
+pub fn decrypt_with_context(
     key: &[u8],
     ciphertext: &[u8],
     nonce: &[u8],
     ad: &[u8],
+    context: &str,
     rounds: Option<usize>,
-) -> Result<Vec<u8>, InvalidMac> {
-    let plaintext = chacha::decrypt(key, ciphertext, nonce, rounds);
+) -> Result<Vec<u8>, TagMismatch> {
+    ChaChaPoly1305::new(key, rounds).decrypt_with_context(ciphertext, nonce, ad, context)
+}
 
-    let poly1305_key = chacha::keystream(key, nonce, 0, rounds);
-    let mut poly1305 = Poly1305::new(&poly1305_key);
+pub fn encrypt_in_place(
+    key: &[u8],
+    buffer: &mut Vec<u8>,
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) {
+    ChaChaPoly1305::new(key, rounds).encrypt_in_place(buffer, nonce, ad)
+}
 
-    poly1305.update(ad);
-    poly1305.update(&ciphertext);
+pub fn decrypt_bounded(
+    key: &[u8],
+    ciphertext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    max_len: usize,
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, DecryptError> {
+    ChaChaPoly1305::new(key, rounds).decrypt_bounded(ciphertext, nonce, ad, max_len)
+}
 
-    let lengths = [(ad.len() as u64).to_le_bytes(), (ciphertext.len() as u64).to_le_bytes()].concat();
-    poly1305.update(&lengths);
+pub fn encrypt_detached(
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> (Vec<u8>, [u8; 16]) {
+    ChaChaPoly1305::new(key, rounds).encrypt_detached(plaintext, nonce, ad)
+}
 
-    if poly1305.verify(&ciphertext[ciphertext.len() - 16..]) {
-        Ok(plaintext)
-    } else {
-        Err(InvalidMac)
+pub fn decrypt_detached(
+    key: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8; 16],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, TagMismatch> {
+    ChaChaPoly1305::new(key, rounds).decrypt_detached(ciphertext, tag, nonce, ad)
+}
+
+pub fn decrypt_in_place(
+    key: &[u8],
+    buffer: &mut Vec<u8>,
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<(), TagMismatch> {
+    ChaChaPoly1305::new(key, rounds).decrypt_in_place(buffer, nonce, ad)
+}
+
+#[cfg(feature = "std")]
+pub fn encrypt_with_aad_file<P: AsRef<Path>>(
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    aad_path: P,
+    rounds: Option<usize>,
+) -> io::Result<Vec<u8>> {
+    ChaChaPoly1305::new(key, rounds).encrypt_with_aad_file(plaintext, nonce, aad_path)
+}
+
+pub fn encrypt_with_aad_chunks<I>(
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    ad_chunks: I,
+    rounds: Option<usize>,
+) -> Vec<u8>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    ChaChaPoly1305::new(key, rounds).encrypt_with_aad_chunks(plaintext, nonce, ad_chunks)
+}
+
+pub fn decrypt_with_aad_chunks<I>(
+    key: &[u8],
+    ciphertext: &[u8],
+    nonce: &[u8],
+    ad_chunks: I,
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, TagMismatch>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    ChaChaPoly1305::new(key, rounds).decrypt_with_aad_chunks(ciphertext, nonce, ad_chunks)
+}
+
+/// Splits a combined `ciphertext || tag` into the ciphertext and the 16-byte tag, without
+/// copying. Fails if `combined` is shorter than a tag.
+pub fn split_tag(combined: &[u8]) -> Result<(&[u8], &[u8; 16]), InvalidLength> {
+    if combined.len() < 16 {
+        return Err(InvalidLength);
+    }
+
+    let (ciphertext, tag) = combined.split_at(combined.len() - 16);
+
+    Ok((ciphertext, tag.try_into().unwrap()))
+}
+
+/// Joins a detached ciphertext and tag into the combined `ciphertext || tag` layout used
+/// by [`encrypt`] and [`decrypt`].
+pub fn join_tag(ciphertext: &[u8], tag: &[u8; 16]) -> Vec<u8> {
+    let mut combined = Vec::with_capacity(ciphertext.len() + 16);
+    combined.extend_from_slice(ciphertext);
+    combined.extend_from_slice(tag);
+
+    combined
+}
+
+/// Derives the nonce for message `index` of a sequence.
+///
+/// Following TLS 1.3 (RFC 8446 §5.3), `index` is encoded as a big-endian 64-bit integer,
+/// left-padded to 12 bytes and XORed into `base_nonce`. Every index therefore gets a
+/// distinct nonce for the same base nonce.
+///
+/// The base nonce is exactly 12 bytes, so one of another length does not compile:
+///
+/// ```compile_fail
+/// use raycrypt::aeads::chachapoly1305::sequence_nonce;
+///
+/// sequence_nonce(&[0u8; 8], 0);
+/// ```
+pub fn sequence_nonce(base_nonce: &[u8; 12], index: u64) -> [u8; 12] {
+    let mut nonce = *base_nonce;
+
+    for (i, j) in nonce[4..].iter_mut().zip(index.to_be_bytes()) {
+        *i ^= j;
     }
+
+    nonce
+}
+
+pub fn encrypt_sequence(
+    key: &[u8],
+    messages: Vec<Vec<u8>>,
+    base_nonce: &[u8; 12],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Vec<Vec<u8>> {
+    ChaChaPoly1305::new(key, rounds).encrypt_sequence(messages, base_nonce, ad)
+}
+
+pub fn decrypt_sequence(
+    key: &[u8],
+    ciphertexts: &[Vec<u8>],
+    base_nonce: &[u8; 12],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<Vec<u8>>, SequenceError> {
+    ChaChaPoly1305::new(key, rounds).decrypt_sequence(ciphertexts, base_nonce, ad)
 }
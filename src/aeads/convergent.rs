@@ -0,0 +1,66 @@
+//! Convergent encryption with ChaCha-Poly1305, for deduplicating encrypted storage.
+//!
+//! The content key is derived from the plaintext itself, so identical plaintexts encrypt
+//! to identical ciphertexts and can be stored once. The derivation hashes the AAD and the
+//! plaintext with SHA-256 and expands the digest with [`chacha::expand`] under a
+//! `secret`, the convergence secret shared by everyone who should be able to deduplicate
+//! against each other. Every content key encrypts exactly one plaintext and AAD pair, so
+//! the nonce is fixed at zero.
+//!
+//! # Warning
+//!
+//! Convergent encryption deliberately leaks equality: anyone who sees two ciphertexts
+//! learns whether the plaintexts are the same. Anyone who also holds the secret can go
+//! further and confirm a guess of a plaintext by encrypting it and comparing, and for
+//! plaintexts with little entropy, such as a form letter with a few unknown fields, can
+//! recover them by brute force. A secret only keeps outsiders from doing this. Do not use
+//! this mode for data that is guessable or whose presence alone is sensitive.
+use crate::aeads::chachapoly1305::ChaChaPoly1305;
+use crate::ciphers::chacha;
+pub use crate::errors::TagMismatch;
+use crate::hashes::sha2::Sha256;
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+const NONCE: [u8; 12] = [0u8; 12];
+
+/// Derives the content key for `plaintext` and `ad` under the convergence secret.
+pub fn derive_key(secret: &[u8; 32], plaintext: &[u8], ad: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(&(ad.len() as u64).to_le_bytes());
+    hasher.update(ad);
+    hasher.update(plaintext);
+    let digest = hasher.finalize();
+
+    let mut expanded = chacha::expand(secret, &digest, 32);
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&expanded);
+    expanded.zeroize();
+
+    key
+}
+
+/// Encrypts `plaintext` under its derived content key and returns the key along with
+/// `ciphertext || tag`. The key is needed for decryption and has to be stored alongside
+/// a reference to the ciphertext.
+pub fn encrypt(
+    secret: &[u8; 32],
+    plaintext: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> ([u8; 32], Vec<u8>) {
+    let key = derive_key(secret, plaintext, ad);
+    let ciphertext = ChaChaPoly1305::new(&key, rounds).encrypt(plaintext, &NONCE, ad);
+
+    (key, ciphertext)
+}
+
+/// Decrypts a message produced by [`encrypt`] with its content key.
+pub fn decrypt(
+    key: &[u8; 32],
+    ciphertext: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, TagMismatch> {
+    ChaChaPoly1305::new(key, rounds).decrypt(ciphertext, &NONCE, ad)
+}
@@ -0,0 +1,222 @@
+//! Public-key authenticated encryption with X25519 and XChaCha20-Poly1305, compatible
+//! with libsodium's `crypto_box_curve25519xchacha20poly1305` functions.
+//!
+//! A [`CryptoBox`] holds the key shared between a secret key and another party's public
+//! key, `HChaCha20(X25519(secret, public), 0)`, and seals messages with libsodium's
+//! XChaCha20-Poly1305 secretbox: the first 32 bytes of keystream block 0 are the
+//! Poly1305 key, the message is encrypted with the keystream from byte 32 on, and the tag
+//! covers the ciphertext alone. A boxed message is `tag || ciphertext`, as
+//! `crypto_box_curve25519xchacha20poly1305_easy` produces, and [`CryptoBox::seal`]
+//! prepends the nonce to it, as PyNaCl's `Box.encrypt` does.
+//!
+//! [`seal_anonymous`] encrypts to a public key without a sender key, like
+//! `crypto_box_curve25519xchacha20poly1305_seal`: it boxes the message under a fresh
+//! ephemeral keypair and the nonce `BLAKE2b-192(ephemeral public key || recipient public
+//! key)`, and prepends the ephemeral public key.
+//!
+//! ```
+//! # #[cfg(feature = "getrandom")]
+//! # {
+//! use raycrypt::aeads::cryptobox::{open_anonymous, seal_anonymous, CryptoBox};
+//! use raycrypt::ecc::x25519::generate_keypair;
+//!
+//! let (alice_secret, alice_public) = generate_keypair();
+//! let (bob_secret, bob_public) = generate_keypair();
+//!
+//! let sealed = CryptoBox::seal(&alice_secret, &bob_public, b"message").unwrap();
+//! assert_eq!(CryptoBox::open(&bob_secret, &alice_public, &sealed).unwrap(), b"message");
+//!
+//! let sealed = seal_anonymous(&bob_public, b"message").unwrap();
+//! assert_eq!(open_anonymous(&bob_secret, &sealed).unwrap(), b"message");
+//! # }
+//! ```
+use crate::ciphers::chacha::{hchacha, ChaCha};
+use crate::ecc::x25519;
+pub use crate::errors::InvalidMac;
+use crate::hashes::blake2b::blake2b;
+use crate::macs::poly1305;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const PUBLIC_KEY_LENGTH: usize = 32;
+pub const SECRET_KEY_LENGTH: usize = 32;
+pub const NONCE_LENGTH: usize = 24;
+pub const TAG_LENGTH: usize = 16;
+/// The number of bytes [`seal_anonymous`] adds to a message: the ephemeral public key and
+/// the tag.
+pub const SEAL_OVERHEAD: usize = PUBLIC_KEY_LENGTH + TAG_LENGTH;
+
+/// The key shared between a secret key and a public key.
+#[derive(ZeroizeOnDrop)]
+pub struct CryptoBox {
+    key: [u8; 32],
+}
+
+impl CryptoBox {
+    /// Computes the key shared between `secret_key` and `public_key`, the same for both
+    /// sides of a conversation.
+    ///
+    /// Returns `None` if `public_key` is a point of small order, see
+    /// [`x25519::diffie_hellman`]. Panics if either key is not 32 bytes.
+    pub fn new(secret_key: &[u8], public_key: &[u8]) -> Option<CryptoBox> {
+        assert_eq!(
+            secret_key.len(),
+            SECRET_KEY_LENGTH,
+            "secret keys are 32 bytes"
+        );
+        assert_eq!(
+            public_key.len(),
+            PUBLIC_KEY_LENGTH,
+            "public keys are 32 bytes"
+        );
+
+        let mut shared = x25519::diffie_hellman(secret_key, public_key)?;
+        let key = hchacha(&shared, &[0u8; 16], None).unwrap();
+        shared.zeroize();
+
+        Some(CryptoBox { key })
+    }
+
+    /// Derives the XChaCha20 subcipher and its nonce, with the high counter word zero.
+    fn cipher(&self, nonce: &[u8]) -> (ChaCha, [u8; 12]) {
+        assert_eq!(nonce.len(), NONCE_LENGTH, "box nonces are 24 bytes");
+
+        let mut subkey = hchacha(&self.key, &nonce[..16], None).unwrap();
+        let cipher = ChaCha::new(&subkey, None);
+        subkey.zeroize();
+
+        let mut chacha_nonce = [0u8; 12];
+        chacha_nonce[4..].copy_from_slice(&nonce[16..]);
+
+        (cipher, chacha_nonce)
+    }
+
+    /// Encrypts `plaintext` under a 24-byte nonce and returns `tag || ciphertext`.
+    ///
+    /// Every message under the same pair of keys needs its own nonce. Panics if `nonce`
+    /// is not 24 bytes.
+    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8]) -> Vec<u8> {
+        let (cipher, chacha_nonce) = self.cipher(nonce);
+
+        let mut output = vec![0u8; TAG_LENGTH];
+        output.extend_from_slice(plaintext);
+        cipher.apply_keystream_range(&mut output[TAG_LENGTH..], &chacha_nonce, 32);
+
+        let mut poly1305_key = cipher.keystream(&chacha_nonce, 0);
+        let tag = poly1305::poly1305(&poly1305_key[..32], &output[TAG_LENGTH..]);
+        poly1305_key.zeroize();
+        output[..TAG_LENGTH].copy_from_slice(&tag);
+
+        output
+    }
+
+    /// Decrypts a message produced by [`CryptoBox::encrypt`], checking the tag before
+    /// decrypting. Panics if `nonce` is not 24 bytes.
+    pub fn decrypt(&self, boxed: &[u8], nonce: &[u8]) -> Result<Vec<u8>, InvalidMac> {
+        if boxed.len() < TAG_LENGTH {
+            return Err(InvalidMac);
+        }
+
+        let (cipher, chacha_nonce) = self.cipher(nonce);
+        let (tag, ciphertext) = boxed.split_at(TAG_LENGTH);
+
+        let mut poly1305_key = cipher.keystream(&chacha_nonce, 0);
+        let valid = poly1305::verify(&poly1305_key[..32], ciphertext, tag);
+        poly1305_key.zeroize();
+        if !valid {
+            return Err(InvalidMac);
+        }
+
+        let mut plaintext = ciphertext.to_vec();
+        cipher.apply_keystream_range(&mut plaintext, &chacha_nonce, 32);
+
+        Ok(plaintext)
+    }
+
+    /// Encrypts `plaintext` from the owner of `sender_secret_key` to the owner of
+    /// `recipient_public_key` under a random nonce, and returns
+    /// `nonce || tag || ciphertext`.
+    ///
+    /// Returns `None` if the public key is a point of small order.
+    #[cfg(feature = "getrandom")]
+    pub fn seal(
+        sender_secret_key: &[u8],
+        recipient_public_key: &[u8],
+        plaintext: &[u8],
+    ) -> Option<Vec<u8>> {
+        let shared = CryptoBox::new(sender_secret_key, recipient_public_key)?;
+
+        let mut nonce = [0u8; NONCE_LENGTH];
+        getrandom(&mut nonce).expect("failed to read random bytes");
+
+        let mut output = nonce.to_vec();
+        output.extend(shared.encrypt(plaintext, &nonce));
+
+        Some(output)
+    }
+
+    /// Decrypts a message produced by [`CryptoBox::seal`] with the recipient's secret key and
+    /// the sender's public key.
+    ///
+    /// Fails if the message is too short, was not sealed between these keys or was
+    /// tampered with, or if the public key is a point of small order.
+    pub fn open(
+        recipient_secret_key: &[u8],
+        sender_public_key: &[u8],
+        sealed: &[u8],
+    ) -> Result<Vec<u8>, InvalidMac> {
+        if sealed.len() < NONCE_LENGTH {
+            return Err(InvalidMac);
+        }
+
+        let shared = CryptoBox::new(recipient_secret_key, sender_public_key).ok_or(InvalidMac)?;
+        let (nonce, boxed) = sealed.split_at(NONCE_LENGTH);
+
+        shared.decrypt(boxed, nonce)
+    }
+}
+
+/// The nonce of a sealed box, derived from both public keys.
+fn anonymous_nonce(ephemeral_public_key: &[u8], recipient_public_key: &[u8]) -> Vec<u8> {
+    blake2b(
+        &[ephemeral_public_key, recipient_public_key].concat(),
+        NONCE_LENGTH,
+    )
+}
+
+/// Encrypts `plaintext` to the owner of `recipient_public_key` without revealing the
+/// sender, and returns `ephemeral public key || tag || ciphertext`.
+///
+/// The recipient can check that the message was not altered, but not who sent it.
+/// Returns `None` if the public key is a point of small order.
+#[cfg(feature = "getrandom")]
+pub fn seal_anonymous(recipient_public_key: &[u8], plaintext: &[u8]) -> Option<Vec<u8>> {
+    let (mut ephemeral_secret_key, ephemeral_public_key) = x25519::generate_keypair();
+    let shared = CryptoBox::new(&ephemeral_secret_key, recipient_public_key);
+    ephemeral_secret_key.zeroize();
+
+    let nonce = anonymous_nonce(&ephemeral_public_key, recipient_public_key);
+    let mut output = ephemeral_public_key.to_vec();
+    output.extend(shared?.encrypt(plaintext, &nonce));
+
+    Some(output)
+}
+
+/// Decrypts a message produced by [`seal_anonymous`] with the recipient's secret key.
+///
+/// Fails if the message is too short, was not sealed to this key or was tampered with.
+pub fn open_anonymous(recipient_secret_key: &[u8], sealed: &[u8]) -> Result<Vec<u8>, InvalidMac> {
+    if sealed.len() < SEAL_OVERHEAD {
+        return Err(InvalidMac);
+    }
+
+    let (ephemeral_public_key, boxed) = sealed.split_at(PUBLIC_KEY_LENGTH);
+    let recipient_public_key = x25519::public_key(recipient_secret_key);
+    let shared = CryptoBox::new(recipient_secret_key, ephemeral_public_key).ok_or(InvalidMac)?;
+    let nonce = anonymous_nonce(ephemeral_public_key, &recipient_public_key);
+
+    shared.decrypt(boxed, &nonce)
+}
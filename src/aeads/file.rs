@@ -0,0 +1,236 @@
+//! At-rest encryption of whole files with XChaCha20-Poly1305.
+//!
+//! [`seal_file`] writes a [`HEADER_LENGTH`]-byte header followed by the input split into
+//! chunks, each sealed as its own message with a 16-byte tag. The header is
+//! `magic || version || algorithm || salt || chunk size`, with the chunk size as a
+//! little-endian 32-bit integer, and is authenticated as the AAD of every chunk, so it
+//! cannot be altered without [`open_file`] noticing.
+//!
+//! The nonce of chunk `i` is `salt (16 bytes) || i (7 bytes, big-endian) || last (1 byte)`,
+//! where `last` is 1 for the final chunk only. The salt is random for every file and goes
+//! through HChaCha20, so every file is encrypted under its own subkey of `key`, and
+//! reordered, dropped or truncated chunks fail to authenticate.
+//!
+//! Errors are returned as [`io::Error`]s. A file that is malformed or does not
+//! authenticate fails with [`io::ErrorKind::InvalidData`] wrapping a [`FormatError`].
+use crate::aeads::versioned::XCHACHAPOLY1305;
+use crate::aeads::xchachapoly1305::{XChaChaPoly1305, KEY_LENGTH, TAG_LENGTH};
+use crate::errors::FormatError;
+use getrandom::getrandom;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// The bytes every sealed file starts with.
+pub const MAGIC: [u8; 4] = *b"RAYF";
+/// The version of the header layout.
+pub const VERSION: u8 = 1;
+pub const SALT_LENGTH: usize = 16;
+pub const HEADER_LENGTH: usize = MAGIC.len() + 2 + SALT_LENGTH + 4;
+/// The chunk size of [`seal_file`].
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+/// The largest chunk size [`open_file`] accepts, which bounds the memory a crafted header
+/// can make it allocate.
+pub const MAX_CHUNK_SIZE: usize = 1 << 24;
+
+fn invalid(error: FormatError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn chunk_nonce(salt: &[u8; SALT_LENGTH], counter: u64, last: bool) -> [u8; 24] {
+    let mut nonce = [0u8; 24];
+    nonce[..SALT_LENGTH].copy_from_slice(salt);
+    nonce[SALT_LENGTH..23].copy_from_slice(&counter.to_be_bytes()[1..]);
+    nonce[23] = last as u8;
+
+    nonce
+}
+
+/// Reads until `buffer` is full or the input ends, and returns the number of bytes read.
+fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(filled)
+}
+
+/// Encrypts the file at `path_in` under the 32-byte `key` and writes it to `path_out`,
+/// in chunks of [`DEFAULT_CHUNK_SIZE`] bytes.
+///
+/// Panics if `key` is not 32 bytes.
+pub fn seal_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    path_in: P,
+    path_out: Q,
+    key: &[u8],
+) -> io::Result<()> {
+    seal_file_with_chunk_size(path_in, path_out, key, DEFAULT_CHUNK_SIZE)
+}
+
+/// Like [`seal_file`], with chunks of `chunk_size` bytes.
+///
+/// Panics if `key` is not 32 bytes, or if `chunk_size` is zero or above
+/// [`MAX_CHUNK_SIZE`].
+pub fn seal_file_with_chunk_size<P: AsRef<Path>, Q: AsRef<Path>>(
+    path_in: P,
+    path_out: Q,
+    key: &[u8],
+    chunk_size: usize,
+) -> io::Result<()> {
+    assert_eq!(key.len(), KEY_LENGTH, "file keys are 32 bytes");
+    assert!(
+        chunk_size > 0 && chunk_size <= MAX_CHUNK_SIZE,
+        "chunk size must be between 1 and {} bytes",
+        MAX_CHUNK_SIZE
+    );
+
+    let mut salt = [0u8; SALT_LENGTH];
+    getrandom(&mut salt).expect("failed to read random bytes");
+
+    let mut header = Vec::with_capacity(HEADER_LENGTH);
+    header.extend_from_slice(&MAGIC);
+    header.extend_from_slice(&[VERSION, XCHACHAPOLY1305]);
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&(chunk_size as u32).to_le_bytes());
+
+    let mut reader = File::open(path_in)?;
+    let mut writer = BufWriter::new(File::create(path_out)?);
+    writer.write_all(&header)?;
+
+    let cipher = XChaChaPoly1305::new(key, None);
+    let mut current = vec![0u8; chunk_size];
+    let mut next = vec![0u8; chunk_size];
+    let mut filled = read_full(&mut reader, &mut current)?;
+
+    // a full chunk is only sealed once the next read shows whether it is the last one
+    let mut counter = 0u64;
+    loop {
+        let next_filled = if filled == chunk_size {
+            read_full(&mut reader, &mut next)?
+        } else {
+            0
+        };
+        let last = next_filled == 0;
+
+        let nonce = chunk_nonce(&salt, counter, last);
+        writer.write_all(&cipher.encrypt(&current[..filled], &nonce, &header))?;
+
+        if last {
+            break;
+        }
+        std::mem::swap(&mut current, &mut next);
+        filled = next_filled;
+        counter += 1;
+    }
+
+    writer.flush()
+}
+
+/// Decrypts a file sealed by [`seal_file`] under `key` and writes the plaintext to
+/// `path_out`.
+///
+/// Every chunk is authenticated before its plaintext is written. If any chunk fails, the
+/// partially written output is removed and [`io::ErrorKind::InvalidData`] is returned.
+///
+/// Panics if `key` is not 32 bytes.
+pub fn open_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    path_in: P,
+    path_out: Q,
+    key: &[u8],
+) -> io::Result<()> {
+    assert_eq!(key.len(), KEY_LENGTH, "file keys are 32 bytes");
+
+    let mut reader = File::open(path_in)?;
+    let (header, salt, chunk_size) = read_header(&mut reader)?;
+
+    let path_out = path_out.as_ref();
+    let mut writer = BufWriter::new(File::create(path_out)?);
+    let result = open_chunks(&mut reader, &mut writer, key, &header, &salt, chunk_size)
+        .and_then(|()| writer.flush());
+
+    if result.is_err() {
+        drop(writer);
+        let _ = fs::remove_file(path_out);
+    }
+
+    result
+}
+
+/// Reads and checks the header, and returns it along with its salt and chunk size.
+fn read_header<R: Read>(
+    reader: &mut R,
+) -> io::Result<([u8; HEADER_LENGTH], [u8; SALT_LENGTH], usize)> {
+    let mut header = [0u8; HEADER_LENGTH];
+    if read_full(reader, &mut header)? < HEADER_LENGTH {
+        return Err(invalid(FormatError::InvalidLength));
+    }
+
+    if header[..MAGIC.len()] != MAGIC {
+        return Err(invalid(FormatError::InvalidHeader));
+    }
+
+    let version = header[MAGIC.len()];
+    if version != VERSION {
+        return Err(invalid(FormatError::UnknownVersion(version)));
+    }
+
+    if header[MAGIC.len() + 1] != XCHACHAPOLY1305 {
+        return Err(invalid(FormatError::InvalidHeader));
+    }
+
+    let mut salt = [0u8; SALT_LENGTH];
+    salt.copy_from_slice(&header[MAGIC.len() + 2..HEADER_LENGTH - 4]);
+
+    let mut chunk_size = [0u8; 4];
+    chunk_size.copy_from_slice(&header[HEADER_LENGTH - 4..]);
+    let chunk_size = u32::from_le_bytes(chunk_size) as usize;
+    if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+        return Err(invalid(FormatError::InvalidHeader));
+    }
+
+    Ok((header, salt, chunk_size))
+}
+
+fn open_chunks<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    key: &[u8],
+    header: &[u8],
+    salt: &[u8; SALT_LENGTH],
+    chunk_size: usize,
+) -> io::Result<()> {
+    let cipher = XChaChaPoly1305::new(key, None);
+    let frame_size = chunk_size + TAG_LENGTH;
+    let mut current = vec![0u8; frame_size];
+    let mut next = vec![0u8; frame_size];
+    let mut filled = read_full(reader, &mut current)?;
+
+    let mut counter = 0u64;
+    loop {
+        let next_filled = if filled == frame_size {
+            read_full(reader, &mut next)?
+        } else {
+            0
+        };
+        let last = next_filled == 0;
+
+        let nonce = chunk_nonce(salt, counter, last);
+        let plaintext = cipher
+            .decrypt(&current[..filled], &nonce, header)
+            .map_err(|error| invalid(error.into()))?;
+        writer.write_all(&plaintext)?;
+
+        if last {
+            return Ok(());
+        }
+        std::mem::swap(&mut current, &mut next);
+        filled = next_filled;
+        counter += 1;
+    }
+}
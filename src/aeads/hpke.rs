@@ -0,0 +1,411 @@
+//! Hybrid public key encryption (RFC 9180) in base mode, with the ciphersuite
+//! DHKEM(X25519, HKDF-SHA256), HKDF-SHA256 and ChaCha20-Poly1305.
+//!
+//! The sender encapsulates a fresh shared secret to the recipient's X25519 public key, and
+//! both sides run it through the HPKE key schedule, bound to an `info` string, into a
+//! ChaCha20-Poly1305 key, a base nonce and an exporter secret. The encapsulated key,
+//! `enc`, is the sender's ephemeral public key and has to reach the recipient along with
+//! the ciphertexts.
+//!
+//! A [`SenderContext`] seals any number of messages in order, each under the base nonce
+//! XORed with its sequence number, and a [`ReceiverContext`] opens them in the same
+//! order. Both can derive further secrets with [`SenderContext::export`], the same on
+//! each side. [`seal`] and [`open`] do a setup and a single message in one call.
+//!
+//! This interoperates with other HPKE implementations on the same ciphersuite, e.g. for
+//! OHTTP, MLS or ECH, as the suite identifiers are part of every derivation.
+//!
+//! ```
+//! # #[cfg(feature = "getrandom")]
+//! # {
+//! use raycrypt::aeads::hpke::{generate_keypair, setup_base_receiver, setup_base_sender};
+//!
+//! let (secret_key, public_key) = generate_keypair();
+//!
+//! let (enc, mut sender) = setup_base_sender(&public_key, b"info").unwrap();
+//! let first = sender.seal(b"first", b"aad").unwrap();
+//! let second = sender.seal(b"second", b"aad").unwrap();
+//!
+//! let mut receiver = setup_base_receiver(&enc, &secret_key, b"info").unwrap();
+//! assert_eq!(receiver.open(&first, b"aad").unwrap(), b"first");
+//! assert_eq!(receiver.open(&second, b"aad").unwrap(), b"second");
+//!
+//! assert_eq!(
+//!     sender.export(b"context", 32).unwrap(),
+//!     receiver.export(b"context", 32).unwrap()
+//! );
+//! # }
+//! ```
+use crate::aeads::chachapoly1305::ChaChaPoly1305;
+use crate::ecc::x25519;
+pub use crate::errors::{HpkeError, InvalidLength, InvalidMac, MessageLimitReached, TagMismatch};
+use crate::kdfs::hkdf::{self, Sha256};
+use alloc::vec::Vec;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const PUBLIC_KEY_LENGTH: usize = 32;
+pub const SECRET_KEY_LENGTH: usize = 32;
+/// The length of the encapsulated key, `enc`.
+pub const ENCAPSULATED_KEY_LENGTH: usize = 32;
+pub const TAG_LENGTH: usize = 16;
+
+/// DHKEM(X25519, HKDF-SHA256).
+pub const KEM_ID: u16 = 0x0020;
+/// HKDF-SHA256.
+pub const KDF_ID: u16 = 0x0001;
+/// ChaCha20-Poly1305.
+pub const AEAD_ID: u16 = 0x0003;
+
+const MODE_BASE: u8 = 0x00;
+
+/// The suite identifier of the KEM, `"KEM" || I2OSP(kem_id, 2)`.
+const KEM_SUITE_ID: [u8; 5] = [b'K', b'E', b'M', 0x00, 0x20];
+/// The suite identifier of the key schedule, `"HPKE" || kem_id || kdf_id || aead_id`.
+const HPKE_SUITE_ID: [u8; 10] = [b'H', b'P', b'K', b'E', 0x00, 0x20, 0x00, 0x01, 0x00, 0x03];
+
+fn labeled_extract(suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[u8]) -> Vec<u8> {
+    hkdf::hmac::<Sha256>(salt, &[b"HPKE-v1", suite_id, label, ikm])
+}
+
+fn labeled_expand(
+    suite_id: &[u8],
+    prk: &[u8],
+    label: &[u8],
+    info: &[u8],
+    length: usize,
+) -> Result<Vec<u8>, InvalidLength> {
+    let length_bytes = u16::try_from(length)
+        .map_err(|_| InvalidLength)?
+        .to_be_bytes();
+    let labeled_info = [&length_bytes[..], b"HPKE-v1", suite_id, label, info].concat();
+
+    hkdf::expand::<Sha256>(prk, &labeled_info, length)
+}
+
+/// Derives a `(secret, public)` keypair from at least 32 bytes of input keying material,
+/// as `DeriveKeyPair` of the KEM does.
+///
+/// The same `ikm` always gives the same keypair. Panics if `ikm` is shorter than 32 bytes.
+pub fn derive_keypair(ikm: &[u8]) -> ([u8; 32], [u8; 32]) {
+    assert!(
+        ikm.len() >= 32,
+        "the input keying material is at least 32 bytes"
+    );
+
+    let mut dkp_prk = labeled_extract(&KEM_SUITE_ID, b"", b"dkp_prk", ikm);
+    let mut secret = labeled_expand(&KEM_SUITE_ID, &dkp_prk, b"sk", b"", 32).unwrap();
+    dkp_prk.zeroize();
+
+    let keypair = x25519::keypair_from_seed(secret[..].try_into().unwrap());
+    secret.zeroize();
+
+    keypair
+}
+
+/// Returns a random `(secret, public)` keypair.
+///
+/// Panics if the operating system's random number generator fails.
+#[cfg(feature = "getrandom")]
+pub fn generate_keypair() -> ([u8; 32], [u8; 32]) {
+    x25519::generate_keypair()
+}
+
+/// Derives the KEM shared secret from a Diffie-Hellman output and both public keys.
+fn extract_and_expand(mut dh: [u8; 32], enc: &[u8], public_key: &[u8]) -> [u8; 32] {
+    let mut eae_prk = labeled_extract(&KEM_SUITE_ID, b"", b"eae_prk", &dh);
+    dh.zeroize();
+
+    let kem_context = [enc, public_key].concat();
+    let mut shared_secret =
+        labeled_expand(&KEM_SUITE_ID, &eae_prk, b"shared_secret", &kem_context, 32).unwrap();
+    eae_prk.zeroize();
+
+    let output = shared_secret[..].try_into().unwrap();
+    shared_secret.zeroize();
+
+    output
+}
+
+/// The keys of one direction of an HPKE context.
+#[derive(Zeroize, ZeroizeOnDrop)]
+struct Context {
+    key: [u8; 32],
+    base_nonce: [u8; 12],
+    exporter_secret: [u8; 32],
+    sequence: u64,
+    exhausted: bool,
+}
+
+impl Context {
+    /// Runs the key schedule of base mode over `shared_secret` and `info`.
+    fn new(mut shared_secret: [u8; 32], info: &[u8]) -> Context {
+        let psk_id_hash = labeled_extract(&HPKE_SUITE_ID, b"", b"psk_id_hash", b"");
+        let info_hash = labeled_extract(&HPKE_SUITE_ID, b"", b"info_hash", info);
+        let key_schedule_context = [&[MODE_BASE], &psk_id_hash[..], &info_hash].concat();
+
+        let mut secret = labeled_extract(&HPKE_SUITE_ID, &shared_secret, b"secret", b"");
+        shared_secret.zeroize();
+
+        let expand = |label: &[u8], output: &mut [u8]| {
+            let mut okm = labeled_expand(
+                &HPKE_SUITE_ID,
+                &secret,
+                label,
+                &key_schedule_context,
+                output.len(),
+            )
+            .unwrap();
+            output.copy_from_slice(&okm);
+            okm.zeroize();
+        };
+
+        let mut context = Context {
+            key: [0u8; 32],
+            base_nonce: [0u8; 12],
+            exporter_secret: [0u8; 32],
+            sequence: 0,
+            exhausted: false,
+        };
+        expand(b"key", &mut context.key);
+        expand(b"base_nonce", &mut context.base_nonce);
+        expand(b"exp", &mut context.exporter_secret);
+        secret.zeroize();
+
+        context
+    }
+
+    /// Returns the nonce of the next message, or `None` once the sequence number has run
+    /// out.
+    fn next_nonce(&self) -> Option<[u8; 12]> {
+        if self.exhausted {
+            return None;
+        }
+
+        let mut nonce = self.base_nonce;
+        for (byte, sequence) in nonce[4..].iter_mut().zip(self.sequence.to_be_bytes()) {
+            *byte ^= sequence;
+        }
+
+        Some(nonce)
+    }
+
+    fn advance(&mut self) {
+        match self.sequence.checked_add(1) {
+            Some(sequence) => self.sequence = sequence,
+            None => self.exhausted = true,
+        }
+    }
+
+    fn export(&self, exporter_context: &[u8], length: usize) -> Result<Vec<u8>, InvalidLength> {
+        labeled_expand(
+            &HPKE_SUITE_ID,
+            &self.exporter_secret,
+            b"sec",
+            exporter_context,
+            length,
+        )
+    }
+}
+
+/// The sender's side of an HPKE context, see [`setup_base_sender`].
+pub struct SenderContext {
+    context: Context,
+}
+
+impl SenderContext {
+    /// Encrypts `plaintext` as the next message and returns `ciphertext || tag`.
+    ///
+    /// Fails once the 64-bit sequence number has run out.
+    pub fn seal(&mut self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, MessageLimitReached> {
+        let nonce = self.context.next_nonce().ok_or(MessageLimitReached)?;
+        let ciphertext =
+            ChaChaPoly1305::new(&self.context.key, None).encrypt(plaintext, &nonce, aad);
+        self.context.advance();
+
+        Ok(ciphertext)
+    }
+
+    /// Derives `length` bytes of secret bound to `exporter_context`, the same as the
+    /// receiver's [`ReceiverContext::export`].
+    ///
+    /// Fails if `length` is above `255 * 32`.
+    pub fn export(&self, exporter_context: &[u8], length: usize) -> Result<Vec<u8>, InvalidLength> {
+        self.context.export(exporter_context, length)
+    }
+
+    /// Returns the sequence number of the next message.
+    pub fn sequence(&self) -> u64 {
+        self.context.sequence
+    }
+}
+
+/// The recipient's side of an HPKE context, see [`setup_base_receiver`].
+pub struct ReceiverContext {
+    context: Context,
+}
+
+impl ReceiverContext {
+    /// Decrypts the next message, sealed by [`SenderContext::seal`].
+    ///
+    /// Messages have to be opened in the order they were sealed. If a message does not
+    /// authenticate, the sequence number does not advance, so the next attempt expects the
+    /// same message again. Fails with [`HpkeError::MessageLimitReached`] once the sequence
+    /// numbers are used up.
+    pub fn open(&mut self, ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, HpkeError> {
+        let nonce = self.context.next_nonce().ok_or(MessageLimitReached)?;
+        let plaintext =
+            ChaChaPoly1305::new(&self.context.key, None).decrypt(ciphertext, &nonce, aad)?;
+        self.context.advance();
+
+        Ok(plaintext)
+    }
+
+    /// Derives `length` bytes of secret bound to `exporter_context`, see
+    /// [`SenderContext::export`].
+    pub fn export(&self, exporter_context: &[u8], length: usize) -> Result<Vec<u8>, InvalidLength> {
+        self.context.export(exporter_context, length)
+    }
+
+    /// Returns the sequence number of the next message.
+    pub fn sequence(&self) -> u64 {
+        self.context.sequence
+    }
+}
+
+/// Sets up a context for sending to the owner of `public_key` and returns the
+/// encapsulated key along with it.
+///
+/// Returns `None` if `public_key` is a point of small order. Panics if it is not 32
+/// bytes.
+#[cfg(feature = "getrandom")]
+pub fn setup_base_sender(public_key: &[u8], info: &[u8]) -> Option<([u8; 32], SenderContext)> {
+    let mut ikm = [0u8; 32];
+    getrandom::getrandom(&mut ikm).expect("failed to read random bytes");
+
+    let result = setup_base_sender_with_ikm(public_key, info, &ikm);
+    ikm.zeroize();
+
+    result
+}
+
+/// Like [`setup_base_sender`], but derives the ephemeral keypair from `ikm` with
+/// [`derive_keypair`] instead of generating it.
+///
+/// This is meant for test vectors, which fix the ephemeral key. Reusing `ikm` for two
+/// setups to the same recipient gives both the same keys and nonces, which is as bad as
+/// reusing a nonce. Panics if `ikm` is shorter than 32 bytes.
+pub fn setup_base_sender_with_ikm(
+    public_key: &[u8],
+    info: &[u8],
+    ikm: &[u8],
+) -> Option<([u8; 32], SenderContext)> {
+    assert_eq!(
+        public_key.len(),
+        PUBLIC_KEY_LENGTH,
+        "public keys are 32 bytes"
+    );
+
+    let (mut ephemeral_secret_key, enc) = derive_keypair(ikm);
+    let dh = x25519::diffie_hellman(&ephemeral_secret_key, public_key);
+    ephemeral_secret_key.zeroize();
+
+    let shared_secret = extract_and_expand(dh?, &enc, public_key);
+    let context = Context::new(shared_secret, info);
+
+    Some((enc, SenderContext { context }))
+}
+
+/// Sets up the context for receiving from the sender that produced `enc`, with the
+/// recipient's secret key.
+///
+/// Returns `None` if `enc` is a point of small order. Panics if `enc` or `secret_key` is
+/// not 32 bytes.
+pub fn setup_base_receiver(enc: &[u8], secret_key: &[u8], info: &[u8]) -> Option<ReceiverContext> {
+    assert_eq!(
+        enc.len(),
+        ENCAPSULATED_KEY_LENGTH,
+        "encapsulated keys are 32 bytes"
+    );
+    assert_eq!(
+        secret_key.len(),
+        SECRET_KEY_LENGTH,
+        "secret keys are 32 bytes"
+    );
+
+    let dh = x25519::diffie_hellman(secret_key, enc)?;
+    let shared_secret = extract_and_expand(dh, enc, &x25519::public_key(secret_key));
+    let context = Context::new(shared_secret, info);
+
+    Some(ReceiverContext { context })
+}
+
+/// Encrypts a single message to the owner of `public_key` and returns the encapsulated
+/// key and `ciphertext || tag`.
+///
+/// Returns `None` if `public_key` is a point of small order.
+#[cfg(feature = "getrandom")]
+pub fn seal(
+    public_key: &[u8],
+    info: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Option<([u8; 32], Vec<u8>)> {
+    let (enc, mut context) = setup_base_sender(public_key, info)?;
+    let ciphertext = context.seal(plaintext, aad).unwrap();
+
+    Some((enc, ciphertext))
+}
+
+/// Decrypts a message produced by [`seal`] with the recipient's secret key.
+///
+/// Fails if the message does not authenticate, or if `enc` is a point of small order.
+pub fn open(
+    enc: &[u8],
+    secret_key: &[u8],
+    info: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, InvalidMac> {
+    let mut context = setup_base_receiver(enc, secret_key, info).ok_or(InvalidMac)?;
+
+    context.open(ciphertext, aad).map_err(|_| InvalidMac)
+}
+
+/// Derives a secret for the owner of `public_key` without sending a message, and returns
+/// the encapsulated key and the secret, see [`receive_export`].
+///
+/// Returns `None` if `public_key` is a point of small order. Panics if `length` is above
+/// `255 * 32`.
+#[cfg(feature = "getrandom")]
+pub fn send_export(
+    public_key: &[u8],
+    info: &[u8],
+    exporter_context: &[u8],
+    length: usize,
+) -> Option<([u8; 32], Vec<u8>)> {
+    let (enc, context) = setup_base_sender(public_key, info)?;
+    let secret = context
+        .export(exporter_context, length)
+        .expect("exported secrets are at most 8160 bytes");
+
+    Some((enc, secret))
+}
+
+/// Derives the secret of [`send_export`] with the recipient's secret key.
+///
+/// Returns `None` if `enc` is a point of small order. Panics if `length` is above
+/// `255 * 32`.
+pub fn receive_export(
+    enc: &[u8],
+    secret_key: &[u8],
+    info: &[u8],
+    exporter_context: &[u8],
+    length: usize,
+) -> Option<Vec<u8>> {
+    let context = setup_base_receiver(enc, secret_key, info)?;
+    let secret = context
+        .export(exporter_context, length)
+        .expect("exported secrets are at most 8160 bytes");
+
+    Some(secret)
+}
@@ -0,0 +1,84 @@
+//! Key wrapping: encrypting data-encryption keys under a key-encryption key with
+//! XChaCha20-Poly1305.
+//!
+//! [`wrap_key`] encrypts a key under the 32-byte key-encryption key and a random nonce,
+//! and lays it out as
+//!
+//! `version || algorithm || nonce || ciphertext || tag`
+//!
+//! with the first two bytes authenticated as AAD, so [`unwrap_key`] needs nothing but the
+//! key-encryption key. Keys of any length can be wrapped, and the wrapped key is
+//! [`OVERHEAD`] bytes longer.
+//!
+//! ```
+//! # #[cfg(feature = "getrandom")]
+//! # {
+//! use raycrypt::aeads::keywrap::{unwrap_key, wrap_key};
+//! use raycrypt::aeads::xchachapoly1305::generate_key;
+//!
+//! let kek = generate_key();
+//! let key = generate_key();
+//!
+//! let wrapped = wrap_key(&kek, &key);
+//! assert_eq!(unwrap_key(&kek, &wrapped).unwrap(), key);
+//! # }
+//! ```
+use crate::aeads::versioned::XCHACHAPOLY1305;
+use crate::aeads::xchachapoly1305::{XChaChaPoly1305, KEY_LENGTH, NONCE_LENGTH, TAG_LENGTH};
+use crate::errors::FormatError;
+use alloc::vec::Vec;
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
+
+/// The version of the wrapped key layout.
+pub const VERSION: u8 = 1;
+pub const HEADER_LENGTH: usize = 2 + NONCE_LENGTH;
+/// The number of bytes a wrapped key is longer than the key.
+pub const OVERHEAD: usize = HEADER_LENGTH + TAG_LENGTH;
+
+/// Encrypts `key` under the key-encryption key `kek` and a random nonce.
+///
+/// Panics if `kek` is not 32 bytes.
+#[cfg(feature = "getrandom")]
+pub fn wrap_key(kek: &[u8], key: &[u8]) -> Vec<u8> {
+    assert_eq!(kek.len(), KEY_LENGTH, "key-encryption keys are 32 bytes");
+
+    let mut nonce = [0u8; NONCE_LENGTH];
+    getrandom(&mut nonce).expect("failed to read random bytes");
+
+    let header = [VERSION, XCHACHAPOLY1305];
+    let ciphertext = XChaChaPoly1305::new(kek, None).encrypt(key, &nonce, &header);
+
+    let mut wrapped = Vec::with_capacity(key.len() + OVERHEAD);
+    wrapped.extend_from_slice(&header);
+    wrapped.extend_from_slice(&nonce);
+    wrapped.extend_from_slice(&ciphertext);
+
+    wrapped
+}
+
+/// Decrypts a key wrapped by [`wrap_key`] under `kek`.
+///
+/// Fails with [`FormatError::InvalidMac`] if `kek` is the wrong key or the wrapped key was
+/// tampered with, and with another [`FormatError`] if it is malformed. Panics if `kek` is
+/// not 32 bytes.
+pub fn unwrap_key(kek: &[u8], wrapped: &[u8]) -> Result<Vec<u8>, FormatError> {
+    assert_eq!(kek.len(), KEY_LENGTH, "key-encryption keys are 32 bytes");
+
+    if wrapped.len() < OVERHEAD {
+        return Err(FormatError::InvalidLength);
+    }
+
+    if wrapped[0] != VERSION {
+        return Err(FormatError::UnknownVersion(wrapped[0]));
+    }
+
+    if wrapped[1] != XCHACHAPOLY1305 {
+        return Err(FormatError::InvalidHeader);
+    }
+
+    let (header, nonce) = wrapped[..HEADER_LENGTH].split_at(2);
+    let ciphertext = &wrapped[HEADER_LENGTH..];
+
+    Ok(XChaChaPoly1305::new(kek, None).decrypt(ciphertext, nonce, header)?)
+}
@@ -0,0 +1,132 @@
+//! Nonce generation: random nonces from [`NonceGenerator`], and counter nonces from a
+//! [`NonceSequence`] for sessions that number their messages.
+use crate::ciphers::chacha::ChaCha20Rng;
+pub use crate::errors::MessageLimitReached;
+use alloc::boxed::Box;
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
+
+enum Source {
+    #[cfg(feature = "getrandom")]
+    Os,
+    Rng(Box<ChaCha20Rng>),
+}
+
+/// Generates random nonces, from the operating system by default or from a seeded
+/// [`ChaCha20Rng`] for reproducible tests.
+pub struct NonceGenerator {
+    source: Source,
+}
+
+impl NonceGenerator {
+    #[cfg(feature = "getrandom")]
+    pub fn new() -> NonceGenerator {
+        NonceGenerator { source: Source::Os }
+    }
+
+    /// Draws nonces from `rng` instead of the operating system. With a fixed seed the
+    /// nonces are predictable, so only use this in tests.
+    pub fn with_rng(rng: ChaCha20Rng) -> NonceGenerator {
+        NonceGenerator {
+            source: Source::Rng(Box::new(rng)),
+        }
+    }
+
+    /// Returns a fresh `N`-byte nonce.
+    ///
+    /// Panics if the operating system's random number generator fails.
+    pub fn generate<const N: usize>(&mut self) -> [u8; N] {
+        let mut nonce = [0u8; N];
+
+        match &mut self.source {
+            #[cfg(feature = "getrandom")]
+            Source::Os => getrandom(&mut nonce).expect("failed to read random bytes"),
+            Source::Rng(rng) => rng.fill_bytes(&mut nonce),
+        }
+
+        nonce
+    }
+}
+
+#[cfg(feature = "getrandom")]
+impl Default for NonceGenerator {
+    fn default() -> NonceGenerator {
+        NonceGenerator::new()
+    }
+}
+
+/// Counter nonces: a fixed prefix followed by the message number as a big-endian 64-bit
+/// integer in the last 8 bytes.
+///
+/// Every nonce of a sequence is distinct, so a key can encrypt one message per nonce
+/// without the birthday bound of random nonces. For XChaCha-Poly1305 only the last 8
+/// bytes change, which keeps the HChaCha subkey the same for the whole sequence, as in
+/// [`sequence_nonce`](crate::aeads::xchachapoly1305::sequence_nonce). Two sequences must
+/// never share a key and a prefix.
+///
+/// ```
+/// use raycrypt::aeads::nonce::NonceSequence;
+///
+/// let mut nonces = NonceSequence::<12>::with_limit(&[0xaa; 4], 2);
+/// assert_eq!(nonces.next_nonce().unwrap()[4..], [0, 0, 0, 0, 0, 0, 0, 0]);
+/// assert_eq!(nonces.next_nonce().unwrap()[4..], [0, 0, 0, 0, 0, 0, 0, 1]);
+/// assert!(nonces.next_nonce().is_err());
+/// ```
+pub struct NonceSequence<const N: usize> {
+    nonce: [u8; N],
+    issued: u64,
+    limit: u64,
+}
+
+impl<const N: usize> NonceSequence<N> {
+    /// Starts a sequence at message 0. `prefix` is the first `N - 8` bytes of every nonce.
+    ///
+    /// Panics if `prefix` is not `N - 8` bytes.
+    pub fn new(prefix: &[u8]) -> NonceSequence<N> {
+        NonceSequence::with_limit(prefix, u64::MAX)
+    }
+
+    /// Like [`NonceSequence::new`], but issues at most `limit` nonces.
+    pub fn with_limit(prefix: &[u8], limit: u64) -> NonceSequence<N> {
+        assert_eq!(
+            N.checked_sub(8),
+            Some(prefix.len()),
+            "the prefix is 8 bytes shorter than the nonce"
+        );
+
+        let mut nonce = [0u8; N];
+        nonce[..prefix.len()].copy_from_slice(prefix);
+
+        NonceSequence {
+            nonce,
+            issued: 0,
+            limit,
+        }
+    }
+
+    /// Returns the nonce of the next message.
+    ///
+    /// Fails once the sequence has issued as many nonces as its limit allows, after which
+    /// the key has to be replaced.
+    pub fn next_nonce(&mut self) -> Result<[u8; N], MessageLimitReached> {
+        if self.issued >= self.limit {
+            return Err(MessageLimitReached);
+        }
+
+        let mut nonce = self.nonce;
+        nonce[N - 8..].copy_from_slice(&self.issued.to_be_bytes());
+        self.issued += 1;
+
+        Ok(nonce)
+    }
+
+    /// Returns the number of nonces issued so far.
+    pub fn issued(&self) -> u64 {
+        self.issued
+    }
+
+    /// Returns the number of nonces that can still be issued.
+    pub fn remaining(&self) -> u64 {
+        self.limit - self.issued
+    }
+}
@@ -0,0 +1,117 @@
+//! Password-based encryption with Argon2id and XChaCha20-Poly1305.
+//!
+//! [`seal_with_password`] derives a key from the password and a random salt with
+//! Argon2id, then encrypts under a random nonce. The output is a self-describing envelope
+//!
+//! `version || algorithm || memory cost || time cost || parallelism || salt || nonce || ciphertext || tag`
+//!
+//! with the costs as little-endian 32-bit integers, so [`open_with_password`] needs
+//! nothing but the password. Everything before the ciphertext is authenticated as AAD.
+//!
+//! ```
+//! use raycrypt::aeads::password::{open_with_password, seal_with_password_and_params};
+//! use raycrypt::kdfs::argon2::Params;
+//!
+//! let params = Params::new(64, 1, 1).unwrap();
+//! let envelope = seal_with_password_and_params(b"hunter2", b"message", params);
+//! assert_eq!(open_with_password(b"hunter2", &envelope).unwrap(), b"message");
+//! assert!(open_with_password(b"hunter3", &envelope).is_err());
+//! ```
+use crate::aeads::versioned::XCHACHAPOLY1305;
+use crate::aeads::xchachapoly1305::{XChaChaPoly1305, KEY_LENGTH, NONCE_LENGTH, TAG_LENGTH};
+use crate::errors::FormatError;
+use crate::kdfs::argon2::{Argon2, Params, Variant, SALT_LENGTH};
+use alloc::vec::Vec;
+use getrandom::getrandom;
+use zeroize::Zeroize;
+
+/// The version of the envelope layout.
+pub const VERSION: u8 = 1;
+pub const HEADER_LENGTH: usize = 2 + 12 + SALT_LENGTH + NONCE_LENGTH;
+/// The number of bytes an envelope is longer than its plaintext.
+pub const OVERHEAD: usize = HEADER_LENGTH + TAG_LENGTH;
+/// The largest memory cost in KiB [`open_with_password`] accepts, 1 GiB, which bounds the
+/// memory a crafted envelope can make it allocate.
+pub const MAX_MEMORY_COST: u32 = 1 << 20;
+/// The largest time cost [`open_with_password`] accepts.
+pub const MAX_TIME_COST: u32 = 64;
+
+fn derive_key(password: &[u8], salt: &[u8], params: Params) -> XChaChaPoly1305 {
+    let mut key = Argon2::new(Variant::Argon2id, params)
+        .hash_raw(password, salt, KEY_LENGTH)
+        .unwrap();
+    let cipher = XChaChaPoly1305::new(&key, None);
+    key.zeroize();
+
+    cipher
+}
+
+/// Encrypts `plaintext` under a key derived from `password` with Argon2id and the
+/// default [`Params`].
+pub fn seal_with_password(password: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    seal_with_password_and_params(password, plaintext, Params::default())
+}
+
+/// Like [`seal_with_password`], with the Argon2id costs `params`. They are stored in the
+/// envelope, and [`open_with_password`] rejects envelopes whose costs are above
+/// [`MAX_MEMORY_COST`] or [`MAX_TIME_COST`].
+pub fn seal_with_password_and_params(password: &[u8], plaintext: &[u8], params: Params) -> Vec<u8> {
+    let mut envelope = Vec::with_capacity(plaintext.len() + OVERHEAD);
+    envelope.extend_from_slice(&[VERSION, XCHACHAPOLY1305]);
+    for cost in [
+        params.memory_cost(),
+        params.time_cost(),
+        params.parallelism(),
+    ] {
+        envelope.extend_from_slice(&cost.to_le_bytes());
+    }
+
+    let mut random = [0u8; SALT_LENGTH + NONCE_LENGTH];
+    getrandom(&mut random).expect("failed to read random bytes");
+    envelope.extend_from_slice(&random);
+
+    let (salt, nonce) = random.split_at(SALT_LENGTH);
+    let ciphertext = derive_key(password, salt, params).encrypt(plaintext, nonce, &envelope);
+    envelope.extend_from_slice(&ciphertext);
+
+    envelope
+}
+
+/// Decrypts an envelope produced by [`seal_with_password`] with `password`.
+///
+/// Fails with [`FormatError::InvalidMac`] if the password is wrong or the envelope was
+/// tampered with, and with another [`FormatError`] if it is malformed. The costs are
+/// checked before any key is derived.
+pub fn open_with_password(password: &[u8], envelope: &[u8]) -> Result<Vec<u8>, FormatError> {
+    if envelope.len() < OVERHEAD {
+        return Err(FormatError::InvalidLength);
+    }
+
+    if envelope[0] != VERSION {
+        return Err(FormatError::UnknownVersion(envelope[0]));
+    }
+
+    if envelope[1] != XCHACHAPOLY1305 {
+        return Err(FormatError::InvalidHeader);
+    }
+
+    let mut costs = [0u32; 3];
+    for (i, cost) in costs.iter_mut().enumerate() {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&envelope[2 + 4 * i..6 + 4 * i]);
+        *cost = u32::from_le_bytes(bytes);
+    }
+
+    let [memory_cost, time_cost, parallelism] = costs;
+    if memory_cost > MAX_MEMORY_COST || time_cost > MAX_TIME_COST {
+        return Err(FormatError::InvalidHeader);
+    }
+    let params =
+        Params::new(memory_cost, time_cost, parallelism).map_err(|_| FormatError::InvalidHeader)?;
+
+    let (header, ciphertext) = envelope.split_at(HEADER_LENGTH);
+    let salt = &header[14..14 + SALT_LENGTH];
+    let nonce = &header[14 + SALT_LENGTH..];
+
+    Ok(derive_key(password, salt, params).decrypt(ciphertext, nonce, header)?)
+}
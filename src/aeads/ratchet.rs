@@ -0,0 +1,342 @@
+//! The symmetric-key ratchet of the Signal protocol, for forward-secret messaging.
+//!
+//! A [`Chain`] turns a 32-byte chain key into one message key per message, and replaces
+//! the chain key as it goes, with `HMAC-SHA-256(chain key, 0x01)` as the message key and
+//! `HMAC-SHA-256(chain key, 0x02)` as the next chain key. Once a message key is used and
+//! dropped it cannot be derived again, so a later compromise of the chain does not
+//! expose earlier messages.
+//!
+//! Each message key encrypts one message with XChaCha20-Poly1305, under a key and nonce
+//! expanded from it with HKDF-SHA-256. The message is laid out as
+//! `header || ciphertext || tag`, with an 8-byte [`Header`] that is authenticated along
+//! with the AAD. A [`Receiver`] keeps the keys of messages that arrive out of order, up
+//! to a limit, and only moves its chain forward once a message authenticates.
+//!
+//! The chain keys of both directions come from elsewhere, such as an X25519 exchange
+//! through HKDF. Restarting the chains with fresh Diffie-Hellman outputs, and carrying
+//! the public keys in the AAD, makes this the double ratchet.
+//!
+//! ```
+//! use raycrypt::aeads::ratchet::{Receiver, Sender};
+//!
+//! let chain_key = [0x42; 32];
+//! let mut alice = Sender::new(&chain_key);
+//! let mut bob = Receiver::new(&chain_key);
+//!
+//! let first = alice.encrypt(b"first", b"ad").unwrap();
+//! let second = alice.encrypt(b"second", b"ad").unwrap();
+//!
+//! // out of order, and each message only once
+//! assert_eq!(bob.decrypt(&second, b"ad").unwrap(), b"second");
+//! assert_eq!(bob.decrypt(&first, b"ad").unwrap(), b"first");
+//! assert!(bob.decrypt(&first, b"ad").is_err());
+//! ```
+use crate::aeads::xchachapoly1305::XChaChaPoly1305;
+pub use crate::errors::{MessageLimitReached, RatchetError, TagMismatch};
+use crate::kdfs::hkdf::{self, Sha256};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const KEY_LENGTH: usize = 32;
+pub const HEADER_LENGTH: usize = 8;
+pub const TAG_LENGTH: usize = 16;
+
+/// The skipped message keys a [`Receiver::new`] keeps, as in the Signal specification's
+/// `MAX_SKIP`.
+pub const DEFAULT_MAX_SKIP: u32 = 1000;
+
+const MESSAGE_KEY_INFO: &[u8] = b"raycrypt ratchet message key";
+
+/// The header of a message, sent in the clear and authenticated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Header {
+    /// The number of messages sent on the sender's previous chain, so that in a double
+    /// ratchet the receiver can keep the keys of the ones it has not seen yet.
+    pub previous_chain_length: u32,
+    /// The position of the message in its chain.
+    pub index: u32,
+}
+
+impl Header {
+    pub fn to_bytes(self) -> [u8; HEADER_LENGTH] {
+        let mut bytes = [0u8; HEADER_LENGTH];
+        bytes[..4].copy_from_slice(&self.previous_chain_length.to_be_bytes());
+        bytes[4..].copy_from_slice(&self.index.to_be_bytes());
+
+        bytes
+    }
+
+    /// Reads the header at the start of `message`, or returns `None` if the message is
+    /// too short to have one.
+    pub fn parse(message: &[u8]) -> Option<Header> {
+        let bytes = message.get(..HEADER_LENGTH)?;
+
+        Some(Header {
+            previous_chain_length: u32::from_be_bytes(bytes[..4].try_into().unwrap()),
+            index: u32::from_be_bytes(bytes[4..].try_into().unwrap()),
+        })
+    }
+}
+
+/// A KDF chain, advanced once per message.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Chain {
+    key: [u8; 32],
+    index: u32,
+    exhausted: bool,
+}
+
+impl Chain {
+    /// Panics if `chain_key` is not 32 bytes.
+    pub fn new(chain_key: &[u8]) -> Chain {
+        Chain {
+            key: chain_key
+                .try_into()
+                .expect("ratchet chain keys are 32 bytes"),
+            index: 0,
+            exhausted: false,
+        }
+    }
+
+    /// Returns the index of the next message key.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the next message key and replaces the chain key.
+    ///
+    /// Fails once all 2^32 message keys of the chain have been used.
+    pub fn next_key(&mut self) -> Result<MessageKey, MessageLimitReached> {
+        if self.exhausted {
+            return Err(MessageLimitReached);
+        }
+
+        let mut message_key = hkdf::hmac::<Sha256>(&self.key, &[&[0x01]]);
+        let mut chain_key = hkdf::hmac::<Sha256>(&self.key, &[&[0x02]]);
+
+        let key = MessageKey {
+            key: message_key[..].try_into().unwrap(),
+            index: self.index,
+        };
+        self.key.copy_from_slice(&chain_key);
+        message_key.zeroize();
+        chain_key.zeroize();
+
+        match self.index.checked_add(1) {
+            Some(index) => self.index = index,
+            None => self.exhausted = true,
+        }
+
+        Ok(key)
+    }
+}
+
+/// The key of one message. Encrypting or decrypting consumes it, so it is used once.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct MessageKey {
+    key: [u8; 32],
+    index: u32,
+}
+
+impl MessageKey {
+    /// Returns the index of the message in its chain.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Encrypts `plaintext` and returns `header || ciphertext || tag`.
+    pub fn encrypt(self, plaintext: &[u8], header: Header, ad: &[u8]) -> Vec<u8> {
+        let header = header.to_bytes();
+
+        let mut output = Vec::with_capacity(HEADER_LENGTH + plaintext.len() + TAG_LENGTH);
+        output.extend_from_slice(&header);
+        output.extend(self.with_cipher(|cipher, nonce| {
+            cipher.encrypt(plaintext, nonce, &associated_data(&header, ad))
+        }));
+
+        output
+    }
+
+    /// Decrypts a message produced by [`MessageKey::encrypt`], header included.
+    pub fn decrypt(self, message: &[u8], ad: &[u8]) -> Result<Vec<u8>, TagMismatch> {
+        if message.len() < HEADER_LENGTH + TAG_LENGTH {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+        let (header, ciphertext) = message.split_at(HEADER_LENGTH);
+
+        self.with_cipher(|cipher, nonce| {
+            cipher.decrypt(ciphertext, nonce, &associated_data(header, ad))
+        })
+    }
+
+    fn with_cipher<T>(&self, f: impl FnOnce(&XChaChaPoly1305, &[u8]) -> T) -> T {
+        let mut okm = hkdf::expand::<Sha256>(&self.key, MESSAGE_KEY_INFO, 32 + 24).unwrap();
+        let (key, nonce) = okm.split_at(32);
+        let result = f(&XChaChaPoly1305::new(key, None), nonce);
+        okm.zeroize();
+
+        result
+    }
+}
+
+/// The header comes first, as it has a fixed length.
+fn associated_data(header: &[u8], ad: &[u8]) -> Vec<u8> {
+    [header, ad].concat()
+}
+
+/// The sending half of a chain.
+pub struct Sender {
+    chain: Chain,
+    previous_chain_length: u32,
+}
+
+impl Sender {
+    /// Panics if `chain_key` is not 32 bytes.
+    pub fn new(chain_key: &[u8]) -> Sender {
+        Sender::with_previous_chain_length(chain_key, 0)
+    }
+
+    /// Like [`Sender::new`], for a chain that replaces one on which
+    /// `previous_chain_length` messages were sent. Every header carries the number.
+    pub fn with_previous_chain_length(chain_key: &[u8], previous_chain_length: u32) -> Sender {
+        Sender {
+            chain: Chain::new(chain_key),
+            previous_chain_length,
+        }
+    }
+
+    /// Encrypts `plaintext` under the next message key and returns
+    /// `header || ciphertext || tag`.
+    ///
+    /// Fails once the chain has used all of its 2^32 message keys.
+    pub fn encrypt(&mut self, plaintext: &[u8], ad: &[u8]) -> Result<Vec<u8>, MessageLimitReached> {
+        let key = self.chain.next_key()?;
+        let header = Header {
+            previous_chain_length: self.previous_chain_length,
+            index: key.index(),
+        };
+
+        Ok(key.encrypt(plaintext, header, ad))
+    }
+
+    /// Returns the number of messages sent on this chain.
+    pub fn messages(&self) -> u32 {
+        self.chain.index()
+    }
+}
+
+/// The receiving half of a chain, which decrypts messages in any order, each once.
+pub struct Receiver {
+    chain: Chain,
+    skipped: BTreeMap<u32, MessageKey>,
+    max_skip: u32,
+}
+
+impl Receiver {
+    /// Panics if `chain_key` is not 32 bytes.
+    pub fn new(chain_key: &[u8]) -> Receiver {
+        Receiver::with_max_skip(chain_key, DEFAULT_MAX_SKIP)
+    }
+
+    /// Starts a receiver that keeps the keys of up to `max_skip` messages that have not
+    /// arrived yet, and rejects messages further ahead than that.
+    ///
+    /// Each kept key is 32 bytes, and a message far ahead costs one HMAC per skipped key,
+    /// so the limit bounds what a forged header can make the receiver do.
+    pub fn with_max_skip(chain_key: &[u8], max_skip: u32) -> Receiver {
+        Receiver {
+            chain: Chain::new(chain_key),
+            skipped: BTreeMap::new(),
+            max_skip,
+        }
+    }
+
+    /// Decrypts a message produced by [`Sender::encrypt`].
+    ///
+    /// The keys of the messages before it that have not arrived are kept for later. If
+    /// the message does not decrypt, the receiver is left unchanged.
+    pub fn decrypt(&mut self, message: &[u8], ad: &[u8]) -> Result<Vec<u8>, RatchetError> {
+        let header = Header::parse(message).ok_or(TagMismatch {
+            length_valid: false,
+        })?;
+
+        if header.index < self.chain.index() {
+            let key = self
+                .skipped
+                .get(&header.index)
+                .ok_or(RatchetError::KeyUnavailable)?;
+            let plaintext = key.clone().decrypt(message, ad)?;
+            self.skipped.remove(&header.index);
+
+            return Ok(plaintext);
+        }
+
+        // advanced on a copy, so a forged message cannot move the chain
+        let mut chain = self.chain.clone();
+        let skipped = Receiver::advance(&mut chain, header.index, self.max_skip)?;
+        let key = chain.next_key().map_err(|_| RatchetError::KeyUnavailable)?;
+        let plaintext = key.decrypt(message, ad)?;
+
+        self.chain = chain;
+        self.store(skipped);
+
+        Ok(plaintext)
+    }
+
+    /// Keeps the keys of all messages before `index` that have not arrived, e.g. those
+    /// of the previous chain in a double ratchet, as given by the
+    /// [`Header::previous_chain_length`] of the first message of the next one.
+    ///
+    /// Fails without changing the receiver if that would skip more than the limit.
+    pub fn skip_to(&mut self, index: u32) -> Result<(), RatchetError> {
+        if index <= self.chain.index() {
+            return Ok(());
+        }
+
+        let skipped = Receiver::advance(&mut self.chain, index, self.max_skip)?;
+        self.store(skipped);
+
+        Ok(())
+    }
+
+    /// Advances `chain` to `index` and returns the keys passed on the way.
+    fn advance(
+        chain: &mut Chain,
+        index: u32,
+        max_skip: u32,
+    ) -> Result<Vec<MessageKey>, RatchetError> {
+        if index - chain.index() > max_skip {
+            return Err(RatchetError::TooManySkipped);
+        }
+
+        let mut skipped = Vec::with_capacity((index - chain.index()) as usize);
+        while chain.index() < index {
+            skipped.push(chain.next_key().map_err(|_| RatchetError::KeyUnavailable)?);
+        }
+
+        Ok(skipped)
+    }
+
+    /// Keeps `keys`, dropping the oldest keys beyond the limit.
+    fn store(&mut self, keys: Vec<MessageKey>) {
+        for key in keys {
+            self.skipped.insert(key.index(), key);
+        }
+        while self.skipped.len() > self.max_skip as usize {
+            self.skipped.pop_first();
+        }
+    }
+
+    /// Returns the index the next message in order will have.
+    pub fn index(&self) -> u32 {
+        self.chain.index()
+    }
+
+    /// Returns the number of skipped message keys kept.
+    pub fn skipped_keys(&self) -> usize {
+        self.skipped.len()
+    }
+}
@@ -0,0 +1,333 @@
+//! A record layer in the style of TLS 1.3 (RFC 8446, section 5), over any [`Aead`].
+//!
+//! Each direction of a connection has its own traffic secret, from which HKDF-SHA256
+//! derives the AEAD key and a static IV with `HKDF-Expand-Label`, as in TLS. The nonce
+//! of a record is the IV XORed with its 64-bit sequence number, so no nonce is ever sent
+//! and records cannot be dropped, replayed or reordered without the next one failing.
+//!
+//! A record is laid out exactly like a TLS 1.3 application data record:
+//!
+//! ```text
+//! 0x17 || 0x0303 || length (2 bytes, big-endian) || AEAD(data || 0x17)
+//! ```
+//!
+//! with the 5-byte header as the AAD. After `records_per_key` records the traffic secret
+//! is replaced by `HKDF-Expand-Label(secret, "traffic upd", "", 32)`, as by a TLS
+//! `KeyUpdate`, and the sequence number starts over. Both ends count records, so they
+//! update at the same point without any message, and must be created with the same
+//! limit.
+//!
+//! ```
+//! use raycrypt::aeads::chachapoly1305::ChaChaPoly1305;
+//! use raycrypt::aeads::record::{derive_connection, record_length, Role};
+//!
+//! let shared_secret = [0x42u8; 32];
+//! let (mut client, _) =
+//!     derive_connection::<ChaChaPoly1305>(&shared_secret, b"transcript", Role::Client, 1000);
+//! let (_, mut server) =
+//!     derive_connection::<ChaChaPoly1305>(&shared_secret, b"transcript", Role::Server, 1000);
+//!
+//! let records = client.seal(b"hello");
+//! let length = record_length(&records).unwrap().unwrap();
+//! assert_eq!(server.open(&records[..length]).unwrap(), b"hello");
+//! ```
+use crate::aeads::Aead;
+pub use crate::errors::RecordError;
+use crate::kdfs::hkdf::{self, Sha256};
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+/// The length of a traffic secret, the output length of SHA-256.
+pub const SECRET_LENGTH: usize = 32;
+pub const HEADER_LENGTH: usize = 5;
+/// The most data a record holds. [`Sender::seal`] splits longer data.
+pub const MAX_PLAINTEXT_LENGTH: usize = 1 << 14;
+/// The most a record may hold after its header, as in TLS.
+pub const MAX_CIPHERTEXT_LENGTH: usize = MAX_PLAINTEXT_LENGTH + 256;
+/// The records per key of the TLS 1.3 limit for AES-GCM, which is also well within the
+/// limits of the other AEADs of this crate.
+pub const DEFAULT_RECORDS_PER_KEY: u64 = 1 << 24;
+
+const APPLICATION_DATA: u8 = 0x17;
+const LEGACY_VERSION: [u8; 2] = [0x03, 0x03];
+
+/// `HKDF-Expand-Label` of RFC 8446, section 7.1, with SHA-256.
+///
+/// Panics if `secret` is shorter than 32 bytes or `label` or `context` too long for the
+/// one byte lengths of the encoding.
+pub fn expand_label(secret: &[u8], label: &[u8], context: &[u8], length: u16) -> Vec<u8> {
+    assert!(
+        label.len() <= 249 && context.len() <= 255,
+        "the label or context is too long"
+    );
+
+    let mut info = Vec::with_capacity(10 + label.len() + context.len());
+    info.extend_from_slice(&length.to_be_bytes());
+    info.push(6 + label.len() as u8);
+    info.extend_from_slice(b"tls13 ");
+    info.extend_from_slice(label);
+    info.push(context.len() as u8);
+    info.extend_from_slice(context);
+
+    hkdf::expand::<Sha256>(secret, &info, length as usize).expect("the secret is too short")
+}
+
+/// Which end of the connection [`derive_connection`] is creating.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    Client,
+    Server,
+}
+
+/// Derives both traffic secrets of a connection from `shared_secret`, e.g. the result of
+/// a key exchange, and returns this end's sender and receiver.
+///
+/// The secrets are derived as TLS derives application traffic secrets, with `context`,
+/// e.g. a transcript of the handshake, hashed in the place of the TLS transcript hash:
+///
+/// ```text
+/// secret = HKDF-Extract("", shared_secret)
+/// client = HKDF-Expand-Label(secret, "c ap traffic", SHA-256(context), 32)
+/// server = HKDF-Expand-Label(secret, "s ap traffic", SHA-256(context), 32)
+/// ```
+///
+/// The client sends with the client secret and the server with the server secret.
+pub fn derive_connection<A: Aead>(
+    shared_secret: &[u8],
+    context: &[u8],
+    role: Role,
+    records_per_key: u64,
+) -> (Sender<A>, Receiver<A>) {
+    let mut secret = hkdf::extract::<Sha256>(b"", shared_secret);
+    let mut transcript = Sha256::new();
+    transcript.update(context);
+    let transcript = transcript.finalize();
+
+    let mut client = expand_label(&secret, b"c ap traffic", &transcript, 32);
+    let mut server = expand_label(&secret, b"s ap traffic", &transcript, 32);
+    secret.zeroize();
+
+    let (sending, receiving) = match role {
+        Role::Client => (&client, &server),
+        Role::Server => (&server, &client),
+    };
+    let connection = (
+        Sender {
+            keys: TrafficKeys::new(sending, records_per_key),
+        },
+        Receiver {
+            keys: TrafficKeys::new(receiving, records_per_key),
+        },
+    );
+
+    client.zeroize();
+    server.zeroize();
+
+    connection
+}
+
+/// Returns the total length of the record that `buffer` starts with, once its header has
+/// arrived, so a stream can be cut into the records [`Receiver::open`] takes. The length
+/// may be more than `buffer` holds yet.
+///
+/// Fails if the header is not that of an application data record within
+/// [`MAX_CIPHERTEXT_LENGTH`].
+pub fn record_length(buffer: &[u8]) -> Result<Option<usize>, RecordError> {
+    if buffer.len() < HEADER_LENGTH {
+        return Ok(None);
+    }
+
+    let length = u16::from_be_bytes([buffer[3], buffer[4]]) as usize;
+    if buffer[0] != APPLICATION_DATA
+        || buffer[1..3] != LEGACY_VERSION
+        || length > MAX_CIPHERTEXT_LENGTH
+    {
+        return Err(RecordError::InvalidHeader);
+    }
+
+    Ok(Some(HEADER_LENGTH + length))
+}
+
+/// The state shared by [`Sender`] and [`Receiver`]: the keys of one direction and the
+/// sequence number of its next record.
+struct TrafficKeys<A: Aead> {
+    cipher: A,
+    secret: Vec<u8>,
+    iv: Vec<u8>,
+    sequence: u64,
+    records_per_key: u64,
+    epoch: u64,
+}
+
+impl<A: Aead> TrafficKeys<A> {
+    fn new(secret: &[u8], records_per_key: u64) -> TrafficKeys<A> {
+        assert!(
+            records_per_key > 0,
+            "a key has to be used for at least one record"
+        );
+        assert!(
+            A::NONCE_LENGTH >= 8,
+            "the nonce is too short for a sequence number"
+        );
+
+        let mut key = expand_label(secret, b"key", b"", A::KEY_LENGTH as u16);
+        let cipher = A::from_key(&key);
+        key.zeroize();
+
+        TrafficKeys {
+            cipher,
+            secret: secret.to_vec(),
+            iv: expand_label(secret, b"iv", b"", A::NONCE_LENGTH as u16),
+            sequence: 0,
+            records_per_key,
+            epoch: 0,
+        }
+    }
+
+    fn nonce(&self) -> Vec<u8> {
+        let mut nonce = self.iv.clone();
+        let offset = nonce.len() - 8;
+        for (byte, sequence) in nonce[offset..].iter_mut().zip(self.sequence.to_be_bytes()) {
+            *byte ^= sequence;
+        }
+
+        nonce
+    }
+
+    /// Moves on to the next record, updating the keys once this one has used up its
+    /// records.
+    fn advance(&mut self) {
+        self.sequence += 1;
+        if self.sequence < self.records_per_key {
+            return;
+        }
+
+        let mut secret = expand_label(&self.secret, b"traffic upd", b"", SECRET_LENGTH as u16);
+        let epoch = self.epoch + 1;
+        *self = TrafficKeys::new(&secret, self.records_per_key);
+        self.epoch = epoch;
+        secret.zeroize();
+    }
+}
+
+impl<A: Aead> Drop for TrafficKeys<A> {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+        self.iv.zeroize();
+    }
+}
+
+/// The sending direction of a connection.
+pub struct Sender<A: Aead> {
+    keys: TrafficKeys<A>,
+}
+
+impl<A: Aead> Sender<A> {
+    /// Starts sending under `traffic_secret`, updating it after every `records_per_key`
+    /// records. Panics if `records_per_key` is zero.
+    pub fn new(traffic_secret: &[u8; SECRET_LENGTH], records_per_key: u64) -> Sender<A> {
+        Sender {
+            keys: TrafficKeys::new(traffic_secret, records_per_key),
+        }
+    }
+
+    /// Seals `data` into records of at most [`MAX_PLAINTEXT_LENGTH`] bytes each and
+    /// returns them back to back. Empty data is sent as one empty record.
+    pub fn seal(&mut self, data: &[u8]) -> Vec<u8> {
+        let records = data.len().div_ceil(MAX_PLAINTEXT_LENGTH).max(1);
+        let mut output =
+            Vec::with_capacity(data.len() + records * (HEADER_LENGTH + 1 + A::TAG_LENGTH));
+
+        for chunk in data.chunks(MAX_PLAINTEXT_LENGTH) {
+            self.seal_record(chunk, &mut output);
+        }
+        if data.is_empty() {
+            self.seal_record(&[], &mut output);
+        }
+
+        output
+    }
+
+    fn seal_record(&mut self, chunk: &[u8], output: &mut Vec<u8>) {
+        let length = (chunk.len() + 1 + A::TAG_LENGTH) as u16;
+        let mut header = [APPLICATION_DATA, LEGACY_VERSION[0], LEGACY_VERSION[1], 0, 0];
+        header[3..].copy_from_slice(&length.to_be_bytes());
+
+        let mut inner = Vec::with_capacity(chunk.len() + 1);
+        inner.extend_from_slice(chunk);
+        inner.push(APPLICATION_DATA);
+
+        output.extend_from_slice(&header);
+        output.extend(
+            self.keys
+                .cipher
+                .encrypt(&inner, &self.keys.nonce(), &header),
+        );
+        inner.zeroize();
+
+        self.keys.advance();
+    }
+
+    /// Returns the sequence number of the next record under the current key.
+    pub fn sequence(&self) -> u64 {
+        self.keys.sequence
+    }
+
+    /// Returns how many times the key has been updated.
+    pub fn epoch(&self) -> u64 {
+        self.keys.epoch
+    }
+}
+
+/// The receiving direction of a connection.
+pub struct Receiver<A: Aead> {
+    keys: TrafficKeys<A>,
+}
+
+impl<A: Aead> Receiver<A> {
+    /// Starts receiving under `traffic_secret`, see [`Sender::new`].
+    pub fn new(traffic_secret: &[u8; SECRET_LENGTH], records_per_key: u64) -> Receiver<A> {
+        Receiver {
+            keys: TrafficKeys::new(traffic_secret, records_per_key),
+        }
+    }
+
+    /// Opens the next record, which has to be exactly one whole record, see
+    /// [`record_length`]. Records have to be opened in the order they were sealed.
+    ///
+    /// As in TLS, zero bytes after the content type are accepted as padding.
+    pub fn open(&mut self, record: &[u8]) -> Result<Vec<u8>, RecordError> {
+        if record_length(record)? != Some(record.len()) {
+            return Err(RecordError::InvalidHeader);
+        }
+
+        let (header, ciphertext) = record.split_at(HEADER_LENGTH);
+        let mut inner = self
+            .keys
+            .cipher
+            .decrypt(ciphertext, &self.keys.nonce(), header)?;
+
+        match inner.iter().rposition(|byte| *byte != 0) {
+            Some(end) if inner[end] == APPLICATION_DATA => {
+                inner.truncate(end);
+                self.keys.advance();
+
+                Ok(inner)
+            }
+            _ => {
+                inner.zeroize();
+                Err(RecordError::UnexpectedContent)
+            }
+        }
+    }
+
+    /// Returns the sequence number of the next record under the current key.
+    pub fn sequence(&self) -> u64 {
+        self.keys.sequence
+    }
+
+    /// Returns how many times the key has been updated.
+    pub fn epoch(&self) -> u64 {
+        self.keys.epoch
+    }
+}
@@ -0,0 +1,129 @@
+//! Creating an AEAD from its name, for protocols and configuration files that pick the
+//! algorithm at runtime.
+//!
+//! [`from_name`] returns the cipher as a [`DynAead`], which every [`Aead`] implements.
+//! Only the algorithms enabled by the crate's features can be created:
+//!
+//! | name | algorithm |
+//! |------|-----------|
+//! | `chacha20poly1305`, `chacha12poly1305`, `chacha8poly1305` | [`ChaChaPoly1305`](super::chachapoly1305::ChaChaPoly1305) |
+//! | `xchacha20poly1305`, `xchacha12poly1305`, `xchacha8poly1305` | [`XChaChaPoly1305`](super::xchachapoly1305::XChaChaPoly1305) |
+//! | `aegis256` | [`Aegis256`](super::aegis256::Aegis256) |
+//! | `aegis128l` | [`Aegis128L`](super::aegis128l::Aegis128L) |
+//! | `aes256gcm` | [`Aes256Gcm`](super::aes256gcm::Aes256Gcm) |
+//! | `chacha20blake2b` | `ChaChaBlake2`, with the `blake2` feature |
+//!
+//! ```
+//! use raycrypt::aeads::registry::from_name;
+//!
+//! let cipher = from_name("xchacha20poly1305", &[0x42; 32]).unwrap();
+//! let nonce = vec![0; cipher.nonce_length()];
+//!
+//! let ciphertext = cipher.encrypt(b"message", &nonce, b"ad");
+//! assert_eq!(cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap(), b"message");
+//! ```
+use crate::aeads::Aead;
+pub use crate::errors::AlgorithmError;
+use crate::errors::TagMismatch;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// An AEAD whose algorithm is only known at runtime.
+///
+/// The methods behave like those of [`Aead`]: a nonce of the wrong length panics, and a
+/// message that does not authenticate fails with [`TagMismatch`].
+pub trait DynAead {
+    fn key_length(&self) -> usize;
+    fn nonce_length(&self) -> usize;
+    fn tag_length(&self) -> usize;
+
+    /// Encrypts `plaintext` and appends the tag.
+    fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8>;
+
+    /// Decrypts a message produced by [`DynAead::encrypt`].
+    fn decrypt(&self, ciphertext: &[u8], nonce: &[u8], ad: &[u8]) -> Result<Vec<u8>, TagMismatch>;
+}
+
+impl<A: Aead> DynAead for A {
+    fn key_length(&self) -> usize {
+        A::KEY_LENGTH
+    }
+
+    fn nonce_length(&self) -> usize {
+        A::NONCE_LENGTH
+    }
+
+    fn tag_length(&self) -> usize {
+        A::TAG_LENGTH
+    }
+
+    fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        Aead::encrypt(self, plaintext, nonce, ad)
+    }
+
+    fn decrypt(&self, ciphertext: &[u8], nonce: &[u8], ad: &[u8]) -> Result<Vec<u8>, TagMismatch> {
+        Aead::decrypt(self, ciphertext, nonce, ad)
+    }
+}
+
+/// Creates the AEAD called `name` under `key`, with names as in the module documentation.
+///
+/// Fails if no enabled algorithm has that name, or if `key` has the wrong length for it.
+pub fn from_name(name: &str, key: &[u8]) -> Result<Box<dyn DynAead>, AlgorithmError> {
+    let cipher: Box<dyn DynAead> = match name {
+        #[cfg(all(feature = "chacha", feature = "poly1305"))]
+        "chacha20poly1305" | "chacha12poly1305" | "chacha8poly1305" => {
+            use crate::aeads::chachapoly1305::ChaChaPoly1305;
+
+            check_key::<ChaChaPoly1305>(key)?;
+            Box::new(ChaChaPoly1305::new(key, chacha_rounds(name)))
+        }
+        #[cfg(feature = "xchacha")]
+        "xchacha20poly1305" | "xchacha12poly1305" | "xchacha8poly1305" => {
+            use crate::aeads::xchachapoly1305::XChaChaPoly1305;
+
+            check_key::<XChaChaPoly1305>(key)?;
+            Box::new(XChaChaPoly1305::new(key, chacha_rounds(name)))
+        }
+        #[cfg(feature = "aegis256")]
+        "aegis256" => boxed::<crate::aeads::aegis256::Aegis256>(key)?,
+        #[cfg(feature = "aegis128l")]
+        "aegis128l" => boxed::<crate::aeads::aegis128l::Aegis128L>(key)?,
+        #[cfg(feature = "aes256gcm")]
+        "aes256gcm" => boxed::<crate::aeads::aes256gcm::Aes256Gcm>(key)?,
+        #[cfg(feature = "blake2")]
+        "chacha20blake2b" => boxed::<crate::aeads::chachablake2::ChaChaBlake2>(key)?,
+        _ => return Err(AlgorithmError::UnknownAlgorithm),
+    };
+
+    Ok(cipher)
+}
+
+fn check_key<A: Aead>(key: &[u8]) -> Result<(), AlgorithmError> {
+    if key.len() == A::KEY_LENGTH {
+        Ok(())
+    } else {
+        Err(AlgorithmError::InvalidKeyLength)
+    }
+}
+
+#[allow(dead_code)] // unused when only ChaCha20-Poly1305 is enabled
+fn boxed<A: Aead + 'static>(key: &[u8]) -> Result<Box<dyn DynAead>, AlgorithmError> {
+    check_key::<A>(key)?;
+
+    Ok(Box::new(A::from_key(key)))
+}
+
+/// Returns the round preset a ChaCha name selects.
+#[cfg(all(feature = "chacha", feature = "poly1305"))]
+fn chacha_rounds(name: &str) -> Option<usize> {
+    use crate::ciphers::chacha::{CHACHA12, CHACHA20, CHACHA8};
+
+    if name.contains("chacha12") {
+        CHACHA12
+    } else if name.contains("chacha8") {
+        CHACHA8
+    } else {
+        CHACHA20
+    }
+}
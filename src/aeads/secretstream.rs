@@ -0,0 +1,272 @@
+//! An ordered stream of messages under one key, compatible with libsodium's
+//! `crypto_secretstream_xchacha20poly1305`.
+//!
+//! The sender starts a [`PushStream`], which draws a random 24-byte header, and the
+//! receiver opens a [`PullStream`] with the same key and header. Every message is sealed
+//! as `encrypted tag byte || ciphertext || MAC`, [`OVERHEAD`] bytes longer than its
+//! plaintext. The key is derived from the header with HChaCha20, and the nonce evolves
+//! with every message, so reordered, dropped or replayed messages fail to authenticate.
+//! The sender marks the last message with [`Tag::Final`]; a receiver that never sees it
+//! knows the stream was truncated.
+//!
+//! Both sides derive a new key after a message tagged [`Tag::Rekey`], after an explicit
+//! `rekey` call made on both sides at the same point, and when the 32-bit message counter
+//! wraps around.
+use crate::aeads::nonce::NonceGenerator;
+use crate::ciphers::chacha::{hchacha, ChaCha};
+pub use crate::errors::TagMismatch;
+use crate::macs::poly1305::Poly1305;
+use crate::utils::constant_time::ct_eq;
+use alloc::vec::Vec;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const KEY_LENGTH: usize = 32;
+pub const HEADER_LENGTH: usize = 24;
+/// The number of bytes a sealed message is longer than its plaintext: the tag byte and a
+/// 16-byte MAC.
+pub const OVERHEAD: usize = 17;
+
+/// The tag of a message, authenticated along with it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tag {
+    /// An ordinary message.
+    Message = 0,
+    /// The end of a set of messages, e.g. of a record made of several chunks. The stream
+    /// goes on as after [`Tag::Message`].
+    Push = 1,
+    /// Both sides derive a new key after this message.
+    Rekey = 2,
+    /// The last message of the stream.
+    Final = 3,
+}
+
+impl Tag {
+    fn from_byte(byte: u8) -> Option<Tag> {
+        match byte {
+            0 => Some(Tag::Message),
+            1 => Some(Tag::Push),
+            2 => Some(Tag::Rekey),
+            3 => Some(Tag::Final),
+            _ => None,
+        }
+    }
+}
+
+/// The key and nonce shared by both ends of a stream.
+#[derive(ZeroizeOnDrop)]
+struct State {
+    key: [u8; 32],
+    #[zeroize(skip)]
+    cipher: ChaCha,
+    counter: u32,
+    inonce: [u8; 8],
+}
+
+impl State {
+    fn new(key: &[u8], header: &[u8]) -> State {
+        assert_eq!(
+            header.len(),
+            HEADER_LENGTH,
+            "secretstream headers are 24 bytes"
+        );
+        let subkey = hchacha(key, &header[..16], None).expect("secretstream keys are 32 bytes");
+
+        let mut inonce = [0u8; 8];
+        inonce.copy_from_slice(&header[16..]);
+
+        State {
+            cipher: ChaCha::new(&subkey, None),
+            key: subkey,
+            counter: 1,
+            inonce,
+        }
+    }
+
+    /// The ChaCha20 nonce of the next message: the little-endian counter followed by the
+    /// 8 bytes that evolve with every MAC.
+    fn nonce(&self) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[..4].copy_from_slice(&self.counter.to_le_bytes());
+        nonce[4..].copy_from_slice(&self.inonce);
+
+        nonce
+    }
+
+    /// Authenticates `ad` and the 64-byte block holding the encrypted tag byte, each
+    /// padded to 16 bytes, then the ciphertext and their lengths.
+    fn mac(&self, nonce: &[u8; 12], ad: &[u8], block: &[u8; 64], ciphertext: &[u8]) -> [u8; 16] {
+        let mut poly1305_key = self.cipher.keystream(nonce, 0);
+        let mut poly1305 = Poly1305::new(&poly1305_key[..32]);
+        poly1305_key.zeroize();
+
+        poly1305.update(ad);
+        poly1305.update(block);
+        let (full, rest) = ciphertext.split_at(ciphertext.len() / 16 * 16);
+        poly1305.update(full);
+
+        // libsodium pads the ciphertext with `len % 16` zeros rather than up to a multiple
+        // of 16, so the tail can end mid-block
+        let mut tail = [0u8; 46];
+        tail[..rest.len()].copy_from_slice(rest);
+        let lengths = 2 * rest.len();
+        tail[lengths..lengths + 8].copy_from_slice(&(ad.len() as u64).to_le_bytes());
+        tail[lengths + 8..lengths + 16]
+            .copy_from_slice(&(64 + ciphertext.len() as u64).to_le_bytes());
+        poly1305.update_unpadded(&tail[..lengths + 16]);
+        tail.zeroize();
+
+        poly1305.tag().try_into().unwrap()
+    }
+
+    /// Moves on to the next message after one with `mac` and `tag`.
+    fn advance(&mut self, mac: &[u8; 16], tag: Tag) {
+        for (byte, mac) in self.inonce.iter_mut().zip(mac) {
+            *byte ^= mac;
+        }
+        self.counter = self.counter.wrapping_add(1);
+
+        if tag as u8 & Tag::Rekey as u8 != 0 || self.counter == 0 {
+            self.rekey();
+        }
+    }
+
+    /// Replaces the key and the evolving part of the nonce with the keystream over them,
+    /// and resets the counter.
+    fn rekey(&mut self) {
+        let mut next = [0u8; 40];
+        next[..32].copy_from_slice(&self.key);
+        next[32..].copy_from_slice(&self.inonce);
+        self.cipher.encrypt_in_place(&mut next, &self.nonce(), 0);
+
+        self.key.copy_from_slice(&next[..32]);
+        self.inonce.copy_from_slice(&next[32..]);
+        next.zeroize();
+
+        self.cipher = ChaCha::new(&self.key, None);
+        self.counter = 1;
+    }
+}
+
+/// The sending end of a stream.
+pub struct PushStream {
+    state: State,
+}
+
+impl PushStream {
+    /// Starts a stream under a 32-byte key and returns it with the header the receiver
+    /// needs to open it. The header is random and does not need to be kept secret.
+    #[cfg(feature = "getrandom")]
+    pub fn new(key: &[u8]) -> (PushStream, [u8; HEADER_LENGTH]) {
+        PushStream::with_nonce_generator(key, &mut NonceGenerator::new())
+    }
+
+    /// Like [`PushStream::new`], but draws the header from `nonces`. Only use a seeded
+    /// generator in tests.
+    pub fn with_nonce_generator(
+        key: &[u8],
+        nonces: &mut NonceGenerator,
+    ) -> (PushStream, [u8; HEADER_LENGTH]) {
+        let header = nonces.generate::<HEADER_LENGTH>();
+
+        (
+            PushStream {
+                state: State::new(key, &header),
+            },
+            header,
+        )
+    }
+
+    /// Seals the next message of the stream with `tag`, authenticating `ad` along with it.
+    ///
+    /// Panics if the message is too long for the ChaCha20 block counter, about 256 GiB.
+    pub fn push(&mut self, message: &[u8], ad: &[u8], tag: Tag) -> Vec<u8> {
+        let nonce = self.state.nonce();
+
+        let mut block = [0u8; 64];
+        block[0] = tag as u8;
+        self.state.cipher.encrypt_in_place(&mut block, &nonce, 1);
+
+        let mut sealed = Vec::with_capacity(message.len() + OVERHEAD);
+        sealed.push(block[0]);
+        sealed.extend_from_slice(message);
+        self.state
+            .cipher
+            .encrypt_in_place(&mut sealed[1..], &nonce, 2);
+
+        let mac = self.state.mac(&nonce, ad, &block, &sealed[1..]);
+        block.zeroize();
+        sealed.extend_from_slice(&mac);
+
+        self.state.advance(&mac, tag);
+
+        sealed
+    }
+
+    /// Derives a new key without sending a message. The receiver has to call
+    /// [`PullStream::rekey`] at the same point of the stream.
+    pub fn rekey(&mut self) {
+        self.state.rekey();
+    }
+}
+
+/// The receiving end of a stream.
+pub struct PullStream {
+    state: State,
+}
+
+impl PullStream {
+    /// Opens the stream started with `key` that sent `header`.
+    ///
+    /// Panics if `header` is not 24 bytes.
+    pub fn new(key: &[u8], header: &[u8]) -> PullStream {
+        PullStream {
+            state: State::new(key, header),
+        }
+    }
+
+    /// Opens the next message of the stream and returns it with its tag.
+    ///
+    /// Fails if the message does not authenticate, e.g. because it is not the next one
+    /// of the stream, or if it carries a tag this implementation does not know. The
+    /// stream is left unchanged in that case, so the correct next message can still be
+    /// opened.
+    pub fn pull(&mut self, sealed: &[u8], ad: &[u8]) -> Result<(Vec<u8>, Tag), TagMismatch> {
+        if sealed.len() < OVERHEAD {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        let nonce = self.state.nonce();
+        let (ciphertext, mac) = sealed[1..].split_at(sealed.len() - OVERHEAD);
+
+        let mut block = [0u8; 64];
+        block[0] = sealed[0];
+        self.state.cipher.encrypt_in_place(&mut block, &nonce, 1);
+        let tag = Tag::from_byte(block[0]);
+
+        // the MAC covers the tag byte as sent
+        block[0] = sealed[0];
+        let expected = self.state.mac(&nonce, ad, &block, ciphertext);
+        block.zeroize();
+
+        let tag = match tag {
+            Some(tag) if ct_eq(&expected, mac) => tag,
+            _ => return Err(TagMismatch { length_valid: true }),
+        };
+
+        let mut plaintext = ciphertext.to_vec();
+        self.state
+            .cipher
+            .encrypt_in_place(&mut plaintext, &nonce, 2);
+
+        self.state.advance(&expected, tag);
+
+        Ok((plaintext, tag))
+    }
+
+    /// Derives a new key at the point where the sender called [`PushStream::rekey`].
+    pub fn rekey(&mut self) {
+        self.state.rekey();
+    }
+}
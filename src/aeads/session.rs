@@ -0,0 +1,108 @@
+//! A message counting session around XChaCha-Poly1305 with generated nonces.
+//!
+//! Every message is encrypted under a fresh random 24-byte nonce and laid out as
+//! `nonce || ciphertext || tag`. The session counts the messages it encrypts and refuses
+//! to encrypt more than its limit, so a key is rotated well before random nonces get
+//! anywhere near colliding.
+use crate::aeads::nonce::NonceGenerator;
+use crate::aeads::xchachapoly1305::XChaChaPoly1305;
+pub use crate::errors::{MessageLimitReached, TagMismatch};
+use alloc::vec::Vec;
+
+pub const NONCE_LENGTH: usize = 24;
+pub const TAG_LENGTH: usize = 16;
+
+/// The message limit of [`Session::new`]. With 192-bit random nonces the probability of
+/// any collision within `2^32` messages is below `2^-128`.
+pub const DEFAULT_MESSAGE_LIMIT: u64 = 1 << 32;
+
+pub struct Session {
+    aead: XChaChaPoly1305,
+    nonces: NonceGenerator,
+    messages: u64,
+    limit: u64,
+}
+
+impl Session {
+    pub fn new(key: &[u8], rounds: Option<usize>) -> Session {
+        Session::with_limit(key, DEFAULT_MESSAGE_LIMIT, rounds)
+    }
+
+    /// Starts a session that encrypts at most `limit` messages.
+    pub fn with_limit(key: &[u8], limit: u64, rounds: Option<usize>) -> Session {
+        Session::with_nonce_generator(key, limit, NonceGenerator::new(), rounds)
+    }
+
+    /// Like [`Session::with_limit`], but draws nonces from `nonces`. Only use a seeded
+    /// generator in tests.
+    pub fn with_nonce_generator(
+        key: &[u8],
+        limit: u64,
+        nonces: NonceGenerator,
+        rounds: Option<usize>,
+    ) -> Session {
+        Session {
+            aead: XChaChaPoly1305::new(key, rounds),
+            nonces,
+            messages: 0,
+            limit,
+        }
+    }
+
+    /// Encrypts `plaintext` under a fresh nonce and returns `nonce || ciphertext || tag`.
+    ///
+    /// Fails once the session has encrypted as many messages as its limit allows.
+    pub fn encrypt(&mut self, plaintext: &[u8], ad: &[u8]) -> Result<Vec<u8>, MessageLimitReached> {
+        if self.messages >= self.limit {
+            return Err(MessageLimitReached);
+        }
+        self.messages += 1;
+
+        let nonce = self.nonces.generate::<NONCE_LENGTH>();
+
+        let mut output = Vec::with_capacity(NONCE_LENGTH + plaintext.len() + TAG_LENGTH);
+        output.extend_from_slice(&nonce);
+        output.extend(self.aead.encrypt(plaintext, &nonce, ad));
+
+        Ok(output)
+    }
+
+    /// Decrypts a message produced by [`Session::encrypt`]. Decryption does not count
+    /// towards the limit.
+    pub fn decrypt(&self, message: &[u8], ad: &[u8]) -> Result<Vec<u8>, TagMismatch> {
+        self.aead.decrypt_with_prepended_nonce(message, ad)
+    }
+
+    /// Returns the number of messages encrypted so far.
+    pub fn messages(&self) -> u64 {
+        self.messages
+    }
+
+    /// Returns the number of messages that can still be encrypted.
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.messages)
+    }
+}
+
+/// Encrypts `plaintext` under a fresh random nonce and returns
+/// `nonce || ciphertext || tag`, the layout of [`Session::encrypt`].
+///
+/// Every call draws a new nonce, so there is no nonce for the caller to get wrong. For
+/// many messages under one key prefer a [`Session`], which also enforces a message
+/// limit.
+pub fn encrypt(key: &[u8], plaintext: &[u8], ad: &[u8], rounds: Option<usize>) -> Vec<u8> {
+    Session::with_limit(key, 1, rounds)
+        .encrypt(plaintext, ad)
+        .unwrap()
+}
+
+/// Decrypts a message produced by [`encrypt`] or [`Session::encrypt`], reading the nonce
+/// from its first 24 bytes.
+pub fn decrypt(
+    key: &[u8],
+    message: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, TagMismatch> {
+    Session::with_limit(key, 0, rounds).decrypt(message, ad)
+}
@@ -0,0 +1,229 @@
+//! Framed streaming encryption with ChaCha-Poly1305 using the STREAM construction.
+//!
+//! The input is split into frames of `frame_size` bytes, each sealed as its own AEAD
+//! message with a 16 byte tag. The nonce of every frame is
+//! `prefix (7 bytes) || counter (4 bytes, big-endian) || last (1 byte)`, where `last` is
+//! 1 for the final frame and 0 otherwise. Reordered, dropped or truncated frames therefore
+//! fail to authenticate.
+use crate::aeads::chachapoly1305::ChaChaPoly1305;
+pub use crate::errors::InvalidMac;
+use alloc::vec::Vec;
+
+pub const PREFIX_LENGTH: usize = 7;
+pub const TAG_LENGTH: usize = 16;
+
+fn frame_nonce(prefix: &[u8; PREFIX_LENGTH], counter: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..PREFIX_LENGTH].copy_from_slice(prefix);
+    nonce[PREFIX_LENGTH..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+
+    nonce
+}
+
+fn split_prefix(nonce: &[u8]) -> [u8; PREFIX_LENGTH] {
+    let mut prefix = [0u8; PREFIX_LENGTH];
+    prefix.copy_from_slice(&nonce[..PREFIX_LENGTH]);
+
+    prefix
+}
+
+pub struct StreamEncryptor {
+    aead: ChaChaPoly1305,
+    prefix: [u8; PREFIX_LENGTH],
+    ad: Vec<u8>,
+    frame_size: usize,
+    counter: u32,
+    processed: u64,
+    buffer: Vec<u8>,
+}
+
+impl StreamEncryptor {
+    /// Starts a stream under `key` and the 7 byte nonce prefix `nonce`. `ad` is
+    /// authenticated with every frame.
+    ///
+    /// Panics if `frame_size` is zero.
+    pub fn new(
+        key: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        frame_size: usize,
+        rounds: Option<usize>,
+    ) -> StreamEncryptor {
+        assert!(frame_size > 0, "frame size must be non-zero");
+
+        StreamEncryptor {
+            aead: ChaChaPoly1305::new(key, rounds),
+            prefix: split_prefix(nonce),
+            ad: ad.to_vec(),
+            frame_size,
+            counter: 0,
+            processed: 0,
+            buffer: Vec::with_capacity(frame_size),
+        }
+    }
+
+    fn seal(&mut self, plaintext: &[u8], last: bool) -> Vec<u8> {
+        let nonce = frame_nonce(&self.prefix, self.counter, last);
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .expect("stream frame counter overflowed");
+
+        self.aead.encrypt(plaintext, &nonce, &self.ad)
+    }
+
+    /// Buffers `data` and returns every frame completed by it, each `frame_size + 16`
+    /// bytes long. Returns an empty vector if no frame was completed.
+    ///
+    /// A full frame is only sealed once more input arrives after it, as the frame that
+    /// ends the stream has to be marked as final by [`StreamEncryptor::finalize`].
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        self.processed += data.len() as u64;
+
+        let mut output = Vec::new();
+        let mut data = data;
+
+        while !data.is_empty() {
+            if self.buffer.len() == self.frame_size {
+                let frame = core::mem::take(&mut self.buffer);
+                output.extend(self.seal(&frame, false));
+            }
+
+            let take = (self.frame_size - self.buffer.len()).min(data.len());
+            self.buffer.extend_from_slice(&data[..take]);
+            data = &data[take..];
+        }
+
+        output
+    }
+
+    /// Feeds every chunk of `chunks` through [`StreamEncryptor::update`] in order and
+    /// returns the completed frames concatenated.
+    pub fn update_all<'a, I: IntoIterator<Item = &'a [u8]>>(&mut self, chunks: I) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.update_all_into(chunks, &mut output);
+
+        output
+    }
+
+    /// Like [`StreamEncryptor::update_all`], but appends the frames to `out`.
+    pub fn update_all_into<'a, I: IntoIterator<Item = &'a [u8]>>(
+        &mut self,
+        chunks: I,
+        out: &mut Vec<u8>,
+    ) {
+        for chunk in chunks {
+            out.extend(self.update(chunk));
+        }
+    }
+
+    /// Returns the counter of the next frame to be sealed and the number of plaintext
+    /// bytes passed to [`StreamEncryptor::update`] so far.
+    pub fn position(&self) -> (u32, u64) {
+        (self.counter, self.processed)
+    }
+
+    /// Seals the buffered input as the final frame, which may be shorter than
+    /// `frame_size` or empty.
+    pub fn finalize(mut self) -> Vec<u8> {
+        let frame = core::mem::take(&mut self.buffer);
+        self.seal(&frame, true)
+    }
+}
+
+pub struct StreamDecryptor {
+    aead: ChaChaPoly1305,
+    prefix: [u8; PREFIX_LENGTH],
+    ad: Vec<u8>,
+    frame_size: usize,
+    counter: u32,
+    processed: u64,
+    buffer: Vec<u8>,
+}
+
+impl StreamDecryptor {
+    /// Opens a stream produced by [`StreamEncryptor`] with the same key, nonce prefix,
+    /// `ad` and `frame_size`.
+    ///
+    /// Panics if `frame_size` is zero.
+    pub fn new(
+        key: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        frame_size: usize,
+        rounds: Option<usize>,
+    ) -> StreamDecryptor {
+        assert!(frame_size > 0, "frame size must be non-zero");
+
+        StreamDecryptor {
+            aead: ChaChaPoly1305::new(key, rounds),
+            prefix: split_prefix(nonce),
+            ad: ad.to_vec(),
+            frame_size,
+            counter: 0,
+            processed: 0,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn open(&mut self, frame: &[u8], last: bool) -> Result<Vec<u8>, InvalidMac> {
+        let nonce = frame_nonce(&self.prefix, self.counter, last);
+        let plaintext = self.aead.decrypt(frame, &nonce, &self.ad)?;
+        self.counter = self.counter.checked_add(1).ok_or(InvalidMac)?;
+
+        Ok(plaintext)
+    }
+
+    /// Buffers `data` and returns the plaintext of every frame that is known not to be
+    /// the last one.
+    pub fn update(&mut self, data: &[u8]) -> Result<Vec<u8>, InvalidMac> {
+        let frame_len = self.frame_size + TAG_LENGTH;
+        self.processed += data.len() as u64;
+        self.buffer.extend_from_slice(data);
+
+        let mut output = Vec::new();
+        let mut consumed = 0;
+        while self.buffer.len() - consumed > frame_len {
+            let frame = self.buffer[consumed..consumed + frame_len].to_vec();
+            output.extend(self.open(&frame, false)?);
+            consumed += frame_len;
+        }
+        self.buffer.drain(..consumed);
+
+        Ok(output)
+    }
+
+    /// Returns the counter of the next frame to be opened and the number of ciphertext
+    /// bytes passed to [`StreamDecryptor::update`] so far.
+    pub fn position(&self) -> (u32, u64) {
+        (self.counter, self.processed)
+    }
+
+    /// Authenticates the remaining input as the final frame. Fails if the stream was
+    /// truncated or the final frame was tampered with.
+    pub fn finalize(mut self) -> Result<Vec<u8>, InvalidMac> {
+        let frame = core::mem::take(&mut self.buffer);
+        self.open(&frame, true)
+    }
+}
+
+pub fn encrypt_init(
+    key: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    frame_size: usize,
+    rounds: Option<usize>,
+) -> StreamEncryptor {
+    StreamEncryptor::new(key, nonce, ad, frame_size, rounds)
+}
+
+pub fn decrypt_init(
+    key: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    frame_size: usize,
+    rounds: Option<usize>,
+) -> StreamDecryptor {
+    StreamDecryptor::new(key, nonce, ad, frame_size, rounds)
+}
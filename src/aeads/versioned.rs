@@ -0,0 +1,117 @@
+//! A small self-describing format around the ChaCha-Poly1305 AEADs.
+//!
+//! A blob is laid out as `version || nonce || length || ciphertext || tag`, where the
+//! version byte selects the cipher and with it the nonce and tag sizes, and `length` is
+//! the plaintext length as a little-endian 64-bit integer. Blobs whose ciphertext does not
+//! match the declared length are rejected before any decryption is attempted.
+//!
+//! The header, `version || nonce || length`, is authenticated as a prefix of the AAD, so
+//! a blob cannot be passed off under another version or length.
+//!
+//! Versions 1 and 2 name the first layout, `version || nonce || ciphertext || tag` with
+//! only the AAD authenticated. Blobs in it are still decrypted, but no longer written.
+
+use crate::aeads::{chachapoly1305, xchachapoly1305};
+use crate::errors::FormatError;
+use alloc::vec::Vec;
+
+/// ChaCha20-Poly1305 with a 12-byte nonce and a 16-byte tag, in the first layout.
+pub const CHACHAPOLY1305: u8 = 1;
+/// XChaCha20-Poly1305 with a 24-byte nonce and a 16-byte tag, in the first layout.
+pub const XCHACHAPOLY1305: u8 = 2;
+/// ChaCha20-Poly1305 with a 12-byte nonce, a 16-byte tag and the length field.
+pub const CHACHAPOLY1305_V2: u8 = 3;
+/// XChaCha20-Poly1305 with a 24-byte nonce, a 16-byte tag and the length field.
+pub const XCHACHAPOLY1305_V2: u8 = 4;
+
+/// Size of the length field.
+const LENGTH_SIZE: usize = 8;
+
+/// Returns the nonce, tag and length field size used by `version`.
+fn params(version: u8) -> Result<(usize, usize, usize), FormatError> {
+    match version {
+        CHACHAPOLY1305 => Ok((12, 16, 0)),
+        XCHACHAPOLY1305 => Ok((24, 16, 0)),
+        CHACHAPOLY1305_V2 => Ok((12, 16, LENGTH_SIZE)),
+        XCHACHAPOLY1305_V2 => Ok((24, 16, LENGTH_SIZE)),
+        _ => Err(FormatError::UnknownVersion(version)),
+    }
+}
+
+/// Encrypts `plaintext` with the cipher of `version` and returns the blob.
+///
+/// Fails with [`FormatError::UnknownVersion`] unless `version` is [`CHACHAPOLY1305_V2`]
+/// or [`XCHACHAPOLY1305_V2`], and with [`FormatError::InvalidLength`] if `nonce` has the
+/// wrong length for it.
+pub fn encrypt_versioned(
+    version: u8,
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, FormatError> {
+    if version != CHACHAPOLY1305_V2 && version != XCHACHAPOLY1305_V2 {
+        return Err(FormatError::UnknownVersion(version));
+    }
+
+    let (nonce_len, _, _) = params(version)?;
+
+    if nonce.len() != nonce_len {
+        return Err(FormatError::InvalidLength);
+    }
+
+    let length = (plaintext.len() as u64).to_le_bytes();
+    let header = [&[version], nonce, &length].concat();
+    let header_ad = [&header, ad].concat();
+
+    let ciphertext = match version {
+        CHACHAPOLY1305_V2 => chachapoly1305::encrypt(key, plaintext, nonce, &header_ad, None),
+        _ => xchachapoly1305::encrypt(key, plaintext, nonce, &header_ad, None),
+    };
+
+    Ok([header, ciphertext].concat())
+}
+
+/// Decrypts a blob produced by [`encrypt_versioned`] with the same `key` and `ad`, or a
+/// blob in the first layout.
+///
+/// Fails with [`FormatError::InvalidMac`] if the key or AAD is wrong or the blob was
+/// tampered with, and with another [`FormatError`] if it is malformed.
+pub fn decrypt_versioned(key: &[u8], blob: &[u8], ad: &[u8]) -> Result<Vec<u8>, FormatError> {
+    let version = match blob.first() {
+        Some(version) => *version,
+        None => return Err(FormatError::InvalidLength),
+    };
+
+    let (nonce_len, tag_len, length_size) = params(version)?;
+
+    let header_len = 1 + nonce_len + length_size;
+    if blob.len() < header_len + tag_len {
+        return Err(FormatError::InvalidLength);
+    }
+
+    let (header, ciphertext) = blob.split_at(header_len);
+    let nonce = &header[1..1 + nonce_len];
+
+    let header_ad = if length_size == 0 {
+        ad.to_vec()
+    } else {
+        let mut length = [0u8; LENGTH_SIZE];
+        length.copy_from_slice(&header[1 + nonce_len..]);
+
+        if u64::from_le_bytes(length) != (ciphertext.len() - tag_len) as u64 {
+            return Err(FormatError::InvalidLength);
+        }
+
+        [header, ad].concat()
+    };
+
+    let plaintext = match version {
+        CHACHAPOLY1305 | CHACHAPOLY1305_V2 => {
+            chachapoly1305::decrypt(key, ciphertext, nonce, &header_ad, None)?
+        }
+        _ => xchachapoly1305::decrypt(key, ciphertext, nonce, &header_ad, None)?,
+    };
+
+    Ok(plaintext)
+}
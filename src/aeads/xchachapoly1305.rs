@@ -1,6 +1,382 @@
-use crate::aeads::chachapoly1305;
-use crate::ciphers::chacha::hchacha;
-use crate::errors::InvalidMac;
+use crate::aeads::chachapoly1305::{self, split_tag, ChaChaPoly1305, Decryptor, Encryptor};
+#[cfg(feature = "getrandom")]
+use crate::aeads::nonce::NonceGenerator;
+use crate::ciphers::chacha::{check_rounds, hchacha};
+use crate::config::Config;
+use crate::errors::{DecryptError, InvalidLength, SequenceError, TagMismatch};
+use alloc::vec::Vec;
+use core::ops::Deref;
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const KEY_LENGTH: usize = 32;
+pub const NONCE_LENGTH: usize = 24;
+pub const TAG_LENGTH: usize = 16;
+
+/// Returns a random key from the operating system's random number generator.
+///
+/// Panics if the random number generator fails.
+#[cfg(feature = "getrandom")]
+pub fn generate_key() -> [u8; KEY_LENGTH] {
+    let mut key = [0u8; KEY_LENGTH];
+    getrandom(&mut key).expect("failed to read random bytes");
+
+    key
+}
+
+#[derive(ZeroizeOnDrop)]
+pub struct XChaChaPoly1305 {
+    key: [u8; 32],
+    rounds: Option<usize>,
+}
+
+impl XChaChaPoly1305 {
+    /// Panics if `key` is not 32 bytes.
+    pub fn new(key: &[u8], rounds: Option<usize>) -> XChaChaPoly1305 {
+        assert_eq!(key.len(), 32, "XChaCha-Poly1305 keys are 32 bytes");
+        check_rounds(rounds);
+
+        let mut cipher_key = [0u8; 32];
+        cipher_key.copy_from_slice(key);
+
+        XChaChaPoly1305 {
+            key: cipher_key,
+            rounds,
+        }
+    }
+
+    /// Derives the ChaCha-Poly1305 subcipher and its 12-byte nonce for a 24-byte nonce.
+    fn subcipher(&self, nonce: &[u8]) -> (ChaChaPoly1305, [u8; 12]) {
+        self.subcipher_with_rounds(nonce, self.rounds)
+    }
+
+    /// Like [`XChaChaPoly1305::subcipher`], with `rounds` for both HChaCha and the
+    /// subcipher.
+    fn subcipher_with_rounds(
+        &self,
+        nonce: &[u8],
+        rounds: Option<usize>,
+    ) -> (ChaChaPoly1305, [u8; 12]) {
+        assert_eq!(nonce.len(), 24, "XChaCha-Poly1305 nonces are 24 bytes");
+        let mut subkey = hchacha(&self.key, &nonce[0..16], rounds).unwrap();
+        let cipher = ChaChaPoly1305::new(&subkey, rounds);
+        subkey.zeroize();
+
+        let mut chacha_nonce = [0u8; 12];
+        chacha_nonce[4..].copy_from_slice(&nonce[16..24]);
+
+        (cipher, chacha_nonce)
+    }
+
+    /// Encrypts `plaintext` under a 24-byte nonce. Panics if `nonce` has another length,
+    /// see [`XChaChaPoly1305::try_encrypt`] for a version that fails instead.
+    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        let (mut ciphertext, tag) = self.encrypt_detached(plaintext, nonce, ad);
+        ciphertext.extend_from_slice(&tag);
+
+        ciphertext
+    }
+
+    /// Decrypts a message produced by [`XChaChaPoly1305::encrypt`]. Panics if `nonce` is
+    /// not 24 bytes, see [`XChaChaPoly1305::try_decrypt`] for a version that fails instead.
+    pub fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        let (ciphertext, tag) = split_tag(ciphertext).map_err(|_| TagMismatch {
+            length_valid: false,
+        })?;
+
+        self.decrypt_detached(ciphertext, tag, nonce, ad)
+    }
+
+    /// Like [`XChaChaPoly1305::encrypt`], but fails instead of panicking if `nonce` is not
+    /// 24 bytes, e.g. for nonces that come from untrusted input, or `plaintext` is longer
+    /// than [`MAX_PLAINTEXT_LENGTH`](chachapoly1305::MAX_PLAINTEXT_LENGTH).
+    pub fn try_encrypt(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, InvalidLength> {
+        if nonce.len() != NONCE_LENGTH {
+            return Err(InvalidLength);
+        }
+
+        let (cipher, chacha_nonce) = self.subcipher(nonce);
+        cipher.try_encrypt(plaintext, &chacha_nonce, ad)
+    }
+
+    /// Like [`XChaChaPoly1305::decrypt`], but fails with [`DecryptError::InvalidNonce`]
+    /// if `nonce` is not 24 bytes instead of panicking.
+    pub fn try_decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, DecryptError> {
+        if nonce.len() != NONCE_LENGTH {
+            return Err(DecryptError::InvalidNonce);
+        }
+
+        Ok(self.decrypt(ciphertext, nonce, ad)?)
+    }
+
+    /// Encrypts `plaintext` under a fresh random nonce and returns
+    /// `nonce || ciphertext || tag`.
+    ///
+    /// 24-byte nonces are long enough to be drawn at random for every message, so there is
+    /// no nonce for the caller to get wrong.
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt_with_random_nonce(&self, plaintext: &[u8], ad: &[u8]) -> Vec<u8> {
+        let nonce = NonceGenerator::new().generate::<NONCE_LENGTH>();
+
+        let mut output = Vec::with_capacity(NONCE_LENGTH + plaintext.len() + TAG_LENGTH);
+        output.extend_from_slice(&nonce);
+        output.extend(self.encrypt(plaintext, &nonce, ad));
+
+        output
+    }
+
+    /// Decrypts a message produced by [`XChaChaPoly1305::encrypt_with_random_nonce`],
+    /// reading the nonce from its first 24 bytes.
+    pub fn decrypt_with_prepended_nonce(
+        &self,
+        message: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        if message.len() < NONCE_LENGTH + TAG_LENGTH {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+
+        let (nonce, ciphertext) = message.split_at(NONCE_LENGTH);
+        self.decrypt(ciphertext, nonce, ad)
+    }
+
+    /// Like [`XChaChaPoly1305::encrypt`], but runs `rounds` rounds for this call instead
+    /// of the cipher's own, see [`ChaChaPoly1305::encrypt_with_rounds`]. The rounds apply
+    /// to HChaCha as well, as for a cipher created with `rounds`.
+    ///
+    /// Panics if `rounds` is odd or below [`MIN_ROUNDS`](crate::ciphers::chacha::MIN_ROUNDS).
+    pub fn encrypt_with_rounds(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        rounds: Option<usize>,
+    ) -> Vec<u8> {
+        let (cipher, chacha_nonce) =
+            self.subcipher_with_rounds(nonce, self.override_rounds(rounds));
+        cipher.encrypt(plaintext, &chacha_nonce, ad)
+    }
+
+    /// Decrypts a message produced by [`XChaChaPoly1305::encrypt_with_rounds`] with the
+    /// same `rounds`. Panics if `rounds` is odd or below 8.
+    pub fn decrypt_with_rounds(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        rounds: Option<usize>,
+    ) -> Result<Vec<u8>, TagMismatch> {
+        let (cipher, chacha_nonce) =
+            self.subcipher_with_rounds(nonce, self.override_rounds(rounds));
+        cipher.decrypt(ciphertext, &chacha_nonce, ad)
+    }
+
+    /// Checks a per-call round count and falls back to the cipher's own without one.
+    fn override_rounds(&self, rounds: Option<usize>) -> Option<usize> {
+        check_rounds(rounds);
+
+        rounds.or(self.rounds)
+    }
+
+    /// Like [`XChaChaPoly1305::decrypt`], but rejects messages whose plaintext would be
+    /// longer than `max_len` bytes, see [`ChaChaPoly1305::decrypt_bounded`].
+    pub fn decrypt_bounded(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        max_len: usize,
+    ) -> Result<Vec<u8>, DecryptError> {
+        if ciphertext.len().saturating_sub(16) > max_len {
+            return Err(DecryptError::TooLong { max_len });
+        }
+
+        Ok(self.decrypt(ciphertext, nonce, ad)?)
+    }
+
+    /// Like [`XChaChaPoly1305::encrypt`], but returns the ciphertext and the tag
+    /// separately.
+    pub fn encrypt_detached(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> (Vec<u8>, [u8; 16]) {
+        let (cipher, chacha_nonce) = self.subcipher(nonce);
+        cipher.encrypt_detached(plaintext, &chacha_nonce, ad)
+    }
+
+    /// Decrypts a ciphertext and tag produced by [`XChaChaPoly1305::encrypt_detached`].
+    pub fn decrypt_detached(
+        &self,
+        ciphertext: &[u8],
+        tag: &[u8; 16],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        let (cipher, chacha_nonce) = self.subcipher(nonce);
+        cipher.decrypt_detached(ciphertext, tag, &chacha_nonce, ad)
+    }
+
+    /// Like [`XChaChaPoly1305::encrypt`], but takes the plaintext as a list of buffers, see
+    /// [`ChaChaPoly1305::encrypt_vectored`].
+    pub fn encrypt_vectored<B: Deref<Target = [u8]>>(
+        &self,
+        plaintext: &[B],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Vec<u8> {
+        let (cipher, chacha_nonce) = self.subcipher(nonce);
+        cipher.encrypt_vectored(plaintext, &chacha_nonce, ad)
+    }
+
+    /// Decrypts a message given as a list of buffers, see
+    /// [`ChaChaPoly1305::decrypt_vectored`].
+    pub fn decrypt_vectored<B: Deref<Target = [u8]>>(
+        &self,
+        ciphertext: &[B],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        let (cipher, chacha_nonce) = self.subcipher(nonce);
+        cipher.decrypt_vectored(ciphertext, &chacha_nonce, ad)
+    }
+
+    /// Encrypts each message under its own nonce, derived from the 24-byte `base_nonce`
+    /// with [`sequence_nonce`] using the message's position in `messages`.
+    ///
+    /// Only the last 8 bytes of the nonce change between messages, so every message
+    /// shares the HChaCha subkey, which is derived once instead of per message. Each
+    /// ciphertext is identical to [`XChaChaPoly1305::encrypt`] under its own nonce.
+    pub fn encrypt_sequence(
+        &self,
+        messages: Vec<Vec<u8>>,
+        base_nonce: &[u8; 24],
+        ad: &[u8],
+    ) -> Vec<Vec<u8>> {
+        let (cipher, chacha_nonce) = self.subcipher(base_nonce);
+        cipher.encrypt_sequence(messages, &chacha_nonce, ad)
+    }
+
+    /// Decrypts messages produced by [`XChaChaPoly1305::encrypt_sequence`], see
+    /// [`ChaChaPoly1305::decrypt_sequence`].
+    pub fn decrypt_sequence(
+        &self,
+        ciphertexts: &[Vec<u8>],
+        base_nonce: &[u8; 24],
+        ad: &[u8],
+    ) -> Result<Vec<Vec<u8>>, SequenceError> {
+        let (cipher, chacha_nonce) = self.subcipher(base_nonce);
+        cipher.decrypt_sequence(ciphertexts, &chacha_nonce, ad)
+    }
+
+    /// Starts an incremental encryption under a 24-byte nonce, see
+    /// [`ChaChaPoly1305::encryptor`]. The subkey is derived once, here.
+    ///
+    /// Panics if `nonce` is not 24 bytes.
+    pub fn encryptor(&self, nonce: &[u8], ad: &[u8]) -> Encryptor<'static> {
+        let (cipher, chacha_nonce) = self.subcipher(nonce);
+        cipher.into_encryptor(&chacha_nonce, ad)
+    }
+
+    /// Starts an incremental decryption under a 24-byte nonce. Like
+    /// [`ChaChaPoly1305::decryptor`], it returns plaintext before the tag is checked, see
+    /// [`Decryptor`].
+    ///
+    /// Panics if `nonce` is not 24 bytes.
+    pub fn decryptor(&self, nonce: &[u8], ad: &[u8]) -> Decryptor<'static> {
+        let (cipher, chacha_nonce) = self.subcipher(nonce);
+        cipher.into_decryptor(&chacha_nonce, ad)
+    }
+
+    pub fn config(&self) -> Config {
+        Config {
+            algorithm: "XChaCha-Poly1305",
+            rounds: self.rounds.unwrap_or(20),
+            nonce_len: NONCE_LENGTH,
+            tag_len: TAG_LENGTH,
+        }
+    }
+}
+
+/// An [`XChaChaPoly1305`] bound to one nonce that can encrypt a single message.
+///
+/// [`SingleUseCipher::encrypt`] consumes the cipher, so the nonce cannot be reused by
+/// accident. Decrypt as usual with [`XChaChaPoly1305::decrypt`] and the same nonce.
+///
+/// ```compile_fail
+/// use raycrypt::aeads::xchachapoly1305::SingleUseCipher;
+///
+/// let cipher = SingleUseCipher::new(&[0u8; 32], &[0u8; 24], None);
+/// cipher.encrypt(b"first", b"");
+/// cipher.encrypt(b"second", b""); // the cipher was moved by the first call
+/// ```
+///
+/// The nonce is exactly 24 bytes, so a 12-byte ChaCha nonce does not compile either:
+///
+/// ```compile_fail
+/// use raycrypt::aeads::xchachapoly1305::SingleUseCipher;
+///
+/// SingleUseCipher::new(&[0u8; 32], &[0u8; 12], None);
+/// ```
+pub struct SingleUseCipher {
+    cipher: XChaChaPoly1305,
+    nonce: [u8; 24],
+}
+
+impl SingleUseCipher {
+    pub fn new(key: &[u8], nonce: &[u8; 24], rounds: Option<usize>) -> SingleUseCipher {
+        SingleUseCipher {
+            cipher: XChaChaPoly1305::new(key, rounds),
+            nonce: *nonce,
+        }
+    }
+
+    pub fn encrypt(self, plaintext: &[u8], ad: &[u8]) -> Vec<u8> {
+        self.cipher.encrypt(plaintext, &self.nonce, ad)
+    }
+}
+
+/// Derives the 24-byte nonce for message `index` of a sequence.
+///
+/// `index` is XORed into the last 8 bytes of `base_nonce` as in
+/// [`chachapoly1305::sequence_nonce`], which are the bytes that become the ChaCha nonce.
+/// The first 16 bytes, the HChaCha input, stay the same for the whole sequence.
+///
+/// The base nonce is exactly 24 bytes, so a 12-byte ChaCha nonce does not compile:
+///
+/// ```compile_fail
+/// use raycrypt::aeads::xchachapoly1305::sequence_nonce;
+///
+/// sequence_nonce(&[0u8; 12], 0);
+/// ```
+pub fn sequence_nonce(base_nonce: &[u8; 24], index: u64) -> [u8; 24] {
+    let mut nonce = *base_nonce;
+
+    let mut chacha_nonce = [0u8; 12];
+    chacha_nonce[4..].copy_from_slice(&nonce[16..]);
+    nonce[16..].copy_from_slice(&chachapoly1305::sequence_nonce(&chacha_nonce, index)[4..]);
+
+    nonce
+}
 
 pub fn encrypt(
     key: &[u8],
@@ -9,25 +385,106 @@ pub fn encrypt(
     ad: &[u8],
     rounds: Option<usize>,
 ) -> Vec<u8> {
-    let subkey = hchacha(key, &nonce[0..16], rounds);
+    XChaChaPoly1305::new(key, rounds).encrypt(plaintext, nonce, ad)
+}
 
-    let mut chacha_nonce = [0u8; 12];
-    chacha_nonce[4..].copy_from_slice(&nonce[16..24]);
+pub fn decrypt(
+    key: &[u8],
+    ciphertext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, TagMismatch> {
+    XChaChaPoly1305::new(key, rounds).decrypt(ciphertext, nonce, ad)
+}
 
-    chachapoly1305::encrypt(&subkey, plaintext, &chacha_nonce, ad, rounds)
+pub fn try_encrypt(
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, InvalidLength> {
+    XChaChaPoly1305::new(key, rounds).try_encrypt(plaintext, nonce, ad)
 }
 
-pub fn decrypt(
+pub fn try_decrypt(
+    key: &[u8],
+    ciphertext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, DecryptError> {
+    XChaChaPoly1305::new(key, rounds).try_decrypt(ciphertext, nonce, ad)
+}
+
+#[cfg(feature = "getrandom")]
+pub fn encrypt_with_random_nonce(
     key: &[u8],
     plaintext: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Vec<u8> {
+    XChaChaPoly1305::new(key, rounds).encrypt_with_random_nonce(plaintext, ad)
+}
+
+pub fn decrypt_with_prepended_nonce(
+    key: &[u8],
+    message: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, TagMismatch> {
+    XChaChaPoly1305::new(key, rounds).decrypt_with_prepended_nonce(message, ad)
+}
+
+pub fn encrypt_sequence(
+    key: &[u8],
+    messages: Vec<Vec<u8>>,
+    base_nonce: &[u8; 24],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Vec<Vec<u8>> {
+    XChaChaPoly1305::new(key, rounds).encrypt_sequence(messages, base_nonce, ad)
+}
+
+pub fn decrypt_sequence(
+    key: &[u8],
+    ciphertexts: &[Vec<u8>],
+    base_nonce: &[u8; 24],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<Vec<u8>>, SequenceError> {
+    XChaChaPoly1305::new(key, rounds).decrypt_sequence(ciphertexts, base_nonce, ad)
+}
+
+pub fn decrypt_bounded(
+    key: &[u8],
+    ciphertext: &[u8],
     nonce: &[u8],
     ad: &[u8],
+    max_len: usize,
     rounds: Option<usize>,
-) -> Result<Vec<u8>, InvalidMac> {
-    let subkey = hchacha(key, &nonce[0..16], rounds);
+) -> Result<Vec<u8>, DecryptError> {
+    XChaChaPoly1305::new(key, rounds).decrypt_bounded(ciphertext, nonce, ad, max_len)
+}
 
-    let mut chacha_nonce = [0u8; 12];
-    chacha_nonce[4..].copy_from_slice(&nonce[16..24]);
+pub fn encrypt_detached(
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> (Vec<u8>, [u8; 16]) {
+    XChaChaPoly1305::new(key, rounds).encrypt_detached(plaintext, nonce, ad)
+}
 
-    chachapoly1305::decrypt(&subkey, plaintext, &chacha_nonce, ad, rounds)
+pub fn decrypt_detached(
+    key: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8; 16],
+    nonce: &[u8],
+    ad: &[u8],
+    rounds: Option<usize>,
+) -> Result<Vec<u8>, TagMismatch> {
+    XChaChaPoly1305::new(key, rounds).decrypt_detached(ciphertext, tag, nonce, ad)
 }
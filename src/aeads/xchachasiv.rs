@@ -0,0 +1,138 @@
+//! XChaCha20-SIV, a deterministic AEAD that stays secure when nonces repeat.
+//!
+//! The tag is a synthetic IV: HMAC-SHA-256 under a MAC subkey over the nonce, the AAD and
+//! the plaintext, the first two prefixed with their lengths. Its first 24 bytes are the
+//! XChaCha20 nonce the plaintext is then encrypted with, under a separate encryption
+//! subkey. Both subkeys are derived from the 32-byte key with [`chacha::expand`].
+//!
+//! Since the nonce only goes into the MAC, repeating it, or leaving it empty, reveals
+//! whether the same plaintext was encrypted with the same AAD and nonce before, but
+//! never reuses keystream for two different messages. This makes it the safe choice when
+//! nonces cannot be managed reliably, and for deterministic encryption such as key
+//! wrapping. The price is a second pass over the message and a 32-byte tag. The
+//! construction is specific to this crate and is not compatible with other SIV modes.
+//!
+//! ```
+//! use raycrypt::aeads::xchachasiv::XChaChaSiv;
+//!
+//! let cipher = XChaChaSiv::new(&[0x42; 32]);
+//!
+//! // without a nonce, equal messages give equal ciphertexts and nothing else leaks
+//! let ciphertext = cipher.encrypt(b"message", b"", b"ad");
+//! assert_eq!(ciphertext, cipher.encrypt(b"message", b"", b"ad"));
+//! assert_eq!(cipher.decrypt(&ciphertext, b"", b"ad").unwrap(), b"message");
+//! ```
+use crate::ciphers::chacha::{self, hchacha, ChaCha};
+pub use crate::errors::TagMismatch;
+use crate::macs::hmac::HmacSha256;
+use crate::utils::constant_time::ct_eq;
+use alloc::vec::Vec;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const KEY_LENGTH: usize = 32;
+pub const TAG_LENGTH: usize = 32;
+
+const SUBKEY_CONTEXT: &[u8] = b"raycrypt XChaCha20-SIV subkeys";
+
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct XChaChaSiv {
+    mac_key: [u8; 32],
+    encryption_key: [u8; 32],
+}
+
+impl XChaChaSiv {
+    /// Panics if `key` is not 32 bytes.
+    pub fn new(key: &[u8]) -> XChaChaSiv {
+        let key: &[u8; KEY_LENGTH] = key.try_into().expect("XChaCha20-SIV keys are 32 bytes");
+
+        let mut subkeys = chacha::expand(key, SUBKEY_CONTEXT, 64);
+        let mut cipher = XChaChaSiv {
+            mac_key: [0u8; 32],
+            encryption_key: [0u8; 32],
+        };
+        cipher.mac_key.copy_from_slice(&subkeys[..32]);
+        cipher.encryption_key.copy_from_slice(&subkeys[32..]);
+        subkeys.zeroize();
+
+        cipher
+    }
+
+    /// Encrypts `plaintext` and returns `ciphertext || tag`.
+    ///
+    /// `nonce` may have any length, including none at all, and may repeat: it only makes
+    /// equal messages encrypt differently. Panics if `plaintext` is longer than the 256 GiB
+    /// of keystream one XChaCha20 nonce provides.
+    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        let tag = self.tag(plaintext, nonce, ad);
+
+        let mut output = Vec::with_capacity(plaintext.len() + TAG_LENGTH);
+        output.extend_from_slice(plaintext);
+        self.apply_keystream(&mut output, &tag);
+        output.extend_from_slice(&tag);
+
+        output
+    }
+
+    /// Decrypts a message produced by [`XChaChaSiv::encrypt`] with the same nonce and AAD.
+    ///
+    /// The tag can only be checked once the plaintext is known, so the message is
+    /// decrypted first, and the plaintext is wiped if the tag does not match.
+    pub fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        if ciphertext.len() < TAG_LENGTH {
+            return Err(TagMismatch {
+                length_valid: false,
+            });
+        }
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - TAG_LENGTH);
+
+        let mut plaintext = ciphertext.to_vec();
+        self.apply_keystream(&mut plaintext, tag);
+
+        if !ct_eq(&self.tag(&plaintext, nonce, ad), tag) {
+            plaintext.zeroize();
+            return Err(TagMismatch { length_valid: true });
+        }
+
+        Ok(plaintext)
+    }
+
+    fn tag(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        let mut mac = HmacSha256::new(&self.mac_key);
+        mac.update(&(nonce.len() as u64).to_le_bytes());
+        mac.update(nonce);
+        mac.update(&(ad.len() as u64).to_le_bytes());
+        mac.update(ad);
+        mac.update(plaintext);
+
+        mac.finalize()
+    }
+
+    /// XORs the XChaCha20 keystream for the synthetic IV `tag` into `buffer`.
+    fn apply_keystream(&self, buffer: &mut [u8], tag: &[u8]) {
+        let mut subkey = hchacha(&self.encryption_key, &tag[..16], None).unwrap();
+        let cipher = ChaCha::new(&subkey, None);
+        subkey.zeroize();
+
+        let mut chacha_nonce = [0u8; 12];
+        chacha_nonce[4..].copy_from_slice(&tag[16..24]);
+        cipher.encrypt_in_place(buffer, &chacha_nonce, 0);
+    }
+}
+
+pub fn encrypt(key: &[u8], plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+    XChaChaSiv::new(key).encrypt(plaintext, nonce, ad)
+}
+
+pub fn decrypt(
+    key: &[u8],
+    ciphertext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, TagMismatch> {
+    XChaChaSiv::new(key).decrypt(ciphertext, nonce, ad)
+}
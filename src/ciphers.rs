@@ -1 +1,2 @@
+#[cfg(feature = "chacha")]
 pub mod chacha;
@@ -1,3 +1,15 @@
 pub(crate) mod detect;
 pub(crate) mod backends;
+pub(crate) mod kdf;
+#[cfg(feature = "rayon")]
+pub(crate) mod par;
+pub(crate) mod rng;
+pub(crate) mod stream;
+#[cfg(feature = "trace")]
+pub(crate) mod trace;
 pub use detect::*;
+pub use kdf::*;
+pub use rng::*;
+pub use stream::*;
+#[cfg(feature = "trace")]
+pub use trace::*;
@@ -1,9 +1,17 @@
-use cfg_if::cfg_if;
-pub mod fallback;
+//! ChaCha backends.
+//!
+//! ChaCha is defined over little-endian 32-bit words, so every backend must read the key,
+//! nonce and counter as little-endian words and serialize the output state the same way,
+//! regardless of the host's byte order. The portable backend does this explicitly with
+//! `from_le_bytes`/`to_le_bytes`; the SIMD backends only exist on x86 and little-endian
+//! AArch64, so their unaligned loads and stores already produce the right layout.
 
-cfg_if! {
-    if #[cfg(any(target_arch = "x86", target_arch = "x86_64"))] {
-        pub mod avx2;
-        pub mod sse2;
-    }
-}
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub mod avx2;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub mod avx512;
+pub mod fallback;
+#[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+pub mod neon;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub mod sse2;
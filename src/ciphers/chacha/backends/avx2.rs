@@ -4,6 +4,7 @@ use core::arch::x86::*;
 use core::arch::x86_64::*;
 
 use crate::utils::from_le_bytes;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 const SIGMA: [u32; 8] = [
     0x61707865, 0x3320646e, 0x79622d32, 0x6b206574, 0x61707865, 0x3320646e, 0x79622d32, 0x6b206574,
@@ -66,8 +67,11 @@ unsafe fn double_quarter_round(mut data: [__m256i; 4]) -> [__m256i; 4] {
 
 #[target_feature(enable = "avx2")]
 pub unsafe fn rounds(mut items: [__m256i; 4], rounds: usize) -> [__m256i; 4] {
+    // `rounds` counts double rounds here
+    debug_assert!(rounds >= 1, "ChaCha needs at least one double round");
+
     items[3] = _mm256_add_epi32(items[3], _mm256_set_epi32(0, 0, 0, 1, 0, 0, 0, 0));
-    let initial_state = items.clone();
+    let initial_state = items;
 
     for _ in 0..rounds {
         items = double_quarter_round(items);
@@ -87,40 +91,123 @@ pub unsafe fn rounds(mut items: [__m256i; 4], rounds: usize) -> [__m256i; 4] {
     output
 }
 
-unsafe fn encrypt_remainder(block: &[u8], keystream: __m256i, ciphertext: &mut Vec<u8>) {
-    let mut ks_block = [0u8; 32];
-    _mm256_storeu_si256(ks_block.as_mut_ptr() as *mut __m256i, keystream);
+// Eight blocks at once: every vector holds one state word of eight consecutive blocks,
+// one block per lane. The quarter rounds then work on whole vectors and need no
+// shuffles between the column and diagonal rounds, which the two-block layout above is
+// bound by.
+const WIDE_BLOCKS: usize = 8;
 
-    for (i, j) in ks_block.iter().zip(block.iter()) {
-        ciphertext.push(i ^ j);
-    }
+#[inline(always)]
+unsafe fn rotl<const C: i32, const D: i32>(x: __m256i) -> __m256i {
+    _mm256_or_si256(_mm256_slli_epi32(x, C), _mm256_srli_epi32(x, D))
 }
 
-unsafe fn encrypt_block(block: &[u8], keystream: [__m256i; 4], ciphertext: &mut Vec<u8>) {
-    let mut ptr = block.as_ptr() as *const __m256i;
+#[inline(always)]
+unsafe fn wide_quarter_round(v: &mut [__m256i; 16], a: usize, b: usize, c: usize, d: usize) {
+    v[a] = _mm256_add_epi32(v[a], v[b]);
+    v[d] = rotl::<16, 16>(_mm256_xor_si256(v[d], v[a]));
+    v[c] = _mm256_add_epi32(v[c], v[d]);
+    v[b] = rotl::<12, 20>(_mm256_xor_si256(v[b], v[c]));
+    v[a] = _mm256_add_epi32(v[a], v[b]);
+    v[d] = rotl::<8, 24>(_mm256_xor_si256(v[d], v[a]));
+    v[c] = _mm256_add_epi32(v[c], v[d]);
+    v[b] = rotl::<7, 25>(_mm256_xor_si256(v[b], v[c]));
+}
 
-    for i in 0..4 {
-        if i * 32 > block.len() {
-            return;
-        }
-        if (i + 1) * 32 > block.len() {
-            encrypt_remainder(&block[i * 32..], keystream[i], ciphertext);
-            continue;
-        }
+/// Transposes eight vectors of eight words, so lane `i` of every input becomes vector
+/// `i` of the output.
+#[inline(always)]
+unsafe fn transpose(r: &[__m256i]) -> [__m256i; 8] {
+    let t0 = _mm256_unpacklo_epi32(r[0], r[1]);
+    let t1 = _mm256_unpackhi_epi32(r[0], r[1]);
+    let t2 = _mm256_unpacklo_epi32(r[2], r[3]);
+    let t3 = _mm256_unpackhi_epi32(r[2], r[3]);
+    let t4 = _mm256_unpacklo_epi32(r[4], r[5]);
+    let t5 = _mm256_unpackhi_epi32(r[4], r[5]);
+    let t6 = _mm256_unpacklo_epi32(r[6], r[7]);
+    let t7 = _mm256_unpackhi_epi32(r[6], r[7]);
+
+    let u0 = _mm256_unpacklo_epi64(t0, t2);
+    let u1 = _mm256_unpackhi_epi64(t0, t2);
+    let u2 = _mm256_unpacklo_epi64(t1, t3);
+    let u3 = _mm256_unpackhi_epi64(t1, t3);
+    let u4 = _mm256_unpacklo_epi64(t4, t6);
+    let u5 = _mm256_unpackhi_epi64(t4, t6);
+    let u6 = _mm256_unpacklo_epi64(t5, t7);
+    let u7 = _mm256_unpackhi_epi64(t5, t7);
+
+    [
+        _mm256_permute2x128_si256(u0, u4, 0x20),
+        _mm256_permute2x128_si256(u1, u5, 0x20),
+        _mm256_permute2x128_si256(u2, u6, 0x20),
+        _mm256_permute2x128_si256(u3, u7, 0x20),
+        _mm256_permute2x128_si256(u0, u4, 0x31),
+        _mm256_permute2x128_si256(u1, u5, 0x31),
+        _mm256_permute2x128_si256(u2, u6, 0x31),
+        _mm256_permute2x128_si256(u3, u7, 0x31),
+    ]
+}
 
-        let plaintext_block = _mm256_loadu_si256(ptr);
+/// Runs `rounds` double rounds over eight blocks in the word-per-vector layout and
+/// returns their keystream in order, two vectors per block.
+#[target_feature(enable = "avx2")]
+unsafe fn wide_rounds(initial_state: &[__m256i; 16], rounds: usize) -> [__m256i; 16] {
+    let mut v = *initial_state;
+
+    for _ in 0..rounds {
+        wide_quarter_round(&mut v, 0, 4, 8, 12);
+        wide_quarter_round(&mut v, 1, 5, 9, 13);
+        wide_quarter_round(&mut v, 2, 6, 10, 14);
+        wide_quarter_round(&mut v, 3, 7, 11, 15);
+        wide_quarter_round(&mut v, 0, 5, 10, 15);
+        wide_quarter_round(&mut v, 1, 6, 11, 12);
+        wide_quarter_round(&mut v, 2, 7, 8, 13);
+        wide_quarter_round(&mut v, 3, 4, 9, 14);
+    }
+
+    for (word, initial) in v.iter_mut().zip(initial_state) {
+        *word = _mm256_add_epi32(*word, *initial);
+    }
+
+    let mut low = transpose(&v[..8]);
+    let mut high = transpose(&v[8..]);
+
+    let mut keystream = [_mm256_setzero_si256(); 16];
+    for i in 0..WIDE_BLOCKS {
+        keystream[2 * i] = low[i];
+        keystream[2 * i + 1] = high[i];
+    }
+    v.zeroize();
+    low.zeroize();
+    high.zeroize();
+
+    keystream
+}
+
+unsafe fn encrypt_remainder(block: &mut [u8], keystream: __m256i) {
+    let mut ks_block = [0u8; 32];
+    _mm256_storeu_si256(ks_block.as_mut_ptr() as *mut __m256i, keystream);
 
-        let ciphertext_block = _mm256_xor_si256(plaintext_block, keystream[i]);
+    for (i, j) in block.iter_mut().zip(ks_block.iter()) {
+        *i ^= j;
+    }
 
-        let mut output_block = [0u8; 32];
-        _mm256_storeu_si256(output_block.as_mut_ptr() as *mut __m256i, ciphertext_block);
+    ks_block.zeroize();
+}
 
-        ciphertext.append(&mut output_block.to_vec());
+unsafe fn encrypt_block(block: &mut [u8], keystream: [__m256i; 4]) {
+    for (i, chunk) in block.chunks_mut(32).enumerate() {
+        if chunk.len() < 32 {
+            encrypt_remainder(chunk, keystream[i]);
+            continue;
+        }
 
-        ptr = ptr.add(1);
+        let ptr = chunk.as_mut_ptr() as *mut __m256i;
+        _mm256_storeu_si256(ptr, _mm256_xor_si256(_mm256_loadu_si256(ptr), keystream[i]));
     }
 }
 
+#[derive(ZeroizeOnDrop)]
 pub struct ChaCha {
     state: [__m256i; 3],
     rounds: usize,
@@ -155,11 +242,35 @@ impl ChaCha {
         rounds(state, self.rounds)
     }
 
+    /// Builds the initial state of eight blocks starting at `nonce_block[0]` in the
+    /// word-per-vector layout of [`wide_rounds`].
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn wide_state(&self, nonce_block: &[u32; 4]) -> [__m256i; 16] {
+        // the low lane of each row is the state of a single block
+        let mut rows = [[0u32; 8]; 3];
+        for (row, state) in rows.iter_mut().zip(&self.state) {
+            _mm256_storeu_si256(row.as_mut_ptr() as *mut __m256i, *state);
+        }
+
+        let mut wide_state = [_mm256_setzero_si256(); 16];
+        for (i, word) in wide_state[..12].iter_mut().enumerate() {
+            *word = _mm256_set1_epi32(rows[i / 4][i % 4] as i32);
+        }
+        for (i, word) in wide_state[12..].iter_mut().enumerate() {
+            *word = _mm256_set1_epi32(nonce_block[i] as i32);
+        }
+        wide_state[12] = _mm256_add_epi32(wide_state[12], _mm256_set_epi32(7, 6, 5, 4, 3, 2, 1, 0));
+        rows.zeroize();
+
+        wide_state
+    }
+
     #[inline]
     #[target_feature(enable = "avx2")]
-    pub unsafe fn _encrypt(&self, plaintext: &[u8], nonce: &[u8]) -> Vec<u8> {
+    pub unsafe fn _encrypt(&self, data: &mut [u8], nonce: &[u8], counter: u32) {
         let nonce_block = [
-            1,
+            counter,
             from_le_bytes(&nonce[0..4]),
             from_le_bytes(&nonce[4..8]),
             from_le_bytes(&nonce[8..12]),
@@ -169,23 +280,62 @@ impl ChaCha {
 
         let mut nonce = _mm256_broadcastsi128_si256(nonce_vector);
 
-        let mut ciphertext: Vec<u8> = Vec::new();
+        let wide_len = data.len() / (WIDE_BLOCKS * 64) * WIDE_BLOCKS * 64;
+        let (wide, rest) = data.split_at_mut(wide_len);
 
-        for block in plaintext.chunks(128) {
-            let keystream = self._keystream(nonce);
+        if !wide.is_empty() {
+            let mut wide_state = self.wide_state(&nonce_block);
+            let step = _mm256_set1_epi32(WIDE_BLOCKS as i32);
 
-            nonce = _mm256_add_epi32(nonce, _mm256_set_epi32(0, 0, 0, 2, 0, 0, 0, 2));
+            for batch in wide.chunks_exact_mut(WIDE_BLOCKS * 64) {
+                let mut keystream = wide_rounds(&wide_state, self.rounds);
+                wide_state[12] = _mm256_add_epi32(wide_state[12], step);
+
+                for (block, pair) in batch.chunks_exact_mut(128).zip(keystream.chunks_exact(4)) {
+                    encrypt_block(block, pair.try_into().unwrap());
+                }
+                keystream.zeroize();
+            }
+            wide_state.zeroize();
 
-            encrypt_block(block, keystream, &mut ciphertext);
+            let done = (wide_len / 64) as i32;
+            nonce = _mm256_add_epi32(nonce, _mm256_set_epi32(0, 0, 0, done, 0, 0, 0, done));
         }
 
-        ciphertext.to_vec()
+        // fewer than eight blocks are left, which go through the two-block layout
+        for block in rest.chunks_mut(128) {
+            let mut keystream = self._keystream(nonce);
+
+            nonce = _mm256_add_epi32(nonce, _mm256_set_epi32(0, 0, 0, 2, 0, 0, 0, 2));
+
+            encrypt_block(block, keystream);
+            keystream.zeroize();
+        }
     }
 }
 
 impl ChaCha {
-    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8]) -> Vec<u8> {
-        unsafe { self._encrypt(plaintext, nonce) }
+    pub fn apply_keystream(&self, data: &mut [u8], nonce: &[u8], counter: u32) {
+        unsafe { self._encrypt(data, nonce, counter) }
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds * 2
+    }
+
+    pub fn key_words(&self) -> [u32; 8] {
+        // both lanes of each row hold the same words
+        let mut words = [0u32; 8];
+        unsafe {
+            let (low, high) = (
+                _mm256_castsi256_si128(self.state[1]),
+                _mm256_castsi256_si128(self.state[2]),
+            );
+            _mm_storeu_si128(words.as_mut_ptr() as *mut __m128i, low);
+            _mm_storeu_si128(words[4..].as_mut_ptr() as *mut __m128i, high);
+        }
+
+        words
     }
 
     pub fn keystream(&self, nonce: &[u8], counter: u32) -> [u8; 64] {
@@ -199,12 +349,13 @@ impl ChaCha {
         unsafe {
             let nonce = _mm256_broadcastsi128_si256(_mm_loadu_si128(nonce_block.as_mut_ptr() as *mut __m128i));
 
-            let keystream = self._keystream(nonce);
+            let mut keystream = self._keystream(nonce);
 
             let mut output = [0u8; 64];
 
             _mm256_storeu_si256(output.as_mut_ptr() as *mut __m256i, keystream[0]);
             _mm256_storeu_si256((output.as_mut_ptr() as *mut __m256i).add(1), keystream[1]);
+            keystream.zeroize();
 
             output
         }
@@ -0,0 +1,185 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use crate::ciphers::chacha::backends::avx2;
+use crate::utils::from_le_bytes;
+use core::sync::atomic;
+use core::{mem, ptr};
+use zeroize::Zeroize;
+
+const SIGMA: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+// Sixteen blocks at once, in the word-per-vector layout of the AVX2 backend: every vector
+// holds one state word of sixteen consecutive blocks. Messages shorter than a full batch,
+// and the tail of longer ones, go through the AVX2 backend.
+const WIDE_BLOCKS: usize = 16;
+
+/// Overwrites `vectors` with zeros the way `zeroize` does for the narrower registers.
+/// `zeroize` has no implementation for AVX-512 registers.
+fn wipe<const N: usize>(vectors: &mut [__m512i; N]) {
+    for vector in vectors.iter_mut() {
+        unsafe { ptr::write_volatile(vector, mem::zeroed()) };
+    }
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+#[inline(always)]
+unsafe fn wide_quarter_round(v: &mut [__m512i; 16], a: usize, b: usize, c: usize, d: usize) {
+    v[a] = _mm512_add_epi32(v[a], v[b]);
+    v[d] = _mm512_rol_epi32::<16>(_mm512_xor_si512(v[d], v[a]));
+    v[c] = _mm512_add_epi32(v[c], v[d]);
+    v[b] = _mm512_rol_epi32::<12>(_mm512_xor_si512(v[b], v[c]));
+    v[a] = _mm512_add_epi32(v[a], v[b]);
+    v[d] = _mm512_rol_epi32::<8>(_mm512_xor_si512(v[d], v[a]));
+    v[c] = _mm512_add_epi32(v[c], v[d]);
+    v[b] = _mm512_rol_epi32::<7>(_mm512_xor_si512(v[b], v[c]));
+}
+
+/// Transposes sixteen vectors of sixteen words, so lane `i` of every input becomes
+/// vector `i` of the output: the 64-byte keystream block of block `i`.
+#[inline(always)]
+unsafe fn transpose(v: &[__m512i; 16]) -> [__m512i; 16] {
+    // within each 128-bit lane, gather words 4 * r..4 * r + 4 of the lane's four blocks
+    let mut rows = [_mm512_setzero_si512(); 16];
+    for r in 0..4 {
+        let t0 = _mm512_unpacklo_epi32(v[4 * r], v[4 * r + 1]);
+        let t1 = _mm512_unpackhi_epi32(v[4 * r], v[4 * r + 1]);
+        let t2 = _mm512_unpacklo_epi32(v[4 * r + 2], v[4 * r + 3]);
+        let t3 = _mm512_unpackhi_epi32(v[4 * r + 2], v[4 * r + 3]);
+
+        rows[4 * r] = _mm512_unpacklo_epi64(t0, t2);
+        rows[4 * r + 1] = _mm512_unpackhi_epi64(t0, t2);
+        rows[4 * r + 2] = _mm512_unpacklo_epi64(t1, t3);
+        rows[4 * r + 3] = _mm512_unpackhi_epi64(t1, t3);
+    }
+
+    // then transpose the 128-bit lanes, so each vector holds the four rows of one block
+    let mut blocks = [_mm512_setzero_si512(); 16];
+    for k in 0..4 {
+        let s0 = _mm512_shuffle_i32x4::<0x44>(rows[k], rows[4 + k]);
+        let s1 = _mm512_shuffle_i32x4::<0xee>(rows[k], rows[4 + k]);
+        let s2 = _mm512_shuffle_i32x4::<0x44>(rows[8 + k], rows[12 + k]);
+        let s3 = _mm512_shuffle_i32x4::<0xee>(rows[8 + k], rows[12 + k]);
+
+        blocks[k] = _mm512_shuffle_i32x4::<0x88>(s0, s2);
+        blocks[4 + k] = _mm512_shuffle_i32x4::<0xdd>(s0, s2);
+        blocks[8 + k] = _mm512_shuffle_i32x4::<0x88>(s1, s3);
+        blocks[12 + k] = _mm512_shuffle_i32x4::<0xdd>(s1, s3);
+    }
+    wipe(&mut rows);
+
+    blocks
+}
+
+/// Runs `rounds` double rounds over sixteen blocks and returns their keystream in order,
+/// one vector per block.
+#[target_feature(enable = "avx512f")]
+unsafe fn wide_rounds(initial_state: &[__m512i; 16], rounds: usize) -> [__m512i; 16] {
+    let mut v = *initial_state;
+
+    for _ in 0..rounds {
+        wide_quarter_round(&mut v, 0, 4, 8, 12);
+        wide_quarter_round(&mut v, 1, 5, 9, 13);
+        wide_quarter_round(&mut v, 2, 6, 10, 14);
+        wide_quarter_round(&mut v, 3, 7, 11, 15);
+        wide_quarter_round(&mut v, 0, 5, 10, 15);
+        wide_quarter_round(&mut v, 1, 6, 11, 12);
+        wide_quarter_round(&mut v, 2, 7, 8, 13);
+        wide_quarter_round(&mut v, 3, 4, 9, 14);
+    }
+
+    for (word, initial) in v.iter_mut().zip(initial_state) {
+        *word = _mm512_add_epi32(*word, *initial);
+    }
+
+    let keystream = transpose(&v);
+    wipe(&mut v);
+
+    keystream
+}
+
+/// The AVX2 backend with a sixteen-block path for long messages.
+pub struct ChaCha {
+    inner: avx2::ChaCha,
+}
+
+impl ChaCha {
+    pub fn new(key: &[u8], rounds: Option<usize>) -> ChaCha {
+        ChaCha {
+            inner: avx2::ChaCha::new(key, rounds),
+        }
+    }
+
+    /// Builds the initial state of sixteen blocks starting at block `counter`.
+    #[inline]
+    #[target_feature(enable = "avx512f")]
+    unsafe fn wide_state(&self, nonce: &[u8], counter: u32) -> [__m512i; 16] {
+        let mut key = self.inner.key_words();
+
+        let mut wide_state = [_mm512_setzero_si512(); 16];
+        for (word, value) in wide_state[..4].iter_mut().zip(SIGMA) {
+            *word = _mm512_set1_epi32(value as i32);
+        }
+        for (word, value) in wide_state[4..12].iter_mut().zip(key) {
+            *word = _mm512_set1_epi32(value as i32);
+        }
+        wide_state[12] = _mm512_add_epi32(
+            _mm512_set1_epi32(counter as i32),
+            _mm512_set_epi32(15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0),
+        );
+        for (i, word) in wide_state[13..].iter_mut().enumerate() {
+            *word = _mm512_set1_epi32(from_le_bytes(&nonce[4 * i..4 * i + 4]) as i32);
+        }
+        key.zeroize();
+
+        wide_state
+    }
+
+    #[target_feature(enable = "avx512f")]
+    unsafe fn _encrypt(&self, data: &mut [u8], nonce: &[u8], counter: u32) {
+        let wide_len = data.len() / (WIDE_BLOCKS * 64) * WIDE_BLOCKS * 64;
+        let (wide, rest) = data.split_at_mut(wide_len);
+
+        if !wide.is_empty() {
+            let mut wide_state = self.wide_state(nonce, counter);
+            let step = _mm512_set1_epi32(WIDE_BLOCKS as i32);
+            let rounds = self.inner.rounds() / 2;
+
+            for batch in wide.chunks_exact_mut(WIDE_BLOCKS * 64) {
+                let mut keystream = wide_rounds(&wide_state, rounds);
+                wide_state[12] = _mm512_add_epi32(wide_state[12], step);
+
+                for (block, keystream) in batch.chunks_exact_mut(64).zip(&keystream) {
+                    let ptr = block.as_mut_ptr() as *mut __m512i;
+                    _mm512_storeu_si512(ptr, _mm512_xor_si512(_mm512_loadu_si512(ptr), *keystream));
+                }
+                wipe(&mut keystream);
+            }
+            wipe(&mut wide_state);
+        }
+
+        // the last counter of the batches may be the last one, so the next wraps around
+        let counter = counter.wrapping_add((wide_len / 64) as u32);
+        self.inner.apply_keystream(rest, nonce, counter);
+    }
+}
+
+impl ChaCha {
+    pub fn apply_keystream(&self, data: &mut [u8], nonce: &[u8], counter: u32) {
+        unsafe { self._encrypt(data, nonce, counter) }
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.inner.rounds()
+    }
+
+    pub fn key_words(&self) -> [u32; 8] {
+        self.inner.key_words()
+    }
+
+    pub fn keystream(&self, nonce: &[u8], counter: u32) -> [u8; 64] {
+        self.inner.keystream(nonce, counter)
+    }
+}
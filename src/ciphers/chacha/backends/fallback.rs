@@ -1,4 +1,5 @@
 use crate::utils::from_le_bytes;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 fn quarter_round(a: usize, b: usize, c: usize, d: usize, block: &mut [u32; 16]) {
     block[a] = block[a].wrapping_add(block[b]);
@@ -32,39 +33,57 @@ fn double_round(mut block: [u32; 16]) -> [u32; 16] {
     block
 }
 
+#[derive(ZeroizeOnDrop)]
 pub struct ChaCha {
-    key: Vec<u8>,
+    /// The key as little-endian words, rows 1 and 2 of the state.
+    key: [u32; 8],
     rounds: usize,
 }
 
 impl ChaCha {
     pub fn new(key: &[u8], rounds: Option<usize>) -> ChaCha {
+        let mut words = [0u32; 8];
+        for (word, chunk) in words.iter_mut().zip(key[..32].chunks(4)) {
+            *word = from_le_bytes(chunk);
+        }
+
         ChaCha {
-            key: key.to_vec(),
+            key: words,
             rounds: rounds.unwrap_or(20) / 2,
         }
     }
 
+    pub fn rounds(&self) -> usize {
+        self.rounds * 2
+    }
+
+    pub fn key_words(&self) -> [u32; 8] {
+        self.key
+    }
+
     pub fn keystream(&self, nonce: &[u8], counter: u32) -> [u8; 64] {
-        let mut state = [
+        let mut state: [u32; 16] = [
             0x61707865,
             0x3320646e,
             0x79622d32,
             0x6b206574,
-            from_le_bytes(&self.key[0..4]),
-            from_le_bytes(&self.key[4..8]),
-            from_le_bytes(&self.key[8..12]),
-            from_le_bytes(&self.key[12..16]),
-            from_le_bytes(&self.key[16..20]),
-            from_le_bytes(&self.key[20..24]),
-            from_le_bytes(&self.key[24..28]),
-            from_le_bytes(&self.key[28..]),
+            self.key[0],
+            self.key[1],
+            self.key[2],
+            self.key[3],
+            self.key[4],
+            self.key[5],
+            self.key[6],
+            self.key[7],
             counter,
+            from_le_bytes(&nonce[0..4]),
             from_le_bytes(&nonce[4..8]),
             from_le_bytes(&nonce[8..12]),
-            from_le_bytes(&nonce[12..]),
         ];
 
+        let mut initial_state = state;
+
+        debug_assert!(self.rounds >= 1, "ChaCha needs at least one double round");
         for _ in 0..self.rounds {
             state = double_round(state);
         }
@@ -72,28 +91,30 @@ impl ChaCha {
         let mut result = [0u8; 64];
 
         for (index, chunk) in state.iter().enumerate() {
-            result[index * 4..].copy_from_slice(&chunk.to_le_bytes());
+            let word = chunk.wrapping_add(initial_state[index]);
+            result[index * 4..(index + 1) * 4].copy_from_slice(&word.to_le_bytes());
         }
 
+        state.zeroize();
+        initial_state.zeroize();
+
         result
     }
 
-    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8]) -> Vec<u8> {
-        let mut ciphertext: Vec<u8> = Vec::new();
-
-        for block in plaintext.chunks(64) {
-            let keystream = self.keystream(nonce, 1);
+    pub fn apply_keystream(&self, data: &mut [u8], nonce: &[u8], counter: u32) {
+        for (index, block) in data.chunks_mut(64).enumerate() {
+            let mut keystream = self.keystream(nonce, counter.wrapping_add(index as u32));
 
-            for (key, chunk) in block.iter().zip(keystream) {
-                ciphertext.push(chunk ^ key);
+            for (byte, key) in block.iter_mut().zip(&keystream) {
+                *byte ^= key;
             }
-        }
 
-        ciphertext
+            keystream.zeroize();
+        }
     }
 }
 
-pub fn hchacha(key: &[u8], nonce: &[u8], rounds: usize) -> [u8; 32] {
+pub fn hchacha(key: &[u8], nonce: &[u8], rounds: Option<usize>) -> [u8; 32] {
     let mut state = [
         0x61707865,
         0x3320646e,
@@ -113,6 +134,9 @@ pub fn hchacha(key: &[u8], nonce: &[u8], rounds: usize) -> [u8; 32] {
         from_le_bytes(&nonce[12..16]),
     ];
 
+    let rounds = rounds.unwrap_or(20);
+    debug_assert!(rounds >= 2, "ChaCha needs at least one double round");
+
     for _ in 0..(rounds / 2) {
         state = double_round(state);
     }
@@ -126,5 +150,7 @@ pub fn hchacha(key: &[u8], nonce: &[u8], rounds: usize) -> [u8; 32] {
         result_chunk.copy_from_slice(&chunk.to_le_bytes());
     }
 
+    state.zeroize();
+
     result
 }
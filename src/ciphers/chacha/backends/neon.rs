@@ -0,0 +1,298 @@
+use core::arch::aarch64::*;
+
+use crate::utils::from_le_bytes;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+const SIGMA: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+#[inline(always)]
+unsafe fn rotl<const C: i32, const D: i32>(x: uint32x4_t) -> uint32x4_t {
+    vsriq_n_u32::<D>(vshlq_n_u32::<C>(x), x)
+}
+
+/// A rotation by 16 swaps the halves of every word.
+#[inline(always)]
+unsafe fn rotl16(x: uint32x4_t) -> uint32x4_t {
+    vreinterpretq_u32_u16(vrev32q_u16(vreinterpretq_u16_u32(x)))
+}
+
+#[inline(always)]
+unsafe fn rows_to_cols(vs: &mut [uint32x4_t; 4]) {
+    vs[2] = vextq_u32::<1>(vs[2], vs[2]);
+    vs[3] = vextq_u32::<2>(vs[3], vs[3]);
+    vs[0] = vextq_u32::<3>(vs[0], vs[0]);
+}
+
+#[inline(always)]
+unsafe fn cols_to_rows(vs: &mut [uint32x4_t; 4]) {
+    vs[2] = vextq_u32::<3>(vs[2], vs[2]);
+    vs[3] = vextq_u32::<2>(vs[3], vs[3]);
+    vs[0] = vextq_u32::<1>(vs[0], vs[0]);
+}
+
+#[inline(always)]
+unsafe fn permute(data: &mut [uint32x4_t; 4]) {
+    data[0] = vaddq_u32(data[0], data[1]);
+    data[3] = veorq_u32(data[3], data[0]);
+    data[3] = rotl16(data[3]);
+
+    data[2] = vaddq_u32(data[2], data[3]);
+    data[1] = veorq_u32(data[1], data[2]);
+    data[1] = rotl::<12, 20>(data[1]);
+
+    data[0] = vaddq_u32(data[0], data[1]);
+    data[3] = veorq_u32(data[3], data[0]);
+    data[3] = rotl::<8, 24>(data[3]);
+
+    data[2] = vaddq_u32(data[2], data[3]);
+    data[1] = veorq_u32(data[1], data[2]);
+    data[1] = rotl::<7, 25>(data[1]);
+}
+
+#[inline(always)]
+unsafe fn double_quarter_round(mut data: [uint32x4_t; 4]) -> [uint32x4_t; 4] {
+    permute(&mut data);
+    rows_to_cols(&mut data);
+    permute(&mut data);
+    cols_to_rows(&mut data);
+
+    data
+}
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn rounds(data: [uint32x4_t; 4], rounds: usize) -> [uint32x4_t; 4] {
+    debug_assert!(rounds >= 2, "ChaCha needs at least one double round");
+
+    let mut state = data;
+
+    for _ in 0..(rounds / 2) {
+        state = double_quarter_round(state);
+    }
+
+    for i in 0..4 {
+        state[i] = vaddq_u32(state[i], data[i]);
+    }
+
+    state
+}
+
+// Four blocks at once: every vector holds one state word of four consecutive blocks, one
+// block per lane, so the quarter rounds need no shuffles between the column and diagonal
+// rounds. A single block leaves the three-cycle latency of each vector operation exposed,
+// four independent blocks hide it.
+const WIDE_BLOCKS: usize = 4;
+
+#[inline(always)]
+unsafe fn wide_quarter_round(v: &mut [uint32x4_t; 16], a: usize, b: usize, c: usize, d: usize) {
+    v[a] = vaddq_u32(v[a], v[b]);
+    v[d] = rotl16(veorq_u32(v[d], v[a]));
+    v[c] = vaddq_u32(v[c], v[d]);
+    v[b] = rotl::<12, 20>(veorq_u32(v[b], v[c]));
+    v[a] = vaddq_u32(v[a], v[b]);
+    v[d] = rotl::<8, 24>(veorq_u32(v[d], v[a]));
+    v[c] = vaddq_u32(v[c], v[d]);
+    v[b] = rotl::<7, 25>(veorq_u32(v[b], v[c]));
+}
+
+/// Transposes four vectors of four words, so lane `i` of every input becomes vector `i`
+/// of the output.
+#[inline(always)]
+unsafe fn transpose(r: &[uint32x4_t]) -> [uint32x4_t; 4] {
+    let t0 = vreinterpretq_u64_u32(vtrn1q_u32(r[0], r[1]));
+    let t1 = vreinterpretq_u64_u32(vtrn2q_u32(r[0], r[1]));
+    let t2 = vreinterpretq_u64_u32(vtrn1q_u32(r[2], r[3]));
+    let t3 = vreinterpretq_u64_u32(vtrn2q_u32(r[2], r[3]));
+
+    [
+        vreinterpretq_u32_u64(vtrn1q_u64(t0, t2)),
+        vreinterpretq_u32_u64(vtrn1q_u64(t1, t3)),
+        vreinterpretq_u32_u64(vtrn2q_u64(t0, t2)),
+        vreinterpretq_u32_u64(vtrn2q_u64(t1, t3)),
+    ]
+}
+
+/// Runs `rounds` rounds over four blocks in the word-per-vector layout and returns their
+/// keystream in order, four vectors per block.
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn wide_rounds(initial_state: &[uint32x4_t; 16], rounds: usize) -> [[uint32x4_t; 4]; 4] {
+    let mut v = *initial_state;
+
+    for _ in 0..(rounds / 2) {
+        wide_quarter_round(&mut v, 0, 4, 8, 12);
+        wide_quarter_round(&mut v, 1, 5, 9, 13);
+        wide_quarter_round(&mut v, 2, 6, 10, 14);
+        wide_quarter_round(&mut v, 3, 7, 11, 15);
+        wide_quarter_round(&mut v, 0, 5, 10, 15);
+        wide_quarter_round(&mut v, 1, 6, 11, 12);
+        wide_quarter_round(&mut v, 2, 7, 8, 13);
+        wide_quarter_round(&mut v, 3, 4, 9, 14);
+    }
+
+    for (word, initial) in v.iter_mut().zip(initial_state) {
+        *word = vaddq_u32(*word, *initial);
+    }
+
+    // row `r` of every block comes from the four words `4 * r..4 * r + 4`
+    let mut keystream = [[vdupq_n_u32(0); 4]; WIDE_BLOCKS];
+    for (r, words) in v.chunks_exact(4).enumerate() {
+        for (block, row) in keystream.iter_mut().zip(transpose(words)) {
+            block[r] = row;
+        }
+    }
+    v.zeroize();
+
+    keystream
+}
+
+/// XORs up to 64 bytes of `block` with `keystream`.
+#[inline(always)]
+unsafe fn xor_block(block: &mut [u8], keystream: &[uint32x4_t; 4]) {
+    for (chunk, keystream) in block.chunks_mut(16).zip(keystream) {
+        if chunk.len() == 16 {
+            let input = vld1q_u8(chunk.as_ptr());
+            let output = veorq_u8(input, vreinterpretq_u8_u32(*keystream));
+            vst1q_u8(chunk.as_mut_ptr(), output);
+            continue;
+        }
+
+        // XOR a partial last chunk through a buffer, so the load stays in bounds
+        let mut partial = [0u8; 16];
+        partial[..chunk.len()].copy_from_slice(chunk);
+
+        let output = veorq_u8(vld1q_u8(partial.as_ptr()), vreinterpretq_u8_u32(*keystream));
+        vst1q_u8(partial.as_mut_ptr(), output);
+
+        chunk.copy_from_slice(&partial[..chunk.len()]);
+    }
+}
+
+#[derive(ZeroizeOnDrop)]
+pub struct ChaCha {
+    state: [uint32x4_t; 3],
+    rounds: usize,
+}
+
+#[inline(always)]
+unsafe fn load_words(bytes: &[u8]) -> uint32x4_t {
+    vreinterpretq_u32_u8(vld1q_u8(bytes.as_ptr()))
+}
+
+impl ChaCha {
+    pub fn new(key: &[u8], rounds: Option<usize>) -> ChaCha {
+        unsafe {
+            ChaCha {
+                state: [
+                    vld1q_u32(SIGMA.as_ptr()),
+                    load_words(&key[..16]),
+                    load_words(&key[16..32]),
+                ],
+                rounds: rounds.unwrap_or(20),
+            }
+        }
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn _keystream(&self, nonce: uint32x4_t) -> [uint32x4_t; 4] {
+        rounds(
+            [self.state[0], self.state[1], self.state[2], nonce],
+            self.rounds,
+        )
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn _encrypt(&self, data: &mut [u8], nonce: &[u8], counter: u32) {
+        let nonce_block = [
+            counter,
+            from_le_bytes(&nonce[0..4]),
+            from_le_bytes(&nonce[4..8]),
+            from_le_bytes(&nonce[8..12]),
+        ];
+
+        let wide_len = data.len() / (WIDE_BLOCKS * 64) * WIDE_BLOCKS * 64;
+        let (wide, rest) = data.split_at_mut(wide_len);
+
+        if !wide.is_empty() {
+            let mut wide_state = self.wide_state(&nonce_block);
+            let step = vdupq_n_u32(WIDE_BLOCKS as u32);
+
+            for batch in wide.chunks_exact_mut(WIDE_BLOCKS * 64) {
+                let mut keystream = wide_rounds(&wide_state, self.rounds);
+                wide_state[12] = vaddq_u32(wide_state[12], step);
+
+                for (block, keystream) in batch.chunks_exact_mut(64).zip(&keystream) {
+                    xor_block(block, keystream);
+                }
+                keystream.zeroize();
+            }
+            wide_state.zeroize();
+        }
+
+        // fewer than four blocks are left, which go through the one-block layout
+        let done = (wide_len / 64) as u32;
+        let mut nonce = vld1q_u32(nonce_block.as_ptr());
+        nonce = vaddq_u32(nonce, vsetq_lane_u32::<0>(done, vdupq_n_u32(0)));
+        let one = vsetq_lane_u32::<0>(1, vdupq_n_u32(0));
+
+        for block in rest.chunks_mut(64) {
+            let mut keystream = self._keystream(nonce);
+            nonce = vaddq_u32(nonce, one);
+
+            xor_block(block, &keystream);
+            keystream.zeroize();
+        }
+    }
+
+    /// Builds the initial state of four blocks starting at `nonce_block[0]` in the
+    /// word-per-vector layout of [`wide_rounds`].
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn wide_state(&self, nonce_block: &[u32; 4]) -> [uint32x4_t; 16] {
+        let mut rows = [[0u32; 4]; 3];
+        for (row, state) in rows.iter_mut().zip(&self.state) {
+            vst1q_u32(row.as_mut_ptr(), *state);
+        }
+
+        let mut wide_state = [vdupq_n_u32(0); 16];
+        for (i, word) in wide_state[..12].iter_mut().enumerate() {
+            *word = vdupq_n_u32(rows[i / 4][i % 4]);
+        }
+        for (i, word) in wide_state[12..].iter_mut().enumerate() {
+            *word = vdupq_n_u32(nonce_block[i]);
+        }
+        let lanes = [0u32, 1, 2, 3];
+        wide_state[12] = vaddq_u32(wide_state[12], vld1q_u32(lanes.as_ptr()));
+        rows.zeroize();
+
+        wide_state
+    }
+}
+
+impl ChaCha {
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    pub fn key_words(&self) -> [u32; 8] {
+        let mut words = [0u32; 8];
+        unsafe {
+            vst1q_u32(words.as_mut_ptr(), self.state[1]);
+            vst1q_u32(words[4..].as_mut_ptr(), self.state[2]);
+        }
+
+        words
+    }
+
+    pub fn keystream(&self, nonce: &[u8], counter: u32) -> [u8; 64] {
+        let mut output = [0u8; 64];
+        self.apply_keystream(&mut output, nonce, counter);
+
+        output
+    }
+
+    pub fn apply_keystream(&self, data: &mut [u8], nonce: &[u8], counter: u32) {
+        unsafe { self._encrypt(data, nonce, counter) }
+    }
+}
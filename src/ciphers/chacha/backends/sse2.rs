@@ -4,6 +4,7 @@ use core::arch::x86::*;
 use core::arch::x86_64::*;
 
 use crate::utils::from_le_bytes;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 const SIGMA: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
 
@@ -63,9 +64,11 @@ unsafe fn double_quarter_round(mut data: [__m128i; 4]) -> [__m128i; 4] {
 #[inline]
 #[target_feature(enable = "sse2")]
 pub unsafe fn rounds(data: [__m128i; 4], rounds: usize, hchacha: bool) -> [__m128i; 4] {
-    let mut stuff = data.clone();
+    debug_assert!(rounds >= 2, "ChaCha needs at least one double round");
 
-    let original = stuff.clone();
+    let mut stuff = data;
+
+    let original = stuff;
 
     for _ in 0..(rounds / 2) {
         stuff = double_quarter_round(stuff);
@@ -85,25 +88,28 @@ pub unsafe fn rounds(data: [__m128i; 4], rounds: usize, hchacha: bool) -> [__m12
     stuff
 }
 
+#[derive(ZeroizeOnDrop)]
 pub struct ChaCha {
     state: [__m128i; 3],
     rounds: usize,
 }
 
-unsafe fn encrypt_block(block: &[u8], keystream: [__m128i; 4], ciphertext: &mut Vec<u8>) {
-    for i in 0..4 {
-        if i * 16 > block.len() {
-            break;
+unsafe fn encrypt_block(block: &mut [u8], keystream: [__m128i; 4]) {
+    for (i, chunk) in block.chunks_mut(16).enumerate() {
+        if chunk.len() == 16 {
+            let ptr = chunk.as_mut_ptr() as *mut __m128i;
+            _mm_storeu_si128(ptr, _mm_xor_si128(_mm_loadu_si128(ptr), keystream[i]));
+            continue;
         }
 
-        let plaintext_block = _mm_loadu_si128(block[i * 16..].as_ptr() as *const __m128i);
+        // copy a partial last chunk so the load and store stay within bounds
+        let mut partial = [0u8; 16];
+        partial[..chunk.len()].copy_from_slice(chunk);
 
-        let ciphertext_block = _mm_xor_si128(plaintext_block, keystream[i]);
+        let ptr = partial.as_mut_ptr() as *mut __m128i;
+        _mm_storeu_si128(ptr, _mm_xor_si128(_mm_loadu_si128(ptr), keystream[i]));
 
-        let mut output_block = [0u8; 16];
-        _mm_storeu_si128(output_block.as_mut_ptr() as *mut __m128i, ciphertext_block);
-
-        ciphertext.append(&mut output_block.to_vec());
+        chunk.copy_from_slice(&partial[..chunk.len()]);
     }
 }
 
@@ -129,9 +135,9 @@ impl ChaCha {
         )
     }
 
-    unsafe fn _encrypt(&self, plaintext: &[u8], nonce: &[u8]) -> Vec<u8> {
+    unsafe fn _encrypt(&self, data: &mut [u8], nonce: &[u8], counter: u32) {
         let nonce_block = [
-            1,
+            counter,
             from_le_bytes(&nonce[0..4]),
             from_le_bytes(&nonce[4..8]),
             from_le_bytes(&nonce[8..12]),
@@ -139,21 +145,32 @@ impl ChaCha {
 
         let mut nonce = _mm_loadu_si128(nonce_block.as_ptr() as *const __m128i);
 
-        let mut ciphertext: Vec<u8> = Vec::new();
-
-        for block in plaintext.chunks(64) {
-            let keystream = self._keystream(&nonce);
+        for block in data.chunks_mut(64) {
+            let mut keystream = self._keystream(&nonce);
 
             nonce = _mm_add_epi32(nonce, _mm_set_epi32(0, 0, 0, 1));
 
-            encrypt_block(block, keystream, &mut ciphertext);
+            encrypt_block(block, keystream);
+            keystream.zeroize();
         }
-
-        ciphertext[..plaintext.len()].to_vec()
     }
 }
 
 impl ChaCha {
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    pub fn key_words(&self) -> [u32; 8] {
+        let mut words = [0u32; 8];
+        unsafe {
+            _mm_storeu_si128(words.as_mut_ptr() as *mut __m128i, self.state[1]);
+            _mm_storeu_si128(words[4..].as_mut_ptr() as *mut __m128i, self.state[2]);
+        }
+
+        words
+    }
+
     pub fn keystream(&self, nonce: &[u8], counter: u32) -> [u8; 64] {
         unsafe {
             let nonce_block = [
@@ -165,20 +182,21 @@ impl ChaCha {
 
             let nonce = _mm_loadu_si128(nonce_block.as_ptr() as *const __m128i);
 
-            let ks = self._keystream(&nonce);
+            let mut ks = self._keystream(&nonce);
 
             let mut output = [0u8; 64];
 
             for (index, i) in ks.iter().enumerate() {
                 _mm_storeu_si128((output.as_mut_ptr() as *mut __m128i).add(index), *i);
             }
+            ks.zeroize();
 
             output
         }
     }
 
-    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8]) -> Vec<u8> {
-        unsafe { self._encrypt(plaintext, nonce) }
+    pub fn apply_keystream(&self, data: &mut [u8], nonce: &[u8], counter: u32) {
+        unsafe { self._encrypt(data, nonce, counter) }
     }
 }
 
@@ -191,7 +209,10 @@ pub fn hchacha(key: &[u8], nonce: &[u8], rounds: Option<usize>) -> [u8; 32] {
             _mm_loadu_si128(nonce.as_ptr() as *const __m128i),
         ];
 
-        for _ in 0..(rounds.unwrap_or(20) / 2) {
+        let rounds = rounds.unwrap_or(20);
+        debug_assert!(rounds >= 2, "ChaCha needs at least one double round");
+
+        for _ in 0..(rounds / 2) {
             state = double_quarter_round(state);
         }
 
@@ -200,6 +221,7 @@ pub fn hchacha(key: &[u8], nonce: &[u8], rounds: Option<usize>) -> [u8; 32] {
         _mm_storeu_si128(output.as_mut_ptr() as *mut __m128i, state[0]);
 
         _mm_storeu_si128(output[16..].as_mut_ptr() as *mut __m128i, state[3]);
+        state.zeroize();
 
         output
     }
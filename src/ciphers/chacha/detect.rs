@@ -1,16 +1,614 @@
-use cfg_if::cfg_if;
+use crate::ciphers::chacha::backends::fallback;
+#[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+use crate::ciphers::chacha::backends::neon;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use crate::ciphers::chacha::backends::{avx2, avx512, sse2};
+use crate::config::Config;
+use crate::errors::InvalidLength;
+#[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+use crate::utils::aarch64_feature_detected;
+use crate::utils::from_le_bytes;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use crate::utils::x86_feature_detected;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+use zeroize::Zeroize;
 
-cfg_if! {
-    if #[cfg(all(any(target_arch="x86", target_arch="x86_64"), target_feature="avx2"))] {
-        pub use crate::ciphers::chacha::backends::avx2::ChaCha;
-        pub use crate::ciphers::chacha::backends::sse2::hchacha;
+/// The ChaCha implementations, from fastest to slowest.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Backend {
+    /// Sixteen blocks at a time for long messages, and the AVX2 code below that.
+    Avx512,
+    Avx2,
+    Sse2,
+    Neon,
+    Fallback,
+}
+
+impl Backend {
+    /// Returns whether the running CPU supports this backend.
+    pub fn is_supported(self) -> bool {
+        match self {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Avx512 => x86_feature_detected!("avx512f", "avx2"),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Avx2 => x86_feature_detected!("avx2"),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Sse2 => x86_feature_detected!("sse2"),
+            #[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+            Backend::Neon => aarch64_feature_detected!("neon"),
+            Backend::Fallback => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Returns the name of the backend: `"avx512"`, `"avx2"`, `"sse2"`, `"neon"` or
+    /// `"portable"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Avx512 => "avx512",
+            Backend::Avx2 => "avx2",
+            Backend::Sse2 => "sse2",
+            Backend::Neon => "neon",
+            Backend::Fallback => "portable",
+        }
+    }
+}
+
+/// Detects the fastest backend supported by the running CPU.
+pub fn detect_backend() -> Backend {
+    [Backend::Avx512, Backend::Avx2, Backend::Sse2, Backend::Neon]
+        .into_iter()
+        .find(|backend| backend.is_supported())
+        .unwrap_or(Backend::Fallback)
+}
+
+/// Returns the backend used by [`ChaCha`] and [`hchacha`].
+///
+/// Detection runs once, on first use, and the result is cached for the lifetime of the
+/// process.
+#[cfg(feature = "std")]
+pub fn backend() -> Backend {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+    *BACKEND.get_or_init(detect_backend)
+}
+
+/// Returns the backend used by [`ChaCha`] and [`hchacha`].
+///
+/// Without the `std` feature the CPU cannot be queried, so this is the fastest backend
+/// the crate was compiled for, e.g. with `-C target-feature=+avx2`.
+#[cfg(not(feature = "std"))]
+pub fn backend() -> Backend {
+    detect_backend()
+}
+
+/// Returns the name of the backend used by [`ChaCha`], see [`Backend::name`].
+pub fn active_backend() -> &'static str {
+    backend().name()
+}
+
+/// The constant first row of the state, "expand 32-byte k".
+const SIGMA: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+enum Inner {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Avx512(avx512::ChaCha),
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Avx2(avx2::ChaCha),
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Sse2(sse2::ChaCha),
+    #[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+    Neon(neon::ChaCha),
+    Fallback(fallback::ChaCha),
+}
+
+macro_rules! dispatch {
+    ($inner:expr, $cipher:ident => $call:expr) => {
+        match $inner {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Inner::Avx512($cipher) => $call,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Inner::Avx2($cipher) => $call,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Inner::Sse2($cipher) => $call,
+            #[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+            Inner::Neon($cipher) => $call,
+            Inner::Fallback($cipher) => $call,
+        }
+    };
+}
+
+/// ChaCha running on the backend selected by [`backend`].
+pub struct ChaCha(Inner);
+
+impl ChaCha {
+    /// The size of a keystream block in bytes.
+    pub const BLOCK_SIZE: usize = 64;
+
+    /// `rounds` defaults to 20, see [`CHACHA8`], [`CHACHA12`] and [`CHACHA20`] for the
+    /// standard variants. Panics if `key` is not 32 bytes or `rounds` is odd or below
+    /// [`MIN_ROUNDS`].
+    pub fn new(key: &[u8], rounds: Option<usize>) -> ChaCha {
+        ChaCha::with_backend(key, rounds, backend()).unwrap()
+    }
+
+    /// Creates the cipher on a specific backend instead of the detected one, or returns
+    /// `None` if the CPU does not support it. All backends produce identical output.
+    ///
+    /// Panics if `key` is not 32 bytes or `rounds` is odd or below [`MIN_ROUNDS`].
+    pub fn with_backend(key: &[u8], rounds: Option<usize>, backend: Backend) -> Option<ChaCha> {
+        assert_eq!(key.len(), 32, "ChaCha keys are 32 bytes");
+        check_rounds(rounds);
+        if !backend.is_supported() {
+            return None;
+        }
+
+        Some(ChaCha(match backend {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Avx512 => Inner::Avx512(avx512::ChaCha::new(key, rounds)),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Avx2 => Inner::Avx2(avx2::ChaCha::new(key, rounds)),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Sse2 => Inner::Sse2(sse2::ChaCha::new(key, rounds)),
+            #[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+            Backend::Neon => Inner::Neon(neon::ChaCha::new(key, rounds)),
+            _ => Inner::Fallback(fallback::ChaCha::new(key, rounds)),
+        }))
+    }
+
+    /// Returns a cipher with the same key and backend that runs `rounds` rounds.
+    ///
+    /// Panics if `rounds` is odd or below [`MIN_ROUNDS`].
+    pub fn with_rounds(&self, rounds: usize) -> ChaCha {
+        check_rounds(Some(rounds));
+
+        let mut words = dispatch!(&self.0, cipher => cipher.key_words());
+        let mut key = [0u8; 32];
+        for (bytes, word) in key.chunks_exact_mut(4).zip(words.iter()) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        words.zeroize();
+
+        let rounds = Some(rounds);
+        let cipher = ChaCha(match &self.0 {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Inner::Avx512(_) => Inner::Avx512(avx512::ChaCha::new(&key, rounds)),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Inner::Avx2(_) => Inner::Avx2(avx2::ChaCha::new(&key, rounds)),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Inner::Sse2(_) => Inner::Sse2(sse2::ChaCha::new(&key, rounds)),
+            #[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+            Inner::Neon(_) => Inner::Neon(neon::ChaCha::new(&key, rounds)),
+            Inner::Fallback(_) => Inner::Fallback(fallback::ChaCha::new(&key, rounds)),
+        });
+        key.zeroize();
+
+        cipher
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8]) -> Vec<u8> {
+        self.encrypt_with_counter(plaintext, nonce, 1)
+    }
+
+    /// Decrypts `ciphertext`. ChaCha is a stream cipher, so this is the same keystream
+    /// XOR as [`ChaCha::encrypt`].
+    pub fn decrypt(&self, ciphertext: &[u8], nonce: &[u8]) -> Vec<u8> {
+        self.encrypt(ciphertext, nonce)
+    }
+
+    /// Encrypts `plaintext` with the keystream starting at block `counter`.
+    ///
+    /// Panics if the plaintext needs blocks past the end of the 32-bit counter, see
+    /// [`last_block_counter`].
+    pub fn encrypt_with_counter(&self, plaintext: &[u8], nonce: &[u8], counter: u32) -> Vec<u8> {
+        let mut ciphertext = Vec::with_capacity(plaintext.len());
+        self.encrypt_into(plaintext, nonce, counter, &mut ciphertext);
+
+        ciphertext
+    }
+
+    /// Like [`ChaCha::encrypt_with_counter`], but fails instead of panicking if the
+    /// plaintext needs blocks past the end of the 32-bit counter, counting from `counter`.
+    /// Nothing is encrypted in that case.
+    pub fn try_encrypt_with_counter(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        counter: u32,
+    ) -> Result<Vec<u8>, InvalidLength> {
+        last_block_counter(counter, plaintext.len() as u64)?;
+
+        Ok(self.encrypt_with_counter(plaintext, nonce, counter))
+    }
+
+    /// Like [`ChaCha::encrypt_with_counter`], but appends the output to `out`.
+    pub fn encrypt_into(&self, plaintext: &[u8], nonce: &[u8], counter: u32, out: &mut Vec<u8>) {
+        let start = out.len();
+        out.extend_from_slice(plaintext);
+
+        self.encrypt_in_place(&mut out[start..], nonce, counter)
+    }
+
+    /// XORs the keystream starting at block `counter` directly into `buffer`, without
+    /// allocating. As encryption and decryption are the same operation, this does both.
+    ///
+    /// Panics if the buffer needs blocks past the end of the 32-bit counter, see
+    /// [`last_block_counter`].
+    pub fn encrypt_in_place(&self, buffer: &mut [u8], nonce: &[u8], counter: u32) {
+        last_block_counter(counter, buffer.len() as u64)
+            .expect("plaintext is too long for the ChaCha block counter");
+
+        dispatch!(&self.0, cipher => cipher.apply_keystream(buffer, nonce, counter))
+    }
+
+    /// Encrypts `plaintext` with the keystream of `variant` starting at block `counter`.
+    ///
+    /// Unlike [`ChaCha::encrypt_with_counter`] this never panics or wraps around: it fails
+    /// if `nonce` is not [`Variant::nonce_length`] bytes or if the plaintext needs blocks
+    /// past the end of the variant's counter.
+    pub fn encrypt_with_variant(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        counter: u64,
+        variant: Variant,
+    ) -> Result<Vec<u8>, InvalidLength> {
+        variant.check(nonce, counter, plaintext.len() as u64)?;
+
+        let mut ciphertext = plaintext.to_vec();
+        self.apply_keystream_with_variant(&mut ciphertext, nonce, counter, variant)?;
+
+        Ok(ciphertext)
+    }
+
+    /// Like [`ChaCha::encrypt_with_variant`], but XORs the keystream into `buffer` in
+    /// place. The buffer is left untouched on failure.
+    pub fn apply_keystream_with_variant(
+        &self,
+        buffer: &mut [u8],
+        nonce: &[u8],
+        counter: u64,
+        variant: Variant,
+    ) -> Result<(), InvalidLength> {
+        variant.check(nonce, counter, buffer.len() as u64)?;
+
+        if variant == Variant::Ietf {
+            self.encrypt_in_place(buffer, nonce, counter as u32);
+            return Ok(());
+        }
+
+        // the last row is the 64-bit counter and then the nonce, so a block of the djb
+        // variant is a block of the IETF one with the high counter word as the first word
+        // of the nonce, and each run of 2^32 blocks is one IETF call
+        let mut ietf_nonce = [0u8; 12];
+        ietf_nonce[4..].copy_from_slice(nonce);
+
+        let mut counter = counter;
+        let mut rest = buffer;
+        while !rest.is_empty() {
+            let blocks = (1u64 << 32) - (counter & 0xffff_ffff);
+            let len = blocks.saturating_mul(64).min(rest.len() as u64) as usize;
+            let (run, tail) = rest.split_at_mut(len);
+
+            ietf_nonce[..4].copy_from_slice(&((counter >> 32) as u32).to_le_bytes());
+            self.encrypt_in_place(run, &ietf_nonce, counter as u32);
+
+            counter = counter.wrapping_add(blocks);
+            rest = tail;
+        }
+
+        Ok(())
+    }
+
+    /// XORs `buffer` in place with the keystream from byte `byte_offset` on, counted from
+    /// the start of block 0, so any byte range of a message can be encrypted or decrypted
+    /// without processing the bytes before it.
+    ///
+    /// The first block is `byte_offset / 64`, used from byte `byte_offset % 64` on. For
+    /// a message encrypted from block 1, as in ChaCha-Poly1305, add 64 to the offset.
+    ///
+    /// Panics if the range needs blocks past the end of the 32-bit counter.
+    pub fn apply_keystream_range(&self, buffer: &mut [u8], nonce: &[u8], byte_offset: u64) {
+        let counter = u32::try_from(byte_offset / 64)
+            .expect("offset is too large for the ChaCha block counter");
+        let skip = (byte_offset % 64) as usize;
+        last_block_counter(counter, (skip + buffer.len()) as u64)
+            .expect("range is too long for the ChaCha block counter");
+
+        if skip == 0 {
+            return self.encrypt_in_place(buffer, nonce, counter);
+        }
+
+        let (head, tail) = buffer.split_at_mut((64 - skip).min(buffer.len()));
+        let mut keystream = self.keystream(nonce, counter);
+        for (byte, key) in head.iter_mut().zip(&keystream[skip..]) {
+            *byte ^= key;
+        }
+        keystream.zeroize();
+
+        if !tail.is_empty() {
+            self.encrypt_in_place(tail, nonce, counter + 1);
+        }
     }
-    else if #[cfg(all(any(target_arch="x86", target_arch="x86_64"), target_feature="sse2"))] {
-        pub use crate::ciphers::chacha::backends::sse2::ChaCha;
-        pub use crate::ciphers::chacha::backends::sse2::hchacha;
+
+    /// Returns the 64 bytes of keystream starting at byte `byte_offset`, see
+    /// [`ChaCha::apply_keystream_range`]. Unless the offset is a multiple of 64 they span
+    /// two blocks.
+    pub fn keystream_at(&self, nonce: &[u8], byte_offset: u64) -> [u8; 64] {
+        let mut keystream = [0u8; 64];
+        self.apply_keystream_range(&mut keystream, nonce, byte_offset);
+
+        keystream
+    }
+
+    /// Lazily encrypts `plaintext` with the keystream starting at block `counter`,
+    /// yielding the ciphertext in pieces of `chunk_size` bytes. The last piece may be
+    /// shorter. `chunk_size` does not have to be a multiple of the block size; chunks
+    /// that start inside a block continue its keystream.
+    ///
+    /// Panics if `chunk_size` is zero or, before yielding anything, if the plaintext
+    /// needs blocks past the end of the 32-bit counter.
+    pub fn encrypt_chunks<'a>(
+        &'a self,
+        plaintext: &'a [u8],
+        nonce: &'a [u8],
+        counter: u32,
+        chunk_size: usize,
+    ) -> impl Iterator<Item = Vec<u8>> + 'a {
+        assert!(chunk_size > 0, "chunk size must be non-zero");
+        last_block_counter(counter, plaintext.len() as u64)
+            .expect("plaintext is too long for the ChaCha block counter");
+
+        plaintext
+            .chunks(chunk_size)
+            .enumerate()
+            .map(move |(i, chunk)| {
+                let offset = (i * chunk_size) as u64;
+                let block = counter as u64 + offset / 64;
+                let skip = (offset % 64) as usize;
+
+                let mut ciphertext = Vec::with_capacity(chunk.len());
+                let (head, tail) = match skip {
+                    0 => (&chunk[..0], chunk),
+                    _ => chunk.split_at((64 - skip).min(chunk.len())),
+                };
+
+                if !head.is_empty() {
+                    let mut keystream = self.keystream(nonce, block as u32);
+                    ciphertext.extend(head.iter().zip(&keystream[skip..]).map(|(a, b)| a ^ b));
+                    keystream.zeroize();
+                }
+
+                if !tail.is_empty() {
+                    let block = block + (skip > 0) as u64;
+                    self.encrypt_into(tail, nonce, block as u32, &mut ciphertext);
+                }
+
+                ciphertext
+            })
+    }
+
+    pub fn keystream(&self, nonce: &[u8], counter: u32) -> [u8; 64] {
+        dispatch!(&self.0, cipher => cipher.keystream(nonce, counter))
+    }
+
+    /// Returns `n` words of keystream starting at block `counter`, each read as a
+    /// little-endian `u32`. Serialising the words in little-endian order gives the
+    /// keystream bytes. Fails if `nonce` is not 12 bytes.
+    pub fn keystream_words(
+        &self,
+        nonce: &[u8],
+        counter: u32,
+        n: usize,
+    ) -> Result<Vec<u32>, InvalidLength> {
+        if nonce.len() != 12 {
+            return Err(InvalidLength);
+        }
+
+        let keystream = self.encrypt_with_counter(&vec![0u8; n * 4], nonce, counter);
+
+        Ok(keystream.chunks(4).map(from_le_bytes).collect())
+    }
+
+    pub fn rounds(&self) -> usize {
+        dispatch!(&self.0, cipher => cipher.rounds())
+    }
+
+    /// Exports the fixed rows of the state, for hardware offload engines that take a
+    /// pre-expanded ChaCha state instead of a key.
+    ///
+    /// The words are in state order, the first three rows of [`initial_state`]: words 0
+    /// to 3 are the constants and words 4 to 11 the key read as little-endian words. Only
+    /// row 3, the block counter and nonce, changes between blocks. The output contains
+    /// the key, so zeroize it once it has been handed over.
+    pub fn export_state(&self) -> [u32; 12] {
+        let mut key = dispatch!(&self.0, cipher => cipher.key_words());
+
+        let mut state = [0u32; 12];
+        state[..4].copy_from_slice(&SIGMA);
+        state[4..].copy_from_slice(&key);
+        key.zeroize();
+
+        state
     }
-    else {
-        pub use crate::ciphers::chacha::backends::fallback::*;
+
+    /// Computes [`hchacha`] of this cipher's key and `nonce` without needing the key.
+    ///
+    /// HChaCha outputs rows 0 and 3 of the state after the rounds, and the keystream
+    /// block is that state plus the initial state. As the initial rows 0 and 3 are the
+    /// constants and `nonce`, read as the counter and nonce of the block, subtracting them
+    /// again gives the HChaCha output.
+    pub fn hchacha(&self, nonce: &[u8; 16]) -> [u8; 32] {
+        let (block_nonce, counter) = split_iv(nonce);
+        let mut keystream = self.keystream(&block_nonce, counter);
+
+        let mut output = [0u8; 32];
+        for (i, (row, out)) in [0, 1, 2, 3, 12, 13, 14, 15]
+            .into_iter()
+            .zip(output.chunks_exact_mut(4))
+            .enumerate()
+        {
+            let initial = match i {
+                0..=3 => SIGMA[i],
+                _ => from_le_bytes(&nonce[(i - 4) * 4..]),
+            };
+            let word = from_le_bytes(&keystream[row * 4..]).wrapping_sub(initial);
+            out.copy_from_slice(&word.to_le_bytes());
+        }
+        keystream.zeroize();
+
+        output
+    }
+}
+
+/// Returns the block counter of the last block used to process `len` bytes starting at
+/// block `counter`.
+///
+/// The block offset is computed in 64 bits, so it is never truncated. Fails if any block
+/// would need a counter above `u32::MAX`, as the counter would otherwise wrap around and
+/// reuse keystream.
+pub fn last_block_counter(counter: u32, len: u64) -> Result<u32, InvalidLength> {
+    let blocks = len.div_ceil(64).max(1);
+
+    u32::try_from(counter as u64 + blocks - 1).map_err(|_| InvalidLength)
+}
+
+/// The layout of the block counter and nonce in the last row of the state.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Variant {
+    /// RFC 8439: a 32-bit block counter and a 12-byte nonce, so a nonce has 256 GiB of
+    /// keystream.
+    #[default]
+    Ietf,
+    /// Bernstein's original ChaCha, as in libsodium's `crypto_stream_chacha20`: a 64-bit
+    /// block counter and an 8-byte nonce, so the counter never runs out in practice.
+    Djb,
+}
+
+impl Variant {
+    /// Returns the length of the variant's nonces in bytes.
+    pub const fn nonce_length(self) -> usize {
+        match self {
+            Variant::Ietf => 12,
+            Variant::Djb => 8,
+        }
+    }
+
+    /// Fails if `nonce` has the wrong length, or if `len` bytes starting at block
+    /// `counter` need blocks past the end of the variant's counter.
+    fn check(self, nonce: &[u8], counter: u64, len: u64) -> Result<(), InvalidLength> {
+        if nonce.len() != self.nonce_length() {
+            return Err(InvalidLength);
+        }
+
+        match self {
+            Variant::Ietf => {
+                last_block_counter(u32::try_from(counter).map_err(|_| InvalidLength)?, len)?;
+            }
+            Variant::Djb => {
+                counter
+                    .checked_add(len.div_ceil(64).max(1) - 1)
+                    .ok_or(InvalidLength)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// ChaCha8, the fastest standard variant, as a `rounds` argument.
+pub const CHACHA8: Option<usize> = Some(8);
+/// ChaCha12, as a `rounds` argument.
+pub const CHACHA12: Option<usize> = Some(12);
+/// ChaCha20, the variant of RFC 8439 and the default when `rounds` is `None`.
+pub const CHACHA20: Option<usize> = Some(20);
+
+/// The fewest rounds a cipher can be created with. Fewer rounds are broken or close to it,
+/// so they are rejected rather than left to a typo.
+pub const MIN_ROUNDS: usize = 8;
+
+/// Panics if `rounds` is odd or below [`MIN_ROUNDS`]. ChaCha is only defined for an even
+/// number of rounds and the backends would silently round it down.
+pub(crate) fn check_rounds(rounds: Option<usize>) {
+    if let Some(rounds) = rounds {
+        assert!(
+            rounds % 2 == 0 && rounds >= MIN_ROUNDS,
+            "ChaCha needs an even number of rounds, at least {MIN_ROUNDS}, got {rounds}"
+        );
+    }
+}
+
+/// Derives a subkey from a 32 byte key and a 16 byte nonce, as used by XChaCha.
+///
+/// Fails if `key` is not 32 bytes or `nonce` is not 16 bytes. Panics if `rounds` is odd or
+/// below [`MIN_ROUNDS`].
+pub fn hchacha(key: &[u8], nonce: &[u8], rounds: Option<usize>) -> Result<[u8; 32], InvalidLength> {
+    check_rounds(rounds);
+    if key.len() != 32 || nonce.len() != 16 {
+        return Err(InvalidLength);
+    }
+
+    Ok(match backend() {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Backend::Avx512 | Backend::Avx2 | Backend::Sse2 => sse2::hchacha(key, nonce, rounds),
+        _ => fallback::hchacha(key, nonce, rounds),
+    })
+}
+
+/// Returns the ChaCha state matrix before any rounds are applied (RFC 8439 §2.3), as
+/// four rows of four words.
+///
+/// Row 0 holds the constants, rows 1 and 2 the key, and row 3 the block counter followed
+/// by the nonce. This is meant for checking the state setup against other
+/// implementations. Fails if `key` is not 32 bytes or `nonce` is not 12 bytes.
+pub fn initial_state(
+    key: &[u8],
+    nonce: &[u8],
+    counter: u32,
+) -> Result<[[u32; 4]; 4], InvalidLength> {
+    if key.len() != 32 || nonce.len() != 12 {
+        return Err(InvalidLength);
+    }
+
+    let word = |bytes: &[u8], i: usize| from_le_bytes(&bytes[i * 4..]);
+
+    Ok([
+        SIGMA,
+        [word(key, 0), word(key, 1), word(key, 2), word(key, 3)],
+        [word(key, 4), word(key, 5), word(key, 6), word(key, 7)],
+        [counter, word(nonce, 0), word(nonce, 1), word(nonce, 2)],
+    ])
+}
+
+/// Splits a combined 16-byte IV into a 12-byte nonce and a 32-bit block counter.
+///
+/// The IV follows the layout of the last row of the ChaCha state (RFC 8439 §2.3):
+/// bytes 0..4 are the block counter in little-endian order and bytes 4..16 are the nonce.
+/// This is the same layout used by e.g. OpenSSL's `EVP_chacha20`.
+pub fn split_iv(iv: &[u8; 16]) -> ([u8; 12], u32) {
+    let mut nonce = [0u8; 12];
+    nonce.copy_from_slice(&iv[4..]);
+
+    (nonce, from_le_bytes(&iv[..4]))
+}
+
+impl ChaCha {
+    /// Encrypts with a combined 16-byte IV, see [`split_iv`] for the layout.
+    pub fn encrypt_iv(&self, plaintext: &[u8], iv: &[u8; 16]) -> Vec<u8> {
+        let (nonce, counter) = split_iv(iv);
+        self.encrypt_with_counter(plaintext, &nonce, counter)
+    }
+
+    pub fn config(&self) -> Config {
+        Config {
+            algorithm: "ChaCha",
+            rounds: self.rounds(),
+            nonce_len: 12,
+            tag_len: 0,
+        }
     }
 }
 
@@ -18,10 +616,24 @@ pub fn encrypt(key: &[u8], plaintext: &[u8], nonce: &[u8], rounds: Option<usize>
     ChaCha::new(key, rounds).encrypt(plaintext, nonce)
 }
 
-pub fn decrypt(key: &[u8], plaintext: &[u8], nonce: &[u8], rounds: Option<usize>) -> Vec<u8> {
-    ChaCha::new(key, rounds).encrypt(plaintext, nonce)
+pub fn decrypt(key: &[u8], ciphertext: &[u8], nonce: &[u8], rounds: Option<usize>) -> Vec<u8> {
+    ChaCha::new(key, rounds).decrypt(ciphertext, nonce)
 }
 
 pub fn keystream(key: &[u8], nonce: &[u8], counter: u32, rounds: Option<usize>) -> [u8; 64] {
     ChaCha::new(key, rounds).keystream(nonce, counter)
 }
+
+pub fn keystream_words(
+    key: &[u8],
+    nonce: &[u8],
+    counter: u32,
+    n: usize,
+    rounds: Option<usize>,
+) -> Result<Vec<u32>, InvalidLength> {
+    ChaCha::new(key, rounds).keystream_words(nonce, counter, n)
+}
+
+pub fn encrypt_iv(key: &[u8], plaintext: &[u8], iv: &[u8; 16], rounds: Option<usize>) -> Vec<u8> {
+    ChaCha::new(key, rounds).encrypt_iv(plaintext, iv)
+}
@@ -0,0 +1,35 @@
+use crate::ciphers::chacha::{hchacha, ChaCha};
+use alloc::vec;
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+/// Expands the pseudorandom key `prk` into `length` bytes of key material bound to `info`.
+///
+/// This is a ChaCha-based construction, not HKDF-HMAC, and its output will not match
+/// HKDF-Expand. `info` is absorbed by chaining HChaCha over 16 byte blocks, starting
+/// with a block holding the length of `info`, and finally the output is the ChaCha20
+/// keystream under the resulting key with an all-zero nonce.
+///
+/// `prk` must already be uniformly random, e.g. the output of a key exchange that has
+/// been hashed.
+///
+/// Panics if `length` exceeds 256 GiB, the maximum keystream length for one nonce.
+pub fn expand(prk: &[u8; 32], info: &[u8], length: usize) -> Vec<u8> {
+    assert!(length as u64 <= 64 << 32, "output length too large");
+
+    let mut block = [0u8; 16];
+    block[..8].copy_from_slice(&(info.len() as u64).to_le_bytes());
+    let mut key = hchacha(prk, &block, None).unwrap();
+
+    for chunk in info.chunks(16) {
+        let mut block = [0u8; 16];
+        block[..chunk.len()].copy_from_slice(chunk);
+
+        key = hchacha(&key, &block, None).unwrap();
+    }
+
+    let cipher = ChaCha::new(&key, None);
+    key.zeroize();
+
+    cipher.encrypt_with_counter(&vec![0u8; length], &[0u8; 12], 0)
+}
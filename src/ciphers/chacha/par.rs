@@ -0,0 +1,71 @@
+//! Multi-threaded ChaCha encryption of large buffers.
+//!
+//! Only built with the `rayon` feature. As every block is generated from its own counter,
+//! the buffer is split into segments that are encrypted on the rayon thread pool, each
+//! from the counter its first block would have had in a serial pass.
+use crate::ciphers::chacha::{last_block_counter, ChaCha};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+
+/// The size of the segments handed to the thread pool. Must be a multiple of 64, so every
+/// segment starts on a block boundary.
+const SEGMENT_SIZE: usize = 64 * 1024;
+
+impl ChaCha {
+    /// Like [`ChaCha::encrypt`], but spreads the work over the rayon thread pool. The
+    /// output is identical.
+    pub fn encrypt_par(&self, plaintext: &[u8], nonce: &[u8]) -> Vec<u8> {
+        self.encrypt_par_with_counter(plaintext, nonce, 1)
+    }
+
+    /// Like [`ChaCha::encrypt_par`], but on a thread pool of its own with `threads`
+    /// threads instead of the global one. With one thread this is [`ChaCha::encrypt`], and
+    /// zero picks rayon's default, one thread per core.
+    ///
+    /// Panics if the thread pool cannot be created.
+    pub fn encrypt_with_threads(&self, plaintext: &[u8], nonce: &[u8], threads: usize) -> Vec<u8> {
+        if threads == 1 {
+            return self.encrypt(plaintext, nonce);
+        }
+
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("failed to create the thread pool")
+            .install(|| self.encrypt_par(plaintext, nonce))
+    }
+
+    /// Like [`ChaCha::encrypt_with_counter`], but spreads the work over the rayon thread
+    /// pool. The output is identical.
+    ///
+    /// Panics if the plaintext needs blocks past the end of the 32-bit counter.
+    pub fn encrypt_par_with_counter(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        counter: u32,
+    ) -> Vec<u8> {
+        let mut ciphertext = plaintext.to_vec();
+        self.encrypt_in_place_par(&mut ciphertext, nonce, counter);
+
+        ciphertext
+    }
+
+    /// Like [`ChaCha::encrypt_in_place`], but spreads the work over the rayon thread
+    /// pool.
+    ///
+    /// Panics if the buffer needs blocks past the end of the 32-bit counter.
+    pub fn encrypt_in_place_par(&self, buffer: &mut [u8], nonce: &[u8], counter: u32) {
+        // checked up front, so the counters of the segments below cannot overflow
+        last_block_counter(counter, buffer.len() as u64)
+            .expect("plaintext is too long for the ChaCha block counter");
+
+        buffer
+            .par_chunks_mut(SEGMENT_SIZE)
+            .enumerate()
+            .for_each(|(i, segment)| {
+                let blocks = (i * SEGMENT_SIZE / 64) as u32;
+                self.encrypt_in_place(segment, nonce, counter + blocks);
+            });
+    }
+}
@@ -0,0 +1,117 @@
+use crate::ciphers::chacha::{expand, ChaCha};
+use zeroize::ZeroizeOnDrop;
+
+/// A deterministic random number generator built on the ChaCha20 keystream.
+///
+/// The seed is used as the key, and the output is the keystream with a 64-bit block
+/// counter spread over the block counter and the first nonce word, as in the original
+/// ChaCha design. The same seed always produces the same output, which makes this useful
+/// for reproducible tests; seed it from the OS for anything else.
+#[derive(ZeroizeOnDrop)]
+pub struct ChaCha20Rng {
+    key: [u8; 32],
+    #[zeroize(skip)]
+    cipher: ChaCha,
+    counter: u64,
+    block: [u8; 64],
+    offset: usize,
+}
+
+impl ChaCha20Rng {
+    pub fn from_seed(seed: &[u8; 32]) -> ChaCha20Rng {
+        ChaCha20Rng {
+            key: *seed,
+            cipher: ChaCha::new(seed, None),
+            counter: 0,
+            block: [0u8; 64],
+            offset: 64,
+        }
+    }
+
+    /// Mixes `entropy` into the generator.
+    ///
+    /// The new key is derived with [`expand`] from the current key, using `entropy` as the
+    /// info input, and the keystream restarts at block 0 under that key. Any buffered
+    /// output is discarded. The output after a reseed depends on both the previous state
+    /// and `entropy`, so a generator stays unpredictable as long as either one is.
+    pub fn reseed(&mut self, entropy: &[u8]) {
+        let key = expand(&self.key, entropy, 32);
+        self.key.copy_from_slice(&key);
+        self.cipher = ChaCha::new(&self.key, None);
+        self.counter = 0;
+        self.offset = 64;
+    }
+
+    /// Moves the generator to the start of keystream block `block`, discarding any
+    /// buffered output. Any part of the output can be reached this way without
+    /// generating what comes before it.
+    pub fn seek(&mut self, block: u64) {
+        self.counter = block;
+        self.offset = 64;
+    }
+
+    /// Returns the next 4 bytes of output as a little-endian integer.
+    pub fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+
+        u32::from_le_bytes(bytes)
+    }
+
+    /// Returns the next 8 bytes of output as a little-endian integer.
+    pub fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Fills `dest` with the next bytes of output.
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            if self.offset == 64 {
+                let mut nonce = [0u8; 12];
+                nonce[..4].copy_from_slice(&((self.counter >> 32) as u32).to_le_bytes());
+
+                self.block = self.cipher.keystream(&nonce, self.counter as u32);
+                self.counter = self.counter.wrapping_add(1);
+                self.offset = 0;
+            }
+
+            *byte = self.block[self.offset];
+            self.offset += 1;
+        }
+    }
+}
+
+#[cfg(feature = "rand_core")]
+impl rand_core::RngCore for ChaCha20Rng {
+    fn next_u32(&mut self) -> u32 {
+        ChaCha20Rng::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        ChaCha20Rng::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        ChaCha20Rng::fill_bytes(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        ChaCha20Rng::fill_bytes(self, dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rand_core")]
+impl rand_core::CryptoRng for ChaCha20Rng {}
+
+#[cfg(feature = "rand_core")]
+impl rand_core::SeedableRng for ChaCha20Rng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: [u8; 32]) -> ChaCha20Rng {
+        ChaCha20Rng::from_seed(&seed)
+    }
+}
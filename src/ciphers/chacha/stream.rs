@@ -0,0 +1,117 @@
+use crate::ciphers::chacha::{split_iv, ChaCha};
+use alloc::vec::Vec;
+use zeroize::ZeroizeOnDrop;
+
+/// A stateful ChaCha keystream which can be applied to data in pieces.
+///
+/// Keystream bytes left over from a partial block are kept for the next call, so applying
+/// the keystream to `a` and then to `b` is the same as applying it to `a || b` at once.
+/// OpenSSL's `EVP_chacha20` keeps leftover keystream across `EVP_EncryptUpdate` calls the
+/// same way, so data encrypted there in pieces can be decrypted here in any other pieces.
+pub struct ChaChaStream {
+    cipher: ChaCha,
+    position: KeystreamPosition,
+}
+
+impl ChaChaStream {
+    pub fn new(key: &[u8], nonce: &[u8], counter: u32, rounds: Option<usize>) -> ChaChaStream {
+        ChaChaStream {
+            cipher: ChaCha::new(key, rounds),
+            position: KeystreamPosition::new(nonce, counter),
+        }
+    }
+
+    /// Starts the stream from a combined 16-byte IV, see [`split_iv`] for the layout.
+    pub fn from_iv(key: &[u8], iv: &[u8; 16], rounds: Option<usize>) -> ChaChaStream {
+        let (nonce, counter) = split_iv(iv);
+        ChaChaStream::new(key, &nonce, counter, rounds)
+    }
+
+    /// Returns how many bytes of the current keystream block are left before the next
+    /// block has to be generated and the counter advances.
+    pub fn keystream_remaining(&self) -> usize {
+        self.position.remaining()
+    }
+
+    /// Moves the stream to the start of block `counter`, discarding any keystream left
+    /// over from the current block. Blocks are independent, so the keystream can be
+    /// applied at any position without generating the blocks before it.
+    pub fn seek(&mut self, counter: u32) {
+        self.position.seek(counter);
+    }
+
+    /// XORs the keystream into `data`, continuing where the previous call stopped.
+    pub fn apply_keystream(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(data.len());
+        self.position.apply(&self.cipher, data, &mut output);
+
+        output
+    }
+}
+
+/// The position in a ChaCha keystream, with the unused rest of the current block, kept
+/// apart from the cipher so the cipher can be borrowed.
+#[derive(ZeroizeOnDrop)]
+pub(crate) struct KeystreamPosition {
+    nonce: [u8; 12],
+    counter: u32,
+    block: [u8; 64],
+    offset: usize,
+}
+
+impl KeystreamPosition {
+    /// Starts at the beginning of block `counter`.
+    pub(crate) fn new(nonce: &[u8], counter: u32) -> KeystreamPosition {
+        let mut stream_nonce = [0u8; 12];
+        stream_nonce.copy_from_slice(&nonce[..12]);
+
+        KeystreamPosition {
+            nonce: stream_nonce,
+            counter,
+            block: [0u8; 64],
+            offset: 64,
+        }
+    }
+
+    pub(crate) fn seek(&mut self, counter: u32) {
+        self.counter = counter;
+        self.offset = 64;
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        64 - self.offset
+    }
+
+    /// XORs the keystream of `cipher` into `data`, appending the result to `output`.
+    ///
+    /// The leftover bytes of the current block are used first, whole blocks go through
+    /// the backend in one call, and a trailing partial block is generated and kept for
+    /// the next call.
+    pub(crate) fn apply(&mut self, cipher: &ChaCha, data: &[u8], output: &mut Vec<u8>) {
+        let leftover = self.remaining().min(data.len());
+        for (i, j) in data[..leftover].iter().zip(&self.block[self.offset..]) {
+            output.push(i ^ j);
+        }
+        self.offset += leftover;
+
+        let data = &data[leftover..];
+        let full_blocks = data.len() / 64;
+
+        if full_blocks > 0 {
+            cipher.encrypt_into(&data[..full_blocks * 64], &self.nonce, self.counter, output);
+            self.counter = self.counter.wrapping_add(full_blocks as u32);
+        }
+
+        let tail = &data[full_blocks * 64..];
+
+        if !tail.is_empty() {
+            self.block = cipher.keystream(&self.nonce, self.counter);
+            self.counter = self.counter.wrapping_add(1);
+
+            for (i, j) in tail.iter().zip(self.block.iter()) {
+                output.push(i ^ j);
+            }
+            self.offset = tail.len();
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Intermediate ChaCha states for comparing against reference implementations.
+//!
+//! Only built with the `trace` feature. The traces contain the key, so this is meant for
+//! test and debugging builds.
+use crate::ciphers::chacha::ChaCha;
+use crate::utils::from_le_bytes;
+
+/// The states one block went through, as printed in the worked example of RFC 8439
+/// §2.3.2.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BlockTrace {
+    pub counter: u32,
+    /// The state before the rounds, see [`initial_state`](super::initial_state).
+    pub initial_state: [[u32; 4]; 4],
+    /// The state after the rounds, before the initial state is added back in.
+    pub rounds_state: [[u32; 4]; 4],
+    /// The serialized sum of both states, which is XORed into the block.
+    pub keystream: [u8; 64],
+}
+
+impl ChaCha {
+    /// Like [`ChaCha::encrypt_with_counter`], but also returns the states of every
+    /// block, starting with the one at `counter`.
+    pub fn encrypt_traced(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        counter: u32,
+    ) -> (Vec<u8>, Vec<BlockTrace>) {
+        let ciphertext = self.encrypt_with_counter(plaintext, nonce, counter);
+        let state = self.export_state();
+
+        let traces = (0..plaintext.len().div_ceil(64))
+            .map(|i| {
+                let counter = counter.wrapping_add(i as u32);
+                let nonce_row = [
+                    counter,
+                    from_le_bytes(&nonce[0..4]),
+                    from_le_bytes(&nonce[4..8]),
+                    from_le_bytes(&nonce[8..12]),
+                ];
+                let initial_state: [[u32; 4]; 4] = core::array::from_fn(|row| match row {
+                    3 => nonce_row,
+                    _ => core::array::from_fn(|column| state[row * 4 + column]),
+                });
+
+                // the keystream is the rounds state plus the initial state, word by word
+                let keystream = self.keystream(nonce, counter);
+                let rounds_state = core::array::from_fn(|row| {
+                    core::array::from_fn(|column| {
+                        let i = row * 4 + column;
+                        from_le_bytes(&keystream[i * 4..]).wrapping_sub(initial_state[row][column])
+                    })
+                });
+
+                BlockTrace {
+                    counter,
+                    initial_state,
+                    rounds_state,
+                    keystream,
+                }
+            })
+            .collect();
+
+        (ciphertext, traces)
+    }
+}
@@ -0,0 +1,10 @@
+/// A description of how a cipher is configured, for logging and diagnostics.
+///
+/// It deliberately never contains any key material.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Config {
+    pub algorithm: &'static str,
+    pub rounds: usize,
+    pub nonce_len: usize,
+    pub tag_len: usize,
+}
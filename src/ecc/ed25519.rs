@@ -1,17 +1,18 @@
 // adapted from the rust-crypto ed25519 implementation
 
 use crate::ecc::ge::{ge_scalarmult_base, sc_muladd, sc_reduce, GeP2, GeP3};
-use crate::utils::const_time_eq;
+use crate::hashes::sha2::Sha512;
+use crate::utils::constant_time::ct_eq;
+use core::error::Error;
 use core::fmt::{Debug, Display};
+#[cfg(feature = "getrandom")]
 use getrandom::getrandom;
-use sha2::{Digest, Sha512};
-use std::error::Error;
 
 #[derive(Debug)]
 pub struct InvalidKey;
 
 impl Display for InvalidKey {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "This key is an invalid size!")
     }
 }
@@ -51,7 +52,7 @@ impl SigningKey {
         let az: [u8; 64] = {
             let mut hasher = Sha512::new();
             hasher.update(&secret[0..32]);
-            let mut hash_output: [u8; 64] = hasher.finalize().into();
+            let mut hash_output: [u8; 64] = hasher.finalize();
             hash_output[0] &= 248;
             hash_output[31] &= 63;
             hash_output[31] |= 64;
@@ -73,7 +74,7 @@ impl SigningKey {
         let mut secret: [u8; 64] = {
             let mut hasher = Sha512::new();
             hasher.update(seed);
-            let mut hash_output: [u8; 64] = hasher.finalize().into();
+            let mut hash_output: [u8; 64] = hasher.finalize();
             hash_output[0] &= 248;
             hash_output[31] &= 63;
             hash_output[31] |= 64;
@@ -90,6 +91,7 @@ impl SigningKey {
     }
 
     /// A constructor that randomly generates a secret key
+    #[cfg(feature = "getrandom")]
     pub fn generate() -> Result<SigningKey, InvalidKey> {
         let mut seed = [0u8; 32];
         let result = getrandom(&mut seed);
@@ -100,8 +102,8 @@ impl SigningKey {
 
         let mut secret: [u8; 64] = {
             let mut hasher = Sha512::new();
-            hasher.update(seed);
-            let mut hash_output: [u8; 64] = hasher.finalize().into();
+            hasher.update(&seed);
+            let mut hash_output: [u8; 64] = hasher.finalize();
             hash_output[0] &= 248;
             hash_output[31] &= 63;
             hash_output[31] |= 64;
@@ -110,16 +112,16 @@ impl SigningKey {
 
         let a = ge_scalarmult_base(&secret[0..32]);
         let public_key = a.to_bytes();
-        for (dest, src) in (&mut secret[32..64]).iter_mut().zip(public_key.iter()) {
+        for (dest, src) in secret[32..64].iter_mut().zip(public_key.iter()) {
             *dest = *src;
         }
-        for (dest, src) in (&mut secret[0..32]).iter_mut().zip(seed.iter()) {
+        for (dest, src) in secret[0..32].iter_mut().zip(seed.iter()) {
             *dest = *src;
         }
         let mut secret: [u8; 64] = {
             let mut hasher = Sha512::new();
-            hasher.update(seed);
-            let mut hash_output: [u8; 64] = hasher.finalize().into();
+            hasher.update(&seed);
+            let mut hash_output: [u8; 64] = hasher.finalize();
             hash_output[0] &= 248;
             hash_output[31] &= 63;
             hash_output[31] |= 64;
@@ -128,10 +130,10 @@ impl SigningKey {
 
         let a = ge_scalarmult_base(&secret[0..32]);
         let public_key = a.to_bytes();
-        for (dest, src) in (&mut secret[32..64]).iter_mut().zip(public_key.iter()) {
+        for (dest, src) in secret[32..64].iter_mut().zip(public_key.iter()) {
             *dest = *src;
         }
-        for (dest, src) in (&mut secret[0..32]).iter_mut().zip(seed.iter()) {
+        for (dest, src) in secret[0..32].iter_mut().zip(seed.iter()) {
             *dest = *src;
         }
 
@@ -149,7 +151,7 @@ impl SigningKey {
             let mut hasher = Sha512::new();
             hasher.update(&self.az[32..64]);
             hasher.update(msg);
-            let mut hash_output: [u8; 64] = hasher.finalize().into();
+            let mut hash_output: [u8; 64] = hasher.finalize();
             sc_reduce(&mut hash_output[0..64]);
             hash_output
         };
@@ -163,7 +165,7 @@ impl SigningKey {
             let mut hasher = Sha512::new();
             hasher.update(signature.as_ref());
             hasher.update(msg);
-            let mut hram: [u8; 64] = hasher.finalize().into();
+            let mut hram: [u8; 64] = hasher.finalize();
             sc_reduce(&mut hram);
             sc_muladd(
                 &mut signature[32..64],
@@ -218,14 +220,31 @@ impl VerifyingKey {
 
         let mut hasher = Sha512::new();
         hasher.update(&signature[0..32]);
-        hasher.update(self.public);
+        hasher.update(&self.public);
         hasher.update(message);
-        let mut hash: [u8; 64] = hasher.finalize().into();
+        let mut hash: [u8; 64] = hasher.finalize();
         sc_reduce(&mut hash);
 
         let r = GeP2::double_scalarmult_vartime(hash.as_ref(), a, &signature[32..64]);
         let rcheck = r.to_bytes();
 
-        const_time_eq(rcheck.as_ref(), &signature[0..32])
+        ct_eq(rcheck.as_ref(), &signature[0..32])
+    }
+}
+
+/// Verifies a batch of signatures, `signatures[i]` over `messages[i]` by `keys[i]`.
+///
+/// Returns `true` only if every signature is valid and the three slices have the same
+/// length. Each signature is checked on its own, so this is no faster than calling
+/// [`VerifyingKey::verify`] in a loop, and it does not tell which signature failed.
+pub fn verify_batch(messages: &[&[u8]], signatures: &[&[u8]], keys: &[VerifyingKey]) -> bool {
+    if messages.len() != signatures.len() || messages.len() != keys.len() {
+        return false;
     }
+
+    messages
+        .iter()
+        .zip(signatures)
+        .zip(keys)
+        .all(|((message, signature), key)| key.verify(message, signature))
 }
@@ -1,6 +1,6 @@
-use crate::utils::const_time_eq;
+use crate::utils::constant_time::ct_eq;
 use core::ops::{Add, Index, IndexMut, Mul, Sub};
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use zeroize::Zeroize;
 
 fn load3(s: &[u8]) -> i64 {
     let mut result = s[0] as u64;
@@ -93,8 +93,8 @@ impl FieldElement {
         ])
     }
 
-    pub fn to_bytes(&self) -> [u8; 32] {
-        let mut h = self.0.clone();
+    pub fn to_bytes(self) -> [u8; 32] {
+        let mut h = self.0;
 
         let mut q = (19 * h[9] + (1 << 24)) >> 25;
 
@@ -116,7 +116,7 @@ impl FieldElement {
         }
 
         [
-            (h[0] >> 0) as u8,
+            h[0] as u8,
             (h[0] >> 8) as u8,
             (h[0] >> 16) as u8,
             ((h[0] >> 24) | (h[1] << 2)) as u8,
@@ -132,7 +132,7 @@ impl FieldElement {
             (h[4] >> 2) as u8,
             (h[4] >> 10) as u8,
             (h[4] >> 18) as u8,
-            (h[5] >> 0) as u8,
+            h[5] as u8,
             (h[5] >> 8) as u8,
             (h[5] >> 16) as u8,
             ((h[5] >> 24) | (h[6] << 1)) as u8,
@@ -152,8 +152,8 @@ impl FieldElement {
     }
 
     pub fn swap(&mut self, other: &mut FieldElement, swap: i32) {
-        let mut f = self.0.clone();
-        let mut g = other.0.clone();
+        let mut f = self.0;
+        let mut g = other.0;
         let mut x = [0i32; 10];
 
         for i in 0..10 {
@@ -175,8 +175,8 @@ impl FieldElement {
     }
 
     pub fn mov(&self, other: FieldElement) -> FieldElement {
-        let f = self.0.clone();
-        let mut g = other.0.clone();
+        let f = self.0;
+        let mut g = other.0;
         let mut x = [0i32; 10];
 
         for i in 0..10 {
@@ -196,14 +196,14 @@ impl FieldElement {
         let b = -swap;
 
         for i in 0..10 {
-            x[i] = (&self)[i] ^ (&other)[i];
+            x[i] = self[i] ^ other[i];
             x[i] &= b;
         }
 
         let mut output = FieldElement::zero();
 
         for i in 0..10 {
-            output[i] = (&self)[i] ^ (&other)[i];
+            output[i] = self[i] ^ other[i];
         }
 
         *self = output
@@ -299,15 +299,7 @@ impl FieldElement {
         let mut h8 = f0f8_2 + f1f7_4 + f2f6_2 + f3f5_4 + f4f4 + f9f9_38;
         let mut h9 = f0f9_2 + f1f8_2 + f2f7_2 + f3f6_2 + f4f5_2;
         let mut carry0: i64;
-        let carry1: i64;
-        let carry2: i64;
-        let carry3: i64;
         let mut carry4: i64;
-        let carry5: i64;
-        let carry6: i64;
-        let carry7: i64;
-        let carry8: i64;
-        let carry9: i64;
 
         h0 += h0;
         h1 += h1;
@@ -327,35 +319,35 @@ impl FieldElement {
         h5 += carry4;
         h4 -= carry4 << 26;
 
-        carry1 = (h1 + (1 << 24)) >> 25;
+        let carry1: i64 = (h1 + (1 << 24)) >> 25;
         h2 += carry1;
         h1 -= carry1 << 25;
-        carry5 = (h5 + (1 << 24)) >> 25;
+        let carry5: i64 = (h5 + (1 << 24)) >> 25;
         h6 += carry5;
         h5 -= carry5 << 25;
 
-        carry2 = (h2 + (1 << 25)) >> 26;
+        let carry2: i64 = (h2 + (1 << 25)) >> 26;
         h3 += carry2;
         h2 -= carry2 << 26;
-        carry6 = (h6 + (1 << 25)) >> 26;
+        let carry6: i64 = (h6 + (1 << 25)) >> 26;
         h7 += carry6;
         h6 -= carry6 << 26;
 
-        carry3 = (h3 + (1 << 24)) >> 25;
+        let carry3: i64 = (h3 + (1 << 24)) >> 25;
         h4 += carry3;
         h3 -= carry3 << 25;
-        carry7 = (h7 + (1 << 24)) >> 25;
+        let carry7: i64 = (h7 + (1 << 24)) >> 25;
         h8 += carry7;
         h7 -= carry7 << 25;
 
         carry4 = (h4 + (1 << 25)) >> 26;
         h5 += carry4;
         h4 -= carry4 << 26;
-        carry8 = (h8 + (1 << 25)) >> 26;
+        let carry8: i64 = (h8 + (1 << 25)) >> 26;
         h9 += carry8;
         h8 -= carry8 << 26;
 
-        carry9 = (h9 + (1 << 24)) >> 25;
+        let carry9: i64 = (h9 + (1 << 24)) >> 25;
         h0 += carry9 * 19;
         h9 -= carry9 << 25;
 
@@ -466,7 +458,7 @@ impl FieldElement {
 
         for i in 0..10 {
             let shift = if i & 1 == 1 { 24 } else { 25 };
-            let carry = (h[i] + (1 << shift.clone())) >> shift.clone() + 1;
+            let carry = (h[i] + (1 << shift)) >> (shift + 1);
 
             let add = if i == 9 { carry * 19 } else { carry };
 
@@ -491,57 +483,57 @@ impl FieldElement {
         let mut t0 = self.square();
         let mut t1 = t0.square();
         t1 = t1.square();
-        t1 = &self * &t1;
-        t0 = &t0 * &t1;
+        t1 = self * t1;
+        t0 = t0 * t1;
         let mut t2 = t0.square();
-        t1 = &t1 * &t2;
+        t1 = t1 * t2;
         t2 = t1.square();
 
         for _ in 1..5 {
             t2 = t2.square();
         }
 
-        t1 = &t2 * &t1;
+        t1 = t2 * t1;
         t2 = t1.square();
 
         for _ in 1..10 {
             t2 = t2.square();
         }
 
-        t2 = &t2 * &t1;
+        t2 = t2 * t1;
         let mut t3 = t2.square();
 
         for _ in 1..20 {
             t3 = t3.square();
         }
 
-        t2 = &t3 * &t2;
+        t2 = t3 * t2;
 
         for _ in 1..11 {
             t2 = t2.square();
         }
 
-        t1 = &t2 * &t1;
+        t1 = t2 * t1;
         t2 = t1.square();
 
         for _ in 1..50 {
             t2 = t2.square();
         }
 
-        t2 = &t2 * &t1;
+        t2 = t2 * t1;
         t3 = t2.square();
 
         for _ in 1..100 {
             t3 = t3.square();
         }
 
-        t2 = &t3 * &t2;
+        t2 = t3 * t2;
 
         for _ in 1..51 {
             t2 = t2.square();
         }
 
-        t1 = &t2 * &t1;
+        t1 = t2 * t1;
 
         for _ in 1..6 {
             t1 = t1.square();
@@ -551,7 +543,7 @@ impl FieldElement {
     }
 
     pub fn mul32(&self, n: i64) -> FieldElement {
-        let f = self.0.clone();
+        let f = self.0;
 
         let mut h = [0i64; 10];
 
@@ -561,7 +553,7 @@ impl FieldElement {
 
         for i in 0..10 {
             let shift = if i & 1 == 1 { 25 } else { 26 };
-            let carry = (h[i] + (shift.clone() - 1)) >> shift.clone();
+            let carry = (h[i] + (shift - 1)) >> shift;
 
             let add = if i == 9 { carry * 19 } else { carry };
 
@@ -579,35 +571,34 @@ impl FieldElement {
 
     pub fn pow25523(&self) -> FieldElement {
         let z2 = &self.square();
-        let z8 = (0..2).fold(z2.clone(), |x, _| x.square());
-        let z9 = self.clone() * z8;
-        let z11 = z2.clone() * z9.clone();
+        let z8 = (0..2).fold(*z2, |x, _| x.square());
+        let z9 = *self * z8;
+        let z11 = *z2 * z9;
         let z22 = z11.square();
-        let z_5_0 = z9.clone() * z22;
-        let z_10_5 = (0..5).fold(z_5_0.clone(), |x, _| x.square());
+        let z_5_0 = z9 * z22;
+        let z_10_5 = (0..5).fold(z_5_0, |x, _| x.square());
         let z_10_0 = z_10_5 * z_5_0;
-        let z_20_10 = (0..10).fold(z_10_0.clone(), |x, _| x.square());
-        let z_20_0 = &z_20_10 * &z_10_0;
-        let z_40_20 = (0..20).fold(z_20_0.clone(), |x, _| x.square());
+        let z_20_10 = (0..10).fold(z_10_0, |x, _| x.square());
+        let z_20_0 = z_20_10 * z_10_0;
+        let z_40_20 = (0..20).fold(z_20_0, |x, _| x.square());
         let z_40_0 = z_40_20 * z_20_0;
         let z_50_10 = (0..10).fold(z_40_0, |x, _| x.square());
         let z_50_0 = z_50_10 * z_10_0;
-        let z_100_50 = (0..50).fold(z_50_0.clone(), |x, _| x.square());
-        let z_100_0 = &z_100_50 * &z_50_0;
-        let z_200_100 = (0..100).fold(z_100_0.clone(), |x, _| x.square());
+        let z_100_50 = (0..50).fold(z_50_0, |x, _| x.square());
+        let z_100_0 = z_100_50 * z_50_0;
+        let z_200_100 = (0..100).fold(z_100_0, |x, _| x.square());
         let z_200_0 = z_200_100 * z_100_0;
         let z_250_50 = (0..50).fold(z_200_0, |x, _| x.square());
         let z_250_0 = z_250_50 * z_50_0;
         let z_252_2 = (0..2).fold(z_250_0, |x, _| x.square());
-        let z_252_3 = z_252_2.clone() * self.clone();
 
-        z_252_3
+        z_252_2 * *self
     }
 
     pub fn is_nonzero(&self) -> bool {
         let bs = self.to_bytes();
         let zero = [0; 32];
-        !const_time_eq(bs.as_ref(), zero.as_ref())
+        !ct_eq(bs.as_ref(), zero.as_ref())
     }
 
     pub fn is_negative(&self) -> bool {
@@ -642,7 +633,7 @@ impl Add for FieldElement {
     type Output = FieldElement;
 
     fn add(self, rhs: FieldElement) -> FieldElement {
-        &self + &rhs
+        Add::add(&self, &rhs)
     }
 }
 
@@ -666,7 +657,7 @@ impl Sub for FieldElement {
     type Output = FieldElement;
 
     fn sub(self, rhs: FieldElement) -> FieldElement {
-        &self - &rhs
+        Sub::sub(&self, &rhs)
     }
 }
 
@@ -674,7 +665,7 @@ impl Sub<&FieldElement> for FieldElement {
     type Output = FieldElement;
 
     fn sub(self, rhs: &FieldElement) -> FieldElement {
-        &self - rhs
+        Sub::sub(&self, rhs)
     }
 }
 
@@ -858,7 +849,7 @@ impl Mul for &FieldElement {
 
         for i in 0..10 {
             let shift = if i & 1 == 1 { 24 } else { 25 };
-            let carry = (h[i] + (1 << shift.clone())) >> shift.clone() + 1;
+            let carry = (h[i] + (1 << shift)) >> (shift + 1);
 
             let add = if i == 9 { carry * 19 } else { carry };
 
@@ -884,7 +875,7 @@ impl Mul for FieldElement {
     type Output = FieldElement;
 
     fn mul(self, rhs: FieldElement) -> FieldElement {
-        &self * &rhs
+        Mul::mul(&self, &rhs)
     }
 }
 
@@ -1,8 +1,8 @@
 // adapted from the rust-crypto ed25519 implementation
 
-use std::cmp::min;
-use std::ops::{Add, Sub, Mul};
-use crate::utils::const_time_eq as fixed_time_eq;
+use core::cmp::min;
+use core::ops::{Add, Sub, Mul};
+use crate::utils::constant_time::ct_eq as fixed_time_eq;
 
 #[derive(Clone, Copy)]
 pub struct Fe(pub [i32; 10]);
@@ -294,15 +294,7 @@ impl Mul for Fe {
         let mut h8 = f0g8+f1g7_2 +f2g6   +f3g5_2 +f4g4   +f5g3_2 +f6g2   +f7g1_2 +f8g0   +f9g9_38;
         let mut h9 = f0g9+f1g8   +f2g7   +f3g6   +f4g5   +f5g4   +f6g3   +f7g2   +f8g1   +f9g0   ;
         let mut carry0;
-        let carry1;
-        let carry2;
-        let carry3;
         let mut carry4;
-        let carry5;
-        let carry6;
-        let carry7;
-        let carry8;
-        let carry9;
 
         /*
         |h0| <= (1.1*1.1*2^52*(1+19+19+19+19)+1.1*1.1*2^50*(38+38+38+38+38))
@@ -318,35 +310,35 @@ impl Mul for Fe {
         /* |h1| <= 1.51*2^58 */
         /* |h5| <= 1.51*2^58 */
 
-        carry1 = (h1 + (1<<24)) >> 25; h2 += carry1; h1 -= carry1 << 25;
-        carry5 = (h5 + (1<<24)) >> 25; h6 += carry5; h5 -= carry5 << 25;
+        let carry1 = (h1 + (1<<24)) >> 25; h2 += carry1; h1 -= carry1 << 25;
+        let carry5 = (h5 + (1<<24)) >> 25; h6 += carry5; h5 -= carry5 << 25;
         /* |h1| <= 2^24; from now on fits into int32 */
         /* |h5| <= 2^24; from now on fits into int32 */
         /* |h2| <= 1.21*2^59 */
         /* |h6| <= 1.21*2^59 */
 
-        carry2 = (h2 + (1<<25)) >> 26; h3 += carry2; h2 -= carry2 << 26;
-        carry6 = (h6 + (1<<25)) >> 26; h7 += carry6; h6 -= carry6 << 26;
+        let carry2 = (h2 + (1<<25)) >> 26; h3 += carry2; h2 -= carry2 << 26;
+        let carry6 = (h6 + (1<<25)) >> 26; h7 += carry6; h6 -= carry6 << 26;
         /* |h2| <= 2^25; from now on fits into int32 unchanged */
         /* |h6| <= 2^25; from now on fits into int32 unchanged */
         /* |h3| <= 1.51*2^58 */
         /* |h7| <= 1.51*2^58 */
 
-        carry3 = (h3 + (1<<24)) >> 25; h4 += carry3; h3 -= carry3 << 25;
-        carry7 = (h7 + (1<<24)) >> 25; h8 += carry7; h7 -= carry7 << 25;
+        let carry3 = (h3 + (1<<24)) >> 25; h4 += carry3; h3 -= carry3 << 25;
+        let carry7 = (h7 + (1<<24)) >> 25; h8 += carry7; h7 -= carry7 << 25;
         /* |h3| <= 2^24; from now on fits into int32 unchanged */
         /* |h7| <= 2^24; from now on fits into int32 unchanged */
         /* |h4| <= 1.52*2^33 */
         /* |h8| <= 1.52*2^33 */
 
         carry4 = (h4 + (1<<25)) >> 26; h5 += carry4; h4 -= carry4 << 26;
-        carry8 = (h8 + (1<<25)) >> 26; h9 += carry8; h8 -= carry8 << 26;
+        let carry8 = (h8 + (1<<25)) >> 26; h9 += carry8; h8 -= carry8 << 26;
         /* |h4| <= 2^25; from now on fits into int32 unchanged */
         /* |h8| <= 2^25; from now on fits into int32 unchanged */
         /* |h5| <= 1.01*2^24 */
         /* |h9| <= 1.51*2^58 */
 
-        carry9 = (h9 + (1<<24)) >> 25; h0 += carry9 * 19; h9 -= carry9 << 25;
+        let carry9 = (h9 + (1<<24)) >> 25; h0 += carry9 * 19; h9 -= carry9 << 25;
         /* |h9| <= 2^24; from now on fits into int32 unchanged */
         /* |h0| <= 1.8*2^37 */
 
@@ -413,8 +405,8 @@ impl Fe {
       so floor(2^(-255)(h + 19 2^(-25) h9 + 2^(-1))) = q.
     */
 
-    pub fn to_bytes(&self) -> [u8; 32] {
-        let &Fe(es) = self;
+    pub fn to_bytes(self) -> [u8; 32] {
+        let Fe(es) = self;
         let mut h0 = es[0];
         let mut h1 = es[1];
         let mut h2 = es[2];
@@ -462,7 +454,7 @@ impl Fe {
         Goal: Output h0+...+2^230 h9.
         */
         [
-            (h0 >> 0) as u8,
+            h0 as u8,
             (h0 >> 8) as u8,
             (h0 >> 16) as u8,
             ((h0 >> 24) | (h1 << 2)) as u8,
@@ -478,7 +470,7 @@ impl Fe {
             (h4 >> 2) as u8,
             (h4 >> 10) as u8,
             (h4 >> 18) as u8,
-            (h5 >> 0) as u8,
+            h5 as u8,
             (h5 >> 8) as u8,
             (h5 >> 16) as u8,
             ((h5 >> 24) | (h6 << 1)) as u8,
@@ -497,56 +489,6 @@ impl Fe {
         ]
     }
 
-    pub fn maybe_swap_with(&mut self, other: &mut Fe, do_swap: i32) {
-        let &mut Fe(f) = self;
-        let &mut Fe(g) = other;
-        let f0 = f[0];
-        let f1 = f[1];
-        let f2 = f[2];
-        let f3 = f[3];
-        let f4 = f[4];
-        let f5 = f[5];
-        let f6 = f[6];
-        let f7 = f[7];
-        let f8 = f[8];
-        let f9 = f[9];
-        let g0 = g[0];
-        let g1 = g[1];
-        let g2 = g[2];
-        let g3 = g[3];
-        let g4 = g[4];
-        let g5 = g[5];
-        let g6 = g[6];
-        let g7 = g[7];
-        let g8 = g[8];
-        let g9 = g[9];
-        let mut x0 = f0 ^ g0;
-        let mut x1 = f1 ^ g1;
-        let mut x2 = f2 ^ g2;
-        let mut x3 = f3 ^ g3;
-        let mut x4 = f4 ^ g4;
-        let mut x5 = f5 ^ g5;
-        let mut x6 = f6 ^ g6;
-        let mut x7 = f7 ^ g7;
-        let mut x8 = f8 ^ g8;
-        let mut x9 = f9 ^ g9;
-        let b = -do_swap;
-        x0 &= b;
-        x1 &= b;
-        x2 &= b;
-        x3 &= b;
-        x4 &= b;
-        x5 &= b;
-        x6 &= b;
-        x7 &= b;
-        x8 &= b;
-        x9 &= b;
-        *self  = Fe([f0^x0, f1^x1, f2^x2, f3^x3, f4^x4,
-                     f5^x5, f6^x6, f7^x7, f8^x8, f9^x9]);
-        *other = Fe([g0^x0, g1^x1, g2^x2, g3^x3, g4^x4,
-                     g5^x5, g6^x6, g7^x7, g8^x8, g9^x9]);
-    }
-
     pub fn maybe_set(&mut self, other: &Fe, do_swap: i32) {
         let &mut Fe(f) = self;
         let &Fe(g) = other;
@@ -595,48 +537,6 @@ impl Fe {
                      f5^x5, f6^x6, f7^x7, f8^x8, f9^x9]);
     }
 
-    /*
-    h = f * 121666
-    Can overlap h with f.
-
-    Preconditions:
-       |f| bounded by 1.1*2^26,1.1*2^25,1.1*2^26,1.1*2^25,etc.
-
-    Postconditions:
-       |h| bounded by 1.1*2^25,1.1*2^24,1.1*2^25,1.1*2^24,etc.
-    */
-
-    fn mul_121666(&self) -> Fe {
-        let &Fe(f) = self;
-
-        let mut h0 = (f[0] as i64) * 121666;
-        let mut h1 = (f[1] as i64) * 121666;
-        let mut h2 = (f[2] as i64) * 121666;
-        let mut h3 = (f[3] as i64) * 121666;
-        let mut h4 = (f[4] as i64) * 121666;
-        let mut h5 = (f[5] as i64) * 121666;
-        let mut h6 = (f[6] as i64) * 121666;
-        let mut h7 = (f[7] as i64) * 121666;
-        let mut h8 = (f[8] as i64) * 121666;
-        let mut h9 = (f[9] as i64) * 121666;
-
-        let carry9 = (h9 + (1<<24)) >> 25; h0 += carry9 * 19; h9 -= carry9 << 25;
-        let carry1 = (h1 + (1<<24)) >> 25; h2 += carry1; h1 -= carry1 << 25;
-        let carry3 = (h3 + (1<<24)) >> 25; h4 += carry3; h3 -= carry3 << 25;
-        let carry5 = (h5 + (1<<24)) >> 25; h6 += carry5; h5 -= carry5 << 25;
-        let carry7 = (h7 + (1<<24)) >> 25; h8 += carry7; h7 -= carry7 << 25;
-
-        let carry0 = (h0 + (1<<25)) >> 26; h1 += carry0; h0 -= carry0 << 26;
-        let carry2 = (h2 + (1<<25)) >> 26; h3 += carry2; h2 -= carry2 << 26;
-        let carry4 = (h4 + (1<<25)) >> 26; h5 += carry4; h4 -= carry4 << 26;
-        let carry6 = (h6 + (1<<25)) >> 26; h7 += carry6; h6 -= carry6 << 26;
-        let carry8 = (h8 + (1<<25)) >> 26; h9 += carry8; h8 -= carry8 << 26;
-
-        Fe([h0 as i32, h1 as i32, h2 as i32, h3 as i32, h4 as i32,
-            h5 as i32, h6 as i32, h7 as i32, h8 as i32, h9 as i32])
-    }
-
-
     /*
     h = f * f
     Can overlap h with f.
@@ -858,15 +758,7 @@ impl Fe {
         let mut h8 = f0f8_2+f1f7_4 +f2f6_2 +f3f5_4 +f4f4   +f9f9_38;
         let mut h9 = f0f9_2+f1f8_2 +f2f7_2 +f3f6_2 +f4f5_2;
         let mut carry0: i64;
-        let carry1: i64;
-        let carry2: i64;
-        let carry3: i64;
         let mut carry4: i64;
-        let carry5: i64;
-        let carry6: i64;
-        let carry7: i64;
-        let carry8: i64;
-        let carry9: i64;
 
         h0 += h0;
         h1 += h1;
@@ -882,19 +774,19 @@ impl Fe {
         carry0 = (h0 + (1<<25)) >> 26; h1 += carry0; h0 -= carry0 << 26;
         carry4 = (h4 + (1<<25)) >> 26; h5 += carry4; h4 -= carry4 << 26;
 
-        carry1 = (h1 + (1<<24)) >> 25; h2 += carry1; h1 -= carry1 << 25;
-        carry5 = (h5 + (1<<24)) >> 25; h6 += carry5; h5 -= carry5 << 25;
+        let carry1: i64 = (h1 + (1<<24)) >> 25; h2 += carry1; h1 -= carry1 << 25;
+        let carry5: i64 = (h5 + (1<<24)) >> 25; h6 += carry5; h5 -= carry5 << 25;
 
-        carry2 = (h2 + (1<<25)) >> 26; h3 += carry2; h2 -= carry2 << 26;
-        carry6 = (h6 + (1<<25)) >> 26; h7 += carry6; h6 -= carry6 << 26;
+        let carry2: i64 = (h2 + (1<<25)) >> 26; h3 += carry2; h2 -= carry2 << 26;
+        let carry6: i64 = (h6 + (1<<25)) >> 26; h7 += carry6; h6 -= carry6 << 26;
 
-        carry3 = (h3 + (1<<24)) >> 25; h4 += carry3; h3 -= carry3 << 25;
-        carry7 = (h7 + (1<<24)) >> 25; h8 += carry7; h7 -= carry7 << 25;
+        let carry3: i64 = (h3 + (1<<24)) >> 25; h4 += carry3; h3 -= carry3 << 25;
+        let carry7: i64 = (h7 + (1<<24)) >> 25; h8 += carry7; h7 -= carry7 << 25;
 
         carry4 = (h4 + (1<<25)) >> 26; h5 += carry4; h4 -= carry4 << 26;
-        carry8 = (h8 + (1<<25)) >> 26; h9 += carry8; h8 -= carry8 << 26;
+        let carry8: i64 = (h8 + (1<<25)) >> 26; h9 += carry8; h8 -= carry8 << 26;
 
-        carry9 = (h9 + (1<<24)) >> 25; h0 += carry9 * 19; h9 -= carry9 << 25;
+        let carry9: i64 = (h9 + (1<<24)) >> 25; h0 += carry9 * 19; h9 -= carry9 << 25;
 
         carry0 = (h0 + (1<<25)) >> 26; h1 += carry0; h0 -= carry0 << 26;
 
@@ -971,9 +863,8 @@ impl Fe {
         /* qhasm: z_255_21 = z_255_5*z11 */
         /* asm 1: fe_mul(>z_255_21=fe#12,<z_255_5=fe#2,<z11=fe#1); */
         /* asm 2: fe_mul(>z_255_21=out,<z_255_5=t1,<z11=t0); */
-        let z_255_21 = z_255_5*z11;
 
-        z_255_21
+        z_255_5*z11
     }
 
 
@@ -1015,9 +906,8 @@ impl Fe {
         let z_250_50 = (0..50).fold(z_200_0, |x, _| x.square());
         let z_250_0 = z_250_50 * z_50_0;
         let z_252_2 = (0..2).fold(z_250_0, |x, _| x.square());
-        let z_252_3 = z_252_2 * *self;
 
-        z_252_3
+        z_252_2 * *self
     }
 }
 
@@ -1087,7 +977,7 @@ pub struct GeCached {
 }
 
 impl GeP1P1 {
-    fn to_p2(&self) -> GeP2 {
+    fn to_p2(self) -> GeP2 {
         GeP2 {
             x: self.x * self.t,
             y: self.y * self.z,
@@ -1095,7 +985,7 @@ impl GeP1P1 {
         }
     }
 
-    fn to_p3(&self) -> GeP3 {
+    fn to_p3(self) -> GeP3 {
         GeP3 {
             x: self.x * self.t,
             y: self.y * self.z,
@@ -1114,7 +1004,7 @@ impl GeP2 {
         }
     }
 
-    pub fn to_bytes(&self) -> [u8; 32] {
+    pub fn to_bytes(self) -> [u8; 32] {
         let recip = self.z.invert();
         let x = self.x * recip;
         let y = self.y * recip;
@@ -1267,14 +1157,14 @@ impl GeP3 {
         let t = x * y;
 
         Some(GeP3 {
-            x: x,
-            y: y,
-            z: z,
-            t: t,
+            x,
+            y,
+            z,
+            t,
         })
     }
 
-    fn to_p2(&self) -> GeP2 {
+    fn to_p2(self) -> GeP2 {
         GeP2 {
             x: self.x,
             y: self.y,
@@ -1282,7 +1172,7 @@ impl GeP3 {
         }
     }
 
-    fn to_cached(&self) -> GeCached {
+    fn to_cached(self) -> GeCached {
         GeCached {
             y_plus_x: self.y + self.x,
             y_minus_x: self.y - self.x,
@@ -1304,7 +1194,7 @@ impl GeP3 {
         self.to_p2().dbl()
     }
 
-    pub fn to_bytes(&self) -> [u8; 32] {
+    pub fn to_bytes(self) -> [u8; 32] {
         let recip = self.z.invert();
         let x = self.x * recip;
         let y = self.y * recip;
@@ -1472,7 +1362,7 @@ pub fn ge_scalarmult_base(a: &[u8]) -> GeP3 {
     let mut t: GePrecomp;
 
     for i in 0..32 {
-        es[2 * i + 0] = ((a[i] >> 0) & 15) as i8;
+        es[2 * i] = (a[i] & 15) as i8;
         es[2 * i + 1] = ((a[i] >> 4) & 15) as i8;
     }
     /* each es[i] is between 0 and 15 */
@@ -1558,11 +1448,6 @@ pub fn sc_reduce(s: &mut [u8]) {
     let mut carry9: i64;
     let mut carry10: i64;
     let mut carry11: i64;
-    let carry12: i64;
-    let carry13: i64;
-    let carry14: i64;
-    let carry15: i64;
-    let carry16: i64;
 
     s11 += s23 * 666643;
     s12 += s23 * 470296;
@@ -1615,13 +1500,13 @@ pub fn sc_reduce(s: &mut [u8]) {
     carry10 = (s10 + (1 << 20)) >> 21;
     s11 += carry10;
     s10 -= carry10 << 21;
-    carry12 = (s12 + (1 << 20)) >> 21;
+    let carry12: i64 = (s12 + (1 << 20)) >> 21;
     s13 += carry12;
     s12 -= carry12 << 21;
-    carry14 = (s14 + (1 << 20)) >> 21;
+    let carry14: i64 = (s14 + (1 << 20)) >> 21;
     s15 += carry14;
     s14 -= carry14 << 21;
-    carry16 = (s16 + (1 << 20)) >> 21;
+    let carry16: i64 = (s16 + (1 << 20)) >> 21;
     s17 += carry16;
     s16 -= carry16 << 21;
 
@@ -1634,10 +1519,10 @@ pub fn sc_reduce(s: &mut [u8]) {
     carry11 = (s11 + (1 << 20)) >> 21;
     s12 += carry11;
     s11 -= carry11 << 21;
-    carry13 = (s13 + (1 << 20)) >> 21;
+    let carry13: i64 = (s13 + (1 << 20)) >> 21;
     s14 += carry13;
     s13 -= carry13 << 21;
-    carry15 = (s15 + (1 << 20)) >> 21;
+    let carry15: i64 = (s15 + (1 << 20)) >> 21;
     s16 += carry15;
     s15 -= carry15 << 21;
 
@@ -1808,7 +1693,7 @@ pub fn sc_reduce(s: &mut [u8]) {
     s11 += carry10;
     s10 -= carry10 << 21;
 
-    s[0] = (s0 >> 0) as u8;
+    s[0] = s0 as u8;
     s[1] = (s0 >> 8) as u8;
     s[2] = ((s0 >> 16) | (s1 << 5)) as u8;
     s[3] = (s1 >> 3) as u8;
@@ -1829,7 +1714,7 @@ pub fn sc_reduce(s: &mut [u8]) {
     s[18] = ((s6 >> 18) | (s7 << 3)) as u8;
     s[19] = (s7 >> 5) as u8;
     s[20] = (s7 >> 13) as u8;
-    s[21] = (s8 >> 0) as u8;
+    s[21] = s8 as u8;
     s[22] = (s8 >> 8) as u8;
     s[23] = ((s8 >> 16) | (s9 << 5)) as u8;
     s[24] = (s9 >> 3) as u8;
@@ -1930,12 +1815,6 @@ pub fn sc_muladd(s: &mut [u8], a: &[u8], b: &[u8], c: &[u8]) {
     let mut carry14: i64;
     let mut carry15: i64;
     let mut carry16: i64;
-    let carry17: i64;
-    let carry18: i64;
-    let carry19: i64;
-    let carry20: i64;
-    let carry21: i64;
-    let carry22: i64;
 
     s0 = c0 + a0 * b0;
     s1 = c1 + a0 * b1 + a1 * b0;
@@ -2051,13 +1930,13 @@ pub fn sc_muladd(s: &mut [u8], a: &[u8], b: &[u8], c: &[u8]) {
     carry16 = (s16 + (1 << 20)) >> 21;
     s17 += carry16;
     s16 -= carry16 << 21;
-    carry18 = (s18 + (1 << 20)) >> 21;
+    let carry18: i64 = (s18 + (1 << 20)) >> 21;
     s19 += carry18;
     s18 -= carry18 << 21;
-    carry20 = (s20 + (1 << 20)) >> 21;
+    let carry20: i64 = (s20 + (1 << 20)) >> 21;
     s21 += carry20;
     s20 -= carry20 << 21;
-    carry22 = (s22 + (1 << 20)) >> 21;
+    let carry22: i64 = (s22 + (1 << 20)) >> 21;
     s23 += carry22;
     s22 -= carry22 << 21;
 
@@ -2085,13 +1964,13 @@ pub fn sc_muladd(s: &mut [u8], a: &[u8], b: &[u8], c: &[u8]) {
     carry15 = (s15 + (1 << 20)) >> 21;
     s16 += carry15;
     s15 -= carry15 << 21;
-    carry17 = (s17 + (1 << 20)) >> 21;
+    let carry17: i64 = (s17 + (1 << 20)) >> 21;
     s18 += carry17;
     s17 -= carry17 << 21;
-    carry19 = (s19 + (1 << 20)) >> 21;
+    let carry19: i64 = (s19 + (1 << 20)) >> 21;
     s20 += carry19;
     s19 -= carry19 << 21;
-    carry21 = (s21 + (1 << 20)) >> 21;
+    let carry21: i64 = (s21 + (1 << 20)) >> 21;
     s22 += carry21;
     s21 -= carry21 << 21;
 
@@ -2339,7 +2218,7 @@ pub fn sc_muladd(s: &mut [u8], a: &[u8], b: &[u8], c: &[u8]) {
     s11 += carry10;
     s10 -= carry10 << 21;
 
-    s[0] = (s0 >> 0) as u8;
+    s[0] = s0 as u8;
     s[1] = (s0 >> 8) as u8;
     s[2] = ((s0 >> 16) | (s1 << 5)) as u8;
     s[3] = (s1 >> 3) as u8;
@@ -2360,7 +2239,7 @@ pub fn sc_muladd(s: &mut [u8], a: &[u8], b: &[u8], c: &[u8]) {
     s[18] = ((s6 >> 18) | (s7 << 3)) as u8;
     s[19] = (s7 >> 5) as u8;
     s[20] = (s7 >> 13) as u8;
-    s[21] = (s8 >> 0) as u8;
+    s[21] = s8 as u8;
     s[22] = (s8 >> 8) as u8;
     s[23] = ((s8 >> 16) | (s9 << 5)) as u8;
     s[24] = (s9 >> 3) as u8;
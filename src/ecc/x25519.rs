@@ -1,12 +1,11 @@
 use crate::ecc::field::FieldElement;
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
 use zeroize::Zeroize;
 
 pub fn scalarmult(n: &[u8], p: &[u8]) -> [u8; 32] {
     let mut t = [0u8; 32];
-
-    for i in 0..32 {
-        t[i] = n[i];
-    }
+    t.copy_from_slice(&n[..32]);
 
     t[0] &= 248;
     t[31] &= 127;
@@ -15,7 +14,7 @@ pub fn scalarmult(n: &[u8], p: &[u8]) -> [u8; 32] {
     let x1 = FieldElement::from_bytes(p);
     let mut x2 = FieldElement::one();
     let mut z2 = FieldElement::zero();
-    let mut x3 = x1.clone();
+    let mut x3 = x1;
     let mut z3 = FieldElement::one();
 
     let mut swap = 0;
@@ -26,21 +25,21 @@ pub fn scalarmult(n: &[u8], p: &[u8]) -> [u8; 32] {
         z2.swap(&mut z3, swap);
         swap = bit as i32;
 
-        let a = &x2 + &z2;
-        let b = &x2 - &z2;
+        let a = x2 + z2;
+        let b = x2 - z2;
         let aa = a.square();
         let bb = b.square();
-        x2 = &aa * &bb;
-        let e = &aa - &bb;
-        let mut da = &x3 - &z3;
+        x2 = aa * bb;
+        let e = aa - bb;
+        let mut da = x3 - z3;
         da = da * a;
-        let mut cb = &x3 + &z3;
+        let mut cb = x3 + z3;
         cb = cb * b;
-        x3 = &da + &cb;
+        x3 = da + cb;
         x3 = x3.square();
-        z3 = &da - &cb;
+        z3 = da - cb;
         z3 = z3.square();
-        z3 = &z3 * &x1;
+        z3 = z3 * x1;
         z2 = e.mul32(121666);
         z2 = z2 + bb;
         z2 = z2 * e;
@@ -61,3 +60,45 @@ pub fn scalarmult_base(x: &[u8]) -> [u8; 32] {
     base[0] = 9;
     scalarmult(x, base.as_ref())
 }
+
+/// Returns the public key of a 32-byte secret key.
+pub fn public_key(secret: &[u8]) -> [u8; 32] {
+    scalarmult_base(secret)
+}
+
+/// Returns a `(secret, public)` keypair for a 32-byte seed.
+///
+/// Any 32 bytes are a valid X25519 secret key, so the seed is the secret key; the same
+/// seed always gives the same keypair.
+pub fn keypair_from_seed(seed: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    (*seed, public_key(seed))
+}
+
+/// Returns a random `(secret, public)` keypair.
+///
+/// Panics if the operating system's random number generator fails.
+#[cfg(feature = "getrandom")]
+pub fn generate_keypair() -> ([u8; 32], [u8; 32]) {
+    let mut secret = [0u8; 32];
+    getrandom(&mut secret).expect("failed to read random bytes");
+
+    let keypair = keypair_from_seed(&secret);
+    secret.zeroize();
+
+    keypair
+}
+
+/// Returns the secret shared between the owner of `secret` and the owner of `public`.
+///
+/// Returns `None` if `public` is a point of small order, which makes the shared secret all
+/// zeros whatever the secret key is. The shared secret is not uniformly random, so derive
+/// keys from it with a KDF rather than using it as a key directly.
+pub fn diffie_hellman(secret: &[u8], public: &[u8]) -> Option<[u8; 32]> {
+    let shared = scalarmult(secret, public);
+
+    if shared.iter().fold(0, |acc, byte| acc | byte) == 0 {
+        return None;
+    }
+
+    Some(shared)
+}
@@ -0,0 +1,261 @@
+//! Hex and Base64 (RFC 4648) in constant time, for keys, tags and tokens.
+//!
+//! The usual codecs look every character up in a table or branch on its range, which
+//! leaks the secret being encoded through cache and branch timing. Here each character is
+//! mapped with arithmetic on masks instead, so the time taken depends only on the length
+//! of the input. Only the lengths and the position of Base64 padding, which follow from
+//! the length of the data, are treated as public.
+//!
+//! Decoding is strict: it accepts exactly what encoding produces, except that hex digits
+//! may be in either case. Whitespace, missing or extra padding, and Base64 whose unused
+//! trailing bits are not zero are all rejected, so every encoded string decodes to one
+//! value and back. [`decode_base64_lenient`] skips whitespace and takes padding either
+//! way, for input written by people.
+//!
+//! ```
+//! use raycrypt::encoding::{decode_base64, decode_hex, encode_base64, encode_hex, Base64};
+//!
+//! assert_eq!(encode_hex(b"\x01\xab"), "01ab");
+//! assert_eq!(decode_hex("01AB").unwrap(), b"\x01\xab");
+//!
+//! assert_eq!(encode_base64(b"\xfb\xff", Base64::UrlSafeUnpadded), "-_8");
+//! assert_eq!(decode_base64("+/8=", Base64::Standard).unwrap(), b"\xfb\xff");
+//! assert!(decode_base64("+/8", Base64::Standard).is_err());
+//! ```
+pub use crate::errors::EncodingError;
+use alloc::string::String;
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+/// The Base64 alphabets of RFC 4648, with or without `=` padding.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Base64 {
+    /// The standard alphabet, with `+` and `/`, padded.
+    #[default]
+    Standard,
+    StandardUnpadded,
+    /// The URL and filename safe alphabet, with `-` and `_`, padded.
+    UrlSafe,
+    UrlSafeUnpadded,
+}
+
+impl Base64 {
+    fn url_safe(self) -> bool {
+        matches!(self, Base64::UrlSafe | Base64::UrlSafeUnpadded)
+    }
+
+    fn padded(self) -> bool {
+        matches!(self, Base64::Standard | Base64::UrlSafe)
+    }
+}
+
+/// Returns 0xff if `x == y` and 0 otherwise.
+fn eq(x: u8, y: u8) -> u8 {
+    ((0u16.wrapping_sub((x ^ y) as u16) >> 8) as u8) ^ 0xff
+}
+
+/// Returns 0xff if `x > y` and 0 otherwise.
+fn gt(x: u8, y: u8) -> u8 {
+    ((y as u16).wrapping_sub(x as u16) >> 8) as u8
+}
+
+/// Returns 0xff if `low <= x <= high` and 0 otherwise.
+fn in_range(x: u8, low: u8, high: u8) -> u8 {
+    !gt(low, x) & !gt(x, high)
+}
+
+/// Returns the hex digit for the nibble `x`.
+fn hex_digit(x: u8) -> u8 {
+    x + b'0' + (gt(x, 9) & (b'a' - b'0' - 10))
+}
+
+/// Returns the value of the hex digit `c`, or 0xff if it is not one.
+fn hex_value(c: u8) -> u8 {
+    let digit = c ^ b'0';
+    let letter = (c | 0x20).wrapping_sub(b'a' - 10);
+
+    let is_digit = gt(10, digit);
+    let is_letter = in_range(c | 0x20, b'a', b'f');
+
+    (is_digit & digit) | (is_letter & letter) | !(is_digit | is_letter)
+}
+
+/// Returns the Base64 character for the 6-bit value `x`.
+fn base64_char(x: u8, url_safe: bool) -> u8 {
+    let (c62, c63) = if url_safe { (b'-', b'_') } else { (b'+', b'/') };
+
+    (gt(26, x) & x.wrapping_add(b'A'))
+        | (in_range(x, 26, 51) & x.wrapping_add(b'a' - 26))
+        | (in_range(x, 52, 61) & x.wrapping_sub(52 - b'0'))
+        | (eq(x, 62) & c62)
+        | (eq(x, 63) & c63)
+}
+
+/// Returns the 6-bit value of the Base64 character `c`, or 0xff if it is not one.
+fn base64_value(c: u8, url_safe: bool) -> u8 {
+    let (c62, c63) = if url_safe { (b'-', b'_') } else { (b'+', b'/') };
+
+    let value = (in_range(c, b'A', b'Z') & c.wrapping_sub(b'A'))
+        | (in_range(c, b'a', b'z') & c.wrapping_sub(b'a' - 26))
+        | (in_range(c, b'0', b'9') & c.wrapping_add(52 - b'0'))
+        | (eq(c, c62) & 62)
+        | (eq(c, c63) & 63);
+
+    // 'A' is the only valid character with the value 0
+    value | (eq(value, 0) & !eq(c, b'A'))
+}
+
+/// Encodes `data` as lowercase hex.
+pub fn encode_hex(data: &[u8]) -> String {
+    let mut encoded = Vec::with_capacity(data.len() * 2);
+    for &byte in data {
+        encoded.push(hex_digit(byte >> 4));
+        encoded.push(hex_digit(byte & 0xf));
+    }
+
+    String::from_utf8(encoded).unwrap()
+}
+
+/// Decodes hex, in either case.
+///
+/// Fails if `encoded` has an odd length or any character, including whitespace, is not
+/// a hex digit.
+pub fn decode_hex(encoded: &str) -> Result<Vec<u8>, EncodingError> {
+    let encoded = encoded.as_bytes();
+    if !encoded.len().is_multiple_of(2) {
+        return Err(EncodingError::InvalidLength);
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len() / 2);
+    let mut invalid = 0u8;
+    for pair in encoded.chunks_exact(2) {
+        let (high, low) = (hex_value(pair[0]), hex_value(pair[1]));
+        invalid |= (high | low) >> 4;
+        decoded.push((high << 4) | (low & 0xf));
+    }
+
+    if core::hint::black_box(invalid) != 0 {
+        return Err(EncodingError::InvalidCharacter);
+    }
+
+    Ok(decoded)
+}
+
+/// Encodes `data` as Base64 in `variant`.
+pub fn encode_base64(data: &[u8], variant: Base64) -> String {
+    let url_safe = variant.url_safe();
+    let mut encoded = Vec::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let mut bytes = [0u8; 3];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for i in 0..=chunk.len() {
+            encoded.push(base64_char((bits >> (18 - 6 * i)) as u8 & 63, url_safe));
+        }
+        if variant.padded() {
+            encoded.resize(encoded.len() + 3 - chunk.len(), b'=');
+        }
+    }
+
+    String::from_utf8(encoded).unwrap()
+}
+
+/// Decodes Base64 in `variant`, accepting only what [`encode_base64`] produces.
+///
+/// Fails on characters outside the alphabet, including whitespace, on padding that is
+/// missing, present or misplaced when the variant says otherwise, and on a last character
+/// with bits set that no data fills.
+pub fn decode_base64(encoded: &str, variant: Base64) -> Result<Vec<u8>, EncodingError> {
+    let mut encoded = encoded.as_bytes();
+
+    if variant.padded() {
+        if !encoded.len().is_multiple_of(4) {
+            return Err(EncodingError::InvalidLength);
+        }
+
+        let padding = padding_length(encoded);
+        encoded = &encoded[..encoded.len() - padding];
+    }
+
+    decode_unpadded(encoded, variant.url_safe(), true)
+}
+
+/// Decodes Base64 in `variant` more permissively than [`decode_base64`], for input
+/// written or pasted by people.
+///
+/// ASCII whitespace is skipped anywhere, padding may be present or not whatever the
+/// variant, and unused trailing bits are ignored. Characters outside the alphabet still
+/// fail. Which characters are whitespace is not hidden, only the values of the others.
+pub fn decode_base64_lenient(encoded: &str, variant: Base64) -> Result<Vec<u8>, EncodingError> {
+    let mut stripped: Vec<u8> = encoded
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+
+    let padding = padding_length(&stripped);
+    if padding > 0 && !stripped.len().is_multiple_of(4) {
+        stripped.zeroize();
+        return Err(EncodingError::InvalidPadding);
+    }
+    stripped.truncate(stripped.len() - padding);
+
+    let decoded = decode_unpadded(&stripped, variant.url_safe(), false);
+    stripped.zeroize();
+
+    decoded
+}
+
+/// Returns the number of `=` at the end of `encoded`, at most two.
+fn padding_length(encoded: &[u8]) -> usize {
+    encoded
+        .iter()
+        .rev()
+        .take(2)
+        .take_while(|&&c| c == b'=')
+        .count()
+}
+
+/// Decodes Base64 without padding. If `strict`, the unused bits of the last character
+/// have to be zero.
+fn decode_unpadded(encoded: &[u8], url_safe: bool, strict: bool) -> Result<Vec<u8>, EncodingError> {
+    if encoded.len() % 4 == 1 {
+        return Err(EncodingError::InvalidLength);
+    }
+    if encoded.last() == Some(&b'=') {
+        return Err(EncodingError::InvalidPadding);
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len() * 3 / 4);
+    let mut invalid = 0u8;
+    let mut bits = 0u32;
+
+    for chunk in encoded.chunks(4) {
+        bits = 0;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = base64_value(c, url_safe);
+            invalid |= value >> 6;
+            bits |= ((value & 63) as u32) << (18 - 6 * i);
+        }
+
+        decoded.extend_from_slice(&bits.to_be_bytes()[1..chunk.len()]);
+    }
+
+    // the bits of the last partial group that no byte was decoded from must be zero
+    let unused = match encoded.len() % 4 {
+        2 => bits & 0xffff,
+        3 => bits & 0xff,
+        _ => 0,
+    };
+    let noncanonical = core::hint::black_box(unused) != 0;
+
+    if core::hint::black_box(invalid) != 0 {
+        return Err(EncodingError::InvalidCharacter);
+    }
+    if strict && noncanonical {
+        return Err(EncodingError::InvalidPadding);
+    }
+
+    Ok(decoded)
+}
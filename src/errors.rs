@@ -1,5 +1,5 @@
-use std::error::Error;
-use std::fmt;
+use core::error::Error;
+use core::fmt;
 
 #[derive(Clone, Copy, PartialEq)]
 pub struct InvalidMac;
@@ -19,3 +19,536 @@ impl fmt::Debug for InvalidMac {
 }
 
 impl Error for InvalidMac {}
+
+/// The tag of an AEAD ciphertext did not match.
+///
+/// `length_valid` is false if the input was too short to contain a tag at all, or too long
+/// to be a message, in which case no authentication was attempted. If it is true the input was well-formed, but the
+/// key, nonce, AAD or ciphertext did not match the tag.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TagMismatch {
+    pub length_valid: bool,
+}
+
+impl fmt::Display for TagMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.length_valid {
+            InvalidMac.fmt(f)
+        } else {
+            write!(f, "The input is too short to contain a MAC.")
+        }
+    }
+}
+
+impl Error for TagMismatch {}
+
+impl From<TagMismatch> for InvalidMac {
+    fn from(_: TagMismatch) -> InvalidMac {
+        InvalidMac
+    }
+}
+
+/// A key, nonce or other input had the wrong length.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct InvalidLength;
+
+impl fmt::Display for InvalidLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The input has an invalid length.")
+    }
+}
+
+impl fmt::Debug for InvalidLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The input has an invalid length.")
+    }
+}
+
+impl Error for InvalidLength {}
+
+/// A session has encrypted as many messages as its limit allows, or a nonce sequence has
+/// run out of nonces, and the key has to be replaced before encrypting more.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MessageLimitReached;
+
+impl fmt::Display for MessageLimitReached {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The message limit of this key has been reached.")
+    }
+}
+
+impl Error for MessageLimitReached {}
+
+/// A message of a sequence did not authenticate. Decryption stopped there, so no
+/// plaintext of the sequence was returned.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SequenceError {
+    /// The position of the message in the sequence.
+    pub index: usize,
+    pub error: TagMismatch,
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Message {} of the sequence: {}", self.index, self.error)
+    }
+}
+
+impl Error for SequenceError {}
+
+/// Errors returned by a decryption that checks its inputs before authenticating them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DecryptError {
+    /// The plaintext would be longer than the limit of `max_len` bytes. The ciphertext was
+    /// rejected before anything was allocated or authenticated.
+    TooLong { max_len: usize },
+    /// The nonce had the wrong length for the cipher. Nothing was authenticated.
+    InvalidNonce,
+    /// The ciphertext was within the limit, but did not authenticate.
+    TagMismatch(TagMismatch),
+    /// The message authenticated, but its padding is malformed.
+    InvalidPadding,
+}
+
+impl fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecryptError::TooLong { max_len } => {
+                write!(f, "The message is longer than the limit of {} bytes.", max_len)
+            }
+            DecryptError::InvalidNonce => InvalidLength.fmt(f),
+            DecryptError::TagMismatch(error) => error.fmt(f),
+            DecryptError::InvalidPadding => InvalidPadding.fmt(f),
+        }
+    }
+}
+
+impl Error for DecryptError {}
+
+impl From<TagMismatch> for DecryptError {
+    fn from(error: TagMismatch) -> DecryptError {
+        DecryptError::TagMismatch(error)
+    }
+}
+
+impl From<InvalidPadding> for DecryptError {
+    fn from(_: InvalidPadding) -> DecryptError {
+        DecryptError::InvalidPadding
+    }
+}
+
+/// Padded data does not end in an ISO/IEC 7816-4 marker followed only by zeros.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct InvalidPadding;
+
+impl fmt::Display for InvalidPadding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The padding is malformed.")
+    }
+}
+
+impl Error for InvalidPadding {}
+
+/// Errors returned when parsing a self-describing ciphertext.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FormatError {
+    /// The version byte does not name a known format.
+    UnknownVersion(u8),
+    /// The input is too short, its declared length does not match, or a nonce has the
+    /// wrong length for the version.
+    InvalidLength,
+    /// The header does not start with the expected magic bytes, names an unsupported
+    /// algorithm or holds an out of range parameter.
+    InvalidHeader,
+    /// The structure was valid, but the MAC did not match.
+    InvalidMac,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::UnknownVersion(version) => write!(f, "Unknown format version {}.", version),
+            FormatError::InvalidLength => InvalidLength.fmt(f),
+            FormatError::InvalidHeader => write!(f, "The header is malformed."),
+            FormatError::InvalidMac => InvalidMac.fmt(f),
+        }
+    }
+}
+
+impl Error for FormatError {}
+
+impl From<InvalidMac> for FormatError {
+    fn from(_: InvalidMac) -> FormatError {
+        FormatError::InvalidMac
+    }
+}
+
+impl From<InvalidLength> for FormatError {
+    fn from(_: InvalidLength) -> FormatError {
+        FormatError::InvalidLength
+    }
+}
+
+impl From<TagMismatch> for FormatError {
+    fn from(error: TagMismatch) -> FormatError {
+        if error.length_valid {
+            FormatError::InvalidMac
+        } else {
+            FormatError::InvalidLength
+        }
+    }
+}
+
+/// Errors returned when hashing a password with Argon2 or checking it against a stored
+/// hash.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Argon2Error {
+    /// A cost, the salt or the output length is out of the range Argon2 allows.
+    InvalidParams,
+    /// The password hash string is malformed, or names a variant or version this
+    /// implementation does not support.
+    InvalidFormat,
+}
+
+impl fmt::Display for Argon2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Argon2Error::InvalidParams => write!(f, "The Argon2 parameters are out of range."),
+            Argon2Error::InvalidFormat => write!(f, "The password hash string is malformed."),
+        }
+    }
+}
+
+impl Error for Argon2Error {}
+
+/// A known-answer test of [`self_test`](crate::selftest::self_test) failed, so the
+/// implementation of `algorithm` cannot be trusted on this machine.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SelfTestError {
+    pub algorithm: &'static str,
+}
+
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The known-answer test of {} failed.", self.algorithm)
+    }
+}
+
+impl Error for SelfTestError {}
+
+/// An algorithm could not be created by name.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AlgorithmError {
+    /// No enabled algorithm has that name.
+    UnknownAlgorithm,
+    /// The key has the wrong length for the algorithm.
+    InvalidKeyLength,
+}
+
+impl fmt::Display for AlgorithmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlgorithmError::UnknownAlgorithm => write!(f, "Unknown or disabled algorithm."),
+            AlgorithmError::InvalidKeyLength => write!(f, "The key has an invalid length."),
+        }
+    }
+}
+
+impl Error for AlgorithmError {}
+
+/// Hex or Base64 could not be decoded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EncodingError {
+    /// The input cannot be a whole encoding, e.g. hex of odd length.
+    InvalidLength,
+    /// A character, such as whitespace, is not part of the encoding.
+    InvalidCharacter,
+    /// Base64 padding is missing, misplaced or not allowed, or the unused bits of the
+    /// last character are not zero.
+    InvalidPadding,
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodingError::InvalidLength => InvalidLength.fmt(f),
+            EncodingError::InvalidCharacter => write!(f, "The input has an invalid character."),
+            EncodingError::InvalidPadding => write!(f, "The input is not padded canonically."),
+        }
+    }
+}
+
+impl Error for EncodingError {}
+
+/// A ratchet message could not be decrypted. The receiving chain is left as it was.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RatchetError {
+    /// The message is further ahead of the chain than the receiver skips keys for.
+    TooManySkipped,
+    /// The key of the message is gone: it was used for an earlier copy of the message,
+    /// or discarded as one of the oldest skipped keys.
+    KeyUnavailable,
+    /// The key was available, but the message did not authenticate.
+    TagMismatch(TagMismatch),
+}
+
+impl fmt::Display for RatchetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RatchetError::TooManySkipped => write!(f, "Too many messages were skipped."),
+            RatchetError::KeyUnavailable => {
+                write!(f, "The message was already received or its key discarded.")
+            }
+            RatchetError::TagMismatch(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for RatchetError {}
+
+impl From<TagMismatch> for RatchetError {
+    fn from(error: TagMismatch) -> RatchetError {
+        RatchetError::TagMismatch(error)
+    }
+}
+
+/// A record could not be opened. The receiver is left as it was, but as in TLS the
+/// connection should be closed rather than carry on past a bad record.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecordError {
+    /// The header does not describe an application data record of a valid length, or
+    /// the record is not as long as its header says.
+    InvalidHeader,
+    /// The record did not authenticate.
+    TagMismatch(TagMismatch),
+    /// The record authenticated, but does not hold application data.
+    UnexpectedContent,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::InvalidHeader => write!(f, "The record header is malformed."),
+            RecordError::TagMismatch(error) => error.fmt(f),
+            RecordError::UnexpectedContent => {
+                write!(f, "The record has an unexpected content type.")
+            }
+        }
+    }
+}
+
+impl Error for RecordError {}
+
+impl From<TagMismatch> for RecordError {
+    fn from(error: TagMismatch) -> RecordError {
+        RecordError::TagMismatch(error)
+    }
+}
+
+/// An HPKE message could not be opened. The context is left as it was.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HpkeError {
+    /// The context has used up its sequence numbers and cannot open any more messages.
+    MessageLimitReached,
+    /// The message did not authenticate.
+    TagMismatch(TagMismatch),
+}
+
+impl fmt::Display for HpkeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HpkeError::MessageLimitReached => MessageLimitReached.fmt(f),
+            HpkeError::TagMismatch(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for HpkeError {}
+
+impl From<MessageLimitReached> for HpkeError {
+    fn from(_: MessageLimitReached) -> HpkeError {
+        HpkeError::MessageLimitReached
+    }
+}
+
+impl From<TagMismatch> for HpkeError {
+    fn from(error: TagMismatch) -> HpkeError {
+        HpkeError::TagMismatch(error)
+    }
+}
+
+/// Any error of this crate, by kind, for callers that handle failures without caring
+/// which function or format they came from.
+///
+/// Every error type of this module converts into it, so `?` can collect them into one
+/// type, and a message that does not authenticate is [`CryptoError::InvalidTag`]
+/// whichever AEAD, MAC or format rejected it. With the `wasm` feature this is the
+/// `ErrorKind` of the errors the JavaScript bindings throw.
+#[cfg_attr(
+    feature = "wasm",
+    wasm_bindgen::prelude::wasm_bindgen(js_name = ErrorKind)
+)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CryptoError {
+    /// A key has the wrong length.
+    InvalidKeyLength,
+    /// A nonce has the wrong length.
+    InvalidNonceLength,
+    /// Some other input has the wrong length.
+    InvalidLength,
+    /// A message or MAC did not authenticate.
+    InvalidTag,
+    /// A message is too long for the cipher's block counter, or a key or nonce sequence
+    /// has used up its messages.
+    CounterOverflow,
+    /// A message is longer than the limit the caller set.
+    MessageTooLong,
+    /// An encoding, header, padding or password hash string is malformed.
+    InvalidFormat,
+    /// An algorithm name or format version is unknown, or its feature is disabled.
+    UnknownAlgorithm,
+    /// A parameter, such as an Argon2 cost, is out of range.
+    InvalidParameters,
+    /// A known-answer test of [`self_test`](crate::selftest::self_test) failed.
+    SelfTestFailed,
+    /// A key was asked for that was not created extractable.
+    KeyNotExtractable,
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CryptoError::InvalidKeyLength => write!(f, "The key has an invalid length."),
+            CryptoError::InvalidNonceLength => write!(f, "The nonce has an invalid length."),
+            CryptoError::InvalidLength => InvalidLength.fmt(f),
+            CryptoError::InvalidTag => InvalidMac.fmt(f),
+            CryptoError::CounterOverflow => write!(f, "The message or key limit was exceeded."),
+            CryptoError::MessageTooLong => write!(f, "The message is longer than the limit."),
+            CryptoError::InvalidFormat => write!(f, "The input is malformed."),
+            CryptoError::UnknownAlgorithm => AlgorithmError::UnknownAlgorithm.fmt(f),
+            CryptoError::InvalidParameters => write!(f, "A parameter is out of range."),
+            CryptoError::SelfTestFailed => write!(f, "A known-answer test failed."),
+            CryptoError::KeyNotExtractable => write!(f, "The key is not extractable."),
+        }
+    }
+}
+
+impl Error for CryptoError {}
+
+impl From<InvalidMac> for CryptoError {
+    fn from(_: InvalidMac) -> CryptoError {
+        CryptoError::InvalidTag
+    }
+}
+
+impl From<TagMismatch> for CryptoError {
+    fn from(_: TagMismatch) -> CryptoError {
+        CryptoError::InvalidTag
+    }
+}
+
+impl From<InvalidLength> for CryptoError {
+    fn from(_: InvalidLength) -> CryptoError {
+        CryptoError::InvalidLength
+    }
+}
+
+impl From<MessageLimitReached> for CryptoError {
+    fn from(_: MessageLimitReached) -> CryptoError {
+        CryptoError::CounterOverflow
+    }
+}
+
+impl From<SequenceError> for CryptoError {
+    fn from(_: SequenceError) -> CryptoError {
+        CryptoError::InvalidTag
+    }
+}
+
+impl From<DecryptError> for CryptoError {
+    fn from(error: DecryptError) -> CryptoError {
+        match error {
+            DecryptError::TooLong { .. } => CryptoError::MessageTooLong,
+            DecryptError::InvalidNonce => CryptoError::InvalidNonceLength,
+            DecryptError::TagMismatch(_) => CryptoError::InvalidTag,
+            DecryptError::InvalidPadding => CryptoError::InvalidFormat,
+        }
+    }
+}
+
+impl From<InvalidPadding> for CryptoError {
+    fn from(_: InvalidPadding) -> CryptoError {
+        CryptoError::InvalidFormat
+    }
+}
+
+impl From<FormatError> for CryptoError {
+    fn from(error: FormatError) -> CryptoError {
+        match error {
+            FormatError::UnknownVersion(_) => CryptoError::UnknownAlgorithm,
+            FormatError::InvalidLength => CryptoError::InvalidLength,
+            FormatError::InvalidHeader => CryptoError::InvalidFormat,
+            FormatError::InvalidMac => CryptoError::InvalidTag,
+        }
+    }
+}
+
+impl From<Argon2Error> for CryptoError {
+    fn from(error: Argon2Error) -> CryptoError {
+        match error {
+            Argon2Error::InvalidParams => CryptoError::InvalidParameters,
+            Argon2Error::InvalidFormat => CryptoError::InvalidFormat,
+        }
+    }
+}
+
+impl From<SelfTestError> for CryptoError {
+    fn from(_: SelfTestError) -> CryptoError {
+        CryptoError::SelfTestFailed
+    }
+}
+
+impl From<AlgorithmError> for CryptoError {
+    fn from(error: AlgorithmError) -> CryptoError {
+        match error {
+            AlgorithmError::UnknownAlgorithm => CryptoError::UnknownAlgorithm,
+            AlgorithmError::InvalidKeyLength => CryptoError::InvalidKeyLength,
+        }
+    }
+}
+
+impl From<EncodingError> for CryptoError {
+    fn from(_: EncodingError) -> CryptoError {
+        CryptoError::InvalidFormat
+    }
+}
+
+impl From<RatchetError> for CryptoError {
+    fn from(error: RatchetError) -> CryptoError {
+        match error {
+            RatchetError::TooManySkipped => CryptoError::CounterOverflow,
+            // either way the message cannot be authenticated any more
+            RatchetError::KeyUnavailable | RatchetError::TagMismatch(_) => CryptoError::InvalidTag,
+        }
+    }
+}
+
+impl From<RecordError> for CryptoError {
+    fn from(error: RecordError) -> CryptoError {
+        match error {
+            RecordError::InvalidHeader | RecordError::UnexpectedContent => {
+                CryptoError::InvalidFormat
+            }
+            RecordError::TagMismatch(_) => CryptoError::InvalidTag,
+        }
+    }
+}
+
+impl From<HpkeError> for CryptoError {
+    fn from(error: HpkeError) -> CryptoError {
+        match error {
+            HpkeError::MessageLimitReached => CryptoError::CounterOverflow,
+            HpkeError::TagMismatch(_) => CryptoError::InvalidTag,
+        }
+    }
+}
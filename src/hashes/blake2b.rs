@@ -0,0 +1,181 @@
+//! BLAKE2b (RFC 7693), with 64-bit words and outputs of up to 64 bytes.
+//!
+//! With a key, BLAKE2b is a MAC and a PRF; the key is processed as a first block of
+//! its own, so a keyed hash is as fast as an unkeyed one after that block.
+use crate::hashes::SIGMA;
+use alloc::vec::Vec;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const BLOCK_SIZE: usize = 128;
+pub const MAX_OUTPUT_LENGTH: usize = 64;
+pub const MAX_KEY_LENGTH: usize = 64;
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+#[inline(always)]
+fn g(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// Mixes one block into `h`, `counter` being the number of bytes hashed up to and
+/// including this block.
+fn compress(h: &mut [u64; 8], block: &[u8], counter: u128, last: bool) {
+    let mut m = [0u64; 16];
+    for (word, bytes) in m.iter_mut().zip(block.chunks_exact(8)) {
+        *word = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= counter as u64;
+    v[13] ^= (counter >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+
+    for round in 0..12 {
+        let s = &SIGMA[round % 10];
+        g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for (i, word) in h.iter_mut().enumerate() {
+        *word ^= v[i] ^ v[i + 8];
+    }
+
+    m.zeroize();
+    v.zeroize();
+}
+
+/// An incremental BLAKE2b hash.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Blake2b {
+    h: [u64; 8],
+    counter: u128,
+    buffer: [u8; BLOCK_SIZE],
+    buffered: usize,
+    output_length: usize,
+}
+
+impl Blake2b {
+    /// Starts an unkeyed hash with an output of `output_length` bytes.
+    ///
+    /// Panics if `output_length` is 0 or above 64.
+    pub fn new(output_length: usize) -> Blake2b {
+        Blake2b::with_key(&[], output_length)
+    }
+
+    /// Starts a hash keyed with up to 64 bytes, with an output of `output_length` bytes.
+    /// An empty key is the same as no key.
+    ///
+    /// Panics if `output_length` is 0 or above 64, or if the key is longer than 64 bytes.
+    pub fn with_key(key: &[u8], output_length: usize) -> Blake2b {
+        assert!(
+            (1..=MAX_OUTPUT_LENGTH).contains(&output_length),
+            "BLAKE2b outputs are 1 to 64 bytes, got {output_length}"
+        );
+        assert!(
+            key.len() <= MAX_KEY_LENGTH,
+            "BLAKE2b keys are at most 64 bytes, got {}",
+            key.len()
+        );
+
+        let mut h = IV;
+        h[0] ^= 0x01010000 ^ ((key.len() as u64) << 8) ^ output_length as u64;
+
+        let mut hash = Blake2b {
+            h,
+            counter: 0,
+            buffer: [0u8; BLOCK_SIZE],
+            buffered: 0,
+            output_length,
+        };
+
+        if !key.is_empty() {
+            hash.buffer[..key.len()].copy_from_slice(key);
+            hash.buffered = BLOCK_SIZE;
+        }
+
+        hash
+    }
+
+    /// Adds `data` to the hash. A message may be split across any number of calls.
+    pub fn update(&mut self, mut data: &[u8]) {
+        let fill = (BLOCK_SIZE - self.buffered).min(data.len());
+        self.buffer[self.buffered..self.buffered + fill].copy_from_slice(&data[..fill]);
+        self.buffered += fill;
+        data = &data[fill..];
+
+        // the last block is kept back for `finalize`, which compresses it as the final one
+        if data.is_empty() {
+            return;
+        }
+
+        self.counter += BLOCK_SIZE as u128;
+        compress(&mut self.h, &self.buffer, self.counter, false);
+
+        while data.len() > BLOCK_SIZE {
+            self.counter += BLOCK_SIZE as u128;
+            compress(&mut self.h, &data[..BLOCK_SIZE], self.counter, false);
+            data = &data[BLOCK_SIZE..];
+        }
+
+        self.buffer[..data.len()].copy_from_slice(data);
+        self.buffered = data.len();
+    }
+
+    /// Returns the hash of everything passed to [`Blake2b::update`].
+    pub fn finalize(mut self) -> Vec<u8> {
+        self.counter += self.buffered as u128;
+        self.buffer[self.buffered..].fill(0);
+        compress(&mut self.h, &self.buffer, self.counter, true);
+
+        let mut output: Vec<u8> = self.h.iter().flat_map(|word| word.to_le_bytes()).collect();
+        output.truncate(self.output_length);
+
+        output
+    }
+}
+
+/// Returns the unkeyed `output_length`-byte BLAKE2b hash of `data`.
+///
+/// Panics if `output_length` is 0 or above 64.
+pub fn blake2b(data: &[u8], output_length: usize) -> Vec<u8> {
+    let mut hash = Blake2b::new(output_length);
+    hash.update(data);
+
+    hash.finalize()
+}
+
+/// Returns the `output_length`-byte BLAKE2b hash of `data` keyed with `key`.
+///
+/// Panics if `output_length` is 0 or above 64, or if the key is longer than 64 bytes.
+pub fn keyed(key: &[u8], data: &[u8], output_length: usize) -> Vec<u8> {
+    let mut hash = Blake2b::with_key(key, output_length);
+    hash.update(data);
+
+    hash.finalize()
+}
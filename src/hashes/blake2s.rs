@@ -0,0 +1,171 @@
+//! BLAKE2s (RFC 7693), with 32-bit words and outputs of up to 32 bytes, for platforms
+//! without fast 64-bit arithmetic.
+use crate::hashes::SIGMA;
+use alloc::vec::Vec;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const BLOCK_SIZE: usize = 64;
+pub const MAX_OUTPUT_LENGTH: usize = 32;
+pub const MAX_KEY_LENGTH: usize = 32;
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+#[inline(always)]
+fn g(v: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(12);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(8);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(7);
+}
+
+/// Mixes one block into `h`, `counter` being the number of bytes hashed up to and
+/// including this block.
+fn compress(h: &mut [u32; 8], block: &[u8], counter: u64, last: bool) {
+    let mut m = [0u32; 16];
+    for (word, bytes) in m.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    let mut v = [0u32; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= counter as u32;
+    v[13] ^= (counter >> 32) as u32;
+    if last {
+        v[14] = !v[14];
+    }
+
+    for s in &SIGMA {
+        g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for (i, word) in h.iter_mut().enumerate() {
+        *word ^= v[i] ^ v[i + 8];
+    }
+
+    m.zeroize();
+    v.zeroize();
+}
+
+/// An incremental BLAKE2s hash.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Blake2s {
+    h: [u32; 8],
+    counter: u64,
+    buffer: [u8; BLOCK_SIZE],
+    buffered: usize,
+    output_length: usize,
+}
+
+impl Blake2s {
+    /// Starts an unkeyed hash with an output of `output_length` bytes.
+    ///
+    /// Panics if `output_length` is 0 or above 32.
+    pub fn new(output_length: usize) -> Blake2s {
+        Blake2s::with_key(&[], output_length)
+    }
+
+    /// Starts a hash keyed with up to 32 bytes, with an output of `output_length` bytes.
+    /// An empty key is the same as no key.
+    ///
+    /// Panics if `output_length` is 0 or above 32, or if the key is longer than 32 bytes.
+    pub fn with_key(key: &[u8], output_length: usize) -> Blake2s {
+        assert!(
+            (1..=MAX_OUTPUT_LENGTH).contains(&output_length),
+            "BLAKE2s outputs are 1 to 32 bytes, got {output_length}"
+        );
+        assert!(
+            key.len() <= MAX_KEY_LENGTH,
+            "BLAKE2s keys are at most 32 bytes, got {}",
+            key.len()
+        );
+
+        let mut h = IV;
+        h[0] ^= 0x01010000 ^ ((key.len() as u32) << 8) ^ output_length as u32;
+
+        let mut hash = Blake2s {
+            h,
+            counter: 0,
+            buffer: [0u8; BLOCK_SIZE],
+            buffered: 0,
+            output_length,
+        };
+
+        if !key.is_empty() {
+            hash.buffer[..key.len()].copy_from_slice(key);
+            hash.buffered = BLOCK_SIZE;
+        }
+
+        hash
+    }
+
+    /// Adds `data` to the hash. A message may be split across any number of calls.
+    pub fn update(&mut self, mut data: &[u8]) {
+        let fill = (BLOCK_SIZE - self.buffered).min(data.len());
+        self.buffer[self.buffered..self.buffered + fill].copy_from_slice(&data[..fill]);
+        self.buffered += fill;
+        data = &data[fill..];
+
+        // the last block is kept back for `finalize`, which compresses it as the final one
+        if data.is_empty() {
+            return;
+        }
+
+        self.counter += BLOCK_SIZE as u64;
+        compress(&mut self.h, &self.buffer, self.counter, false);
+
+        while data.len() > BLOCK_SIZE {
+            self.counter += BLOCK_SIZE as u64;
+            compress(&mut self.h, &data[..BLOCK_SIZE], self.counter, false);
+            data = &data[BLOCK_SIZE..];
+        }
+
+        self.buffer[..data.len()].copy_from_slice(data);
+        self.buffered = data.len();
+    }
+
+    /// Returns the hash of everything passed to [`Blake2s::update`].
+    pub fn finalize(mut self) -> Vec<u8> {
+        self.counter += self.buffered as u64;
+        self.buffer[self.buffered..].fill(0);
+        compress(&mut self.h, &self.buffer, self.counter, true);
+
+        let mut output: Vec<u8> = self.h.iter().flat_map(|word| word.to_le_bytes()).collect();
+        output.truncate(self.output_length);
+
+        output
+    }
+}
+
+/// Returns the unkeyed `output_length`-byte BLAKE2s hash of `data`.
+///
+/// Panics if `output_length` is 0 or above 32.
+pub fn blake2s(data: &[u8], output_length: usize) -> Vec<u8> {
+    let mut hash = Blake2s::new(output_length);
+    hash.update(data);
+
+    hash.finalize()
+}
+
+/// Returns the `output_length`-byte BLAKE2s hash of `data` keyed with `key`.
+///
+/// Panics if `output_length` is 0 or above 32, or if the key is longer than 32 bytes.
+pub fn keyed(key: &[u8], data: &[u8], output_length: usize) -> Vec<u8> {
+    let mut hash = Blake2s::with_key(key, output_length);
+    hash.update(data);
+
+    hash.finalize()
+}
@@ -0,0 +1,688 @@
+//! BLAKE3, a hash built on a binary tree of 1 KiB chunks, with a keyed mode, key
+//! derivation and output of any length.
+//!
+//! The three modes differ only in the key and flags the tree is hashed under:
+//! [`hash`] uses the fixed IV, [`keyed_hash`] a 32-byte key, and [`derive_key`] a key
+//! hashed from a context string, which should be hardcoded, globally unique and
+//! application-specific. [`Blake3::finalize_xof`] reads as much output as needed.
+//!
+//! As every chunk is hashed independently, the SSE4.1 backend compresses four chunks, or
+//! four parent nodes, at once; single blocks are always compressed by the portable
+//! implementation. With the `rayon` feature, `Blake3::update_par` also spreads large
+//! inputs over the rayon thread pool.
+//!
+//! ```
+//! use raycrypt::hashes::blake3::{hash, Blake3};
+//!
+//! let mut hasher = Blake3::new();
+//! hasher.update(b"a");
+//! hasher.update(b"bc");
+//! assert_eq!(hasher.finalize(), hash(b"abc"));
+//! ```
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod sse41;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use crate::utils::x86_feature_detected;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const OUTPUT_LENGTH: usize = 32;
+pub const KEY_LENGTH: usize = 32;
+pub const BLOCK_SIZE: usize = 64;
+pub const CHUNK_SIZE: usize = 1024;
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// The message words each of the seven rounds reads, in order: the previous round's
+/// order permuted by `[2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8]`.
+const MSG_SCHEDULE: [[usize; 16]; 7] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8],
+    [3, 4, 10, 12, 13, 2, 7, 14, 6, 5, 9, 0, 11, 15, 8, 1],
+    [10, 7, 12, 9, 14, 3, 13, 15, 4, 0, 11, 2, 5, 8, 1, 6],
+    [12, 13, 9, 11, 15, 10, 14, 8, 7, 2, 5, 3, 0, 1, 6, 4],
+    [9, 14, 11, 5, 8, 12, 15, 1, 13, 3, 0, 10, 2, 6, 4, 7],
+    [11, 15, 5, 0, 1, 9, 8, 6, 14, 10, 2, 12, 3, 4, 7, 13],
+];
+
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+const KEYED_HASH: u32 = 1 << 4;
+const DERIVE_KEY_CONTEXT: u32 = 1 << 5;
+const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+/// The number of chunks in the subtrees hashed as a unit, which bounds the chaining
+/// values kept on the stack while hashing one. Must be a power of two.
+const SUBTREE_CHUNKS: usize = 64;
+
+/// Inputs of up to 2^64 bytes have at most 2^54 chunks, so a tree at most 54 levels deep.
+const MAX_DEPTH: usize = 54;
+
+/// The BLAKE3 implementations, from fastest to slowest.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Backend {
+    /// SSE4.1, four chunks or parent nodes at a time.
+    Sse41,
+    Portable,
+}
+
+impl Backend {
+    /// Returns whether the running CPU supports this backend.
+    pub fn is_supported(self) -> bool {
+        match self {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Sse41 => x86_feature_detected!("sse2", "ssse3", "sse4.1"),
+            Backend::Portable => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Returns the name of the backend: `"sse41"` or `"portable"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Sse41 => "sse41",
+            Backend::Portable => "portable",
+        }
+    }
+}
+
+/// Detects the fastest backend supported by the running CPU.
+pub fn detect_backend() -> Backend {
+    if Backend::Sse41.is_supported() {
+        Backend::Sse41
+    } else {
+        Backend::Portable
+    }
+}
+
+/// Returns the backend used by [`Blake3::new`] and the other constructors.
+///
+/// Detection runs once, on first use, and the result is cached for the lifetime of the
+/// process.
+#[cfg(feature = "std")]
+pub fn backend() -> Backend {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+    *BACKEND.get_or_init(detect_backend)
+}
+
+/// Returns the backend used by [`Blake3::new`] and the other constructors.
+///
+/// Without the `std` feature the CPU cannot be queried, so this is the fastest backend
+/// the crate was compiled for, e.g. with `-C target-feature=+sse4.1`.
+#[cfg(not(feature = "std"))]
+pub fn backend() -> Backend {
+    detect_backend()
+}
+
+#[inline(always)]
+fn g(v: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(12);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(8);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(7);
+}
+
+/// Compresses `block`, of which the first `block_len` bytes are message, under the
+/// chaining value `cv`, and returns the full 16-word state. Its first half is the next
+/// chaining value, and all of it is output in the root node.
+fn compress(
+    cv: &[u32; 8],
+    block: &[u8; 64],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    let mut m = [0u32; 16];
+    for (word, bytes) in m.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    let mut v = [0u32; 16];
+    v[..8].copy_from_slice(cv);
+    v[8..12].copy_from_slice(&IV[..4]);
+    v[12] = counter as u32;
+    v[13] = (counter >> 32) as u32;
+    v[14] = block_len;
+    v[15] = flags;
+
+    for s in &MSG_SCHEDULE {
+        g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for i in 0..8 {
+        v[i] ^= v[i + 8];
+        v[i + 8] ^= cv[i];
+    }
+
+    m.zeroize();
+    v
+}
+
+/// Returns the first half of a compression, the chaining value.
+fn compress_cv(
+    cv: &[u32; 8],
+    block: &[u8; 64],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 8] {
+    let mut state = compress(cv, block, counter, block_len, flags);
+    let mut next = [0u32; 8];
+    next.copy_from_slice(&state[..8]);
+    state.zeroize();
+
+    next
+}
+
+fn words_from_bytes(bytes: &[u8; 32]) -> [u32; 8] {
+    let mut words = [0u32; 8];
+    for (word, bytes) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    words
+}
+
+/// Returns the block of a parent node, the chaining values of its two children.
+fn parent_block(left: &[u32; 8], right: &[u32; 8]) -> [u8; 64] {
+    let mut block = [0u8; 64];
+    for (bytes, word) in block.chunks_exact_mut(4).zip(left.iter().chain(right)) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+
+    block
+}
+
+/// Hashes `inputs`, each a whole number of blocks, into one chaining value each, with
+/// `flags_start` added to the first block of every input and `flags_end` to the last.
+/// The counter of the `i`-th input is `counter + i` if `increment_counter`, and
+/// `counter` otherwise.
+#[allow(clippy::too_many_arguments)]
+fn hash_many(
+    backend: Backend,
+    inputs: &[&[u8]],
+    key: &[u32; 8],
+    counter: u64,
+    increment_counter: bool,
+    flags: u32,
+    flags_start: u32,
+    flags_end: u32,
+    out: &mut [[u32; 8]],
+) {
+    let counter_for = |i: usize| {
+        if increment_counter {
+            counter + i as u64
+        } else {
+            counter
+        }
+    };
+
+    // the SIMD backends take four inputs at a time, and leave the rest to the portable code
+    let done = match backend {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Backend::Sse41 => {
+            let whole = inputs.len() - inputs.len() % 4;
+            for done in (0..whole).step_by(4) {
+                let batch: &[&[u8]; 4] = inputs[done..done + 4].try_into().unwrap();
+                let counters = [0, 1, 2, 3].map(|i| counter_for(done + i));
+                let out: &mut [[u32; 8]; 4] = (&mut out[done..done + 4]).try_into().unwrap();
+
+                // the backend was checked to be supported when the hasher was created
+                unsafe { sse41::hash4(batch, key, counters, flags, flags_start, flags_end, out) };
+            }
+
+            whole
+        }
+        _ => 0,
+    };
+
+    for (i, (input, cv)) in inputs.iter().zip(out.iter_mut()).enumerate().skip(done) {
+        let blocks = input.len() / BLOCK_SIZE;
+        *cv = *key;
+
+        for (j, block) in input.chunks_exact(BLOCK_SIZE).enumerate() {
+            let mut block_flags = flags;
+            if j == 0 {
+                block_flags |= flags_start;
+            }
+            if j == blocks - 1 {
+                block_flags |= flags_end;
+            }
+
+            *cv = compress_cv(
+                cv,
+                block.try_into().unwrap(),
+                counter_for(i),
+                64,
+                block_flags,
+            );
+        }
+    }
+}
+
+/// Returns the chaining value of the complete subtree of `input.len() / CHUNK_SIZE`
+/// chunks, a power of two up to [`SUBTREE_CHUNKS`], whose first chunk is `counter`.
+fn subtree_cv(
+    backend: Backend,
+    key: &[u32; 8],
+    input: &[u8],
+    counter: u64,
+    flags: u32,
+) -> [u32; 8] {
+    let mut cvs = [[0u32; 8]; SUBTREE_CHUNKS];
+    let mut blocks = [[0u8; 64]; SUBTREE_CHUNKS / 2];
+    let mut n = input.len() / CHUNK_SIZE;
+
+    let mut chunks = [&[][..]; SUBTREE_CHUNKS];
+    for (chunk, input) in chunks.iter_mut().zip(input.chunks_exact(CHUNK_SIZE)) {
+        *chunk = input;
+    }
+    hash_many(
+        backend,
+        &chunks[..n],
+        key,
+        counter,
+        true,
+        flags,
+        CHUNK_START,
+        CHUNK_END,
+        &mut cvs[..n],
+    );
+
+    // each level halves the chaining values, until the root of the subtree is left
+    while n > 1 {
+        n /= 2;
+        for (i, block) in blocks[..n].iter_mut().enumerate() {
+            *block = parent_block(&cvs[2 * i], &cvs[2 * i + 1]);
+        }
+
+        let mut parents = [&[][..]; SUBTREE_CHUNKS / 2];
+        for (parent, block) in parents.iter_mut().zip(&blocks[..n]) {
+            *parent = block;
+        }
+        hash_many(
+            backend,
+            &parents[..n],
+            key,
+            0,
+            false,
+            flags | PARENT,
+            0,
+            0,
+            &mut cvs[..n],
+        );
+    }
+
+    let cv = cvs[0];
+    cvs.zeroize();
+    blocks.zeroize();
+
+    cv
+}
+
+/// The last compression of a node, from which either its chaining value or, for the
+/// root, any amount of output is taken.
+#[derive(Clone, Zeroize)]
+struct Output {
+    cv: [u32; 8],
+    block: [u8; 64],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        compress_cv(
+            &self.cv,
+            &self.block,
+            self.counter,
+            self.block_len,
+            self.flags,
+        )
+    }
+
+    /// Returns the `index`-th 64-byte block of output of the root node.
+    fn root_block(&self, index: u64) -> [u8; 64] {
+        let mut state = compress(
+            &self.cv,
+            &self.block,
+            index,
+            self.block_len,
+            self.flags | ROOT,
+        );
+
+        let mut output = [0u8; 64];
+        for (bytes, word) in output.chunks_exact_mut(4).zip(state) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        state.zeroize();
+
+        output
+    }
+}
+
+fn parent_output(left: &[u32; 8], right: &[u32; 8], key: &[u32; 8], flags: u32) -> Output {
+    Output {
+        cv: *key,
+        block: parent_block(left, right),
+        counter: 0,
+        block_len: BLOCK_SIZE as u32,
+        flags: flags | PARENT,
+    }
+}
+
+/// The chunk being hashed, one block at a time. The last block is kept back, as it is
+/// compressed with the `CHUNK_END` flag.
+#[derive(Clone, Zeroize)]
+struct ChunkState {
+    cv: [u32; 8],
+    counter: u64,
+    block: [u8; 64],
+    block_len: usize,
+    blocks_compressed: usize,
+    flags: u32,
+}
+
+impl ChunkState {
+    fn new(key: &[u32; 8], counter: u64, flags: u32) -> ChunkState {
+        ChunkState {
+            cv: *key,
+            counter,
+            block: [0u8; 64],
+            block_len: 0,
+            blocks_compressed: 0,
+            flags,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.blocks_compressed * BLOCK_SIZE + self.block_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 {
+            CHUNK_START
+        } else {
+            0
+        }
+    }
+
+    /// Adds `data` to the chunk, which must have room for it.
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.block_len == BLOCK_SIZE {
+                let flags = self.flags | self.start_flag();
+                self.cv = compress_cv(&self.cv, &self.block, self.counter, 64, flags);
+                self.blocks_compressed += 1;
+                self.block = [0u8; 64];
+                self.block_len = 0;
+            }
+
+            let taken = data.len().min(BLOCK_SIZE - self.block_len);
+            self.block[self.block_len..self.block_len + taken].copy_from_slice(&data[..taken]);
+            self.block_len += taken;
+            data = &data[taken..];
+        }
+    }
+
+    fn output(&self) -> Output {
+        Output {
+            cv: self.cv,
+            block: self.block,
+            counter: self.counter,
+            block_len: self.block_len as u32,
+            flags: self.flags | self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+/// An incremental BLAKE3 hash, in any of the three modes.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Blake3 {
+    key: [u32; 8],
+    chunk: ChunkState,
+    // the chaining values of the complete subtrees to the left of the current chunk
+    cv_stack: [[u32; 8]; MAX_DEPTH],
+    stack_len: usize,
+    flags: u32,
+    #[zeroize(skip)]
+    backend: Backend,
+}
+
+impl Blake3 {
+    /// Starts an unkeyed hash on the backend selected by [`backend`].
+    pub fn new() -> Blake3 {
+        Blake3::with_backend(backend()).unwrap()
+    }
+
+    /// Starts an unkeyed hash on `backend`, or returns `None` if the CPU does not support
+    /// it.
+    pub fn with_backend(backend: Backend) -> Option<Blake3> {
+        if !backend.is_supported() {
+            return None;
+        }
+
+        Some(Blake3::with_key_and_flags(IV, 0, backend))
+    }
+
+    /// Starts a hash keyed with a 32-byte key, a MAC when the key is secret.
+    ///
+    /// Panics if `key` is not 32 bytes.
+    pub fn new_keyed(key: &[u8]) -> Blake3 {
+        let key: &[u8; KEY_LENGTH] = key.try_into().expect("BLAKE3 keys are 32 bytes");
+
+        Blake3::with_key_and_flags(words_from_bytes(key), KEYED_HASH, backend())
+    }
+
+    /// Starts deriving a key from the key material passed to [`Blake3::update`], for the
+    /// use described by `context`.
+    ///
+    /// The context should be hardcoded, globally unique and application-specific, such
+    /// as `"example.com 2024-01-01 session tokens v1"`, and never contain key material.
+    pub fn new_derive_key(context: &str) -> Blake3 {
+        let mut context_hasher = Blake3::with_key_and_flags(IV, DERIVE_KEY_CONTEXT, backend());
+        context_hasher.update(context.as_bytes());
+        let mut context_key = context_hasher.finalize();
+
+        let hasher = Blake3::with_key_and_flags(
+            words_from_bytes(&context_key),
+            DERIVE_KEY_MATERIAL,
+            backend(),
+        );
+        context_key.zeroize();
+
+        hasher
+    }
+
+    fn with_key_and_flags(key: [u32; 8], flags: u32, backend: Backend) -> Blake3 {
+        Blake3 {
+            key,
+            chunk: ChunkState::new(&key, 0, flags),
+            cv_stack: [[0u32; 8]; MAX_DEPTH],
+            stack_len: 0,
+            flags,
+            backend,
+        }
+    }
+
+    /// Adds `data` to the hash. A message may be split across any number of calls.
+    pub fn update(&mut self, mut data: &[u8]) {
+        if self.chunk.len() > 0 {
+            let taken = data.len().min(CHUNK_SIZE - self.chunk.len());
+            self.chunk.update(&data[..taken]);
+            data = &data[taken..];
+
+            // the chunk is only complete once more input follows, as it could be the root
+            if data.is_empty() {
+                return;
+            }
+            self.push_chunk();
+        }
+
+        // whole subtrees, keeping at least one byte back for the last chunk
+        while data.len() > CHUNK_SIZE {
+            let counter = self.chunk.counter;
+            let chunks = subtree_chunks(counter, (data.len() - 1) / CHUNK_SIZE);
+            let (subtree, rest) = data.split_at(chunks * CHUNK_SIZE);
+
+            let cv = subtree_cv(self.backend, &self.key, subtree, counter, self.flags);
+            self.push_cv(cv, counter);
+            self.chunk = ChunkState::new(&self.key, counter + chunks as u64, self.flags);
+            data = rest;
+        }
+
+        // the current chunk now has input, so every subtree on the stack is complete
+        self.chunk.update(data);
+        self.merge_cv_stack(self.chunk.counter);
+    }
+
+    /// Pushes the chaining value of the current chunk, which must be full, and starts
+    /// the next one.
+    fn push_chunk(&mut self) {
+        let counter = self.chunk.counter;
+        let cv = self.chunk.output().chaining_value();
+        self.push_cv(cv, counter);
+        self.chunk = ChunkState::new(&self.key, counter + 1, self.flags);
+    }
+
+    /// Pushes the chaining value of a complete subtree, preceded by `counter` chunks.
+    ///
+    /// Merging is deferred until more input follows, as the right-most subtree on the
+    /// stack may turn out to be the root.
+    fn push_cv(&mut self, cv: [u32; 8], counter: u64) {
+        self.merge_cv_stack(counter);
+        self.cv_stack[self.stack_len] = cv;
+        self.stack_len += 1;
+    }
+
+    /// Merges the subtrees on the stack until it holds one per bit set in `counter`, the
+    /// number of chunks to their left.
+    fn merge_cv_stack(&mut self, counter: u64) {
+        while self.stack_len > counter.count_ones() as usize {
+            let right = self.cv_stack[self.stack_len - 1];
+            let left = self.cv_stack[self.stack_len - 2];
+            self.stack_len -= 1;
+            self.cv_stack[self.stack_len - 1] =
+                parent_output(&left, &right, &self.key, self.flags).chaining_value();
+        }
+    }
+
+    fn root_output(&self) -> Output {
+        let mut output = self.chunk.output();
+        for left in self.cv_stack[..self.stack_len].iter().rev() {
+            output = parent_output(left, &output.chaining_value(), &self.key, self.flags);
+        }
+
+        output
+    }
+
+    /// Returns the 32-byte hash of everything passed to [`Blake3::update`].
+    pub fn finalize(self) -> [u8; 32] {
+        let mut block = self.root_output().root_block(0);
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&block[..32]);
+        block.zeroize();
+
+        digest
+    }
+
+    /// Returns a reader for output of any length, of which the first 32 bytes are
+    /// [`Blake3::finalize`]. Shorter outputs are prefixes of longer ones.
+    pub fn finalize_xof(self) -> OutputReader {
+        OutputReader {
+            output: self.root_output(),
+            position: 0,
+        }
+    }
+}
+
+impl Default for Blake3 {
+    fn default() -> Blake3 {
+        Blake3::new()
+    }
+}
+
+/// Returns the number of chunks in the largest complete subtree that starts at chunk
+/// `counter` and fits in `available` chunks, at least one.
+fn subtree_chunks(counter: u64, available: usize) -> usize {
+    let mut chunks = SUBTREE_CHUNKS;
+    while chunks > available || !counter.is_multiple_of(chunks as u64) {
+        chunks /= 2;
+    }
+
+    chunks
+}
+
+/// Reads the output of [`Blake3::finalize_xof`], up to 2^64 bytes of it.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct OutputReader {
+    output: Output,
+    position: u64,
+}
+
+impl OutputReader {
+    /// Fills `buffer` with the next `buffer.len()` bytes of output.
+    pub fn fill(&mut self, buffer: &mut [u8]) {
+        let mut buffer = buffer;
+        while !buffer.is_empty() {
+            let mut block = self.output.root_block(self.position / 64);
+            let offset = (self.position % 64) as usize;
+            let taken = buffer.len().min(64 - offset);
+
+            buffer[..taken].copy_from_slice(&block[offset..offset + taken]);
+            block.zeroize();
+            self.position += taken as u64;
+            buffer = &mut buffer[taken..];
+        }
+    }
+
+    /// Returns the position of the next byte of output.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+/// Returns the BLAKE3 hash of `data`.
+pub fn hash(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake3::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Returns the BLAKE3 hash of `data` keyed with `key`, a MAC if the key is secret.
+///
+/// Panics if `key` is not 32 bytes.
+pub fn keyed_hash(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake3::new_keyed(key);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Derives a 32-byte key from `key_material` for the use described by `context`, see
+/// [`Blake3::new_derive_key`].
+pub fn derive_key(context: &str, key_material: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake3::new_derive_key(context);
+    hasher.update(key_material);
+    hasher.finalize()
+}
@@ -0,0 +1,56 @@
+//! Multi-threaded BLAKE3 hashing of large inputs.
+//!
+//! Only built with the `rayon` feature. Complete subtrees of [`SUBTREE_CHUNKS`] chunks
+//! are hashed on the rayon thread pool, each into one chaining value, which are then
+//! pushed onto the stack in order as a serial pass would have.
+use super::{subtree_cv, Blake3, ChunkState, CHUNK_SIZE, SUBTREE_CHUNKS};
+use alloc::vec::Vec;
+use rayon::prelude::*;
+use zeroize::Zeroize;
+
+const SUBTREE_SIZE: usize = SUBTREE_CHUNKS * CHUNK_SIZE;
+
+impl Blake3 {
+    /// Like [`Blake3::update`], but spreads the work over the rayon thread pool. The hash
+    /// is identical.
+    pub fn update_par(&mut self, mut data: &[u8]) {
+        // serially up to the start of a subtree
+        let position = self.chunk.counter * CHUNK_SIZE as u64 + self.chunk.len() as u64;
+        let head = (position.wrapping_neg() % SUBTREE_SIZE as u64) as usize;
+        let (head, rest) = data.split_at(head.min(data.len()));
+        self.update(head);
+        data = rest;
+
+        // as in `update`, the last byte is kept back for the last chunk
+        let subtrees = data.len().saturating_sub(1) / SUBTREE_SIZE;
+        if subtrees == 0 {
+            self.update(data);
+            return;
+        }
+        if self.chunk.len() == CHUNK_SIZE {
+            self.push_chunk();
+        }
+
+        let (whole, rest) = data.split_at(subtrees * SUBTREE_SIZE);
+        let counter = self.chunk.counter;
+        let (backend, key, flags) = (self.backend, &self.key, self.flags);
+
+        let mut cvs: Vec<[u32; 8]> = whole
+            .par_chunks(SUBTREE_SIZE)
+            .enumerate()
+            .map(|(i, subtree)| {
+                let chunks = (i * SUBTREE_CHUNKS) as u64;
+                subtree_cv(backend, key, subtree, counter + chunks, flags)
+            })
+            .collect();
+
+        for (i, cv) in cvs.iter().enumerate() {
+            self.push_cv(*cv, counter + (i * SUBTREE_CHUNKS) as u64);
+        }
+        cvs.zeroize();
+
+        let next = counter + (subtrees * SUBTREE_CHUNKS) as u64;
+        self.chunk = ChunkState::new(&self.key, next, self.flags);
+        self.update(rest);
+    }
+}
@@ -0,0 +1,157 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use super::{BLOCK_SIZE, IV, MSG_SCHEDULE};
+
+#[inline(always)]
+unsafe fn rot16(x: __m128i) -> __m128i {
+    _mm_shuffle_epi8(
+        x,
+        _mm_set_epi8(13, 12, 15, 14, 9, 8, 11, 10, 5, 4, 7, 6, 1, 0, 3, 2),
+    )
+}
+
+#[inline(always)]
+unsafe fn rot12(x: __m128i) -> __m128i {
+    _mm_or_si128(_mm_srli_epi32(x, 12), _mm_slli_epi32(x, 20))
+}
+
+#[inline(always)]
+unsafe fn rot8(x: __m128i) -> __m128i {
+    _mm_shuffle_epi8(
+        x,
+        _mm_set_epi8(12, 15, 14, 13, 8, 11, 10, 9, 4, 7, 6, 5, 0, 3, 2, 1),
+    )
+}
+
+#[inline(always)]
+unsafe fn rot7(x: __m128i) -> __m128i {
+    _mm_or_si128(_mm_srli_epi32(x, 7), _mm_slli_epi32(x, 25))
+}
+
+#[inline(always)]
+unsafe fn g(v: &mut [__m128i; 16], a: usize, b: usize, c: usize, d: usize, x: __m128i, y: __m128i) {
+    v[a] = _mm_add_epi32(_mm_add_epi32(v[a], v[b]), x);
+    v[d] = rot16(_mm_xor_si128(v[d], v[a]));
+    v[c] = _mm_add_epi32(v[c], v[d]);
+    v[b] = rot12(_mm_xor_si128(v[b], v[c]));
+    v[a] = _mm_add_epi32(_mm_add_epi32(v[a], v[b]), y);
+    v[d] = rot8(_mm_xor_si128(v[d], v[a]));
+    v[c] = _mm_add_epi32(v[c], v[d]);
+    v[b] = rot7(_mm_xor_si128(v[b], v[c]));
+}
+
+/// Transposes four rows of four words into four columns.
+#[inline(always)]
+unsafe fn transpose(rows: [__m128i; 4]) -> [__m128i; 4] {
+    let ab01 = _mm_unpacklo_epi32(rows[0], rows[1]);
+    let ab23 = _mm_unpackhi_epi32(rows[0], rows[1]);
+    let cd01 = _mm_unpacklo_epi32(rows[2], rows[3]);
+    let cd23 = _mm_unpackhi_epi32(rows[2], rows[3]);
+
+    [
+        _mm_unpacklo_epi64(ab01, cd01),
+        _mm_unpackhi_epi64(ab01, cd01),
+        _mm_unpacklo_epi64(ab23, cd23),
+        _mm_unpackhi_epi64(ab23, cd23),
+    ]
+}
+
+/// Hashes four inputs of the same whole number of blocks at once, each in one lane of
+/// the vectors, see `hash_many`.
+///
+/// The caller must check that the CPU supports SSE4.1, see `Backend::Sse41`.
+#[target_feature(enable = "sse2,ssse3,sse4.1")]
+pub(super) unsafe fn hash4(
+    inputs: &[&[u8]; 4],
+    key: &[u32; 8],
+    counters: [u64; 4],
+    flags: u32,
+    flags_start: u32,
+    flags_end: u32,
+    out: &mut [[u32; 8]; 4],
+) {
+    let blocks = inputs[0].len() / BLOCK_SIZE;
+    assert!(inputs
+        .iter()
+        .all(|input| input.len() == blocks * BLOCK_SIZE));
+
+    let mut h = key.map(|word| _mm_set1_epi32(word as i32));
+    let counter_low = _mm_set_epi32(
+        counters[3] as i32,
+        counters[2] as i32,
+        counters[1] as i32,
+        counters[0] as i32,
+    );
+    let counter_high = _mm_set_epi32(
+        (counters[3] >> 32) as i32,
+        (counters[2] >> 32) as i32,
+        (counters[1] >> 32) as i32,
+        (counters[0] >> 32) as i32,
+    );
+
+    for block in 0..blocks {
+        let mut block_flags = flags;
+        if block == 0 {
+            block_flags |= flags_start;
+        }
+        if block == blocks - 1 {
+            block_flags |= flags_end;
+        }
+
+        // word i of every input's block, one input per lane
+        let mut m = [_mm_setzero_si128(); 16];
+        for quarter in 0..4 {
+            let rows = inputs.map(|input| {
+                _mm_loadu_si128(
+                    input.as_ptr().add(BLOCK_SIZE * block + 16 * quarter) as *const __m128i
+                )
+            });
+            m[4 * quarter..4 * quarter + 4].copy_from_slice(&transpose(rows));
+        }
+
+        let mut v = [
+            h[0],
+            h[1],
+            h[2],
+            h[3],
+            h[4],
+            h[5],
+            h[6],
+            h[7],
+            _mm_set1_epi32(IV[0] as i32),
+            _mm_set1_epi32(IV[1] as i32),
+            _mm_set1_epi32(IV[2] as i32),
+            _mm_set1_epi32(IV[3] as i32),
+            counter_low,
+            counter_high,
+            _mm_set1_epi32(BLOCK_SIZE as i32),
+            _mm_set1_epi32(block_flags as i32),
+        ];
+
+        for s in &MSG_SCHEDULE {
+            g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+            g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+            g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+            g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+            g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+            g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+            g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+            g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+        }
+
+        for i in 0..8 {
+            h[i] = _mm_xor_si128(v[i], v[i + 8]);
+        }
+    }
+
+    // back from one word per vector to one input per vector
+    let low = transpose([h[0], h[1], h[2], h[3]]);
+    let high = transpose([h[4], h[5], h[6], h[7]]);
+    for (cv, (low, high)) in out.iter_mut().zip(low.into_iter().zip(high)) {
+        _mm_storeu_si128(cv.as_mut_ptr() as *mut __m128i, low);
+        _mm_storeu_si128(cv.as_mut_ptr().add(4) as *mut __m128i, high);
+    }
+}
@@ -0,0 +1,473 @@
+//! SHA-256 and SHA-512 (FIPS 180-4), the hashes of HKDF's RFC 5869 vectors, Ed25519 and
+//! most interoperable formats.
+//!
+//! SHA-256 runs on the x86 SHA extensions when the CPU has them and on a portable
+//! implementation otherwise; SHA-512 is always portable, as few CPUs accelerate it.
+//!
+//! ```
+//! use raycrypt::hashes::sha2::{sha256, Sha256};
+//!
+//! let mut hasher = Sha256::new();
+//! hasher.update(b"a");
+//! hasher.update(b"bc");
+//! assert_eq!(hasher.finalize(), sha256(b"abc"));
+//! ```
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod shani;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use crate::utils::x86_feature_detected;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+const K256: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const IV256: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const K512: [u64; 80] = [
+    0x428a2f98d728ae22,
+    0x7137449123ef65cd,
+    0xb5c0fbcfec4d3b2f,
+    0xe9b5dba58189dbbc,
+    0x3956c25bf348b538,
+    0x59f111f1b605d019,
+    0x923f82a4af194f9b,
+    0xab1c5ed5da6d8118,
+    0xd807aa98a3030242,
+    0x12835b0145706fbe,
+    0x243185be4ee4b28c,
+    0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f,
+    0x80deb1fe3b1696b1,
+    0x9bdc06a725c71235,
+    0xc19bf174cf692694,
+    0xe49b69c19ef14ad2,
+    0xefbe4786384f25e3,
+    0x0fc19dc68b8cd5b5,
+    0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275,
+    0x4a7484aa6ea6e483,
+    0x5cb0a9dcbd41fbd4,
+    0x76f988da831153b5,
+    0x983e5152ee66dfab,
+    0xa831c66d2db43210,
+    0xb00327c898fb213f,
+    0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2,
+    0xd5a79147930aa725,
+    0x06ca6351e003826f,
+    0x142929670a0e6e70,
+    0x27b70a8546d22ffc,
+    0x2e1b21385c26c926,
+    0x4d2c6dfc5ac42aed,
+    0x53380d139d95b3df,
+    0x650a73548baf63de,
+    0x766a0abb3c77b2a8,
+    0x81c2c92e47edaee6,
+    0x92722c851482353b,
+    0xa2bfe8a14cf10364,
+    0xa81a664bbc423001,
+    0xc24b8b70d0f89791,
+    0xc76c51a30654be30,
+    0xd192e819d6ef5218,
+    0xd69906245565a910,
+    0xf40e35855771202a,
+    0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8,
+    0x1e376c085141ab53,
+    0x2748774cdf8eeb99,
+    0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63,
+    0x4ed8aa4ae3418acb,
+    0x5b9cca4f7763e373,
+    0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc,
+    0x78a5636f43172f60,
+    0x84c87814a1f0ab72,
+    0x8cc702081a6439ec,
+    0x90befffa23631e28,
+    0xa4506cebde82bde9,
+    0xbef9a3f7b2c67915,
+    0xc67178f2e372532b,
+    0xca273eceea26619c,
+    0xd186b8c721c0c207,
+    0xeada7dd6cde0eb1e,
+    0xf57d4f7fee6ed178,
+    0x06f067aa72176fba,
+    0x0a637dc5a2c898a6,
+    0x113f9804bef90dae,
+    0x1b710b35131c471b,
+    0x28db77f523047d84,
+    0x32caab7b40c72493,
+    0x3c9ebe0a15c9bebc,
+    0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6,
+    0x597f299cfc657e2a,
+    0x5fcb6fab3ad6faec,
+    0x6c44198c4a475817,
+];
+
+const IV512: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// The SHA-256 implementations, from fastest to slowest.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Backend {
+    /// The x86 SHA extensions.
+    ShaNi,
+    Portable,
+}
+
+impl Backend {
+    /// Returns whether the running CPU supports this backend.
+    pub fn is_supported(self) -> bool {
+        match self {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::ShaNi => x86_feature_detected!("sha", "sse2", "ssse3", "sse4.1"),
+            Backend::Portable => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Returns the name of the backend: `"shani"` or `"portable"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::ShaNi => "shani",
+            Backend::Portable => "portable",
+        }
+    }
+}
+
+/// Detects the fastest backend supported by the running CPU.
+pub fn detect_backend() -> Backend {
+    if Backend::ShaNi.is_supported() {
+        Backend::ShaNi
+    } else {
+        Backend::Portable
+    }
+}
+
+/// Returns the backend used by [`Sha256::new`] and [`sha256`].
+///
+/// Detection runs once, on first use, and the result is cached for the lifetime of the
+/// process.
+#[cfg(feature = "std")]
+pub fn backend() -> Backend {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+    *BACKEND.get_or_init(detect_backend)
+}
+
+/// Returns the backend used by [`Sha256::new`] and [`sha256`].
+///
+/// Without the `std` feature the CPU cannot be queried, so this is the fastest backend
+/// the crate was compiled for, e.g. with `-C target-feature=+sha`.
+#[cfg(not(feature = "std"))]
+pub fn backend() -> Backend {
+    detect_backend()
+}
+
+fn compress256_portable(h: &mut [u32; 8], blocks: &[u8]) {
+    let mut w = [0u32; 64];
+
+    for block in blocks.chunks_exact(Sha256::BLOCK_SIZE) {
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for t in 16..64 {
+            let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+            let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+            w[t] = w[t - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[t - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = *h;
+        for t in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K256[t])
+                .wrapping_add(w[t]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (word, value) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *word = word.wrapping_add(value);
+        }
+    }
+
+    w.zeroize();
+}
+
+/// Compresses `blocks`, a whole number of 64-byte blocks, into `h`.
+fn compress256(backend: Backend, h: &mut [u32; 8], blocks: &[u8]) {
+    match backend {
+        // the backend was checked to be supported when the hasher was created
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Backend::ShaNi => unsafe { shani::compress256(h, blocks) },
+        _ => compress256_portable(h, blocks),
+    }
+}
+
+fn compress512(h: &mut [u64; 8], blocks: &[u8]) {
+    let mut w = [0u64; 80];
+
+    for block in blocks.chunks_exact(Sha512::BLOCK_SIZE) {
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(8)) {
+            *word = u64::from_be_bytes(bytes.try_into().unwrap());
+        }
+        for t in 16..80 {
+            let s0 = w[t - 15].rotate_right(1) ^ w[t - 15].rotate_right(8) ^ (w[t - 15] >> 7);
+            let s1 = w[t - 2].rotate_right(19) ^ w[t - 2].rotate_right(61) ^ (w[t - 2] >> 6);
+            w[t] = w[t - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[t - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = *h;
+        for t in 0..80 {
+            let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K512[t])
+                .wrapping_add(w[t]);
+            let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (word, value) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *word = word.wrapping_add(value);
+        }
+    }
+
+    w.zeroize();
+}
+
+/// An incremental SHA-256 hash.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Sha256 {
+    h: [u32; 8],
+    length: u64,
+    buffer: [u8; 64],
+    buffered: usize,
+    #[zeroize(skip)]
+    backend: Backend,
+}
+
+impl Sha256 {
+    pub const BLOCK_SIZE: usize = 64;
+    pub const OUTPUT_LENGTH: usize = 32;
+
+    /// Starts a hash on the backend selected by [`backend`].
+    pub fn new() -> Sha256 {
+        Sha256::with_backend(backend()).unwrap()
+    }
+
+    /// Starts a hash on `backend`, or returns `None` if the CPU does not support it.
+    pub fn with_backend(backend: Backend) -> Option<Sha256> {
+        if !backend.is_supported() {
+            return None;
+        }
+
+        Some(Sha256 {
+            h: IV256,
+            length: 0,
+            buffer: [0u8; 64],
+            buffered: 0,
+            backend,
+        })
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+
+        if self.buffered > 0 {
+            let taken = data.len().min(Sha256::BLOCK_SIZE - self.buffered);
+            self.buffer[self.buffered..self.buffered + taken].copy_from_slice(&data[..taken]);
+            self.buffered += taken;
+            data = &data[taken..];
+
+            if self.buffered < Sha256::BLOCK_SIZE {
+                return;
+            }
+
+            compress256(self.backend, &mut self.h, &self.buffer);
+            self.buffered = 0;
+        }
+
+        let whole = data.len() - data.len() % Sha256::BLOCK_SIZE;
+        compress256(self.backend, &mut self.h, &data[..whole]);
+
+        let rest = &data[whole..];
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    pub fn finalize(mut self) -> [u8; 32] {
+        let bit_length = self.length.wrapping_mul(8);
+
+        // a 0x80 byte, zeros up to 8 bytes short of a block, and the length in bits
+        let mut padding = [0u8; 2 * 64];
+        padding[0] = 0x80;
+        let blocks = if self.buffered < Sha256::BLOCK_SIZE - 8 {
+            1
+        } else {
+            2
+        };
+        let padded = blocks * Sha256::BLOCK_SIZE - self.buffered;
+        padding[padded - 8..padded].copy_from_slice(&bit_length.to_be_bytes());
+        self.update(&padding[..padded]);
+
+        let mut digest = [0u8; 32];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.h) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+
+        digest
+    }
+}
+
+impl Default for Sha256 {
+    fn default() -> Sha256 {
+        Sha256::new()
+    }
+}
+
+/// An incremental SHA-512 hash.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Sha512 {
+    h: [u64; 8],
+    length: u128,
+    buffer: [u8; 128],
+    buffered: usize,
+}
+
+impl Sha512 {
+    pub const BLOCK_SIZE: usize = 128;
+    pub const OUTPUT_LENGTH: usize = 64;
+
+    pub fn new() -> Sha512 {
+        Sha512 {
+            h: IV512,
+            length: 0,
+            buffer: [0u8; 128],
+            buffered: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u128);
+
+        if self.buffered > 0 {
+            let taken = data.len().min(Sha512::BLOCK_SIZE - self.buffered);
+            self.buffer[self.buffered..self.buffered + taken].copy_from_slice(&data[..taken]);
+            self.buffered += taken;
+            data = &data[taken..];
+
+            if self.buffered < Sha512::BLOCK_SIZE {
+                return;
+            }
+
+            compress512(&mut self.h, &self.buffer);
+            self.buffered = 0;
+        }
+
+        let whole = data.len() - data.len() % Sha512::BLOCK_SIZE;
+        compress512(&mut self.h, &data[..whole]);
+
+        let rest = &data[whole..];
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    pub fn finalize(mut self) -> [u8; 64] {
+        let bit_length = self.length.wrapping_mul(8);
+
+        // a 0x80 byte, zeros up to 16 bytes short of a block, and the length in bits
+        let mut padding = [0u8; 2 * 128];
+        padding[0] = 0x80;
+        let blocks = if self.buffered < Sha512::BLOCK_SIZE - 16 {
+            1
+        } else {
+            2
+        };
+        let padded = blocks * Sha512::BLOCK_SIZE - self.buffered;
+        padding[padded - 16..padded].copy_from_slice(&bit_length.to_be_bytes());
+        self.update(&padding[..padded]);
+
+        let mut digest = [0u8; 64];
+        for (bytes, word) in digest.chunks_exact_mut(8).zip(self.h) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+
+        digest
+    }
+}
+
+impl Default for Sha512 {
+    fn default() -> Sha512 {
+        Sha512::new()
+    }
+}
+
+/// Returns the SHA-256 digest of `data`.
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Returns the SHA-512 digest of `data`.
+pub fn sha512(data: &[u8]) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    hasher.update(data);
+    hasher.finalize()
+}
@@ -0,0 +1,67 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use super::K256;
+
+/// Computes the next four message words from the previous sixteen.
+#[inline(always)]
+unsafe fn schedule(w0: __m128i, w1: __m128i, w2: __m128i, w3: __m128i) -> __m128i {
+    let t = _mm_add_epi32(_mm_sha256msg1_epu32(w0, w1), _mm_alignr_epi8(w3, w2, 4));
+    _mm_sha256msg2_epu32(t, w3)
+}
+
+/// Runs four rounds on the message words `w`, the `i`-th group of four.
+#[inline(always)]
+unsafe fn rounds4(abef: &mut __m128i, cdgh: &mut __m128i, w: __m128i, i: usize) {
+    let k = _mm_loadu_si128(K256.as_ptr().add(4 * i) as *const __m128i);
+    let wk = _mm_add_epi32(w, k);
+    *cdgh = _mm_sha256rnds2_epu32(*cdgh, *abef, wk);
+    *abef = _mm_sha256rnds2_epu32(*abef, *cdgh, _mm_shuffle_epi32(wk, 0x0e));
+}
+
+/// Compresses `blocks`, a whole number of 64-byte blocks, into `h`.
+///
+/// The caller must check that the CPU supports the SHA extensions, see `Backend::ShaNi`.
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+pub(super) unsafe fn compress256(h: &mut [u32; 8], blocks: &[u8]) {
+    // reverses the bytes of each word, as SHA-256 reads its message big-endian
+    let mask = _mm_set_epi64x(0x0c0d_0e0f_0809_0a0b, 0x0405_0607_0001_0203);
+
+    // the round instructions keep the state as (a, b, e, f) and (c, d, g, h)
+    let dcba = _mm_loadu_si128(h.as_ptr() as *const __m128i);
+    let hgfe = _mm_loadu_si128(h.as_ptr().add(4) as *const __m128i);
+    let cdab = _mm_shuffle_epi32(dcba, 0xb1);
+    let efgh = _mm_shuffle_epi32(hgfe, 0x1b);
+    let mut abef = _mm_alignr_epi8(cdab, efgh, 8);
+    let mut cdgh = _mm_blend_epi16(efgh, cdab, 0xf0);
+
+    for block in blocks.chunks_exact(64) {
+        let (abef_saved, cdgh_saved) = (abef, cdgh);
+
+        let mut w = [_mm_setzero_si128(); 4];
+        for (i, words) in w.iter_mut().enumerate() {
+            let bytes = _mm_loadu_si128(block.as_ptr().add(16 * i) as *const __m128i);
+            *words = _mm_shuffle_epi8(bytes, mask);
+            rounds4(&mut abef, &mut cdgh, *words, i);
+        }
+
+        for i in 4..16 {
+            let next = schedule(w[0], w[1], w[2], w[3]);
+            rounds4(&mut abef, &mut cdgh, next, i);
+            w = [w[1], w[2], w[3], next];
+        }
+
+        abef = _mm_add_epi32(abef, abef_saved);
+        cdgh = _mm_add_epi32(cdgh, cdgh_saved);
+    }
+
+    let feba = _mm_shuffle_epi32(abef, 0x1b);
+    let dchg = _mm_shuffle_epi32(cdgh, 0xb1);
+    let dcba = _mm_blend_epi16(feba, dchg, 0xf0);
+    let hgef = _mm_alignr_epi8(dchg, feba, 8);
+
+    _mm_storeu_si128(h.as_mut_ptr() as *mut __m128i, dcba);
+    _mm_storeu_si128(h.as_mut_ptr().add(4) as *mut __m128i, hgef);
+}
@@ -0,0 +1,14 @@
+#[cfg(feature = "argon2")]
+pub mod argon2;
+pub mod hkdf;
+
+/// Derives a 32-byte subkey from a 32-byte key and a 16-byte nonce with HChaCha20, the
+/// first step of XChaCha20.
+///
+/// HChaCha20 is a PRF when the key is uniformly random, so one master key can give a
+/// fresh subkey for every distinct nonce, e.g. one per session. It is no substitute for
+/// [`hkdf`] when the input is not already a uniformly random key.
+#[cfg(feature = "chacha")]
+pub fn hchacha20(key: &[u8; 32], nonce: &[u8; 16]) -> [u8; 32] {
+    crate::ciphers::chacha::hchacha(key, nonce, None).unwrap()
+}
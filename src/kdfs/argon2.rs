@@ -0,0 +1,583 @@
+//! Argon2 password hashing (RFC 9106), in the Argon2id and Argon2i variants.
+//!
+//! Argon2id is the variant to use for passwords; Argon2i only makes data-independent
+//! memory accesses, for settings where side channels matter more than GPU attacks.
+//! [`Argon2::hash_password`] returns a PHC string such as
+//! `$argon2id$v=19$m=65536,t=3,p=4$<salt>$<hash>` that holds everything needed to check
+//! the password later with [`verify_password`], and [`Argon2::hash_raw`] derives a key
+//! from a password, e.g. for one of the AEADs.
+//!
+//! Lanes are computed one after another on the calling thread, so the parallelism cost
+//! changes the output but does not make hashing faster.
+use crate::encoding::{self, Base64};
+use crate::errors::Argon2Error;
+use crate::hashes::blake2b::Blake2b;
+use crate::utils::constant_time::ct_eq;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// The length of the salts [`Argon2::hash_password`] generates.
+pub const SALT_LENGTH: usize = 16;
+/// The length of the hashes [`Argon2::hash_password`] computes.
+pub const OUTPUT_LENGTH: usize = 32;
+
+const VERSION: u32 = 0x13;
+const BLOCK_WORDS: usize = 128;
+const SYNC_POINTS: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Variant {
+    Argon2i = 1,
+    Argon2id = 2,
+}
+
+impl Variant {
+    fn name(self) -> &'static str {
+        match self {
+            Variant::Argon2i => "argon2i",
+            Variant::Argon2id => "argon2id",
+        }
+    }
+}
+
+/// The costs of an Argon2 hash.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Params {
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+}
+
+impl Params {
+    /// Uses `memory_cost` KiB of memory, `time_cost` passes over it and `parallelism`
+    /// lanes.
+    ///
+    /// Fails if `time_cost` is 0, if `parallelism` is 0 or at least 2^24, or if
+    /// `memory_cost` is below `8 * parallelism`.
+    pub fn new(memory_cost: u32, time_cost: u32, parallelism: u32) -> Result<Params, Argon2Error> {
+        if time_cost == 0
+            || !(1..1 << 24).contains(&parallelism)
+            || (memory_cost as u64) < 8 * parallelism as u64
+        {
+            return Err(Argon2Error::InvalidParams);
+        }
+
+        Ok(Params {
+            memory_cost,
+            time_cost,
+            parallelism,
+        })
+    }
+
+    pub fn memory_cost(&self) -> u32 {
+        self.memory_cost
+    }
+
+    pub fn time_cost(&self) -> u32 {
+        self.time_cost
+    }
+
+    pub fn parallelism(&self) -> u32 {
+        self.parallelism
+    }
+}
+
+impl Default for Params {
+    /// The second recommended option of RFC 9106: 64 MiB, 3 passes and 4 lanes.
+    fn default() -> Params {
+        Params {
+            memory_cost: 64 * 1024,
+            time_cost: 3,
+            parallelism: 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Block([u64; BLOCK_WORDS]);
+
+impl Block {
+    const ZERO: Block = Block([0; BLOCK_WORDS]);
+
+    fn from_bytes(bytes: &[u8]) -> Block {
+        let mut block = Block::ZERO;
+        for (word, bytes) in block.0.iter_mut().zip(bytes.chunks_exact(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        block
+    }
+}
+
+#[inline(always)]
+fn blamka(x: u64, y: u64) -> u64 {
+    let product = (x & 0xffffffff).wrapping_mul(y & 0xffffffff);
+    x.wrapping_add(y).wrapping_add(product.wrapping_mul(2))
+}
+
+#[inline(always)]
+fn gb(v: &mut [u64; BLOCK_WORDS], a: usize, b: usize, c: usize, d: usize) {
+    v[a] = blamka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = blamka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = blamka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = blamka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// The BLAKE2b round without message words, over the sixteen words at `i`.
+#[inline(always)]
+fn permute(v: &mut [u64; BLOCK_WORDS], i: [usize; 16]) {
+    gb(v, i[0], i[4], i[8], i[12]);
+    gb(v, i[1], i[5], i[9], i[13]);
+    gb(v, i[2], i[6], i[10], i[14]);
+    gb(v, i[3], i[7], i[11], i[15]);
+    gb(v, i[0], i[5], i[10], i[15]);
+    gb(v, i[1], i[6], i[11], i[12]);
+    gb(v, i[2], i[7], i[8], i[13]);
+    gb(v, i[3], i[4], i[9], i[14]);
+}
+
+/// The compression function G of `x` and `y`, XORed into `previous` on later passes.
+fn compress(x: &Block, y: &Block, previous: Option<&Block>) -> Block {
+    let mut r = Block::ZERO;
+    for (word, (x, y)) in r.0.iter_mut().zip(x.0.iter().zip(&y.0)) {
+        *word = x ^ y;
+    }
+
+    let mut q = r;
+    // rows of sixteen consecutive words, then columns of eight pairs of words
+    for row in 0..8 {
+        permute(&mut q.0, core::array::from_fn(|j| 16 * row + j));
+    }
+    for column in 0..8 {
+        permute(
+            &mut q.0,
+            core::array::from_fn(|j| 2 * column + 16 * (j / 2) + j % 2),
+        );
+    }
+
+    for (i, word) in q.0.iter_mut().enumerate() {
+        *word ^= r.0[i];
+        if let Some(previous) = previous {
+            *word ^= previous.0[i];
+        }
+    }
+    r.0.zeroize();
+
+    q
+}
+
+/// The variable-length hash H' of RFC 9106 §3.3, filling `output`.
+fn hash_long(output: &mut [u8], parts: &[&[u8]]) {
+    let length = (output.len() as u32).to_le_bytes();
+
+    if output.len() <= 64 {
+        let mut hash = Blake2b::new(output.len());
+        hash.update(&length);
+        for part in parts {
+            hash.update(part);
+        }
+        output.copy_from_slice(&hash.finalize());
+        return;
+    }
+
+    let mut hash = Blake2b::new(64);
+    hash.update(&length);
+    for part in parts {
+        hash.update(part);
+    }
+    let mut v = hash.finalize();
+    output[..32].copy_from_slice(&v[..32]);
+
+    let mut position = 32;
+    while output.len() - position > 64 {
+        let next = crate::hashes::blake2b::blake2b(&v, 64);
+        v.zeroize();
+        v = next;
+
+        output[position..position + 32].copy_from_slice(&v[..32]);
+        position += 32;
+    }
+
+    let last = crate::hashes::blake2b::blake2b(&v, output.len() - position);
+    output[position..].copy_from_slice(&last);
+    v.zeroize();
+}
+
+/// An Argon2 instance with its variant, costs and optional secret key.
+#[derive(ZeroizeOnDrop)]
+pub struct Argon2 {
+    #[zeroize(skip)]
+    variant: Variant,
+    #[zeroize(skip)]
+    params: Params,
+    secret: Vec<u8>,
+    associated_data: Vec<u8>,
+}
+
+/// The shape of the memory: `lanes` rows of `lane_length` blocks, each split into four
+/// segments.
+struct Layout {
+    lanes: usize,
+    lane_length: usize,
+    segment_length: usize,
+    passes: u32,
+    variant: Variant,
+}
+
+impl Layout {
+    /// Returns the index in its lane of the block referenced by `j1`, for block
+    /// `index` of the segment at `pass` and `slice` (RFC 9106 §3.4.1.2).
+    fn reference_index(
+        &self,
+        pass: u32,
+        slice: usize,
+        index: usize,
+        j1: u32,
+        same_lane: bool,
+    ) -> usize {
+        let area = if pass == 0 {
+            if slice == 0 || same_lane {
+                slice * self.segment_length + index - 1
+            } else {
+                slice * self.segment_length - (index == 0) as usize
+            }
+        } else if same_lane {
+            self.lane_length - self.segment_length + index - 1
+        } else {
+            self.lane_length - self.segment_length - (index == 0) as usize
+        };
+
+        let x = (j1 as u64 * j1 as u64) >> 32;
+        let y = (area as u64 * x) >> 32;
+        let relative = area - 1 - y as usize;
+
+        let start = if pass != 0 && slice != SYNC_POINTS - 1 {
+            (slice + 1) * self.segment_length
+        } else {
+            0
+        };
+
+        (start + relative) % self.lane_length
+    }
+
+    fn fill_segment(&self, memory: &mut [Block], pass: u32, slice: usize, lane: usize) {
+        let data_independent = match self.variant {
+            Variant::Argon2i => true,
+            Variant::Argon2id => pass == 0 && slice < SYNC_POINTS / 2,
+        };
+
+        let mut input = Block::ZERO;
+        let mut addresses = Block::ZERO;
+        let next_addresses = |input: &mut Block, addresses: &mut Block| {
+            input.0[6] += 1;
+            let once = compress(&Block::ZERO, input, None);
+            *addresses = compress(&Block::ZERO, &once, None);
+        };
+
+        if data_independent {
+            input.0[..6].copy_from_slice(&[
+                pass as u64,
+                lane as u64,
+                slice as u64,
+                memory.len() as u64,
+                self.passes as u64,
+                self.variant as u64,
+            ]);
+        }
+
+        // the first two blocks of each lane are filled from the initial hash
+        let first = if pass == 0 && slice == 0 {
+            if data_independent {
+                next_addresses(&mut input, &mut addresses);
+            }
+            2
+        } else {
+            0
+        };
+
+        for index in first..self.segment_length {
+            let offset = slice * self.segment_length + index;
+            let current = lane * self.lane_length + offset;
+            let previous = if offset == 0 {
+                current + self.lane_length - 1
+            } else {
+                current - 1
+            };
+
+            let pseudo_rand = if data_independent {
+                if index % BLOCK_WORDS == 0 {
+                    next_addresses(&mut input, &mut addresses);
+                }
+                addresses.0[index % BLOCK_WORDS]
+            } else {
+                memory[previous].0[0]
+            };
+
+            let reference_lane = if pass == 0 && slice == 0 {
+                lane
+            } else {
+                (pseudo_rand >> 32) as usize % self.lanes
+            };
+            let reference = self.reference_index(
+                pass,
+                slice,
+                index,
+                pseudo_rand as u32,
+                reference_lane == lane,
+            );
+
+            let block = compress(
+                &memory[previous],
+                &memory[reference_lane * self.lane_length + reference],
+                (pass != 0).then_some(&memory[current]),
+            );
+            memory[current] = block;
+        }
+    }
+}
+
+impl Argon2 {
+    pub fn new(variant: Variant, params: Params) -> Argon2 {
+        Argon2 {
+            variant,
+            params,
+            secret: Vec::new(),
+            associated_data: Vec::new(),
+        }
+    }
+
+    /// Mixes a secret key, or pepper, into every hash. A hash computed with a secret can
+    /// only be checked with [`Argon2::verify`] on an instance with the same secret.
+    pub fn with_secret(mut self, secret: &[u8]) -> Argon2 {
+        self.secret = secret.to_vec();
+        self
+    }
+
+    /// Mixes associated data into every hash. Unlike the salt, it is not stored in the
+    /// password hash string.
+    pub fn with_associated_data(mut self, associated_data: &[u8]) -> Argon2 {
+        self.associated_data = associated_data.to_vec();
+        self
+    }
+
+    /// Returns `length` bytes derived from `password` and `salt`, e.g. a key.
+    ///
+    /// Fails if the salt is shorter than 8 bytes or `length` is below 4.
+    pub fn hash_raw(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+        length: usize,
+    ) -> Result<Vec<u8>, Argon2Error> {
+        if salt.len() < 8 || length < 4 || length as u64 > u32::MAX as u64 {
+            return Err(Argon2Error::InvalidParams);
+        }
+
+        let lanes = self.params.parallelism as usize;
+        let lane_length = self.params.memory_cost as usize / (SYNC_POINTS * lanes) * SYNC_POINTS;
+        let layout = Layout {
+            lanes,
+            lane_length,
+            segment_length: lane_length / SYNC_POINTS,
+            passes: self.params.time_cost,
+            variant: self.variant,
+        };
+
+        let mut h0 = {
+            let mut hash = Blake2b::new(64);
+            for word in [
+                self.params.parallelism,
+                length as u32,
+                self.params.memory_cost,
+                self.params.time_cost,
+                VERSION,
+                self.variant as u32,
+            ] {
+                hash.update(&word.to_le_bytes());
+            }
+            for input in [password, salt, &self.secret, &self.associated_data] {
+                hash.update(&(input.len() as u32).to_le_bytes());
+                hash.update(input);
+            }
+
+            hash.finalize()
+        };
+
+        let mut memory = vec![Block::ZERO; lanes * lane_length];
+        let mut bytes = [0u8; 8 * BLOCK_WORDS];
+        for lane in 0..lanes {
+            for column in 0..2 {
+                hash_long(
+                    &mut bytes,
+                    &[
+                        &h0,
+                        &(column as u32).to_le_bytes(),
+                        &(lane as u32).to_le_bytes(),
+                    ],
+                );
+                memory[lane * lane_length + column] = Block::from_bytes(&bytes);
+            }
+        }
+        h0.zeroize();
+
+        for pass in 0..layout.passes {
+            for slice in 0..SYNC_POINTS {
+                for lane in 0..lanes {
+                    layout.fill_segment(&mut memory, pass, slice, lane);
+                }
+            }
+        }
+
+        let mut last = Block::ZERO;
+        for lane in 0..lanes {
+            for (word, block) in last
+                .0
+                .iter_mut()
+                .zip(&memory[(lane + 1) * lane_length - 1].0)
+            {
+                *word ^= block;
+            }
+        }
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(&last.0) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+
+        let mut output = vec![0u8; length];
+        hash_long(&mut output, &[&bytes]);
+
+        bytes.zeroize();
+        last.0.zeroize();
+        for block in memory.iter_mut() {
+            block.0.zeroize();
+        }
+
+        Ok(output)
+    }
+
+    /// Hashes `password` with a random salt and returns the PHC string to store.
+    ///
+    /// Panics if the operating system's random number generator fails.
+    #[cfg(feature = "getrandom")]
+    pub fn hash_password(&self, password: &[u8]) -> String {
+        let mut salt = [0u8; SALT_LENGTH];
+        getrandom(&mut salt).expect("failed to read random bytes");
+
+        self.hash_password_with_salt(password, &salt).unwrap()
+    }
+
+    /// Hashes `password` with `salt` and returns the PHC string to store.
+    ///
+    /// Fails if the salt is shorter than 8 bytes.
+    pub fn hash_password_with_salt(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+    ) -> Result<String, Argon2Error> {
+        let hash = self.hash_raw(password, salt, OUTPUT_LENGTH)?;
+
+        Ok(format!(
+            "${}$v={}$m={},t={},p={}${}${}",
+            self.variant.name(),
+            VERSION,
+            self.params.memory_cost,
+            self.params.time_cost,
+            self.params.parallelism,
+            encode_base64(salt),
+            encode_base64(&hash)
+        ))
+    }
+
+    /// Checks `password` against the PHC string `encoded`, using the variant and costs
+    /// stored in it and the secret and associated data of this instance.
+    ///
+    /// Returns `Ok(false)` if the password is wrong, and fails if `encoded` cannot be
+    /// parsed.
+    pub fn verify(&self, encoded: &str, password: &[u8]) -> Result<bool, Argon2Error> {
+        let parsed = parse(encoded)?;
+        let argon2 = Argon2 {
+            variant: parsed.variant,
+            params: parsed.params,
+            secret: self.secret.clone(),
+            associated_data: self.associated_data.clone(),
+        };
+
+        let mut hash = argon2.hash_raw(password, &parsed.salt, parsed.hash.len())?;
+        let equal = ct_eq(&hash, &parsed.hash);
+        hash.zeroize();
+
+        Ok(equal)
+    }
+}
+
+/// Checks `password` against the PHC string `encoded`, as [`Argon2::verify`] does for an
+/// instance without a secret or associated data.
+pub fn verify_password(encoded: &str, password: &[u8]) -> Result<bool, Argon2Error> {
+    Argon2::new(Variant::Argon2id, Params::default()).verify(encoded, password)
+}
+
+struct Parsed {
+    variant: Variant,
+    params: Params,
+    salt: Vec<u8>,
+    hash: Vec<u8>,
+}
+
+fn parse(encoded: &str) -> Result<Parsed, Argon2Error> {
+    let fields: Vec<&str> = encoded.split('$').collect();
+    let [empty, variant, version, costs, salt, hash] = fields[..] else {
+        return Err(Argon2Error::InvalidFormat);
+    };
+
+    let variant = match variant {
+        "argon2i" => Variant::Argon2i,
+        "argon2id" => Variant::Argon2id,
+        _ => return Err(Argon2Error::InvalidFormat),
+    };
+    if !empty.is_empty() || version != format!("v={VERSION}") {
+        return Err(Argon2Error::InvalidFormat);
+    }
+
+    let mut values = [0u32; 3];
+    let costs: Vec<&str> = costs.split(',').collect();
+    if costs.len() != 3 {
+        return Err(Argon2Error::InvalidFormat);
+    }
+    for ((value, cost), name) in values.iter_mut().zip(costs).zip(["m=", "t=", "p="]) {
+        let digits = cost.strip_prefix(name).ok_or(Argon2Error::InvalidFormat)?;
+        if digits.is_empty()
+            || (digits.starts_with('0') && digits != "0")
+            || digits.starts_with('+')
+        {
+            return Err(Argon2Error::InvalidFormat);
+        }
+        *value = digits.parse().map_err(|_| Argon2Error::InvalidFormat)?;
+    }
+
+    Ok(Parsed {
+        variant,
+        params: Params::new(values[0], values[1], values[2])?,
+        salt: decode_base64(salt)?,
+        hash: decode_base64(hash)?,
+    })
+}
+
+/// Encodes `data` in standard base64 without padding, as PHC strings do.
+fn encode_base64(data: &[u8]) -> String {
+    encoding::encode_base64(data, Base64::StandardUnpadded)
+}
+
+/// Decodes unpadded standard base64, rejecting anything [`encode_base64`] would not
+/// have produced.
+fn decode_base64(encoded: &str) -> Result<Vec<u8>, Argon2Error> {
+    encoding::decode_base64(encoded, Base64::StandardUnpadded)
+        .map_err(|_| Argon2Error::InvalidFormat)
+}
@@ -0,0 +1,86 @@
+//! HKDF (RFC 5869) with HMAC-SHA256, HMAC-SHA512 or HMAC-BLAKE2b-512.
+//!
+//! [`extract`] turns input keying material that is not uniformly random, e.g. a
+//! Diffie-Hellman shared secret, into a pseudorandom key, and [`expand`] stretches a
+//! pseudorandom key into any number of subkeys bound to an `info` context. [`derive`]
+//! does both. The hash is picked with a type parameter:
+//!
+//! ```
+//! use raycrypt::kdfs::hkdf::{self, Sha256};
+//!
+//! let key = hkdf::derive::<Sha256>(b"salt", b"input key material", b"context", 32).unwrap();
+//! assert_eq!(key.len(), 32);
+//! ```
+use crate::errors::InvalidLength;
+#[cfg(feature = "blake2b")]
+pub use crate::hashes::blake2b::Blake2b;
+pub use crate::hashes::sha2::{Sha256, Sha512};
+pub use crate::macs::hmac::Hash;
+use crate::macs::hmac::Hmac;
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+/// Returns the HMAC (RFC 2104) of the concatenation of `parts` under `key`.
+pub fn hmac<H: Hash>(key: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+    let mut mac = Hmac::<H>::new(key);
+    for part in parts {
+        mac.update(part);
+    }
+
+    mac.finalize()
+}
+
+/// Returns the pseudorandom key extracted from `ikm` with `salt`.
+///
+/// The salt does not need to be secret. An empty salt is the same as a salt of
+/// `H::OUTPUT_LENGTH` zero bytes, as in RFC 5869.
+pub fn extract<H: Hash>(salt: &[u8], ikm: &[u8]) -> Vec<u8> {
+    hmac::<H>(salt, &[ikm])
+}
+
+/// Expands the pseudorandom key `prk` into `length` bytes bound to `info`.
+///
+/// Fails if `prk` is shorter than `H::OUTPUT_LENGTH` or if `length` is above
+/// `255 * H::OUTPUT_LENGTH`, the most HKDF can produce.
+pub fn expand<H: Hash>(prk: &[u8], info: &[u8], length: usize) -> Result<Vec<u8>, InvalidLength> {
+    if prk.len() < H::OUTPUT_LENGTH || length > 255 * H::OUTPUT_LENGTH {
+        return Err(InvalidLength);
+    }
+
+    let mut okm = Vec::with_capacity(length + H::OUTPUT_LENGTH);
+    let mut block = Vec::new();
+    let mut counter = 1u8;
+
+    while okm.len() < length {
+        let next = hmac::<H>(prk, &[&block, info, &[counter]]);
+        block.zeroize();
+        block = next;
+
+        okm.extend_from_slice(&block);
+        counter = counter.wrapping_add(1);
+    }
+    block.zeroize();
+
+    // truncating keeps the discarded bytes in the allocation, so wipe them first
+    okm[length..].zeroize();
+    okm.truncate(length);
+
+    Ok(okm)
+}
+
+/// Extracts a pseudorandom key from `ikm` and `salt` and expands it into `length` bytes
+/// bound to `info`.
+///
+/// Fails if `length` is above `255 * H::OUTPUT_LENGTH`.
+pub fn derive<H: Hash>(
+    salt: &[u8],
+    ikm: &[u8],
+    info: &[u8],
+    length: usize,
+) -> Result<Vec<u8>, InvalidLength> {
+    let mut prk = extract::<H>(salt, ikm);
+    let okm = expand::<H>(&prk, info, length);
+    prk.zeroize();
+
+    okm
+}
@@ -1,19 +1,39 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::needless_range_loop)]
+
+extern crate alloc;
+
 pub mod aeads;
 pub mod ciphers;
+pub mod config;
 pub mod ecc;
+pub mod encoding;
 pub mod errors;
+pub mod hashes;
+pub mod kdfs;
 pub mod macs;
+pub mod padding;
+#[cfg(all(feature = "protected", unix))]
+pub mod protected;
+pub mod selftest;
 pub(crate) mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "aegis256")]
+use alloc::vec::Vec;
 
-pub fn encrypt(key: Vec<u8>, msg: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
-    aeads::aegis256::encrypt::<16>(&key, msg, nonce, ad)
+#[cfg(feature = "aegis256")]
+pub fn encrypt(key: &[u8], msg: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+    aeads::aegis256::encrypt::<16>(key, msg, nonce, ad)
 }
 
+#[cfg(feature = "aegis256")]
 pub fn decrypt(
-    key: Vec<u8>,
+    key: &[u8],
     msg: &[u8],
     nonce: &[u8],
     ad: &[u8],
 ) -> Result<Vec<u8>, errors::InvalidMac> {
-    aeads::aegis256::decrypt::<16>(&key, msg, nonce, ad)
+    aeads::aegis256::decrypt::<16>(key, msg, nonce, ad)
 }
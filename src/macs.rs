@@ -1 +1,5 @@
+#[cfg(feature = "aegis256")]
+pub mod aegismac;
+pub mod hmac;
+#[cfg(feature = "poly1305")]
 pub mod poly1305;
@@ -0,0 +1,264 @@
+//! AEGIS-MAC (draft-irtf-cfrg-aegis-aead): AEGISMAC-256 and AEGISMAC-128L, MACs built on
+//! the AEGIS state that run at close to AES speed on CPUs with AES instructions, far
+//! faster than HMAC.
+//!
+//! The data is absorbed like AEGIS associated data, and the tag binds both the length of
+//! the data and the length of the tag, so 16- and 32-byte tags of the same data are
+//! unrelated. Tags are `MAC_LENGTH` bytes, 16 or 32. The MACs run on the AES backend
+//! selected by [`aegis256::backend`](crate::aeads::aegis256::backend).
+//!
+//! Unlike encryption, AEGIS-MAC does not reveal anything when a nonce is reused, so a
+//! fixed nonce such as all zeros is fine when tags only need to depend on the key and the
+//! data.
+//!
+//! ```
+//! use raycrypt::macs::aegismac::{aegismac256, AegisMac256};
+//!
+//! let key = [0x42u8; 32];
+//! let nonce = [0u8; 32];
+//!
+//! let mut mac = AegisMac256::<32>::new(&key, &nonce);
+//! mac.update(b"first part, ");
+//! mac.update(b"second part");
+//! assert!(mac.verify(&aegismac256::<32>(&key, &nonce, b"first part, second part")));
+//! ```
+#[cfg(feature = "aegis128l")]
+use crate::aeads::aegis128l;
+use crate::aeads::aegis256::backends::{dispatch, AesBlock, State};
+use crate::aeads::aegis256::{backend, Backend};
+use crate::utils::constant_time::ct_eq;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+fn check_lengths<const MAC_LENGTH: usize>(key: &[u8], nonce: &[u8], length: usize) {
+    assert!(
+        MAC_LENGTH == 16 || MAC_LENGTH == 32,
+        "AEGIS-MAC tags are 16 or 32 bytes"
+    );
+    assert_eq!(key.len(), length, "the key has the wrong length");
+    assert_eq!(nonce.len(), length, "the nonce has the wrong length");
+}
+
+/// An incremental AEGISMAC-256 with a `MAC_LENGTH` byte tag, under a 32-byte key and
+/// nonce.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct AegisMac256<const MAC_LENGTH: usize> {
+    state: [[u8; 16]; 6],
+    buffer: [u8; 16],
+    buffered: usize,
+    length: usize,
+    #[zeroize(skip)]
+    backend: Backend,
+}
+
+#[inline(always)]
+fn absorb_256<Block: AesBlock>(state: &mut [[u8; 16]; 6], blocks: &[u8]) {
+    let mut loaded = State::<Block>::from_blocks(state);
+    for block in blocks.chunks_exact(16) {
+        loaded.absorb(block);
+    }
+
+    *state = loaded.blocks();
+}
+
+impl<const MAC_LENGTH: usize> AegisMac256<MAC_LENGTH> {
+    /// Starts a MAC under `key` and `nonce`.
+    ///
+    /// Panics if `MAC_LENGTH` is not 16 or 32, or if `key` or `nonce` is not 32 bytes.
+    pub fn new(key: &[u8], nonce: &[u8]) -> AegisMac256<MAC_LENGTH> {
+        AegisMac256::with_backend(backend(), key, nonce).unwrap()
+    }
+
+    /// Like [`AegisMac256::new`], but runs on `backend` instead of the detected one, or
+    /// returns `None` if the CPU does not support it.
+    pub fn with_backend(
+        backend: Backend,
+        key: &[u8],
+        nonce: &[u8],
+    ) -> Option<AegisMac256<MAC_LENGTH>> {
+        check_lengths::<MAC_LENGTH>(key, nonce, 32);
+        if !backend.is_supported() {
+            return None;
+        }
+
+        Some(AegisMac256 {
+            state: dispatch!(backend, Block => State::<Block>::new(key, nonce).blocks()),
+            buffer: [0u8; 16],
+            buffered: 0,
+            length: 0,
+            backend,
+        })
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len();
+
+        if self.buffered > 0 {
+            let taken = data.len().min(16 - self.buffered);
+            self.buffer[self.buffered..self.buffered + taken].copy_from_slice(&data[..taken]);
+            self.buffered += taken;
+            data = &data[taken..];
+
+            if self.buffered < 16 {
+                return;
+            }
+
+            let buffer = self.buffer;
+            dispatch!(self.backend, Block => absorb_256::<Block>(&mut self.state, &buffer));
+            self.buffered = 0;
+        }
+
+        let whole = data.len() - data.len() % 16;
+        dispatch!(self.backend, Block => absorb_256::<Block>(&mut self.state, &data[..whole]));
+
+        let rest = &data[whole..];
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    pub fn finalize(mut self) -> [u8; MAC_LENGTH] {
+        // the last partial block is zero padded, while the length counts only the data
+        if self.buffered > 0 {
+            let mut padded = [0u8; 16];
+            padded[..self.buffered].copy_from_slice(&self.buffer[..self.buffered]);
+            dispatch!(self.backend, Block => absorb_256::<Block>(&mut self.state, &padded));
+        }
+
+        let (state, length) = (&self.state, self.length);
+        dispatch!(self.backend, Block => {
+            State::<Block>::from_blocks(state).finalize::<MAC_LENGTH>(length, MAC_LENGTH)
+        })
+    }
+
+    /// Returns whether `tag` is the MAC, compared in constant time. A tag of any other
+    /// length is rejected.
+    pub fn verify(self, tag: &[u8]) -> bool {
+        ct_eq(&self.finalize(), tag)
+    }
+}
+
+/// Returns the AEGISMAC-256 tag of `data` under a 32-byte key and nonce.
+pub fn aegismac256<const MAC_LENGTH: usize>(
+    key: &[u8],
+    nonce: &[u8],
+    data: &[u8],
+) -> [u8; MAC_LENGTH] {
+    let mut mac = AegisMac256::<MAC_LENGTH>::new(key, nonce);
+    mac.update(data);
+
+    mac.finalize()
+}
+
+/// An incremental AEGISMAC-128L with a `MAC_LENGTH` byte tag, under a 16-byte key and
+/// nonce.
+#[cfg(feature = "aegis128l")]
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct AegisMac128L<const MAC_LENGTH: usize> {
+    state: [[u8; 16]; 8],
+    buffer: [u8; aegis128l::RATE],
+    buffered: usize,
+    length: usize,
+    #[zeroize(skip)]
+    backend: Backend,
+}
+
+#[cfg(feature = "aegis128l")]
+#[inline(always)]
+fn absorb_128l<Block: AesBlock>(state: &mut [[u8; 16]; 8], blocks: &[u8]) {
+    let mut loaded = aegis128l::State::<Block>::from_blocks(state);
+    for block in blocks.chunks_exact(aegis128l::RATE) {
+        loaded.absorb(block);
+    }
+
+    *state = loaded.blocks();
+}
+
+#[cfg(feature = "aegis128l")]
+impl<const MAC_LENGTH: usize> AegisMac128L<MAC_LENGTH> {
+    /// Starts a MAC under `key` and `nonce`.
+    ///
+    /// Panics if `MAC_LENGTH` is not 16 or 32, or if `key` or `nonce` is not 16 bytes.
+    pub fn new(key: &[u8], nonce: &[u8]) -> AegisMac128L<MAC_LENGTH> {
+        AegisMac128L::with_backend(backend(), key, nonce).unwrap()
+    }
+
+    /// Like [`AegisMac128L::new`], but runs on `backend` instead of the detected one, or
+    /// returns `None` if the CPU does not support it.
+    pub fn with_backend(
+        backend: Backend,
+        key: &[u8],
+        nonce: &[u8],
+    ) -> Option<AegisMac128L<MAC_LENGTH>> {
+        check_lengths::<MAC_LENGTH>(key, nonce, 16);
+        if !backend.is_supported() {
+            return None;
+        }
+
+        Some(AegisMac128L {
+            state: dispatch!(backend, Block => aegis128l::State::<Block>::new(key, nonce).blocks()),
+            buffer: [0u8; aegis128l::RATE],
+            buffered: 0,
+            length: 0,
+            backend,
+        })
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        const RATE: usize = aegis128l::RATE;
+        self.length += data.len();
+
+        if self.buffered > 0 {
+            let taken = data.len().min(RATE - self.buffered);
+            self.buffer[self.buffered..self.buffered + taken].copy_from_slice(&data[..taken]);
+            self.buffered += taken;
+            data = &data[taken..];
+
+            if self.buffered < RATE {
+                return;
+            }
+
+            let buffer = self.buffer;
+            dispatch!(self.backend, Block => absorb_128l::<Block>(&mut self.state, &buffer));
+            self.buffered = 0;
+        }
+
+        let whole = data.len() - data.len() % RATE;
+        dispatch!(self.backend, Block => absorb_128l::<Block>(&mut self.state, &data[..whole]));
+
+        let rest = &data[whole..];
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    pub fn finalize(mut self) -> [u8; MAC_LENGTH] {
+        // the last partial block is zero padded, while the length counts only the data
+        if self.buffered > 0 {
+            let mut padded = [0u8; aegis128l::RATE];
+            padded[..self.buffered].copy_from_slice(&self.buffer[..self.buffered]);
+            dispatch!(self.backend, Block => absorb_128l::<Block>(&mut self.state, &padded));
+        }
+
+        let (state, length) = (&self.state, self.length);
+        dispatch!(self.backend, Block => {
+            aegis128l::State::<Block>::from_blocks(state).finalize::<MAC_LENGTH>(length, MAC_LENGTH)
+        })
+    }
+
+    /// Returns whether `tag` is the MAC, compared in constant time. A tag of any other
+    /// length is rejected.
+    pub fn verify(self, tag: &[u8]) -> bool {
+        ct_eq(&self.finalize(), tag)
+    }
+}
+
+/// Returns the AEGISMAC-128L tag of `data` under a 16-byte key and nonce.
+#[cfg(feature = "aegis128l")]
+pub fn aegismac128l<const MAC_LENGTH: usize>(
+    key: &[u8],
+    nonce: &[u8],
+    data: &[u8],
+) -> [u8; MAC_LENGTH] {
+    let mut mac = AegisMac128L::<MAC_LENGTH>::new(key, nonce);
+    mac.update(data);
+
+    mac.finalize()
+}
@@ -0,0 +1,169 @@
+//! HMAC (RFC 2104) over SHA-256, SHA-512 or BLAKE2b-512.
+//!
+//! [`Hmac`] is generic over the [`Hash`] it is built on, and [`HmacSha256`],
+//! [`HmacSha512`] and [`HmacBlake2b`] name the common instances. Keys may have any
+//! length; keys longer than the hash's block are hashed first.
+//!
+//! ```
+//! use raycrypt::macs::hmac::HmacSha256;
+//!
+//! let mut mac = HmacSha256::new(b"key");
+//! mac.update(b"GET /v1/items");
+//! mac.update(b"?page=2");
+//! let tag = mac.finalize();
+//!
+//! let mut mac = HmacSha256::new(b"key");
+//! mac.update(b"GET /v1/items?page=2");
+//! assert!(mac.verify(&tag));
+//! ```
+#[cfg(feature = "blake2b")]
+use crate::hashes::blake2b::Blake2b;
+use crate::hashes::sha2::{Sha256, Sha512};
+use crate::utils::constant_time::ct_eq;
+use alloc::vec;
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+/// An incremental hash function HMAC, and so HKDF, can be instantiated with.
+pub trait Hash: Clone {
+    /// The block size of the hash, which HMAC pads its key to.
+    const BLOCK_SIZE: usize;
+    /// The length of a digest, and so of a MAC.
+    const OUTPUT_LENGTH: usize;
+
+    /// Starts an unkeyed hash.
+    fn new() -> Self;
+    fn update(&mut self, data: &[u8]);
+    fn finalize(self) -> Vec<u8>;
+}
+
+impl Hash for Sha256 {
+    const BLOCK_SIZE: usize = Sha256::BLOCK_SIZE;
+    const OUTPUT_LENGTH: usize = Sha256::OUTPUT_LENGTH;
+
+    fn new() -> Sha256 {
+        Sha256::new()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        Sha256::update(self, data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        Sha256::finalize(self).to_vec()
+    }
+}
+
+impl Hash for Sha512 {
+    const BLOCK_SIZE: usize = Sha512::BLOCK_SIZE;
+    const OUTPUT_LENGTH: usize = Sha512::OUTPUT_LENGTH;
+
+    fn new() -> Sha512 {
+        Sha512::new()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        Sha512::update(self, data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        Sha512::finalize(self).to_vec()
+    }
+}
+
+/// Unkeyed BLAKE2b with a 64-byte output, used inside HMAC like any other hash rather
+/// than through BLAKE2b's own keyed mode.
+#[cfg(feature = "blake2b")]
+impl Hash for Blake2b {
+    const BLOCK_SIZE: usize = crate::hashes::blake2b::BLOCK_SIZE;
+    const OUTPUT_LENGTH: usize = 64;
+
+    fn new() -> Blake2b {
+        Blake2b::new(64)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        Blake2b::update(self, data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        Blake2b::finalize(self)
+    }
+}
+
+/// An incremental HMAC over the hash `H`.
+#[derive(Clone)]
+pub struct Hmac<H: Hash> {
+    inner: H,
+    outer: H,
+}
+
+pub type HmacSha256 = Hmac<Sha256>;
+pub type HmacSha512 = Hmac<Sha512>;
+#[cfg(feature = "blake2b")]
+pub type HmacBlake2b = Hmac<Blake2b>;
+
+impl<H: Hash> Hmac<H> {
+    /// Starts a MAC under `key`, which may have any length.
+    pub fn new(key: &[u8]) -> Hmac<H> {
+        let mut block = vec![0u8; H::BLOCK_SIZE];
+        if key.len() > H::BLOCK_SIZE {
+            let mut hasher = H::new();
+            hasher.update(key);
+            let mut digest = hasher.finalize();
+            block[..digest.len()].copy_from_slice(&digest);
+            digest.zeroize();
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = H::new();
+        block.iter_mut().for_each(|byte| *byte ^= 0x36);
+        inner.update(&block);
+
+        let mut outer = H::new();
+        block.iter_mut().for_each(|byte| *byte ^= 0x36 ^ 0x5c);
+        outer.update(&block);
+
+        block.zeroize();
+
+        Hmac { inner, outer }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    /// Returns the MAC, `H::OUTPUT_LENGTH` bytes.
+    pub fn finalize(self) -> Vec<u8> {
+        let Hmac { inner, mut outer } = self;
+
+        let mut digest = inner.finalize();
+        outer.update(&digest);
+        digest.zeroize();
+
+        outer.finalize()
+    }
+
+    /// Returns whether `tag` is the MAC, compared in constant time. A tag of any length
+    /// other than `H::OUTPUT_LENGTH` is rejected.
+    pub fn verify(self, tag: &[u8]) -> bool {
+        ct_eq(&self.finalize(), tag)
+    }
+}
+
+/// Returns the HMAC of `message` under `key`.
+pub fn hmac<H: Hash>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<H>::new(key);
+    mac.update(message);
+
+    mac.finalize()
+}
+
+/// Returns whether `tag` is the HMAC of `message` under `key`, compared in constant time.
+pub fn verify<H: Hash>(key: &[u8], message: &[u8], tag: &[u8]) -> bool {
+    let mut mac = Hmac::<H>::new(key);
+    mac.update(message);
+
+    mac.verify(tag)
+}
@@ -1,5 +1,9 @@
+use crate::utils::constant_time::{ct_eq, ct_select};
 use crate::utils::*;
+use alloc::vec::Vec;
+use zeroize::ZeroizeOnDrop;
 
+#[derive(ZeroizeOnDrop)]
 pub struct Poly1305 {
     r: [u32; 5],
     h: [u32; 5],
@@ -46,7 +50,7 @@ impl Poly1305 {
     }
 
     fn finish(&mut self) {
-        let mut h = self.h.clone();
+        let mut h = self.h;
 
         let mut c: u32 = 0;
 
@@ -58,7 +62,7 @@ impl Poly1305 {
 
         h[0] += c * 5;
         c = h[0] >> 26;
-        h[0] = h[0] & 0x3ffffff;
+        h[0] &= 0x3ffffff;
         h[1] += c;
 
         let mut g = [0u32; 5];
@@ -73,16 +77,12 @@ impl Poly1305 {
 
         g[4] = h[4].wrapping_add(c).wrapping_sub(1 << 26);
 
-        let mut mask = (g[4] >> (32 - 1)).wrapping_sub(1);
-
-        for i in g.iter_mut() {
-            *i &= mask;
-        }
-
-        mask = !mask;
+        // g = h - p, which is the reduced value unless the subtraction borrowed into the
+        // top bit of g[4]
+        let reduce = (g[4] >> 31) ^ 1;
 
         for i in 0..5 {
-            h[i] = (h[i] & mask) | g[i];
+            h[i] = ct_select(reduce, g[i], h[i]);
         }
 
         h[0] |= h[1] << 26;
@@ -113,6 +113,7 @@ impl Poly1305 {
 }
 
 impl Poly1305 {
+    /// Creates a MAC under a 32-byte one-time key. Panics if `key` is shorter.
     pub fn new(key: &[u8]) -> Poly1305 {
         let mut r = [0u32; 5];
         r[0] = (from_le_bytes(&key[0..4])) & 0x3ffffff;
@@ -141,6 +142,8 @@ impl Poly1305 {
         }
     }
 
+    /// Authenticates `data` zero-padded to a multiple of 16 bytes, as ChaCha-Poly1305 does
+    /// with its AAD and ciphertext (RFC 8439 §2.8).
     pub fn update(&mut self, data: &[u8]) {
         for chunk in data.chunks(16) {
             let mut m = [0u8; 16];
@@ -149,6 +152,9 @@ impl Poly1305 {
         }
     }
 
+    /// Authenticates `data` as plain Poly1305 does (RFC 8439 §2.5). A message may be split
+    /// across calls, but only its last part may have a length that is not a multiple of
+    /// 16 bytes.
     pub fn update_unpadded(&mut self, data: &[u8]) {
         for chunk in data.chunks(16) {
             if chunk.len() == 16 {
@@ -170,7 +176,28 @@ impl Poly1305 {
         self.output.to_vec()
     }
 
+    /// Returns whether `other` equals the tag. The comparison runs in constant time over
+    /// all 16 bytes, and a tag of any other length is rejected.
     pub fn verify(&mut self, other: &[u8]) -> bool {
-        const_time_eq(&self.tag(), other)
+        ct_eq(&self.tag(), other)
     }
 }
+
+/// Computes the Poly1305 tag of `message` under a 32-byte one-time key.
+pub fn poly1305(key: &[u8], message: &[u8]) -> [u8; 16] {
+    let mut mac = Poly1305::new(key);
+    mac.update_unpadded(message);
+
+    let mut tag = [0u8; 16];
+    tag.copy_from_slice(&mac.tag());
+
+    tag
+}
+
+/// Returns whether `tag` is the Poly1305 tag of `message`, compared in constant time.
+pub fn verify(key: &[u8], message: &[u8], tag: &[u8]) -> bool {
+    let mut mac = Poly1305::new(key);
+    mac.update_unpadded(message);
+
+    mac.verify(tag)
+}
@@ -0,0 +1,110 @@
+//! Padding that hides the exact length of a message before it is encrypted.
+//!
+//! An AEAD ciphertext is exactly as long as its plaintext plus the tag, so the length of
+//! every message is public. Padding rounds it up first: [`Padding::Block`] to a multiple
+//! of a block size, and [`Padding::Padme`] to one of a small set of lengths that grows
+//! with the message, so that at most about 12% is added, and a length leaks only
+//! O(log log L) bits rather than O(log L) (Nikitin et al., "Reducing Metadata Leakage
+//! from Encrypted Files and Communication with PURBs", 2019).
+//!
+//! Both append the ISO/IEC 7816-4 marker, a 0x80 byte followed by zeros, so [`unpad`]
+//! removes either without knowing which was used. It reads every byte whatever the
+//! padding is, so the time taken depends only on the padded length.
+//!
+//! ```
+//! use raycrypt::padding::{pad, unpad, Padding};
+//!
+//! let padded = pad(b"message", Padding::Block(16));
+//! assert_eq!(padded, b"message\x80\0\0\0\0\0\0\0\0");
+//! assert_eq!(unpad(&padded).unwrap(), b"message");
+//!
+//! assert_eq!(pad(&[0; 1000], Padding::Padme).len(), 1024);
+//! ```
+pub use crate::errors::InvalidPadding;
+use alloc::vec::Vec;
+
+/// How far [`pad`] extends a message.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Padding {
+    /// To the next multiple of the block size, which must not be zero. A message that
+    /// is already a multiple gains a whole block, as the marker always has to fit.
+    Block(usize),
+    /// To the next length [`padme_length`] allows.
+    Padme,
+}
+
+/// Returns the smallest length at least `len` that PADMÉ pads to: `len` rounded up so
+/// that only its top `log2(log2(len)) + 1` bits may be set.
+///
+/// ```
+/// use raycrypt::padding::padme_length;
+///
+/// assert_eq!(padme_length(9), 10);
+/// assert_eq!(padme_length(1000), 1024);
+/// assert_eq!(padme_length(1025), 1088);
+/// ```
+pub fn padme_length(len: usize) -> usize {
+    if len <= 1 {
+        return len;
+    }
+
+    let exponent = len.ilog2();
+    let significant = exponent.ilog2() + 1;
+    let mask = (1usize << (exponent - significant)) - 1;
+
+    (len + mask) & !mask
+}
+
+/// Returns the length of a `len` byte message after [`pad`].
+pub fn padded_length(len: usize, padding: Padding) -> usize {
+    match padding {
+        Padding::Block(block_size) => {
+            assert!(block_size > 0, "the block size must not be zero");
+            (len / block_size + 1) * block_size
+        }
+        Padding::Padme => padme_length(len + 1),
+    }
+}
+
+/// Appends the marker byte and zeros to `data`, up to [`padded_length`].
+///
+/// Panics if the block size of [`Padding::Block`] is zero.
+pub fn pad(data: &[u8], padding: Padding) -> Vec<u8> {
+    let length = padded_length(data.len(), padding);
+
+    let mut padded = Vec::with_capacity(length);
+    padded.extend_from_slice(data);
+    padded.push(0x80);
+    padded.resize(length, 0);
+
+    padded
+}
+
+/// Strips the padding [`pad`] added, of either kind. Fails unless `padded` ends in the
+/// marker byte followed only by zeros.
+pub fn unpad(padded: &[u8]) -> Result<&[u8], InvalidPadding> {
+    let mut found = 0u8;
+    let mut invalid = 0u8;
+    let mut length = 0usize;
+
+    for (i, &byte) in padded.iter().enumerate().rev() {
+        let searching = !found;
+        let marker = searching & eq(byte, 0x80);
+
+        // every byte after the marker has to be zero
+        invalid |= searching & !marker & !eq(byte, 0);
+        length |= i & ((marker & 1) as usize).wrapping_neg();
+        found |= marker;
+    }
+
+    if found == 0 || invalid != 0 {
+        return Err(InvalidPadding);
+    }
+
+    Ok(&padded[..length])
+}
+
+/// Returns 0xff if `x == y` and 0 otherwise.
+fn eq(x: u8, y: u8) -> u8 {
+    ((0u16.wrapping_sub((x ^ y) as u16) >> 8) as u8) ^ 0xff
+}
@@ -0,0 +1,250 @@
+//! Locked, guarded memory for long-lived keys, like libsodium's `sodium_malloc`.
+//!
+//! A [`SecretBox`] is its own anonymous mapping, never part of the heap:
+//!
+//! - its pages are locked with `mlock`, so they are never written to swap, and on Linux
+//!   they are left out of core dumps;
+//! - it sits between two inaccessible guard pages, and its data ends right at the
+//!   second, so reading or writing past either end faults instead of reaching other
+//!   memory;
+//! - it is zeroed before it is unmapped.
+//!
+//! [`ProtectedAead`] keeps the key of an AEAD in a [`SecretBox`] for as long as the
+//! cipher lives, which suits session keys of servers. The operating system limits how
+//! much memory a process may lock (`ulimit -l`), and every box takes at least three
+//! pages, so boxes are for a few keys rather than for bulk data.
+//!
+//! ```
+//! use raycrypt::aeads::xchachapoly1305::XChaChaPoly1305;
+//! use raycrypt::aeads::Aead;
+//! use raycrypt::protected::{ProtectedAead, SecretBox};
+//!
+//! let key = SecretBox::generate(32).unwrap();
+//! let cipher = ProtectedAead::<XChaChaPoly1305>::new(key);
+//!
+//! let nonce = [0u8; 24];
+//! let ciphertext = cipher.encrypt(b"message", &nonce, b"ad");
+//! assert_eq!(cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap(), b"message");
+//! ```
+use crate::aeads::Aead;
+use crate::errors::TagMismatch;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+use getrandom::getrandom;
+use std::io;
+use zeroize::Zeroize;
+
+fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+fn check(result: libc::c_int) -> io::Result<()> {
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Secret bytes in locked memory between guard pages, zeroed on drop.
+///
+/// The contents are only reachable through [`SecretBox::expose`] and
+/// [`SecretBox::expose_mut`], and `Debug` prints the length alone.
+pub struct SecretBox {
+    /// The start of the mapping, at the first guard page.
+    mapping: NonNull<u8>,
+    mapping_len: usize,
+    /// The start of the data, which ends at the second guard page.
+    data: NonNull<u8>,
+    len: usize,
+}
+
+// the box owns its mapping exclusively, like a Box<[u8]>
+unsafe impl Send for SecretBox {}
+unsafe impl Sync for SecretBox {}
+
+impl SecretBox {
+    /// Maps, guards and locks `len` zero bytes. Fails if the system refuses any step,
+    /// typically because locking would exceed the process's limit.
+    pub fn new(len: usize) -> io::Result<SecretBox> {
+        let page = page_size();
+        let data_len = len
+            .div_ceil(page)
+            .max(1)
+            .checked_mul(page)
+            .filter(|data_len| *data_len <= isize::MAX as usize - 2 * page)
+            .ok_or(io::ErrorKind::OutOfMemory)?;
+        let mapping_len = data_len + 2 * page;
+
+        let mapping = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                mapping_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if mapping == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mapping = mapping.cast::<u8>();
+        // from here on, dropping the box unmaps it again if a step fails
+        let secret = unsafe {
+            SecretBox {
+                mapping: NonNull::new_unchecked(mapping),
+                mapping_len,
+                data: NonNull::new_unchecked(mapping.add(page + data_len - len)),
+                len,
+            }
+        };
+
+        unsafe {
+            let data_pages = mapping.add(page);
+            check(libc::mprotect(mapping.cast(), page, libc::PROT_NONE))?;
+            check(libc::mprotect(
+                data_pages.add(data_len).cast(),
+                page,
+                libc::PROT_NONE,
+            ))?;
+            check(libc::mlock(data_pages.cast(), data_len))?;
+
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            check(libc::madvise(
+                data_pages.cast(),
+                data_len,
+                libc::MADV_DONTDUMP,
+            ))?;
+        }
+
+        Ok(secret)
+    }
+
+    /// Moves a copy of `data` into a new box. The caller should wipe its own copy.
+    pub fn from_slice(data: &[u8]) -> io::Result<SecretBox> {
+        let mut secret = SecretBox::new(data.len())?;
+        secret.expose_mut().copy_from_slice(data);
+
+        Ok(secret)
+    }
+
+    /// Fills a new box with `len` bytes from the operating system's random number
+    /// generator, so the key is never held anywhere else.
+    ///
+    /// Panics if the random number generator fails.
+    pub fn generate(len: usize) -> io::Result<SecretBox> {
+        let mut secret = SecretBox::new(len)?;
+        getrandom(secret.expose_mut()).expect("failed to read random bytes");
+
+        Ok(secret)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn expose(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.data.as_ptr(), self.len) }
+    }
+
+    pub fn expose_mut(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.data.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for SecretBox {
+    fn drop(&mut self) {
+        self.expose_mut().zeroize();
+
+        let page = page_size();
+        unsafe {
+            // the data pages may not be locked if creation failed, which munlock ignores
+            libc::munlock(
+                self.mapping.as_ptr().add(page).cast(),
+                self.mapping_len - 2 * page,
+            );
+            libc::munmap(self.mapping.as_ptr().cast(), self.mapping_len);
+        }
+    }
+}
+
+impl fmt::Debug for SecretBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretBox")
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An AEAD whose key lives in a [`SecretBox`].
+///
+/// Each call creates the cipher from the boxed key and drops it afterwards, as
+/// [`XChaChaPoly1305`](crate::aeads::xchachapoly1305::XChaChaPoly1305) does with its
+/// subkeys, so the key is only copied out of the box while a message is processed, and
+/// the ciphers of this crate wipe that copy when they are dropped. Ciphers with a key
+/// schedule, such as AES-GCM, redo it for every message.
+pub struct ProtectedAead<A: Aead> {
+    key: SecretBox,
+    aead: PhantomData<fn() -> A>,
+}
+
+impl<A: Aead> ProtectedAead<A> {
+    /// Panics if `key` is not as long as the key of `A`.
+    pub fn new(key: SecretBox) -> ProtectedAead<A> {
+        assert_eq!(key.len(), A::KEY_LENGTH, "the key has the wrong length");
+
+        ProtectedAead {
+            key,
+            aead: PhantomData,
+        }
+    }
+
+    fn cipher(&self) -> A {
+        A::from_key(self.key.expose())
+    }
+}
+
+impl<A: Aead> Aead for ProtectedAead<A> {
+    const KEY_LENGTH: usize = A::KEY_LENGTH;
+    const NONCE_LENGTH: usize = A::NONCE_LENGTH;
+    const TAG_LENGTH: usize = A::TAG_LENGTH;
+
+    type Tag = A::Tag;
+
+    /// Copies the key into a new [`SecretBox`]. Panics if the key has the wrong length or
+    /// the box cannot be created, see [`ProtectedAead::new`] to handle the latter.
+    fn from_key(key: &[u8]) -> Self {
+        let key = SecretBox::from_slice(key).expect("failed to lock the key");
+        ProtectedAead::new(key)
+    }
+
+    fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        self.cipher().encrypt(plaintext, nonce, ad)
+    }
+
+    fn decrypt(&self, ciphertext: &[u8], nonce: &[u8], ad: &[u8]) -> Result<Vec<u8>, TagMismatch> {
+        self.cipher().decrypt(ciphertext, nonce, ad)
+    }
+
+    fn encrypt_detached(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> (Vec<u8>, Self::Tag) {
+        self.cipher().encrypt_detached(plaintext, nonce, ad)
+    }
+
+    fn decrypt_detached(
+        &self,
+        ciphertext: &[u8],
+        tag: &Self::Tag,
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, TagMismatch> {
+        self.cipher().decrypt_detached(ciphertext, tag, nonce, ad)
+    }
+}
@@ -0,0 +1,242 @@
+//! Known-answer tests of the algorithms built into the crate, for environments that have
+//! to check their cryptography at startup.
+//!
+//! [`self_test`] runs one published test vector per algorithm on the backend the CPU
+//! selects: RFC 8439 for Poly1305 and ChaCha20-Poly1305, draft-irtf-cfrg-xchacha for
+//! XChaCha20-Poly1305, draft-irtf-cfrg-aegis-aead for AEGIS, the GCM specification for
+//! AES-256-GCM, RFC 7693 for BLAKE2 and FIPS 180 for SHA-2. Every AEAD has to encrypt to
+//! the expected output, decrypt it back and reject it with a flipped tag bit.
+//!
+//! ```
+//! raycrypt::selftest::self_test().expect("the known-answer tests failed");
+//! ```
+use crate::aeads::Aead;
+pub use crate::errors::SelfTestError;
+
+/// The message of the RFC 8439 and XChaCha test vectors.
+#[cfg(all(feature = "chacha", feature = "poly1305"))]
+const SUNSCREEN: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+
+/// Decodes a lowercase hex string at compile time.
+const fn unhex<const N: usize>(hex: &str) -> [u8; N] {
+    const fn nibble(digit: u8) -> u8 {
+        match digit {
+            b'0'..=b'9' => digit - b'0',
+            b'a'..=b'f' => digit - b'a' + 10,
+            _ => panic!("invalid hex digit"),
+        }
+    }
+
+    let hex = hex.as_bytes();
+    assert!(hex.len() == 2 * N, "wrong hex length");
+
+    let mut output = [0u8; N];
+    let mut i = 0;
+    while i < N {
+        output[i] = nibble(hex[2 * i]) << 4 | nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+
+    output
+}
+
+fn check(passed: bool, algorithm: &'static str) -> Result<(), SelfTestError> {
+    if passed {
+        Ok(())
+    } else {
+        Err(SelfTestError { algorithm })
+    }
+}
+
+/// Checks that `A` encrypts `plaintext` to `expected`, decrypts it back, and rejects it
+/// once a bit of the tag is flipped.
+// unused when every AEAD feature is off
+#[allow(dead_code)]
+fn check_aead<A: Aead>(
+    algorithm: &'static str,
+    key: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+    plaintext: &[u8],
+    expected: &[u8],
+) -> Result<(), SelfTestError> {
+    let cipher = A::from_key(key);
+
+    let mut tampered = expected.to_vec();
+    tampered[expected.len() - 1] ^= 1;
+
+    check(
+        cipher.encrypt(plaintext, nonce, ad) == expected
+            && cipher.decrypt(expected, nonce, ad).as_deref() == Ok(plaintext)
+            && cipher.decrypt(&tampered, nonce, ad).is_err(),
+        algorithm,
+    )
+}
+
+/// Runs the known-answer tests of every algorithm enabled by the crate's features, and
+/// fails with the first algorithm whose output is wrong.
+pub fn self_test() -> Result<(), SelfTestError> {
+    #[cfg(feature = "poly1305")]
+    {
+        const KEY: [u8; 32] =
+            unhex("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b");
+        const TAG: [u8; 16] = unhex("a8061dc1305136c6c22b8baf0c0127a9");
+
+        let message = b"Cryptographic Forum Research Group";
+        check(
+            crate::macs::poly1305::poly1305(&KEY, message) == TAG,
+            "Poly1305",
+        )?;
+    }
+
+    #[cfg(all(feature = "chacha", feature = "poly1305"))]
+    {
+        const KEY: [u8; 32] =
+            unhex("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f");
+        const NONCE: [u8; 12] = unhex("070000004041424344454647");
+        const AD: [u8; 12] = unhex("50515253c0c1c2c3c4c5c6c7");
+        const EXPECTED: [u8; 130] = unhex(concat!(
+            "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca96712",
+            "82fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58",
+            "fab324e4fad675945585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b61161ae10b594f09",
+            "e26a7e902ecbd0600691",
+        ));
+
+        check_aead::<crate::aeads::chachapoly1305::ChaChaPoly1305>(
+            "ChaCha-Poly1305",
+            &KEY,
+            &NONCE,
+            &AD,
+            SUNSCREEN,
+            &EXPECTED,
+        )?;
+    }
+
+    #[cfg(feature = "xchacha")]
+    {
+        const KEY: [u8; 32] =
+            unhex("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f");
+        const NONCE: [u8; 24] = unhex("404142434445464748494a4b4c4d4e4f5051525354555657");
+        const AD: [u8; 12] = unhex("50515253c0c1c2c3c4c5c6c7");
+        const EXPECTED: [u8; 130] = unhex(concat!(
+            "bd6d179d3e83d43b9576579493c0e939572a1700252bfaccbed2902c21396cbb731c7f1b0b4aa644",
+            "0bf3a82f4eda7e39ae64c6708c54c216cb96b72e1213b4522f8c9ba40db5d945b11b69b982c1bb9e",
+            "3f3fac2bc369488f76b2383565d3fff921f9664c97637da9768812f615c68b13b52ec0875924c1c7",
+            "987947deafd8780acf49",
+        ));
+
+        check_aead::<crate::aeads::xchachapoly1305::XChaChaPoly1305>(
+            "XChaCha-Poly1305",
+            &KEY,
+            &NONCE,
+            &AD,
+            SUNSCREEN,
+            &EXPECTED,
+        )?;
+    }
+
+    #[cfg(feature = "aegis256")]
+    {
+        const KEY: [u8; 32] =
+            unhex("1001000000000000000000000000000000000000000000000000000000000000");
+        const NONCE: [u8; 32] =
+            unhex("1000020000000000000000000000000000000000000000000000000000000000");
+        const EXPECTED: [u8; 32] =
+            unhex("754fc3d8c973246dcc6d741412a4b2363fe91994768b332ed7f570a19ec5896e");
+
+        check_aead::<crate::aeads::aegis256::Aegis256>(
+            "AEGIS-256",
+            &KEY,
+            &NONCE,
+            b"",
+            &[0u8; 16],
+            &EXPECTED,
+        )?;
+    }
+
+    #[cfg(feature = "aegis256")]
+    {
+        const KEY: [u8; 32] =
+            unhex("1001000000000000000000000000000000000000000000000000000000000000");
+        const NONCE: [u8; 32] =
+            unhex("1000020000000000000000000000000000000000000000000000000000000000");
+        const DATA: [u8; 35] =
+            unhex("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f202122");
+        const TAG: [u8; 16] = unhex("c08e20cfc56f27195a46c9cef5c162d4");
+
+        check(
+            crate::macs::aegismac::aegismac256::<16>(&KEY, &NONCE, &DATA) == TAG,
+            "AEGISMAC-256",
+        )?;
+    }
+
+    #[cfg(feature = "aegis128l")]
+    {
+        const KEY: [u8; 16] = unhex("10010000000000000000000000000000");
+        const NONCE: [u8; 16] = unhex("10000200000000000000000000000000");
+        const EXPECTED: [u8; 32] =
+            unhex("c1c0e58bd913006feba00f4b3cc3594eabe0ece80c24868a226a35d16bdae37a");
+
+        check_aead::<crate::aeads::aegis128l::Aegis128L>(
+            "AEGIS-128L",
+            &KEY,
+            &NONCE,
+            b"",
+            &[0u8; 16],
+            &EXPECTED,
+        )?;
+    }
+
+    #[cfg(feature = "aes256gcm")]
+    {
+        const EXPECTED: [u8; 32] =
+            unhex("cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919");
+
+        check_aead::<crate::aeads::aes256gcm::Aes256Gcm>(
+            "AES-256-GCM",
+            &[0u8; 32],
+            &[0u8; 12],
+            b"",
+            &[0u8; 16],
+            &EXPECTED,
+        )?;
+    }
+
+    #[cfg(feature = "blake2b")]
+    {
+        const DIGEST: [u8; 64] = unhex(concat!(
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1",
+            "7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+        ));
+
+        check(
+            crate::hashes::blake2b::blake2b(b"abc", 64) == DIGEST,
+            "BLAKE2b",
+        )?;
+    }
+
+    #[cfg(feature = "blake2s")]
+    {
+        const DIGEST: [u8; 32] =
+            unhex("508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982");
+
+        check(
+            crate::hashes::blake2s::blake2s(b"abc", 32) == DIGEST,
+            "BLAKE2s",
+        )?;
+    }
+
+    {
+        const SHA256: [u8; 32] =
+            unhex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        const SHA512: [u8; 64] = unhex(concat!(
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a",
+            "2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+        ));
+
+        check(crate::hashes::sha2::sha256(b"abc") == SHA256, "SHA-256")?;
+        check(crate::hashes::sha2::sha512(b"abc") == SHA512, "SHA-512")?;
+    }
+
+    Ok(())
+}
@@ -1,13 +1,38 @@
+pub(crate) mod constant_time;
+
+#[cfg(any(feature = "chacha", feature = "poly1305"))]
 pub(crate) fn from_le_bytes(x: &[u8]) -> u32 {
     u32::from_le_bytes([x[0], x[1], x[2], x[3]])
 }
 
-pub(crate) fn const_time_eq(a: &[u8], b: &[u8]) -> bool {
-    let mut temp = 0;
+/// Returns whether the running CPU supports all of the x86 target features given. Without
+/// the `std` feature there is no runtime detection, and this is whether the crate was
+/// compiled with the target features enabled.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+macro_rules! x86_feature_detected {
+    ($($feature:tt),+) => {{
+        #[cfg(feature = "std")]
+        let detected = $(std::is_x86_feature_detected!($feature))&&+;
+        #[cfg(not(feature = "std"))]
+        let detected = cfg!(all($(target_feature = $feature),+));
+        detected
+    }};
+}
 
-    for (i, j) in a.iter().zip(b.iter()) {
-        temp |= i ^ j;
-    }
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub(crate) use x86_feature_detected;
 
-    temp == 0
+/// Like [`x86_feature_detected`], for aarch64 target features.
+#[cfg(all(target_arch = "aarch64", any(feature = "chacha", feature = "aegis256")))]
+macro_rules! aarch64_feature_detected {
+    ($($feature:tt),+) => {{
+        #[cfg(feature = "std")]
+        let detected = $(std::arch::is_aarch64_feature_detected!($feature))&&+;
+        #[cfg(not(feature = "std"))]
+        let detected = cfg!(all($(target_feature = $feature),+));
+        detected
+    }};
 }
+
+#[cfg(all(target_arch = "aarch64", any(feature = "chacha", feature = "aegis256")))]
+pub(crate) use aarch64_feature_detected;
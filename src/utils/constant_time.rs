@@ -0,0 +1,55 @@
+//! Comparisons and selections whose timing does not depend on secret data, for tags,
+//! MACs and the conditional steps of field arithmetic.
+
+/// Compares two byte strings in time that depends only on their lengths.
+///
+/// The XOR differences of all bytes are accumulated before the single branch on the
+/// result, and the accumulator goes through `black_box` so the compiler cannot turn the
+/// loop back into an early exit. Inputs of different lengths are never equal; lengths
+/// are not secret.
+pub(crate) fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut temp = 0;
+
+    for (i, j) in a.iter().zip(b.iter()) {
+        temp |= i ^ j;
+    }
+
+    core::hint::black_box(temp) == 0
+}
+
+/// Returns `a` if `choice` is 1 and `b` if it is 0, without branching on `choice`.
+///
+/// `choice` must be 0 or 1. It goes through `black_box` so the compiler cannot see that
+/// it is a boolean and branch on it after all.
+#[cfg(feature = "poly1305")]
+#[inline(always)]
+pub(crate) fn ct_select(choice: u32, a: u32, b: u32) -> u32 {
+    let mask = core::hint::black_box(choice).wrapping_neg();
+
+    (a & mask) | (b & !mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ct_eq() {
+        assert!(ct_eq(b"", b""));
+        assert!(ct_eq(b"tag", b"tag"));
+        assert!(!ct_eq(b"tag", b"tab"));
+        assert!(!ct_eq(b"tag", b"tags"));
+    }
+
+    #[cfg(feature = "poly1305")]
+    #[test]
+    fn test_ct_select() {
+        assert_eq!(ct_select(1, 0xdead_beef, 7), 0xdead_beef);
+        assert_eq!(ct_select(0, 0xdead_beef, 7), 7);
+        assert_eq!(ct_select(1, 0, u32::MAX), 0);
+    }
+}
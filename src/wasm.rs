@@ -0,0 +1,396 @@
+//! wasm-bindgen bindings for XChaCha20-Poly1305 and XChaCha20-SIV, so the same
+//! implementation runs in browsers and other JavaScript hosts.
+//!
+//! The bindings are built from a `cdylib`, which the crate does not declare itself as it
+//! would break `no_std` targets without a panic handler:
+//!
+//! ```text
+//! cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/raycrypt.wasm
+//! ```
+//!
+//! Byte arrays are passed as `Uint8Array`s, and every error is thrown as a
+//! [`CryptoError`] whose `kind` is an `ErrorKind`. Keys are 32 bytes and nonces 24 bytes;
+//! [`generate_key`] and [`generate_nonce`] draw them from `crypto.getRandomValues`.
+//! The ciphers also take keys as hex or Base64 with `fromHex` and `fromBase64`. Their
+//! keys can only be read back, e.g. to pass the cipher to a worker, if they were created
+//! `extractable`, as with WebCrypto.
+//! [`XChaChaSiv`] takes nonces of any length, or none, and is the safer choice where
+//! nonces cannot be kept unique. [`Blake3`] hashes data passed in any number of pieces,
+//! such as the chunks of a stream.
+//!
+//! ```js
+//! import init, { ErrorKind, XChaChaPoly1305, generateKey, generateNonce } from "./pkg/raycrypt.js";
+//!
+//! await init();
+//! const cipher = new XChaChaPoly1305(generateKey());
+//! const nonce = generateNonce();
+//! const ciphertext = cipher.encrypt(plaintext, nonce, ad);
+//!
+//! try {
+//!     const decrypted = cipher.decrypt(ciphertext, nonce, ad);
+//! } catch (error) {
+//!     if (error.kind !== ErrorKind.InvalidTag) throw error;
+//!     // the message was tampered with
+//! }
+//! ```
+use crate::aeads::nonce::NonceGenerator;
+use crate::aeads::xchachapoly1305::{self, KEY_LENGTH, NONCE_LENGTH};
+use crate::aeads::xchachasiv;
+use crate::encoding::{self, Base64};
+use crate::errors;
+use crate::hashes::blake3;
+use alloc::string::String;
+use alloc::vec::Vec;
+use wasm_bindgen::prelude::*;
+use zeroize::{ZeroizeOnDrop, Zeroizing};
+
+/// XChaCha20-Poly1305 under a fixed key.
+#[wasm_bindgen]
+pub struct XChaChaPoly1305 {
+    cipher: xchachapoly1305::XChaChaPoly1305,
+    key: ExportableKey,
+}
+
+#[wasm_bindgen]
+impl XChaChaPoly1305 {
+    /// Throws if `key` is not 32 bytes. Unless `extractable` is true, the key cannot be
+    /// read back with `exportKey`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(key: &[u8], extractable: Option<bool>) -> Result<XChaChaPoly1305, CryptoError> {
+        if key.len() != KEY_LENGTH {
+            return Err(errors::CryptoError::InvalidKeyLength.into());
+        }
+
+        Ok(XChaChaPoly1305 {
+            cipher: xchachapoly1305::XChaChaPoly1305::new(key, None),
+            key: ExportableKey::new(key, extractable),
+        })
+    }
+
+    /// Creates the cipher from a hex key, such as one read from a configuration file.
+    #[wasm_bindgen(js_name = fromHex)]
+    pub fn from_hex(
+        encoded: &str,
+        extractable: Option<bool>,
+    ) -> Result<XChaChaPoly1305, CryptoError> {
+        let key = Zeroizing::new(encoding::decode_hex(encoded)?);
+        XChaChaPoly1305::new(&key, extractable)
+    }
+
+    /// Creates the cipher from a Base64 key, with or without padding and whitespace.
+    #[wasm_bindgen(js_name = fromBase64)]
+    pub fn from_base64(
+        encoded: &str,
+        url_safe: bool,
+        extractable: Option<bool>,
+    ) -> Result<XChaChaPoly1305, CryptoError> {
+        let key = Zeroizing::new(decode_base64_key(encoded, url_safe)?);
+        XChaChaPoly1305::new(&key, extractable)
+    }
+
+    /// Returns the key, to hand the cipher to a worker or store it. Throws unless the
+    /// cipher was created extractable.
+    #[wasm_bindgen(js_name = exportKey)]
+    pub fn export_key(&self) -> Result<Vec<u8>, CryptoError> {
+        self.key.export()
+    }
+
+    /// Returns `ciphertext || tag`. Throws if `nonce` is not 24 bytes.
+    pub fn encrypt(
+        &self,
+        plaintext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        if nonce.len() != NONCE_LENGTH {
+            return Err(errors::CryptoError::InvalidNonceLength.into());
+        }
+
+        // with a valid nonce, the only failure left is a message too long for the counter
+        self.cipher
+            .try_encrypt(plaintext, nonce, ad)
+            .map_err(|_| errors::CryptoError::CounterOverflow.into())
+    }
+
+    /// Throws if `nonce` is not 24 bytes or the message does not authenticate.
+    pub fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        Ok(self.cipher.try_decrypt(ciphertext, nonce, ad)?)
+    }
+
+    /// Encrypts under a random nonce and returns `nonce || ciphertext || tag`.
+    #[wasm_bindgen(js_name = encryptWithRandomNonce)]
+    pub fn encrypt_with_random_nonce(&self, plaintext: &[u8], ad: &[u8]) -> Vec<u8> {
+        self.cipher.encrypt_with_random_nonce(plaintext, ad)
+    }
+
+    /// Decrypts a message produced by `encryptWithRandomNonce`. Throws if the message does
+    /// not authenticate.
+    #[wasm_bindgen(js_name = decryptWithPrependedNonce)]
+    pub fn decrypt_with_prepended_nonce(
+        &self,
+        message: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        Ok(self.cipher.decrypt_with_prepended_nonce(message, ad)?)
+    }
+}
+
+/// The error every binding throws. Its `kind` tells failures apart, such as
+/// `ErrorKind.InvalidTag` for a message that does not authenticate and
+/// `ErrorKind.InvalidKeyLength`, and its `message` describes it.
+#[wasm_bindgen]
+pub struct CryptoError(errors::CryptoError);
+
+#[wasm_bindgen]
+impl CryptoError {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> errors::CryptoError {
+        self.0
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl<E: Into<errors::CryptoError>> From<E> for CryptoError {
+    fn from(error: E) -> CryptoError {
+        CryptoError(error.into())
+    }
+}
+
+/// XChaCha20-SIV under a fixed key, for when nonces may repeat or there is none.
+#[wasm_bindgen]
+pub struct XChaChaSiv {
+    cipher: xchachasiv::XChaChaSiv,
+    key: ExportableKey,
+}
+
+#[wasm_bindgen]
+impl XChaChaSiv {
+    /// Throws if `key` is not 32 bytes. Unless `extractable` is true, the key cannot be
+    /// read back with `exportKey`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(key: &[u8], extractable: Option<bool>) -> Result<XChaChaSiv, CryptoError> {
+        if key.len() != xchachasiv::KEY_LENGTH {
+            return Err(errors::CryptoError::InvalidKeyLength.into());
+        }
+
+        Ok(XChaChaSiv {
+            cipher: xchachasiv::XChaChaSiv::new(key),
+            key: ExportableKey::new(key, extractable),
+        })
+    }
+
+    /// Creates the cipher from a hex key, see [`XChaChaPoly1305::from_hex`].
+    #[wasm_bindgen(js_name = fromHex)]
+    pub fn from_hex(encoded: &str, extractable: Option<bool>) -> Result<XChaChaSiv, CryptoError> {
+        let key = Zeroizing::new(encoding::decode_hex(encoded)?);
+        XChaChaSiv::new(&key, extractable)
+    }
+
+    /// Creates the cipher from a Base64 key, see [`XChaChaPoly1305::from_base64`].
+    #[wasm_bindgen(js_name = fromBase64)]
+    pub fn from_base64(
+        encoded: &str,
+        url_safe: bool,
+        extractable: Option<bool>,
+    ) -> Result<XChaChaSiv, CryptoError> {
+        let key = Zeroizing::new(decode_base64_key(encoded, url_safe)?);
+        XChaChaSiv::new(&key, extractable)
+    }
+
+    /// Returns the key. Throws unless the cipher was created extractable.
+    #[wasm_bindgen(js_name = exportKey)]
+    pub fn export_key(&self) -> Result<Vec<u8>, CryptoError> {
+        self.key.export()
+    }
+
+    /// Returns `ciphertext || tag`. `nonce` may have any length, including zero.
+    pub fn encrypt(&self, plaintext: &[u8], nonce: &[u8], ad: &[u8]) -> Vec<u8> {
+        self.cipher.encrypt(plaintext, nonce, ad)
+    }
+
+    /// Throws if the message does not authenticate.
+    pub fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        Ok(self.cipher.decrypt(ciphertext, nonce, ad)?)
+    }
+}
+
+/// A copy of a cipher's key, kept only if the cipher was created extractable, like a
+/// WebCrypto `CryptoKey`.
+#[derive(ZeroizeOnDrop)]
+struct ExportableKey(Option<[u8; KEY_LENGTH]>);
+
+impl ExportableKey {
+    fn new(key: &[u8], extractable: Option<bool>) -> ExportableKey {
+        ExportableKey(extractable.unwrap_or(false).then(|| {
+            let mut copy = [0u8; KEY_LENGTH];
+            copy.copy_from_slice(key);
+            copy
+        }))
+    }
+
+    fn export(&self) -> Result<Vec<u8>, CryptoError> {
+        match &self.0 {
+            Some(key) => Ok(key.to_vec()),
+            None => Err(errors::CryptoError::KeyNotExtractable.into()),
+        }
+    }
+}
+
+fn decode_base64_key(encoded: &str, url_safe: bool) -> Result<Vec<u8>, CryptoError> {
+    Ok(encoding::decode_base64_lenient(
+        encoded,
+        base64_variant(url_safe, true),
+    )?)
+}
+
+/// An incremental BLAKE3 hash, unkeyed, keyed or deriving a key.
+#[wasm_bindgen]
+pub struct Blake3(blake3::Blake3);
+
+#[wasm_bindgen]
+impl Blake3 {
+    /// Starts an unkeyed hash.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Blake3 {
+        Blake3(blake3::Blake3::new())
+    }
+
+    /// Starts a hash keyed with `key`. Throws if `key` is not 32 bytes.
+    pub fn keyed(key: &[u8]) -> Result<Blake3, CryptoError> {
+        if key.len() != blake3::KEY_LENGTH {
+            return Err(errors::CryptoError::InvalidKeyLength.into());
+        }
+
+        Ok(Blake3(blake3::Blake3::new_keyed(key)))
+    }
+
+    /// Starts deriving a key from the data passed to `update`, for the use described by
+    /// the hardcoded, application-specific `context`.
+    #[wasm_bindgen(js_name = deriveKey)]
+    pub fn derive_key(context: &str) -> Blake3 {
+        Blake3(blake3::Blake3::new_derive_key(context))
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// Returns the 32-byte hash of the data so far. More data may still be added.
+    pub fn finalize(&self) -> Vec<u8> {
+        self.0.clone().finalize().to_vec()
+    }
+
+    /// Returns `length` bytes of output, of which the first 32 are `finalize()`.
+    #[wasm_bindgen(js_name = finalizeXof)]
+    pub fn finalize_xof(&self, length: usize) -> Vec<u8> {
+        let mut output = alloc::vec![0u8; length];
+        self.0.clone().finalize_xof().fill(&mut output);
+
+        output
+    }
+}
+
+impl Default for Blake3 {
+    fn default() -> Blake3 {
+        Blake3::new()
+    }
+}
+
+/// Encrypts `plaintext` under `key`, see [`XChaChaPoly1305::encrypt`].
+#[wasm_bindgen]
+pub fn encrypt(
+    key: &[u8],
+    plaintext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    XChaChaPoly1305::new(key, None)?.encrypt(plaintext, nonce, ad)
+}
+
+/// Decrypts `ciphertext` under `key`, see [`XChaChaPoly1305::decrypt`].
+#[wasm_bindgen]
+pub fn decrypt(
+    key: &[u8],
+    ciphertext: &[u8],
+    nonce: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    XChaChaPoly1305::new(key, None)?.decrypt(ciphertext, nonce, ad)
+}
+
+/// Encodes `data` as lowercase hex in constant time, see [`encoding`].
+#[wasm_bindgen(js_name = encodeHex)]
+pub fn encode_hex(data: &[u8]) -> String {
+    encoding::encode_hex(data)
+}
+
+/// Decodes hex in constant time. Throws on whitespace or any other non-hex character.
+#[wasm_bindgen(js_name = decodeHex)]
+pub fn decode_hex(encoded: &str) -> Result<Vec<u8>, CryptoError> {
+    Ok(encoding::decode_hex(encoded)?)
+}
+
+fn base64_variant(url_safe: bool, padded: bool) -> Base64 {
+    match (url_safe, padded) {
+        (false, true) => Base64::Standard,
+        (false, false) => Base64::StandardUnpadded,
+        (true, true) => Base64::UrlSafe,
+        (true, false) => Base64::UrlSafeUnpadded,
+    }
+}
+
+/// Encodes `data` as Base64 in constant time, in the URL-safe alphabet if `urlSafe`.
+#[wasm_bindgen(js_name = encodeBase64)]
+pub fn encode_base64(data: &[u8], url_safe: bool, padded: bool) -> String {
+    encoding::encode_base64(data, base64_variant(url_safe, padded))
+}
+
+/// Decodes Base64 in constant time. Throws unless `encoded` is exactly what
+/// `encodeBase64` produces with the same options.
+#[wasm_bindgen(js_name = decodeBase64)]
+pub fn decode_base64(encoded: &str, url_safe: bool, padded: bool) -> Result<Vec<u8>, CryptoError> {
+    Ok(encoding::decode_base64(
+        encoded,
+        base64_variant(url_safe, padded),
+    )?)
+}
+
+/// Runs the known-answer tests of [`self_test`](crate::selftest::self_test). Throws if
+/// any fails.
+#[wasm_bindgen(js_name = selfTest)]
+pub fn self_test() -> Result<(), CryptoError> {
+    Ok(crate::selftest::self_test()?)
+}
+
+/// Returns a random 32-byte key.
+#[wasm_bindgen(js_name = generateKey)]
+pub fn generate_key() -> Vec<u8> {
+    xchachapoly1305::generate_key().to_vec()
+}
+
+/// Returns the nonce length of XChaCha20-Poly1305, 24 bytes.
+#[wasm_bindgen(js_name = nonceLength)]
+pub fn nonce_length() -> usize {
+    NONCE_LENGTH
+}
+
+/// Returns a random 24-byte nonce.
+#[wasm_bindgen(js_name = generateNonce)]
+pub fn generate_nonce() -> Vec<u8> {
+    NonceGenerator::new().generate::<NONCE_LENGTH>().to_vec()
+}
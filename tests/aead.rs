@@ -0,0 +1,138 @@
+use raycrypt::aeads::aegis128l::Aegis128L;
+use raycrypt::aeads::aegis256::Aegis256;
+use raycrypt::aeads::aes256gcm::Aes256Gcm;
+use raycrypt::aeads::chachapoly1305::ChaChaPoly1305;
+use raycrypt::aeads::xchachapoly1305::XChaChaPoly1305;
+use raycrypt::aeads::Aead;
+use raycrypt::errors::TagMismatch;
+
+/// Runs the same code against any AEAD, as a caller generic over [`Aead`] would.
+fn check<A: Aead>() {
+    let key: Vec<u8> = (0..A::KEY_LENGTH as u8).collect();
+    let nonce = vec![0x24u8; A::NONCE_LENGTH];
+    let cipher = A::from_key(&key);
+
+    for len in [0, 1, 16, 65] {
+        let plaintext = vec![0xa5u8; len];
+
+        let ciphertext = cipher.encrypt(&plaintext, &nonce, b"ad");
+        assert_eq!(ciphertext.len(), len + A::TAG_LENGTH);
+        assert_eq!(
+            cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap(),
+            plaintext
+        );
+        assert_eq!(
+            cipher.decrypt(&ciphertext, &nonce, b"other"),
+            Err(TagMismatch { length_valid: true })
+        );
+
+        let (detached, tag) = cipher.encrypt_detached(&plaintext, &nonce, b"ad");
+        assert_eq!(tag.as_ref().len(), A::TAG_LENGTH);
+        assert_eq!([detached.as_slice(), tag.as_ref()].concat(), ciphertext);
+        assert_eq!(
+            cipher
+                .decrypt_detached(&detached, &tag, &nonce, b"ad")
+                .unwrap(),
+            plaintext
+        );
+    }
+
+    assert_eq!(
+        cipher.decrypt(&vec![0u8; A::TAG_LENGTH - 1], &nonce, b"ad"),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+}
+
+#[test]
+fn test_aeads() {
+    check::<ChaChaPoly1305>();
+    check::<XChaChaPoly1305>();
+    check::<Aegis256>();
+    check::<Aegis256<32>>();
+    check::<Aegis128L>();
+    check::<Aes256Gcm>();
+
+    #[cfg(feature = "blake2")]
+    check::<raycrypt::aeads::chachablake2::ChaChaBlake2>();
+}
+
+#[test]
+fn test_same_output() {
+    // the trait methods are the ciphers' own, with their default options
+    let key = [0x42u8; 32];
+    let nonce = [0x24u8; 12];
+
+    let cipher = ChaChaPoly1305::new(&key, None);
+    let generic = <ChaChaPoly1305 as Aead>::from_key(&key);
+    assert_eq!(
+        Aead::encrypt(&generic, b"message", &nonce, b"ad"),
+        cipher.encrypt(b"message", &nonce, b"ad")
+    );
+
+    let cipher = Aes256Gcm::new(&key);
+    let generic = <Aes256Gcm as Aead>::from_key(&key);
+    assert_eq!(
+        Aead::encrypt(&generic, b"message", &nonce, b"ad"),
+        cipher.encrypt(b"message", &nonce, b"ad")
+    );
+}
+
+#[cfg(feature = "getrandom")]
+#[test]
+fn test_generate_key() {
+    use raycrypt::aeads::{aegis128l, aegis256, aes256gcm, chachapoly1305, xchachapoly1305};
+
+    // each key fits its cipher's Aead::KEY_LENGTH
+    assert_eq!(
+        chachapoly1305::generate_key().len(),
+        <ChaChaPoly1305 as Aead>::KEY_LENGTH
+    );
+    assert_eq!(
+        xchachapoly1305::generate_key().len(),
+        <XChaChaPoly1305 as Aead>::KEY_LENGTH
+    );
+    assert_eq!(
+        aegis256::generate_key().len(),
+        <Aegis256 as Aead>::KEY_LENGTH
+    );
+    assert_eq!(
+        aegis128l::generate_key().len(),
+        <Aegis128L as Aead>::KEY_LENGTH
+    );
+    assert_eq!(
+        aes256gcm::generate_key().len(),
+        <Aes256Gcm as Aead>::KEY_LENGTH
+    );
+
+    assert_ne!(aegis128l::generate_key(), aegis128l::generate_key());
+    assert_ne!(
+        xchachapoly1305::generate_key(),
+        xchachapoly1305::generate_key()
+    );
+}
+
+#[test]
+#[should_panic(expected = "the key has the wrong length")]
+fn test_short_key() {
+    <XChaChaPoly1305 as Aead>::from_key(&[0x42; 31]);
+}
+
+#[test]
+#[should_panic(expected = "the key has the wrong length")]
+fn test_long_key() {
+    <Aes256Gcm as Aead>::from_key(&[0x42; 33]);
+}
+
+#[test]
+#[should_panic(expected = "XChaCha-Poly1305 keys are 32 bytes")]
+fn test_short_key_new() {
+    XChaChaPoly1305::new(&[0x42; 31], None);
+}
+
+#[test]
+#[should_panic(expected = "ChaCha keys are 32 bytes")]
+fn test_long_key_new() {
+    ChaChaPoly1305::new(&[0x42; 33], None);
+}
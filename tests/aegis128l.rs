@@ -0,0 +1,164 @@
+use hex::decode;
+use raycrypt::aeads::aegis128l::{
+    decrypt, decrypt_detached, decrypt_with_backend, encrypt, encrypt_detached,
+    encrypt_with_backend, Aegis128L,
+};
+use raycrypt::aeads::aegis256::Backend;
+use raycrypt::errors::TagMismatch;
+
+#[test]
+fn test_vectors() {
+    // the AEGIS-128L test vectors of draft-irtf-cfrg-aegis-aead, with both tag lengths
+    let key = decode("10010000000000000000000000000000").unwrap();
+    let nonce = decode("10000200000000000000000000000000").unwrap();
+    let vectors: [(&str, &str, &str, &str, &str); 5] = [
+        (
+            "",
+            "00000000000000000000000000000000",
+            "c1c0e58bd913006feba00f4b3cc3594e",
+            "abe0ece80c24868a226a35d16bdae37a",
+            "25835bfbb21632176cf03840687cb968cace4617af1bd0f7d064c639a5c79ee4",
+        ),
+        (
+            "",
+            "",
+            "",
+            "c2b879a67def9d74e6c14f708bbcc9b4",
+            "1360dc9db8ae42455f6e5b6a9d488ea4f2184c4e12120249335c4ee84bafe25d",
+        ),
+        (
+            "0001020304050607",
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+            "79d94593d8c2119d7e8fd9b8fc77845c5c077a05b2528b6ac54b563aed8efe84",
+            "cc6f3372f6aa1bb82388d695c3962d9a",
+            "022cb796fe7e0ae1197525ff67e309484cfbab6528ddef89f17d74ef8ecd82b3",
+        ),
+        (
+            "0001020304050607",
+            "000102030405060708090a0b0c0d",
+            "79d94593d8c2119d7e8fd9b8fc77",
+            "5c04b3dba849b2701effbe32c7f0fab7",
+            "86f1b80bfb463aba711d15405d094baf4a55a15dbfec81a76f35ed0b9c8b04ac",
+        ),
+        (
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+             20212223242526272829",
+            "101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f\
+             3031323334353637",
+            "b31052ad1cca4e291abcf2df3502e6bdb1bfd6db36798be3607b1f94d34478aa\
+             7ede7f7a990fec10",
+            "7542a745733014f9474417b337399507",
+            "b91e2947a33da8bee89b6794e647baf0fc835ff574aca3fc27c33be0db2aff98",
+        ),
+    ];
+
+    for (ad, msg, ciphertext, tag, long_tag) in vectors {
+        let (ad, msg) = (decode(ad).unwrap(), decode(msg).unwrap());
+
+        let sealed = decode([ciphertext, tag].concat()).unwrap();
+        assert_eq!(encrypt::<16>(&key, &msg, &nonce, &ad), sealed);
+        assert_eq!(decrypt::<16>(&key, &sealed, &nonce, &ad).unwrap(), msg);
+
+        let sealed = decode([ciphertext, long_tag].concat()).unwrap();
+        assert_eq!(encrypt::<32>(&key, &msg, &nonce, &ad), sealed);
+        assert_eq!(decrypt::<32>(&key, &sealed, &nonce, &ad).unwrap(), msg);
+
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert!(decrypt::<32>(&key, &tampered, &nonce, &ad).is_err());
+        }
+    }
+}
+
+#[test]
+fn test_partial_blocks() {
+    let key = [0x42u8; 16];
+    let nonce = [0x24u8; 16];
+    let data: Vec<u8> = (0..=255).cycle().take(200).collect();
+
+    // lengths around the 32-byte rate, where half of a two-block update is padding
+    for len in [1, 15, 16, 17, 31, 32, 33, 47, 48, 63, 64, 65, 200] {
+        let ad = &data[..len];
+        let msg = &data[data.len() - len..];
+
+        let ciphertext = encrypt::<16>(&key, msg, &nonce, ad);
+        assert_eq!(ciphertext.len(), len + 16);
+        assert_eq!(decrypt::<16>(&key, &ciphertext, &nonce, ad).unwrap(), msg);
+
+        let mut tampered = ciphertext.clone();
+        tampered[len - 1] ^= 0x80;
+        assert!(decrypt::<16>(&key, &tampered, &nonce, ad).is_err());
+
+        let reference =
+            encrypt_with_backend::<16>(Backend::Portable, &key, msg, &nonce, ad).unwrap();
+        assert_eq!(ciphertext, reference);
+        assert_eq!(
+            decrypt_with_backend::<16>(Backend::Portable, &key, &ciphertext, &nonce, ad)
+                .unwrap()
+                .unwrap(),
+            msg
+        );
+    }
+}
+
+#[test]
+fn test_cipher() {
+    let key = [0x3cu8; 16];
+    let nonce = [0x7du8; 16];
+    let cipher = Aegis128L::new(&key);
+
+    for len in [0, 1, 31, 32, 33, 100] {
+        let msg = vec![0xa5u8; len];
+        let ciphertext = cipher.encrypt(&msg, &nonce, b"ad");
+        assert_eq!(ciphertext, encrypt::<16>(&key, &msg, &nonce, b"ad"));
+        assert_eq!(cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap(), msg);
+
+        assert_eq!(
+            cipher.decrypt(&ciphertext, &nonce, b""),
+            Err(TagMismatch { length_valid: true })
+        );
+    }
+
+    assert_eq!(
+        cipher.decrypt(&[0u8; 15], &nonce, b""),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+    assert!(decrypt::<16>(&key, &[0u8; 15], &nonce, b"").is_err());
+}
+
+#[test]
+fn test_detached() {
+    let key = [0x5au8; 16];
+    let nonce = [0xa5u8; 16];
+    let msg: Vec<u8> = (0..75).collect();
+    let combined = encrypt::<32>(&key, &msg, &nonce, b"ad");
+
+    let (ciphertext, tag) = encrypt_detached::<32>(&key, &msg, &nonce, b"ad");
+    assert_eq!([ciphertext.as_slice(), &tag].concat(), combined);
+    assert_eq!(
+        decrypt_detached::<32>(&key, &ciphertext, &tag, &nonce, b"ad").unwrap(),
+        msg
+    );
+
+    let mut forged = tag;
+    forged[31] ^= 1;
+    assert!(decrypt_detached::<32>(&key, &ciphertext, &forged, &nonce, b"ad").is_err());
+
+    let cipher = Aegis128L::new(&key);
+    let (ciphertext, tag) = cipher.encrypt_detached(&msg, &nonce, b"ad");
+    assert_eq!(
+        [ciphertext.as_slice(), &tag].concat(),
+        cipher.encrypt(&msg, &nonce, b"ad")
+    );
+    assert_eq!(
+        cipher.decrypt_detached(&ciphertext, &tag, &nonce, b"ad"),
+        Ok(msg)
+    );
+    assert_eq!(
+        cipher.decrypt_detached(&ciphertext, &tag, &nonce, b""),
+        Err(TagMismatch { length_valid: true })
+    );
+}
@@ -1,5 +1,6 @@
 use hex::decode;
-use raycrypt::aeads::aegis256::{decrypt, encrypt};
+use raycrypt::aeads::aegis256::{decrypt, decrypt_detached, encrypt, encrypt_detached, Aegis256};
+use raycrypt::errors::TagMismatch;
 use serde_json::{from_str, Value};
 use std::fs;
 
@@ -15,6 +16,104 @@ fn test_encrypt() {
     let output = encrypt::<16>(&key, &msg, &nonce, &ad);
 
     assert_eq!(output, expected_output);
+
+    // the crate root borrows the key like the module functions
+    assert_eq!(raycrypt::encrypt(&key, &msg, &nonce, &ad), expected_output);
+    assert_eq!(raycrypt::decrypt(&key, &output, &nonce, &ad).unwrap(), msg);
+}
+
+#[test]
+fn test_vectors() {
+    // the AEGIS-256 test vectors of draft-irtf-cfrg-aegis-aead, with both tag lengths
+    let key = decode("1001000000000000000000000000000000000000000000000000000000000000").unwrap();
+    let nonce = decode("1000020000000000000000000000000000000000000000000000000000000000").unwrap();
+    let vectors: [(&str, &str, &str, &str, &str); 5] = [
+        (
+            "",
+            "00000000000000000000000000000000",
+            "754fc3d8c973246dcc6d741412a4b236",
+            "3fe91994768b332ed7f570a19ec5896e",
+            "1181a1d18091082bf0266f66297d167d2e68b845f61a3b0527d31fc7b7b89f13",
+        ),
+        (
+            "",
+            "",
+            "",
+            "e3def978a0f054afd1e761d7553afba3",
+            "6a348c930adbd654896e1666aad67de989ea75ebaa2b82fb588977b1ffec864a",
+        ),
+        (
+            "0001020304050607",
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+            "f373079ed84b2709faee373584585d60accd191db310ef5d8b11833df9dec711",
+            "8d86f91ee606e9ff26a01b64ccbdd91d",
+            "b7d28d0c3c0ebd409fd22b44160503073a547412da0854bfb9723020dab8da1a",
+        ),
+        (
+            "0001020304050607",
+            "000102030405060708090a0b0c0d",
+            "f373079ed84b2709faee37358458",
+            "c60b9c2d33ceb058f96e6dd03c215652",
+            "8c1cc703c81281bee3f6d9966e14948b4a175b2efbdc31e61a98b4465235c2d9",
+        ),
+        (
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+             20212223242526272829",
+            "101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f\
+             3031323334353637",
+            "57754a7d09963e7c787583a2e7b859bb24fa1e04d49fd550b2511a358e3bca25\
+             2a9b1b8b30cc4a67",
+            "ab8a7d53fd0e98d727accca94925e128",
+            "a3aca270c006094d71c20e6910b5161c0826df233d08919a566ec2c05990f734",
+        ),
+    ];
+
+    for (ad, msg, ciphertext, tag, long_tag) in vectors {
+        let (ad, msg) = (decode(ad).unwrap(), decode(msg).unwrap());
+
+        let sealed = decode([ciphertext, tag].concat()).unwrap();
+        assert_eq!(encrypt::<16>(&key, &msg, &nonce, &ad), sealed);
+        assert_eq!(decrypt::<16>(&key, &sealed, &nonce, &ad).unwrap(), msg);
+
+        let sealed = decode([ciphertext, long_tag].concat()).unwrap();
+        assert_eq!(encrypt::<32>(&key, &msg, &nonce, &ad), sealed);
+        assert_eq!(decrypt::<32>(&key, &sealed, &nonce, &ad).unwrap(), msg);
+
+        // any change to the ciphertext, tag or AAD is rejected
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert!(decrypt::<32>(&key, &tampered, &nonce, &ad).is_err());
+        }
+        assert!(decrypt::<32>(&key, &sealed, &nonce, b"other").is_err());
+    }
+}
+
+#[test]
+fn test_cipher() {
+    let key = [0x3cu8; 32];
+    let nonce = [0x7du8; 32];
+    let cipher = Aegis256::new(&key);
+
+    for len in [0, 1, 15, 16, 17, 100] {
+        let msg = vec![0xa5u8; len];
+        let ciphertext = cipher.encrypt(&msg, &nonce, b"ad");
+        assert_eq!(ciphertext, encrypt::<16>(&key, &msg, &nonce, b"ad"));
+        assert_eq!(cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap(), msg);
+
+        assert_eq!(
+            cipher.decrypt(&ciphertext, &nonce, b""),
+            Err(TagMismatch { length_valid: true })
+        );
+    }
+
+    assert_eq!(
+        cipher.decrypt(&[0u8; 15], &nonce, b""),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+    assert!(decrypt::<16>(&key, &[0u8; 15], &nonce, b"").is_err());
 }
 
 #[test]
@@ -36,10 +135,430 @@ fn test_aegis256_wycheproof() {
         let expected = [ciphertext.clone(), tag].concat();
 
         let output = encrypt::<16>(&key, &pt, &nonce, &aad);
+        let decrypted = decrypt::<16>(&key, &expected, &nonce, &aad);
         if test["result"].as_str().unwrap() == "valid" {
             assert_eq!(output, expected);
+            assert_eq!(decrypted.unwrap(), pt);
         } else {
             assert_ne!(output, expected);
+            assert!(decrypted.is_err());
         }
     }
 }
+
+#[test]
+fn test_tag_batch() {
+    let keys: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 32]).collect();
+    let keys: Vec<&[u8]> = keys.iter().map(|key| key.as_slice()).collect();
+    let nonce = [0x5au8; 32];
+
+    for len in [0, 15, 16, 33] {
+        let ad: Vec<u8> = (0..len).collect();
+
+        let tags = raycrypt::aeads::aegis256::tag_batch::<16>(&keys, &nonce, &ad);
+        let long_tags = raycrypt::aeads::aegis256::tag_batch::<32>(&keys, &nonce, &ad);
+        assert_eq!(tags.len(), keys.len());
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(tags[i].to_vec(), encrypt::<16>(key, &[], &nonce, &ad));
+            assert_eq!(long_tags[i].to_vec(), encrypt::<32>(key, &[], &nonce, &ad));
+        }
+
+        assert_ne!(tags[0], tags[1]);
+    }
+}
+
+#[test]
+fn test_init_state() {
+    // the key and nonce of the first AEGIS-256 test vector, see test_encrypt
+    let key = decode("1001000000000000000000000000000000000000000000000000000000000000").unwrap();
+    let nonce = decode("1000020000000000000000000000000000000000000000000000000000000000").unwrap();
+
+    let expected = [
+        "fad23649d2643a7592ae8ee88674b6e9",
+        "cfdfe091f691349e962d7eccfe83a614",
+        "5a1bb5099b262f83d0c50effe6b38a4a",
+        "5b19bd17da585168fd4dccc3c826426f",
+        "60fa43ae95df84551188b633318e840a",
+        "8073d5e6303d95a69b8db0281d8b9262",
+    ];
+
+    let state = raycrypt::aeads::aegis256::init_state(&key, &nonce).unwrap();
+
+    for (block, expected) in state.iter().zip(expected) {
+        assert_eq!(hex::encode(block), expected);
+    }
+
+    assert!(raycrypt::aeads::aegis256::init_state(&key[..31], &nonce).is_err());
+    assert!(raycrypt::aeads::aegis256::init_state(&key, &nonce[..16]).is_err());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_nonce_reuse_warning() {
+    use raycrypt::aeads::aegis256::reuse_warnings;
+
+    let key = [0x5au8; 32];
+    let nonce = [0xa5u8; 32];
+
+    let before = reuse_warnings();
+    let first = encrypt::<16>(&key, b"first", &nonce, b"");
+    let second = encrypt::<16>(&key, b"other", &nonce, b"");
+
+    // other tests may warn concurrently, so compare against a lower bound
+    assert!(reuse_warnings() > before);
+
+    // the keystream repeats, leaking the XOR of the plaintexts
+    let xor: Vec<u8> = first
+        .iter()
+        .zip(&second)
+        .map(|(a, b)| a ^ b)
+        .take(5)
+        .collect();
+    assert_eq!(
+        xor,
+        b"first"
+            .iter()
+            .zip(b"other")
+            .map(|(a, b)| a ^ b)
+            .collect::<Vec<u8>>()
+    );
+}
+
+#[test]
+fn test_active_backend() {
+    let name = raycrypt::aeads::aegis256::active_backend();
+    assert!(["aesni", "armcrypto", "portable"].contains(&name));
+    assert_eq!(name, raycrypt::aeads::aegis256::backend().name());
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    assert_eq!(name == "aesni", is_x86_feature_detected!("aes"));
+    #[cfg(target_arch = "aarch64")]
+    assert_eq!(
+        name == "armcrypto",
+        std::arch::is_aarch64_feature_detected!("aes")
+    );
+}
+
+#[test]
+fn test_backend_parity() {
+    use raycrypt::aeads::aegis256::{decrypt_with_backend, encrypt_with_backend, Backend};
+
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = [0x5au8; 32];
+    let msg: Vec<u8> = (0..=255).cycle().take(300).collect();
+
+    let reference = |len: usize| {
+        encrypt_with_backend::<16>(
+            Backend::Portable,
+            &key,
+            &msg[..len],
+            &nonce,
+            &msg[..len / 2],
+        )
+        .unwrap()
+    };
+
+    // the vector of test_encrypt, on the software AES round
+    let key_1 = decode("1001000000000000000000000000000000000000000000000000000000000000").unwrap();
+    let nonce_1 =
+        decode("1000020000000000000000000000000000000000000000000000000000000000").unwrap();
+    assert_eq!(
+        encrypt_with_backend::<16>(Backend::Portable, &key_1, &[0u8; 16], &nonce_1, b"").unwrap(),
+        decode("754fc3d8c973246dcc6d741412a4b2363fe91994768b332ed7f570a19ec5896e").unwrap()
+    );
+
+    for backend in [Backend::AesNi, Backend::ArmCrypto] {
+        if !backend.is_supported() {
+            assert!(encrypt_with_backend::<16>(backend, &key, &msg, &nonce, b"").is_none());
+            continue;
+        }
+
+        for len in [0, 1, 15, 16, 17, 64, 300] {
+            let ad = &msg[..len / 2];
+            let ciphertext = encrypt_with_backend::<16>(backend, &key, &msg[..len], &nonce, ad);
+            assert_eq!(
+                ciphertext.unwrap(),
+                reference(len),
+                "{:?} on {} bytes",
+                backend,
+                len
+            );
+
+            assert_eq!(
+                encrypt_with_backend::<32>(backend, &key, &msg[..len], &nonce, ad),
+                encrypt_with_backend::<32>(Backend::Portable, &key, &msg[..len], &nonce, ad)
+            );
+
+            let ciphertext = reference(len);
+            assert_eq!(
+                decrypt_with_backend::<16>(backend, &key, &ciphertext, &nonce, ad),
+                decrypt_with_backend::<16>(Backend::Portable, &key, &ciphertext, &nonce, ad)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_short_ad_tail() {
+    use raycrypt::aeads::aegis256::{decrypt_with_backend, encrypt_with_backend, Backend};
+
+    // Wycheproof cases 171, 185 and 187, whose AAD ends in a partial block of 1, 15 and
+    // 17 bytes. The AAD is copied into vectors of its exact length, so a load past the
+    // end of the tail would be caught by Miri or ASan.
+    let vectors = [
+        (
+            "f5a6375911231929e1e94198cdcf5aac2588cc51ee90ebc8fb2aafc7d8149577",
+            "7bcb8e86c49eb178338921172ae41ba912300a8bc97b1e99d3ec3a94ce649463",
+            "50",
+            "b9853c71787e125df24f8a9aa36d312f",
+            "4e2563d6829dae52d04bca505b9f207b1b2bb98b1e8531f415e6975343384f5d",
+        ),
+        (
+            "a2535b907026e2d6cedbfebf302c9296faa3158b423aa26e4d6d504359148da9",
+            "bdcb4fc291c6788b90e5a1e485672dc822c46a7e370c4d862131a0219d34b758",
+            "ca547fbe17b40378c7232f68ee0f92",
+            "71da938c536d70377fd420f58d2d5da5",
+            "ed6a5014dbd9f0b864e705d8e2e3ba3c6327dc228ee2a5a453f068612fbdfdbc",
+        ),
+        (
+            "8d518a2d33c2dac728ff6f026c9d44280ee408814b865c6e57cc6085e71dd00e",
+            "1dc3e02f859383c3835bab259f054e7f74f2c1a274fa48de7cfe291de1d188a0",
+            "ca1f296d57ef15a54fe6b4171080c3c571",
+            "2cd892ab60d5a6efc18dc6e4b9e2fcc2",
+            "d9b72bbe9ba176915abdd81581aeedc6b5b9918ce4c461a1fc7674471886b540",
+        ),
+    ];
+
+    for (key, nonce, ad, msg, sealed) in vectors {
+        let (key, nonce) = (decode(key).unwrap(), decode(nonce).unwrap());
+        let (ad, msg, sealed) = (
+            decode(ad).unwrap(),
+            decode(msg).unwrap(),
+            decode(sealed).unwrap(),
+        );
+
+        for backend in [Backend::AesNi, Backend::ArmCrypto, Backend::Portable] {
+            if !backend.is_supported() {
+                continue;
+            }
+
+            assert_eq!(
+                encrypt_with_backend::<16>(backend, &key, &msg, &nonce, &ad).unwrap(),
+                sealed,
+                "{:?} with {} bytes of AAD",
+                backend,
+                ad.len()
+            );
+            assert_eq!(
+                decrypt_with_backend::<16>(backend, &key, &sealed, &nonce, &ad)
+                    .unwrap()
+                    .unwrap(),
+                msg
+            );
+            assert!(
+                decrypt_with_backend::<16>(backend, &key, &sealed, &nonce, &ad[1..])
+                    .unwrap()
+                    .is_err()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_three_byte_tail() {
+    use raycrypt::aeads::aegis256::{decrypt_with_backend, encrypt_with_backend, Backend};
+
+    let key = [0x0fu8; 32];
+    let nonce = [0xf0u8; 32];
+    let data: Vec<u8> = (0..35).collect();
+
+    // 3-byte AAD and plaintext, alone and after two full blocks, each in a vector of its
+    // exact length so that Miri or ASan catch a load past its end
+    for len in [3, 35] {
+        let ad = data[..len].to_vec();
+        let msg = data[data.len() - len..].to_vec();
+        let reference =
+            encrypt_with_backend::<16>(Backend::Portable, &key, &msg, &nonce, &ad).unwrap();
+        assert_eq!(reference.len(), len + 16);
+
+        for backend in [Backend::AesNi, Backend::ArmCrypto, Backend::Portable] {
+            if !backend.is_supported() {
+                continue;
+            }
+
+            let ciphertext = encrypt_with_backend::<16>(backend, &key, &msg, &nonce, &ad);
+            assert_eq!(ciphertext.unwrap(), reference, "{:?}", backend);
+
+            assert_eq!(
+                decrypt_with_backend::<16>(backend, &key, &reference, &nonce, &ad)
+                    .unwrap()
+                    .unwrap(),
+                msg
+            );
+        }
+    }
+}
+
+#[test]
+fn test_detached() {
+    let key = [0x5au8; 32];
+    let nonce = [0xa5u8; 32];
+    let msg: Vec<u8> = (0..75).collect();
+    let combined = encrypt::<32>(&key, &msg, &nonce, b"ad");
+
+    let (ciphertext, tag) = encrypt_detached::<32>(&key, &msg, &nonce, b"ad");
+    assert_eq!([ciphertext.as_slice(), &tag].concat(), combined);
+    assert_eq!(
+        decrypt_detached::<32>(&key, &ciphertext, &tag, &nonce, b"ad").unwrap(),
+        msg
+    );
+
+    let mut forged = tag;
+    forged[31] ^= 1;
+    assert!(decrypt_detached::<32>(&key, &ciphertext, &forged, &nonce, b"ad").is_err());
+
+    let cipher = Aegis256::new(&key);
+    let (ciphertext, tag) = cipher.encrypt_detached(&msg, &nonce, b"ad");
+    assert_eq!(
+        [ciphertext.as_slice(), &tag].concat(),
+        cipher.encrypt(&msg, &nonce, b"ad")
+    );
+    assert_eq!(
+        cipher.decrypt_detached(&ciphertext, &tag, &nonce, b"ad"),
+        Ok(msg)
+    );
+    assert_eq!(
+        cipher.decrypt_detached(&ciphertext, &tag, &nonce, b""),
+        Err(TagMismatch { length_valid: true })
+    );
+}
+
+#[test]
+fn test_tag_length() {
+    let key = [0x3cu8; 32];
+    let nonce = [0x7du8; 32];
+    let cipher = Aegis256::<32>::with_tag_length(&key);
+    let msg: Vec<u8> = (0..40).collect();
+
+    let ciphertext = cipher.encrypt(&msg, &nonce, b"ad");
+    assert_eq!(ciphertext, encrypt::<32>(&key, &msg, &nonce, b"ad"));
+    assert_eq!(cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap(), msg);
+
+    // the first 16 bytes of the long tag are not the short tag
+    let short = Aegis256::new(&key).encrypt(&msg, &nonce, b"ad");
+    assert_ne!(short[40..], ciphertext[40..56]);
+    assert!(cipher.decrypt(&short, &nonce, b"ad").is_err());
+
+    let (detached, tag) = cipher.encrypt_detached(&msg, &nonce, b"ad");
+    assert_eq!([detached.as_slice(), &tag].concat(), ciphertext);
+    assert_eq!(
+        cipher.decrypt_detached(&detached, &tag, &nonce, b"ad"),
+        Ok(msg)
+    );
+    assert_eq!(
+        cipher.decrypt(&ciphertext[..31], &nonce, b"ad"),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "16 or 32 bytes")]
+fn test_invalid_tag_length() {
+    Aegis256::<24>::with_tag_length(&[0u8; 32]);
+}
+
+fn check_incremental<const MAC_LENGTH: usize>() {
+    let key: Vec<u8> = (0x20..0x40).collect();
+    let nonce = [0x5eu8; 32];
+    let ad: Vec<u8> = (0..37).collect();
+    let cipher = Aegis256::<MAC_LENGTH>::with_tag_length(&key);
+
+    let mut seed = 0x9e3779b9u32;
+    let mut next = |bound: usize| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 8) as usize % bound
+    };
+
+    for len in [0, 1, 15, 16, 17, 33, 100, 1000] {
+        let msg: Vec<u8> = (0..len).map(|i| (i * 11 % 256) as u8).collect();
+        let expected = cipher.encrypt(&msg, &nonce, &ad);
+        let (body, tag) = expected.split_at(len);
+
+        for _ in 0..8 {
+            // random chunk sizes, including empty chunks and chunks within one block
+            let mut bounds = vec![0];
+            while *bounds.last().unwrap() < len {
+                let step = next(40);
+                bounds.push((bounds.last().unwrap() + step).min(len));
+            }
+            let ad_split = next(ad.len() + 1);
+
+            let mut encryptor = cipher.encryptor(&nonce, &ad[..ad_split]);
+            encryptor.update_aad(&ad[ad_split..]);
+            let mut ciphertext = Vec::new();
+            for pair in bounds.windows(2) {
+                ciphertext.extend(encryptor.update(&msg[pair[0]..pair[1]]));
+            }
+            ciphertext.extend(encryptor.finalize());
+            assert_eq!(ciphertext, expected, "{len} bytes");
+
+            let mut decryptor = cipher.decryptor(&nonce, &ad[..ad_split]);
+            decryptor.update_aad(&ad[ad_split..]);
+            let mut decrypted = Vec::new();
+            for pair in bounds.windows(2) {
+                decrypted.extend(decryptor.update(&body[pair[0]..pair[1]]));
+            }
+            assert_eq!(decryptor.finalize(tag), Ok(()));
+            assert_eq!(decrypted, msg);
+        }
+    }
+}
+
+#[test]
+fn test_incremental() {
+    check_incremental::<16>();
+    check_incremental::<32>();
+}
+
+#[test]
+fn test_incremental_tamper() {
+    let key = [0x77u8; 32];
+    let nonce = [0x88u8; 32];
+    let cipher = Aegis256::new(&key);
+    let sealed = cipher.encrypt(&[5u8; 50], &nonce, b"");
+    let (body, tag) = sealed.split_at(50);
+
+    let mut tampered = body.to_vec();
+    tampered[49] ^= 1;
+    let mut decryptor = cipher.decryptor(&nonce, b"");
+    decryptor.update(&tampered[..20]);
+    decryptor.update(&tampered[20..]);
+    assert_eq!(
+        decryptor.finalize(tag),
+        Err(TagMismatch { length_valid: true })
+    );
+
+    let mut decryptor = cipher.decryptor(&nonce, b"other");
+    decryptor.update(body);
+    assert!(decryptor.finalize(tag).is_err());
+
+    let mut decryptor = cipher.decryptor(&nonce, b"");
+    decryptor.update(body);
+    assert_eq!(
+        decryptor.finalize(&tag[..15]),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "AAD must be supplied before any message data")]
+fn test_incremental_aad_after_data() {
+    let mut encryptor = Aegis256::new(&[0u8; 32]).encryptor(&[0u8; 32], b"");
+    encryptor.update(b"data");
+    encryptor.update_aad(b"late");
+}
@@ -0,0 +1,103 @@
+use hex::decode;
+use raycrypt::aeads::aegis256::Backend;
+use raycrypt::macs::aegismac::{aegismac128l, aegismac256, AegisMac128L, AegisMac256};
+
+const DATA: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f202122";
+
+#[test]
+fn test_aegismac128l() {
+    // draft-irtf-cfrg-aegis-aead, AEGISMAC-128L test vector
+    let key = decode("10010000000000000000000000000000").unwrap();
+    let nonce = decode("10000200000000000000000000000000").unwrap();
+    let data = decode(DATA).unwrap();
+
+    assert_eq!(
+        aegismac128l::<16>(&key, &nonce, &data).to_vec(),
+        decode("d3f09b2842ad301687d6902c921d7818").unwrap()
+    );
+    assert_eq!(
+        aegismac128l::<32>(&key, &nonce, &data).to_vec(),
+        decode("9490e7c89d420c9f37417fa625eb38e8cad53c5cbec55285e8499ea48377f2a3").unwrap()
+    );
+}
+
+#[test]
+fn test_aegismac256() {
+    // draft-irtf-cfrg-aegis-aead, AEGISMAC-256 test vector
+    let key = decode("1001000000000000000000000000000000000000000000000000000000000000").unwrap();
+    let nonce = decode("1000020000000000000000000000000000000000000000000000000000000000").unwrap();
+    let data = decode(DATA).unwrap();
+
+    assert_eq!(
+        aegismac256::<16>(&key, &nonce, &data).to_vec(),
+        decode("c08e20cfc56f27195a46c9cef5c162d4").unwrap()
+    );
+    assert_eq!(
+        aegismac256::<32>(&key, &nonce, &data).to_vec(),
+        decode("a5c906ede3d69545c11e20afa360b221f936e946ed2dba3d7c75ad6dc2784126").unwrap()
+    );
+}
+
+#[test]
+fn test_streaming() {
+    let data: Vec<u8> = (0..=255u8).cycle().take(200).collect();
+    let expected256 = aegismac256::<32>(&[1u8; 32], &[2u8; 32], &data);
+    let expected128l = aegismac128l::<32>(&[1u8; 16], &[2u8; 16], &data);
+
+    for split in 0..=data.len() {
+        let mut mac = AegisMac256::<32>::new(&[1u8; 32], &[2u8; 32]);
+        mac.update(&data[..split]);
+        mac.update(&data[split..]);
+        assert_eq!(mac.finalize(), expected256);
+
+        let mut mac = AegisMac128L::<32>::new(&[1u8; 16], &[2u8; 16]);
+        mac.update(&data[..split]);
+        mac.update(&data[split..]);
+        assert_eq!(mac.finalize(), expected128l);
+    }
+}
+
+#[test]
+fn test_backends() {
+    let data: Vec<u8> = (0..=255u8).cycle().take(100).collect();
+
+    for backend in [Backend::AesNi, Backend::ArmCrypto, Backend::Portable] {
+        let Some(mut mac) = AegisMac256::<16>::with_backend(backend, &[1u8; 32], &[2u8; 32]) else {
+            continue;
+        };
+        mac.update(&data);
+        assert_eq!(
+            mac.finalize(),
+            aegismac256::<16>(&[1u8; 32], &[2u8; 32], &data),
+            "{}",
+            backend.name()
+        );
+
+        let mut mac = AegisMac128L::<16>::with_backend(backend, &[1u8; 16], &[2u8; 16]).unwrap();
+        mac.update(&data);
+        assert_eq!(
+            mac.finalize(),
+            aegismac128l::<16>(&[1u8; 16], &[2u8; 16], &data),
+            "{}",
+            backend.name()
+        );
+    }
+}
+
+#[test]
+fn test_verify() {
+    let tag = aegismac256::<16>(&[1u8; 32], &[0u8; 32], b"message");
+
+    let mut tampered = tag;
+    tampered[15] ^= 1;
+
+    let mut mac = AegisMac256::<16>::new(&[1u8; 32], &[0u8; 32]);
+    mac.update(b"message");
+    assert!(mac.clone().verify(&tag));
+    assert!(!mac.clone().verify(&tampered));
+    assert!(!mac.verify(&tag[..8]));
+
+    // the tag length is bound into the tag
+    let long = aegismac256::<32>(&[1u8; 32], &[0u8; 32], b"message");
+    assert_ne!(long[..16], tag);
+}
@@ -0,0 +1,183 @@
+use hex::decode;
+use raycrypt::aeads::aegis256::Backend;
+use raycrypt::aeads::aes256gcm::{
+    decrypt, decrypt_detached, decrypt_with_backend, encrypt, encrypt_detached,
+    encrypt_with_backend, Aes256Gcm,
+};
+use raycrypt::errors::TagMismatch;
+
+#[test]
+fn test_vectors() {
+    // test cases 13 to 16 of the GCM specification, the AES-256 ones with 96-bit nonces
+    let key = "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308";
+    let nonce = "cafebabefacedbaddecaf888";
+    let msg = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+               1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39";
+    let ciphertext = "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+                      8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662";
+    let zero_key = "0000000000000000000000000000000000000000000000000000000000000000";
+    let zero_nonce = "000000000000000000000000";
+
+    let vectors: [(&str, &str, &str, &str, &str, &str); 4] = [
+        (
+            zero_key,
+            zero_nonce,
+            "",
+            "",
+            "",
+            "530f8afbc74536b9a963b4f1c4cb738b",
+        ),
+        (
+            zero_key,
+            zero_nonce,
+            "",
+            "00000000000000000000000000000000",
+            "cea7403d4d606b6e074ec5d3baf39d18",
+            "d0d1c8a799996bf0265b98b5d48ab919",
+        ),
+        (
+            key,
+            nonce,
+            "",
+            msg,
+            ciphertext,
+            "eb9f796c8d356fc31a8433884b696f4f",
+        ),
+        (
+            key,
+            nonce,
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            &msg[..120],
+            &ciphertext[..120],
+            "76fc6ece0f4e1768cddf8853bb2d551b",
+        ),
+    ];
+
+    for (key, nonce, ad, msg, ciphertext, tag) in vectors {
+        let (key, nonce) = (decode(key).unwrap(), decode(nonce).unwrap());
+        let (ad, msg) = (decode(ad).unwrap(), decode(msg).unwrap());
+        let sealed = decode([ciphertext, tag].concat()).unwrap();
+
+        assert_eq!(encrypt(&key, &msg, &nonce, &ad), sealed);
+        assert_eq!(decrypt(&key, &sealed, &nonce, &ad).unwrap(), msg);
+
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert!(decrypt(&key, &tampered, &nonce, &ad).is_err());
+        }
+    }
+}
+
+#[test]
+fn test_partial_blocks() {
+    let key: Vec<u8> = (0..32).collect();
+    let nonce: Vec<u8> = (0..12).collect();
+    let data: Vec<u8> = (0..200).map(|i| (i * 7) as u8).collect();
+
+    // generated with the AESGCM class of the Python cryptography package
+    let vectors = [
+        (1, "362ffc6b2d9087f30ad92ef84110874dfd"),
+        (
+            17,
+            "460ad90dd8c1e929b401d0c5e4b51b07f2950058133cf0452fa4d612b1f66097d7",
+        ),
+        (
+            65,
+            "f6ba69dd083119f964b16075b4e56b77a2fea802cd3f142e610782eb68158338\
+             9088315a0275a95abd74a8336d6bdbc2ef516f9b47f288e806d76d574dbf9684\
+             818cfff16c5c96651a31b4deb7faddb444",
+        ),
+    ];
+
+    for (len, sealed) in vectors {
+        let ad = &data[..len];
+        let msg = &data[data.len() - len..];
+        assert_eq!(encrypt(&key, msg, &nonce, ad), decode(sealed).unwrap());
+    }
+
+    for len in [0, 1, 15, 16, 17, 31, 32, 33, 64, 65, 200] {
+        let ad = &data[..len];
+        let msg = &data[data.len() - len..];
+
+        let ciphertext = encrypt(&key, msg, &nonce, ad);
+        assert_eq!(ciphertext.len(), len + 16);
+        assert_eq!(decrypt(&key, &ciphertext, &nonce, ad).unwrap(), msg);
+
+        let mut tampered = ciphertext.clone();
+        tampered[len] ^= 0x80;
+        assert!(decrypt(&key, &tampered, &nonce, ad).is_err());
+
+        let reference = encrypt_with_backend(Backend::Portable, &key, msg, &nonce, ad).unwrap();
+        assert_eq!(ciphertext, reference);
+        assert_eq!(
+            decrypt_with_backend(Backend::Portable, &key, &ciphertext, &nonce, ad)
+                .unwrap()
+                .unwrap(),
+            msg
+        );
+    }
+}
+
+#[test]
+fn test_cipher() {
+    let key = [0x3cu8; 32];
+    let nonce = [0x7du8; 12];
+    let cipher = Aes256Gcm::new(&key);
+
+    for len in [0, 1, 15, 16, 17, 100] {
+        let msg = vec![0xa5u8; len];
+        let ciphertext = cipher.encrypt(&msg, &nonce, b"ad");
+        assert_eq!(ciphertext, encrypt(&key, &msg, &nonce, b"ad"));
+        assert_eq!(cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap(), msg);
+
+        assert_eq!(
+            cipher.decrypt(&ciphertext, &nonce, b""),
+            Err(TagMismatch { length_valid: true })
+        );
+    }
+
+    assert_eq!(
+        cipher.decrypt(&[0u8; 15], &nonce, b""),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+    assert!(decrypt(&key, &[0u8; 15], &nonce, b"").is_err());
+}
+
+#[test]
+fn test_detached() {
+    let key = [0x5au8; 32];
+    let nonce = [0xa5u8; 12];
+    let msg: Vec<u8> = (0..75).collect();
+    let combined = encrypt(&key, &msg, &nonce, b"ad");
+
+    let (ciphertext, tag) = encrypt_detached(&key, &msg, &nonce, b"ad");
+    assert_eq!([ciphertext.as_slice(), &tag].concat(), combined);
+    assert_eq!(
+        decrypt_detached(&key, &ciphertext, &tag, &nonce, b"ad").unwrap(),
+        msg
+    );
+
+    let mut forged = tag;
+    forged[15] ^= 1;
+    assert!(decrypt_detached(&key, &ciphertext, &forged, &nonce, b"ad").is_err());
+
+    let cipher = Aes256Gcm::new(&key);
+    let (ciphertext, tag) = cipher.encrypt_detached(&msg, &nonce, b"ad");
+    assert_eq!(
+        [ciphertext.as_slice(), &tag].concat(),
+        cipher.encrypt(&msg, &nonce, b"ad")
+    );
+    assert_eq!(
+        cipher.decrypt_detached(&ciphertext, &tag, &nonce, b"ad"),
+        Ok(msg)
+    );
+}
+
+#[test]
+#[should_panic(expected = "nonces are 12 bytes")]
+fn test_nonce_length() {
+    encrypt(&[0u8; 32], b"message", &[0u8; 16], b"");
+}
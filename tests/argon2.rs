@@ -0,0 +1,123 @@
+use hex::decode;
+use raycrypt::errors::Argon2Error;
+use raycrypt::kdfs::argon2::{verify_password, Argon2, Params, Variant};
+
+// RFC 9106 §5.2 and §5.3
+#[test]
+fn test_rfc9106() {
+    let params = Params::new(32, 3, 4).unwrap();
+
+    for (variant, tag) in [
+        (
+            Variant::Argon2i,
+            "c814d9d1dc7f37aa13f0d77f2494bda1c8de6b016dd388d29952a4c4672b6ce8",
+        ),
+        (
+            Variant::Argon2id,
+            "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659",
+        ),
+    ] {
+        let argon2 = Argon2::new(variant, params)
+            .with_secret(&[0x03; 8])
+            .with_associated_data(&[0x04; 12]);
+
+        assert_eq!(
+            argon2.hash_raw(&[0x01; 32], &[0x02; 16], 32).unwrap(),
+            decode(tag).unwrap()
+        );
+    }
+}
+
+// generated with libsodium's crypto_pwhash
+#[test]
+fn test_libsodium() {
+    let salt: Vec<u8> = (0..16).collect();
+
+    let argon2 = Argon2::new(Variant::Argon2id, Params::new(64, 2, 1).unwrap());
+    assert_eq!(
+        argon2.hash_raw(b"password", &salt, 32).unwrap(),
+        decode("716882821df77bc6414358b07159fb63ca1c90beaeb9d0ca0e43bd887dcf2ca9").unwrap()
+    );
+
+    // outputs longer than 64 bytes are chained
+    let argon2 = Argon2::new(Variant::Argon2id, Params::new(256, 1, 1).unwrap());
+    assert_eq!(
+        argon2
+            .hash_raw(b"correct horse battery staple", &salt, 100)
+            .unwrap(),
+        decode(concat!(
+            "84dabd7d9449d15dd265209e04bbd63f9bc2d466b562a66b98b73e75778d5733",
+            "19c77bc883d161283f720b3906d92d0ce8dc90b062e21824b72f9f88b970d0e8",
+            "080a8c89b2516cdab2b053c10291c383c99e6fd8d571af98aff7aa344bf9f364",
+            "9e22b84c"
+        ))
+        .unwrap()
+    );
+
+    let argon2 = Argon2::new(Variant::Argon2i, Params::new(64, 3, 1).unwrap());
+    assert_eq!(
+        argon2.hash_raw(b"password", &salt, 32).unwrap(),
+        decode("05627f8127ace33f4d76fa5cd17fb002b861fcacfbe2945a68a966d9fda6399b").unwrap()
+    );
+
+    for encoded in [
+        "$argon2id$v=19$m=64,t=2,p=1$fgqGZy5Cpeaw8q+yh5503w$KITdQvBAtLzCtzp9nloPgaxKKR3JruVe/DtZz2Ip2F4",
+        "$argon2i$v=19$m=64,t=3,p=1$7Ogijq31axParSamKso20Q$PftQpgVWyq/QvEYGxDFbwPSoZPnVvM+zB9ogU2Q48WE",
+    ] {
+        assert_eq!(verify_password(encoded, b"password"), Ok(true));
+        assert_eq!(verify_password(encoded, b"passwore"), Ok(false));
+    }
+}
+
+#[test]
+fn test_hash_password() {
+    let argon2 = Argon2::new(Variant::Argon2id, Params::new(128, 2, 2).unwrap());
+
+    let encoded = argon2.hash_password(b"hunter2");
+    assert!(encoded.starts_with("$argon2id$v=19$m=128,t=2,p=2$"));
+    assert_ne!(encoded, argon2.hash_password(b"hunter2"));
+
+    assert_eq!(verify_password(&encoded, b"hunter2"), Ok(true));
+    assert_eq!(verify_password(&encoded, b"hunter3"), Ok(false));
+
+    // a hash with a secret only verifies with the same secret
+    let peppered =
+        Argon2::new(Variant::Argon2id, Params::new(128, 2, 2).unwrap()).with_secret(b"pepper");
+    let encoded = peppered.hash_password(b"hunter2");
+    assert_eq!(peppered.verify(&encoded, b"hunter2"), Ok(true));
+    assert_eq!(verify_password(&encoded, b"hunter2"), Ok(false));
+}
+
+#[test]
+fn test_invalid() {
+    assert_eq!(Params::new(64, 0, 1), Err(Argon2Error::InvalidParams));
+    assert_eq!(Params::new(64, 1, 0), Err(Argon2Error::InvalidParams));
+    assert_eq!(Params::new(15, 1, 2), Err(Argon2Error::InvalidParams));
+
+    let argon2 = Argon2::new(Variant::Argon2id, Params::new(16, 1, 2).unwrap());
+    assert_eq!(
+        argon2.hash_raw(b"password", &[0; 7], 32),
+        Err(Argon2Error::InvalidParams)
+    );
+    assert_eq!(
+        argon2.hash_raw(b"password", &[0; 8], 3),
+        Err(Argon2Error::InvalidParams)
+    );
+
+    for encoded in [
+        "",
+        "$argon2d$v=19$m=64,t=2,p=1$fgqGZy5Cpeaw8q+yh5503w$KITdQvBAtLzCtzp9nloPgaxKKR3JruVe/DtZz2Ip2F4",
+        "$argon2id$v=16$m=64,t=2,p=1$fgqGZy5Cpeaw8q+yh5503w$KITdQvBAtLzCtzp9nloPgaxKKR3JruVe/DtZz2Ip2F4",
+        "$argon2id$v=19$t=2,m=64,p=1$fgqGZy5Cpeaw8q+yh5503w$KITdQvBAtLzCtzp9nloPgaxKKR3JruVe/DtZz2Ip2F4",
+        "$argon2id$v=19$m=064,t=2,p=1$fgqGZy5Cpeaw8q+yh5503w$KITdQvBAtLzCtzp9nloPgaxKKR3JruVe/DtZz2Ip2F4",
+        "$argon2id$v=19$m=64,t=2,p=1$fgqGZy5Cpeaw8q+yh5503w=$KITdQvBAtLzCtzp9nloPgaxKKR3JruVe/DtZz2Ip2F4",
+        "$argon2id$v=19$m=64,t=2,p=1$fgqGZy5Cpeaw8q+yh5503x$KITdQvBAtLzCtzp9nloPgaxKKR3JruVe/DtZz2Ip2F4",
+        "$argon2id$v=19$m=64,t=2,p=1$fgqGZy5Cpeaw8q+yh5503w$KITdQvBAtLzCtzp9nloPgaxKKR3JruVe/DtZz2Ip2F4$",
+    ] {
+        assert_eq!(
+            verify_password(encoded, b"password"),
+            Err(Argon2Error::InvalidFormat),
+            "{encoded}"
+        );
+    }
+}
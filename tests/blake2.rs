@@ -0,0 +1,102 @@
+use hex::decode;
+use raycrypt::hashes::{blake2b, blake2s};
+use serde_json::{from_str, Value};
+use std::fs;
+
+struct Vector {
+    key: Vec<u8>,
+    input: Vec<u8>,
+    output_length: usize,
+    hash: Vec<u8>,
+}
+
+fn vectors(name: &str) -> Vec<Vector> {
+    let raw = fs::read_to_string("tests/vectors/blake2.json").unwrap();
+    let data: Value = from_str(&raw).unwrap();
+
+    data[name]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|test| Vector {
+            key: decode(test["key"].as_str().unwrap()).unwrap(),
+            input: decode(test["input"].as_str().unwrap()).unwrap(),
+            output_length: test["outputLength"].as_u64().unwrap() as usize,
+            hash: decode(test["hash"].as_str().unwrap()).unwrap(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_rfc7693() {
+    // RFC 7693 appendices A and B
+    assert_eq!(
+        blake2b::blake2b(b"abc", 64),
+        decode(concat!(
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1",
+            "7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        ))
+        .unwrap()
+    );
+    assert_eq!(
+        blake2s::blake2s(b"abc", 32),
+        decode("508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982").unwrap()
+    );
+}
+
+#[test]
+fn test_blake2b() {
+    for Vector {
+        key,
+        input,
+        output_length,
+        hash,
+    } in vectors("blake2b")
+    {
+        assert_eq!(blake2b::keyed(&key, &input, output_length), hash);
+
+        // any split of the input gives the same hash
+        for split in [1, 127, 128, 129] {
+            let mut hasher = blake2b::Blake2b::with_key(&key, output_length);
+            for chunk in input.chunks(split) {
+                hasher.update(chunk);
+            }
+            hasher.update(&[]);
+            assert_eq!(hasher.finalize(), hash);
+        }
+    }
+}
+
+#[test]
+fn test_blake2s() {
+    for Vector {
+        key,
+        input,
+        output_length,
+        hash,
+    } in vectors("blake2s")
+    {
+        assert_eq!(blake2s::keyed(&key, &input, output_length), hash);
+
+        for split in [1, 63, 64, 65] {
+            let mut hasher = blake2s::Blake2s::with_key(&key, output_length);
+            for chunk in input.chunks(split) {
+                hasher.update(chunk);
+            }
+            hasher.update(&[]);
+            assert_eq!(hasher.finalize(), hash);
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "BLAKE2b outputs are 1 to 64 bytes, got 65")]
+fn test_blake2b_output_length() {
+    blake2b::blake2b(b"", 65);
+}
+
+#[test]
+#[should_panic(expected = "BLAKE2s keys are at most 32 bytes, got 33")]
+fn test_blake2s_key_length() {
+    blake2s::keyed(&[0u8; 33], b"", 32);
+}
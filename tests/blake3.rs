@@ -0,0 +1,182 @@
+use hex::decode;
+use raycrypt::hashes::blake3::{self, derive_key, hash, keyed_hash, Backend, Blake3};
+use serde_json::{from_str, Value};
+use std::fs;
+
+struct Vector {
+    input: Vec<u8>,
+    hash: Vec<u8>,
+    keyed_hash: Vec<u8>,
+    derive_key: Vec<u8>,
+}
+
+/// Returns the key, the context string and the cases of the vectors, in the format of the
+/// official BLAKE3 test vectors: the input is the byte sequence 0, 1, ..., 250 repeated,
+/// and the outputs are 131 bytes, to cover several output blocks.
+fn vectors() -> (Vec<u8>, String, Vec<Vector>) {
+    let raw = fs::read_to_string("tests/vectors/blake3.json").unwrap();
+    let data: Value = from_str(&raw).unwrap();
+
+    let cases = data["cases"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|test| Vector {
+            input: (0..test["input_len"].as_u64().unwrap())
+                .map(|i| (i % 251) as u8)
+                .collect(),
+            hash: decode(test["hash"].as_str().unwrap()).unwrap(),
+            keyed_hash: decode(test["keyed_hash"].as_str().unwrap()).unwrap(),
+            derive_key: decode(test["derive_key"].as_str().unwrap()).unwrap(),
+        })
+        .collect();
+
+    (
+        data["key"].as_str().unwrap().as_bytes().to_vec(),
+        data["context_string"].as_str().unwrap().to_string(),
+        cases,
+    )
+}
+
+fn xof(hasher: Blake3, length: usize) -> Vec<u8> {
+    let mut output = vec![0u8; length];
+    hasher.finalize_xof().fill(&mut output);
+    output
+}
+
+#[test]
+fn test_vectors() {
+    let (key, context, cases) = vectors();
+
+    for case in cases {
+        let length = case.input.len();
+        assert_eq!(hash(&case.input)[..], case.hash[..32], "{length} bytes");
+        assert_eq!(keyed_hash(&key, &case.input)[..], case.keyed_hash[..32]);
+        assert_eq!(derive_key(&context, &case.input)[..], case.derive_key[..32]);
+
+        for (mut hasher, expected) in [
+            (Blake3::new(), &case.hash),
+            (Blake3::new_keyed(&key), &case.keyed_hash),
+            (Blake3::new_derive_key(&context), &case.derive_key),
+        ] {
+            hasher.update(&case.input);
+            assert_eq!(&xof(hasher, expected.len()), expected, "{length} bytes");
+        }
+    }
+}
+
+#[test]
+fn test_empty() {
+    assert_eq!(
+        hash(b"").to_vec(),
+        decode("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262").unwrap()
+    );
+}
+
+#[test]
+fn test_streaming() {
+    let (key, _, cases) = vectors();
+
+    // splits within a block, on block and chunk boundaries, and across subtrees
+    for case in cases.iter().filter(|case| case.input.len() <= 8193) {
+        let length = case.input.len();
+        for split in [1, 63, 64, 65, 1023, 1024, 1025, 2048, 4097, length / 2] {
+            let split = split.min(length);
+
+            let mut hasher = Blake3::new_keyed(&key);
+            hasher.update(&case.input[..split]);
+            hasher.update(&case.input[split..]);
+            assert_eq!(
+                hasher.finalize()[..],
+                case.keyed_hash[..32],
+                "{length} bytes split at {split}"
+            );
+        }
+    }
+
+    // one byte at a time
+    let case = cases.iter().find(|case| case.input.len() == 3073).unwrap();
+    let mut hasher = Blake3::new();
+    for byte in &case.input {
+        hasher.update(&[*byte]);
+    }
+    assert_eq!(hasher.finalize()[..], case.hash[..32]);
+}
+
+#[test]
+fn test_xof() {
+    let mut hasher = Blake3::new_derive_key("raycrypt 2024-06-01 blake3 xof test");
+    hasher.update(b"key material");
+    let expected = xof(hasher.clone(), 300);
+    assert_eq!(expected[..32], hasher.clone().finalize());
+
+    // reading in pieces that straddle the 64-byte output blocks
+    let mut reader = hasher.finalize_xof();
+    let mut output = vec![0u8; 300];
+    for piece in output.chunks_mut(37) {
+        reader.fill(piece);
+    }
+    assert_eq!(output, expected);
+    assert_eq!(reader.position(), 300);
+}
+
+#[test]
+fn test_backends() {
+    assert!(Backend::Portable.is_supported());
+    assert!(blake3::backend().is_supported());
+
+    let (_, _, cases) = vectors();
+
+    for backend in [Backend::Sse41, Backend::Portable] {
+        for case in &cases {
+            let Some(mut hasher) = Blake3::with_backend(backend) else {
+                continue;
+            };
+
+            hasher.update(&case.input);
+            assert_eq!(
+                hasher.finalize()[..],
+                case.hash[..32],
+                "{} on {} bytes",
+                backend.name(),
+                case.input.len()
+            );
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_invalid_key() {
+    Blake3::new_keyed(&[0u8; 31]);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_update_par() {
+    let data: Vec<u8> = (0..=250).cycle().take(1_000_000).collect();
+
+    // several subtrees, the last one partial, and starting part way into the first one
+    for offset in [0, 1, 1024, 5000, 65_536] {
+        for len in [0, 1, 65_536, 65_537, 200_000, 1_000_000 - 65_536] {
+            let mut serial = Blake3::new();
+            serial.update(&data[..offset]);
+            serial.update(&data[offset..offset + len]);
+
+            let mut parallel = Blake3::new();
+            parallel.update(&data[..offset]);
+            parallel.update_par(&data[offset..offset + len]);
+
+            assert_eq!(
+                parallel.finalize(),
+                serial.finalize(),
+                "{len} bytes after {offset}"
+            );
+        }
+    }
+
+    let case = vectors().2.pop().unwrap();
+    let mut hasher = Blake3::new();
+    hasher.update_par(&case.input);
+    assert_eq!(hasher.finalize()[..], case.hash[..32]);
+}
@@ -1,5 +1,6 @@
 use raycrypt::aeads;
 use raycrypt::ciphers::chacha;
+use raycrypt::errors::{DecryptError, InvalidLength};
 use serde_json::{from_str, Value};
 use std::fs;
 
@@ -23,11 +24,74 @@ fn test_keystream() {
         0xa2, 0x50, 0x3c, 0x4e,
     ];
 
-    let output = chacha::keystream(key.to_vec(), &nonce, 1, None);
+    let output = chacha::keystream(&key, &nonce, 1, None);
 
     assert_eq!(output, expected_output);
 }
 
+#[test]
+fn test_keystream_little_endian() {
+    let vectors: [([u8; 32], [u8; 12], u32, &str); 5] = [
+        (
+            [0u8; 32],
+            [0u8; 12],
+            0,
+            "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586",
+        ),
+        (
+            {
+                let mut key = [0u8; 32];
+                key[31] = 1;
+                key
+            },
+            [0u8; 12],
+            1,
+            "3aeb5224ecf849929b9d828db1ced4dd832025e8018b8160b82284f3c949aa5a8eca00bbb4a73bdad192b5c42f73f2fd4e273644c8b36125a64addeb006c13a0",
+        ),
+        (
+            {
+                let mut key = [0u8; 32];
+                key[1] = 0xff;
+                key
+            },
+            [0u8; 12],
+            2,
+            "72d54dfbf12ec44b362692df94137f328fea8da73990265ec1bbbea1ae9af0ca13b25aa26cb4a648cb9b9d1be65b2c0924a66c54d545ec1b7374f4872e99f096",
+        ),
+        (
+            [0u8; 32],
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2],
+            0,
+            "c2c64d378cd536374ae204b9ef933fcd1a8b2288b3dfa49672ab765b54ee27c78a970e0e955c14f3a88e741b97c286f75f8fc299e8148362fa198a39531bed6d",
+        ),
+        (
+            [
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+                0x1c, 0x1d, 0x1e, 0x1f,
+            ],
+            [
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,
+            ],
+            0x04030201,
+            "e7df5e57378a8ecb6e95fd45f8901bb204570c24b2e3f44fafa227211792af00ad4efe399de5000a541f923922b8e469fadcd4914342726d55a9d788a6f2a1b2",
+        ),
+    ];
+
+    for (key, nonce, counter, expected) in vectors {
+        let expected = hex::decode(expected).unwrap();
+
+        assert_eq!(
+            chacha::keystream(&key, &nonce, counter, None).to_vec(),
+            expected
+        );
+        assert_eq!(
+            chacha::ChaCha::new(&key, None).encrypt_with_counter(&[0u8; 64], &nonce, counter),
+            expected
+        );
+    }
+}
+
 #[test]
 fn test_chacha() {
     let key = [
@@ -53,9 +117,158 @@ fn test_chacha() {
 
     let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
 
-    let output = chacha::encrypt(key.to_vec(), plaintext, &nonce, None);
+    let output = chacha::encrypt(&key, plaintext, &nonce, None);
 
     assert_eq!(output, expected_output);
+
+    // the 114 bytes cross into a second block, which must use counter 2, on every
+    // backend, including those that generate several blocks at once
+    for backend in [
+        chacha::Backend::Avx512,
+        chacha::Backend::Avx2,
+        chacha::Backend::Sse2,
+        chacha::Backend::Neon,
+        chacha::Backend::Fallback,
+    ] {
+        let Some(cipher) = chacha::ChaCha::with_backend(&key, None, backend) else {
+            continue;
+        };
+
+        assert_eq!(
+            cipher.encrypt(plaintext, &nonce),
+            expected_output,
+            "{:?}",
+            backend
+        );
+
+        let second_block: Vec<u8> = plaintext[64..]
+            .iter()
+            .zip(cipher.keystream(&nonce, 2))
+            .map(|(a, b)| a ^ b)
+            .collect();
+        assert_eq!(second_block, expected_output[64..]);
+    }
+}
+
+#[test]
+fn test_chacha_iv() {
+    // RFC 8439, appendix A.2, test vector #3: counter 42 in the first four bytes of the IV
+    let key =
+        hex::decode("1c9240a5eb55d38af333888604f6b5f0473917c1402b80099dca5cbc207075c0").unwrap();
+    let iv: [u8; 16] = hex::decode("2a000000000000000000000000000002")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let plaintext = b"'Twas brillig, and the slithy toves\nDid gyre and gimble in the wabe:\nAll mimsy were the borogoves,\nAnd the mome raths outgrabe.";
+    let expected = hex::decode("62e6347f95ed87a45ffae7426f27a1df5fb69110044c0d73118effa95b01e5cf166d3df2d721caf9b21e5fb14c616871fd84c54f9d65b283196c7fe4f60553ebf39c6402c42234e32a356b3e764312a61a5532055716ead6962568f87d3f3f7704c6a8d1bcd1bf4d50d6154b6da731b187b58dfd728afa36757a797ac188d1").unwrap();
+
+    assert_eq!(
+        chacha::split_iv(&iv),
+        ([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2], 42)
+    );
+    assert_eq!(chacha::encrypt_iv(&key, plaintext, &iv, None), expected);
+    assert_eq!(
+        chacha::ChaCha::new(&key, None).encrypt_iv(plaintext, &iv),
+        expected
+    );
+}
+
+#[test]
+fn test_chacha_stream() {
+    let key = [0x11u8; 32];
+    let nonce = [0x22u8; 12];
+    let plaintext = [0x33u8; 500];
+
+    let expected = chacha::ChaCha::new(&key, None).encrypt_with_counter(&plaintext, &nonce, 1);
+
+    let mut stream = chacha::ChaChaStream::new(&key, &nonce, 1, None);
+    assert_eq!(stream.keystream_remaining(), 0);
+
+    let mut output = stream.apply_keystream(&plaintext[..100]);
+    assert_eq!(stream.keystream_remaining(), 28);
+
+    output.extend(stream.apply_keystream(&plaintext[100..110]));
+    assert_eq!(stream.keystream_remaining(), 18);
+
+    output.extend(stream.apply_keystream(&plaintext[110..128]));
+    assert_eq!(stream.keystream_remaining(), 0);
+
+    output.extend(stream.apply_keystream(&plaintext[128..]));
+    assert_eq!(stream.keystream_remaining(), 12);
+
+    assert_eq!(output, expected);
+
+    // seeking drops the leftover keystream and restarts at a block boundary
+    stream.seek(3);
+    assert_eq!(stream.keystream_remaining(), 0);
+    assert_eq!(
+        stream.apply_keystream(&plaintext[128..200]),
+        expected[128..200]
+    );
+
+    stream.seek(1);
+    assert_eq!(stream.apply_keystream(&plaintext[..10]), expected[..10]);
+}
+
+#[test]
+fn test_chacha_variants() {
+    use chacha::{ChaCha, Variant};
+
+    let key: Vec<u8> = (0..32).collect();
+    let cipher = ChaCha::new(&key, None);
+
+    // libsodium's crypto_stream_chacha20_xor_ic, at counters 0, 2^32 - 1 and 2^32, so
+    // the middle one crosses into the high counter word
+    let nonce: Vec<u8> = (0..8).collect();
+    let first = hex::decode(
+        "f798a189f195e66982105ffb640bb7757f579da31602fc93ec01ac56f85ac3c1\
+         34a4547b733b46413042c9440049176905d3be59ea1c53f15916155c2be8241a",
+    )
+    .unwrap();
+    let crossing = hex::decode(
+        "a2b8d04b13877b4a7013cb9031e4b70836e9705a9691bd18f8fca48502eacdca\
+         e0b8faaeef6c5dfee436afd8268aa6385dabb2855761127a3946b50d649f9a4b\
+         2fcab2c09a960545c6f57e9269ebc22b4ed12782e66dc4cb612536f5cdbed4bc\
+         ba16af8a92140bf4ded4808af8eee82bd0f18fbb64f073c2a547bc2372528f36",
+    )
+    .unwrap();
+
+    let encrypt =
+        |len, counter| cipher.encrypt_with_variant(&vec![0u8; len], &nonce, counter, Variant::Djb);
+    assert_eq!(encrypt(64, 0).unwrap(), first);
+    assert_eq!(encrypt(128, u32::MAX as u64).unwrap(), crossing);
+    assert_eq!(encrypt(64, 1 << 32).unwrap(), crossing[64..]);
+
+    assert!(encrypt(64, u64::MAX).is_ok());
+    assert!(encrypt(65, u64::MAX).is_err());
+    assert!(cipher
+        .encrypt_with_variant(b"message", &[0u8; 12], 0, Variant::Djb)
+        .is_err());
+
+    // the IETF variant fails where the 32-bit counter runs out instead of wrapping
+    let nonce = [0x22u8; 12];
+    let plaintext = [0x33u8; 100];
+    assert_eq!(
+        cipher
+            .encrypt_with_variant(&plaintext, &nonce, 1, Variant::Ietf)
+            .unwrap(),
+        cipher.encrypt_with_counter(&plaintext, &nonce, 1)
+    );
+    assert!(cipher
+        .encrypt_with_variant(&plaintext[..64], &nonce, u32::MAX as u64, Variant::Ietf)
+        .is_ok());
+    assert!(cipher
+        .encrypt_with_variant(&plaintext, &nonce, u32::MAX as u64, Variant::Ietf)
+        .is_err());
+    assert!(cipher
+        .encrypt_with_variant(&plaintext, &nonce, 1 << 32, Variant::Ietf)
+        .is_err());
+
+    let mut buffer = plaintext;
+    assert!(cipher
+        .apply_keystream_with_variant(&mut buffer, &nonce[..8], 0, Variant::Ietf)
+        .is_err());
+    assert_eq!(buffer, plaintext);
 }
 
 #[test]
@@ -88,9 +301,16 @@ fn test_chacha_aead() {
 
     let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
 
-    let output = aeads::chachapoly1305::encrypt(key.to_vec(), plaintext, &nonce, &aead, None);
+    let output = aeads::chachapoly1305::encrypt(&key, plaintext, &nonce, &aead, None);
 
     assert_eq!(output, expected_output.to_vec());
+
+    // neither the 12-byte AAD nor the 114-byte ciphertext is a multiple of 16, so this
+    // only authenticates if both are zero-padded before the length block
+    assert_eq!(
+        aeads::chachapoly1305::decrypt(&key, &expected_output, &nonce, &aead, None).unwrap(),
+        plaintext
+    );
 }
 
 #[test]
@@ -111,11 +331,22 @@ fn test_hchacha() {
         213, 138, 116, 168, 83, 193, 46, 196, 19, 38, 211, 236, 220,
     ];
 
-    let output = chacha::hchacha(key.to_vec(), &nonce, None);
+    let output = chacha::hchacha(&key, &nonce, None).unwrap();
 
     assert_eq!(output, expected_output);
 }
 
+#[test]
+fn test_hchacha_lengths() {
+    assert!(chacha::hchacha(&[], &[0u8; 16], None).is_err());
+    assert!(chacha::hchacha(&[0u8; 31], &[0u8; 16], None).is_err());
+    assert!(chacha::hchacha(&[0u8; 33], &[0u8; 16], None).is_err());
+    assert!(chacha::hchacha(&[0u8; 32], &[0u8; 15], None).is_err());
+    assert!(chacha::hchacha(&[0u8; 32], &[], None).is_err());
+
+    assert!(chacha::hchacha(&[0u8; 32], &[0u8; 16], None).is_ok());
+}
+
 #[test]
 fn test_xchacha() {
     let key = [
@@ -153,7 +384,7 @@ fn test_xchacha() {
         0xc8, 0xee, 0xbb, 0xd9, 0x30, 0x04, 0x10, 0x9d, 0xe8, 0x42,
     ];
 
-    let output = aeads::xchachapoly1305::encrypt(key.to_vec(), &plaintext, &nonce, &aad, None);
+    let output = aeads::xchachapoly1305::encrypt(&key, &plaintext, &nonce, &aad, None);
 
     assert_eq!(output, expected_ct.to_vec());
 }
@@ -188,12 +419,9 @@ fn test_tag() {
         0x6c, 0x64, 0x20, 0x62, 0x65, 0x20, 0x69, 0x74, 0x2e,
     ];
 
-    let output = aeads::xchachapoly1305::encrypt(key.to_vec(), &plaintext, &nonce, &aad, None);
+    let output = aeads::xchachapoly1305::encrypt(&key, &plaintext, &nonce, &aad, None);
 
-    let _ = match aeads::xchachapoly1305::decrypt(key.to_vec(), &output, &nonce, &false_aad, None) {
-        Ok(_) => Err(String::from("Tag checking failed")),
-        Err(_) => Ok(()),
-    };
+    assert!(aeads::xchachapoly1305::decrypt(&key, &output, &nonce, &false_aad, None).is_err());
 }
 
 #[test]
@@ -232,66 +460,722 @@ fn test_decrypt() {
         0xc8, 0xee, 0xbb, 0xd9, 0x30, 0x04, 0x10, 0x9d, 0xe8, 0x42,
     ];
 
-    let ciphertext = aeads::xchachapoly1305::encrypt(key.to_vec(), &plaintext, &nonce, &aad, None);
+    let ciphertext = aeads::xchachapoly1305::encrypt(&key, &plaintext, &nonce, &aad, None);
     assert_eq!(ciphertext, expected.to_vec());
 
-    let _ = match aeads::xchachapoly1305::decrypt(key.to_vec(), &ciphertext, &nonce, &aad, None) {
-        Ok(_) => Ok(()),
-        Err(_) => Err(String::from("Tag checking failed")),
-    };
+    let decrypted = aeads::xchachapoly1305::decrypt(&key, &ciphertext, &nonce, &aad, None);
+    assert_eq!(decrypted.unwrap(), plaintext.to_vec());
+}
+
+type Encrypt = fn(&[u8], &[u8], &[u8], &[u8], Option<usize>) -> Result<Vec<u8>, InvalidLength>;
+type Decrypt = fn(&[u8], &[u8], &[u8], &[u8], Option<usize>) -> Result<Vec<u8>, DecryptError>;
+
+/// Runs every vector of a Wycheproof AEAD file. Valid vectors have to encrypt to the
+/// expected output and decrypt back to the message. Invalid ones, with tampered tags or
+/// nonces of the wrong size, must fail to decrypt, and to encrypt if the nonce is wrong.
+fn check_wycheproof(path: &str, encrypt: Encrypt, decrypt: Decrypt) {
+    let raw = fs::read_to_string(path).unwrap();
+    let data: Value = from_str(&raw).unwrap();
+
+    let mut checked = 0;
+    for group in data["testGroups"].as_array().unwrap() {
+        for test in group["tests"].as_array().unwrap() {
+            let id = test["tcId"].as_u64().unwrap();
+            let key = hex::decode(test["key"].as_str().unwrap()).unwrap();
+            let nonce = hex::decode(test["iv"].as_str().unwrap()).unwrap();
+            let aad = hex::decode(test["aad"].as_str().unwrap()).unwrap();
+            let pt = hex::decode(test["msg"].as_str().unwrap()).unwrap();
+
+            let ciphertext = hex::decode(test["ct"].as_str().unwrap()).unwrap();
+            let tag = hex::decode(test["tag"].as_str().unwrap()).unwrap();
+            let expected = [ciphertext, tag].concat();
+
+            let output = encrypt(&key, &pt, &nonce, &aad, None);
+            let decrypted = decrypt(&key, &expected, &nonce, &aad, None);
+
+            if test["result"].as_str().unwrap() == "valid" {
+                assert_eq!(output.unwrap(), expected, "tcId {}", id);
+                assert_eq!(decrypted.unwrap(), pt, "tcId {}", id);
+            } else {
+                assert!(decrypted.is_err(), "tcId {}", id);
+                if let Ok(output) = output {
+                    assert_ne!(output, expected, "tcId {}", id);
+                }
+            }
+            checked += 1;
+        }
+    }
+
+    assert_eq!(checked, data["numberOfTests"].as_u64().unwrap());
 }
 
 #[test]
 fn test_chacha20_poly1305_wycheproof() {
-    let raw = fs::read_to_string("tests/vectors/chacha20-poly1305.json").unwrap();
-    let data: Value = from_str(&raw).unwrap();
+    check_wycheproof(
+        "tests/vectors/chacha20-poly1305.json",
+        aeads::chachapoly1305::try_encrypt,
+        aeads::chachapoly1305::try_decrypt,
+    );
+}
 
-    let tests = data["testGroups"][0]["tests"].as_array().unwrap();
+#[test]
+fn test_xchacha20_poly1305_wycheproof() {
+    check_wycheproof(
+        "tests/vectors/xchacha20-poly1305.json",
+        aeads::xchachapoly1305::try_encrypt,
+        aeads::xchachapoly1305::try_decrypt,
+    );
+}
 
-    for test in tests {
-        let key = hex::decode(test["key"].as_str().unwrap()).unwrap();
-        let nonce = hex::decode(test["iv"].as_str().unwrap()).unwrap();
-        let aad = hex::decode(test["aad"].as_str().unwrap()).unwrap();
-        let pt = hex::decode(test["msg"].as_str().unwrap()).unwrap();
+#[test]
+fn test_backend_cached() {
+    let backend = chacha::backend();
 
-        let ciphertext = hex::decode(test["ct"].as_str().unwrap()).unwrap();
-        let tag = hex::decode(test["tag"].as_str().unwrap()).unwrap();
+    assert_eq!(backend, chacha::detect_backend());
+    assert_eq!(backend, chacha::backend());
+}
 
-        let expected = [ciphertext.clone(), tag].concat();
+#[test]
+fn test_expand() {
+    let prk = [0x0bu8; 32];
 
-        let output = aeads::chachapoly1305::encrypt(key.to_vec(), &pt, &nonce, &aad, None);
+    let okm = chacha::expand(&prk, b"context", 100);
+    assert_eq!(okm.len(), 100);
+    assert_eq!(okm, chacha::expand(&prk, b"context", 100));
 
-        if test["result"].as_str().unwrap() == "valid" {
-            assert_eq!(output, expected);
-        } else {
-            assert_ne!(output, expected);
+    // shorter outputs are prefixes of longer ones
+    assert_eq!(okm[..42], chacha::expand(&prk, b"context", 42));
+
+    assert_ne!(okm, chacha::expand(&prk, b"other context", 100));
+    assert_ne!(okm, chacha::expand(&prk, b"", 100));
+    assert_ne!(okm, chacha::expand(&[0x0cu8; 32], b"context", 100));
+
+    // zero padding of the last info block does not collide
+    assert_ne!(
+        chacha::expand(&prk, b"info", 32),
+        chacha::expand(&prk, b"info\0", 32)
+    );
+}
+
+#[test]
+fn test_backend_parity() {
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = [0x4a; 12];
+    let plaintext: Vec<u8> = (0..=255).cycle().take(5000).collect();
+
+    for rounds in [8, 12, 20] {
+        let reference =
+            chacha::ChaCha::with_backend(&key, Some(rounds), chacha::Backend::Fallback).unwrap();
+
+        for backend in [
+            chacha::Backend::Avx512,
+            chacha::Backend::Avx2,
+            chacha::Backend::Sse2,
+            chacha::Backend::Neon,
+        ] {
+            let Some(cipher) = chacha::ChaCha::with_backend(&key, Some(rounds), backend) else {
+                continue;
+            };
+
+            assert_eq!(cipher.rounds(), rounds);
+            assert_eq!(cipher.keystream(&nonce, 7), reference.keystream(&nonce, 7));
+
+            for len in [
+                0, 1, 63, 64, 65, 127, 128, 129, 255, 256, 257, 511, 512, 513, 1000, 5000,
+            ] {
+                assert_eq!(
+                    cipher.encrypt_with_counter(&plaintext[..len], &nonce, 7),
+                    reference.encrypt_with_counter(&plaintext[..len], &nonce, 7),
+                    "{:?} with {} rounds differs on {} bytes",
+                    backend,
+                    rounds,
+                    len
+                );
+            }
+
+            // a batch of eight blocks ending on the last counter
+            let counter = u32::MAX - 7;
+            assert_eq!(
+                cipher.encrypt_with_counter(&plaintext[..512], &nonce, counter),
+                reference.encrypt_with_counter(&plaintext[..512], &nonce, counter)
+            );
+
+            // a batch of sixteen blocks ending on the last counter, and one followed by a
+            // tail
+            for (len, counter) in [(1024, u32::MAX - 15), (1100, u32::MAX - 17)] {
+                assert_eq!(
+                    cipher.encrypt_with_counter(&plaintext[..len], &nonce, counter),
+                    reference.encrypt_with_counter(&plaintext[..len], &nonce, counter)
+                );
+            }
         }
     }
 }
 
 #[test]
-fn test_xchacha20_poly1305_wycheproof() {
-    let raw = fs::read_to_string("tests/vectors/xchacha20-poly1305.json").unwrap();
-    let data: Value = from_str(&raw).unwrap();
+fn test_initial_state() {
+    // RFC 8439 section 2.3.2
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = [
+        0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x4a, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    let expected = [
+        [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574],
+        [0x03020100, 0x07060504, 0x0b0a0908, 0x0f0e0d0c],
+        [0x13121110, 0x17161514, 0x1b1a1918, 0x1f1e1d1c],
+        [0x00000001, 0x09000000, 0x4a000000, 0x00000000],
+    ];
+
+    assert_eq!(chacha::initial_state(&key, &nonce, 1).unwrap(), expected);
+
+    assert!(chacha::initial_state(&key, &nonce[..11], 1).is_err());
+    assert!(chacha::initial_state(&key, &[0u8; 16], 1).is_err());
+    assert!(chacha::initial_state(&key[..31], &nonce, 1).is_err());
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn test_trace() {
+    // RFC 8439 section 2.3.2
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = [
+        0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x4a, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    let cipher = chacha::ChaCha::new(&key, None);
+    let plaintext = [0x5au8; 100];
+    let (ciphertext, traces) = cipher.encrypt_traced(&plaintext, &nonce, 1);
+    assert_eq!(
+        ciphertext,
+        cipher.encrypt_with_counter(&plaintext, &nonce, 1)
+    );
+    assert_eq!(traces.len(), 2);
+
+    let block = &traces[0];
+    assert_eq!(block.counter, 1);
+    assert_eq!(
+        block.initial_state,
+        chacha::initial_state(&key, &nonce, 1).unwrap()
+    );
+    assert_eq!(
+        block.rounds_state,
+        [
+            [0x837778ab, 0xe238d763, 0xa67ae21e, 0x5950bb2f],
+            [0xc4f2d0c7, 0xfc62bb2f, 0x8fa018fc, 0x3f5ec7b7],
+            [0x335271c2, 0xf29489f3, 0xeabda8fc, 0x82e46ebd],
+            [0xd19c12b4, 0xb04e16de, 0x9e83d0cb, 0x4e3c50a2],
+        ]
+    );
+    assert_eq!(
+        hex::encode(block.keystream),
+        "10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e\
+         d2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e"
+    );
+
+    let block = &traces[1];
+    assert_eq!(block.counter, 2);
+    assert_eq!(block.keystream, cipher.keystream(&nonce, 2));
+}
+
+#[test]
+fn test_last_block_counter() {
+    assert_eq!(chacha::last_block_counter(0, 0), Ok(0));
+    assert_eq!(chacha::last_block_counter(1, 64), Ok(1));
+    assert_eq!(chacha::last_block_counter(1, 65), Ok(2));
+
+    // exactly the whole counter space
+    assert_eq!(chacha::last_block_counter(0, 64 << 32), Ok(u32::MAX));
+    assert!(chacha::last_block_counter(0, (64 << 32) + 1).is_err());
+    assert!(chacha::last_block_counter(1, 64 << 32).is_err());
+
+    // a block index that would truncate to 0 as u32
+    assert!(chacha::last_block_counter(0, (64 << 32) * 2).is_err());
+    assert!(chacha::last_block_counter(0, u64::MAX).is_err());
+
+    assert_eq!(chacha::last_block_counter(u32::MAX, 64), Ok(u32::MAX));
+    assert!(chacha::last_block_counter(u32::MAX, 65).is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_counter_overflow() {
+    chacha::ChaCha::new(&[0u8; 32], None).encrypt_with_counter(&[0u8; 65], &[0u8; 12], u32::MAX);
+}
+
+#[test]
+fn test_apply_keystream_range() {
+    let cipher = chacha::ChaCha::new(&[0x3au8; 32], None);
+    let nonce = [0x4bu8; 12];
+    let plaintext: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    let ciphertext = cipher.encrypt_with_counter(&plaintext, &nonce, 0);
+
+    // ranges starting inside, at the start and at the end of a block
+    for offset in [0, 1, 63, 64, 65, 100, 511, 512] {
+        for len in [0, 1, 10, 63, 64, 65, 200, 1000 - offset] {
+            let end = (offset + len).min(plaintext.len());
+
+            let mut range = plaintext[offset..end].to_vec();
+            cipher.apply_keystream_range(&mut range, &nonce, offset as u64);
+            assert_eq!(
+                range,
+                ciphertext[offset..end],
+                "{} bytes at {}",
+                len,
+                offset
+            );
+
+            cipher.apply_keystream_range(&mut range, &nonce, offset as u64);
+            assert_eq!(range, plaintext[offset..end]);
+        }
+
+        let keystream = cipher.encrypt_with_counter(&[0u8; 1100], &nonce, 0);
+        assert_eq!(
+            cipher.keystream_at(&nonce, offset as u64),
+            keystream[offset..offset + 64]
+        );
+    }
+
+    assert_eq!(cipher.keystream_at(&nonce, 64), cipher.keystream(&nonce, 1));
+}
 
-    let tests = data["testGroups"][0]["tests"].as_array().unwrap();
+#[test]
+#[should_panic]
+fn test_apply_keystream_range_overflow() {
+    let cipher = chacha::ChaCha::new(&[0u8; 32], None);
+    cipher.keystream_at(&[0u8; 12], ((u32::MAX as u64) << 6) + 1);
+}
+
+#[test]
+fn test_try_encrypt_with_counter() {
+    let cipher = chacha::ChaCha::new(&[0x17u8; 32], None);
+    let nonce = [0x29u8; 12];
+    let plaintext = [0x5cu8; 192];
+
+    // u32::MAX - 1 leaves two blocks before the counter would wrap to 0
+    let counter = u32::MAX - 1;
+    assert_eq!(
+        cipher.try_encrypt_with_counter(&plaintext[..128], &nonce, counter),
+        Ok(cipher.encrypt_with_counter(&plaintext[..128], &nonce, counter))
+    );
+    assert!(cipher
+        .try_encrypt_with_counter(&plaintext[..129], &nonce, counter)
+        .is_err());
+    assert!(cipher
+        .try_encrypt_with_counter(&plaintext, &nonce, counter)
+        .is_err());
+    assert!(cipher
+        .try_encrypt_with_counter(&plaintext[..65], &nonce, u32::MAX)
+        .is_err());
+}
+
+#[test]
+fn test_keystream_words() {
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = [0x4au8; 12];
+
+    let words = chacha::keystream_words(&key, &nonce, 3, 40, None).unwrap();
+    assert_eq!(words.len(), 40);
+
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    let expected = [
+        chacha::keystream(&key, &nonce, 3, None),
+        chacha::keystream(&key, &nonce, 4, None),
+        chacha::keystream(&key, &nonce, 5, None),
+    ]
+    .concat();
+    assert_eq!(bytes, expected[..160]);
+
+    assert!(chacha::keystream_words(&key, &nonce, 3, 0, None)
+        .unwrap()
+        .is_empty());
+    assert!(chacha::keystream_words(&key, &nonce[..8], 3, 4, None).is_err());
+    assert!(chacha::keystream_words(&key, &[0u8; 16], 3, 4, None).is_err());
+}
+
+#[test]
+#[should_panic(expected = "at least 8")]
+fn test_zero_rounds() {
+    chacha::ChaCha::new(&[0u8; 32], Some(0)).keystream(&[0u8; 12], 0);
+}
+
+#[test]
+#[should_panic(expected = "at least 8")]
+fn test_zero_rounds_hchacha() {
+    chacha::hchacha(&[0u8; 32], &[0u8; 16], Some(0)).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "at least 8")]
+fn test_too_few_rounds() {
+    chacha::ChaCha::new(&[0u8; 32], Some(6));
+}
+
+#[test]
+fn test_presets() {
+    let key = [0x42u8; 32];
+    let nonce = [0x24u8; 12];
+
+    for (preset, rounds) in [
+        (chacha::CHACHA8, 8),
+        (chacha::CHACHA12, 12),
+        (chacha::CHACHA20, 20),
+    ] {
+        let cipher = chacha::ChaCha::new(&key, preset);
+        assert_eq!(cipher.rounds(), rounds);
+        assert_eq!(
+            cipher.keystream(&nonce, 0),
+            chacha::ChaCha::new(&key, Some(rounds)).keystream(&nonce, 0)
+        );
+    }
+
+    assert_eq!(
+        chacha::ChaCha::new(&key, chacha::CHACHA20).keystream(&nonce, 0),
+        chacha::ChaCha::new(&key, None).keystream(&nonce, 0)
+    );
+}
 
-    for test in tests {
-        let key = hex::decode(test["key"].as_str().unwrap()).unwrap();
-        let nonce = hex::decode(test["iv"].as_str().unwrap()).unwrap();
-        let aad = hex::decode(test["aad"].as_str().unwrap()).unwrap();
-        let pt = hex::decode(test["msg"].as_str().unwrap()).unwrap();
+#[test]
+fn test_keystream_parity() {
+    let key: Vec<u8> = (100..132).collect();
+    let nonce = [0x17; 12];
+
+    let reference = chacha::ChaCha::with_backend(&key, None, chacha::Backend::Fallback).unwrap();
+    let cipher = chacha::ChaCha::new(&key, None);
+
+    for counter in [0, 1, 2, 0xffff, u32::MAX] {
+        assert_eq!(
+            cipher.keystream(&nonce, counter),
+            reference.keystream(&nonce, counter)
+        );
+        assert_eq!(
+            reference.keystream(&nonce, counter).to_vec(),
+            reference.encrypt_with_counter(&[0u8; 64], &nonce, counter)
+        );
+    }
+}
+
+#[test]
+fn test_encrypt_chunks() {
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = [0x4au8; 12];
+    let plaintext: Vec<u8> = (0..300).map(|i| i as u8).collect();
+    let cipher = chacha::ChaCha::new(&key, None);
 
-        let ciphertext = hex::decode(test["ct"].as_str().unwrap()).unwrap();
-        let tag = hex::decode(test["tag"].as_str().unwrap()).unwrap();
+    for chunk_size in [1, 7, 63, 64, 65, 128, 299, 300, 1000] {
+        let chunks: Vec<Vec<u8>> = cipher
+            .encrypt_chunks(&plaintext, &nonce, 1, chunk_size)
+            .collect();
 
-        let expected = [ciphertext.clone(), tag].concat();
+        assert_eq!(chunks.len(), plaintext.len().div_ceil(chunk_size));
+        assert!(chunks.iter().all(|chunk| chunk.len() <= chunk_size));
+        assert_eq!(chunks.concat(), cipher.encrypt(&plaintext, &nonce));
+    }
+
+    let chunks: Vec<Vec<u8>> = cipher.encrypt_chunks(&plaintext, &nonce, 5, 40).collect();
+    assert_eq!(
+        chunks.concat(),
+        cipher.encrypt_with_counter(&plaintext, &nonce, 5)
+    );
+
+    assert_eq!(cipher.encrypt_chunks(&[], &nonce, 1, 16).count(), 0);
+
+    // the last block of the counter can still be reached
+    let chunks: Vec<Vec<u8>> = cipher
+        .encrypt_chunks(&plaintext[..64], &nonce, u32::MAX, 10)
+        .collect();
+    assert_eq!(
+        chunks.concat(),
+        cipher.encrypt_with_counter(&plaintext[..64], &nonce, u32::MAX)
+    );
+}
+
+// without std the backend comes from the compile-time target features instead
+#[cfg(feature = "std")]
+#[test]
+fn test_active_backend() {
+    let name = chacha::active_backend();
+    assert!(["avx512", "avx2", "sse2", "neon", "portable"].contains(&name));
+    assert_eq!(name, chacha::backend().name());
 
-        let output = aeads::xchachapoly1305::encrypt(key.to_vec(), &pt, &nonce, &aad, None);
-        if test["result"].as_str().unwrap() == "valid" {
-            assert_eq!(output, expected);
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        let expected = if is_x86_feature_detected!("avx512f") {
+            "avx512"
+        } else if is_x86_feature_detected!("avx2") {
+            "avx2"
+        } else if is_x86_feature_detected!("sse2") {
+            "sse2"
         } else {
-            assert_ne!(output, expected);
+            "portable"
+        };
+        assert_eq!(name, expected);
+    }
+    #[cfg(all(target_arch = "aarch64", target_endian = "little"))]
+    assert_eq!(name, "neon");
+}
+
+#[test]
+fn test_chacha_stream_openssl() {
+    let key: Vec<u8> = (0..32).collect();
+    let mut iv = [0x4au8; 16];
+    iv[..4].copy_from_slice(&7u32.to_le_bytes());
+    let plaintext: Vec<u8> = (0..300).map(|i| i as u8).collect();
+
+    // produced by EVP_chacha20 with EVP_EncryptUpdate calls of 7, 100, 1, 63, 64, 65 bytes
+    let expected = hex::decode(
+        "a34230272cb8e4505319334634715c6dc193731b52aa6a294227ad10da27cc2518c21ebd3d09ef175adc9562\
+         0a47104e07a2888eca4704f6be1770bcbd6977d9b2207c641b2f138db9c447b82d9d6bb5c074a321956e2dc6\
+         d4f5eb947eeafde6f57698a06ba865ebd4ffb79d4ef7ad7e3f0e772246bb46c2e2e0f74f534014e4a2bb1579\
+         baf308aa95b012b60bac3cdc7c2a3c8abb16315ab912669e74e41c535365f16e1b7f59f738725fa32b2d4165\
+         7aae4f2f9f069d6e33eac8f67803f27f18391c8cd9eb3eaaf317f2b4526542931dc1f607b62cc0a3a3b5ae07\
+         6e8fd57b40594670469ad9edcc3ddccc427553ed3fd52b602bfa1cde7f8bdf65e4c14bf5022140cf058979b5\
+         7333fce12783975f549919b7649a1b740d6c4a7475c5e68230d79a9e193219f85137029f",
+    )
+    .unwrap();
+
+    for pieces in [
+        &[300][..],
+        &[7, 100, 1, 63, 64, 65],
+        &[1; 300],
+        &[13, 51, 236],
+    ] {
+        let mut stream = chacha::ChaChaStream::from_iv(&key, &iv, None);
+
+        let mut output = Vec::new();
+        let mut position = 0;
+        for &len in pieces {
+            output.extend(stream.apply_keystream(&plaintext[position..position + len]));
+            position += len;
         }
+
+        assert_eq!(output, expected);
     }
 }
+
+// optimised builds may leave stale copies of the key from construction in the padding
+// of the backend enum, which no Drop impl can reach, so the check only runs in debug builds
+#[test]
+#[cfg(debug_assertions)]
+fn test_zeroize_on_drop() {
+    use std::mem::{size_of, MaybeUninit};
+
+    let backends = [
+        chacha::Backend::Avx512,
+        chacha::Backend::Avx2,
+        chacha::Backend::Sse2,
+        chacha::Backend::Neon,
+        chacha::Backend::Fallback,
+    ];
+
+    for (i, backend) in backends.into_iter().enumerate() {
+        // a key per backend, as the stack may still hold moved-from copies of the
+        // previous cipher, which dropping this one cannot wipe
+        let key: Vec<u8> = (0..32).map(|j| 0xa0 ^ (i * 32 + j) as u8).collect();
+        let Some(cipher) = chacha::ChaCha::with_backend(&key, None, backend) else {
+            continue;
+        };
+
+        let mut slot = MaybeUninit::new(cipher);
+        let bytes = slot.as_mut_ptr() as *const u8;
+        let contains_key = || unsafe {
+            std::slice::from_raw_parts(bytes, size_of::<chacha::ChaCha>())
+                .windows(16)
+                .any(|window| window == &key[..16] || window == &key[16..])
+        };
+
+        assert!(contains_key());
+        unsafe { slot.assume_init_drop() };
+        assert!(!contains_key());
+    }
+}
+
+#[test]
+fn test_hchacha_method() {
+    let key: Vec<u8> = (0x20..0x40).collect();
+
+    for rounds in [None, Some(8), Some(12)] {
+        for nonce in [[0u8; 16], [0xffu8; 16], *b"ChaChaPoly rekey"] {
+            for backend in [
+                chacha::Backend::Avx512,
+                chacha::Backend::Avx2,
+                chacha::Backend::Sse2,
+                chacha::Backend::Neon,
+                chacha::Backend::Fallback,
+            ] {
+                let Some(cipher) = chacha::ChaCha::with_backend(&key, rounds, backend) else {
+                    continue;
+                };
+
+                assert_eq!(
+                    cipher.hchacha(&nonce),
+                    chacha::hchacha(&key, &nonce, rounds).unwrap()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_encrypt_in_place() {
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = [0x4a; 12];
+    let plaintext: Vec<u8> = (0..=255).cycle().take(1000).collect();
+
+    for backend in [
+        chacha::Backend::Avx512,
+        chacha::Backend::Avx2,
+        chacha::Backend::Sse2,
+        chacha::Backend::Neon,
+        chacha::Backend::Fallback,
+    ] {
+        let Some(cipher) = chacha::ChaCha::with_backend(&key, None, backend) else {
+            continue;
+        };
+
+        for len in [0, 1, 15, 16, 17, 63, 64, 65, 127, 128, 129, 1000] {
+            let mut buffer = plaintext[..len].to_vec();
+            cipher.encrypt_in_place(&mut buffer, &nonce, 7);
+            assert_eq!(
+                buffer,
+                cipher.encrypt_with_counter(&plaintext[..len], &nonce, 7),
+                "{:?} differs on {} bytes",
+                backend,
+                len
+            );
+
+            cipher.encrypt_in_place(&mut buffer, &nonce, 7);
+            assert_eq!(buffer, plaintext[..len]);
+        }
+    }
+}
+
+#[test]
+fn test_xchacha_skips_poly1305_block() {
+    let key = [0x80u8; 32];
+    let nonce: Vec<u8> = (0..24).collect();
+
+    let output = aeads::xchachapoly1305::encrypt(&key, &[0u8; 128], &nonce, b"", None);
+
+    let subkey = chacha::hchacha(&key, &nonce[..16], None).unwrap();
+    let mut subnonce = [0u8; 12];
+    subnonce[4..].copy_from_slice(&nonce[16..]);
+
+    // the ciphertext of zeros is the subcipher's keystream from block 1 on; block 0 keys
+    // Poly1305 and must never show up in it
+    let poly1305_block = chacha::keystream(&subkey, &subnonce, 0, None);
+    let mut expected = chacha::keystream(&subkey, &subnonce, 1, None).to_vec();
+    expected.extend(chacha::keystream(&subkey, &subnonce, 2, None));
+
+    assert_eq!(output[..128], expected);
+    assert!(output
+        .windows(32)
+        .all(|window| window != &poly1305_block[..32]));
+}
+
+#[test]
+fn test_export_state() {
+    let key: Vec<u8> = (0..32).collect();
+    let rows = chacha::initial_state(&key, &[0u8; 12], 0).unwrap();
+
+    for backend in [
+        chacha::Backend::Avx512,
+        chacha::Backend::Avx2,
+        chacha::Backend::Sse2,
+        chacha::Backend::Neon,
+        chacha::Backend::Fallback,
+    ] {
+        let Some(cipher) = chacha::ChaCha::with_backend(&key, None, backend) else {
+            continue;
+        };
+
+        assert_eq!(
+            cipher.export_state()[..],
+            rows[..3].concat(),
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_encrypt_par() {
+    let cipher = chacha::ChaCha::new(&[0x5eu8; 32], None);
+    let nonce = [0x6fu8; 12];
+    let plaintext: Vec<u8> = (0..=255).cycle().take(300_000).collect();
+
+    // several segments, the last one partial, and buffers shorter than a segment
+    for len in [0, 1, 64, 65_536, 65_537, 300_000] {
+        assert_eq!(
+            cipher.encrypt_par(&plaintext[..len], &nonce),
+            cipher.encrypt(&plaintext[..len], &nonce),
+            "{} bytes",
+            len
+        );
+    }
+
+    // the last of the 4688 blocks uses counter u32::MAX
+    let counter = u32::MAX - 4687;
+    let mut buffer = plaintext.clone();
+    cipher.encrypt_in_place_par(&mut buffer, &nonce, counter);
+    assert_eq!(
+        buffer,
+        cipher.encrypt_with_counter(&plaintext, &nonce, counter)
+    );
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_encrypt_with_threads() {
+    let cipher = chacha::ChaCha::new(&[0x70u8; 32], None);
+    let nonce = [0x71u8; 12];
+    let plaintext: Vec<u8> = (0..=255).cycle().take(300_000).collect();
+    let expected = cipher.encrypt(&plaintext, &nonce);
+
+    for threads in [0, 1, 2, 3, 8] {
+        assert_eq!(
+            cipher.encrypt_with_threads(&plaintext, &nonce, threads),
+            expected,
+            "{} threads",
+            threads
+        );
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+#[should_panic]
+fn test_encrypt_par_counter_overflow() {
+    let cipher = chacha::ChaCha::new(&[0u8; 32], None);
+    cipher.encrypt_par_with_counter(&[0u8; 300_000], &[0u8; 12], u32::MAX - 4686);
+}
+
+#[test]
+fn test_chacha_decrypt() {
+    let key = [0x42u8; 32];
+    let nonce = [0x24u8; 12];
+    let plaintext: Vec<u8> = (0..=200).collect();
+
+    let cipher = chacha::ChaCha::new(&key, None);
+    let ciphertext = cipher.encrypt(&plaintext, &nonce);
+    assert_eq!(cipher.decrypt(&ciphertext, &nonce), plaintext);
+    assert_eq!(chacha::decrypt(&key, &ciphertext, &nonce, None), plaintext);
+    assert_eq!(cipher.rounds(), 20);
+    assert_eq!(
+        chacha::ChaCha::BLOCK_SIZE,
+        cipher.keystream(&nonce, 0).len()
+    );
+}
+
+#[test]
+#[should_panic(expected = "even number of rounds")]
+fn test_odd_rounds() {
+    chacha::ChaCha::new(&[0u8; 32], Some(7));
+}
+
+#[test]
+#[should_panic(expected = "even number of rounds")]
+fn test_odd_rounds_with_rounds() {
+    chacha::ChaCha::new(&[0u8; 32], None).with_rounds(13);
+}
@@ -0,0 +1,83 @@
+use raycrypt::aeads::chachablake2::{decrypt, encrypt, ChaChaBlake2, TAG_LENGTH};
+use raycrypt::ciphers::chacha;
+use raycrypt::errors::TagMismatch;
+
+#[test]
+fn test_round_trip() {
+    let key = [0x2au8; 32];
+    let nonce = [0x3bu8; 12];
+
+    for len in [0, 1, 64, 200] {
+        let plaintext = vec![0x4cu8; len];
+        let ciphertext = encrypt(&key, &plaintext, &nonce, b"ad", None);
+
+        assert_eq!(ciphertext.len(), len + TAG_LENGTH);
+        assert_eq!(
+            ciphertext[..len],
+            chacha::encrypt(&key, &plaintext, &nonce, None)
+        );
+        assert_eq!(
+            decrypt(&key, &ciphertext, &nonce, b"ad", None).unwrap(),
+            plaintext
+        );
+    }
+
+    let config = ChaChaBlake2::new(&key, None).config();
+    assert_eq!(config.algorithm, "ChaCha-BLAKE2b");
+    assert_eq!(config.tag_len, 32);
+}
+
+#[test]
+fn test_tamper() {
+    let key = [0x2au8; 32];
+    let nonce = [0x3bu8; 12];
+    let ciphertext = encrypt(&key, b"message", &nonce, b"ad", None);
+
+    for i in [0, 6, 7, ciphertext.len() - 1] {
+        let mut tampered = ciphertext.clone();
+        tampered[i] ^= 1;
+
+        assert_eq!(
+            decrypt(&key, &tampered, &nonce, b"ad", None),
+            Err(TagMismatch { length_valid: true })
+        );
+    }
+
+    assert!(decrypt(&key, &ciphertext, &nonce, b"other", None).is_err());
+    assert!(decrypt(&[0u8; 32], &ciphertext, &nonce, b"ad", None).is_err());
+    assert!(decrypt(&key, &ciphertext, &[0u8; 12], b"ad", None).is_err());
+
+    // moving bytes between the AAD and the ciphertext changes the tag
+    let shifted = encrypt(&key, b"", &nonce, b"admessage", None);
+    assert_ne!(shifted[..], ciphertext[7..]);
+
+    assert_eq!(
+        decrypt(&key, &ciphertext[..TAG_LENGTH - 1], &nonce, b"ad", None),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+}
+
+#[test]
+fn test_detached() {
+    let cipher = ChaChaBlake2::new(&[0x2au8; 32], None);
+    let nonce = [0x3bu8; 12];
+
+    let (ciphertext, tag) = cipher.encrypt_detached(b"message", &nonce, b"ad");
+    assert_eq!(
+        [ciphertext.as_slice(), &tag].concat(),
+        cipher.encrypt(b"message", &nonce, b"ad")
+    );
+    assert_eq!(
+        cipher.decrypt_detached(&ciphertext, &tag, &nonce, b"ad"),
+        Ok(b"message".to_vec())
+    );
+
+    let mut forged = tag;
+    forged[TAG_LENGTH - 1] ^= 1;
+    assert_eq!(
+        cipher.decrypt_detached(&ciphertext, &forged, &nonce, b"ad"),
+        Err(TagMismatch { length_valid: true })
+    );
+}
@@ -0,0 +1,1648 @@
+use raycrypt::aeads::{chachapoly1305, xchachapoly1305};
+use raycrypt::ciphers::chacha;
+use raycrypt::errors::{DecryptError, TagMismatch};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::io::IoSlice;
+
+/// Counts the bytes allocated by the current thread, so a test can check that a call
+/// did not allocate.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATED.try_with(|allocated| allocated.set(allocated.get() + layout.size()));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocated() -> usize {
+    ALLOCATED.with(Cell::get)
+}
+
+#[test]
+fn test_encrypt_sequence() {
+    let key = [0x42u8; 32];
+    let base_nonce = [0x24u8; 12];
+    let ad = b"sequence";
+
+    let messages: Vec<Vec<u8>> = (0..100).map(|i| vec![i as u8; i]).collect();
+
+    let ciphertexts =
+        chachapoly1305::encrypt_sequence(&key, messages.clone(), &base_nonce, ad, None);
+    assert_eq!(ciphertexts.len(), messages.len());
+
+    let nonces: HashSet<[u8; 12]> = (0..messages.len() as u64)
+        .map(|i| chachapoly1305::sequence_nonce(&base_nonce, i))
+        .collect();
+    assert_eq!(nonces.len(), messages.len());
+    assert_eq!(chachapoly1305::sequence_nonce(&base_nonce, 0), base_nonce);
+
+    for (index, (message, ciphertext)) in messages.iter().zip(&ciphertexts).enumerate() {
+        let nonce = chachapoly1305::sequence_nonce(&base_nonce, index as u64);
+        let plaintext = chachapoly1305::decrypt(&key, ciphertext, &nonce, ad, None).unwrap();
+
+        assert_eq!(&plaintext, message);
+    }
+}
+
+#[test]
+fn test_decrypt_sequence() {
+    let key = [0x43u8; 32];
+    let base_nonce = [0x25u8; 12];
+    let messages: Vec<Vec<u8>> = (0..20).map(|i| vec![i as u8; i * 3]).collect();
+
+    let mut ciphertexts =
+        chachapoly1305::encrypt_sequence(&key, messages.clone(), &base_nonce, b"ad", None);
+    assert_eq!(
+        chachapoly1305::decrypt_sequence(&key, &ciphertexts, &base_nonce, b"ad", None).unwrap(),
+        messages
+    );
+
+    ciphertexts.swap(3, 4);
+    assert_eq!(
+        chachapoly1305::decrypt_sequence(&key, &ciphertexts, &base_nonce, b"ad", None),
+        Err(chachapoly1305::SequenceError {
+            index: 3,
+            error: TagMismatch { length_valid: true }
+        })
+    );
+
+    ciphertexts.swap(3, 4);
+    ciphertexts[7].truncate(15);
+    let error =
+        chachapoly1305::decrypt_sequence(&key, &ciphertexts, &base_nonce, b"ad", None).unwrap_err();
+    assert_eq!(error.index, 7);
+    assert!(!error.error.length_valid);
+}
+
+#[test]
+fn test_xchacha_sequence() {
+    let key = [0x44u8; 32];
+    let base_nonce: [u8; 24] = core::array::from_fn(|i| i as u8);
+    let messages: Vec<Vec<u8>> = (0..50).map(|i| vec![i as u8; i]).collect();
+    let cipher = xchachapoly1305::XChaChaPoly1305::new(&key, None);
+
+    let ciphertexts = cipher.encrypt_sequence(messages.clone(), &base_nonce, b"ad");
+    assert_eq!(ciphertexts.len(), messages.len());
+
+    // each message is an ordinary XChaCha-Poly1305 message under its own nonce, and only
+    // the part of the nonce after the HChaCha input changes
+    for (index, (message, ciphertext)) in messages.iter().zip(&ciphertexts).enumerate() {
+        let nonce = xchachapoly1305::sequence_nonce(&base_nonce, index as u64);
+        assert_eq!(nonce[..16], base_nonce[..16]);
+        assert_eq!(ciphertext, &cipher.encrypt(message, &nonce, b"ad"));
+    }
+    assert_eq!(
+        xchachapoly1305::sequence_nonce(&base_nonce, 0)[..],
+        base_nonce[..]
+    );
+    let nonces: HashSet<[u8; 24]> = (0..messages.len() as u64)
+        .map(|i| xchachapoly1305::sequence_nonce(&base_nonce, i))
+        .collect();
+    assert_eq!(nonces.len(), messages.len());
+
+    assert_eq!(
+        xchachapoly1305::decrypt_sequence(&key, &ciphertexts, &base_nonce, b"ad", None).unwrap(),
+        messages
+    );
+    assert_eq!(
+        cipher
+            .decrypt_sequence(&ciphertexts, &base_nonce, b"other ad")
+            .unwrap_err()
+            .index,
+        0
+    );
+
+    let mut tampered = ciphertexts.clone();
+    tampered[49][0] ^= 1;
+    assert_eq!(
+        cipher.decrypt_sequence(&tampered, &base_nonce, b"ad"),
+        Err(chachapoly1305::SequenceError {
+            index: 49,
+            error: TagMismatch { length_valid: true }
+        })
+    );
+}
+
+#[test]
+fn test_trailer_ad() {
+    let key = [0x42u8; 32];
+    let nonce = [0x24u8; 12];
+    let plaintext = [0x11u8; 150];
+    let ad = b"header";
+    let trailer = b"trailer metadata";
+
+    let ciphertext =
+        chachapoly1305::encrypt_with_trailer(&key, &plaintext, &nonce, ad, trailer, None);
+
+    assert_eq!(
+        chachapoly1305::decrypt_with_trailer(&key, &ciphertext, &nonce, ad, trailer, None).unwrap(),
+        plaintext
+    );
+
+    assert!(chachapoly1305::decrypt_with_trailer(
+        &key,
+        &ciphertext,
+        &nonce,
+        ad,
+        b"trailer metadatA",
+        None
+    )
+    .is_err());
+    assert!(chachapoly1305::decrypt(&key, &ciphertext, &nonce, ad, None).is_err());
+
+    assert_eq!(
+        chachapoly1305::encrypt_with_trailer(&key, &plaintext, &nonce, ad, b"", None),
+        chachapoly1305::encrypt(&key, &plaintext, &nonce, ad, None)
+    );
+}
+
+#[test]
+fn test_config() {
+    let key: Vec<u8> = (0xa0..0xc0).collect();
+
+    let configs = [
+        (
+            chacha::ChaCha::new(&key, Some(12)).config(),
+            ("ChaCha", 12, 12, 0),
+        ),
+        (
+            chachapoly1305::ChaChaPoly1305::new(&key, None).config(),
+            ("ChaCha-Poly1305", 20, 12, 16),
+        ),
+        (
+            xchachapoly1305::XChaChaPoly1305::new(&key, Some(8)).config(),
+            ("XChaCha-Poly1305", 8, 24, 16),
+        ),
+    ];
+
+    for (config, (algorithm, rounds, nonce_len, tag_len)) in configs {
+        assert_eq!(config.algorithm, algorithm);
+        assert_eq!(config.rounds, rounds);
+        assert_eq!(config.nonce_len, nonce_len);
+        assert_eq!(config.tag_len, tag_len);
+
+        let logged = format!("{:?}", config);
+        assert!(!logged.contains(&format!("{:?}", key)));
+        assert!(!logged.contains(&hex::encode(&key)));
+        assert!(!logged.contains(&key[0].to_string()));
+    }
+}
+
+#[test]
+fn test_ad_binding() {
+    let key = [0x42u8; 32];
+    let nonce = [0x24u8; 12];
+    let xnonce = [0x24u8; 24];
+    let plaintext = [0x11u8; 100];
+
+    let ads: [&[u8]; 2] = [b"", b"authenticated header"];
+
+    for encrypt_ad in ads {
+        let ciphertext = chachapoly1305::encrypt(&key, &plaintext, &nonce, encrypt_ad, None);
+        let xciphertext = xchachapoly1305::encrypt(&key, &plaintext, &xnonce, encrypt_ad, None);
+
+        for decrypt_ad in ads {
+            let output = chachapoly1305::decrypt(&key, &ciphertext, &nonce, decrypt_ad, None);
+            let xoutput = xchachapoly1305::decrypt(&key, &xciphertext, &xnonce, decrypt_ad, None);
+
+            if encrypt_ad == decrypt_ad {
+                assert_eq!(output.unwrap(), plaintext);
+                assert_eq!(xoutput.unwrap(), plaintext);
+            } else {
+                assert!(output.is_err());
+                assert!(xoutput.is_err());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_single_use() {
+    let key = [0x24u8; 32];
+    let nonce = [0x18u8; 24];
+
+    let ciphertext =
+        xchachapoly1305::SingleUseCipher::new(&key, &nonce, None).encrypt(b"once", b"ad");
+
+    let cipher = xchachapoly1305::XChaChaPoly1305::new(&key, None);
+    assert_eq!(ciphertext, cipher.encrypt(b"once", &nonce, b"ad"));
+    assert_eq!(cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap(), b"once");
+}
+
+#[test]
+fn test_into() {
+    let key = [0x11u8; 32];
+    let nonce = [0x22u8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+
+    let mut ciphertext = Vec::new();
+    let mut plaintext = Vec::new();
+
+    for message in [
+        &b"a longer first message, over one block in size......"[..],
+        b"short",
+        b"",
+    ] {
+        cipher.encrypt_into(message, &nonce, b"ad", &mut ciphertext);
+        assert_eq!(ciphertext, cipher.encrypt(message, &nonce, b"ad"));
+
+        cipher
+            .decrypt_into(&ciphertext, &nonce, b"ad", &mut plaintext)
+            .unwrap();
+        assert_eq!(plaintext, message);
+    }
+
+    cipher.encrypt_into(b"message", &nonce, b"ad", &mut ciphertext);
+    let last = ciphertext.len() - 1;
+    ciphertext[last] ^= 1;
+
+    assert!(cipher
+        .decrypt_into(&ciphertext, &nonce, b"ad", &mut plaintext)
+        .is_err());
+    assert!(plaintext.is_empty());
+
+    assert!(cipher
+        .decrypt_into(&[0u8; 15], &nonce, b"ad", &mut plaintext)
+        .is_err());
+}
+
+#[test]
+fn test_aad_file() {
+    let key = [0x33u8; 32];
+    let nonce = [0x44u8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+
+    // spans several read chunks and ends on a partial Poly1305 block
+    for len in [0, 5, 16, 200_003] {
+        let aad: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        let path =
+            std::env::temp_dir().join(format!("raycrypt-aad-{}-{}", std::process::id(), len));
+        std::fs::write(&path, &aad).unwrap();
+
+        let output = cipher.encrypt_with_aad_file(b"plaintext", &nonce, &path);
+        std::fs::remove_file(&path).unwrap();
+
+        let output = output.unwrap();
+        assert_eq!(output, cipher.encrypt(b"plaintext", &nonce, &aad));
+        let (ciphertext, tag) = cipher.encrypt_detached(b"plaintext", &nonce, &aad);
+        assert_eq!(output, [ciphertext, tag.to_vec()].concat());
+        assert_eq!(cipher.decrypt(&output, &nonce, &aad).unwrap(), b"plaintext");
+    }
+
+    let missing = std::env::temp_dir().join("raycrypt-aad-missing");
+    assert!(cipher
+        .encrypt_with_aad_file(b"plaintext", &nonce, missing)
+        .is_err());
+}
+
+#[test]
+fn test_aad_chunks() {
+    let key = [0x35u8; 32];
+    let nonce = [0x46u8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+    let aad: Vec<u8> = (0..100).collect();
+    let ciphertext = cipher.encrypt(b"plaintext", &nonce, &aad);
+
+    // chunk sizes that split the Poly1305 blocks unevenly, including empty chunks
+    for size in [1, 7, 16, 33, 100] {
+        let chunks = aad.chunks(size);
+        assert_eq!(
+            cipher
+                .decrypt_with_aad_chunks(&ciphertext, &nonce, chunks)
+                .unwrap(),
+            b"plaintext"
+        );
+    }
+
+    let mut produced = 0;
+    let generator = std::iter::from_fn(|| {
+        produced += 1;
+        match produced {
+            1 => Some(aad[..40].to_vec()),
+            2 => Some(Vec::new()),
+            3 => Some(aad[40..].to_vec()),
+            _ => None,
+        }
+    });
+    assert_eq!(
+        chachapoly1305::decrypt_with_aad_chunks(&key, &ciphertext, &nonce, generator, None)
+            .unwrap(),
+        b"plaintext"
+    );
+
+    assert_eq!(
+        cipher.decrypt_with_aad_chunks(&ciphertext, &nonce, [&aad[..99]]),
+        cipher.decrypt(&ciphertext, &nonce, &aad[..99])
+    );
+    assert_eq!(
+        cipher.decrypt_with_aad_chunks(&ciphertext, &nonce, [&aad[..50], &aad[51..]]),
+        Err(chachapoly1305::TagMismatch { length_valid: true })
+    );
+    assert_eq!(
+        cipher.decrypt_with_aad_chunks(&ciphertext[..15], &nonce, [&aad]),
+        Err(chachapoly1305::TagMismatch {
+            length_valid: false
+        })
+    );
+
+    let empty = cipher.encrypt(b"", &nonce, b"");
+    let no_chunks: [&[u8]; 0] = [];
+    assert_eq!(
+        cipher
+            .decrypt_with_aad_chunks(&empty, &nonce, no_chunks)
+            .unwrap(),
+        b""
+    );
+    assert_eq!(cipher.encrypt_with_aad_chunks(b"", &nonce, no_chunks), empty);
+
+    for size in [1, 7, 16, 33, 100] {
+        assert_eq!(
+            cipher.encrypt_with_aad_chunks(b"plaintext", &nonce, aad.chunks(size)),
+            ciphertext
+        );
+    }
+    assert_eq!(
+        chachapoly1305::encrypt_with_aad_chunks(
+            &key,
+            b"plaintext",
+            &nonce,
+            [&aad[..3], &aad[3..]],
+            None
+        ),
+        ciphertext
+    );
+}
+
+#[test]
+fn test_incremental_aad() {
+    let key = [0x52u8; 32];
+    let nonce = [0x63u8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+    let aad: Vec<u8> = (0..70).collect();
+
+    for len in [0, 5, 16, 100] {
+        let plaintext = vec![0x11u8; len];
+        let expected = cipher.encrypt(&plaintext, &nonce, &aad);
+        let (body, tag) = chachapoly1305::split_tag(&expected).unwrap();
+
+        for size in [1, 10, 16, 17, 70] {
+            let mut encryptor = cipher.encryptor(&nonce, &aad[..size / 2]);
+            for part in aad[size / 2..].chunks(size) {
+                encryptor.update_aad(part);
+            }
+            let mut ciphertext = encryptor.update(&plaintext);
+            ciphertext.extend(encryptor.finalize());
+            assert_eq!(ciphertext, expected);
+
+            let mut decryptor = cipher.decryptor(&nonce, b"");
+            for part in aad.chunks(size) {
+                decryptor.update_aad(part);
+            }
+            assert_eq!(decryptor.update(body), plaintext);
+            assert_eq!(decryptor.finalize(tag), Ok(()));
+        }
+
+        // the AAD parts are authenticated as their concatenation
+        let mut decryptor = cipher.decryptor(&nonce, &aad[..30]);
+        decryptor.update_aad(&aad[31..]);
+        decryptor.update(body);
+        assert!(decryptor.finalize(tag).is_err());
+    }
+
+    // an XChaCha stream takes AAD parts too
+    let xnonce = [0x74u8; 24];
+    let xcipher = xchachapoly1305::XChaChaPoly1305::new(&key, None);
+    let mut encryptor = xcipher.encryptor(&xnonce, b"head");
+    encryptor.update_aad(b"er");
+    let mut ciphertext = encryptor.update(b"message");
+    ciphertext.extend(encryptor.finalize());
+    assert_eq!(ciphertext, xcipher.encrypt(b"message", &xnonce, b"header"));
+}
+
+#[test]
+#[should_panic(expected = "AAD must be supplied before any message data")]
+fn test_incremental_aad_after_data() {
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&[0u8; 32], None);
+    let mut encryptor = cipher.encryptor(&[0u8; 12], b"");
+    encryptor.update(b"message");
+    encryptor.update_aad(b"late");
+}
+
+#[test]
+fn test_detached() {
+    let key = [0x21u8; 32];
+    let nonce = [0x43u8; 24];
+    let plaintext = b"the tag is kept in a separate header field";
+
+    let (ciphertext, tag) =
+        chachapoly1305::encrypt_detached(&key, plaintext, &nonce[..12], b"ad", None);
+    assert_eq!(
+        chachapoly1305::join_tag(&ciphertext, &tag),
+        chachapoly1305::encrypt(&key, plaintext, &nonce[..12], b"ad", None)
+    );
+    assert_eq!(
+        chachapoly1305::decrypt_detached(&key, &ciphertext, &tag, &nonce[..12], b"ad", None)
+            .unwrap(),
+        plaintext
+    );
+
+    let mut tampered = tag;
+    tampered[0] ^= 1;
+    assert!(chachapoly1305::decrypt_detached(
+        &key,
+        &ciphertext,
+        &tampered,
+        &nonce[..12],
+        b"ad",
+        None
+    )
+    .is_err());
+
+    let (ciphertext, tag) = xchachapoly1305::encrypt_detached(&key, plaintext, &nonce, b"ad", None);
+    assert_eq!(
+        chachapoly1305::join_tag(&ciphertext, &tag),
+        xchachapoly1305::encrypt(&key, plaintext, &nonce, b"ad", None)
+    );
+    assert_eq!(
+        xchachapoly1305::decrypt_detached(&key, &ciphertext, &tag, &nonce, b"ad", None).unwrap(),
+        plaintext
+    );
+    assert!(
+        xchachapoly1305::decrypt_detached(&key, &ciphertext, &tag, &nonce, b"other", None).is_err()
+    );
+
+    // an empty plaintext is just the 16-byte tag
+    let (ciphertext, tag) = chachapoly1305::encrypt_detached(&key, b"", &nonce[..12], b"", None);
+    assert!(ciphertext.is_empty());
+    assert_eq!(
+        chachapoly1305::decrypt(&key, &tag, &nonce[..12], b"", None).unwrap(),
+        b""
+    );
+    let combined = xchachapoly1305::encrypt(&key, b"", &nonce, b"", None);
+    assert_eq!(combined.len(), 16);
+    assert_eq!(
+        xchachapoly1305::decrypt(&key, &combined, &nonce, b"", None).unwrap(),
+        b""
+    );
+    assert!(xchachapoly1305::decrypt(&key, &combined[..15], &nonce, b"", None).is_err());
+}
+
+#[test]
+fn test_encrypt_in_place() {
+    let key = [0x55u8; 32];
+    let nonce = [0x66u8; 12];
+    let plaintext: Vec<u8> = (0..=255).cycle().take(5000).collect();
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+
+    for len in [0, 1, 16, 64, 4096, 4097, 5000] {
+        let mut buffer = plaintext[..len].to_vec();
+        cipher.encrypt_in_place(&mut buffer, &nonce, b"ad");
+        assert_eq!(buffer, cipher.encrypt(&plaintext[..len], &nonce, b"ad"));
+
+        cipher.decrypt_in_place(&mut buffer, &nonce, b"ad").unwrap();
+        assert_eq!(buffer, plaintext[..len]);
+    }
+
+    let mut buffer = plaintext.clone();
+    chachapoly1305::encrypt_in_place(&key, &mut buffer, &nonce, b"ad", None);
+    assert_eq!(buffer.len(), plaintext.len() + 16);
+    assert_eq!(
+        chachapoly1305::decrypt(&key, &buffer, &nonce, b"ad", None).unwrap(),
+        plaintext
+    );
+}
+
+#[test]
+fn test_decrypt_in_place() {
+    let key = [0x55u8; 32];
+    let nonce = [0x66u8; 12];
+    let plaintext = b"decrypted without a second buffer, over a single block";
+
+    let mut buffer = chachapoly1305::encrypt(&key, plaintext, &nonce, b"ad", None);
+    chachapoly1305::decrypt_in_place(&key, &mut buffer, &nonce, b"ad", None).unwrap();
+    assert_eq!(buffer, plaintext);
+
+    let mut buffer = chachapoly1305::encrypt(&key, plaintext, &nonce, b"ad", None);
+    buffer[3] ^= 1;
+    let tampered = buffer.clone();
+
+    assert!(chachapoly1305::decrypt_in_place(&key, &mut buffer, &nonce, b"ad", None).is_err());
+    assert_eq!(buffer, tampered);
+
+    let mut buffer = chachapoly1305::encrypt(&key, plaintext, &nonce, b"ad", None);
+    let original = buffer.clone();
+
+    assert!(chachapoly1305::decrypt_in_place(&key, &mut buffer, &nonce, b"other", None).is_err());
+    assert_eq!(buffer, original);
+
+    let mut short = vec![0u8; 15];
+    assert!(chachapoly1305::decrypt_in_place(&key, &mut short, &nonce, b"", None).is_err());
+    assert_eq!(short, [0u8; 15]);
+}
+
+#[test]
+fn test_inverse_matrix() {
+    let key = [0x77u8; 32];
+    let nonce = [0x88u8; 24];
+    let data: Vec<u8> = (0..1000).map(|i| (i * 7 % 256) as u8).collect();
+
+    let chacha = chachapoly1305::ChaChaPoly1305::new(&key, None);
+    let xchacha = xchachapoly1305::XChaChaPoly1305::new(&key, None);
+
+    for len in [0, 1, 63, 64, 65, 127, 128, 129, 1000] {
+        for ad_len in [0, 1, 16, 17] {
+            let plaintext = &data[..len];
+            let ad = &data[..ad_len];
+
+            let ciphertext = chacha.encrypt(plaintext, &nonce[..12], ad);
+            assert_eq!(ciphertext.len(), len + 16);
+            assert_eq!(
+                chacha.decrypt(&ciphertext, &nonce[..12], ad).unwrap(),
+                plaintext,
+                "ChaCha-Poly1305 with {} bytes and {} bytes of AAD",
+                len,
+                ad_len
+            );
+
+            let ciphertext = xchacha.encrypt(plaintext, &nonce, ad);
+            assert_eq!(ciphertext.len(), len + 16);
+            assert_eq!(
+                xchacha.decrypt(&ciphertext, &nonce, ad).unwrap(),
+                plaintext,
+                "XChaCha-Poly1305 with {} bytes and {} bytes of AAD",
+                len,
+                ad_len
+            );
+        }
+    }
+}
+
+#[test]
+fn test_tag_mismatch() {
+    let key = [0x99u8; 32];
+    let nonce = [0xaau8; 12];
+    let ciphertext = chachapoly1305::encrypt(&key, b"message", &nonce, b"", None);
+
+    // well-formed, but under the wrong key
+    let error = chachapoly1305::decrypt(&[0u8; 32], &ciphertext, &nonce, b"", None).unwrap_err();
+    assert_eq!(error, TagMismatch { length_valid: true });
+
+    // too short to hold a tag
+    let error = chachapoly1305::decrypt(&key, &ciphertext[..15], &nonce, b"", None).unwrap_err();
+    assert_eq!(
+        error,
+        TagMismatch {
+            length_valid: false
+        }
+    );
+
+    let error = xchachapoly1305::decrypt(&key, &[], &[0u8; 24], b"", None).unwrap_err();
+    assert_eq!(
+        error,
+        TagMismatch {
+            length_valid: false
+        }
+    );
+
+    assert_ne!(
+        TagMismatch { length_valid: true }.to_string(),
+        TagMismatch {
+            length_valid: false
+        }
+        .to_string()
+    );
+}
+
+#[test]
+fn test_framed_aad() {
+    let key = [0xbbu8; 32];
+    let nonce = [0xccu8; 12];
+    let a = chachapoly1305::encrypt_framed_aad(&key, b"message", &nonce, &[b"ab", b"c"], None);
+    let b = chachapoly1305::encrypt_framed_aad(&key, b"message", &nonce, &[b"a", b"bc"], None);
+    let c = chachapoly1305::encrypt_framed_aad(&key, b"message", &nonce, &[b"abc"], None);
+    let d = chachapoly1305::encrypt_framed_aad(&key, b"message", &nonce, &[b"abc", b""], None);
+
+    let tags: HashSet<Vec<u8>> = [&a, &b, &c, &d].iter().map(|ct| ct[7..].to_vec()).collect();
+    assert_eq!(tags.len(), 4);
+
+    assert_eq!(
+        chachapoly1305::decrypt_framed_aad(&key, &a, &nonce, &[b"ab", b"c"], None).unwrap(),
+        b"message"
+    );
+    assert!(chachapoly1305::decrypt_framed_aad(&key, &a, &nonce, &[b"a", b"bc"], None).is_err());
+
+    // the framing is a plain encoding of the AAD
+    assert_eq!(
+        a,
+        chachapoly1305::encrypt(&key, b"message", &nonce, b"\x02\0\0\0ab\x01\0\0\0c", None)
+    );
+}
+
+#[test]
+fn test_parity_short() {
+    use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+
+    let key = [0xddu8; 32];
+    let nonce = [0xeeu8; 12];
+    let reference = chacha20poly1305::ChaCha20Poly1305::new((&key).into());
+
+    for len in [0, 64, 128] {
+        let plaintext = vec![0x5au8; len];
+        let payload = Payload {
+            msg: &plaintext,
+            aad: b"ad",
+        };
+
+        let ciphertext = chachapoly1305::encrypt(&key, &plaintext, &nonce, b"ad", None);
+        assert_eq!(
+            ciphertext,
+            reference.encrypt((&nonce).into(), payload).unwrap()
+        );
+        assert_eq!(ciphertext.len(), len + 16);
+
+        let mut into = Vec::new();
+        chachapoly1305::ChaChaPoly1305::new(&key, None)
+            .encrypt_into(&plaintext, &nonce, b"ad", &mut into);
+        assert_eq!(into, ciphertext);
+
+        assert_eq!(
+            chachapoly1305::decrypt(&key, &ciphertext, &nonce, b"ad", None).unwrap(),
+            plaintext
+        );
+    }
+}
+
+#[test]
+fn test_split_join_tag() {
+    let key = [0x12u8; 32];
+    let nonce = [0x34u8; 12];
+
+    for len in [0, 1, 16, 100] {
+        let plaintext = vec![0x56u8; len];
+        let combined = chachapoly1305::encrypt(&key, &plaintext, &nonce, b"", None);
+
+        let (ciphertext, tag) = chachapoly1305::split_tag(&combined).unwrap();
+        assert_eq!(ciphertext.len(), len);
+        assert_eq!(&combined[len..], tag);
+
+        let joined = chachapoly1305::join_tag(ciphertext, tag);
+        assert_eq!(joined, combined);
+        assert_eq!(
+            chachapoly1305::decrypt(&key, &joined, &nonce, b"", None).unwrap(),
+            plaintext
+        );
+    }
+
+    assert!(chachapoly1305::split_tag(&[0u8; 15]).is_err());
+    assert!(chachapoly1305::split_tag(&[]).is_err());
+    assert_eq!(
+        chachapoly1305::split_tag(&[7u8; 16]).unwrap(),
+        (&[][..], &[7u8; 16])
+    );
+}
+
+#[test]
+fn test_segments() {
+    let key = [0x21u8; 32];
+    let nonce = [0x43u8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+
+    let header = [0x01u8; 10];
+    let body = [0x02u8; 70];
+    let footer = [0x03u8; 5];
+    let more = [0x04u8; 100];
+    let segments: [(&[u8], bool); 4] = [
+        (&header, false),
+        (&body, true),
+        (&footer, false),
+        (&more, true),
+    ];
+
+    let output = cipher.encrypt_segments(&segments, &nonce, b"ad");
+    assert_eq!(output.len(), 10 + 70 + 5 + 100 + 16);
+
+    // cleartext segments are copied, encrypted ones continue the same keystream
+    assert_eq!(output[..10], header);
+    assert_eq!(output[80..85], footer);
+
+    let keystream = chachapoly1305::chacha::ChaCha::new(&key, None).encrypt(&[0u8; 170], &nonce);
+    let expected_body: Vec<u8> = body
+        .iter()
+        .zip(&keystream[..70])
+        .map(|(a, b)| a ^ b)
+        .collect();
+    let expected_more: Vec<u8> = more
+        .iter()
+        .zip(&keystream[70..])
+        .map(|(a, b)| a ^ b)
+        .collect();
+    assert_eq!(output[10..80], expected_body);
+    assert_eq!(output[85..185], expected_more);
+
+    let layout = [(10, false), (70, true), (5, false), (100, true)];
+    let plaintext = cipher
+        .decrypt_segments(&output, &layout, &nonce, b"ad")
+        .unwrap();
+    assert_eq!(plaintext, [&header[..], &body, &footer, &more].concat());
+
+    // tampering with a cleartext segment is detected
+    let mut tampered = output.clone();
+    tampered[0] ^= 1;
+    assert_eq!(
+        cipher.decrypt_segments(&tampered, &layout, &nonce, b"ad"),
+        Err(TagMismatch { length_valid: true })
+    );
+
+    // as is a layout that does not cover the input
+    assert_eq!(
+        cipher.decrypt_segments(&output, &layout[..3], &nonce, b"ad"),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+
+    // with every segment encrypted this is plain ChaCha-Poly1305
+    let all: [(&[u8], bool); 2] = [(&body, true), (&more, true)];
+    assert_eq!(
+        cipher.encrypt_segments(&all, &nonce, b"ad"),
+        cipher.encrypt(&[&body[..], &more].concat(), &nonce, b"ad")
+    );
+}
+
+#[test]
+fn test_decrypt_tag_only() {
+    let key = [0x65u8; 32];
+    let nonce = [0x87u8; 24];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+
+    let ciphertext = cipher.encrypt(b"", &nonce[..12], b"ad");
+    assert_eq!(ciphertext.len(), 16);
+    assert_eq!(
+        cipher.decrypt(&ciphertext, &nonce[..12], b"ad").unwrap(),
+        b""
+    );
+
+    let mut out = vec![1, 2, 3];
+    cipher
+        .decrypt_into(&ciphertext, &nonce[..12], b"ad", &mut out)
+        .unwrap();
+    assert!(out.is_empty());
+
+    let mut buffer = ciphertext.clone();
+    cipher
+        .decrypt_in_place(&mut buffer, &nonce[..12], b"ad")
+        .unwrap();
+    assert!(buffer.is_empty());
+
+    // the tag still covers the AAD
+    assert_eq!(
+        cipher.decrypt(&ciphertext, &nonce[..12], b"other"),
+        Err(TagMismatch { length_valid: true })
+    );
+
+    let ciphertext = xchachapoly1305::encrypt(&key, b"", &nonce, b"ad", None);
+    assert_eq!(ciphertext.len(), 16);
+    assert_eq!(
+        xchachapoly1305::decrypt(&key, &ciphertext, &nonce, b"ad", None).unwrap(),
+        b""
+    );
+}
+
+#[test]
+fn test_mac_key() {
+    let key = [0x0fu8; 32];
+    let mac_key = [0xf0u8; 32];
+    let nonce = [0x1eu8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+
+    let ciphertext = cipher.encrypt_with_mac_key(b"message", &nonce, b"ad", &mac_key);
+    assert_eq!(
+        cipher
+            .decrypt_with_mac_key(&ciphertext, &nonce, b"ad", &mac_key)
+            .unwrap(),
+        b"message"
+    );
+
+    // same ciphertext as RFC 8439, but a different tag
+    let standard = cipher.encrypt(b"message", &nonce, b"ad");
+    assert_eq!(ciphertext[..7], standard[..7]);
+    assert_ne!(ciphertext[7..], standard[7..]);
+    assert!(cipher.decrypt(&ciphertext, &nonce, b"ad").is_err());
+
+    let mut tampered = ciphertext.clone();
+    tampered[0] ^= 1;
+    assert!(cipher
+        .decrypt_with_mac_key(&tampered, &nonce, b"ad", &mac_key)
+        .is_err());
+
+    assert!(cipher
+        .decrypt_with_mac_key(&ciphertext, &nonce, b"ad", &[0u8; 32])
+        .is_err());
+    assert!(cipher
+        .decrypt_with_mac_key(&ciphertext, &nonce, b"other", &mac_key)
+        .is_err());
+}
+
+#[test]
+fn test_shared_across_threads() {
+    let cipher = std::sync::Arc::new(xchachapoly1305::XChaChaPoly1305::new(&[0x3cu8; 32], None));
+
+    let handles: Vec<_> = (0..16u8)
+        .map(|thread| {
+            let cipher = cipher.clone();
+
+            std::thread::spawn(move || {
+                (0..64u8)
+                    .map(|i| {
+                        let mut nonce = [0u8; 24];
+                        nonce[0] = thread;
+                        nonce[1] = i;
+
+                        let plaintext = vec![thread ^ i; i as usize * 3];
+                        (
+                            nonce,
+                            plaintext.clone(),
+                            cipher.encrypt(&plaintext, &nonce, b"ad"),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    let mut ciphertexts = HashSet::new();
+    for handle in handles {
+        for (nonce, plaintext, ciphertext) in handle.join().unwrap() {
+            assert_eq!(
+                cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap(),
+                plaintext
+            );
+            assert!(ciphertexts.insert(ciphertext));
+        }
+    }
+}
+
+#[test]
+fn test_context() {
+    let key = [0x1du8; 32];
+    let nonce = [0x2eu8; 12];
+
+    let a = chachapoly1305::encrypt_with_context(&key, b"message", &nonce, b"ad", "app/v1", None);
+    let b = chachapoly1305::encrypt_with_context(&key, b"message", &nonce, b"ad", "app/v2", None);
+
+    // the keystream is the same, only the tags differ
+    assert_eq!(a[..7], b[..7]);
+    assert_ne!(a[7..], b[7..]);
+
+    assert_eq!(
+        chachapoly1305::decrypt_with_context(&key, &a, &nonce, b"ad", "app/v1", None).unwrap(),
+        b"message"
+    );
+    assert!(chachapoly1305::decrypt_with_context(&key, &a, &nonce, b"ad", "app/v2", None).is_err());
+    assert!(chachapoly1305::decrypt(&key, &a, &nonce, b"ad", None).is_err());
+
+    // bytes cannot be moved between the context and the AAD
+    assert!(chachapoly1305::decrypt_with_context(&key, &a, &nonce, b"1ad", "app/v", None).is_err());
+
+    let framed =
+        chachapoly1305::encrypt_framed_aad(&key, b"message", &nonce, &[b"app/v1", b"ad"], None);
+    assert_eq!(a, framed);
+}
+
+#[test]
+fn test_single_pass_parity() {
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = [0x5cu8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+
+    for len in [0, 15, 16, 4095, 4096, 4097, 8192 + 100, 20000] {
+        let plaintext: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+
+        // the two-pass construction: encrypt everything, then authenticate it
+        let ciphertext = chacha::encrypt(&key, &plaintext, &nonce, None);
+        let mut poly1305 =
+            chachapoly1305::Poly1305::new(&chacha::keystream(&key, &nonce, 0, None)[..32]);
+        poly1305.update(b"ad");
+        poly1305.update(&ciphertext);
+        poly1305.update(b"trailer");
+        poly1305.update(&[9u64.to_le_bytes(), (len as u64).to_le_bytes()].concat());
+        let expected = [ciphertext, poly1305.tag()].concat();
+
+        assert_eq!(
+            cipher.encrypt_with_trailer(&plaintext, &nonce, b"ad", b"trailer"),
+            expected
+        );
+
+        let mut out = Vec::new();
+        cipher.encrypt_into(&plaintext, &nonce, b"adtrailer", &mut out);
+        assert_eq!(
+            cipher.decrypt(&out, &nonce, b"adtrailer").unwrap(),
+            plaintext
+        );
+    }
+}
+
+#[test]
+fn test_single_pass_decrypt_parity() {
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = [0x5du8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+
+    for len in [0, 15, 16, 4095, 4096, 4097, 8192 + 100, 20000] {
+        let plaintext: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        let ciphertext = cipher.encrypt_with_trailer(&plaintext, &nonce, b"ad", b"trailer");
+
+        // the two-pass construction: verify the tag first, then decrypt
+        let (body, tag) = chachapoly1305::split_tag(&ciphertext).unwrap();
+        let mut poly1305 =
+            chachapoly1305::Poly1305::new(&chacha::keystream(&key, &nonce, 0, None)[..32]);
+        poly1305.update(b"ad");
+        poly1305.update(body);
+        poly1305.update(b"trailer");
+        poly1305.update(&[9u64.to_le_bytes(), (len as u64).to_le_bytes()].concat());
+        assert!(poly1305.verify(tag));
+        assert_eq!(chacha::encrypt(&key, body, &nonce, None), plaintext);
+
+        assert_eq!(
+            cipher
+                .decrypt_with_trailer(&ciphertext, &nonce, b"ad", b"trailer")
+                .unwrap(),
+            plaintext
+        );
+
+        // a tampered message yields no plaintext, even though it was decrypted alongside
+        // the MAC
+        let mut tampered = ciphertext.clone();
+        tampered[len / 2] ^= 1;
+
+        let mut out = vec![0xffu8; 10];
+        assert!(cipher
+            .decrypt_into(&tampered, &nonce, b"adtrailer", &mut out)
+            .is_err());
+        assert!(out.is_empty());
+        assert!(cipher
+            .decrypt_with_trailer(&tampered, &nonce, b"ad", b"trailer")
+            .is_err());
+    }
+}
+
+#[test]
+fn test_decrypt_aad_order() {
+    use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+
+    let key: Vec<u8> = (0x40..0x60).collect();
+    let nonce = [0x07u8; 24];
+    let aad = [0xa5u8; 20];
+    let plaintext: Vec<u8> = (0..200).map(|i| i as u8).collect();
+
+    // a ciphertext from an independent implementation, so the MAC order of our
+    // encryption cannot mask a mismatch in decryption
+    let reference = chacha20poly1305::XChaCha20Poly1305::new(key.as_slice().into());
+    let payload = Payload {
+        msg: &plaintext,
+        aad: &aad,
+    };
+    let ciphertext = reference.encrypt(nonce.as_slice().into(), payload).unwrap();
+
+    assert_eq!(
+        xchachapoly1305::decrypt(&key, &ciphertext, &nonce, &aad, None).unwrap(),
+        plaintext
+    );
+    assert_eq!(
+        xchachapoly1305::encrypt(&key, &plaintext, &nonce, &aad, None),
+        ciphertext
+    );
+
+    let mut tampered = aad.to_vec();
+    tampered[0] ^= 1;
+    assert!(xchachapoly1305::decrypt(&key, &ciphertext, &nonce, &tampered, None).is_err());
+}
+
+#[test]
+fn test_zeroize_on_drop() {
+    use std::mem::{size_of, MaybeUninit};
+
+    let key: Vec<u8> = (0xa0..0xc0).collect();
+    let mut slot = MaybeUninit::new(xchachapoly1305::XChaChaPoly1305::new(&key, None));
+    let bytes = slot.as_mut_ptr() as *const u8;
+    let contents = || unsafe {
+        std::slice::from_raw_parts(bytes, size_of::<xchachapoly1305::XChaChaPoly1305>()).to_vec()
+    };
+
+    assert!(contents().windows(32).any(|window| window == key));
+    unsafe { slot.assume_init_drop() };
+    assert!(!contents()
+        .windows(16)
+        .any(|window| window == &key[..16] || window == &key[16..]));
+}
+
+#[test]
+fn test_incremental() {
+    let key: Vec<u8> = (0x10..0x30).collect();
+    let nonce = [0x3au8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+
+    let mut seed = 0x2545f491u32;
+    let mut next = |bound: usize| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 8) as usize % bound
+    };
+
+    for len in [0, 1, 15, 16, 63, 64, 65, 1000, 5000] {
+        let plaintext: Vec<u8> = (0..len).map(|i| (i * 7 % 256) as u8).collect();
+        let expected = cipher.encrypt(&plaintext, &nonce, b"header");
+
+        for _ in 0..8 {
+            // random chunk sizes, including empty chunks and chunks spanning blocks
+            let mut bounds = vec![0];
+            while *bounds.last().unwrap() < len {
+                let step = next(150);
+                bounds.push((bounds.last().unwrap() + step).min(len));
+            }
+
+            let mut encryptor = cipher.encryptor(&nonce, b"header");
+            let mut ciphertext = Vec::new();
+            for pair in bounds.windows(2) {
+                ciphertext.extend(encryptor.update(&plaintext[pair[0]..pair[1]]));
+            }
+            ciphertext.extend(encryptor.finalize());
+            assert_eq!(ciphertext, expected);
+
+            let (body, tag) = chachapoly1305::split_tag(&expected).unwrap();
+            let mut decryptor = cipher.decryptor(&nonce, b"header");
+            let mut decrypted = Vec::new();
+            for pair in bounds.windows(2) {
+                decrypted.extend(decryptor.update(&body[pair[0]..pair[1]]));
+            }
+            assert_eq!(decryptor.finalize(tag), Ok(()));
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+}
+
+#[test]
+fn test_xchacha_incremental() {
+    let key = [0x31u8; 32];
+    let nonce: Vec<u8> = (0..24).collect();
+    let plaintext: Vec<u8> = (0..3000).map(|i| (i % 253) as u8).collect();
+    let cipher = xchachapoly1305::XChaChaPoly1305::new(&key, None);
+    let expected = cipher.encrypt(&plaintext, &nonce, b"header");
+
+    let mut encryptor = cipher.encryptor(&nonce, b"header");
+    drop(cipher);
+    let mut ciphertext = Vec::new();
+    for chunk in plaintext.chunks(1000) {
+        ciphertext.extend(encryptor.update(chunk));
+    }
+    ciphertext.extend(encryptor.finalize());
+    assert_eq!(ciphertext, expected);
+
+    let cipher = xchachapoly1305::XChaChaPoly1305::new(&key, None);
+    let (body, tag) = chachapoly1305::split_tag(&expected).unwrap();
+    let mut decryptor = cipher.decryptor(&nonce, b"header");
+    let mut decrypted = Vec::new();
+    for chunk in body.chunks(333) {
+        decrypted.extend(decryptor.update(chunk));
+    }
+    assert_eq!(decryptor.finalize(tag), Ok(()));
+    assert_eq!(decrypted, plaintext);
+
+    let mut decryptor = cipher.decryptor(&nonce, b"other");
+    decryptor.update(body);
+    assert!(decryptor.finalize(tag).is_err());
+
+    // an owned ChaCha-Poly1305 stream matches the borrowed one
+    let chacha = chachapoly1305::ChaChaPoly1305::new(&key, None);
+    let expected = chacha.encrypt(&plaintext, &nonce[..12], b"");
+    let mut encryptor = chacha.into_encryptor(&nonce[..12], b"");
+    let mut ciphertext = encryptor.update(&plaintext);
+    ciphertext.extend(encryptor.finalize());
+    assert_eq!(ciphertext, expected);
+}
+
+#[test]
+fn test_vectored() {
+    let key: Vec<u8> = (0x40..0x60).collect();
+    let nonce = [0x1cu8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+    let plaintext: Vec<u8> = (0..700).map(|i| (i * 13 % 256) as u8).collect();
+    let expected = cipher.encrypt(&plaintext, &nonce, b"header");
+
+    // empty buffers, buffers inside one block and buffers spanning several
+    for bounds in [
+        vec![0, 700],
+        vec![0, 0, 1, 64, 64, 65, 700],
+        vec![0, 15, 16, 17, 200, 333, 699, 700],
+    ] {
+        let buffers: Vec<&[u8]> = bounds
+            .windows(2)
+            .map(|pair| &plaintext[pair[0]..pair[1]])
+            .collect();
+        let ciphertext = cipher.encrypt_vectored(&buffers, &nonce, b"header");
+        assert_eq!(ciphertext, expected);
+
+        let buffers: Vec<&[u8]> = bounds
+            .windows(2)
+            .map(|pair| &expected[pair[0]..pair[1]])
+            .chain([&expected[700..]])
+            .collect();
+        assert_eq!(
+            cipher.decrypt_vectored(&buffers, &nonce, b"header"),
+            Ok(plaintext.clone())
+        );
+    }
+
+    // the tag split over the last buffers, which also hold ciphertext
+    for split in [690, 700, 705, 715, 716] {
+        let (first, second) = expected.split_at(split);
+        let buffers = [
+            first,
+            &second[..second.len() / 2],
+            &second[second.len() / 2..],
+        ];
+        assert_eq!(
+            cipher.decrypt_vectored(&buffers, &nonce, b"header"),
+            Ok(plaintext.clone()),
+            "split at {split}"
+        );
+    }
+
+    let slices = [
+        IoSlice::new(&plaintext[..100]),
+        IoSlice::new(&plaintext[100..]),
+    ];
+    assert_eq!(
+        cipher.encrypt_vectored(&slices, &nonce, b"header"),
+        expected
+    );
+    assert_eq!(
+        cipher.encrypt_vectored::<&[u8]>(&[], &nonce, b"header"),
+        cipher.encrypt(b"", &nonce, b"header")
+    );
+
+    let xchacha = xchachapoly1305::XChaChaPoly1305::new(&key, None);
+    let xnonce = [0x1cu8; 24];
+    let expected = xchacha.encrypt(&plaintext, &xnonce, b"");
+    let ciphertext = xchacha.encrypt_vectored(&[&plaintext[..3], &plaintext[3..]], &xnonce, b"");
+    assert_eq!(ciphertext, expected);
+    assert_eq!(
+        xchacha.decrypt_vectored(&[&expected[..500], &expected[500..]], &xnonce, b""),
+        Ok(plaintext)
+    );
+}
+
+#[test]
+fn test_vectored_tamper() {
+    let key = [0x5cu8; 32];
+    let nonce = [0x6du8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+    let sealed = cipher.encrypt(&[9u8; 80], &nonce, b"ad");
+
+    for i in [0, 79, 80, 95] {
+        let mut tampered = sealed.clone();
+        tampered[i] ^= 1;
+        let (first, second) = tampered.split_at(40);
+        assert_eq!(
+            cipher.decrypt_vectored(&[first, second], &nonce, b"ad"),
+            Err(TagMismatch { length_valid: true })
+        );
+    }
+
+    assert!(cipher
+        .decrypt_vectored(&[&sealed[..40], &sealed[40..]], &nonce, b"other")
+        .is_err());
+    assert_eq!(
+        cipher.decrypt_vectored(&[&sealed[..10], &sealed[10..15]], &nonce, b"ad"),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+}
+
+#[test]
+fn test_incremental_tamper() {
+    let key = [0x77u8; 32];
+    let nonce = [0x88u8; 12];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+    let sealed = cipher.encrypt(&[5u8; 100], &nonce, b"");
+    let (body, tag) = chachapoly1305::split_tag(&sealed).unwrap();
+
+    let mut tampered = body.to_vec();
+    tampered[99] ^= 1;
+    let mut decryptor = cipher.decryptor(&nonce, b"");
+    decryptor.update(&tampered[..30]);
+    decryptor.update(&tampered[30..]);
+    assert_eq!(
+        decryptor.finalize(tag),
+        Err(TagMismatch { length_valid: true })
+    );
+
+    let mut decryptor = cipher.decryptor(&nonce, b"other");
+    decryptor.update(body);
+    assert!(decryptor.finalize(tag).is_err());
+
+    let mut decryptor = cipher.decryptor(&nonce, b"");
+    decryptor.update(body);
+    assert_eq!(
+        decryptor.finalize(&tag[..15]),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+}
+
+#[test]
+fn test_rekey() {
+    let key = [0x9au8; 32];
+    let nonce = [0x1bu8; 12];
+
+    let first = chachapoly1305::ChaChaPoly1305::new(&key, None);
+    let old = first.encrypt(b"message", &nonce, b"");
+
+    let second = first.rekey();
+    let new = second.encrypt(b"message", &nonce, b"");
+    let third = second.rekey();
+    let newer = third.encrypt(b"message", &nonce, b"");
+
+    assert_ne!(old, new);
+    assert_ne!(new, newer);
+    assert_ne!(old, newer);
+
+    // the old key is gone, so its messages no longer decrypt
+    assert!(third.decrypt(&old, &nonce, b"").is_err());
+    assert_eq!(third.decrypt(&newer, &nonce, b"").unwrap(), b"message");
+
+    // the ratchet is HChaCha under a fixed context
+    let next = chacha::hchacha(&key, &chachapoly1305::REKEY_CONTEXT, None).unwrap();
+    assert_eq!(
+        chachapoly1305::encrypt(&next, b"message", &nonce, b"", None),
+        new
+    );
+
+    let reduced = chachapoly1305::ChaChaPoly1305::new(&key, Some(12)).rekey();
+    assert_eq!(reduced.config().rounds, 12);
+}
+
+#[test]
+fn test_decrypt_bounded() {
+    let key = [0x17u8; 32];
+    let nonce = [0x29u8; 24];
+    let plaintext = [0x3bu8; 100];
+
+    let ciphertext = chachapoly1305::encrypt(&key, &plaintext, &nonce[..12], b"ad", None);
+    assert_eq!(
+        chachapoly1305::decrypt_bounded(&key, &ciphertext, &nonce[..12], b"ad", 100, None).unwrap(),
+        plaintext
+    );
+    assert_eq!(
+        chachapoly1305::decrypt_bounded(&key, &ciphertext, &nonce[..12], b"ad", 99, None),
+        Err(DecryptError::TooLong { max_len: 99 })
+    );
+    assert_eq!(
+        chachapoly1305::decrypt_bounded(&key, &ciphertext, &nonce[..12], b"other", 100, None),
+        Err(DecryptError::TagMismatch(TagMismatch {
+            length_valid: true
+        }))
+    );
+
+    let ciphertext = xchachapoly1305::encrypt(&key, &plaintext, &nonce, b"ad", None);
+    assert_eq!(
+        xchachapoly1305::decrypt_bounded(&key, &ciphertext, &nonce, b"ad", 100, None).unwrap(),
+        plaintext
+    );
+    assert_eq!(
+        xchachapoly1305::decrypt_bounded(&key, &ciphertext, &nonce, b"ad", 99, None),
+        Err(DecryptError::TooLong { max_len: 99 })
+    );
+
+    // an oversized message is rejected without allocating a plaintext buffer
+    let oversized = vec![0u8; 1 << 20];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+    let xcipher = xchachapoly1305::XChaChaPoly1305::new(&key, None);
+
+    let before = allocated();
+    let result = cipher.decrypt_bounded(&oversized, &nonce[..12], b"", 1024);
+    let xresult = xcipher.decrypt_bounded(&oversized, &nonce, b"", 1024);
+    assert_eq!(allocated(), before);
+
+    // while a decryption within the limit does allocate
+    assert!(cipher.decrypt_bounded(&oversized, &nonce[..12], b"", 1 << 20).is_err());
+    assert!(allocated() > before);
+
+    assert_eq!(result, Err(DecryptError::TooLong { max_len: 1024 }));
+    assert_eq!(xresult, Err(DecryptError::TooLong { max_len: 1024 }));
+}
+
+#[test]
+fn test_rounds_override() {
+    let key = [0x4eu8; 32];
+    let nonce = [0x2cu8; 24];
+    let plaintext = [0x61u8; 300];
+
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+    let xcipher = xchachapoly1305::XChaChaPoly1305::new(&key, None);
+
+    for rounds in [8, 12, 20] {
+        let ciphertext = cipher.encrypt_with_rounds(&plaintext, &nonce[..12], b"ad", Some(rounds));
+        let reduced = chachapoly1305::ChaChaPoly1305::new(&key, Some(rounds));
+        assert_eq!(ciphertext, reduced.encrypt(&plaintext, &nonce[..12], b"ad"));
+        assert_eq!(
+            cipher
+                .decrypt_with_rounds(&ciphertext, &nonce[..12], b"ad", Some(rounds))
+                .unwrap(),
+            plaintext
+        );
+
+        let ciphertext = xcipher.encrypt_with_rounds(&plaintext, &nonce, b"ad", Some(rounds));
+        let reduced = xchachapoly1305::XChaChaPoly1305::new(&key, Some(rounds));
+        assert_eq!(ciphertext, reduced.encrypt(&plaintext, &nonce, b"ad"));
+        assert_eq!(
+            xcipher
+                .decrypt_with_rounds(&ciphertext, &nonce, b"ad", Some(rounds))
+                .unwrap(),
+            plaintext
+        );
+    }
+
+    // the override only lasts for one call, and without one the cipher's rounds are used
+    let ciphertext = cipher.encrypt_with_rounds(&plaintext, &nonce[..12], b"ad", Some(8));
+    assert!(cipher.decrypt(&ciphertext, &nonce[..12], b"ad").is_err());
+    assert_eq!(
+        cipher.encrypt_with_rounds(&plaintext, &nonce[..12], b"ad", None),
+        cipher.encrypt(&plaintext, &nonce[..12], b"ad")
+    );
+    assert_eq!(cipher.config().rounds, 20);
+}
+
+#[test]
+#[should_panic(expected = "at least 8")]
+fn test_rounds_override_too_few() {
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&[0u8; 32], None);
+    cipher.encrypt_with_rounds(b"message", &[0u8; 12], b"", Some(1));
+}
+
+#[test]
+#[should_panic(expected = "even number of rounds")]
+fn test_odd_rounds() {
+    chachapoly1305::ChaChaPoly1305::new(&[0u8; 32], Some(9));
+}
+
+#[test]
+#[should_panic(expected = "even number of rounds")]
+fn test_xchacha_odd_rounds() {
+    xchachapoly1305::XChaChaPoly1305::new(&[0u8; 32], Some(11));
+}
+
+#[test]
+fn test_lengths() {
+    assert_eq!(chachapoly1305::KEY_LENGTH, 32);
+    assert_eq!(chachapoly1305::NONCE_LENGTH, 12);
+    assert_eq!(chachapoly1305::TAG_LENGTH, 16);
+    assert_eq!(xchachapoly1305::KEY_LENGTH, 32);
+    assert_eq!(xchachapoly1305::NONCE_LENGTH, 24);
+    assert_eq!(xchachapoly1305::TAG_LENGTH, 16);
+
+    let key = [0x42u8; chachapoly1305::KEY_LENGTH];
+    let nonce = [0u8; chachapoly1305::NONCE_LENGTH];
+    let ciphertext = chachapoly1305::encrypt(&key, b"message", &nonce, b"", None);
+    assert_eq!(ciphertext.len(), 7 + chachapoly1305::TAG_LENGTH);
+
+    let config = xchachapoly1305::XChaChaPoly1305::new(&key, None).config();
+    assert_eq!(config.nonce_len, xchachapoly1305::NONCE_LENGTH);
+    assert_eq!(config.tag_len, xchachapoly1305::TAG_LENGTH);
+}
+
+#[test]
+fn test_length_encoding() {
+    use chachapoly1305::{ChaChaPoly1305, LengthEncoding, Poly1305};
+
+    let key = [0x61u8; 32];
+    let nonce = [0x62u8; 12];
+    let ad = b"seven b";
+    let plaintext = b"a message of twenty-six by";
+    let encodings = [
+        LengthEncoding::LittleEndian,
+        LengthEncoding::BigEndian,
+        LengthEncoding::SwappedOrder,
+    ];
+
+    assert_eq!(LengthEncoding::default(), LengthEncoding::LittleEndian);
+    assert_eq!(
+        ChaChaPoly1305::new(&key, None)
+            .with_length_encoding(LengthEncoding::LittleEndian)
+            .encrypt(plaintext, &nonce, ad),
+        chachapoly1305::encrypt(&key, plaintext, &nonce, ad, None)
+    );
+
+    // the tag over the same ciphertext, with the length block built by hand
+    let ciphertext = chacha::ChaCha::new(&key, None).encrypt(plaintext, &nonce);
+    let poly1305_key = chacha::keystream(&key, &nonce, 0, None);
+    let expected_tag = |lengths: [[u8; 8]; 2]| {
+        let mut poly1305 = Poly1305::new(&poly1305_key[..32]);
+        poly1305.update(ad);
+        poly1305.update(&ciphertext);
+        poly1305.update(&lengths.concat());
+        poly1305.tag()
+    };
+    let (ad_len, ciphertext_len) = (ad.len() as u64, plaintext.len() as u64);
+    let lengths = [
+        [ad_len.to_le_bytes(), ciphertext_len.to_le_bytes()],
+        [ad_len.to_be_bytes(), ciphertext_len.to_be_bytes()],
+        [ciphertext_len.to_le_bytes(), ad_len.to_le_bytes()],
+    ];
+
+    let mut outputs = HashSet::new();
+    for (encoding, lengths) in encodings.into_iter().zip(lengths) {
+        let cipher = ChaChaPoly1305::new(&key, None).with_length_encoding(encoding);
+
+        let output = cipher.encrypt(plaintext, &nonce, ad);
+        assert_eq!(output, [ciphertext.clone(), expected_tag(lengths)].concat());
+        assert_eq!(cipher.decrypt(&output, &nonce, ad).unwrap(), plaintext);
+        outputs.insert(output.clone());
+
+        // every other path builds the same length block
+        let mut encryptor = cipher.encryptor(&nonce, ad);
+        let mut streamed = encryptor.update(&plaintext[..10]);
+        streamed.extend(encryptor.update(&plaintext[10..]));
+        streamed.extend(encryptor.finalize());
+        assert_eq!(streamed, output);
+        assert_eq!(
+            cipher
+                .decrypt_with_aad_chunks(&output, &nonce, ad.chunks(3))
+                .unwrap(),
+            plaintext
+        );
+        assert_eq!(
+            cipher
+                .rekey()
+                .decrypt(
+                    &ChaChaPoly1305::new(&key, None)
+                        .rekey()
+                        .with_length_encoding(encoding)
+                        .encrypt(plaintext, &nonce, ad),
+                    &nonce,
+                    ad
+                )
+                .unwrap(),
+            plaintext
+        );
+
+        for other in encodings {
+            if other != encoding {
+                let other = ChaChaPoly1305::new(&key, None).with_length_encoding(other);
+                assert_eq!(
+                    other.decrypt(&output, &nonce, ad),
+                    Err(TagMismatch { length_valid: true })
+                );
+            }
+        }
+    }
+    assert_eq!(outputs.len(), encodings.len());
+}
+
+#[test]
+fn test_xchacha_nonce_length() {
+    let key = [0x71u8; 32];
+    let nonce = [0x72u8; 25];
+    let cipher = xchachapoly1305::XChaChaPoly1305::new(&key, None);
+
+    let ciphertext = cipher
+        .try_encrypt(b"plaintext", &nonce[..24], b"ad")
+        .unwrap();
+    assert_eq!(
+        ciphertext,
+        cipher.encrypt(b"plaintext", &nonce[..24], b"ad")
+    );
+    assert_eq!(
+        cipher
+            .try_decrypt(&ciphertext, &nonce[..24], b"ad")
+            .unwrap(),
+        b"plaintext"
+    );
+    assert_eq!(
+        xchachapoly1305::try_decrypt(&key, &ciphertext, &nonce[..24], b"", None),
+        Err(DecryptError::TagMismatch(TagMismatch {
+            length_valid: true
+        }))
+    );
+
+    // a ChaCha-Poly1305 nonce passed by mistake, one byte short and one byte long
+    for len in [12, 23, 25] {
+        assert!(cipher
+            .try_encrypt(b"plaintext", &nonce[..len], b"ad")
+            .is_err());
+        assert!(xchachapoly1305::try_encrypt(&key, b"", &nonce[..len], b"", None).is_err());
+        assert_eq!(
+            cipher.try_decrypt(&ciphertext, &nonce[..len], b"ad"),
+            Err(DecryptError::InvalidNonce)
+        );
+    }
+}
+
+#[test]
+fn test_xchacha_random_nonce() {
+    let key = [0x73u8; 32];
+    let cipher = xchachapoly1305::XChaChaPoly1305::new(&key, None);
+
+    let a = cipher.encrypt_with_random_nonce(b"plaintext", b"ad");
+    let b = cipher.encrypt_with_random_nonce(b"plaintext", b"ad");
+    assert_eq!(
+        a.len(),
+        xchachapoly1305::NONCE_LENGTH + 9 + xchachapoly1305::TAG_LENGTH
+    );
+    assert_ne!(a[..24], b[..24]);
+
+    // the nonce is prepended to the usual ciphertext
+    let (nonce, ciphertext) = a.split_at(xchachapoly1305::NONCE_LENGTH);
+    assert_eq!(ciphertext, cipher.encrypt(b"plaintext", nonce, b"ad"));
+
+    assert_eq!(
+        cipher.decrypt_with_prepended_nonce(&a, b"ad").unwrap(),
+        b"plaintext"
+    );
+    assert_eq!(
+        xchachapoly1305::decrypt_with_prepended_nonce(&key, &b, b"ad", None).unwrap(),
+        b"plaintext"
+    );
+    assert_eq!(
+        cipher.decrypt_with_prepended_nonce(&a, b"other ad"),
+        Err(TagMismatch { length_valid: true })
+    );
+    assert_eq!(
+        cipher.decrypt_with_prepended_nonce(&a[..39], b"ad"),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "nonces are 24 bytes")]
+fn test_xchacha_short_nonce_panics() {
+    xchachapoly1305::encrypt(&[0u8; 32], b"plaintext", &[0u8; 12], b"", None);
+}
+
+#[test]
+fn test_max_plaintext_length() {
+    // the last block of the longest message uses counter u32::MAX, one byte more would
+    // need counter 0 again
+    let max = chachapoly1305::MAX_PLAINTEXT_LENGTH;
+    assert_eq!(max, (1 << 38) - 64);
+    assert_eq!(chacha::last_block_counter(1, max), Ok(u32::MAX));
+    assert!(chacha::last_block_counter(1, max + 1).is_err());
+
+    let key = [0x73u8; 32];
+    let nonce = [0x74u8; 24];
+    let cipher = chachapoly1305::ChaChaPoly1305::new(&key, None);
+    assert_eq!(
+        cipher.try_encrypt(b"plaintext", &nonce[..12], b"ad"),
+        Ok(cipher.encrypt(b"plaintext", &nonce[..12], b"ad"))
+    );
+    assert!(chachapoly1305::try_encrypt(&key, b"plaintext", &nonce, b"ad", None).is_err());
+    assert!(cipher
+        .try_encrypt(b"plaintext", &nonce[..11], b"ad")
+        .is_err());
+}
+
+#[test]
+fn test_with_backend() {
+    let key = [0x74u8; 32];
+    let nonce = [0x75u8; 12];
+    let plaintext: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    let expected = chachapoly1305::encrypt(&key, &plaintext, &nonce, b"ad", None);
+
+    for backend in [
+        chacha::Backend::Avx512,
+        chacha::Backend::Avx2,
+        chacha::Backend::Sse2,
+        chacha::Backend::Neon,
+        chacha::Backend::Fallback,
+    ] {
+        let Some(cipher) = chachapoly1305::ChaChaPoly1305::with_backend(&key, None, backend) else {
+            assert!(!backend.is_supported());
+            continue;
+        };
+
+        assert_eq!(
+            cipher.encrypt(&plaintext, &nonce, b"ad"),
+            expected,
+            "{}",
+            backend.name()
+        );
+        assert_eq!(cipher.decrypt(&expected, &nonce, b"ad").unwrap(), plaintext);
+    }
+}
@@ -0,0 +1,41 @@
+use raycrypt::aeads::convergent::{decrypt, derive_key, encrypt};
+
+const SECRET: [u8; 32] = [0x3c; 32];
+
+#[test]
+fn test_identical_plaintexts() {
+    let (key, ciphertext) = encrypt(&SECRET, b"shared file", b"", None);
+    let (again_key, again) = encrypt(&SECRET, b"shared file", b"", None);
+
+    assert_eq!(key, again_key);
+    assert_eq!(ciphertext, again);
+    assert_eq!(key, derive_key(&SECRET, b"shared file", b""));
+    assert_eq!(
+        decrypt(&key, &ciphertext, b"", None).unwrap(),
+        b"shared file"
+    );
+}
+
+#[test]
+fn test_distinct_plaintexts() {
+    let (key, ciphertext) = encrypt(&SECRET, b"shared file", b"", None);
+
+    let (other_key, other) = encrypt(&SECRET, b"shared filf", b"", None);
+    assert_ne!(key, other_key);
+    assert_ne!(ciphertext, other);
+
+    // the AAD and the secret are part of the derivation
+    let (ad_key, _) = encrypt(&SECRET, b"shared file", b"ad", None);
+    assert_ne!(key, ad_key);
+    let (secret_key, _) = encrypt(&[0x3d; 32], b"shared file", b"", None);
+    assert_ne!(key, secret_key);
+
+    // the same bytes cannot be moved between the AAD and the plaintext
+    assert_ne!(
+        derive_key(&SECRET, b"bc", b"a"),
+        derive_key(&SECRET, b"c", b"ab")
+    );
+
+    assert!(decrypt(&other_key, &ciphertext, b"", None).is_err());
+    assert!(decrypt(&key, &ciphertext, b"ad", None).is_err());
+}
@@ -0,0 +1,129 @@
+use hex::decode;
+use raycrypt::aeads::cryptobox::*;
+use raycrypt::ecc::x25519;
+
+fn keys() -> ([u8; 32], [u8; 32]) {
+    let sender: Vec<u8> = (0..32).collect();
+    let recipient: Vec<u8> = (32..64).collect();
+
+    (sender.try_into().unwrap(), recipient.try_into().unwrap())
+}
+
+#[test]
+fn test_vectors() {
+    // generated with crypto_box_curve25519xchacha20poly1305_easy from libsodium 1.0.18
+    let (sender, recipient) = keys();
+    let sender_public = x25519::public_key(&sender);
+    let recipient_public = x25519::public_key(&recipient);
+    assert_eq!(
+        recipient_public.to_vec(),
+        decode("358072d6365880d1aeea329adf9121383851ed21a28e3b75e965d0d2cd166254").unwrap()
+    );
+
+    let nonce: Vec<u8> = (0..24).collect();
+    let vectors = [
+        (0, "df0764af542c9a71bbea804ed71ec823"),
+        (5, "166710f32fc3a1c9a8803bed75d890ca08cbeabd73"),
+        (
+            100,
+            "6efdd85f3da768343772caa49a95d25808cbeabd73dba8dab8f0f77a8e34df90\
+             559f96fd6759e6e1bb57641db0f5295a0c44280f7abbcfec976cf91a0cc68954\
+             f5712a020b2043b1308fdc6c4acb441cd2b35eb32b35d27c272e3dd22b3fbf8a\
+             3ea52e79063e12c9ccd228a8189419940cbcb1da",
+        ),
+    ];
+
+    let sending = CryptoBox::new(&sender, &recipient_public).unwrap();
+    let receiving = CryptoBox::new(&recipient, &sender_public).unwrap();
+    for (len, boxed) in vectors {
+        let msg: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+        let boxed = decode(boxed).unwrap();
+
+        assert_eq!(sending.encrypt(&msg, &nonce), boxed);
+        assert_eq!(receiving.decrypt(&boxed, &nonce).unwrap(), msg);
+
+        let sealed = [nonce.as_slice(), &boxed].concat();
+        assert_eq!(
+            CryptoBox::open(&recipient, &sender_public, &sealed).unwrap(),
+            msg
+        );
+
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert_eq!(
+                CryptoBox::open(&recipient, &sender_public, &tampered),
+                Err(InvalidMac)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_seal() {
+    let (sender, recipient) = keys();
+    let sender_public = x25519::public_key(&sender);
+    let recipient_public = x25519::public_key(&recipient);
+
+    let sealed = CryptoBox::seal(&sender, &recipient_public, b"message").unwrap();
+    assert_eq!(sealed.len(), NONCE_LENGTH + TAG_LENGTH + 7);
+    assert_ne!(
+        sealed,
+        CryptoBox::seal(&sender, &recipient_public, b"message").unwrap()
+    );
+    assert_eq!(
+        CryptoBox::open(&recipient, &sender_public, &sealed).unwrap(),
+        b"message"
+    );
+    // the sender can open what it sealed, but nobody without either secret key can
+    assert_eq!(
+        CryptoBox::open(&sender, &recipient_public, &sealed).unwrap(),
+        b"message"
+    );
+    let (other, _) = x25519::generate_keypair();
+    assert!(CryptoBox::open(&other, &sender_public, &sealed).is_err());
+
+    assert!(CryptoBox::open(&recipient, &sender_public, &sealed[..NONCE_LENGTH + 15]).is_err());
+    assert!(CryptoBox::open(&recipient, &sender_public, &[]).is_err());
+
+    // small-order points give an all-zero shared secret
+    let zero = [0u8; 32];
+    assert!(CryptoBox::new(&sender, &zero).is_none());
+    assert!(CryptoBox::seal(&sender, &zero, b"message").is_none());
+    assert!(CryptoBox::open(&recipient, &zero, &sealed).is_err());
+    assert!(seal_anonymous(&zero, b"message").is_none());
+}
+
+#[test]
+fn test_anonymous() {
+    let (_, recipient) = keys();
+    let recipient_public = x25519::public_key(&recipient);
+
+    // generated with crypto_box_curve25519xchacha20poly1305_seal from libsodium 1.0.18
+    let sealed = decode(
+        "e24abfb3dddb05c3d252f55ee172c6892d06f5191d72d31444d2d03c19df1f30\
+         5be37d9e1c186b3b05c8bda9bf355eb8a0ca3251b70c4e08dc041b918a1fbfdb69",
+    )
+    .unwrap();
+    assert_eq!(
+        open_anonymous(&recipient, &sealed).unwrap(),
+        b"anonymous message"
+    );
+
+    for len in [0, 1, 100] {
+        let msg = vec![0x42u8; len];
+        let sealed = seal_anonymous(&recipient_public, &msg).unwrap();
+        assert_eq!(sealed.len(), len + SEAL_OVERHEAD);
+        assert_eq!(open_anonymous(&recipient, &sealed).unwrap(), msg);
+
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert_eq!(open_anonymous(&recipient, &tampered), Err(InvalidMac));
+        }
+    }
+
+    let (sender, _) = keys();
+    assert!(open_anonymous(&sender, &sealed).is_err());
+    assert!(open_anonymous(&recipient, &sealed[..SEAL_OVERHEAD - 1]).is_err());
+}
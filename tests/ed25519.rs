@@ -0,0 +1,55 @@
+use hex::decode;
+use raycrypt::ecc::ed25519::*;
+
+// RFC 8032 §7.1, tests 1 and 2
+const VECTORS: [(&str, &str, &str, &str); 2] = [
+    (
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        "",
+        "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+    ),
+    (
+        "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        "72",
+        "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+    ),
+];
+
+#[test]
+fn test_rfc8032() {
+    for (seed, public, message, signature) in VECTORS {
+        let signer = SigningKey::from_seed(&decode(seed).unwrap()).unwrap();
+        let message = decode(message).unwrap();
+
+        assert_eq!(signer.sign(&message).to_vec(), decode(signature).unwrap());
+
+        let verifier = VerifyingKey::from(&decode(public).unwrap()).unwrap();
+        assert!(verifier.verify(&message, &decode(signature).unwrap()));
+        assert!(!verifier.verify(b"other", &decode(signature).unwrap()));
+    }
+}
+
+#[test]
+fn test_verify_batch() {
+    let signers: Vec<SigningKey> = (0..3).map(|_| SigningKey::generate().unwrap()).collect();
+    let messages: [&[u8]; 3] = [b"first", b"second", b"third"];
+    let signatures: Vec<[u8; 64]> = signers
+        .iter()
+        .zip(messages)
+        .map(|(signer, message)| signer.sign(message))
+        .collect();
+    let signatures: Vec<&[u8]> = signatures.iter().map(|s| s.as_slice()).collect();
+    let keys: Vec<VerifyingKey> = signers.iter().map(|s| s.verifier()).collect();
+
+    assert!(verify_batch(&messages, &signatures, &keys));
+    assert!(verify_batch(&[], &[], &[]));
+
+    // one bad signature fails the batch
+    let mut swapped = signatures.clone();
+    swapped.swap(0, 1);
+    assert!(!verify_batch(&messages, &swapped, &keys));
+
+    assert!(!verify_batch(&messages[..2], &signatures, &keys));
+}
@@ -0,0 +1,147 @@
+use raycrypt::encoding::{
+    decode_base64, decode_base64_lenient, decode_hex, encode_base64, encode_hex, Base64,
+    EncodingError,
+};
+
+const VARIANTS: [Base64; 4] = [
+    Base64::Standard,
+    Base64::StandardUnpadded,
+    Base64::UrlSafe,
+    Base64::UrlSafeUnpadded,
+];
+
+#[test]
+fn test_rfc4648() {
+    let cases = [
+        ("", "", ""),
+        ("f", "Zg==", "66"),
+        ("fo", "Zm8=", "666f"),
+        ("foo", "Zm9v", "666f6f"),
+        ("foob", "Zm9vYg==", "666f6f62"),
+        ("fooba", "Zm9vYmE=", "666f6f6261"),
+        ("foobar", "Zm9vYmFy", "666f6f626172"),
+    ];
+
+    for (data, base64, hex) in cases {
+        assert_eq!(encode_base64(data.as_bytes(), Base64::Standard), base64);
+        assert_eq!(
+            decode_base64(base64, Base64::Standard).unwrap(),
+            data.as_bytes()
+        );
+
+        let unpadded = base64.trim_end_matches('=');
+        assert_eq!(
+            encode_base64(data.as_bytes(), Base64::StandardUnpadded),
+            unpadded
+        );
+        assert_eq!(
+            decode_base64(unpadded, Base64::StandardUnpadded).unwrap(),
+            data.as_bytes()
+        );
+
+        assert_eq!(encode_hex(data.as_bytes()), hex);
+        assert_eq!(decode_hex(hex).unwrap(), data.as_bytes());
+        assert_eq!(decode_hex(&hex.to_uppercase()).unwrap(), data.as_bytes());
+    }
+}
+
+#[test]
+fn test_alphabets() {
+    let data: Vec<u8> = (0..=255).collect();
+
+    let standard = encode_base64(&data, Base64::Standard);
+    let url_safe = encode_base64(&data, Base64::UrlSafe);
+    assert!(standard.contains('+') && standard.contains('/'));
+    assert_eq!(url_safe, standard.replace('+', "-").replace('/', "_"));
+
+    // every character is mapped like a lookup table would
+    let table = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    for c in 0..=255u8 {
+        let encoded = [c, b'A', b'A', b'A'];
+        let encoded = String::from_utf8_lossy(&encoded);
+        let expected = table.iter().position(|&t| t == c);
+        assert_eq!(
+            decode_base64(&encoded, Base64::Standard).ok(),
+            expected.map(|value| vec![(value << 2) as u8, 0, 0]),
+            "{c:#x}"
+        );
+    }
+
+    for c in 0..=255u8 {
+        let encoded = [b'0', c];
+        let encoded = String::from_utf8_lossy(&encoded);
+        let expected = (c as char).to_digit(16).map(|value| vec![value as u8]);
+        assert_eq!(decode_hex(&encoded).ok(), expected, "{c:#x}");
+    }
+}
+
+#[test]
+fn test_roundtrip() {
+    for len in 0..70 {
+        let data: Vec<u8> = (0..len).map(|i| (i * 97 + 13) as u8).collect();
+
+        for variant in VARIANTS {
+            let encoded = encode_base64(&data, variant);
+            assert_eq!(decode_base64(&encoded, variant).unwrap(), data);
+            assert_eq!(decode_base64_lenient(&encoded, variant).unwrap(), data);
+        }
+        assert_eq!(decode_hex(&encode_hex(&data)).unwrap(), data);
+    }
+}
+
+#[test]
+fn test_strict() {
+    use EncodingError::*;
+
+    assert_eq!(decode_hex("abc"), Err(InvalidLength));
+    assert_eq!(decode_hex("ab c"), Err(InvalidCharacter));
+    assert_eq!(decode_hex("ab cd"), Err(InvalidLength));
+    assert_eq!(decode_hex("ab  "), Err(InvalidCharacter));
+    assert_eq!(decode_hex("0g"), Err(InvalidCharacter));
+
+    let cases = [
+        // whitespace
+        ("Zm9v Yg==", Base64::Standard, InvalidLength),
+        ("Zm9vYg==\n", Base64::Standard, InvalidLength),
+        (" Zm9vYmE", Base64::Standard, InvalidCharacter),
+        ("Zm9v\nYg", Base64::StandardUnpadded, InvalidCharacter),
+        // padding where there should be none, or missing
+        ("Zm9vYg", Base64::Standard, InvalidLength),
+        ("Zm9vYg==", Base64::StandardUnpadded, InvalidPadding),
+        ("Zm9v====", Base64::Standard, InvalidPadding),
+        ("Zm9vY===", Base64::Standard, InvalidPadding),
+        ("Zg=v", Base64::Standard, InvalidCharacter),
+        // unused bits set
+        ("Zh==", Base64::Standard, InvalidPadding),
+        ("Zm9=", Base64::Standard, InvalidPadding),
+        ("Zh", Base64::UrlSafeUnpadded, InvalidPadding),
+        // the wrong alphabet
+        ("-_8=", Base64::Standard, InvalidCharacter),
+        ("+/8=", Base64::UrlSafe, InvalidCharacter),
+        // a single character cannot hold a byte
+        ("Zm9vY", Base64::StandardUnpadded, InvalidLength),
+    ];
+
+    for (encoded, variant, error) in cases {
+        assert_eq!(decode_base64(encoded, variant), Err(error), "{encoded:?}");
+    }
+}
+
+#[test]
+fn test_lenient() {
+    let lenient = |encoded| decode_base64_lenient(encoded, Base64::Standard);
+
+    assert_eq!(lenient("Zm9v\nYmFy\n").unwrap(), b"foobar");
+    assert_eq!(lenient(" Zm9v Yg = = ").unwrap(), b"foob");
+    assert_eq!(lenient("Zm9vYg").unwrap(), b"foob");
+    assert_eq!(lenient("Zh==").unwrap(), b"f");
+    assert_eq!(
+        decode_base64_lenient("Zm9vYg==", Base64::UrlSafeUnpadded).unwrap(),
+        b"foob"
+    );
+
+    assert_eq!(lenient("Zm9vYg="), Err(EncodingError::InvalidPadding));
+    assert_eq!(lenient("Zm9v=="), Err(EncodingError::InvalidPadding));
+    assert_eq!(lenient("Zm9v!"), Err(EncodingError::InvalidLength));
+    assert_eq!(lenient("Zm9v!A"), Err(EncodingError::InvalidCharacter));
+}
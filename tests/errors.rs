@@ -0,0 +1,100 @@
+use raycrypt::aeads::registry::{from_name, AlgorithmError};
+use raycrypt::aeads::versioned;
+use raycrypt::aeads::xchachapoly1305::{self, XChaChaPoly1305};
+use raycrypt::encoding;
+use raycrypt::errors::{
+    CryptoError, DecryptError, FormatError, HpkeError, RatchetError, RecordError, TagMismatch,
+};
+use raycrypt::padding;
+
+/// Decrypts with checks that fail with two different error types, both collected by `?`.
+fn decrypt(key: &[u8], ciphertext: &[u8], nonce: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    from_name("xchacha20poly1305", key)?;
+
+    Ok(XChaChaPoly1305::new(key, None).try_decrypt(ciphertext, nonce, b"")?)
+}
+
+#[test]
+fn test_kinds() {
+    let key = [0x42u8; 32];
+    let ciphertext = xchachapoly1305::encrypt(&key, b"message", &[0; 24], b"", None);
+    assert_eq!(decrypt(&key, &ciphertext, &[0; 24]).unwrap(), b"message");
+
+    assert_eq!(
+        decrypt(&key[..16], &ciphertext, &[0; 24]),
+        Err(CryptoError::InvalidKeyLength)
+    );
+    assert_eq!(
+        decrypt(&key, &ciphertext, &[0; 12]),
+        Err(CryptoError::InvalidNonceLength)
+    );
+    assert_eq!(
+        decrypt(&key, &ciphertext, &[1; 24]),
+        Err(CryptoError::InvalidTag)
+    );
+    assert_eq!(
+        decrypt(&key, &ciphertext[..15], &[0; 24]),
+        Err(CryptoError::InvalidTag)
+    );
+}
+
+#[test]
+fn test_conversions() {
+    let cases = [
+        (
+            CryptoError::from(TagMismatch {
+                length_valid: false,
+            }),
+            CryptoError::InvalidTag,
+        ),
+        (FormatError::InvalidMac.into(), CryptoError::InvalidTag),
+        (
+            FormatError::UnknownVersion(9).into(),
+            CryptoError::UnknownAlgorithm,
+        ),
+        (
+            AlgorithmError::UnknownAlgorithm.into(),
+            CryptoError::UnknownAlgorithm,
+        ),
+        (
+            encoding::decode_hex("zz").unwrap_err().into(),
+            CryptoError::InvalidFormat,
+        ),
+        (
+            versioned::decrypt_versioned(&[0; 32], &[], b"")
+                .unwrap_err()
+                .into(),
+            CryptoError::InvalidLength,
+        ),
+        (
+            padding::unpad(b"").unwrap_err().into(),
+            CryptoError::InvalidFormat,
+        ),
+        (
+            DecryptError::InvalidPadding.into(),
+            CryptoError::InvalidFormat,
+        ),
+        (
+            RecordError::InvalidHeader.into(),
+            CryptoError::InvalidFormat,
+        ),
+        (RatchetError::KeyUnavailable.into(), CryptoError::InvalidTag),
+        (
+            RatchetError::TooManySkipped.into(),
+            CryptoError::CounterOverflow,
+        ),
+        (
+            HpkeError::MessageLimitReached.into(),
+            CryptoError::CounterOverflow,
+        ),
+    ];
+
+    for (error, expected) in cases {
+        assert_eq!(error, expected);
+    }
+
+    assert_eq!(
+        CryptoError::InvalidTag.to_string(),
+        TagMismatch { length_valid: true }.to_string()
+    );
+}
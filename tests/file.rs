@@ -0,0 +1,136 @@
+use raycrypt::aeads::file::*;
+use raycrypt::errors::FormatError;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("raycrypt-file-{}-{}", std::process::id(), name))
+}
+
+fn format_error(error: io::Error) -> FormatError {
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    *error
+        .into_inner()
+        .unwrap()
+        .downcast::<FormatError>()
+        .unwrap()
+}
+
+#[test]
+fn test_roundtrip() {
+    let key = [0x42u8; 32];
+    let (input, sealed, opened) = (temp_path("in"), temp_path("sealed"), temp_path("out"));
+
+    // empty, shorter than a chunk, exactly one and two chunks, and a partial last chunk
+    for len in [0usize, 1, 100, 128, 256, 1000] {
+        let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        fs::write(&input, &data).unwrap();
+
+        seal_file_with_chunk_size(&input, &sealed, &key, 128).unwrap();
+        let blob = fs::read(&sealed).unwrap();
+        let chunks = len.div_ceil(128).max(1);
+        assert_eq!(blob.len(), HEADER_LENGTH + len + 16 * chunks);
+        assert_eq!(&blob[..4], &MAGIC);
+        assert_eq!(
+            &blob[HEADER_LENGTH - 4..HEADER_LENGTH],
+            &128u32.to_le_bytes()
+        );
+
+        open_file(&sealed, &opened, &key).unwrap();
+        assert_eq!(fs::read(&opened).unwrap(), data);
+    }
+
+    let data = vec![0x5au8; 3 * DEFAULT_CHUNK_SIZE / 2];
+    fs::write(&input, &data).unwrap();
+    seal_file(&input, &sealed, &key).unwrap();
+    open_file(&sealed, &opened, &key).unwrap();
+    assert_eq!(fs::read(&opened).unwrap(), data);
+
+    // every file gets a fresh salt
+    seal_file(&input, &opened, &key).unwrap();
+    assert_ne!(fs::read(&sealed).unwrap(), fs::read(&opened).unwrap());
+
+    for path in [input, sealed, opened] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_tampering() {
+    let key = [0x24u8; 32];
+    let (input, sealed, opened) = (temp_path("in2"), temp_path("sealed2"), temp_path("out2"));
+
+    let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+    fs::write(&input, &data).unwrap();
+    seal_file_with_chunk_size(&input, &sealed, &key, 100).unwrap();
+    let blob = fs::read(&sealed).unwrap();
+    let frame = 100 + 16;
+
+    let with_byte = |index: usize, byte: u8| {
+        let mut tampered = blob.clone();
+        tampered[index] = byte;
+        tampered
+    };
+
+    let cases = [
+        // altering the salt, the chunk size or a ciphertext byte
+        (with_byte(10, blob[10] ^ 1), FormatError::InvalidMac),
+        (with_byte(HEADER_LENGTH - 4, 99), FormatError::InvalidMac),
+        (
+            with_byte(HEADER_LENGTH + 150, blob[HEADER_LENGTH + 150] ^ 1),
+            FormatError::InvalidMac,
+        ),
+        // dropping the final chunk or swapping two chunks
+        (
+            blob[..HEADER_LENGTH + 2 * frame].to_vec(),
+            FormatError::InvalidMac,
+        ),
+        (
+            [
+                &blob[..HEADER_LENGTH],
+                &blob[HEADER_LENGTH + frame..HEADER_LENGTH + 2 * frame],
+                &blob[HEADER_LENGTH..HEADER_LENGTH + frame],
+                &blob[HEADER_LENGTH + 2 * frame..],
+            ]
+            .concat(),
+            FormatError::InvalidMac,
+        ),
+        (with_byte(0, b'X'), FormatError::InvalidHeader),
+        (with_byte(4, 2), FormatError::UnknownVersion(2)),
+        (with_byte(5, 1), FormatError::InvalidHeader),
+        (
+            [&blob[..HEADER_LENGTH - 4], &[0; 4], &blob[HEADER_LENGTH..]].concat(),
+            FormatError::InvalidHeader,
+        ),
+        (
+            blob[..HEADER_LENGTH - 1].to_vec(),
+            FormatError::InvalidLength,
+        ),
+        (
+            blob[..HEADER_LENGTH + 15].to_vec(),
+            FormatError::InvalidLength,
+        ),
+    ];
+
+    for (tampered, expected) in cases {
+        fs::write(&sealed, &tampered).unwrap();
+        let error = open_file(&sealed, &opened, &key).unwrap_err();
+        assert_eq!(format_error(error), expected);
+        assert!(!opened.exists());
+    }
+
+    fs::write(&sealed, &blob).unwrap();
+    let error = open_file(&sealed, &opened, &[0x25u8; 32]).unwrap_err();
+    assert_eq!(format_error(error), FormatError::InvalidMac);
+    assert!(!opened.exists());
+
+    let missing = temp_path("missing");
+    assert_eq!(
+        open_file(&missing, &opened, &key).unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+
+    fs::remove_file(input).unwrap();
+    fs::remove_file(sealed).unwrap();
+}
@@ -0,0 +1,107 @@
+use hex::decode;
+use raycrypt::kdfs::{self, hkdf};
+
+#[test]
+fn test_rfc5869() {
+    // test case 1
+    let ikm = [0x0bu8; 22];
+    let salt: Vec<u8> = (0..13).collect();
+    let info: Vec<u8> = (0xf0..0xfa).collect();
+
+    let prk = hkdf::extract::<hkdf::Sha256>(&salt, &ikm);
+    assert_eq!(
+        prk,
+        decode("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5").unwrap()
+    );
+
+    let okm = decode(concat!(
+        "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf",
+        "34007208d5b887185865"
+    ))
+    .unwrap();
+    assert_eq!(hkdf::expand::<hkdf::Sha256>(&prk, &info, 42).unwrap(), okm);
+    assert_eq!(
+        hkdf::derive::<hkdf::Sha256>(&salt, &ikm, &info, 42).unwrap(),
+        okm
+    );
+
+    // test case 3, with an empty salt and info
+    assert_eq!(
+        hkdf::derive::<hkdf::Sha256>(&[], &ikm, &[], 42).unwrap(),
+        decode(concat!(
+            "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d",
+            "9d201395faa4b61a96c8"
+        ))
+        .unwrap()
+    );
+}
+
+// generated with Python's hmac and hashlib.blake2b
+#[test]
+fn test_hmac_sha512() {
+    // RFC 4231 test case 1
+    assert_eq!(
+        hkdf::hmac::<hkdf::Sha512>(&[0x0bu8; 20], &[b"Hi There"]),
+        decode(concat!(
+            "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde",
+            "daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"
+        ))
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_blake2b() {
+    let ikm = [0x0bu8; 22];
+    let salt: Vec<u8> = (0..13).collect();
+    let info: Vec<u8> = (0xf0..0xfa).collect();
+
+    assert_eq!(
+        hkdf::derive::<hkdf::Blake2b>(&salt, &ikm, &info, 100).unwrap(),
+        decode(concat!(
+            "8815e1a85b5e90e6174323fdd180248887a7138af6dc5c8320fde21a60a07880",
+            "8267d6a41b6a938d7b30b3c3a7a0aef45b64cf11b2d32e47f46eb5494fbf2a85",
+            "f051c1a1181469c1d6698dc722beeb3a0c5f973b29f43c79a34a5c303547a151",
+            "c151228e"
+        ))
+        .unwrap()
+    );
+
+    // a salt longer than the block is hashed first
+    assert_eq!(
+        hkdf::derive::<hkdf::Blake2b>(&[0u8; 200], &ikm, &info, 42).unwrap(),
+        decode(concat!(
+            "d93dee2c6746796ce0a183b37f7f3d41554b30da42a9533ff9a1a7a2c5ea4bb2",
+            "90491c78d27ebc30b160"
+        ))
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_lengths() {
+    let prk = [0x01u8; 32];
+
+    assert_eq!(
+        hkdf::expand::<hkdf::Sha256>(&prk, b"", 255 * 32)
+            .unwrap()
+            .len(),
+        255 * 32
+    );
+    assert!(hkdf::expand::<hkdf::Sha256>(&prk, b"", 255 * 32 + 1).is_err());
+    assert!(hkdf::expand::<hkdf::Sha256>(&prk[..31], b"", 32).is_err());
+    assert!(hkdf::expand::<hkdf::Blake2b>(&prk, b"", 32).is_err());
+    assert_eq!(hkdf::expand::<hkdf::Sha256>(&prk, b"", 0).unwrap(), b"");
+}
+
+#[test]
+fn test_hchacha20() {
+    // draft-irtf-cfrg-xchacha §2.2.1
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = decode("000000090000004a0000000031415927").unwrap();
+
+    assert_eq!(
+        kdfs::hchacha20(&key.try_into().unwrap(), &nonce.try_into().unwrap()).to_vec(),
+        decode("82413b4227b27bfed30e42508a877d73a0f9e4d58a74a853c12ec41326d3ecdc").unwrap()
+    );
+}
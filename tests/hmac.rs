@@ -0,0 +1,111 @@
+use hex::decode;
+use raycrypt::hashes::blake2b::Blake2b;
+use raycrypt::hashes::sha2::{Sha256, Sha512};
+use raycrypt::macs::hmac::{self, HmacBlake2b, HmacSha256, HmacSha512};
+
+const LONG_KEY_MESSAGE: &[u8] = b"Test Using Larger Than Block-Size Key - Hash Key First";
+
+#[test]
+fn test_rfc4231() {
+    // test cases 1, 2 and 6
+    let cases: [(&[u8], &[u8], &str, &str); 3] = [
+        (
+            &[0x0b; 20],
+            b"Hi There",
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            concat!(
+                "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde",
+                "daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"
+            ),
+        ),
+        (
+            b"Jefe",
+            b"what do ya want for nothing?",
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            concat!(
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554",
+                "9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+            ),
+        ),
+        (
+            &[0xaa; 131],
+            LONG_KEY_MESSAGE,
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            concat!(
+                "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352",
+                "6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"
+            ),
+        ),
+    ];
+
+    for (key, message, sha256, sha512) in cases {
+        let sha256 = decode(sha256).unwrap();
+        let sha512 = decode(sha512).unwrap();
+
+        assert_eq!(hmac::hmac::<Sha256>(key, message), sha256);
+        assert_eq!(hmac::hmac::<Sha512>(key, message), sha512);
+        assert!(hmac::verify::<Sha256>(key, message, &sha256));
+        assert!(hmac::verify::<Sha512>(key, message, &sha512));
+    }
+}
+
+// generated with Python's hmac and hashlib.blake2b
+#[test]
+fn test_blake2b() {
+    assert_eq!(
+        hmac::hmac::<Blake2b>(b"Jefe", b"what do ya want for nothing?"),
+        decode(concat!(
+            "6ff884f8ddc2a6586b3c98a4cd6ebdf14ec10204b6710073eb5865ade37a2643",
+            "b8807c1335d107ecdb9ffeaeb6828c4625ba172c66379efcd222c2de11727ab4"
+        ))
+        .unwrap()
+    );
+
+    let mut mac = HmacBlake2b::new(&[0xaa; 131]);
+    mac.update(LONG_KEY_MESSAGE);
+    assert_eq!(
+        mac.finalize(),
+        decode(concat!(
+            "a54b2943b2a20227d41ca46c0945af09bc1faefb2f49894c23aebc557fb79c48",
+            "89dca74408dc865086667aedee4a3185c53a49c80b814c4c5813ea0c8b38a8f8"
+        ))
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_streaming() {
+    let message: Vec<u8> = (0..=255u8).cycle().take(300).collect();
+    let expected = hmac::hmac::<Sha512>(b"key", &message);
+
+    for split in 0..=message.len() {
+        let mut mac = HmacSha512::new(b"key");
+        mac.update(&message[..split]);
+        let copy = mac.clone();
+        mac.update(&message[split..]);
+        assert_eq!(mac.finalize(), expected);
+
+        // a clone continues from the same state
+        let mut copy = copy;
+        copy.update(&message[split..]);
+        assert!(copy.verify(&expected));
+    }
+}
+
+#[test]
+fn test_verify() {
+    let tag = hmac::hmac::<Sha256>(b"key", b"message");
+
+    let mut tampered = tag.clone();
+    tampered[0] ^= 1;
+
+    let mut mac = HmacSha256::new(b"key");
+    mac.update(b"message");
+    assert!(mac.clone().verify(&tag));
+    assert!(!mac.clone().verify(&tampered));
+    assert!(!mac.clone().verify(&tag[..16]));
+    assert!(!mac.verify(&[]));
+
+    assert!(!hmac::verify::<Sha256>(b"other key", b"message", &tag));
+    assert!(!hmac::verify::<Sha256>(b"key", b"other message", &tag));
+}
@@ -0,0 +1,159 @@
+use hex::decode;
+use raycrypt::aeads::hpke::*;
+use serde_json::{from_str, Value};
+use std::fs;
+
+fn vectors() -> Value {
+    let raw = fs::read_to_string("tests/vectors/hpke.json").unwrap();
+    from_str(&raw).unwrap()
+}
+
+fn field(value: &Value, name: &str) -> Vec<u8> {
+    decode(value[name].as_str().unwrap()).unwrap()
+}
+
+#[test]
+fn test_rfc9180_vectors() {
+    // appendix A.2.1: DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, ChaCha20Poly1305, base mode
+    for vector in vectors()["rfc9180"].as_array().unwrap() {
+        assert_eq!(vector["kem_id"], KEM_ID);
+        assert_eq!(vector["kdf_id"], KDF_ID);
+        assert_eq!(vector["aead_id"], AEAD_ID);
+        let info = field(vector, "info");
+
+        let (secret_key, public_key) = derive_keypair(&field(vector, "ikmR"));
+        assert_eq!(secret_key.to_vec(), field(vector, "skRm"));
+        assert_eq!(public_key.to_vec(), field(vector, "pkRm"));
+        let (ephemeral_secret_key, ephemeral_public_key) = derive_keypair(&field(vector, "ikmE"));
+        assert_eq!(ephemeral_secret_key.to_vec(), field(vector, "skEm"));
+        assert_eq!(ephemeral_public_key.to_vec(), field(vector, "pkEm"));
+
+        let (enc, mut sender) =
+            setup_base_sender_with_ikm(&public_key, &info, &field(vector, "ikmE")).unwrap();
+        assert_eq!(enc.to_vec(), field(vector, "enc"));
+        let mut receiver = setup_base_receiver(&enc, &secret_key, &info).unwrap();
+
+        // the vectors list some of the first 257 messages, which are sealed in order
+        let encryptions = vector["encryptions"].as_array().unwrap();
+        for sequence in 0..=256 {
+            let encryption = encryptions
+                .iter()
+                .find(|encryption| encryption["sequence_number"] == sequence);
+            let (plaintext, aad) = match encryption {
+                Some(encryption) => (field(encryption, "pt"), field(encryption, "aad")),
+                None => (b"unlisted".to_vec(), Vec::new()),
+            };
+
+            assert_eq!(sender.sequence(), sequence);
+            let ciphertext = sender.seal(&plaintext, &aad).unwrap();
+            if let Some(encryption) = encryption {
+                assert_eq!(ciphertext, field(encryption, "ct"), "message {sequence}");
+            }
+            assert_eq!(receiver.open(&ciphertext, &aad).unwrap(), plaintext);
+        }
+
+        for export in vector["exports"].as_array().unwrap() {
+            let context = field(export, "exporter_context");
+            let length = export["L"].as_u64().unwrap() as usize;
+            let expected = field(export, "exported_value");
+
+            assert_eq!(sender.export(&context, length).unwrap(), expected);
+            assert_eq!(receiver.export(&context, length).unwrap(), expected);
+            assert_eq!(
+                receive_export(&enc, &secret_key, &info, &context, length).unwrap(),
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn test_interop() {
+    // sealed by the HPKE implementation of the Python cryptography package
+    for vector in vectors()["interop"].as_array().unwrap() {
+        assert_eq!(
+            open(
+                &field(vector, "enc"),
+                &field(vector, "skRm"),
+                &field(vector, "info"),
+                &field(vector, "aad"),
+                &field(vector, "ct"),
+            )
+            .unwrap(),
+            field(vector, "pt")
+        );
+    }
+}
+
+#[test]
+fn test_receiver() {
+    let (secret_key, public_key) = derive_keypair(&[0x11; 32]);
+    let (enc, mut sender) = setup_base_sender_with_ikm(&public_key, b"info", &[0x22; 32]).unwrap();
+    let first = sender.seal(b"first", b"aad").unwrap();
+    let second = sender.seal(b"second", b"aad").unwrap();
+    assert_eq!(first.len(), 5 + TAG_LENGTH);
+
+    // out of order, under other AAD or info, or tampered with
+    let mut receiver = setup_base_receiver(&enc, &secret_key, b"info").unwrap();
+    assert_eq!(
+        receiver.open(&second, b"aad"),
+        Err(HpkeError::TagMismatch(TagMismatch { length_valid: true }))
+    );
+    assert!(receiver.open(&first, b"other").is_err());
+    let mut tampered = first.clone();
+    tampered[0] ^= 1;
+    assert!(receiver.open(&tampered, b"aad").is_err());
+    assert!(setup_base_receiver(&enc, &secret_key, b"other")
+        .unwrap()
+        .open(&first, b"aad")
+        .is_err());
+
+    // none of which advanced the sequence number
+    assert_eq!(receiver.sequence(), 0);
+    assert_eq!(receiver.open(&first, b"aad").unwrap(), b"first");
+    assert_eq!(receiver.open(&second, b"aad").unwrap(), b"second");
+    assert_eq!(receiver.sequence(), 2);
+
+    assert_eq!(
+        open(&enc, &secret_key, b"info", b"aad", &second),
+        Err(InvalidMac)
+    );
+    assert_eq!(sender.export(b"", 255 * 32 + 1), Err(InvalidLength));
+}
+
+#[test]
+fn test_small_order() {
+    let (secret_key, _) = derive_keypair(&[0x11; 32]);
+    let small_order = [0u8; 32];
+
+    assert!(setup_base_sender_with_ikm(&small_order, b"", &[0x22; 32]).is_none());
+    assert!(setup_base_receiver(&small_order, &secret_key, b"").is_none());
+    assert_eq!(
+        open(&small_order, &secret_key, b"", b"", &[0u8; 16]),
+        Err(InvalidMac)
+    );
+}
+
+#[cfg(feature = "getrandom")]
+#[test]
+fn test_single_shot() {
+    let (secret_key, public_key) = generate_keypair();
+
+    let (enc, ciphertext) = seal(&public_key, b"info", b"aad", b"message").unwrap();
+    assert_eq!(
+        open(&enc, &secret_key, b"info", b"aad", &ciphertext).unwrap(),
+        b"message"
+    );
+
+    // every setup has its own ephemeral key
+    let (other_enc, other_ciphertext) = seal(&public_key, b"info", b"aad", b"message").unwrap();
+    assert_ne!(enc, other_enc);
+    assert_ne!(ciphertext, other_ciphertext);
+
+    let (enc, secret) = send_export(&public_key, b"info", b"context", 64).unwrap();
+    assert_eq!(secret.len(), 64);
+    assert_eq!(
+        receive_export(&enc, &secret_key, b"info", b"context", 64).unwrap(),
+        secret
+    );
+}
@@ -0,0 +1,64 @@
+use raycrypt::aeads::keywrap::{unwrap_key, wrap_key, OVERHEAD};
+use raycrypt::aeads::xchachapoly1305::generate_key;
+use raycrypt::errors::FormatError;
+
+#[test]
+fn test_roundtrip() {
+    let kek = generate_key();
+
+    for len in [0, 16, 32, 64] {
+        let key = vec![0x5au8; len];
+
+        let wrapped = wrap_key(&kek, &key);
+        assert_eq!(wrapped.len(), len + OVERHEAD);
+        assert_eq!(unwrap_key(&kek, &wrapped).unwrap(), key);
+
+        // every wrap draws its own nonce
+        assert_ne!(wrap_key(&kek, &key), wrapped);
+    }
+}
+
+#[test]
+fn test_wrong_kek() {
+    let key = generate_key();
+    let wrapped = wrap_key(&generate_key(), &key);
+
+    assert_eq!(
+        unwrap_key(&generate_key(), &wrapped),
+        Err(FormatError::InvalidMac)
+    );
+}
+
+#[test]
+fn test_tampered() {
+    let kek = [0x01u8; 32];
+    let wrapped = wrap_key(&kek, &[0x02u8; 32]);
+
+    for i in [2, 26, wrapped.len() - 1] {
+        let mut tampered = wrapped.clone();
+        tampered[i] ^= 1;
+        assert_eq!(unwrap_key(&kek, &tampered), Err(FormatError::InvalidMac));
+    }
+
+    let mut tampered = wrapped.clone();
+    tampered[0] = 2;
+    assert_eq!(
+        unwrap_key(&kek, &tampered),
+        Err(FormatError::UnknownVersion(2))
+    );
+
+    let mut tampered = wrapped.clone();
+    tampered[1] = 1;
+    assert_eq!(unwrap_key(&kek, &tampered), Err(FormatError::InvalidHeader));
+
+    assert_eq!(
+        unwrap_key(&kek, &wrapped[..OVERHEAD - 1]),
+        Err(FormatError::InvalidLength)
+    );
+}
+
+#[test]
+#[should_panic(expected = "key-encryption keys are 32 bytes")]
+fn test_short_kek() {
+    wrap_key(&[0u8; 16], &[0u8; 32]);
+}
@@ -0,0 +1,154 @@
+use raycrypt::aeads::nonce::{MessageLimitReached, NonceGenerator, NonceSequence};
+use raycrypt::ciphers::chacha::{self, ChaCha20Rng};
+
+#[test]
+fn test_rng() {
+    let seed = [0x01u8; 32];
+
+    let mut rng = ChaCha20Rng::from_seed(&seed);
+    let mut output = [0u8; 200];
+    rng.fill_bytes(&mut output[..10]);
+    rng.fill_bytes(&mut output[10..]);
+
+    // the output is the ChaCha20 keystream starting at block 0
+    let expected: Vec<u8> = (0..4)
+        .flat_map(|counter| chacha::keystream(&seed, &[0u8; 12], counter, None))
+        .collect();
+    assert_eq!(output, expected[..200]);
+}
+
+#[test]
+fn test_rng_seek() {
+    let seed = [0x02u8; 32];
+
+    let mut rng = ChaCha20Rng::from_seed(&seed);
+    let mut output = [0u8; 300];
+    rng.fill_bytes(&mut output);
+
+    let mut seeked = ChaCha20Rng::from_seed(&seed);
+    seeked.fill_bytes(&mut [0u8; 10]);
+    seeked.seek(2);
+    let mut block = [0u8; 64];
+    seeked.fill_bytes(&mut block);
+    assert_eq!(block, output[128..192]);
+
+    seeked.seek(0);
+    assert_eq!(
+        seeked.next_u64(),
+        u64::from_le_bytes(output[..8].try_into().unwrap())
+    );
+    assert_eq!(
+        seeked.next_u32(),
+        u32::from_le_bytes(output[8..12].try_into().unwrap())
+    );
+
+    // the block counter carries into the first nonce word
+    seeked.seek(1 << 32);
+    let mut nonce = [0u8; 12];
+    nonce[0] = 1;
+    seeked.fill_bytes(&mut block);
+    assert_eq!(block, chacha::keystream(&seed, &nonce, 0, None));
+}
+
+#[cfg(feature = "rand_core")]
+#[test]
+fn test_rand_core() {
+    use rand_core::{RngCore, SeedableRng};
+
+    let seed = [0x03u8; 32];
+    let mut rng = <ChaCha20Rng as SeedableRng>::from_seed(seed);
+    let mut expected = ChaCha20Rng::from_seed(&seed);
+
+    assert_eq!(RngCore::next_u64(&mut rng), expected.next_u64());
+
+    let mut a = [0u8; 100];
+    let mut b = [0u8; 100];
+    rng.try_fill_bytes(&mut a).unwrap();
+    expected.fill_bytes(&mut b);
+    assert_eq!(a, b);
+}
+
+#[test]
+fn test_seeded_nonces() {
+    let mut a = NonceGenerator::with_rng(ChaCha20Rng::from_seed(&[0x07u8; 32]));
+    let mut b = NonceGenerator::with_rng(ChaCha20Rng::from_seed(&[0x07u8; 32]));
+    let mut c = NonceGenerator::with_rng(ChaCha20Rng::from_seed(&[0x08u8; 32]));
+
+    let sequence: Vec<[u8; 12]> = (0..10).map(|_| a.generate()).collect();
+
+    assert_eq!(sequence, (0..10).map(|_| b.generate()).collect::<Vec<_>>());
+    assert_ne!(sequence, (0..10).map(|_| c.generate()).collect::<Vec<_>>());
+    assert_ne!(sequence[0], sequence[1]);
+
+    let x: [u8; 24] = a.generate();
+    let y: [u8; 24] = b.generate();
+    assert_eq!(x, y);
+}
+
+#[test]
+fn test_os_nonces() {
+    let mut generator = NonceGenerator::new();
+
+    let a: [u8; 24] = generator.generate();
+    let b: [u8; 24] = generator.generate();
+    assert_ne!(a, b);
+}
+
+#[test]
+fn test_reseed() {
+    let seed = [0x09u8; 32];
+
+    let mut plain = ChaCha20Rng::from_seed(&seed);
+    let mut reseeded = ChaCha20Rng::from_seed(&seed);
+
+    let mut a = [0u8; 100];
+    let mut b = [0u8; 100];
+    plain.fill_bytes(&mut a[..10]);
+    reseeded.fill_bytes(&mut b[..10]);
+    assert_eq!(a[..10], b[..10]);
+
+    reseeded.reseed(b"fresh entropy");
+    plain.fill_bytes(&mut a[10..]);
+    reseeded.fill_bytes(&mut b[10..]);
+    assert_ne!(a[10..], b[10..]);
+
+    // reseeding is deterministic, and depends on the entropy
+    let mut again = ChaCha20Rng::from_seed(&seed);
+    let mut c = [0u8; 100];
+    again.fill_bytes(&mut c[..10]);
+    again.reseed(b"fresh entropy");
+    again.fill_bytes(&mut c[10..]);
+    assert_eq!(b, c);
+
+    let mut other = ChaCha20Rng::from_seed(&seed);
+    other.reseed(b"other entropy");
+    other.fill_bytes(&mut c[10..]);
+    assert_ne!(b[10..], c[10..]);
+}
+
+#[test]
+fn test_nonce_sequence() {
+    let mut nonces = NonceSequence::<24>::new(&[0x0au8; 16]);
+    let first = nonces.next_nonce().unwrap();
+    let second = nonces.next_nonce().unwrap();
+
+    assert_eq!(first[..16], [0x0au8; 16]);
+    assert_eq!(first[16..], 0u64.to_be_bytes());
+    assert_eq!(second[16..], 1u64.to_be_bytes());
+    assert_eq!(nonces.issued(), 2);
+    assert_eq!(nonces.remaining(), u64::MAX - 2);
+
+    let mut nonces = NonceSequence::<12>::with_limit(&[0x0bu8; 4], 3);
+    for counter in 0..3u64 {
+        assert_eq!(nonces.next_nonce().unwrap()[4..], counter.to_be_bytes());
+    }
+    assert_eq!(nonces.remaining(), 0);
+    assert_eq!(nonces.next_nonce(), Err(MessageLimitReached));
+    assert_eq!(nonces.issued(), 3);
+}
+
+#[test]
+#[should_panic(expected = "8 bytes shorter than the nonce")]
+fn test_nonce_sequence_prefix_length() {
+    NonceSequence::<12>::new(&[0u8; 8]);
+}
@@ -0,0 +1,144 @@
+use raycrypt::aeads::chachapoly1305::ChaChaPoly1305;
+use raycrypt::aeads::xchachapoly1305::XChaChaPoly1305;
+use raycrypt::aeads::Aead;
+use raycrypt::errors::{DecryptError, InvalidPadding, TagMismatch};
+use raycrypt::padding::{pad, padded_length, padme_length, unpad, Padding};
+
+#[test]
+fn test_block() {
+    assert_eq!(pad(b"", Padding::Block(4)), b"\x80\0\0\0");
+    assert_eq!(pad(b"abc", Padding::Block(4)), b"abc\x80");
+    assert_eq!(pad(b"abcd", Padding::Block(4)), b"abcd\x80\0\0\0");
+    assert_eq!(pad(b"abcd", Padding::Block(1)), b"abcd\x80");
+
+    for len in 0..100 {
+        let padded = pad(&vec![0x80; len], Padding::Block(16));
+        assert_eq!(padded.len() % 16, 0);
+        assert!(padded.len() > len && padded.len() <= len + 16);
+        assert_eq!(unpad(&padded).unwrap(), vec![0x80; len]);
+    }
+}
+
+#[test]
+#[should_panic(expected = "the block size must not be zero")]
+fn test_zero_block_size() {
+    pad(b"message", Padding::Block(0));
+}
+
+#[test]
+fn test_padme_length() {
+    let cases = [
+        (0, 0),
+        (1, 1),
+        (2, 2),
+        (3, 3),
+        (9, 10),
+        (17, 18),
+        (100, 104),
+        (1000, 1024),
+        (1024, 1024),
+        (1025, 1088),
+        (1_000_000, 1_015_808),
+    ];
+
+    for (len, expected) in cases {
+        assert_eq!(padme_length(len), expected, "{len}");
+    }
+
+    // the lengths only grow, and by at most 12%
+    let mut previous = 0;
+    for len in 2..100_000 {
+        let padded = padme_length(len);
+        assert!(padded >= len && padded >= previous);
+        assert!(padded * 100 <= len * 112, "{len}");
+        previous = padded;
+    }
+
+    // a fixed point of the rounding
+    assert_eq!(padme_length(padme_length(777)), padme_length(777));
+}
+
+#[test]
+fn test_padme() {
+    for len in 0..300 {
+        let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let padded = pad(&data, Padding::Padme);
+
+        assert_eq!(padded.len(), padme_length(len + 1));
+        assert_eq!(padded.len(), padded_length(len, Padding::Padme));
+        assert_eq!(unpad(&padded).unwrap(), data);
+    }
+}
+
+#[test]
+fn test_unpad_invalid() {
+    let cases: [&[u8]; 6] = [
+        b"",
+        b"\0",
+        b"\0\0\0\0",
+        b"abcd",
+        b"abc\x80\x01",
+        b"abc\x80\0\0\x80\x01",
+    ];
+
+    for padded in cases {
+        assert_eq!(unpad(padded), Err(InvalidPadding), "{padded:?}");
+    }
+
+    assert_eq!(unpad(b"\x80").unwrap(), b"");
+    assert_eq!(unpad(b"\x80\x80\0").unwrap(), b"\x80");
+}
+
+fn check_aead<A: Aead>() {
+    let cipher = A::from_key(&vec![0x42; A::KEY_LENGTH]);
+    let nonce = vec![0x24; A::NONCE_LENGTH];
+
+    for len in [0, 1, 15, 16, 17, 1000] {
+        let message = vec![0x61; len];
+        for padding in [Padding::Block(16), Padding::Padme] {
+            let ciphertext = cipher.encrypt_padded(&message, &nonce, b"ad", padding);
+            assert_eq!(
+                ciphertext.len(),
+                padded_length(len, padding) + A::TAG_LENGTH
+            );
+
+            assert_eq!(
+                cipher.decrypt_padded(&ciphertext, &nonce, b"ad").unwrap(),
+                message
+            );
+            assert_eq!(
+                unpad(&cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap()).unwrap(),
+                message
+            );
+        }
+    }
+
+    let mut ciphertext = cipher.encrypt_padded(b"message", &nonce, b"ad", Padding::Padme);
+    assert_eq!(
+        cipher.decrypt_padded(&ciphertext, &nonce, b"da"),
+        Err(DecryptError::TagMismatch(TagMismatch {
+            length_valid: true
+        }))
+    );
+
+    ciphertext[0] ^= 1;
+    assert_eq!(
+        cipher.decrypt_padded(&ciphertext, &nonce, b"ad"),
+        Err(DecryptError::TagMismatch(TagMismatch {
+            length_valid: true
+        }))
+    );
+
+    // authentic, but not padded
+    let ciphertext = cipher.encrypt(b"message", &nonce, b"ad");
+    assert_eq!(
+        cipher.decrypt_padded(&ciphertext, &nonce, b"ad"),
+        Err(DecryptError::InvalidPadding)
+    );
+}
+
+#[test]
+fn test_aead() {
+    check_aead::<ChaChaPoly1305>();
+    check_aead::<XChaChaPoly1305>();
+}
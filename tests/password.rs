@@ -0,0 +1,90 @@
+use raycrypt::aeads::password::*;
+use raycrypt::errors::FormatError;
+use raycrypt::kdfs::argon2::Params;
+
+#[test]
+fn test_roundtrip() {
+    let params = Params::new(64, 2, 2).unwrap();
+
+    for len in [0, 1, 64, 1000] {
+        let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let envelope = seal_with_password_and_params(b"password", &plaintext, params);
+
+        assert_eq!(envelope.len(), plaintext.len() + OVERHEAD);
+        assert_eq!(&envelope[..2], &[VERSION, 2]);
+        assert_eq!(&envelope[2..6], &64u32.to_le_bytes());
+        assert_eq!(&envelope[6..10], &2u32.to_le_bytes());
+        assert_eq!(&envelope[10..14], &2u32.to_le_bytes());
+        assert_eq!(
+            open_with_password(b"password", &envelope).unwrap(),
+            plaintext
+        );
+
+        // a fresh salt and nonce every time
+        let again = seal_with_password_and_params(b"password", &plaintext, params);
+        assert_ne!(envelope[14..HEADER_LENGTH], again[14..HEADER_LENGTH]);
+    }
+}
+
+#[test]
+fn test_errors() {
+    let params = Params::new(32, 1, 1).unwrap();
+    let envelope = seal_with_password_and_params(b"password", b"message", params);
+
+    assert_eq!(
+        open_with_password(b"passw0rd", &envelope),
+        Err(FormatError::InvalidMac)
+    );
+
+    // every header byte is authenticated, the costs and salt also change the key
+    for i in 0..envelope.len() {
+        let mut tampered = envelope.clone();
+        tampered[i] ^= 1;
+        assert!(open_with_password(b"password", &tampered).is_err());
+    }
+
+    let with_costs = |memory_cost: u32, time_cost: u32, parallelism: u32| {
+        let mut tampered = envelope.clone();
+        tampered[2..6].copy_from_slice(&memory_cost.to_le_bytes());
+        tampered[6..10].copy_from_slice(&time_cost.to_le_bytes());
+        tampered[10..14].copy_from_slice(&parallelism.to_le_bytes());
+        open_with_password(b"password", &tampered)
+    };
+    assert_eq!(with_costs(32, 1, 1), Ok(b"message".to_vec()));
+    assert_eq!(with_costs(32, 2, 1), Err(FormatError::InvalidMac));
+    for (memory_cost, time_cost, parallelism) in [
+        (MAX_MEMORY_COST + 1, 1, 1),
+        (u32::MAX, 1, 1),
+        (32, MAX_TIME_COST + 1, 1),
+        (32, 0, 1),
+        (32, 1, 0),
+        (32, 1, 5),
+    ] {
+        assert_eq!(
+            with_costs(memory_cost, time_cost, parallelism),
+            Err(FormatError::InvalidHeader)
+        );
+    }
+
+    let mut tampered = envelope.clone();
+    tampered[0] = 9;
+    assert_eq!(
+        open_with_password(b"password", &tampered),
+        Err(FormatError::UnknownVersion(9))
+    );
+    tampered[0] = VERSION;
+    tampered[1] = 1;
+    assert_eq!(
+        open_with_password(b"password", &tampered),
+        Err(FormatError::InvalidHeader)
+    );
+
+    assert_eq!(
+        open_with_password(b"password", &envelope[..OVERHEAD - 1]),
+        Err(FormatError::InvalidLength)
+    );
+    assert_eq!(
+        open_with_password(b"password", &[]),
+        Err(FormatError::InvalidLength)
+    );
+}
@@ -1,4 +1,4 @@
-use raycrypt::macs::poly1305::Poly1305;
+use raycrypt::macs::poly1305::{self, Poly1305};
 
 #[test]
 fn test_poly1305_1() {
@@ -13,9 +13,9 @@ fn test_poly1305_1() {
         0xa9,
     ];
 
-    let mut p = Poly1305::new(&key.to_vec());
+    let mut p = Poly1305::new(key.as_ref());
     p.update_unpadded(msg);
-    assert_eq!(p.verify(&tag), true);
+    assert!(p.verify(&tag));
 }
 
 #[test]
@@ -43,9 +43,9 @@ fn test_poly1305_2() {
         0xd9,
     ];
 
-    let mut p = Poly1305::new(&key.to_vec());
+    let mut p = Poly1305::new(key.as_ref());
     p.update_unpadded(&msg);
-    assert_eq!(p.verify(&tag), true);
+    assert!(p.verify(&tag));
 }
 
 #[test]
@@ -73,10 +73,10 @@ fn donna_test_1() {
         0xd9,
     ];
 
-    let mut p = Poly1305::new(&nacl_key.to_vec());
+    let mut p = Poly1305::new(nacl_key.as_ref());
     p.update_unpadded(&nacl_msg);
 
-    assert_eq!(p.verify(&nacl_mac), true);
+    assert!(p.verify(&nacl_mac));
 }
 
 #[test]
@@ -97,8 +97,55 @@ fn donna_test_2() {
         0x00,
     ];
 
-    let mut p2 = Poly1305::new(&wrap_key.to_vec());
+    let mut p2 = Poly1305::new(wrap_key.as_ref());
     p2.update_unpadded(&wrap_msg);
 
-    assert_eq!(p2.verify(&wrap_mac), true);
+    assert!(p2.verify(&wrap_mac));
+}
+
+#[test]
+fn test_verify() {
+    let key = [0x42u8; 32];
+    let mut p = Poly1305::new(&key);
+    p.update(b"message");
+    let tag = p.tag();
+
+    assert!(p.verify(&tag));
+
+    // a difference in any byte is caught, not just in the first one
+    for i in 0..16 {
+        let mut forged = tag.clone();
+        forged[i] ^= 0x80;
+        assert!(!p.verify(&forged));
+    }
+
+    // a prefix of the tag is not a valid tag
+    assert!(!p.verify(&[]));
+    assert!(!p.verify(&tag[..8]));
+    assert!(!p.verify(&[tag.as_slice(), &[0]].concat()));
+}
+
+#[test]
+fn test_one_shot() {
+    let key = [
+        0x85, 0xd6, 0xbe, 0x78, 0x57, 0x55, 0x6d, 0x33, 0x7f, 0x44, 0x52, 0xfe, 0x42, 0xd5, 0x06,
+        0xa8, 0x01, 0x03, 0x80, 0x8a, 0xfb, 0x0d, 0xb2, 0xfd, 0x4a, 0xbf, 0xf6, 0xaf, 0x41, 0x49,
+        0xf5, 0x1b,
+    ];
+    let msg = b"Cryptographic Forum Research Group";
+    let tag = [
+        0xa8, 0x06, 0x1d, 0xc1, 0x30, 0x51, 0x36, 0xc6, 0xc2, 0x2b, 0x8b, 0xaf, 0x0c, 0x01, 0x27,
+        0xa9,
+    ];
+
+    assert_eq!(poly1305::poly1305(&key, msg), tag);
+    assert!(poly1305::verify(&key, msg, &tag));
+    assert!(!poly1305::verify(&key, &msg[1..], &tag));
+    assert!(!poly1305::verify(&key, msg, &tag[..15]));
+
+    // split on a block boundary, the streaming MAC gives the same tag
+    let mut p = Poly1305::new(&key);
+    p.update_unpadded(&msg[..16]);
+    p.update_unpadded(&msg[16..]);
+    assert_eq!(p.tag(), tag);
 }
@@ -0,0 +1,76 @@
+use raycrypt::aeads::aes256gcm::Aes256Gcm;
+use raycrypt::aeads::xchachapoly1305::XChaChaPoly1305;
+use raycrypt::aeads::Aead;
+use raycrypt::protected::{ProtectedAead, SecretBox};
+
+#[test]
+fn test_secret_box() {
+    let secret = SecretBox::new(32).unwrap();
+    assert_eq!(secret.expose(), [0u8; 32]);
+    assert_eq!(secret.len(), 32);
+
+    let mut secret = SecretBox::from_slice(b"key material").unwrap();
+    assert_eq!(secret.expose(), b"key material");
+    secret.expose_mut()[0] = b'K';
+    assert_eq!(secret.expose(), b"Key material");
+
+    // longer than a page, and empty
+    let data: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+    assert_eq!(SecretBox::from_slice(&data).unwrap().expose(), data);
+    assert!(SecretBox::new(0).unwrap().is_empty());
+
+    let first = SecretBox::generate(32).unwrap();
+    let second = SecretBox::generate(32).unwrap();
+    assert_ne!(first.expose(), second.expose());
+
+    let debug = format!("{:?}", SecretBox::from_slice(b"\xaa\xbb").unwrap());
+    assert_eq!(debug, "SecretBox { len: 2, .. }");
+}
+
+fn check_aead<A: Aead>() {
+    let key = vec![0x42; A::KEY_LENGTH];
+    let nonce = vec![0x24; A::NONCE_LENGTH];
+    let plain = A::from_key(&key);
+    let protected = ProtectedAead::<A>::new(SecretBox::from_slice(&key).unwrap());
+
+    let ciphertext = protected.encrypt(b"message", &nonce, b"ad");
+    assert_eq!(ciphertext, plain.encrypt(b"message", &nonce, b"ad"));
+    assert_eq!(
+        protected.decrypt(&ciphertext, &nonce, b"ad").unwrap(),
+        b"message"
+    );
+    assert!(protected.decrypt(&ciphertext, &nonce, b"da").is_err());
+
+    let (ciphertext, tag) = protected.encrypt_detached(b"message", &nonce, b"ad");
+    assert_eq!(
+        plain
+            .decrypt_detached(&ciphertext, &tag, &nonce, b"ad")
+            .unwrap(),
+        b"message"
+    );
+
+    let ciphertext = plain.encrypt(b"message", &nonce, b"ad");
+    let from_key = ProtectedAead::<A>::from_key(&key);
+    assert_eq!(
+        from_key.decrypt(&ciphertext, &nonce, b"ad").unwrap(),
+        b"message"
+    );
+}
+
+#[test]
+fn test_aead() {
+    check_aead::<XChaChaPoly1305>();
+    check_aead::<Aes256Gcm>();
+}
+
+#[test]
+#[should_panic(expected = "the key has the wrong length")]
+fn test_short_key() {
+    ProtectedAead::<XChaChaPoly1305>::new(SecretBox::new(16).unwrap());
+}
+
+#[test]
+#[should_panic(expected = "the key has the wrong length")]
+fn test_long_key() {
+    ProtectedAead::<XChaChaPoly1305>::new(SecretBox::new(64).unwrap());
+}
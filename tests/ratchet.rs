@@ -0,0 +1,186 @@
+use raycrypt::aeads::ratchet::{
+    Chain, Header, RatchetError, Receiver, Sender, HEADER_LENGTH, TAG_LENGTH,
+};
+use raycrypt::aeads::xchachapoly1305;
+use raycrypt::kdfs::hkdf::{self, Sha256};
+
+const CHAIN_KEY: [u8; 32] = [0x5a; 32];
+
+#[test]
+fn test_round_trip() {
+    let mut sender = Sender::new(&CHAIN_KEY);
+    let mut receiver = Receiver::new(&CHAIN_KEY);
+
+    for i in 0..10u32 {
+        let message = sender.encrypt(&i.to_be_bytes(), b"ad").unwrap();
+        assert_eq!(message.len(), HEADER_LENGTH + 4 + TAG_LENGTH);
+        assert_eq!(
+            Header::parse(&message),
+            Some(Header {
+                previous_chain_length: 0,
+                index: i
+            })
+        );
+
+        assert_eq!(receiver.decrypt(&message, b"ad").unwrap(), i.to_be_bytes());
+    }
+
+    assert_eq!(sender.messages(), 10);
+    assert_eq!(receiver.index(), 10);
+    assert_eq!(receiver.skipped_keys(), 0);
+}
+
+#[test]
+fn test_chain() {
+    // the KDF chain of the Signal specification with HMAC-SHA-256, and message keys
+    // expanded into an XChaCha20-Poly1305 key and nonce
+    let mut sender = Sender::new(&CHAIN_KEY);
+    let mut chain_key = CHAIN_KEY.to_vec();
+
+    for i in 0..3u8 {
+        let message = sender.encrypt(&[i], b"ad").unwrap();
+        let message_key = hkdf::hmac::<Sha256>(&chain_key, &[&[0x01]]);
+        chain_key = hkdf::hmac::<Sha256>(&chain_key, &[&[0x02]]);
+
+        let okm =
+            hkdf::expand::<Sha256>(&message_key, b"raycrypt ratchet message key", 56).unwrap();
+        let (header, ciphertext) = message.split_at(HEADER_LENGTH);
+        let ad = [header, b"ad"].concat();
+        assert_eq!(
+            xchachapoly1305::decrypt(&okm[..32], ciphertext, &okm[32..], &ad, None).unwrap(),
+            [i]
+        );
+    }
+
+    let mut chain = Chain::new(&CHAIN_KEY);
+    assert_eq!(chain.next_key().unwrap().index(), 0);
+    assert_eq!(chain.index(), 1);
+}
+
+#[test]
+fn test_out_of_order() {
+    let mut sender = Sender::new(&CHAIN_KEY);
+    let mut receiver = Receiver::new(&CHAIN_KEY);
+    let messages: Vec<Vec<u8>> = (0..5u8)
+        .map(|i| sender.encrypt(&[i], b"").unwrap())
+        .collect();
+
+    assert_eq!(receiver.decrypt(&messages[3], b"").unwrap(), [3]);
+    assert_eq!(receiver.skipped_keys(), 3);
+
+    for i in [1, 0, 4, 2] {
+        assert_eq!(receiver.decrypt(&messages[i], b"").unwrap(), [i as u8]);
+    }
+    assert_eq!(receiver.skipped_keys(), 0);
+
+    // every key is used once
+    for message in &messages {
+        assert_eq!(
+            receiver.decrypt(message, b""),
+            Err(RatchetError::KeyUnavailable)
+        );
+    }
+}
+
+#[test]
+fn test_max_skip() {
+    let mut sender = Sender::new(&CHAIN_KEY);
+    let mut receiver = Receiver::with_max_skip(&CHAIN_KEY, 3);
+    let messages: Vec<Vec<u8>> = (0..10u8)
+        .map(|i| sender.encrypt(&[i], b"").unwrap())
+        .collect();
+
+    assert_eq!(
+        receiver.decrypt(&messages[4], b""),
+        Err(RatchetError::TooManySkipped)
+    );
+    assert_eq!(receiver.index(), 0);
+
+    assert_eq!(receiver.decrypt(&messages[3], b"").unwrap(), [3]);
+    assert_eq!(receiver.decrypt(&messages[7], b"").unwrap(), [7]);
+
+    // only the three newest skipped keys are kept
+    assert_eq!(receiver.skipped_keys(), 3);
+    for i in [0, 1, 2] {
+        assert_eq!(
+            receiver.decrypt(&messages[i], b""),
+            Err(RatchetError::KeyUnavailable)
+        );
+    }
+    for i in [4, 5, 6] {
+        assert_eq!(receiver.decrypt(&messages[i], b"").unwrap(), [i as u8]);
+    }
+}
+
+#[test]
+fn test_forgery() {
+    let mut sender = Sender::new(&CHAIN_KEY);
+    let mut receiver = Receiver::new(&CHAIN_KEY);
+    let first = sender.encrypt(b"first", b"ad").unwrap();
+    let second = sender.encrypt(b"second", b"ad").unwrap();
+
+    // the header, ciphertext, tag and AAD are all authenticated
+    for i in 0..second.len() {
+        let mut forged = second.clone();
+        forged[i] ^= 1;
+        assert!(receiver.decrypt(&forged, b"ad").is_err(), "byte {i}");
+    }
+    assert!(matches!(
+        receiver.decrypt(&second, b"other"),
+        Err(RatchetError::TagMismatch(_))
+    ));
+    assert!(receiver
+        .decrypt(&second[..HEADER_LENGTH + TAG_LENGTH - 1], b"ad")
+        .is_err());
+
+    // none of which moved the chain or kept keys
+    assert_eq!(receiver.index(), 0);
+    assert_eq!(receiver.skipped_keys(), 0);
+    assert_eq!(receiver.decrypt(&first, b"ad").unwrap(), b"first");
+    assert_eq!(receiver.decrypt(&second, b"ad").unwrap(), b"second");
+}
+
+#[test]
+fn test_skip_to() {
+    // the last messages of a chain arrive after the receiver has moved on to the next
+    let mut sender = Sender::new(&CHAIN_KEY);
+    let mut receiver = Receiver::new(&CHAIN_KEY);
+    let old: Vec<Vec<u8>> = (0..4u8)
+        .map(|i| sender.encrypt(&[i], b"").unwrap())
+        .collect();
+    assert_eq!(receiver.decrypt(&old[0], b"").unwrap(), [0]);
+
+    let next_key = [0xa5; 32];
+    let mut next_sender = Sender::with_previous_chain_length(&next_key, sender.messages());
+    let message = next_sender.encrypt(b"next", b"").unwrap();
+    let header = Header::parse(&message).unwrap();
+    assert_eq!(header.previous_chain_length, 4);
+
+    receiver.skip_to(header.previous_chain_length).unwrap();
+    assert_eq!(receiver.skipped_keys(), 3);
+    assert_eq!(
+        Receiver::new(&next_key).decrypt(&message, b"").unwrap(),
+        b"next"
+    );
+
+    for i in [3, 1, 2] {
+        assert_eq!(receiver.decrypt(&old[i], b"").unwrap(), [i as u8]);
+    }
+
+    assert_eq!(
+        Receiver::with_max_skip(&CHAIN_KEY, 2).skip_to(3),
+        Err(RatchetError::TooManySkipped)
+    );
+}
+
+#[test]
+fn test_chain_keys_differ() {
+    let mut alice = Sender::new(&CHAIN_KEY);
+    let mut bob = Receiver::new(&[0x5b; 32]);
+    let message = alice.encrypt(b"message", b"").unwrap();
+
+    assert!(matches!(
+        bob.decrypt(&message, b""),
+        Err(RatchetError::TagMismatch(_))
+    ));
+}
@@ -0,0 +1,217 @@
+use hex::decode;
+use raycrypt::aeads::aes256gcm::Aes256Gcm;
+use raycrypt::aeads::chachapoly1305::ChaChaPoly1305;
+use raycrypt::aeads::record::*;
+use raycrypt::aeads::Aead;
+use raycrypt::errors::TagMismatch;
+use serde_json::{from_str, Value};
+use std::fs;
+
+fn vectors() -> Vec<Value> {
+    let raw = fs::read_to_string("tests/vectors/record.json").unwrap();
+    from_str(&raw).unwrap()
+}
+
+fn field(value: &Value, name: &str) -> Vec<u8> {
+    decode(value[name].as_str().unwrap()).unwrap()
+}
+
+/// Splits a stream into its records with `record_length`.
+fn split(mut stream: &[u8]) -> Vec<&[u8]> {
+    let mut records = Vec::new();
+    while !stream.is_empty() {
+        let length = record_length(stream).unwrap().unwrap();
+        records.push(&stream[..length]);
+        stream = &stream[length..];
+    }
+
+    records
+}
+
+#[test]
+fn test_expand_label() {
+    // RFC 8448, section 3: the server handshake traffic key and IV
+    let secret =
+        decode("b67b7d690cc16c4e75e54213cb2d37b4e9c912bcded9105d42befd59d391ad38").unwrap();
+    assert_eq!(
+        expand_label(&secret, b"key", b"", 16),
+        decode("3fce516009c21727d0f2e4e86ee403bc").unwrap()
+    );
+    assert_eq!(
+        expand_label(&secret, b"iv", b"", 12),
+        decode("5d313eb2671276ee13000b30").unwrap()
+    );
+}
+
+fn check_vector<A: Aead>(vector: &Value) {
+    let records_per_key = vector["records_per_key"].as_u64().unwrap();
+    let (role, secret, peer) = match vector["role"].as_str().unwrap() {
+        "client" => (Role::Client, "client_secret", Role::Server),
+        _ => (Role::Server, "server_secret", Role::Client),
+    };
+
+    let shared_secret = field(vector, "shared_secret");
+    let context = field(vector, "context");
+    let (mut sender, _) = derive_connection::<A>(&shared_secret, &context, role, records_per_key);
+    let (_, mut receiver) = derive_connection::<A>(&shared_secret, &context, peer, records_per_key);
+    let mut from_secret =
+        Sender::<A>::new(&field(vector, secret).try_into().unwrap(), records_per_key);
+
+    let messages = vector["messages"].as_array().unwrap();
+    let records = vector["records"].as_array().unwrap();
+    for (message, expected) in messages.iter().zip(records) {
+        let message = decode(message.as_str().unwrap()).unwrap();
+        let expected = decode(expected.as_str().unwrap()).unwrap();
+
+        assert_eq!(sender.seal(&message), expected);
+        assert_eq!(from_secret.seal(&message), expected);
+        assert_eq!(receiver.open(&expected).unwrap(), message);
+    }
+
+    assert_eq!(sender.epoch(), messages.len() as u64 / records_per_key);
+    assert_eq!(receiver.epoch(), sender.epoch());
+}
+
+#[test]
+fn test_vectors() {
+    // generated with an independent implementation on the Python cryptography package
+    for vector in vectors() {
+        match vector["aead"].as_str().unwrap() {
+            "chacha20poly1305" => check_vector::<ChaChaPoly1305>(&vector),
+            "aes256gcm" => check_vector::<Aes256Gcm>(&vector),
+            aead => panic!("unknown AEAD {aead}"),
+        }
+    }
+}
+
+fn connection() -> (Sender<ChaChaPoly1305>, Receiver<ChaChaPoly1305>) {
+    let (sender, _) = derive_connection(b"shared secret", b"", Role::Client, 3);
+    let (_, receiver) = derive_connection(b"shared secret", b"", Role::Server, 3);
+
+    (sender, receiver)
+}
+
+#[test]
+fn test_fragmentation() {
+    let (mut sender, mut receiver) = connection();
+
+    let data: Vec<u8> = (0..40_000).map(|i| i as u8).collect();
+    let stream = sender.seal(&data);
+    let records = split(&stream);
+    assert_eq!(records.len(), 3);
+    assert_eq!(
+        records[0].len(),
+        HEADER_LENGTH + MAX_PLAINTEXT_LENGTH + 1 + 16
+    );
+
+    let mut received = Vec::new();
+    for record in records {
+        received.extend(receiver.open(record).unwrap());
+    }
+    assert_eq!(received, data);
+
+    // three records used up the first key
+    assert_eq!((sender.epoch(), sender.sequence()), (1, 0));
+    assert_eq!((receiver.epoch(), receiver.sequence()), (1, 0));
+
+    let record = sender.seal(b"");
+    assert_eq!(record.len(), HEADER_LENGTH + 1 + 16);
+    assert_eq!(receiver.open(&record).unwrap(), b"");
+}
+
+#[test]
+fn test_order() {
+    let (mut sender, mut receiver) = connection();
+    let first = sender.seal(b"first");
+    let second = sender.seal(b"second");
+
+    let mismatch = Err(RecordError::TagMismatch(TagMismatch { length_valid: true }));
+    assert_eq!(receiver.open(&second), mismatch);
+    assert_eq!(receiver.open(&first).unwrap(), b"first");
+    assert_eq!(receiver.open(&first), mismatch);
+    assert_eq!(receiver.open(&second).unwrap(), b"second");
+
+    // the directions have different keys
+    let (mut sender, _) =
+        derive_connection::<ChaChaPoly1305>(b"shared secret", b"", Role::Client, 3);
+    let (_, mut receiver) =
+        derive_connection::<ChaChaPoly1305>(b"shared secret", b"", Role::Client, 3);
+    assert_eq!(receiver.open(&sender.seal(b"message")), mismatch);
+
+    // and the context is bound to them
+    let (mut sender, _) =
+        derive_connection::<ChaChaPoly1305>(b"shared secret", b"a", Role::Client, 3);
+    let (_, mut receiver) =
+        derive_connection::<ChaChaPoly1305>(b"shared secret", b"b", Role::Server, 3);
+    assert_eq!(receiver.open(&sender.seal(b"message")), mismatch);
+}
+
+#[test]
+fn test_invalid_records() {
+    let (mut sender, mut receiver) = connection();
+    let record = sender.seal(b"message");
+
+    assert_eq!(record_length(&record[..4]), Ok(None));
+    assert_eq!(record_length(&record[..5]), Ok(Some(record.len())));
+
+    for (position, value) in [(0, 0x16), (1, 0x02), (2, 0x04), (3, 0x41)] {
+        let mut invalid = record.clone();
+        invalid[position] = value;
+        assert_eq!(receiver.open(&invalid), Err(RecordError::InvalidHeader));
+    }
+
+    assert_eq!(
+        receiver.open(&record[..record.len() - 1]),
+        Err(RecordError::InvalidHeader)
+    );
+    let mut longer = record.clone();
+    longer.push(0);
+    assert_eq!(receiver.open(&longer), Err(RecordError::InvalidHeader));
+
+    let mut tampered = record.clone();
+    tampered[HEADER_LENGTH] ^= 1;
+    assert_eq!(
+        receiver.open(&tampered),
+        Err(RecordError::TagMismatch(TagMismatch { length_valid: true }))
+    );
+
+    // nothing above moved the receiver on
+    assert_eq!(receiver.open(&record).unwrap(), b"message");
+    assert_eq!(receiver.sequence(), 1);
+}
+
+/// Seals `inner` as a record by hand, for content `Sender::seal` does not produce.
+fn seal_inner(secret: &[u8], inner: &[u8]) -> Vec<u8> {
+    let key = expand_label(secret, b"key", b"", 32);
+    let iv = expand_label(secret, b"iv", b"", 12);
+    let length = (inner.len() + 16) as u16;
+
+    let mut record = vec![0x17, 0x03, 0x03];
+    record.extend_from_slice(&length.to_be_bytes());
+    let ciphertext = ChaChaPoly1305::from_key(&key).encrypt(inner, &iv, &record);
+    record.extend(ciphertext);
+
+    record
+}
+
+#[test]
+fn test_inner_content() {
+    let secret = [0x42u8; SECRET_LENGTH];
+
+    // zero padding after the content type is removed
+    let mut receiver = Receiver::<ChaChaPoly1305>::new(&secret, DEFAULT_RECORDS_PER_KEY);
+    let record = seal_inner(&secret, b"message\x17\0\0\0");
+    assert_eq!(receiver.open(&record).unwrap(), b"message");
+
+    for inner in [&b"message\x16"[..], b"\0\0\0", b""] {
+        let mut receiver = Receiver::<ChaChaPoly1305>::new(&secret, DEFAULT_RECORDS_PER_KEY);
+        let record = seal_inner(&secret, inner);
+        assert_eq!(receiver.open(&record), Err(RecordError::UnexpectedContent));
+    }
+}
+
+#[test]
+#[should_panic(expected = "a key has to be used for at least one record")]
+fn test_zero_records_per_key() {
+    Sender::<ChaChaPoly1305>::new(&[0; SECRET_LENGTH], 0);
+}
@@ -0,0 +1,103 @@
+use raycrypt::aeads::aegis128l::Aegis128L;
+use raycrypt::aeads::chachapoly1305::ChaChaPoly1305;
+use raycrypt::aeads::registry::{from_name, AlgorithmError};
+use raycrypt::aeads::xchachapoly1305::XChaChaPoly1305;
+use raycrypt::aeads::Aead;
+use raycrypt::ciphers::chacha::{CHACHA12, CHACHA8};
+
+const NAMES: [&str; 9] = [
+    "chacha20poly1305",
+    "chacha12poly1305",
+    "chacha8poly1305",
+    "xchacha20poly1305",
+    "xchacha12poly1305",
+    "xchacha8poly1305",
+    "aegis256",
+    "aegis128l",
+    "aes256gcm",
+];
+
+#[test]
+fn test_roundtrip() {
+    for name in NAMES {
+        let key_length = if name == "aegis128l" { 16 } else { 32 };
+        let cipher = from_name(name, &vec![0x42; key_length]).unwrap();
+        assert_eq!(cipher.key_length(), key_length, "{name}");
+
+        let nonce = vec![1; cipher.nonce_length()];
+        let ciphertext = cipher.encrypt(b"message", &nonce, b"ad");
+        assert_eq!(ciphertext.len(), 7 + cipher.tag_length(), "{name}");
+        assert_eq!(
+            cipher.decrypt(&ciphertext, &nonce, b"ad").unwrap(),
+            b"message"
+        );
+        assert!(
+            cipher.decrypt(&ciphertext, &nonce, b"other").is_err(),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn test_matches_concrete() {
+    let key = [0x42; 32];
+    let nonce = [1; 24];
+
+    let ciphertext =
+        from_name("chacha12poly1305", &key)
+            .unwrap()
+            .encrypt(b"message", &nonce[..12], b"");
+    assert_eq!(
+        ciphertext,
+        ChaChaPoly1305::new(&key, CHACHA12).encrypt(b"message", &nonce[..12], b"")
+    );
+
+    let ciphertext = from_name("xchacha8poly1305", &key)
+        .unwrap()
+        .encrypt(b"message", &nonce, b"");
+    assert_eq!(
+        ciphertext,
+        XChaChaPoly1305::new(&key, CHACHA8).encrypt(b"message", &nonce, b"")
+    );
+
+    let ciphertext =
+        from_name("aegis128l", &key[..16])
+            .unwrap()
+            .encrypt(b"message", &nonce[..16], b"");
+    assert_eq!(
+        ciphertext,
+        Aead::encrypt(&Aegis128L::new(&key[..16]), b"message", &nonce[..16], b"")
+    );
+
+    // the round counts give different ciphertexts
+    let chacha20 =
+        from_name("chacha20poly1305", &key)
+            .unwrap()
+            .encrypt(b"message", &nonce[..12], b"");
+    assert_ne!(chacha20, ciphertext);
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(
+        from_name("chacha6poly1305", &[0; 32]).err(),
+        Some(AlgorithmError::UnknownAlgorithm)
+    );
+    assert_eq!(
+        from_name("ChaCha20Poly1305", &[0; 32]).err(),
+        Some(AlgorithmError::UnknownAlgorithm)
+    );
+    assert_eq!(
+        from_name("", &[]).err(),
+        Some(AlgorithmError::UnknownAlgorithm)
+    );
+
+    assert_eq!(
+        from_name("xchacha20poly1305", &[0; 16]).err(),
+        Some(AlgorithmError::InvalidKeyLength)
+    );
+    assert_eq!(
+        from_name("aegis128l", &[0; 32]).err(),
+        Some(AlgorithmError::InvalidKeyLength)
+    );
+}
@@ -0,0 +1,127 @@
+use hex::decode;
+use raycrypt::aeads::secretstream::{PullStream, PushStream, Tag, HEADER_LENGTH, OVERHEAD};
+
+const KEY: [u8; 32] = [0x61; 32];
+
+// generated with libsodium's crypto_secretstream_xchacha20poly1305
+#[test]
+fn test_libsodium_vectors() {
+    let key: Vec<u8> = (0..32).collect();
+    let header: Vec<u8> = (0x80..0x98).collect();
+    let message: Vec<u8> = (0..100).collect();
+
+    let mut stream = PullStream::new(&key, &header);
+
+    let (plaintext, tag) = stream
+        .pull(&decode("77e8ca4aa2b779f78482c4f91ac4d292da").unwrap(), b"")
+        .unwrap();
+    assert_eq!((plaintext.as_slice(), tag), (&b""[..], Tag::Message));
+
+    let (plaintext, tag) = stream
+        .pull(
+            &decode(concat!(
+                "80679ffff4e3f8ed64209ba8bcdbf9fc275cd89808ce3aa695f29e8678dfc8a18d75b2e678e6fe",
+                "d60ad19780300396fa04dc03e727a3dde44d"
+            ))
+            .unwrap(),
+            b"header",
+        )
+        .unwrap();
+    assert_eq!(plaintext, b"Ladies and Gentlemen of the class of '99");
+    assert_eq!(tag, Tag::Push);
+
+    let (plaintext, tag) = stream
+        .pull(
+            &decode(concat!(
+                "0db98f41128a2cf262ed79d0ca488f3ed3bc787b3f2a459abc3cb7d21dad051ffdee7ea00b2841",
+                "37f232034d632f173da7bd3a08da3752681da623d24e16742717b110c21c2e5b8f2c154b396b4b",
+                "247777160b4629365a23d07719a908ff2410de1de5935fe6cbf417bc4627054c8be608f5df7b5e"
+            ))
+            .unwrap(),
+            b"",
+        )
+        .unwrap();
+    assert_eq!((plaintext, tag), (message, Tag::Rekey));
+
+    stream.rekey();
+    let (plaintext, tag) = stream
+        .pull(
+            &decode(
+                "58e3352eb1f52fa1be936692f548c22d233122d3bd244711bab55ba884a581fe00c1b18f1a88150b",
+            )
+            .unwrap(),
+            b"",
+        )
+        .unwrap();
+    assert_eq!(plaintext, b"after an explicit rekey");
+    assert_eq!(tag, Tag::Message);
+
+    let (plaintext, tag) = stream
+        .pull(
+            &decode("d776e0a3b30250f33276beabb115c1124ba5f0009fc1f6d9").unwrap(),
+            b"",
+        )
+        .unwrap();
+    assert_eq!((plaintext.as_slice(), tag), (&b"the end"[..], Tag::Final));
+}
+
+#[test]
+fn test_round_trip() {
+    let (mut push, header) = PushStream::new(&KEY);
+    assert_eq!(header.len(), HEADER_LENGTH);
+    let mut pull = PullStream::new(&KEY, &header);
+
+    let tags = [
+        Tag::Message,
+        Tag::Push,
+        Tag::Rekey,
+        Tag::Message,
+        Tag::Final,
+    ];
+    for (i, tag) in tags.into_iter().enumerate() {
+        let message = vec![i as u8; 100 * i];
+        let sealed = push.push(&message, b"ad", tag);
+        assert_eq!(sealed.len(), message.len() + OVERHEAD);
+
+        assert_eq!(pull.pull(&sealed, b"ad").unwrap(), (message, tag));
+    }
+}
+
+#[test]
+fn test_rejects_tampering() {
+    let (mut push, header) = PushStream::new(&KEY);
+    let mut pull = PullStream::new(&KEY, &header);
+
+    let first = push.push(b"first", b"", Tag::Message);
+    let second = push.push(b"second", b"", Tag::Final);
+
+    // out of order
+    assert!(pull.pull(&second, b"").is_err());
+
+    let mut flipped = first.clone();
+    flipped[0] ^= 1;
+    assert!(pull.pull(&flipped, b"").is_err());
+    assert!(pull.pull(&first, b"ad").is_err());
+    assert!(pull.pull(&first[..OVERHEAD - 1], b"").is_err());
+
+    // failures leave the stream where it was
+    assert_eq!(pull.pull(&first, b"").unwrap().0, b"first");
+    assert!(pull.pull(&first, b"").is_err());
+    assert_eq!(
+        pull.pull(&second, b"").unwrap(),
+        (b"second".to_vec(), Tag::Final)
+    );
+}
+
+#[test]
+fn test_explicit_rekey() {
+    let (mut push, header) = PushStream::new(&KEY);
+    let mut pull = PullStream::new(&KEY, &header);
+
+    push.rekey();
+    let sealed = push.push(b"message", b"", Tag::Message);
+    assert!(pull.pull(&sealed, b"").is_err());
+
+    pull.rekey();
+    assert_eq!(pull.pull(&sealed, b"").unwrap().0, b"message");
+}
@@ -0,0 +1,81 @@
+use raycrypt::aeads::nonce::NonceGenerator;
+use raycrypt::aeads::session::{self, MessageLimitReached, Session, NONCE_LENGTH, TAG_LENGTH};
+use raycrypt::aeads::xchachapoly1305;
+use raycrypt::ciphers::chacha::ChaCha20Rng;
+use std::collections::HashSet;
+
+const KEY: [u8; 32] = [0x61; 32];
+
+#[test]
+fn test_round_trip() {
+    let mut session = Session::new(&KEY, None);
+    let message = session.encrypt(b"message", b"ad").unwrap();
+
+    assert_eq!(message.len(), NONCE_LENGTH + 7 + TAG_LENGTH);
+    assert_eq!(session.decrypt(&message, b"ad").unwrap(), b"message");
+    assert!(session.decrypt(&message, b"other").is_err());
+    assert!(session
+        .decrypt(&message[..NONCE_LENGTH + TAG_LENGTH - 1], b"ad")
+        .is_err());
+
+    // the output is plain XChaCha-Poly1305 under the prefixed nonce
+    let (nonce, ciphertext) = message.split_at(NONCE_LENGTH);
+    assert_eq!(
+        xchachapoly1305::decrypt(&KEY, ciphertext, nonce, b"ad", None).unwrap(),
+        b"message"
+    );
+}
+
+#[test]
+fn test_message_limit() {
+    let mut session = Session::with_limit(&KEY, 100, None);
+
+    let mut nonces = HashSet::new();
+    for i in 0..100 {
+        assert_eq!(session.remaining(), 100 - i);
+
+        let message = session.encrypt(b"", b"").unwrap();
+        assert!(nonces.insert(message[..NONCE_LENGTH].to_vec()));
+    }
+
+    assert_eq!(session.messages(), 100);
+    assert_eq!(session.remaining(), 0);
+    assert_eq!(session.encrypt(b"", b""), Err(MessageLimitReached));
+    assert_eq!(session.messages(), 100);
+
+    let mut empty = Session::with_limit(&KEY, 0, None);
+    assert_eq!(empty.encrypt(b"", b""), Err(MessageLimitReached));
+}
+
+#[test]
+fn test_seeded_nonces() {
+    let nonces = || NonceGenerator::with_rng(ChaCha20Rng::from_seed(&[0x07; 32]));
+
+    let mut a = Session::with_nonce_generator(&KEY, 10, nonces(), None);
+    let mut b = Session::with_nonce_generator(&KEY, 10, nonces(), None);
+
+    assert_eq!(a.encrypt(b"x", b"").unwrap(), b.encrypt(b"x", b"").unwrap());
+    assert_ne!(a.encrypt(b"x", b"").unwrap(), a.encrypt(b"x", b"").unwrap());
+}
+
+#[test]
+fn test_free_functions() {
+    let a = session::encrypt(&KEY, b"message", b"ad", None);
+    let b = session::encrypt(&KEY, b"message", b"ad", None);
+
+    // a fresh nonce is prepended to every message
+    assert_eq!(a.len(), NONCE_LENGTH + 7 + TAG_LENGTH);
+    assert_ne!(a[..NONCE_LENGTH], b[..NONCE_LENGTH]);
+
+    assert_eq!(session::decrypt(&KEY, &a, b"ad", None).unwrap(), b"message");
+    assert_eq!(session::decrypt(&KEY, &b, b"ad", None).unwrap(), b"message");
+    assert!(session::decrypt(&KEY, &a, b"", None).is_err());
+    assert!(session::decrypt(&KEY, &a[..NONCE_LENGTH], b"ad", None).is_err());
+
+    let mut session = Session::new(&KEY, None);
+    let message = session.encrypt(b"message", b"ad").unwrap();
+    assert_eq!(
+        session::decrypt(&KEY, &message, b"ad", None).unwrap(),
+        b"message"
+    );
+}
@@ -0,0 +1,101 @@
+use hex::decode;
+use raycrypt::hashes::sha2::{self, sha256, sha512, Backend, Sha256, Sha512};
+
+#[test]
+fn test_fips180() {
+    // FIPS 180-2 appendices B and C, and the empty message
+    let two_blocks = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    let sha512_two_blocks = concat!(
+        "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmno",
+        "ijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu"
+    );
+
+    assert_eq!(
+        sha256(b"abc").to_vec(),
+        decode("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad").unwrap()
+    );
+    assert_eq!(
+        sha256(b"").to_vec(),
+        decode("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855").unwrap()
+    );
+    assert_eq!(
+        sha256(two_blocks).to_vec(),
+        decode("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1").unwrap()
+    );
+    assert_eq!(
+        sha256(&[b'a'; 1_000_000]).to_vec(),
+        decode("cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0").unwrap()
+    );
+
+    assert_eq!(
+        sha512(b"abc").to_vec(),
+        decode(concat!(
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a",
+            "2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        ))
+        .unwrap()
+    );
+    assert_eq!(
+        sha512(b"").to_vec(),
+        decode(concat!(
+            "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce",
+            "47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"
+        ))
+        .unwrap()
+    );
+    assert_eq!(
+        sha512(sha512_two_blocks.as_bytes()).to_vec(),
+        decode(concat!(
+            "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018",
+            "501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"
+        ))
+        .unwrap()
+    );
+    assert_eq!(
+        sha512(&[b'a'; 1_000_000]).to_vec(),
+        decode(concat!(
+            "e718483d0ce769644e2e42c7bc15b4638e1f98b13b2044285632a803afa973eb",
+            "de0ff244877ea60a4cb0432ce577c31beb009c5c2c49aa2e4eadb217ad8cc09b"
+        ))
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_streaming() {
+    let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+
+    // every split point of the message, across block boundaries and the padding
+    for length in [0, 55, 56, 63, 64, 111, 112, 127, 128, 1000] {
+        let message = &data[..length];
+        for split in 0..=length {
+            let mut hasher = Sha256::new();
+            hasher.update(&message[..split]);
+            hasher.update(&message[split..]);
+            assert_eq!(hasher.finalize(), sha256(message));
+
+            let mut hasher = Sha512::new();
+            hasher.update(&message[..split]);
+            hasher.update(&message[split..]);
+            assert_eq!(hasher.finalize(), sha512(message));
+        }
+    }
+}
+
+#[test]
+fn test_backends() {
+    assert!(Backend::Portable.is_supported());
+    assert!(sha2::backend().is_supported());
+
+    let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+
+    for backend in [Backend::ShaNi, Backend::Portable] {
+        let Some(mut hasher) = Sha256::with_backend(backend) else {
+            continue;
+        };
+
+        hasher.update(&data[..100]);
+        hasher.update(&data[100..]);
+        assert_eq!(hasher.finalize(), sha256(&data), "{}", backend.name());
+    }
+}
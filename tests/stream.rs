@@ -0,0 +1,172 @@
+use raycrypt::aeads::stream::{
+    decrypt_init, encrypt_init, StreamDecryptor, StreamEncryptor, TAG_LENGTH,
+};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE: [u8; 7] = [7; 7];
+const AD: &[u8] = b"header";
+
+fn encrypt_frames(plaintext: &[u8], frame_size: usize) -> Vec<u8> {
+    let mut encryptor = encrypt_init(&KEY, &NONCE, AD, frame_size, None);
+
+    let mut ciphertext = Vec::new();
+    for chunk in plaintext.chunks(5) {
+        ciphertext.extend(encryptor.update(chunk));
+    }
+    ciphertext.extend(encryptor.finalize());
+
+    ciphertext
+}
+
+#[test]
+fn test_frames() {
+    let plaintext: Vec<u8> = (0..100).collect();
+    let ciphertext = encrypt_frames(&plaintext, 32);
+
+    // three full frames and a final frame of 4 bytes
+    assert_eq!(ciphertext.len(), plaintext.len() + 4 * TAG_LENGTH);
+
+    let mut decryptor = decrypt_init(&KEY, &NONCE, AD, 32, None);
+    let mut decrypted = Vec::new();
+    for chunk in ciphertext.chunks(7) {
+        decrypted.extend(decryptor.update(chunk).unwrap());
+    }
+    decrypted.extend(decryptor.finalize().unwrap());
+
+    assert_eq!(decrypted, plaintext);
+}
+
+#[test]
+fn test_frames_exact() {
+    let plaintext = [1u8; 64];
+    let ciphertext = encrypt_frames(&plaintext, 32);
+
+    // the second full frame is the final one, no empty frame follows it
+    assert_eq!(ciphertext.len(), plaintext.len() + 2 * TAG_LENGTH);
+
+    let mut decryptor = decrypt_init(&KEY, &NONCE, AD, 32, None);
+    let mut decrypted = decryptor.update(&ciphertext).unwrap();
+    decrypted.extend(decryptor.finalize().unwrap());
+
+    assert_eq!(decrypted, plaintext);
+}
+
+#[test]
+fn test_frames_reordered() {
+    let plaintext = [1u8; 96];
+    let ciphertext = encrypt_frames(&plaintext, 32);
+    let frame_len = 32 + TAG_LENGTH;
+
+    let mut swapped = ciphertext[frame_len..2 * frame_len].to_vec();
+    swapped.extend_from_slice(&ciphertext[..frame_len]);
+    swapped.extend_from_slice(&ciphertext[2 * frame_len..]);
+
+    let mut decryptor = decrypt_init(&KEY, &NONCE, AD, 32, None);
+    assert!(decryptor.update(&swapped).is_err());
+}
+
+#[test]
+fn test_truncated() {
+    let plaintext = [1u8; 100];
+    let ciphertext = encrypt_frames(&plaintext, 32);
+    let frame_len = 32 + TAG_LENGTH;
+
+    // drop the final frame, leaving a stream that ends on a frame boundary
+    let mut decryptor = decrypt_init(&KEY, &NONCE, AD, 32, None);
+    decryptor.update(&ciphertext[..3 * frame_len]).unwrap();
+    assert!(decryptor.finalize().is_err());
+
+    // cut the final frame short
+    let mut decryptor = decrypt_init(&KEY, &NONCE, AD, 32, None);
+    decryptor
+        .update(&ciphertext[..ciphertext.len() - 1])
+        .unwrap();
+    assert!(decryptor.finalize().is_err());
+}
+
+#[test]
+fn test_position() {
+    let mut encryptor = encrypt_init(&KEY, &NONCE, AD, 32, None);
+    assert_eq!(encryptor.position(), (0, 0));
+
+    let mut ciphertext = encryptor.update(&[0u8; 20]);
+    assert_eq!(encryptor.position(), (0, 20));
+
+    // the first frame fills up and is sealed as more input follows it
+    ciphertext.extend(encryptor.update(&[0u8; 20]));
+    assert_eq!(encryptor.position(), (1, 40));
+
+    ciphertext.extend(encryptor.update(&[0u8; 30]));
+    assert_eq!(encryptor.position(), (2, 70));
+
+    ciphertext.extend(encryptor.update(&[0u8; 30]));
+    assert_eq!(encryptor.position(), (3, 100));
+    ciphertext.extend(encryptor.finalize());
+
+    let frame_len = 32 + TAG_LENGTH;
+    let mut decryptor = decrypt_init(&KEY, &NONCE, AD, 32, None);
+
+    // a single frame might be the final one, so it stays buffered
+    decryptor.update(&ciphertext[..frame_len]).unwrap();
+    assert_eq!(decryptor.position(), (0, frame_len as u64));
+
+    decryptor
+        .update(&ciphertext[frame_len..3 * frame_len])
+        .unwrap();
+    assert_eq!(decryptor.position(), (2, 3 * frame_len as u64));
+
+    decryptor.update(&ciphertext[3 * frame_len..]).unwrap();
+    assert_eq!(decryptor.position(), (3, ciphertext.len() as u64));
+}
+
+#[test]
+fn test_send() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<StreamEncryptor>();
+    assert_send_sync::<StreamDecryptor>();
+
+    let plaintext: Vec<u8> = (0..100).collect();
+
+    let mut encryptor = encrypt_init(&KEY, &NONCE, AD, 32, None);
+    let input = plaintext.clone();
+    let ciphertext = std::thread::spawn(move || {
+        let mut ciphertext = encryptor.update(&input);
+        ciphertext.extend(encryptor.finalize());
+        ciphertext
+    })
+    .join()
+    .unwrap();
+
+    let mut decryptor = decrypt_init(&KEY, &NONCE, AD, 32, None);
+    let mut decrypted = decryptor.update(&ciphertext).unwrap();
+    decrypted.extend(decryptor.finalize().unwrap());
+
+    assert_eq!(decrypted, plaintext);
+}
+
+#[test]
+fn test_update_all() {
+    let plaintext: Vec<u8> = (0..100).collect();
+    let chunks = [&plaintext[..10], &plaintext[10..70], &plaintext[70..]];
+
+    let mut separate = encrypt_init(&KEY, &NONCE, AD, 32, None);
+    let mut expected = Vec::new();
+    for chunk in chunks {
+        expected.extend(separate.update(chunk));
+    }
+
+    let mut encryptor = encrypt_init(&KEY, &NONCE, AD, 32, None);
+    let ciphertext = encryptor.update_all(chunks);
+    assert_eq!(ciphertext, expected);
+    assert_eq!(encryptor.position(), separate.position());
+
+    let mut into = encrypt_init(&KEY, &NONCE, AD, 32, None);
+    let mut out = b"prefix".to_vec();
+    into.update_all_into(plaintext.chunks(10), &mut out);
+    assert_eq!(out[..6], *b"prefix");
+    assert_eq!(out[6..], expected);
+
+    let mut ciphertext = ciphertext;
+    ciphertext.extend(encryptor.finalize());
+    assert_eq!(ciphertext, encrypt_frames(&plaintext, 32));
+}
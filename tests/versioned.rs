@@ -0,0 +1,149 @@
+use raycrypt::aeads::versioned::*;
+use raycrypt::aeads::{chachapoly1305, xchachapoly1305};
+use raycrypt::errors::FormatError;
+
+#[test]
+fn test_chachapoly1305_versioned() {
+    let key = [0x42u8; 32];
+    let nonce = [0x24u8; 12];
+    let plaintext = b"Ladies and Gentlemen of the class of '99";
+    let ad = b"header";
+
+    let blob = encrypt_versioned(CHACHAPOLY1305_V2, &key, plaintext, &nonce, ad).unwrap();
+
+    assert_eq!(blob[0], CHACHAPOLY1305_V2);
+    assert_eq!(&blob[1..13], &nonce);
+    assert_eq!(&blob[13..21], &(plaintext.len() as u64).to_le_bytes());
+    assert_eq!(blob.len(), 1 + 12 + 8 + plaintext.len() + 16);
+
+    // the header is authenticated ahead of the AAD
+    let header_ad = [&blob[..21], ad].concat();
+    assert_eq!(
+        blob[21..],
+        chachapoly1305::encrypt(&key, plaintext, &nonce, &header_ad, None)
+    );
+
+    assert_eq!(decrypt_versioned(&key, &blob, ad).unwrap(), plaintext);
+}
+
+#[test]
+fn test_xchachapoly1305_versioned() {
+    let key = [0x42u8; 32];
+    let nonce = [0x24u8; 24];
+    let plaintext = b"Ladies and Gentlemen of the class of '99";
+    let ad = b"header";
+
+    let blob = encrypt_versioned(XCHACHAPOLY1305_V2, &key, plaintext, &nonce, ad).unwrap();
+
+    assert_eq!(blob[0], XCHACHAPOLY1305_V2);
+    assert_eq!(&blob[1..25], &nonce);
+    assert_eq!(&blob[25..33], &(plaintext.len() as u64).to_le_bytes());
+    assert_eq!(blob.len(), 1 + 24 + 8 + plaintext.len() + 16);
+
+    let header_ad = [&blob[..33], ad].concat();
+    assert_eq!(
+        blob[33..],
+        xchachapoly1305::encrypt(&key, plaintext, &nonce, &header_ad, None)
+    );
+
+    assert_eq!(decrypt_versioned(&key, &blob, ad).unwrap(), plaintext);
+}
+
+#[test]
+fn test_versioned_errors() {
+    let key = [0x42u8; 32];
+    let nonce = [0x24u8; 12];
+
+    assert_eq!(
+        encrypt_versioned(0xff, &key, b"", &nonce, b""),
+        Err(FormatError::UnknownVersion(0xff))
+    );
+    assert_eq!(
+        encrypt_versioned(XCHACHAPOLY1305_V2, &key, b"", &nonce, b""),
+        Err(FormatError::InvalidLength)
+    );
+
+    let mut blob = encrypt_versioned(CHACHAPOLY1305_V2, &key, b"message", &nonce, b"").unwrap();
+
+    assert_eq!(
+        decrypt_versioned(&key, &blob[..20], b""),
+        Err(FormatError::InvalidLength)
+    );
+    assert_eq!(
+        decrypt_versioned(&key, &[], b""),
+        Err(FormatError::InvalidLength)
+    );
+    assert_eq!(
+        decrypt_versioned(&key, &blob, b"ad"),
+        Err(FormatError::InvalidMac)
+    );
+
+    blob[0] = 0x7f;
+    assert_eq!(
+        decrypt_versioned(&key, &blob, b""),
+        Err(FormatError::UnknownVersion(0x7f))
+    );
+}
+
+#[test]
+fn test_versioned_length_mismatch() {
+    let key = [0x42u8; 32];
+    let nonce = [0x24u8; 12];
+
+    let blob = encrypt_versioned(CHACHAPOLY1305_V2, &key, b"message", &nonce, b"").unwrap();
+
+    // a wrong key would fail with InvalidMac if decryption were attempted
+    let mut declared = blob.clone();
+    declared[13..21].copy_from_slice(&1_000_000u64.to_le_bytes());
+    assert_eq!(
+        decrypt_versioned(&[0u8; 32], &declared, b""),
+        Err(FormatError::InvalidLength)
+    );
+
+    let mut truncated = blob.clone();
+    truncated.remove(22);
+    assert_eq!(
+        decrypt_versioned(&[0u8; 32], &truncated, b""),
+        Err(FormatError::InvalidLength)
+    );
+
+    let mut extended = blob.clone();
+    extended.insert(22, 0);
+    assert_eq!(
+        decrypt_versioned(&[0u8; 32], &extended, b""),
+        Err(FormatError::InvalidLength)
+    );
+
+    assert_eq!(
+        decrypt_versioned(&[0u8; 32], &blob, b""),
+        Err(FormatError::InvalidMac)
+    );
+}
+
+#[test]
+fn test_first_layout() {
+    let key = [0x42u8; 32];
+    let plaintext = b"Ladies and Gentlemen of the class of '99";
+    let ad = b"header";
+
+    // version || nonce || ciphertext || tag, with only the AAD authenticated
+    let nonce = [0x24u8; 12];
+    let ciphertext = chachapoly1305::encrypt(&key, plaintext, &nonce, ad, None);
+    let blob = [&[CHACHAPOLY1305][..], &nonce, &ciphertext].concat();
+    assert_eq!(decrypt_versioned(&key, &blob, ad).unwrap(), plaintext);
+    assert_eq!(
+        decrypt_versioned(&key, &blob, b""),
+        Err(FormatError::InvalidMac)
+    );
+
+    let nonce = [0x24u8; 24];
+    let ciphertext = xchachapoly1305::encrypt(&key, plaintext, &nonce, ad, None);
+    let blob = [&[XCHACHAPOLY1305][..], &nonce, &ciphertext].concat();
+    assert_eq!(decrypt_versioned(&key, &blob, ad).unwrap(), plaintext);
+
+    // but it is no longer written
+    assert_eq!(
+        encrypt_versioned(CHACHAPOLY1305, &key, plaintext, &[0u8; 12], ad),
+        Err(FormatError::UnknownVersion(CHACHAPOLY1305))
+    );
+}
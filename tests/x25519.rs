@@ -184,5 +184,36 @@ fn test_wycheproof() {
         let shared = hex::decode(test["shared"].as_str().unwrap()).unwrap();
 
         assert_eq!(scalarmult(&private, &public).to_vec(), shared);
+
+        // small-order points are rejected
+        match diffie_hellman(&private, &public) {
+            Some(secret) => assert_eq!(secret.to_vec(), shared),
+            None => assert_eq!(shared, [0u8; 32]),
+        }
     }
 }
+
+#[test]
+fn test_key_exchange() {
+    let (alice_sec, alice_pub) = generate_keypair();
+    let (bob_sec, bob_pub) = generate_keypair();
+    assert_ne!(alice_sec, bob_sec);
+    assert_eq!(public_key(&alice_sec), alice_pub);
+
+    assert_eq!(
+        diffie_hellman(&alice_sec, &bob_pub).unwrap(),
+        diffie_hellman(&bob_sec, &alice_pub).unwrap()
+    );
+
+    // the RFC 7748 keypair of Bob
+    let seed = [
+        0x5d, 0xab, 0x08, 0x7e, 0x62, 0x4a, 0x8a, 0x4b, 0x79, 0xe1, 0x7f, 0x8b, 0x83, 0x80, 0x0e,
+        0xe6, 0x6f, 0x3b, 0xb1, 0x29, 0x26, 0x18, 0xb6, 0xfd, 0x1c, 0x2f, 0x8b, 0x27, 0xff, 0x88,
+        0xe0, 0xeb,
+    ];
+    assert_eq!(keypair_from_seed(&seed), (seed, scalarmult_base(&seed)));
+
+    let mut low_order = [0u8; 32];
+    low_order[0] = 1;
+    assert_eq!(diffie_hellman(&seed, &low_order), None);
+}
@@ -0,0 +1,94 @@
+use raycrypt::aeads::xchachasiv::{self, TagMismatch, XChaChaSiv, TAG_LENGTH};
+
+#[test]
+fn test_roundtrip() {
+    let cipher = XChaChaSiv::new(&[0x13u8; 32]);
+
+    for len in [0, 1, 63, 64, 65, 1000] {
+        let plaintext: Vec<u8> = (0..len).map(|i| (i * 11 % 256) as u8).collect();
+
+        for nonce in [&b""[..], &[7u8; 12], &[9u8; 24]] {
+            let ciphertext = cipher.encrypt(&plaintext, nonce, b"ad");
+            assert_eq!(ciphertext.len(), len + TAG_LENGTH);
+            assert_eq!(
+                cipher.decrypt(&ciphertext, nonce, b"ad").unwrap(),
+                plaintext
+            );
+        }
+    }
+
+    let ciphertext = xchachasiv::encrypt(&[1u8; 32], b"message", b"nonce", b"");
+    assert_eq!(
+        xchachasiv::decrypt(&[1u8; 32], &ciphertext, b"nonce", b"").unwrap(),
+        b"message"
+    );
+}
+
+#[test]
+fn test_deterministic() {
+    let cipher = XChaChaSiv::new(&[0x24u8; 32]);
+    let first = cipher.encrypt(b"attack at dawn", b"", b"");
+
+    // equal inputs give equal ciphertexts, any other input changes all of it
+    assert_eq!(cipher.encrypt(b"attack at dawn", b"", b""), first);
+    for other in [
+        cipher.encrypt(b"attack at dusk", b"", b""),
+        cipher.encrypt(b"attack at dawn", b"n", b""),
+        cipher.encrypt(b"attack at dawn", b"", b"a"),
+        XChaChaSiv::new(&[0x25u8; 32]).encrypt(b"attack at dawn", b"", b""),
+    ] {
+        assert_ne!(other[..14], first[..14]);
+        assert_ne!(other[14..], first[14..]);
+    }
+
+    // a repeated nonce does not reuse keystream: the XOR of the ciphertexts is not the
+    // XOR of the plaintexts
+    let a = cipher.encrypt(&[0u8; 32], b"nonce", b"");
+    let b = cipher.encrypt(&[1u8; 32], b"nonce", b"");
+    let xor: Vec<u8> = a[..32].iter().zip(&b[..32]).map(|(x, y)| x ^ y).collect();
+    assert_ne!(xor, [1u8; 32]);
+}
+
+#[test]
+fn test_framing() {
+    // moving bytes between the nonce and the AAD changes the tag
+    let cipher = XChaChaSiv::new(&[0x35u8; 32]);
+    assert_ne!(
+        cipher.encrypt(b"message", b"ab", b"c"),
+        cipher.encrypt(b"message", b"a", b"bc")
+    );
+    assert_ne!(
+        cipher.encrypt(b"cmessage", b"ab", b""),
+        cipher.encrypt(b"message", b"ab", b"c")
+    );
+}
+
+#[test]
+fn test_tamper() {
+    let cipher = XChaChaSiv::new(&[0x46u8; 32]);
+    let ciphertext = cipher.encrypt(b"message", b"nonce", b"ad");
+
+    for i in 0..ciphertext.len() {
+        let mut tampered = ciphertext.clone();
+        tampered[i] ^= 1;
+        assert_eq!(
+            cipher.decrypt(&tampered, b"nonce", b"ad"),
+            Err(TagMismatch { length_valid: true })
+        );
+    }
+
+    assert!(cipher.decrypt(&ciphertext, b"other", b"ad").is_err());
+    assert!(cipher.decrypt(&ciphertext, b"nonce", b"other").is_err());
+    assert_eq!(
+        cipher.decrypt(&ciphertext[..TAG_LENGTH - 1], b"nonce", b"ad"),
+        Err(TagMismatch {
+            length_valid: false
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "XChaCha20-SIV keys are 32 bytes")]
+fn test_key_length() {
+    XChaChaSiv::new(&[0u8; 16]);
+}